@@ -144,7 +144,6 @@ pub struct Builder {
     enable_custom_types: bool,
     enable_index_method: bool,
     enable_materialized_views: bool,
-    enable_vacuum: bool,
     enable_generated_columns: bool,
     enable_multiprocess_wal: bool,
     enable_without_rowid: bool,
@@ -163,7 +162,6 @@ impl Builder {
             enable_custom_types: false,
             enable_index_method: false,
             enable_materialized_views: false,
-            enable_vacuum: false,
             enable_generated_columns: false,
             enable_multiprocess_wal: false,
             enable_without_rowid: false,
@@ -218,8 +216,8 @@ impl Builder {
         self
     }
 
-    pub fn experimental_vacuum(mut self, enabled: bool) -> Self {
-        self.enable_vacuum = enabled;
+    /// Kept for backwards compatibility. In-place VACUUM is now always enabled.
+    pub fn experimental_vacuum(self, _vacuum_enabled: bool) -> Self {
         self
     }
 
@@ -261,9 +259,6 @@ impl Builder {
         if self.enable_materialized_views {
             features.push("views");
         }
-        if self.enable_vacuum {
-            features.push("vacuum");
-        }
         if self.enable_generated_columns {
             features.push("generated_columns");
         }
@@ -253,6 +253,9 @@ pub struct Connection {
     pub(super) is_mvcc_bootstrap_connection: AtomicBool,
     /// Whether pragma foreign_keys=ON for this connection
     pub(super) fk_pragma: AtomicBool,
+    /// Whether pragma defer_foreign_keys=ON for this connection.
+    /// Reset automatically whenever a transaction finishes.
+    pub(super) fk_defer_pragma: AtomicBool,
     pub(crate) fk_deferred_violations: AtomicIsize,
     /// Number of active write statements on this connection.
     pub(crate) n_active_writes: AtomicI32,
@@ -317,6 +320,9 @@ impl Drop for Connection {
                     wal.end_read_tx();
                 }
             }
+            if let Some(journal) = &pager.journal {
+                journal.end_read_tx();
+            }
 
             // Also release WAL locks on all attached database pagers
             self.with_all_attached_pagers_with_index(|attached_pagers| {
@@ -329,6 +335,9 @@ impl Drop for Connection {
                             wal.end_read_tx();
                         }
                     }
+                    if let Some(journal) = &attached_pager.journal {
+                        journal.end_read_tx();
+                    }
                 }
             });
 
@@ -369,7 +378,6 @@ impl Connection {
             .with_views(self.db.experimental_views_enabled())
             .with_custom_types(self.db.experimental_custom_types_enabled())
             .with_index_method(self.db.experimental_index_method_enabled())
-            .with_vacuum(self.db.experimental_vacuum_enabled())
             .with_generated_columns(self.db.experimental_generated_columns_enabled())
             .with_without_rowid(self.db.experimental_without_rowid_enabled())
     }
@@ -1278,6 +1286,22 @@ impl Connection {
         self.fk_pragma.load(Ordering::Acquire)
     }
 
+    pub fn set_defer_foreign_keys_enabled(&self, enable: bool) {
+        self.fk_defer_pragma.store(enable, Ordering::Release);
+        self.bump_prepare_context_generation();
+    }
+
+    pub fn defer_foreign_keys_enabled(&self) -> bool {
+        self.fk_defer_pragma.load(Ordering::Acquire)
+    }
+
+    /// Turn `PRAGMA defer_foreign_keys` off at the end of a transaction, as SQLite does.
+    pub(crate) fn reset_defer_foreign_keys(&self) {
+        if self.fk_defer_pragma.swap(false, Ordering::AcqRel) {
+            self.bump_prepare_context_generation();
+        }
+    }
+
     pub fn set_check_constraints_ignored(&self, ignore: bool) {
         self.check_constraints_pragma
             .store(ignore, Ordering::Release);
@@ -1640,10 +1664,12 @@ impl Connection {
         }
 
         let is_memory_db = is_memory_like(&self.db.path);
-        let should_checkpoint_on_close = pager
-            .wal
-            .as_ref()
-            .is_none_or(|wal| wal.should_checkpoint_on_close());
+        let should_checkpoint_on_close = match pager.wal.as_ref() {
+            Some(wal) => wal.should_checkpoint_on_close(),
+            // Rollback-journal databases write their pages in place at commit, so
+            // there is no WAL to checkpoint.
+            None => false,
+        };
         if self.db.n_connections.fetch_sub(1, Ordering::SeqCst).eq(&1)
             && !self.db.is_readonly()
             && !is_memory_db
@@ -1939,6 +1965,7 @@ impl Connection {
                 materialized_view_info,
                 dbsp_state_roots,
                 dbsp_state_index_roots,
+                &syms,
             ) {
                 Ok(()) => {}
                 Err(LimboError::ExtensionError(msg)) => eprintln!("Warning: {msg}"),
@@ -1992,10 +2019,6 @@ impl Connection {
         self.db.experimental_attach_enabled()
     }
 
-    pub fn experimental_vacuum_enabled(&self) -> bool {
-        self.db.experimental_vacuum_enabled()
-    }
-
     pub fn experimental_multiprocess_wal_enabled(&self) -> bool {
         self.db.experimental_multiprocess_wal_enabled()
     }
@@ -2459,7 +2482,6 @@ impl Connection {
             .with_views(self.db.experimental_views_enabled())
             .with_custom_types(self.db.experimental_custom_types_enabled())
             .with_index_method(self.db.experimental_index_method_enabled())
-            .with_vacuum(self.db.experimental_vacuum_enabled())
             .with_generated_columns(self.db.experimental_generated_columns_enabled())
             .with_without_rowid(self.db.experimental_without_rowid_enabled());
         // Select the IO layer for the attached database:
@@ -2491,6 +2513,8 @@ impl Connection {
             None
         };
         let mut pager = Arc::new(db._init(encryption_key.as_ref())?);
+        // Auto-checkpointing is configured per connection and covers attached databases too.
+        pager.set_wal_autocheckpoint(self.get_pager().get_wal_autocheckpoint());
 
         if !attached_is_fresh {
             self.reject_initialized_attach_mismatches(alias, &db, &pager)?;
@@ -3237,6 +3261,7 @@ impl Connection {
         }
         self.rollback_attached_wal_txns();
         self.set_tx_state(TransactionState::None);
+        self.reset_defer_foreign_keys();
     }
 
     /// Roll back transaction state for helpers that start a manual `BEGIN`
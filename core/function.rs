@@ -312,12 +312,27 @@ pub enum AggFunc {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumIter)]
 pub enum WindowFunc {
     RowNumber,
+    Rank,
+    DenseRank,
+    PercentRank,
+    CumeDist,
+    Ntile,
+    Lag,
+    Lead,
+    FirstValue,
+    LastValue,
+    NthValue,
 }
 
 impl WindowFunc {
     pub fn arities(&self) -> &'static [i32] {
         match self {
-            Self::RowNumber => &[0],
+            Self::RowNumber | Self::Rank | Self::DenseRank | Self::PercentRank | Self::CumeDist => {
+                &[0]
+            }
+            Self::Ntile | Self::FirstValue | Self::LastValue => &[1],
+            Self::Lag | Self::Lead => &[1, 2, 3],
+            Self::NthValue => &[2],
         }
     }
 }
@@ -332,6 +347,16 @@ impl std::fmt::Display for WindowFunc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::RowNumber => write!(f, "row_number"),
+            Self::Rank => write!(f, "rank"),
+            Self::DenseRank => write!(f, "dense_rank"),
+            Self::PercentRank => write!(f, "percent_rank"),
+            Self::CumeDist => write!(f, "cume_dist"),
+            Self::Ntile => write!(f, "ntile"),
+            Self::Lag => write!(f, "lag"),
+            Self::Lead => write!(f, "lead"),
+            Self::FirstValue => write!(f, "first_value"),
+            Self::LastValue => write!(f, "last_value"),
+            Self::NthValue => write!(f, "nth_value"),
         }
     }
 }
@@ -348,6 +373,11 @@ impl PartialEq for AggFunc {
             | (Self::Sum, Self::Sum)
             | (Self::Total, Self::Total)
             | (Self::ArrayAgg, Self::ArrayAgg) => true,
+            #[cfg(feature = "json")]
+            (Self::JsonGroupArray, Self::JsonGroupArray)
+            | (Self::JsonbGroupArray, Self::JsonbGroupArray)
+            | (Self::JsonGroupObject, Self::JsonGroupObject)
+            | (Self::JsonbGroupObject, Self::JsonbGroupObject) => true,
             (Self::External(a), Self::External(b)) => Arc::ptr_eq(a, b),
             _ => false,
         }
@@ -1271,6 +1301,25 @@ impl Func {
                 }
                 Ok(Some(Self::Window(WindowFunc::RowNumber)))
             }
+            "rank" | "dense_rank" | "percent_rank" | "cume_dist" | "ntile" | "lag" | "lead"
+            | "first_value" | "last_value" | "nth_value" => {
+                let func = match normalized_name.as_str() {
+                    "rank" => WindowFunc::Rank,
+                    "dense_rank" => WindowFunc::DenseRank,
+                    "percent_rank" => WindowFunc::PercentRank,
+                    "cume_dist" => WindowFunc::CumeDist,
+                    "ntile" => WindowFunc::Ntile,
+                    "lag" => WindowFunc::Lag,
+                    "lead" => WindowFunc::Lead,
+                    "first_value" => WindowFunc::FirstValue,
+                    "last_value" => WindowFunc::LastValue,
+                    _ => WindowFunc::NthValue,
+                };
+                if !func.arities().contains(&(arg_count as i32)) {
+                    crate::bail_parse_error!("wrong number of arguments to function {}()", name)
+                }
+                Ok(Some(Self::Window(func)))
+            }
             "timediff" => {
                 if arg_count != 2 {
                     crate::bail_parse_error!("wrong number of arguments to function {}()", name)
@@ -3,6 +3,7 @@
 use crate::function::{AggFunc, Func};
 use crate::incremental::dbsp::Hash128;
 use crate::incremental::dbsp::{Delta, DeltaPair, HashableRow};
+use crate::incremental::join_operator::{deserialize_hashable_row, serialize_hashable_row};
 use crate::incremental::operator::{
     generate_storage_id, ComputationTracker, DbspStateCursors, EvalState, IncrementalOperator,
 };
@@ -13,6 +14,7 @@ use crate::sync::Arc;
 use crate::sync::Mutex;
 use crate::translate::plan::ColumnMask;
 use crate::types::{IOResult, ImmutableRecord, SeekKey, SeekOp, SeekResult, ValueRef};
+use crate::vdbe::affinity::{try_for_float, ParsedNumber};
 use crate::{return_and_restore_if_io, return_if_io, LimboError, Result, Value};
 use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
 use std::collections::BTreeMap;
@@ -21,16 +23,17 @@ use std::fmt::{self, Display};
 // Architecture of the Aggregate Operator
 // ========================================
 //
-// This operator implements SQL aggregations (GROUP BY, DISTINCT, COUNT, SUM, AVG, MIN, MAX)
+// This operator implements SQL aggregations (GROUP BY, DISTINCT, COUNT, SUM, AVG, MIN, MAX, ...)
 // using DBSP-style incremental computation. The key insight is that all these operations
 // can be expressed as operations on weighted sets (Z-sets) stored in persistent BTrees.
 //
 // ## Storage Strategy
 //
-// We use three different storage encodings (identified by 2-bit type codes in storage IDs):
-// - **Regular aggregates** (COUNT/SUM/AVG): Store accumulated state as a blob
+// We use four different storage encodings (identified by 2-bit type codes in storage IDs):
+// - **Regular aggregates** (COUNT/SUM/AVG/TOTAL): Store accumulated state as a blob
 // - **MIN/MAX aggregates**: Store individual values; BTree ordering gives us min/max efficiently
 // - **DISTINCT tracking**: Store distinct values with weights (positive = present, zero = deleted)
+// - **Recomputed aggregates**: Store every input tuple with its weight, in input rowid order
 //
 // ## MIN/MAX Handling
 //
@@ -45,6 +48,18 @@ use std::fmt::{self, Display};
 //    - For MIN: scan forward from the beginning to find first value with positive weight
 //    - For MAX: scan backward from the end to find last value with positive weight
 //
+// ## Recomputed Aggregates
+//
+// Aggregates such as GROUP_CONCAT, JSON_GROUP_ARRAY or the ones registered by extensions
+// (median, percentile, ...) have no summary that a deletion can be subtracted from, and
+// their result may depend on input order. For those we keep all inputs of each group:
+// - The argument tuple of every input row is stored, keyed by the row's rowid
+// - When a group changes, its stored inputs are scanned, merged with the batch, and the
+//   aggregate is computed again from scratch, feeding the inputs in rowid order
+// - The last result is kept in the regular state blob, so the old output row can be retracted
+//
+// This costs a scan of the changed groups, so it is only used where no cheaper method exists.
+//
 // ## DISTINCT Handling
 //
 // DISTINCT operations (COUNT(DISTINCT), SUM(DISTINCT), etc.) are implemented using the
@@ -75,6 +90,7 @@ use std::fmt::{self, Display};
 pub const AGG_TYPE_REGULAR: u8 = 0b00; // COUNT/SUM/AVG
 pub const AGG_TYPE_MINMAX: u8 = 0b01; // MIN/MAX (BTree ordering gives both)
 pub const AGG_TYPE_DISTINCT: u8 = 0b10; // DISTINCT values tracking
+pub const AGG_TYPE_RECOMPUTED: u8 = 0b11; // Input tuples of recomputed aggregates
 
 /// Hash a Value to generate an element_id for DISTINCT storage
 /// Uses HashableRow with column_idx as rowid for consistent hashing
@@ -84,6 +100,23 @@ fn hash_value(value: &Value, column_idx: usize) -> Hash128 {
     row.cached_hash()
 }
 
+/// Numeric value of an input of SUM, TOTAL or AVG. Like the non-incremental aggregates,
+/// text and blobs count as their numeric prefix, or 0 when they have none.
+fn numeric_input(value: &Value) -> f64 {
+    let parsed = match value {
+        Value::Numeric(Numeric::Integer(i)) => return *i as f64,
+        Value::Numeric(Numeric::Float(f)) => return f64::from(*f),
+        Value::Text(t) => try_for_float(t.as_str().as_bytes()).1,
+        Value::Blob(b) => try_for_float(b).1,
+        _ => return 0.0,
+    };
+    match parsed {
+        ParsedNumber::Integer(i) => i as f64,
+        ParsedNumber::Float(f) => f,
+        ParsedNumber::None => 0.0,
+    }
+}
+
 // Serialization type codes for aggregate functions
 const AGG_FUNC_COUNT: i64 = 0;
 const AGG_FUNC_SUM: i64 = 1;
@@ -93,10 +126,24 @@ const AGG_FUNC_MAX: i64 = 4;
 const AGG_FUNC_COUNT_DISTINCT: i64 = 5;
 const AGG_FUNC_SUM_DISTINCT: i64 = 6;
 const AGG_FUNC_AVG_DISTINCT: i64 = 7;
+const AGG_FUNC_COUNT_COLUMN: i64 = 8;
+const AGG_FUNC_TOTAL: i64 = 9;
+const AGG_FUNC_RECOMPUTED: i64 = 10;
+
+/// Element id of a stored input of a recomputed aggregate. The high half is the input rowid
+/// with its sign bit flipped, so a scan returns inputs in rowid order; the low half tells
+/// apart different inputs coming from the same rowid.
+fn recomputed_element_id(rowid: i64, args: &[Value]) -> Hash128 {
+    Hash128::new(
+        (rowid as u64) ^ (1 << 63),
+        Hash128::hash_values(args).as_i64() as u64,
+    )
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AggregateFunction {
     Count,
+    CountColumn(usize),   // COUNT(column_index), ignores NULLs
     CountDistinct(usize), // COUNT(DISTINCT column_index)
     Sum(usize),           // Column index
     SumDistinct(usize),   // SUM(DISTINCT column_index)
@@ -104,12 +151,21 @@ pub enum AggregateFunction {
     AvgDistinct(usize),   // AVG(DISTINCT column_index)
     Min(usize),           // Column index
     Max(usize),           // Column index
+    Total(usize),         // TOTAL(column_index)
+    /// An aggregate that is computed again from all stored inputs of a group whenever the
+    /// group changes. `slot` is its position in the aggregate list and keys its storage.
+    Recomputed {
+        func: AggFunc,
+        args: Vec<usize>,
+        slot: usize,
+    },
 }
 
 impl Display for AggregateFunction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AggregateFunction::Count => write!(f, "COUNT(*)"),
+            AggregateFunction::CountColumn(idx) => write!(f, "COUNT(col{idx})"),
             AggregateFunction::CountDistinct(idx) => write!(f, "COUNT(DISTINCT col{idx})"),
             AggregateFunction::Sum(idx) => write!(f, "SUM(col{idx})"),
             AggregateFunction::SumDistinct(idx) => write!(f, "SUM(DISTINCT col{idx})"),
@@ -117,6 +173,15 @@ impl Display for AggregateFunction {
             AggregateFunction::AvgDistinct(idx) => write!(f, "AVG(DISTINCT col{idx})"),
             AggregateFunction::Min(idx) => write!(f, "MIN(col{idx})"),
             AggregateFunction::Max(idx) => write!(f, "MAX(col{idx})"),
+            AggregateFunction::Total(idx) => write!(f, "TOTAL(col{idx})"),
+            AggregateFunction::Recomputed { func, args, .. } => {
+                let args = args
+                    .iter()
+                    .map(|idx| format!("col{idx}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{}({args})", func.as_str().to_uppercase())
+            }
         }
     }
 }
@@ -133,6 +198,12 @@ impl AggregateFunction {
     pub fn to_values(&self) -> Vec<Value> {
         match self {
             AggregateFunction::Count => vec![Value::Numeric(Numeric::Integer(AGG_FUNC_COUNT))],
+            AggregateFunction::CountColumn(idx) => {
+                vec![
+                    Value::Numeric(Numeric::Integer(AGG_FUNC_COUNT_COLUMN)),
+                    Value::from_i64(*idx as i64),
+                ]
+            }
             AggregateFunction::CountDistinct(idx) => {
                 vec![
                     Value::Numeric(Numeric::Integer(AGG_FUNC_COUNT_DISTINCT)),
@@ -175,6 +246,19 @@ impl AggregateFunction {
                     Value::from_i64(*idx as i64),
                 ]
             }
+            AggregateFunction::Total(idx) => {
+                vec![
+                    Value::Numeric(Numeric::Integer(AGG_FUNC_TOTAL)),
+                    Value::from_i64(*idx as i64),
+                ]
+            }
+            // Only the slot is stored: the function itself comes from the view definition
+            AggregateFunction::Recomputed { slot, .. } => {
+                vec![
+                    Value::Numeric(Numeric::Integer(AGG_FUNC_RECOMPUTED)),
+                    Value::from_i64(*slot as i64),
+                ]
+            }
         }
     }
 
@@ -190,6 +274,20 @@ impl AggregateFunction {
                 *cursor += 1;
                 AggregateFunction::Count
             }
+            Value::Numeric(Numeric::Integer(AGG_FUNC_COUNT_COLUMN)) => {
+                *cursor += 1;
+                let idx = values.get(*cursor).ok_or_else(|| {
+                    LimboError::InternalError("Missing COUNT column index".into())
+                })?;
+                if let Value::Numeric(Numeric::Integer(idx)) = idx {
+                    *cursor += 1;
+                    AggregateFunction::CountColumn(*idx as usize)
+                } else {
+                    return Err(LimboError::InternalError(format!(
+                        "Expected Integer for COUNT column index, got {idx:?}"
+                    )));
+                }
+            }
             Value::Numeric(Numeric::Integer(AGG_FUNC_COUNT_DISTINCT)) => {
                 *cursor += 1;
                 let idx = values.get(*cursor).ok_or_else(|| {
@@ -288,6 +386,28 @@ impl AggregateFunction {
                     )));
                 }
             }
+            Value::Numeric(Numeric::Integer(AGG_FUNC_TOTAL)) => {
+                *cursor += 1;
+                let idx = values.get(*cursor).ok_or_else(|| {
+                    LimboError::InternalError("Missing TOTAL column index".into())
+                })?;
+                if let Value::Numeric(Numeric::Integer(idx)) = idx {
+                    *cursor += 1;
+                    AggregateFunction::Total(*idx as usize)
+                } else {
+                    return Err(LimboError::InternalError(format!(
+                        "Expected Integer for TOTAL column index, got {idx:?}"
+                    )));
+                }
+            }
+            // The function of a recomputed aggregate comes from the view definition, and
+            // AggregateState::from_value_vector reads its stored slot and result itself
+            Value::Numeric(Numeric::Integer(AGG_FUNC_RECOMPUTED)) => {
+                return Err(LimboError::InternalError(
+                    "Recomputed aggregate state must be read by AggregateState::from_value_vector"
+                        .into(),
+                ))
+            }
             _ => {
                 return Err(LimboError::InternalError(format!(
                     "Unknown aggregate type code: {type_code:?}"
@@ -312,6 +432,7 @@ impl AggregateFunction {
                     AggFunc::Avg => input_column_idx.map(AggregateFunction::Avg),
                     AggFunc::Min => input_column_idx.map(AggregateFunction::Min),
                     AggFunc::Max => input_column_idx.map(AggregateFunction::Max),
+                    AggFunc::Total => input_column_idx.map(AggregateFunction::Total),
                     _ => None, // Other aggregate functions not yet supported in DBSP
                 }
             }
@@ -336,6 +457,11 @@ type ComputedStates = HashMap<String, (Vec<Value>, AggregateState)>;
 // group_key_str -> (column_index, value_as_hashable_row) -> accumulated_weight
 pub type MinMaxDeltas = HashMap<String, HashMap<(usize, HashableRow), isize>>;
 
+/// Inputs of recomputed aggregates changed by a batch
+/// Maps: group_key_str -> (slot, element_id) -> (input row, accumulated_weight)
+/// The input row carries the source rowid and the aggregate's argument values
+pub type RecomputedDeltas = HashMap<String, HashMap<(usize, Hash128), (HashableRow, isize)>>;
+
 /// Type for tracking distinct values within a batch
 /// Maps: group_key_str -> (column_idx, HashableRow) -> accumulated_weight
 /// HashableRow contains the value with column_idx as rowid for proper hashing
@@ -371,16 +497,23 @@ enum AggregateCommitState {
         write_row: WriteRow,
         min_max_deltas: MinMaxDeltas,
         distinct_deltas: DistinctDeltas,
+        recomputed_deltas: RecomputedDeltas,
         input_delta: Delta, // Keep original input delta for distinct processing
     },
     PersistMinMax {
         delta: Delta,
         min_max_persist_state: MinMaxPersistState,
         distinct_deltas: DistinctDeltas,
+        recomputed_deltas: RecomputedDeltas,
     },
     PersistDistinctValues {
         delta: Delta,
         distinct_persist_state: DistinctPersistState,
+        recomputed_deltas: RecomputedDeltas,
+    },
+    PersistRecomputedInputs {
+        delta: Delta,
+        recomputed_persist_state: RecomputedPersistState,
     },
     Done {
         delta: Delta,
@@ -425,6 +558,13 @@ pub enum AggregateEvalState {
         recompute_state: Box<RecomputeMinMax>,
         pre_existing_groups: HashSet<String>, // Track groups that existed before this delta
     },
+    RecomputeAggregates {
+        delta: Delta,
+        existing_groups: HashMap<String, AggregateState>,
+        old_values: HashMap<String, Vec<Value>>,
+        recompute_state: Box<RecomputeAggregates>,
+        pre_existing_groups: HashSet<String>, // Track groups that existed before this delta
+    },
     Done {
         output: (Delta, ComputedStates),
     },
@@ -473,6 +613,12 @@ pub struct AggregateState {
     // These are populated during eval when we scan the BTree (or in-memory map)
     pub distinct_counts: HashMap<usize, i64>,
     pub distinct_sums: HashMap<usize, f64>,
+    // For COUNT(column): column_index -> number of non-NULL values
+    pub column_counts: HashMap<usize, i64>,
+    // For TOTAL: column_index -> total
+    pub totals: HashMap<usize, f64>,
+    // For recomputed aggregates: slot -> result of the last recomputation
+    pub recomputed: HashMap<usize, Value>,
 
     // Weights of specific distinct values needed for current delta processing
     // (column_index, value) -> weight
@@ -488,12 +634,14 @@ impl AggregateEvalState {
     /// 2. FetchAggregateState → FetchKey (always, loops until all groups processed)
     /// 3. FetchKey (when done) → FetchDistinctValues (always)
     /// 4. FetchDistinctValues → RecomputeMinMax (always)
-    /// 5. RecomputeMinMax → Done (always)
+    /// 5. RecomputeMinMax → RecomputeAggregates (always)
+    /// 6. RecomputeAggregates → Done (always)
     ///
     /// Some states may be no-ops depending on the operator configuration:
     /// - FetchAggregateState: For plain DISTINCT, skips reading aggregate blob (no aggregates to fetch)
     /// - FetchDistinctValues: No-op if no distinct columns exist (distinct_columns is empty)
     /// - RecomputeMinMax: No-op if no MIN/MAX aggregates exist (has_min_max() returns false)
+    /// - RecomputeAggregates: No-op if no recomputed aggregates exist (has_recomputed() returns false)
     ///
     /// This deterministic flow ensures each state always transitions to the same next state,
     /// making the state machine easier to understand and debug.
@@ -702,7 +850,30 @@ impl AggregateEvalState {
                         return_if_io!(recompute_state.process(existing_groups, operator, cursors));
                     }
 
-                    // Now compute final output with updated MIN/MAX values
+                    // Transition to RecomputeAggregates
+                    let recomputed_deltas = operator.extract_recomputed_deltas(delta);
+                    let next_state = AggregateEvalState::RecomputeAggregates {
+                        delta: std::mem::take(delta),
+                        existing_groups: std::mem::take(existing_groups),
+                        old_values: std::mem::take(old_values),
+                        recompute_state: Box::new(RecomputeAggregates::new(recomputed_deltas)),
+                        pre_existing_groups: std::mem::take(pre_existing_groups),
+                    };
+                    *self = next_state;
+                }
+                AggregateEvalState::RecomputeAggregates {
+                    delta,
+                    existing_groups,
+                    old_values,
+                    recompute_state,
+                    pre_existing_groups,
+                } => {
+                    if operator.has_recomputed() {
+                        // Replay the inputs of every changed group through its aggregates
+                        return_if_io!(recompute_state.process(existing_groups, operator, cursors));
+                    }
+
+                    // Now compute final output with updated MIN/MAX and recomputed values
                     let (output_delta, computed_states) = operator.merge_delta_with_existing(
                         delta,
                         existing_groups,
@@ -750,6 +921,10 @@ impl AggregateState {
                 AggregateFunction::Count => {
                     // Count state is already stored at the beginning
                 }
+                AggregateFunction::CountColumn(col_idx) => {
+                    let count = self.column_counts.get(col_idx).copied().unwrap_or(0);
+                    values.push(Value::from_i64(count));
+                }
                 AggregateFunction::CountDistinct(col_idx) => {
                     // Store the distinct count for this column
                     let count = self.distinct_counts.get(col_idx).copied().unwrap_or(0);
@@ -794,6 +969,18 @@ impl AggregateState {
                         values.push(Value::from_i64(0)); // No value
                     }
                 }
+                AggregateFunction::Total(col_idx) => {
+                    let total = self.totals.get(col_idx).copied().unwrap_or(0.0);
+                    values.push(Value::from_f64(total));
+                }
+                AggregateFunction::Recomputed { slot, .. } => {
+                    if let Some(result) = self.recomputed.get(slot) {
+                        values.push(Value::from_i64(1)); // Has value
+                        values.push(result.clone());
+                    } else {
+                        values.push(Value::from_i64(0)); // No value
+                    }
+                }
             }
         }
 
@@ -834,6 +1021,39 @@ impl AggregateState {
 
         // Read each aggregate's state with type and column index
         for _ in 0..num_aggregates {
+            // Recomputed aggregates only store their slot and last result, read them here
+            if let Some(Value::Numeric(Numeric::Integer(AGG_FUNC_RECOMPUTED))) = values.get(cursor)
+            {
+                let slot = match values.get(cursor + 1) {
+                    Some(Value::Numeric(Numeric::Integer(slot))) => *slot as usize,
+                    other => {
+                        return Err(LimboError::InternalError(format!(
+                            "Expected Integer for recomputed aggregate slot, got {other:?}"
+                        )))
+                    }
+                };
+                let has_value = match values.get(cursor + 2) {
+                    Some(Value::Numeric(Numeric::Integer(has_value))) => *has_value,
+                    other => {
+                        return Err(LimboError::InternalError(format!(
+                        "Expected Integer for recomputed aggregate has_value flag, got {other:?}"
+                    )))
+                    }
+                };
+                cursor += 3;
+                if has_value == 1 {
+                    let result = values
+                        .get(cursor)
+                        .ok_or_else(|| {
+                            LimboError::InternalError("Missing recomputed aggregate value".into())
+                        })?
+                        .clone();
+                    cursor += 1;
+                    state.recomputed.insert(slot, result);
+                }
+                continue;
+            }
+
             // Deserialize the aggregate function metadata
             let agg_fn = AggregateFunction::from_values(values, &mut cursor)?;
 
@@ -842,6 +1062,35 @@ impl AggregateState {
                 AggregateFunction::Count => {
                     // Count state is already stored at the beginning
                 }
+                AggregateFunction::CountColumn(col_idx) => {
+                    let count = values.get(cursor).ok_or_else(|| {
+                        LimboError::InternalError("Missing COUNT(column) value".into())
+                    })?;
+                    if let Value::Numeric(Numeric::Integer(count)) = count {
+                        state.column_counts.insert(col_idx, *count);
+                        cursor += 1;
+                    } else {
+                        return Err(LimboError::InternalError(format!(
+                            "Expected Integer for COUNT(column) value, got {count:?}"
+                        )));
+                    }
+                }
+                AggregateFunction::Total(col_idx) => {
+                    let total = values
+                        .get(cursor)
+                        .ok_or_else(|| LimboError::InternalError("Missing TOTAL value".into()))?;
+                    if let Value::Numeric(Numeric::Float(total)) = total {
+                        state.totals.insert(col_idx, f64::from(*total));
+                        cursor += 1;
+                    } else {
+                        return Err(LimboError::InternalError(format!(
+                            "Expected Float for TOTAL value, got {total:?}"
+                        )));
+                    }
+                }
+                AggregateFunction::Recomputed { .. } => {
+                    unreachable!("recomputed aggregates are read before from_values")
+                }
                 AggregateFunction::CountDistinct(col_idx) => {
                     let count = values.get(cursor).ok_or_else(|| {
                         LimboError::InternalError("Missing COUNT(DISTINCT) value".into())
@@ -1078,6 +1327,21 @@ impl AggregateState {
                 AggregateFunction::Count => {
                     // Already handled above
                 }
+                AggregateFunction::CountColumn(col_idx) => {
+                    if values.get(*col_idx).is_some_and(|val| val != &Value::Null) {
+                        *self.column_counts.entry(*col_idx).or_insert(0) += weight as i64;
+                    }
+                }
+                AggregateFunction::Total(col_idx) => {
+                    if let Some(val) = values.get(*col_idx) {
+                        let num_val = numeric_input(val);
+                        *self.totals.entry(*col_idx).or_insert(0.0) += num_val * weight as f64;
+                    }
+                }
+                AggregateFunction::Recomputed { .. } => {
+                    // Computed from the stored inputs by RecomputeAggregates, before the
+                    // delta is merged (see AggregateEvalState::RecomputeAggregates)
+                }
                 AggregateFunction::CountDistinct(col_idx) => {
                     // Only update count if we haven't processed this column yet
                     if !processed_counts.get(*col_idx) {
@@ -1112,11 +1376,7 @@ impl AggregateState {
                         if !processed_sums.get(*col_idx) {
                             let current_sum =
                                 self.distinct_sums.get(col_idx).copied().unwrap_or(0.0);
-                            let value_as_float = match &transition.transitioned_value {
-                                Value::Numeric(Numeric::Integer(i)) => *i as f64,
-                                Value::Numeric(Numeric::Float(f)) => f64::from(*f),
-                                _ => 0.0,
-                            };
+                            let value_as_float = numeric_input(&transition.transitioned_value);
 
                             let new_sum = match transition.transition_type {
                                 TransitionType::Added => current_sum + value_as_float,
@@ -1129,21 +1389,13 @@ impl AggregateState {
                 }
                 AggregateFunction::Sum(col_idx) => {
                     if let Some(val) = values.get(*col_idx) {
-                        let num_val = match val {
-                            Value::Numeric(Numeric::Integer(i)) => *i as f64,
-                            Value::Numeric(Numeric::Float(f)) => f64::from(*f),
-                            _ => 0.0,
-                        };
+                        let num_val = numeric_input(val);
                         *self.sums.entry(*col_idx).or_insert(0.0) += num_val * weight as f64;
                     }
                 }
                 AggregateFunction::Avg(col_idx) => {
                     if let Some(val) = values.get(*col_idx) {
-                        let num_val = match val {
-                            Value::Numeric(Numeric::Integer(i)) => *i as f64,
-                            Value::Numeric(Numeric::Float(f)) => f64::from(*f),
-                            _ => 0.0,
-                        };
+                        let num_val = numeric_input(val);
                         let (sum, count) = self.avgs.entry(*col_idx).or_insert((0.0, 0));
                         *sum += num_val * weight as f64;
                         *count += weight as i64;
@@ -1196,6 +1448,10 @@ impl AggregateState {
                 AggregateFunction::Count => {
                     result.push(Value::from_i64(self.count));
                 }
+                AggregateFunction::CountColumn(col_idx) => {
+                    let count = self.column_counts.get(col_idx).copied().unwrap_or(0);
+                    result.push(Value::from_i64(count));
+                }
                 AggregateFunction::CountDistinct(col_idx) => {
                     // Return the computed DISTINCT count
                     let count = self.distinct_counts.get(col_idx).copied().unwrap_or(0);
@@ -1241,6 +1497,14 @@ impl AggregateState {
                     // Return the MAX value from our state
                     result.push(self.maxs.get(col_idx).cloned().unwrap_or(Value::Null));
                 }
+                AggregateFunction::Total(col_idx) => {
+                    // TOTAL is always a float, 0.0 when there are no non-NULL inputs
+                    let total = self.totals.get(col_idx).copied().unwrap_or(0.0);
+                    result.push(Value::from_f64(total));
+                }
+                AggregateFunction::Recomputed { slot, .. } => {
+                    result.push(self.recomputed.get(slot).cloned().unwrap_or(Value::Null));
+                }
             }
         }
 
@@ -1436,6 +1700,13 @@ impl AggregateOperator {
         !self.distinct_columns.is_empty() || self.is_distinct_only
     }
 
+    /// Check if this operator has any aggregates that are recomputed from their inputs
+    pub fn has_recomputed(&self) -> bool {
+        self.aggregates
+            .iter()
+            .any(|agg| matches!(agg, AggregateFunction::Recomputed { .. }))
+    }
+
     fn eval_internal(
         &mut self,
         state: &mut EvalState,
@@ -1483,6 +1754,12 @@ impl AggregateOperator {
             EvalState::Join(_) => {
                 panic!("Join state should not appear in aggregate operator");
             }
+            EvalState::TopK(_) => {
+                panic!("Top-K state should not appear in aggregate operator");
+            }
+            EvalState::Window(_) => {
+                panic!("Window state should not appear in aggregate operator");
+            }
         }
 
         // Process the delta through the aggregate state machine
@@ -1710,6 +1987,36 @@ impl AggregateOperator {
         min_max_deltas
     }
 
+    /// Extract the inputs of recomputed aggregates from delta changes
+    fn extract_recomputed_deltas(&self, delta: &Delta) -> RecomputedDeltas {
+        let mut recomputed_deltas: RecomputedDeltas = HashMap::default();
+        if !self.has_recomputed() {
+            return recomputed_deltas;
+        }
+
+        for (row, weight) in &delta.changes {
+            let group_key = self.extract_group_key(&row.values);
+            let group_key_str = Self::group_key_to_string(&group_key);
+            let group_entry = recomputed_deltas.entry(group_key_str).or_default();
+
+            for agg in &self.aggregates {
+                if let AggregateFunction::Recomputed { args, slot, .. } = agg {
+                    let arg_values: Vec<Value> = args
+                        .iter()
+                        .map(|idx| row.values.get(*idx).cloned().unwrap_or(Value::Null))
+                        .collect();
+                    let element_id = recomputed_element_id(row.rowid, &arg_values);
+                    let (_, value_weight) = group_entry
+                        .entry((*slot, element_id))
+                        .or_insert_with(|| (HashableRow::new(row.rowid, arg_values), 0));
+                    *value_weight += weight;
+                }
+            }
+        }
+
+        recomputed_deltas
+    }
+
     pub fn set_tracker(&mut self, tracker: Arc<Mutex<ComputationTracker>>) {
         self.tracker = Some(tracker);
     }
@@ -1809,6 +2116,7 @@ impl IncrementalOperator for AggregateOperator {
                     } else {
                         HashMap::default()
                     };
+                    let recomputed_deltas = self.extract_recomputed_deltas(&input_delta);
 
                     // Get old counts before eval modifies the states
                     // We need to extract this from the eval_state before it's consumed
@@ -1826,9 +2134,10 @@ impl IncrementalOperator for AggregateOperator {
                         old_states,
                         current_idx: 0,
                         write_row: WriteRow::new(),
-                        min_max_deltas,  // Store for later use
-                        distinct_deltas, // Store for distinct processing
-                        input_delta,     // Store original input
+                        min_max_deltas,    // Store for later use
+                        distinct_deltas,   // Store for distinct processing
+                        recomputed_deltas, // Store for recomputed aggregate inputs
+                        input_delta,       // Store original input
                     };
                 }
                 AggregateCommitState::PersistDelta {
@@ -1839,6 +2148,7 @@ impl IncrementalOperator for AggregateOperator {
                     write_row,
                     min_max_deltas,
                     distinct_deltas,
+                    recomputed_deltas,
                     input_delta,
                 } => {
                     let states_vec: Vec<_> = computed_states.iter().collect();
@@ -1849,6 +2159,7 @@ impl IncrementalOperator for AggregateOperator {
                             delta: delta.clone(),
                             min_max_persist_state: MinMaxPersistState::new(min_max_deltas.clone()),
                             distinct_deltas: distinct_deltas.clone(),
+                            recomputed_deltas: std::mem::take(recomputed_deltas),
                         };
                     } else {
                         let (group_key_str, (group_key, agg_state)) = states_vec[*current_idx];
@@ -1867,6 +2178,7 @@ impl IncrementalOperator for AggregateOperator {
                                         min_max_deltas,
                                     )),
                                     distinct_deltas: std::mem::take(distinct_deltas),
+                                    recomputed_deltas: std::mem::take(recomputed_deltas),
                                 };
                             } else {
                                 // Move to next group
@@ -1878,6 +2190,7 @@ impl IncrementalOperator for AggregateOperator {
                                     write_row: WriteRow::new(),
                                     min_max_deltas: std::mem::take(min_max_deltas),
                                     distinct_deltas: std::mem::take(distinct_deltas),
+                                    recomputed_deltas: std::mem::take(recomputed_deltas),
                                     input_delta: std::mem::take(input_delta),
                                 };
                             }
@@ -1924,6 +2237,7 @@ impl IncrementalOperator for AggregateOperator {
                         let computed_states = std::mem::take(computed_states);
                         let min_max_deltas = std::mem::take(min_max_deltas);
                         let distinct_deltas = std::mem::take(distinct_deltas);
+                        let recomputed_deltas = std::mem::take(recomputed_deltas);
                         let input_delta = std::mem::take(input_delta);
 
                         self.commit_state = AggregateCommitState::PersistDelta {
@@ -1934,6 +2248,7 @@ impl IncrementalOperator for AggregateOperator {
                             write_row: WriteRow::new(), // Reset for next write
                             min_max_deltas,
                             distinct_deltas,
+                            recomputed_deltas,
                             input_delta,
                         };
                     }
@@ -1942,6 +2257,7 @@ impl IncrementalOperator for AggregateOperator {
                     delta,
                     min_max_persist_state,
                     distinct_deltas,
+                    recomputed_deltas,
                 } => {
                     if self.has_min_max() {
                         return_and_restore_if_io!(
@@ -1963,11 +2279,13 @@ impl IncrementalOperator for AggregateOperator {
                     self.commit_state = AggregateCommitState::PersistDistinctValues {
                         delta,
                         distinct_persist_state,
+                        recomputed_deltas: std::mem::take(recomputed_deltas),
                     };
                 }
                 AggregateCommitState::PersistDistinctValues {
                     delta,
                     distinct_persist_state,
+                    recomputed_deltas,
                 } => {
                     if self.has_distinct() {
                         // Use the state machine to persist distinct values to BTree
@@ -1982,6 +2300,31 @@ impl IncrementalOperator for AggregateOperator {
                         );
                     }
 
+                    // Transition to PersistRecomputedInputs
+                    let delta = std::mem::take(delta);
+                    let recomputed_persist_state =
+                        RecomputedPersistState::new(std::mem::take(recomputed_deltas));
+                    self.commit_state = AggregateCommitState::PersistRecomputedInputs {
+                        delta,
+                        recomputed_persist_state,
+                    };
+                }
+                AggregateCommitState::PersistRecomputedInputs {
+                    delta,
+                    recomputed_persist_state,
+                } => {
+                    if self.has_recomputed() {
+                        return_and_restore_if_io!(
+                            &mut self.commit_state,
+                            state,
+                            recomputed_persist_state.persist_inputs(
+                                self.operator_id,
+                                cursors,
+                                |group_key_str| self.generate_group_hash(group_key_str)
+                            )
+                        );
+                    }
+
                     // Transition to Done
                     let delta = std::mem::take(delta);
                     self.commit_state = AggregateCommitState::Done { delta };
@@ -2500,6 +2843,311 @@ impl ScanState {
     }
 }
 
+// Helper function to read the next stored input of a recomputed aggregate, in rowid order
+fn read_next_recomputed_input(
+    storage_id: i64,
+    zset_hash: Hash128,
+    last_element_id: Option<Hash128>,
+    cursors: &mut DbspStateCursors,
+) -> Result<IOResult<Option<(Hash128, HashableRow, isize)>>> {
+    // NULL sorts first, so the initial seek lands on the first input of the group
+    let index_key_values = vec![
+        Value::from_i64(storage_id),
+        zset_hash.to_value(),
+        last_element_id.map_or(Value::Null, |id| id.to_value()),
+    ];
+    let index_record = ImmutableRecord::from_values(&index_key_values, index_key_values.len());
+    let seek_op = if last_element_id.is_none() {
+        SeekOp::GE { eq_only: false }
+    } else {
+        SeekOp::GT
+    };
+
+    let seek_result = return_if_io!(cursors
+        .index_cursor
+        .seek(SeekKey::IndexKey(&index_record), seek_op));
+    if !matches!(seek_result, SeekResult::Found) {
+        return Ok(IOResult::Done(None));
+    }
+
+    let current_record = return_if_io!(cursors.index_cursor.record());
+    let Some(rec) = current_record else {
+        return Ok(IOResult::Done(None));
+    };
+    let (v0, v1, v2) = rec.get_three_values(0, 1, 2)?;
+    match v0.to_owned() {
+        Value::Numeric(Numeric::Integer(id)) if id == storage_id => {}
+        // Moved past the inputs of this aggregate
+        _ => return Ok(IOResult::Done(None)),
+    }
+    match v1.to_owned() {
+        Value::Blob(blob) if Hash128::from_blob(&blob) == Some(zset_hash) => {}
+        // Moved past the inputs of this group
+        _ => return Ok(IOResult::Done(None)),
+    }
+    let element_id = match v2.to_owned() {
+        Value::Blob(blob) => Hash128::from_blob(&blob)
+            .ok_or_else(|| LimboError::InternalError("Invalid element_id blob".to_string()))?,
+        _ => {
+            return Err(LimboError::InternalError(
+                "Invalid element_id in recomputed aggregate storage".to_string(),
+            ))
+        }
+    };
+
+    let rowid = return_if_io!(cursors.index_cursor.rowid()).ok_or_else(|| {
+        LimboError::InternalError("Index cursor does not have a valid rowid".to_string())
+    })?;
+    let table_res = return_if_io!(cursors
+        .table_cursor
+        .seek(SeekKey::TableRowId(rowid), SeekOp::GE { eq_only: true }));
+    if !matches!(table_res, SeekResult::Found) {
+        return Err(LimboError::InternalError(
+            "Index points to non-existent table row".to_string(),
+        ));
+    }
+
+    let table_record = return_if_io!(cursors.table_cursor.record());
+    let rec = table_record.ok_or_else(|| {
+        LimboError::InternalError("Found rowid in table but could not read record".to_string())
+    })?;
+    // Table format: [storage_id, zset_id, element_id, value_blob, weight]
+    let (value, weight) = rec.get_two_values(3, 4)?;
+    let row = match value.to_owned() {
+        Value::Blob(blob) => deserialize_hashable_row(&blob)?,
+        _ => {
+            return Err(LimboError::InternalError(
+                "Invalid input value in recomputed aggregate storage".to_string(),
+            ))
+        }
+    };
+    let weight = match weight.to_owned() {
+        Value::Numeric(Numeric::Integer(w)) => w as isize,
+        _ => {
+            return Err(LimboError::InternalError(
+                "Invalid weight value in storage".to_string(),
+            ))
+        }
+    };
+
+    Ok(IOResult::Done(Some((element_id, row, weight))))
+}
+
+/// State machine for recomputing aggregates from all inputs of the changed groups
+#[derive(Debug)]
+pub enum RecomputeAggregates {
+    NextAggregate {
+        /// (group_key, slot) pairs to recompute
+        pending: Vec<(String, usize)>,
+        /// Current index in pending
+        current_idx: usize,
+        /// Inputs changed by this batch
+        recomputed_deltas: RecomputedDeltas,
+    },
+    ScanInputs {
+        pending: Vec<(String, usize)>,
+        current_idx: usize,
+        recomputed_deltas: RecomputedDeltas,
+        /// Stored inputs read so far, in element_id (rowid) order
+        inputs: BTreeMap<Hash128, (HashableRow, isize)>,
+        /// Last element_id read, to resume the scan after it
+        last_element_id: Option<Hash128>,
+    },
+    Done,
+}
+
+impl RecomputeAggregates {
+    pub fn new(recomputed_deltas: RecomputedDeltas) -> Self {
+        let mut pending: Vec<(String, usize)> = recomputed_deltas
+            .iter()
+            .flat_map(|(group_key, inputs)| {
+                inputs
+                    .keys()
+                    .map(|(slot, _)| (group_key.clone(), *slot))
+                    .collect::<HashSet<_>>()
+            })
+            .collect();
+        pending.sort();
+
+        if pending.is_empty() {
+            Self::Done
+        } else {
+            Self::NextAggregate {
+                pending,
+                current_idx: 0,
+                recomputed_deltas,
+            }
+        }
+    }
+
+    pub fn process(
+        &mut self,
+        existing_groups: &mut HashMap<String, AggregateState>,
+        operator: &AggregateOperator,
+        cursors: &mut DbspStateCursors,
+    ) -> Result<IOResult<()>> {
+        loop {
+            match self {
+                RecomputeAggregates::NextAggregate {
+                    pending,
+                    current_idx,
+                    recomputed_deltas,
+                } => {
+                    if *current_idx >= pending.len() {
+                        *self = RecomputeAggregates::Done;
+                        return Ok(IOResult::Done(()));
+                    }
+
+                    *self = RecomputeAggregates::ScanInputs {
+                        pending: std::mem::take(pending),
+                        current_idx: *current_idx,
+                        recomputed_deltas: std::mem::take(recomputed_deltas),
+                        inputs: BTreeMap::new(),
+                        last_element_id: None,
+                    };
+                }
+                RecomputeAggregates::ScanInputs {
+                    pending,
+                    current_idx,
+                    recomputed_deltas,
+                    inputs,
+                    last_element_id,
+                } => {
+                    let (group_key, slot) = &pending[*current_idx];
+                    let storage_id =
+                        generate_storage_id(operator.operator_id, *slot, AGG_TYPE_RECOMPUTED);
+                    let zset_hash = operator.generate_group_hash(group_key);
+
+                    // Read every stored input of this group
+                    while let Some((element_id, row, weight)) =
+                        return_if_io!(read_next_recomputed_input(
+                            storage_id,
+                            zset_hash,
+                            *last_element_id,
+                            cursors
+                        ))
+                    {
+                        inputs.insert(element_id, (row, weight));
+                        *last_element_id = Some(element_id);
+                    }
+
+                    // Merge the inputs changed by this batch
+                    if let Some(group_deltas) = recomputed_deltas.get(group_key) {
+                        for ((delta_slot, element_id), (row, weight)) in group_deltas {
+                            if delta_slot == slot {
+                                let (_, stored_weight) = inputs
+                                    .entry(*element_id)
+                                    .or_insert_with(|| (row.clone(), 0));
+                                *stored_weight += weight;
+                            }
+                        }
+                    }
+
+                    let func = operator
+                        .aggregates
+                        .iter()
+                        .find_map(|agg| match agg {
+                            AggregateFunction::Recomputed {
+                                func,
+                                slot: agg_slot,
+                                ..
+                            } if agg_slot == slot => Some(func),
+                            _ => None,
+                        })
+                        .ok_or_else(|| {
+                            LimboError::InternalError(format!(
+                                "No recomputed aggregate in slot {slot}"
+                            ))
+                        })?;
+
+                    // Feed each remaining input as many times as it occurs
+                    let mut arg_rows = Vec::new();
+                    for (row, weight) in inputs.values() {
+                        for _ in 0..(*weight).max(0) {
+                            arg_rows.push(row.values.clone());
+                        }
+                    }
+                    let result = crate::vdbe::execute::compute_aggregate(func, &arg_rows)?;
+                    existing_groups
+                        .entry(group_key.clone())
+                        .or_default()
+                        .recomputed
+                        .insert(*slot, result);
+
+                    *self = RecomputeAggregates::NextAggregate {
+                        pending: std::mem::take(pending),
+                        current_idx: *current_idx + 1,
+                        recomputed_deltas: std::mem::take(recomputed_deltas),
+                    };
+                }
+                RecomputeAggregates::Done => {
+                    return Ok(IOResult::Done(()));
+                }
+            }
+        }
+    }
+}
+
+/// State machine for persisting the inputs of recomputed aggregates to storage
+#[derive(Debug)]
+pub struct RecomputedPersistState {
+    /// (group_key, slot, element_id, input row, weight) to write
+    inputs: Vec<(String, usize, Hash128, HashableRow, isize)>,
+    current_idx: usize,
+    write_row: WriteRow,
+}
+
+impl RecomputedPersistState {
+    pub fn new(recomputed_deltas: RecomputedDeltas) -> Self {
+        let mut inputs = Vec::new();
+        for (group_key, group_inputs) in recomputed_deltas {
+            for ((slot, element_id), (row, weight)) in group_inputs {
+                if weight != 0 {
+                    inputs.push((group_key.clone(), slot, element_id, row, weight));
+                }
+            }
+        }
+        Self {
+            inputs,
+            current_idx: 0,
+            write_row: WriteRow::new(),
+        }
+    }
+
+    pub fn persist_inputs(
+        &mut self,
+        operator_id: i64,
+        cursors: &mut DbspStateCursors,
+        generate_group_hash: impl Fn(&str) -> Hash128,
+    ) -> Result<IOResult<()>> {
+        while let Some((group_key, slot, element_id, row, weight)) =
+            self.inputs.get(self.current_idx)
+        {
+            let storage_id = generate_storage_id(operator_id, *slot, AGG_TYPE_RECOMPUTED);
+            let zset_hash = generate_group_hash(group_key);
+
+            let index_key = vec![
+                Value::from_i64(storage_id),
+                zset_hash.to_value(),
+                element_id.to_value(),
+            ];
+            let record_values = vec![
+                Value::from_i64(storage_id),
+                zset_hash.to_value(),
+                element_id.to_value(),
+                Value::Blob(serialize_hashable_row(row)),
+            ];
+            return_if_io!(self
+                .write_row
+                .write_row(cursors, index_key, record_values, *weight));
+
+            self.write_row = WriteRow::new();
+            self.current_idx += 1;
+        }
+        Ok(IOResult::Done(()))
+    }
+}
+
 /// State machine for persisting Min/Max values to storage
 #[derive(Debug)]
 pub enum MinMaxPersistState {
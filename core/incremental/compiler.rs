@@ -11,6 +11,8 @@ use crate::incremental::expr_compiler::CompiledExpression;
 use crate::incremental::operator::{
     create_dbsp_state_index, DbspStateCursors, EvalState, FilterOperator, FilterPredicate,
     IncrementalOperator, InputOperator, JoinOperator, JoinType, ProjectOperator,
+    TableFunctionOperator, TopKOperator, TopKSortKey, WindowArg, WindowFrame, WindowFrameBound,
+    WindowFrameUnits, WindowFunctionSpec, WindowOperator,
 };
 use crate::schema::Type;
 use crate::storage::btree::{BTreeCursor, BTreeKey, CursorTrait};
@@ -18,8 +20,9 @@ use crate::storage::btree::{BTreeCursor, BTreeKey, CursorTrait};
 use crate::numeric::Numeric;
 use crate::sync::{atomic::Ordering, Arc};
 use crate::translate::logical::{
-    BinaryOperator, Column, ColumnInfo, JoinType as LogicalJoinType, LogicalExpr, LogicalPlan,
-    LogicalSchema, SchemaRef,
+    strip_alias, Aggregate, BinaryOperator, Column, ColumnInfo, Filter,
+    JoinType as LogicalJoinType, Limit, LogicalExpr, LogicalPlan, LogicalSchema, Projection,
+    SchemaRef, SetOperation, SetOperator, TableFunction, Union, Window,
 };
 use crate::types::{IOResult, ImmutableRecord, SeekKey, SeekOp, SeekResult, Value};
 use crate::Pager;
@@ -300,10 +303,31 @@ pub enum DbspOperator {
     },
     /// Input operator - source of data
     Input { name: String, schema: SchemaRef },
+    /// Table function operator - joins each row with the rows of a table-valued function
+    TableFunction {
+        name: String,
+        args: Vec<DbspExpr>,
+        outer: bool,
+        schema: SchemaRef,
+    },
     /// Merge operator for combining streams (used in recursive CTEs and UNION)
     Merge { schema: SchemaRef },
     /// Distinct operator - removes duplicates
     Distinct { schema: SchemaRef },
+    /// Top-K operator - keeps the rows selected by ORDER BY ... LIMIT ... OFFSET
+    TopK {
+        sort_keys: Vec<TopKSortKey>,
+        offset: usize,
+        limit: Option<usize>,
+        schema: SchemaRef,
+    },
+    /// Window operator - appends the values of window functions to each row
+    Window {
+        partition_by: Vec<usize>,
+        order_by: Vec<TopKSortKey>,
+        functions: Vec<WindowFunctionSpec>,
+        schema: SchemaRef,
+    },
 }
 
 /// Represents an expression in DBSP
@@ -831,6 +855,37 @@ impl DbspCircuit {
                         schema.columns.len()
                     )?;
                 }
+                DbspOperator::TopK {
+                    sort_keys,
+                    offset,
+                    limit,
+                    ..
+                } => {
+                    writeln!(
+                        f,
+                        "{indent}TopK[{node_id}]: ORDER BY {sort_keys:?}, LIMIT {limit:?} OFFSET {offset}"
+                    )?;
+                }
+                DbspOperator::TableFunction {
+                    name, args, outer, ..
+                } => {
+                    let join = if *outer { "LEFT " } else { "" };
+                    writeln!(
+                        f,
+                        "{indent}{join}TableFunction[{node_id}]: {name}({args:?})"
+                    )?;
+                }
+                DbspOperator::Window {
+                    partition_by,
+                    order_by,
+                    functions,
+                    ..
+                } => {
+                    writeln!(
+                        f,
+                        "{indent}Window[{node_id}]: PARTITION BY {partition_by:?}, ORDER BY {order_by:?}, functions: {functions:?}"
+                    )?;
+                }
             }
 
             for input_id in &node.inputs {
@@ -1189,6 +1244,19 @@ impl DbspCompiler {
                                             "Only column references are supported in aggregate functions for incremental views".to_string()
                                         ));
                                     }
+                                } else if let Some(arg) = args.first() {
+                                    // COUNT(expr) only counts rows where expr is not NULL
+                                    if let LogicalExpr::Column(col) = arg {
+                                        let (col_idx, _) = input_schema.find_column(&col.name, col.table.as_deref())
+                                            .ok_or_else(|| LimboError::ParseError(
+                                                format!("COUNT column '{}' not found in input", col.name)
+                                            ))?;
+                                        aggregate_functions.push(AggregateFunction::CountColumn(col_idx));
+                                    } else {
+                                        return Err(LimboError::ParseError(
+                                            "Only column references are supported in aggregate functions for incremental views".to_string()
+                                        ));
+                                    }
                                 } else {
                                     aggregate_functions.push(AggregateFunction::Count);
                                 }
@@ -1266,10 +1334,55 @@ impl DbspCompiler {
                                     ));
                                 }
                             }
+                            AggFunc::Total => {
+                                if args.is_empty() {
+                                    return Err(LimboError::ParseError("TOTAL requires an argument".to_string()));
+                                }
+                                if *distinct {
+                                    return Err(LimboError::ParseError(
+                                        "TOTAL(DISTINCT) is not supported in incremental views".to_string()
+                                    ));
+                                }
+                                if let LogicalExpr::Column(col) = &args[0] {
+                                    let (col_idx, _) = input_schema.find_column(&col.name, col.table.as_deref())
+                                        .ok_or_else(|| LimboError::ParseError(
+                                            format!("TOTAL column '{}' not found in input", col.name)
+                                        ))?;
+                                    aggregate_functions.push(AggregateFunction::Total(col_idx));
+                                } else {
+                                    return Err(LimboError::ParseError(
+                                        "Only column references are supported in aggregate functions for incremental views".to_string()
+                                    ));
+                                }
+                            }
+                            // No running summary survives a deletion for these, so they are
+                            // recomputed from the stored inputs of each changed group
                             _ => {
-                                return Err(LimboError::ParseError(
-                                    format!("Unsupported aggregate function in DBSP compiler: {fun:?}")
-                                ));
+                                if *distinct {
+                                    return Err(LimboError::ParseError(format!(
+                                        "{}(DISTINCT) is not supported in incremental views",
+                                        fun.as_str().to_uppercase()
+                                    )));
+                                }
+                                let mut arg_indices = Vec::with_capacity(args.len());
+                                for arg in args {
+                                    if let LogicalExpr::Column(col) = arg {
+                                        let (col_idx, _) = input_schema.find_column(&col.name, col.table.as_deref())
+                                            .ok_or_else(|| LimboError::ParseError(
+                                                format!("{} column '{}' not found in input", fun.as_str().to_uppercase(), col.name)
+                                            ))?;
+                                        arg_indices.push(col_idx);
+                                    } else {
+                                        return Err(LimboError::ParseError(
+                                            "Only column references are supported in aggregate functions for incremental views".to_string()
+                                        ));
+                                    }
+                                }
+                                aggregate_functions.push(AggregateFunction::Recomputed {
+                                    func: fun.clone(),
+                                    args: arg_indices,
+                                    slot: aggregate_functions.len(),
+                                });
                             }
                         }
                     } else {
@@ -1407,10 +1520,18 @@ impl DbspCompiler {
                 );
                 Ok(node_id)
             }
+            LogicalPlan::TableFunction(table_function) => {
+                // Handle a table-valued function called for each input row
+                self.compile_table_function(table_function)
+            }
             LogicalPlan::Union(union) => {
                 // Handle UNION and UNION ALL
                 self.compile_union(union)
             }
+            LogicalPlan::SetOperation(set_op) => {
+                // Handle INTERSECT and EXCEPT
+                self.compile_set_operation(set_op)
+            }
             LogicalPlan::Distinct(distinct) => {
                 // DISTINCT is implemented as GROUP BY all columns with a special aggregate
                 let input_id = self.compile_plan(&distinct.input)?;
@@ -1446,11 +1567,21 @@ impl DbspCompiler {
 
                 Ok(node_id)
             }
+            LogicalPlan::Sort(sort) => {
+                // The rows of a view are unordered, so ORDER BY only matters under a LIMIT
+                self.compile_plan(&sort.input)
+            }
+            LogicalPlan::Limit(limit) => {
+                // Handle LIMIT/OFFSET, together with the ORDER BY below it
+                self.compile_limit(limit)
+            }
+            LogicalPlan::Window(window) => {
+                // Handle window functions sharing one PARTITION BY and ORDER BY
+                self.compile_window(window)
+            }
             _ => Err(LimboError::ParseError(
                 format!("Unsupported operator in DBSP compiler: only Filter, Projection, Join, Aggregate, and Union are supported, got: {:?}",
                     match plan {
-                        LogicalPlan::Sort(_) => "Sort",
-                        LogicalPlan::Limit(_) => "Limit",
                         LogicalPlan::Union(_) => "Union",
                                     LogicalPlan::EmptyRelation(_) => "EmptyRelation",
                         LogicalPlan::Values(_) => "Values",
@@ -1483,6 +1614,10 @@ impl DbspCompiler {
                 // Aggregate of a table
                 format!("agg_{}", Self::extract_source_identifier(&agg.input))
             }
+            LogicalPlan::Window(window) => {
+                // Window functions over a table
+                format!("window_{}", Self::extract_source_identifier(&window.input))
+            }
             LogicalPlan::Sort(sort) => {
                 // Pass through to input
                 Self::extract_source_identifier(&sort.input)
@@ -1497,6 +1632,14 @@ impl DbspCompiler {
                 let right_id = Self::extract_source_identifier(&join.right);
                 format!("join_{left_id}_{right_id}")
             }
+            LogicalPlan::TableFunction(table_function) => {
+                // Table-valued function over a source
+                format!(
+                    "{}_{}",
+                    table_function.vtab.name,
+                    Self::extract_source_identifier(&table_function.input)
+                )
+            }
             LogicalPlan::Union(union) => {
                 // Union of multiple sources
                 if union.inputs.is_empty() {
@@ -1510,6 +1653,12 @@ impl DbspCompiler {
                     format!("union_{}", identifiers.join("_"))
                 }
             }
+            LogicalPlan::SetOperation(set_op) => {
+                // Intersection or difference of two sources
+                let left_id = Self::extract_source_identifier(&set_op.left);
+                let right_id = Self::extract_source_identifier(&set_op.right);
+                format!("setop_{left_id}_{right_id}")
+            }
             LogicalPlan::Distinct(distinct) => {
                 // Distinct of a source
                 format!(
@@ -1573,6 +1722,404 @@ impl DbspCompiler {
         Ok(merge_id)
     }
 
+    /// Compile LIMIT/OFFSET into a top-K operator.
+    ///
+    /// The ORDER BY terms must name columns of the input, either directly or by position.
+    /// Without ORDER BY the window is still maintained, but which rows it holds is arbitrary.
+    fn compile_limit(&mut self, limit: &Limit) -> Result<i64> {
+        let (input, sort_exprs) = match limit.input.as_ref() {
+            LogicalPlan::Sort(sort) => (&sort.input, sort.exprs.as_slice()),
+            _ => (&limit.input, [].as_slice()),
+        };
+        let input_id = self.compile_plan(input)?;
+        let input_schema = input.schema();
+
+        let sort_keys = sort_exprs
+            .iter()
+            .map(|sort_expr| {
+                let column_index = match strip_alias(&sort_expr.expr) {
+                    LogicalExpr::Column(col) => input_schema
+                        .find_column(&col.name, col.table.as_deref())
+                        .or_else(|| input_schema.find_column(&col.name, None))
+                        .map(|(idx, _)| idx),
+                    LogicalExpr::Literal(Value::Numeric(Numeric::Integer(pos))) => {
+                        usize::try_from(*pos)
+                            .ok()
+                            .filter(|pos| (1..=input_schema.column_count()).contains(pos))
+                            .map(|pos| pos - 1)
+                    }
+                    _ => None,
+                };
+                let column_index = column_index.ok_or_else(|| {
+                    LimboError::ParseError(format!(
+                        "ORDER BY term {:?} in an incremental view must be a result column",
+                        sort_expr.expr
+                    ))
+                })?;
+                Ok(TopKSortKey {
+                    column_index,
+                    asc: sort_expr.asc,
+                    nulls_first: sort_expr.nulls_first,
+                    collation: sort_expr.collation.unwrap_or_default(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let offset = limit.skip.unwrap_or(0);
+        let operator_id = self.circuit.next_id;
+        let executable: Box<dyn IncrementalOperator> = Box::new(TopKOperator::new(
+            operator_id,
+            sort_keys.clone(),
+            offset,
+            limit.fetch,
+        ));
+
+        let node_id = self.circuit.add_node(
+            DbspOperator::TopK {
+                sort_keys,
+                offset,
+                limit: limit.fetch,
+                schema: input_schema.clone(),
+            },
+            vec![input_id],
+            executable,
+        );
+        Ok(node_id)
+    }
+
+    /// Compile a window node into a window operator.
+    ///
+    /// PARTITION BY, ORDER BY and the function arguments must be columns of the input (or
+    /// literals, for arguments). Frames may use ROWS and GROUPS offsets, but RANGE frames
+    /// are limited to unbounded and CURRENT ROW bounds.
+    fn compile_window(&mut self, window: &Window) -> Result<i64> {
+        let input_id = self.compile_plan(&window.input)?;
+        let input_schema = window.input.schema();
+        let resolve_column = |expr: &LogicalExpr, what: &str| -> Result<usize> {
+            match strip_alias(expr) {
+                LogicalExpr::Column(col) => input_schema
+                    .find_column(&col.name, col.table.as_deref())
+                    .or_else(|| input_schema.find_column(&col.name, None))
+                    .map(|(idx, _)| idx)
+                    .ok_or_else(|| {
+                        LimboError::ParseError(format!(
+                            "{what} column '{}' not found in input",
+                            col.name
+                        ))
+                    }),
+                _ => Err(LimboError::ParseError(format!(
+                    "Only column references are supported in window {what} for incremental views"
+                ))),
+            }
+        };
+
+        let mut window_spec = None;
+        let mut functions = Vec::with_capacity(window.window_expr.len());
+        for expr in &window.window_expr {
+            let LogicalExpr::WindowFunction {
+                fun,
+                args,
+                partition_by,
+                order_by,
+                frame,
+            } = expr
+            else {
+                return Err(LimboError::ParseError(
+                    "Expected window function in window expressions".to_string(),
+                ));
+            };
+            match window_spec {
+                None => window_spec = Some((partition_by, order_by)),
+                Some((p, o)) if p == partition_by && o == order_by => {}
+                Some(_) => {
+                    return Err(LimboError::InternalError(
+                        "window functions of one window node must share PARTITION BY and ORDER BY"
+                            .to_string(),
+                    ))
+                }
+            }
+
+            let args = args
+                .iter()
+                .map(|arg| match strip_alias(arg) {
+                    LogicalExpr::Literal(value) => Ok(WindowArg::Literal(value.clone())),
+                    arg => resolve_column(arg, "function arguments").map(WindowArg::Column),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let frame = match frame {
+                Some(frame) => Self::compile_window_frame(frame)?,
+                None => WindowFrame::DEFAULT,
+            };
+            functions.push(WindowFunctionSpec {
+                func: fun.clone(),
+                args,
+                frame,
+            });
+        }
+
+        let (partition_by, order_by) = match window_spec {
+            Some((partition_by, order_by)) => (
+                partition_by
+                    .iter()
+                    .map(|expr| resolve_column(expr, "PARTITION BY"))
+                    .collect::<Result<Vec<_>>>()?,
+                order_by
+                    .iter()
+                    .map(|sort_expr| {
+                        Ok(TopKSortKey {
+                            column_index: resolve_column(&sort_expr.expr, "ORDER BY")?,
+                            asc: sort_expr.asc,
+                            nulls_first: sort_expr.nulls_first,
+                            collation: sort_expr.collation.unwrap_or_default(),
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            None => (vec![], vec![]),
+        };
+
+        let operator_id = self.circuit.next_id;
+        let executable: Box<dyn IncrementalOperator> = Box::new(WindowOperator::new(
+            operator_id,
+            partition_by.clone(),
+            order_by.clone(),
+            functions.clone(),
+        ));
+
+        let node_id = self.circuit.add_node(
+            DbspOperator::Window {
+                partition_by,
+                order_by,
+                functions,
+                schema: window.schema.clone(),
+            },
+            vec![input_id],
+            executable,
+        );
+        Ok(node_id)
+    }
+
+    /// Compile a table-valued function called for each row of its input
+    fn compile_table_function(&mut self, table_function: &TableFunction) -> Result<i64> {
+        let input_id = self.compile_plan(&table_function.input)?;
+        let input_schema = table_function.input.schema();
+
+        let mut args = Vec::with_capacity(table_function.args.len());
+        let mut dbsp_args = Vec::with_capacity(table_function.args.len());
+        for arg in &table_function.args {
+            let (compiled, _) = Self::compile_expression(arg, input_schema)?;
+            args.push(compiled);
+            dbsp_args.push(Self::compile_expr(arg)?);
+        }
+
+        let executable: Box<dyn IncrementalOperator> = Box::new(TableFunctionOperator::new(
+            table_function.vtab.clone(),
+            args,
+            table_function.outer,
+        )?);
+        let node_id = self.circuit.add_node(
+            DbspOperator::TableFunction {
+                name: table_function.vtab.name.clone(),
+                args: dbsp_args,
+                outer: table_function.outer,
+                schema: table_function.schema.clone(),
+            },
+            vec![input_id],
+            executable,
+        );
+        Ok(node_id)
+    }
+
+    /// Convert the frame clause of a window function
+    fn compile_window_frame(frame: &turso_parser::ast::FrameClause) -> Result<WindowFrame> {
+        use turso_parser::ast;
+
+        let units = match frame.mode {
+            ast::FrameMode::Rows => WindowFrameUnits::Rows,
+            ast::FrameMode::Range => WindowFrameUnits::Range,
+            ast::FrameMode::Groups => WindowFrameUnits::Groups,
+        };
+        let bound = |bound: &ast::FrameBound| -> Result<WindowFrameBound> {
+            let offset = |expr: &ast::Expr| {
+                match expr {
+                ast::Expr::Literal(ast::Literal::Numeric(n)) if units != WindowFrameUnits::Range => {
+                    n.parse::<usize>().map_err(|_| {
+                        LimboError::ParseError(
+                            "frame starting and ending offsets must be non-negative integers"
+                                .to_string(),
+                        )
+                    })
+                }
+                _ => Err(LimboError::ParseError(
+                    "frame offsets in incremental views must be integer literals in ROWS or GROUPS frames"
+                        .to_string(),
+                )),
+            }
+            };
+            Ok(match bound {
+                ast::FrameBound::UnboundedPreceding => WindowFrameBound::UnboundedPreceding,
+                ast::FrameBound::Preceding(expr) => WindowFrameBound::Preceding(offset(expr)?),
+                ast::FrameBound::CurrentRow => WindowFrameBound::CurrentRow,
+                ast::FrameBound::Following(expr) => WindowFrameBound::Following(offset(expr)?),
+                ast::FrameBound::UnboundedFollowing => WindowFrameBound::UnboundedFollowing,
+            })
+        };
+        let start = bound(&frame.start)?;
+        let end = match &frame.end {
+            Some(end) => bound(end)?,
+            None => WindowFrameBound::CurrentRow,
+        };
+        let supported = !matches!(
+            (start, end),
+            (WindowFrameBound::UnboundedFollowing, _)
+                | (_, WindowFrameBound::UnboundedPreceding)
+                | (WindowFrameBound::CurrentRow, WindowFrameBound::Preceding(_))
+                | (
+                    WindowFrameBound::Following(_),
+                    WindowFrameBound::Preceding(_) | WindowFrameBound::CurrentRow
+                )
+        );
+        if !supported {
+            return Err(LimboError::ParseError(
+                "unsupported frame specification".to_string(),
+            ));
+        }
+        if !matches!(frame.exclude, None | Some(ast::FrameExclude::NoOthers)) {
+            return Err(LimboError::ParseError(
+                "frame exclusions are not supported in incremental views".to_string(),
+            ));
+        }
+        Ok(WindowFrame { units, start, end })
+    }
+
+    /// Compile an INTERSECT or EXCEPT operator
+    ///
+    /// Z-sets have no native intersection or difference, so the operation is rewritten into
+    /// operators that already maintain persistent state:
+    ///
+    /// 1. each side is projected with a marker column (`1, 0` on the left, `0, 1` on the right),
+    /// 2. both sides are merged with UNION ALL,
+    /// 3. the merged stream is grouped by every output column, summing both markers, which gives
+    ///    the multiplicity of each row on either side,
+    /// 4. a filter keeps the groups present on both sides (INTERSECT) or only on the left (EXCEPT),
+    /// 5. a final projection drops the counters.
+    ///
+    /// Like their SQL counterparts, the results contain each row at most once.
+    fn compile_set_operation(&mut self, set_op: &SetOperation) -> Result<i64> {
+        const LEFT_COUNT: &str = "__set_left_count";
+        const RIGHT_COUNT: &str = "__set_right_count";
+
+        let column_names: Vec<String> = (0..set_op.schema.column_count())
+            .map(|i| format!("__set_col{i}"))
+            .collect();
+        let counter_info = |name: &str| ColumnInfo {
+            name: name.to_string(),
+            ty: Type::Integer,
+            database: None,
+            table: None,
+            table_alias: None,
+        };
+
+        // 1. Tag each side with its marker columns, renaming the data columns so that both
+        //    sides line up even when their column names differ.
+        let tag = |input: &Arc<LogicalPlan>, left_marker: i64, right_marker: i64| {
+            let mut exprs = Vec::new();
+            let mut columns = Vec::new();
+            for (col, name) in input.schema().columns.iter().zip(&column_names) {
+                exprs.push(LogicalExpr::Alias {
+                    expr: Box::new(LogicalExpr::Column(Column {
+                        name: col.name.clone(),
+                        table: col.table_alias.clone().or_else(|| col.table.clone()),
+                    })),
+                    alias: name.clone(),
+                });
+                columns.push(ColumnInfo {
+                    name: name.clone(),
+                    ty: col.ty,
+                    database: None,
+                    table: None,
+                    table_alias: None,
+                });
+            }
+            for (name, marker) in [(LEFT_COUNT, left_marker), (RIGHT_COUNT, right_marker)] {
+                exprs.push(LogicalExpr::Alias {
+                    expr: Box::new(LogicalExpr::Literal(Value::from_i64(marker))),
+                    alias: name.to_string(),
+                });
+                columns.push(counter_info(name));
+            }
+            LogicalPlan::Projection(Projection {
+                input: input.clone(),
+                exprs,
+                schema: Arc::new(LogicalSchema::new(columns)),
+            })
+        };
+        let left = tag(&set_op.left, 1, 0);
+        let right = tag(&set_op.right, 0, 1);
+
+        // 2. Merge both sides, keeping duplicates.
+        let merged = LogicalPlan::Union(Union {
+            schema: left.schema().clone(),
+            inputs: vec![Arc::new(left), Arc::new(right)],
+            all: true,
+        });
+
+        // 3. Count the occurrences of every row on each side.
+        let group_expr: Vec<LogicalExpr> = column_names
+            .iter()
+            .map(|name| LogicalExpr::Column(Column::new(name.clone())))
+            .collect();
+        let aggr_expr = [LEFT_COUNT, RIGHT_COUNT]
+            .into_iter()
+            .map(|name| LogicalExpr::AggregateFunction {
+                fun: crate::function::AggFunc::Sum,
+                args: vec![LogicalExpr::Column(Column::new(name))],
+                distinct: false,
+            })
+            .collect();
+        let mut aggregate_columns: Vec<ColumnInfo> =
+            merged.schema().columns[..column_names.len()].to_vec();
+        aggregate_columns.push(counter_info(LEFT_COUNT));
+        aggregate_columns.push(counter_info(RIGHT_COUNT));
+        let aggregate = LogicalPlan::Aggregate(Aggregate {
+            input: Arc::new(merged),
+            group_expr,
+            aggr_expr,
+            schema: Arc::new(LogicalSchema::new(aggregate_columns)),
+        });
+
+        // 4. Keep the rows that belong to the result.
+        let compare = |name: &str, op: BinaryOperator| LogicalExpr::BinaryExpr {
+            left: Box::new(LogicalExpr::Column(Column::new(name))),
+            op,
+            right: Box::new(LogicalExpr::Literal(Value::from_i64(0))),
+        };
+        let right_predicate = match set_op.op {
+            SetOperator::Intersect => compare(RIGHT_COUNT, BinaryOperator::Greater),
+            SetOperator::Except => compare(RIGHT_COUNT, BinaryOperator::LessEquals),
+        };
+        let filter = LogicalPlan::Filter(Filter {
+            input: Arc::new(aggregate),
+            predicate: LogicalExpr::BinaryExpr {
+                left: Box::new(compare(LEFT_COUNT, BinaryOperator::Greater)),
+                op: BinaryOperator::And,
+                right: Box::new(right_predicate),
+            },
+        });
+
+        // 5. Restore the original columns.
+        let projection = LogicalPlan::Projection(Projection {
+            input: Arc::new(filter),
+            exprs: column_names
+                .iter()
+                .map(|name| LogicalExpr::Column(Column::new(name.clone())))
+                .collect(),
+            schema: set_op.schema.clone(),
+        });
+
+        self.compile_plan(&projection)
+    }
+
     /// Convert a logical expression to a DBSP expression
     fn compile_expr(expr: &LogicalExpr) -> Result<DbspExpr> {
         match expr {
@@ -2272,6 +2819,7 @@ mod tests {
     };
     use crate::storage::pager::CreateBTreeFlags;
     use crate::sync::Arc;
+    use crate::translate::collate::CollationSeq;
     use crate::translate::logical::{ColumnInfo, LogicalPlanBuilder, LogicalSchema};
     use crate::util::IOExt;
     use crate::{Database, MemoryIO, Pager, IO};
@@ -6145,4 +6693,349 @@ mod tests {
         assert_eq!(actual_right.name, "right_id");
         assert_eq!(right_idx, 0);
     }
+
+    #[test]
+    fn test_order_by_limit_compiles_to_topk() {
+        let (circuit, _) = compile_sql!("SELECT name, age FROM users ORDER BY age DESC LIMIT 2");
+
+        assert_circuit!(circuit, depth: 3, root: TopK);
+        let node = get_node_at_level(&circuit, 0);
+        match &node.operator {
+            DbspOperator::TopK {
+                sort_keys,
+                offset,
+                limit,
+                ..
+            } => {
+                assert_eq!(
+                    sort_keys,
+                    &vec![TopKSortKey {
+                        column_index: 1,
+                        asc: false,
+                        nulls_first: false,
+                        collation: CollationSeq::Binary,
+                    }]
+                );
+                assert_eq!(*offset, 0);
+                assert_eq!(*limit, Some(2));
+            }
+            _ => panic!("Expected TopK operator at level 0"),
+        }
+        assert_operator!(
+            circuit,
+            1,
+            Projection {
+                columns: ["name", "age"]
+            }
+        );
+        assert_operator!(circuit, 2, Input { name: "users" });
+    }
+
+    #[test]
+    fn test_order_by_without_limit_is_dropped() {
+        let (circuit, _) = compile_sql!("SELECT name FROM users ORDER BY name");
+
+        assert_circuit!(circuit, depth: 2, root: Projection);
+    }
+
+    #[test]
+    fn test_order_by_expression_with_limit_rejected() {
+        let schema = test_schema!();
+        let mut parser = Parser::new(b"SELECT name FROM users ORDER BY length(name) LIMIT 1");
+        let cmd = parser.next().unwrap().unwrap();
+        let stmt = match cmd {
+            ast::Cmd::Stmt(stmt) => stmt,
+            _ => panic!("Expected statement"),
+        };
+        let mut builder = LogicalPlanBuilder::new(&schema);
+        let plan = builder.build_statement(&stmt).unwrap();
+        let (_pager, main_root, state_root, index_root) = setup_btree_for_circuit();
+        match DbspCompiler::new(main_root, state_root, index_root).compile(&plan) {
+            Err(err) => assert!(err.to_string().contains("must be a result column")),
+            Ok(_) => panic!("ORDER BY on an expression should be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_topk_delete_refills_window() {
+        let (mut circuit, pager) =
+            compile_sql!("SELECT name, age FROM users ORDER BY age DESC LIMIT 2");
+
+        let mut init_data = HashMap::default();
+        let mut delta = Delta::new();
+        for (id, name, age) in [
+            (1, "Alice", 25),
+            (2, "Bob", 30),
+            (3, "Charlie", 20),
+            (4, "Dave", 40),
+        ] {
+            delta.insert(
+                id,
+                vec![
+                    Value::from_i64(id),
+                    Value::Text(name.into()),
+                    Value::from_i64(age),
+                ],
+            );
+        }
+        init_data.insert("users".to_string(), delta);
+
+        let result = pager
+            .io
+            .block(|| circuit.commit(init_data.clone(), pager.clone()))
+            .unwrap();
+        let mut names: Vec<_> = result
+            .changes
+            .iter()
+            .map(|(row, weight)| (row.values[0].to_string(), *weight))
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![("Bob".to_string(), 1), ("Dave".to_string(), 1)]);
+
+        // Deleting the top row pulls the next one into the window
+        let mut delete_data = HashMap::default();
+        let mut delta = Delta::new();
+        delta.delete(
+            4,
+            vec![
+                Value::from_i64(4),
+                Value::Text("Dave".into()),
+                Value::from_i64(40),
+            ],
+        );
+        delete_data.insert("users".to_string(), delta);
+
+        let result = test_execute(&mut circuit, delete_data.clone(), pager.clone()).unwrap();
+        let names: Vec<_> = result
+            .changes
+            .iter()
+            .map(|(row, weight)| (row.values[0].to_string(), *weight))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("Dave".to_string(), -1), ("Alice".to_string(), 1)]
+        );
+
+        pager
+            .io
+            .block(|| circuit.commit(delete_data.clone(), pager.clone()))
+            .unwrap();
+        let state = get_current_state(pager.clone(), &circuit).unwrap();
+        let mut names: Vec<_> = state
+            .changes
+            .iter()
+            .map(|(row, _)| row.values[0].to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["Alice".to_string(), "Bob".to_string()]);
+    }
+
+    #[test]
+    fn test_group_concat_recomputed_after_delete() {
+        let (mut circuit, pager) = compile_sql!("SELECT group_concat(name) FROM users");
+
+        let mut init_data = HashMap::default();
+        let mut delta = Delta::new();
+        for (id, name, age) in [(1, "Alice", 25), (2, "Bob", 30), (3, "Charlie", 20)] {
+            delta.insert(
+                id,
+                vec![
+                    Value::from_i64(id),
+                    Value::Text(name.into()),
+                    Value::from_i64(age),
+                ],
+            );
+        }
+        init_data.insert("users".to_string(), delta);
+        pager
+            .io
+            .block(|| circuit.commit(init_data.clone(), pager.clone()))
+            .unwrap();
+
+        // Deleting a row recomputes the group from the inputs that are left
+        let mut delete_data = HashMap::default();
+        let mut delta = Delta::new();
+        delta.delete(
+            2,
+            vec![
+                Value::from_i64(2),
+                Value::Text("Bob".into()),
+                Value::from_i64(30),
+            ],
+        );
+        delete_data.insert("users".to_string(), delta);
+
+        let result = test_execute(&mut circuit, delete_data.clone(), pager.clone()).unwrap();
+        let changes: Vec<_> = result
+            .changes
+            .iter()
+            .map(|(row, weight)| (row.values[0].to_string(), *weight))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("Alice,Bob,Charlie".to_string(), -1),
+                ("Alice,Charlie".to_string(), 1)
+            ]
+        );
+
+        pager
+            .io
+            .block(|| circuit.commit(delete_data.clone(), pager.clone()))
+            .unwrap();
+        let state = get_current_state(pager.clone(), &circuit).unwrap();
+        assert_eq!(state.changes.len(), 1);
+        assert_eq!(state.changes[0].0.values[0].to_string(), "Alice,Charlie");
+    }
+
+    #[test]
+    fn test_window_function_compiles_to_window() {
+        let (circuit, _) =
+            compile_sql!("SELECT name, sum(age) OVER (PARTITION BY name ORDER BY id) FROM users");
+
+        assert_circuit!(circuit, depth: 3, root: Projection);
+        let node = get_node_at_level(&circuit, 1);
+        match &node.operator {
+            DbspOperator::Window {
+                partition_by,
+                order_by,
+                functions,
+                ..
+            } => {
+                assert_eq!(partition_by, &vec![1]);
+                assert_eq!(
+                    order_by,
+                    &vec![TopKSortKey {
+                        column_index: 0,
+                        asc: true,
+                        nulls_first: true,
+                        collation: CollationSeq::Binary,
+                    }]
+                );
+                assert_eq!(functions.len(), 1);
+                assert_eq!(functions[0].args, vec![WindowArg::Column(2)]);
+                assert_eq!(functions[0].frame, WindowFrame::DEFAULT);
+            }
+            _ => panic!("Expected Window operator at level 1"),
+        }
+        assert_operator!(circuit, 2, Input { name: "users" });
+    }
+
+    #[test]
+    fn test_window_range_offset_rejected() {
+        let schema = test_schema!();
+        let mut parser = Parser::new(
+            b"SELECT sum(age) OVER (ORDER BY age RANGE BETWEEN 5 PRECEDING AND CURRENT ROW) FROM users",
+        );
+        let cmd = parser.next().unwrap().unwrap();
+        let stmt = match cmd {
+            ast::Cmd::Stmt(stmt) => stmt,
+            _ => panic!("Expected statement"),
+        };
+        let mut builder = LogicalPlanBuilder::new(&schema);
+        let plan = builder.build_statement(&stmt).unwrap();
+        let (_pager, main_root, state_root, index_root) = setup_btree_for_circuit();
+        match DbspCompiler::new(main_root, state_root, index_root).compile(&plan) {
+            Err(err) => assert!(err.to_string().contains("frame offsets")),
+            Ok(_) => panic!("RANGE offsets should be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_window_running_sum_recomputed_after_delete() {
+        let (mut circuit, pager) =
+            compile_sql!("SELECT name, sum(age) OVER (ORDER BY id) FROM users");
+
+        let mut init_data = HashMap::default();
+        let mut delta = Delta::new();
+        for (id, name, age) in [(1, "Alice", 25), (2, "Bob", 30), (3, "Charlie", 20)] {
+            delta.insert(
+                id,
+                vec![
+                    Value::from_i64(id),
+                    Value::Text(name.into()),
+                    Value::from_i64(age),
+                ],
+            );
+        }
+        init_data.insert("users".to_string(), delta);
+        let result = pager
+            .io
+            .block(|| circuit.commit(init_data.clone(), pager.clone()))
+            .unwrap();
+        let mut changes: Vec<_> = result
+            .changes
+            .iter()
+            .map(|(row, weight)| {
+                (
+                    row.values[0].to_string(),
+                    row.values[1].to_string(),
+                    *weight,
+                )
+            })
+            .collect();
+        changes.sort();
+        assert_eq!(
+            changes,
+            vec![
+                ("Alice".to_string(), "25".to_string(), 1),
+                ("Bob".to_string(), "55".to_string(), 1),
+                ("Charlie".to_string(), "75".to_string(), 1),
+            ]
+        );
+
+        // Deleting a row only changes the running totals that follow it
+        let mut delete_data = HashMap::default();
+        let mut delta = Delta::new();
+        delta.delete(
+            2,
+            vec![
+                Value::from_i64(2),
+                Value::Text("Bob".into()),
+                Value::from_i64(30),
+            ],
+        );
+        delete_data.insert("users".to_string(), delta);
+
+        let result = test_execute(&mut circuit, delete_data.clone(), pager.clone()).unwrap();
+        let mut changes: Vec<_> = result
+            .changes
+            .iter()
+            .map(|(row, weight)| {
+                (
+                    row.values[0].to_string(),
+                    row.values[1].to_string(),
+                    *weight,
+                )
+            })
+            .collect();
+        changes.sort();
+        assert_eq!(
+            changes,
+            vec![
+                ("Bob".to_string(), "55".to_string(), -1),
+                ("Charlie".to_string(), "45".to_string(), 1),
+                ("Charlie".to_string(), "75".to_string(), -1),
+            ]
+        );
+
+        pager
+            .io
+            .block(|| circuit.commit(delete_data.clone(), pager.clone()))
+            .unwrap();
+        let state = get_current_state(pager.clone(), &circuit).unwrap();
+        let mut rows: Vec<_> = state
+            .changes
+            .iter()
+            .map(|(row, _)| (row.values[0].to_string(), row.values[1].to_string()))
+            .collect();
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec![
+                ("Alice".to_string(), "25".to_string()),
+                ("Charlie".to_string(), "45".to_string()),
+            ]
+        );
+    }
 }
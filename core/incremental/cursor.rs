@@ -393,7 +393,6 @@ mod tests {
                 enable_encryption: false,
                 enable_index_method: false,
                 enable_autovacuum: false,
-                enable_vacuum: false,
                 enable_attach: false,
                 enable_generated_columns: false,
                 enable_multiprocess_wal: false,
@@ -444,7 +443,9 @@ mod tests {
         let btree_cursor = Box::new(BTreeCursor::new(pager.clone(), root_page, num_columns));
 
         // Get or create transaction state for this view
-        let tx_state = conn.view_transaction_states.get_or_create("test_view");
+        let tx_state = conn
+            .view_transaction_states
+            .get_or_create(crate::MAIN_DB_ID, "test_view");
 
         // Create the materialized view cursor
         let cursor = MaterializedViewCursor::new(
@@ -1921,7 +1922,9 @@ mod tests {
                 .ok_or_else(|| crate::LimboError::InternalError("View not found".to_string()))?;
 
             let pager = conn.get_pager();
-            let tx_state = conn.view_transaction_states.get_or_create("test_view");
+            let tx_state = conn
+                .view_transaction_states
+                .get_or_create(crate::MAIN_DB_ID, "test_view");
 
             // Create mock cursor that returns TryAdvance from seek and IO from next
             let mock_cursor = MockBTreeCursor::new();
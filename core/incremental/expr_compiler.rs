@@ -330,6 +330,7 @@ impl CompiledExpression {
             syms,
             true,
             DoubleQuotedDml::Enabled,
+            false,
         );
 
         // Translate the transformed expression to bytecode
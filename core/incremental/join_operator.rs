@@ -539,12 +539,18 @@ impl JoinOperator {
                 EvalState::Aggregate(_) => {
                     panic!("Aggregate state should not appear in join operator");
                 }
+                EvalState::TopK(_) => {
+                    panic!("Top-K state should not appear in join operator");
+                }
+                EvalState::Window(_) => {
+                    panic!("Window state should not appear in join operator");
+                }
             }
         }
     }
 }
 
-fn deserialize_hashable_row(blob: &[u8]) -> Result<HashableRow> {
+pub(crate) fn deserialize_hashable_row(blob: &[u8]) -> Result<HashableRow> {
     use crate::types::ImmutableRecord;
 
     let record = ImmutableRecord::from_bin_record(blob.to_vec());
@@ -572,7 +578,7 @@ fn deserialize_hashable_row(blob: &[u8]) -> Result<HashableRow> {
     Ok(HashableRow::new(rowid, values))
 }
 
-fn serialize_hashable_row(row: &HashableRow) -> Vec<u8> {
+pub(crate) fn serialize_hashable_row(row: &HashableRow) -> Vec<u8> {
     use crate::types::ImmutableRecord;
 
     let mut all_values = Vec::with_capacity(row.values.len() + 1);
@@ -154,7 +154,11 @@ impl IncrementalOperator for MergeOperator {
 
                 Ok(IOResult::Done(output))
             }
-            EvalState::Aggregate(_) | EvalState::Join(_) | EvalState::Uninitialized => {
+            EvalState::Aggregate(_)
+            | EvalState::Join(_)
+            | EvalState::TopK(_)
+            | EvalState::Window(_)
+            | EvalState::Uninitialized => {
                 // Merge operator only handles Init state
                 unreachable!("MergeOperator only handles Init state")
             }
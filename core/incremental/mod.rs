@@ -10,4 +10,7 @@ pub mod merge_operator;
 pub mod operator;
 pub mod persistence;
 pub mod project_operator;
+pub mod table_function_operator;
+pub mod topk_operator;
 pub mod view;
+pub mod window_operator;
@@ -9,6 +9,12 @@ pub use crate::incremental::filter_operator::{FilterOperator, FilterPredicate};
 pub use crate::incremental::input_operator::InputOperator;
 pub use crate::incremental::join_operator::{JoinEvalState, JoinOperator, JoinType};
 pub use crate::incremental::project_operator::{ProjectColumn, ProjectOperator};
+pub use crate::incremental::table_function_operator::TableFunctionOperator;
+pub use crate::incremental::topk_operator::{TopKEvalState, TopKOperator, TopKSortKey};
+pub use crate::incremental::window_operator::{
+    WindowArg, WindowEvalState, WindowFrame, WindowFrameBound, WindowFrameUnits,
+    WindowFunctionSpec, WindowOperator,
+};
 
 use crate::incremental::dbsp::{Delta, DeltaPair};
 #[cfg(test)]
@@ -77,6 +83,7 @@ pub fn create_dbsp_state_index(root_page: i64) -> Index {
         has_rowid: true,
         where_clause: None,
         index_method: None,
+        pk_suffix_start: None,
         on_conflict: None,
     }
 }
@@ -101,6 +108,8 @@ pub enum EvalState {
     Init { deltas: DeltaPair },
     Aggregate(Box<AggregateEvalState>),
     Join(Box<JoinEvalState>),
+    TopK(Box<TopKEvalState>),
+    Window(Box<WindowEvalState>),
     Done,
 }
 
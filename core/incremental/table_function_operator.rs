@@ -0,0 +1,371 @@
+// Table function operator for DBSP-style incremental computation
+// This operator joins each row of a stream with the rows of a table-valued function
+
+use crate::incremental::dbsp::{Delta, DeltaPair, HashableRow};
+use crate::incremental::expr_compiler::CompiledExpression;
+use crate::incremental::operator::{
+    ComputationTracker, DbspStateCursors, EvalState, IncrementalOperator,
+};
+use crate::sync::Mutex;
+use crate::sync::{atomic::Ordering, Arc};
+use crate::types::IOResult;
+use crate::vtab::VirtualTable;
+use crate::{Connection, Database, LimboError, Result, Value};
+use turso_ext::{ConstraintInfo, ConstraintOp};
+
+/// Table function operator - calls a table-valued function for each input row
+///
+/// The output of a row is the row followed by each row of the function, called with arguments
+/// evaluated against that row. Table-valued functions are deterministic, so a deleted row
+/// produces the same output rows as when it was inserted, and no state needs to be kept.
+#[derive(Clone)]
+pub struct TableFunctionOperator {
+    vtab: Arc<VirtualTable>,
+    /// Arguments, in the order the function's filter expects them
+    args: Vec<CompiledExpression>,
+    idx_num: i32,
+    idx_str: Option<String>,
+    /// Indices of the function columns that are not arguments
+    output_columns: Vec<usize>,
+    /// Keep input rows the function returns no rows for, padded with NULLs
+    outer: bool,
+    tracker: Option<Arc<Mutex<ComputationTracker>>>,
+    // Internal in-memory connection for expression evaluation and for opening the function,
+    // see ProjectOperator for why the current connection is not used.
+    internal_conn: Arc<Connection>,
+}
+
+impl std::fmt::Debug for TableFunctionOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TableFunctionOperator")
+            .field("name", &self.vtab.name)
+            .field("args", &self.args)
+            .field("idx_num", &self.idx_num)
+            .field("idx_str", &self.idx_str)
+            .field("output_columns", &self.output_columns)
+            .field("outer", &self.outer)
+            .finish()
+    }
+}
+
+impl TableFunctionOperator {
+    /// Create a TableFunctionOperator from the arguments of the call, compiled against the input
+    pub fn new(
+        vtab: Arc<VirtualTable>,
+        args: Vec<CompiledExpression>,
+        outer: bool,
+    ) -> Result<Self> {
+        // The i-th argument constrains the i-th hidden column, like in a regular query
+        let hidden_columns: Vec<usize> = vtab
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, col)| col.hidden())
+            .map(|(i, _)| i)
+            .collect();
+        if args.len() > hidden_columns.len() {
+            return Err(LimboError::ParseError(format!(
+                "Too many arguments for {}: expected at most {}, got {}",
+                vtab.name,
+                hidden_columns.len(),
+                args.len()
+            )));
+        }
+        let constraints: Vec<ConstraintInfo> = hidden_columns
+            .iter()
+            .take(args.len())
+            .enumerate()
+            .map(|(i, &column_index)| ConstraintInfo {
+                column_index: column_index as u32,
+                op: ConstraintOp::Eq,
+                usable: true,
+                index: i,
+            })
+            .collect();
+        let index_info = vtab.best_index(&constraints, &[])?;
+
+        // Order the arguments by the position the function wants them in
+        let mut ordered_args: Vec<Option<CompiledExpression>> = vec![None; args.len()];
+        for (arg, usage) in args.into_iter().zip(&index_info.constraint_usages) {
+            let position = match usage.argv_index {
+                Some(argv_index)
+                    if argv_index >= 1 && argv_index as usize <= ordered_args.len() =>
+                {
+                    argv_index as usize - 1
+                }
+                _ => {
+                    return Err(LimboError::ParseError(format!(
+                        "Table-valued function {} does not use all of its arguments",
+                        vtab.name
+                    )))
+                }
+            };
+            if ordered_args[position].replace(arg).is_some() {
+                return Err(LimboError::InternalError(format!(
+                    "Table-valued function {} uses argument {} twice",
+                    vtab.name,
+                    position + 1
+                )));
+            }
+        }
+        let args = ordered_args
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                LimboError::InternalError(format!(
+                    "Table-valued function {} left an argument position empty",
+                    vtab.name
+                ))
+            })?;
+
+        let output_columns = vtab
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, col)| !col.hidden())
+            .map(|(i, _)| i)
+            .collect();
+
+        // Set up internal connection for expression evaluation
+        let io = Arc::new(crate::MemoryIO::new());
+        let db = Database::open_file(io, ":memory:")?;
+        let internal_conn = db.connect()?;
+        // Set to read-only mode and disable auto-commit since we're only evaluating expressions
+        internal_conn.set_query_only(true);
+        internal_conn.auto_commit.store(false, Ordering::SeqCst);
+
+        Ok(Self {
+            vtab,
+            args,
+            idx_num: index_info.idx_num,
+            idx_str: index_info.idx_str,
+            output_columns,
+            outer,
+            tracker: None,
+            internal_conn,
+        })
+    }
+
+    /// Join one input row with the rows of the function
+    fn apply(&self, row: &HashableRow, weight: isize, output: &mut Delta) -> Result<()> {
+        if let Some(tracker) = &self.tracker {
+            tracker.lock().record_project();
+        }
+
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.execute(&row.values, self.internal_conn.pager.load().clone()))
+            .collect::<Result<Vec<Value>>>()?;
+
+        let mut cursor = self.vtab.open(self.internal_conn.clone())?;
+        let mut has_row = cursor.filter(self.idx_num, self.idx_str.clone(), args.len(), args)?;
+        let mut found = false;
+        while has_row {
+            found = true;
+            let mut values = row.values.clone();
+            for &column in &self.output_columns {
+                values.push(cursor.column(column)?);
+            }
+            output
+                .changes
+                .push((self.output_row(row.rowid, values, cursor.rowid()), weight));
+            has_row = cursor.next()?;
+        }
+
+        if !found && self.outer {
+            let mut values = row.values.clone();
+            values.resize(values.len() + self.output_columns.len(), Value::Null);
+            output
+                .changes
+                .push((self.output_row(row.rowid, values, 0), weight));
+        }
+        Ok(())
+    }
+
+    // The rowid of an output row identifies the input row and the row of the function
+    fn output_row(&self, input_rowid: i64, values: Vec<Value>, function_rowid: i64) -> HashableRow {
+        let mut key = values.clone();
+        key.push(Value::from_i64(function_rowid));
+        let rowid = HashableRow::new(input_rowid, key).cached_hash().as_i64();
+        HashableRow::new(rowid, values)
+    }
+}
+
+impl IncrementalOperator for TableFunctionOperator {
+    fn eval(
+        &mut self,
+        state: &mut EvalState,
+        _cursors: &mut DbspStateCursors,
+    ) -> Result<IOResult<Delta>> {
+        let delta = match state {
+            EvalState::Init { deltas } => {
+                // Table function operators only use left_delta, right_delta must be empty
+                assert!(
+                    deltas.right.is_empty(),
+                    "TableFunctionOperator expects right_delta to be empty"
+                );
+                std::mem::take(&mut deltas.left)
+            }
+            _ => unreachable!(
+                "TableFunctionOperator doesn't execute the state machine. Should be in Init state"
+            ),
+        };
+
+        let mut output_delta = Delta::new();
+        for (row, weight) in &delta.changes {
+            self.apply(row, *weight, &mut output_delta)?;
+        }
+
+        *state = EvalState::Done;
+        Ok(IOResult::Done(output_delta))
+    }
+
+    fn commit(
+        &mut self,
+        deltas: DeltaPair,
+        _cursors: &mut DbspStateCursors,
+    ) -> Result<IOResult<Delta>> {
+        // Table function operator only uses left delta, right must be empty
+        assert!(
+            deltas.right.is_empty(),
+            "TableFunctionOperator expects right delta to be empty in commit"
+        );
+
+        let mut output_delta = Delta::new();
+        for (row, weight) in &deltas.left.changes {
+            self.apply(row, *weight, &mut output_delta)?;
+        }
+
+        Ok(IOResult::Done(output_delta))
+    }
+
+    fn set_tracker(&mut self, tracker: Arc<Mutex<ComputationTracker>>) {
+        self.tracker = Some(tracker);
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::incremental::expr_compiler::{ExpressionExecutor, TrivialExpression};
+    use crate::incremental::operator::create_dbsp_state_index;
+    use crate::storage::btree::BTreeCursor;
+    use crate::storage::pager::CreateBTreeFlags;
+    use crate::util::IOExt;
+    use crate::{MemoryIO, IO};
+
+    /// json_each(c1) over rows of (id, json)
+    fn json_each_operator(outer: bool) -> TableFunctionOperator {
+        let vtab = VirtualTable::builtin_functions(false)
+            .into_iter()
+            .find(|vtab| vtab.name == "json_each")
+            .unwrap();
+        let arg = CompiledExpression {
+            executor: ExpressionExecutor::Trivial(TrivialExpression::Column(1)),
+            input_count: 2,
+        };
+        TableFunctionOperator::new(vtab, vec![arg], outer).unwrap()
+    }
+
+    fn eval(operator: &mut TableFunctionOperator, delta: Delta) -> Delta {
+        let io: Arc<dyn IO> = Arc::new(MemoryIO::new());
+        let db = Database::open_file(io, ":memory:").unwrap();
+        let pager = db.connect().unwrap().pager.load().clone();
+        let _ = pager.io.block(|| pager.allocate_page1());
+        let table_root_page_id = pager
+            .io
+            .block(|| pager.btree_create(&CreateBTreeFlags::new_table()))
+            .unwrap() as i64;
+        let index_root_page_id = pager
+            .io
+            .block(|| pager.btree_create(&CreateBTreeFlags::new_index()))
+            .unwrap() as i64;
+        let index_def = create_dbsp_state_index(index_root_page_id);
+        let mut cursors = DbspStateCursors::new(
+            BTreeCursor::new_table(pager.clone(), table_root_page_id, 5),
+            BTreeCursor::new_index(pager.clone(), index_root_page_id, &index_def, 4),
+        );
+        let mut state: EvalState = DeltaPair::new(delta, Delta::new()).into();
+        pager
+            .io
+            .block(|| operator.eval(&mut state, &mut cursors))
+            .unwrap()
+    }
+
+    fn row(id: i64, json: &str) -> Vec<Value> {
+        vec![Value::from_i64(id), Value::Text(json.into())]
+    }
+
+    /// The weight, the input row and the key and value columns of the function
+    fn changes(delta: &Delta) -> Vec<(isize, Vec<Value>)> {
+        delta
+            .changes
+            .iter()
+            .map(|(row, weight)| (*weight, row.values[..4].to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_table_function_insert_joins_function_rows() {
+        let mut operator = json_each_operator(false);
+        let mut delta = Delta::new();
+        delta.insert(1, row(1, r#"["a","b"]"#));
+        delta.insert(2, row(2, "[]"));
+        let output = eval(&mut operator, delta);
+
+        let mut expected_a = row(1, r#"["a","b"]"#);
+        expected_a.extend([Value::from_i64(0), Value::Text("a".into())]);
+        let mut expected_b = row(1, r#"["a","b"]"#);
+        expected_b.extend([Value::from_i64(1), Value::Text("b".into())]);
+        assert_eq!(changes(&output), vec![(1, expected_a), (1, expected_b)]);
+        // The row of the function is a column of every output row
+        assert!(output.changes.iter().all(|(row, _)| row.values.len() == 10));
+    }
+
+    #[test]
+    fn test_table_function_delete_retracts_inserted_rows() {
+        let mut operator = json_each_operator(false);
+        let mut insert = Delta::new();
+        insert.insert(1, row(1, r#"["a","b"]"#));
+        let inserted = eval(&mut operator, insert);
+
+        let mut delete = Delta::new();
+        delete.delete(1, row(1, r#"["a","b"]"#));
+        let deleted = eval(&mut operator, delete);
+
+        // A delete produces the same rows with the opposite weight, so they cancel out
+        assert_eq!(deleted.changes.len(), 2);
+        for ((inserted_row, inserted_weight), (deleted_row, deleted_weight)) in
+            inserted.changes.iter().zip(&deleted.changes)
+        {
+            assert_eq!(inserted_row.rowid, deleted_row.rowid);
+            assert_eq!(inserted_row.values, deleted_row.values);
+            assert_eq!((*inserted_weight, *deleted_weight), (1, -1));
+        }
+        let mut combined = inserted;
+        combined.merge(&deleted);
+        combined.consolidate();
+        assert!(combined.is_empty());
+    }
+
+    #[test]
+    fn test_table_function_outer_keeps_rows_without_output() {
+        let mut operator = json_each_operator(true);
+        let mut delta = Delta::new();
+        delta.insert(2, row(2, "[]"));
+        let output = eval(&mut operator, delta);
+
+        assert_eq!(output.changes.len(), 1);
+        let (output_row, weight) = &output.changes[0];
+        assert_eq!(*weight, 1);
+        assert_eq!(output_row.values[..2], row(2, "[]")[..]);
+        assert!(output_row.values[2..].iter().all(|v| *v == Value::Null));
+
+        let mut delta = Delta::new();
+        delta.delete(2, row(2, "[]"));
+        let output = eval(&mut operator, delta);
+        assert_eq!(output.changes.len(), 1);
+        assert_eq!(output.changes[0].1, -1);
+    }
+}
@@ -0,0 +1,766 @@
+// Top-K operator for DBSP-style incremental computation
+// Maintains the rows selected by ORDER BY ... LIMIT ... OFFSET in a view definition
+
+use crate::incremental::dbsp::{Delta, DeltaPair, Hash128, HashableRow};
+use crate::incremental::join_operator::{deserialize_hashable_row, serialize_hashable_row};
+use crate::incremental::operator::{
+    generate_storage_id, ComputationTracker, DbspStateCursors, EvalState, IncrementalOperator,
+};
+use crate::incremental::persistence::WriteRow;
+use crate::numeric::Numeric;
+use crate::storage::btree::CursorTrait;
+use crate::sync::Arc;
+use crate::sync::Mutex;
+use crate::translate::collate::CollationSeq;
+use crate::types::{IOResult, ImmutableRecord, SeekKey, SeekOp, SeekResult};
+use crate::{return_and_restore_if_io, return_if_io, LimboError, Result, Value};
+use std::collections::BTreeMap;
+
+/// One term of the ORDER BY clause, resolved to a column of the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopKSortKey {
+    /// Index of the sorted column in the input rows
+    pub column_index: usize,
+    pub asc: bool,
+    pub nulls_first: bool,
+    /// Collation of text values
+    pub collation: CollationSeq,
+}
+
+// Tags used by the sort key encoding. NULLs get their own tag on either side of
+// non-NULL values, so NULLS FIRST/LAST is independent of the sort direction.
+const NULLS_FIRST_TAG: u8 = 0x01;
+const VALUE_TAG: u8 = 0x02;
+const NULLS_LAST_TAG: u8 = 0x03;
+
+// Storage class markers, following SQLite's ordering: numeric < text < blob
+const NUMERIC_CLASS: u8 = 0x10;
+const TEXT_CLASS: u8 = 0x20;
+const BLOB_CLASS: u8 = 0x30;
+
+/// Encode the ORDER BY columns of a row into a blob whose byte order is the SQL order
+/// of the row. The blob is stored as the zset_id of the state index, so a range scan
+/// over the operator's storage returns the rows already sorted.
+pub(crate) fn encode_sort_key(values: &[Value], sort_keys: &[TopKSortKey]) -> Vec<u8> {
+    let mut key = Vec::new();
+    for sort_key in sort_keys {
+        match values.get(sort_key.column_index).unwrap_or(&Value::Null) {
+            Value::Null => key.push(if sort_key.nulls_first {
+                NULLS_FIRST_TAG
+            } else {
+                NULLS_LAST_TAG
+            }),
+            value => {
+                key.push(VALUE_TAG);
+                let start = key.len();
+                encode_sort_value(value, sort_key.collation, &mut key);
+                if !sort_key.asc {
+                    // Every encoded value is prefix-free, so inverting the bytes reverses the order
+                    for byte in &mut key[start..] {
+                        *byte = !*byte;
+                    }
+                }
+            }
+        }
+    }
+    key
+}
+
+fn encode_sort_value(value: &Value, collation: CollationSeq, out: &mut Vec<u8>) {
+    match value {
+        Value::Null => unreachable!("NULLs are encoded by their tag alone"),
+        Value::Numeric(numeric) => {
+            // Integers and floats compare numerically. The float approximation orders
+            // values of different magnitude; the exact remainder breaks ties between
+            // large integers that round to the same float.
+            let (approx, remainder) = match numeric {
+                Numeric::Integer(i) => {
+                    let approx = *i as f64;
+                    (approx, (*i as i128 - approx as i128) as i64)
+                }
+                Numeric::Float(f) => (f64::from(*f), 0),
+            };
+            // Normalize -0.0 so that it equals 0.0
+            let approx = if approx == 0.0 { 0.0 } else { approx };
+            let bits = approx.to_bits();
+            let bits = if bits >> 63 == 1 {
+                !bits
+            } else {
+                bits | (1 << 63)
+            };
+            out.push(NUMERIC_CLASS);
+            out.extend_from_slice(&bits.to_be_bytes());
+            out.extend_from_slice(&((remainder as u64) ^ (1 << 63)).to_be_bytes());
+        }
+        Value::Text(text) => {
+            // Encode the text the way the collation sees it, so that the byte order of
+            // the key is the collation order and equal strings get equal keys
+            out.push(TEXT_CLASS);
+            match collation {
+                CollationSeq::NoCase => {
+                    encode_sort_bytes(text.as_str().to_ascii_lowercase().as_bytes(), out)
+                }
+                CollationSeq::Rtrim => {
+                    encode_sort_bytes(text.as_str().trim_end_matches(' ').as_bytes(), out)
+                }
+                CollationSeq::Unset | CollationSeq::Binary => {
+                    encode_sort_bytes(text.as_str().as_bytes(), out)
+                }
+            }
+        }
+        Value::Blob(blob) => {
+            out.push(BLOB_CLASS);
+            encode_sort_bytes(blob, out);
+        }
+    }
+}
+
+/// Escape 0x00 as 0x00 0xFF and terminate with 0x00 0x00, so that shorter values sort
+/// first and no encoding is a prefix of another.
+fn encode_sort_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    for &byte in bytes {
+        out.push(byte);
+        if byte == 0 {
+            out.push(0xFF);
+        }
+    }
+    out.extend_from_slice(&[0x00, 0x00]);
+}
+
+/// A row of the operator's state together with its position in the ORDER BY order
+#[derive(Debug, Clone)]
+pub struct OrderedRow {
+    pub(crate) sort_key: Vec<u8>,
+    pub(crate) element_hash: Hash128,
+    pub(crate) row: HashableRow,
+    pub(crate) weight: isize,
+}
+
+/// Where a scan of ordered rows continues
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum OrderedScan<'a> {
+    /// At the first row whose sort key is not smaller than the given bytes
+    From(&'a [u8]),
+    /// At the row following the given one
+    After(&'a [u8], Hash128),
+}
+
+// Helper function to read the next row of an ordered state, in sort key order
+pub(crate) fn read_next_ordered_row(
+    storage_id: i64,
+    position: OrderedScan,
+    cursors: &mut DbspStateCursors,
+) -> Result<IOResult<Option<OrderedRow>>> {
+    // Index key: (storage_id, sort_key, element_hash). NULL sorts first, so seeking from
+    // a sort key lands on the first row with that key.
+    let (index_key_values, seek_op) = match position {
+        OrderedScan::From(sort_key) => (
+            vec![
+                Value::from_i64(storage_id),
+                Value::Blob(sort_key.to_vec()),
+                Value::Null,
+            ],
+            SeekOp::GE { eq_only: false },
+        ),
+        OrderedScan::After(sort_key, element_hash) => (
+            vec![
+                Value::from_i64(storage_id),
+                Value::Blob(sort_key.to_vec()),
+                element_hash.to_value(),
+            ],
+            SeekOp::GT,
+        ),
+    };
+    let index_record = ImmutableRecord::from_values(&index_key_values, index_key_values.len());
+
+    let seek_result = return_if_io!(cursors
+        .index_cursor
+        .seek(SeekKey::IndexKey(&index_record), seek_op));
+    if !matches!(seek_result, SeekResult::Found) {
+        return Ok(IOResult::Done(None));
+    }
+
+    let current_record = return_if_io!(cursors.index_cursor.record());
+    let Some(rec) = current_record else {
+        return Ok(IOResult::Done(None));
+    };
+    let (v0, v1, v2) = rec.get_three_values(0, 1, 2)?;
+    match v0.to_owned() {
+        Value::Numeric(Numeric::Integer(id)) if id == storage_id => {}
+        // Moved past the rows of this operator
+        _ => return Ok(IOResult::Done(None)),
+    }
+    let sort_key = match v1.to_owned() {
+        Value::Blob(blob) => blob,
+        _ => {
+            return Err(LimboError::InternalError(
+                "Invalid sort key in ordered storage".to_string(),
+            ))
+        }
+    };
+    let element_hash = match v2.to_owned() {
+        Value::Blob(blob) => Hash128::from_blob(&blob)
+            .ok_or_else(|| LimboError::InternalError("Invalid element_hash blob".to_string()))?,
+        _ => {
+            return Err(LimboError::InternalError(
+                "Invalid element_hash in ordered storage".to_string(),
+            ))
+        }
+    };
+
+    let rowid = return_if_io!(cursors.index_cursor.rowid()).ok_or_else(|| {
+        LimboError::InternalError("Index cursor does not have a valid rowid".to_string())
+    })?;
+    let table_res = return_if_io!(cursors
+        .table_cursor
+        .seek(SeekKey::TableRowId(rowid), SeekOp::GE { eq_only: true }));
+    if !matches!(table_res, SeekResult::Found) {
+        return Err(LimboError::InternalError(
+            "Index points to non-existent table row".to_string(),
+        ));
+    }
+
+    let table_record = return_if_io!(cursors.table_cursor.record());
+    let rec = table_record.ok_or_else(|| {
+        LimboError::InternalError("Found rowid in table but could not read record".to_string())
+    })?;
+    // Table format: [storage_id, zset_id, element_id, value_blob, weight]
+    let (value, weight) = rec.get_two_values(3, 4)?;
+    let row = match value.to_owned() {
+        Value::Blob(blob) => deserialize_hashable_row(&blob)?,
+        _ => {
+            return Err(LimboError::InternalError(
+                "Invalid row value in ordered storage".to_string(),
+            ))
+        }
+    };
+    let weight = match weight.to_owned() {
+        Value::Numeric(Numeric::Integer(w)) => w as isize,
+        _ => {
+            return Err(LimboError::InternalError(
+                "Invalid weight value in storage".to_string(),
+            ))
+        }
+    };
+
+    Ok(IOResult::Done(Some(OrderedRow {
+        sort_key,
+        element_hash,
+        row,
+        weight,
+    })))
+}
+
+// Top-K specific eval states
+#[derive(Debug)]
+pub enum TopKEvalState {
+    /// Reading the persisted rows in order until the window is known to be covered
+    ReadState {
+        /// The consolidated input delta, with sort keys computed
+        changes: Vec<OrderedRow>,
+        /// Persisted rows read so far, in order
+        stored: Vec<OrderedRow>,
+        /// Number of row copies in `stored`
+        stored_count: usize,
+        /// Stop reading after this many copies, or read everything if unbounded
+        read_limit: Option<usize>,
+    },
+    Done {
+        output: Delta,
+    },
+}
+
+impl TopKEvalState {
+    fn process_topk_state(
+        &mut self,
+        cursors: &mut DbspStateCursors,
+        storage_id: i64,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<IOResult<Delta>> {
+        loop {
+            match self {
+                TopKEvalState::ReadState {
+                    changes,
+                    stored,
+                    stored_count,
+                    read_limit,
+                } => {
+                    let next_row = if read_limit.is_some_and(|limit| *stored_count >= limit) {
+                        None
+                    } else {
+                        let position = match stored.last() {
+                            Some(row) => OrderedScan::After(&row.sort_key, row.element_hash),
+                            None => OrderedScan::From(&[]),
+                        };
+                        return_if_io!(read_next_ordered_row(storage_id, position, cursors))
+                    };
+                    match next_row {
+                        Some(row) => {
+                            *stored_count += row.weight.max(0) as usize;
+                            stored.push(row);
+                        }
+                        None => {
+                            let output = Self::window_delta(stored, changes, offset, limit);
+                            *self = TopKEvalState::Done { output };
+                        }
+                    }
+                }
+                TopKEvalState::Done { output } => {
+                    return Ok(IOResult::Done(std::mem::take(output)));
+                }
+            }
+        }
+    }
+
+    /// Compute the change of the window from the persisted rows before and after
+    /// applying `changes`.
+    ///
+    /// `stored` only holds a prefix of the persisted rows, but it contains at least as
+    /// many copies as the window plus every deleted copy. Even if all deletions fall
+    /// inside the prefix, the window after the change still ends within it, and
+    /// inserted rows that sort past the prefix can never reach the window.
+    fn window_delta(
+        stored: &[OrderedRow],
+        changes: &[OrderedRow],
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Delta {
+        let mut output = Delta::new();
+        Self::push_window(
+            stored.iter().map(|r| (&r.row, r.weight)),
+            offset,
+            limit,
+            -1,
+            &mut output,
+        );
+
+        let mut merged: BTreeMap<(&[u8], Hash128), (&HashableRow, isize)> = BTreeMap::new();
+        for r in stored.iter().chain(changes) {
+            merged
+                .entry((r.sort_key.as_slice(), r.element_hash))
+                .or_insert((&r.row, 0))
+                .1 += r.weight;
+        }
+        Self::push_window(merged.into_values(), offset, limit, 1, &mut output);
+
+        output.consolidate();
+        // Rows leaving the window are applied before rows entering it, so that a row
+        // that only moved within the view keeps its rowid.
+        output.changes.sort_by_key(|(_, weight)| *weight);
+        output
+    }
+
+    /// Add the rows at positions offset..offset+limit of an ordered multiset to `output`
+    fn push_window<'a>(
+        rows: impl Iterator<Item = (&'a HashableRow, isize)>,
+        offset: usize,
+        limit: Option<usize>,
+        sign: isize,
+        output: &mut Delta,
+    ) {
+        let end = limit.map(|limit| offset.saturating_add(limit));
+        let mut position = 0usize;
+        for (row, weight) in rows {
+            if end.is_some_and(|end| position >= end) {
+                break;
+            }
+            if weight <= 0 {
+                continue;
+            }
+            let start = position.max(offset);
+            position += weight as usize;
+            let stop = end.map_or(position, |end| position.min(end));
+            if stop > start {
+                output
+                    .changes
+                    .push((row.clone(), sign * (stop - start) as isize));
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum TopKCommitState {
+    Idle,
+    Eval {
+        eval_state: EvalState,
+    },
+    CommitDelta {
+        changes: Delta,
+        output: Delta,
+        current_idx: usize,
+        write_row: WriteRow,
+    },
+    Invalid,
+}
+
+/// Top-K operator - keeps the rows selected by ORDER BY ... LIMIT ... OFFSET
+///
+/// Every input row is persisted, keyed by its encoded sort key, so the window can be
+/// recomputed from the front of the state btree. This lets deletions pull the rows
+/// that follow the window back into it.
+#[derive(Debug)]
+pub struct TopKOperator {
+    /// Unique operator ID for indexing in persistent storage
+    operator_id: i64,
+    /// ORDER BY terms. Without them the window is taken in an arbitrary but stable order
+    sort_keys: Vec<TopKSortKey>,
+    /// Number of leading rows skipped (OFFSET)
+    offset: usize,
+    /// Maximum number of rows in the window (LIMIT)
+    limit: Option<usize>,
+
+    commit_state: TopKCommitState,
+}
+
+impl TopKOperator {
+    pub fn new(
+        operator_id: i64,
+        sort_keys: Vec<TopKSortKey>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Self {
+        Self {
+            operator_id,
+            sort_keys,
+            offset,
+            limit,
+            commit_state: TopKCommitState::Idle,
+        }
+    }
+
+    fn storage_id(&self) -> i64 {
+        generate_storage_id(self.operator_id, 0, 0)
+    }
+
+    fn ordered_row(&self, row: &HashableRow, weight: isize) -> OrderedRow {
+        OrderedRow {
+            sort_key: encode_sort_key(&row.values, &self.sort_keys),
+            element_hash: row.cached_hash(),
+            row: row.clone(),
+            weight,
+        }
+    }
+
+    fn eval_internal(
+        &mut self,
+        state: &mut EvalState,
+        cursors: &mut DbspStateCursors,
+    ) -> Result<IOResult<Delta>> {
+        loop {
+            let loop_state = std::mem::replace(state, EvalState::Uninitialized);
+            match loop_state {
+                EvalState::Uninitialized => {
+                    panic!("Cannot eval TopKOperator with Uninitialized state");
+                }
+                EvalState::Init { deltas } => {
+                    let mut delta = deltas.left;
+                    delta.consolidate();
+                    if delta.is_empty() {
+                        *state = EvalState::Done;
+                        return Ok(IOResult::Done(Delta::new()));
+                    }
+
+                    let changes: Vec<OrderedRow> = delta
+                        .changes
+                        .iter()
+                        .map(|(row, weight)| self.ordered_row(row, *weight))
+                        .collect();
+                    let deleted: usize =
+                        changes.iter().map(|r| r.weight.min(0).unsigned_abs()).sum();
+                    let read_limit = self
+                        .limit
+                        .map(|limit| self.offset.saturating_add(limit).saturating_add(deleted));
+
+                    *state = EvalState::TopK(Box::new(TopKEvalState::ReadState {
+                        changes,
+                        stored: Vec::new(),
+                        stored_count: 0,
+                        read_limit,
+                    }));
+                }
+                EvalState::TopK(mut topk_state) => {
+                    let result = topk_state.process_topk_state(
+                        cursors,
+                        self.storage_id(),
+                        self.offset,
+                        self.limit,
+                    );
+                    *state = EvalState::TopK(topk_state);
+                    let output = return_if_io!(result);
+                    *state = EvalState::Done;
+                    return Ok(IOResult::Done(output));
+                }
+                EvalState::Done => {
+                    return Ok(IOResult::Done(Delta::new()));
+                }
+                EvalState::Aggregate(_) | EvalState::Join(_) | EvalState::Window(_) => {
+                    panic!("Only top-K state should appear in top-K operator");
+                }
+            }
+        }
+    }
+}
+
+impl IncrementalOperator for TopKOperator {
+    fn eval(
+        &mut self,
+        state: &mut EvalState,
+        cursors: &mut DbspStateCursors,
+    ) -> Result<IOResult<Delta>> {
+        let delta = return_if_io!(self.eval_internal(state, cursors));
+        Ok(IOResult::Done(delta))
+    }
+
+    fn commit(
+        &mut self,
+        deltas: DeltaPair,
+        cursors: &mut DbspStateCursors,
+    ) -> Result<IOResult<Delta>> {
+        loop {
+            let mut state = std::mem::replace(&mut self.commit_state, TopKCommitState::Invalid);
+            match &mut state {
+                TopKCommitState::Idle => {
+                    self.commit_state = TopKCommitState::Eval {
+                        eval_state: deltas.clone().into(),
+                    }
+                }
+                TopKCommitState::Eval { ref mut eval_state } => {
+                    // The window is computed against the state before this delta is persisted
+                    let output = return_and_restore_if_io!(
+                        &mut self.commit_state,
+                        state,
+                        self.eval(eval_state, cursors)
+                    );
+                    let mut changes = deltas.left.clone();
+                    changes.consolidate();
+                    self.commit_state = TopKCommitState::CommitDelta {
+                        changes,
+                        output,
+                        current_idx: 0,
+                        write_row: WriteRow::new(),
+                    };
+                }
+                TopKCommitState::CommitDelta {
+                    changes,
+                    output,
+                    current_idx,
+                    ref mut write_row,
+                } => {
+                    if *current_idx >= changes.changes.len() {
+                        self.commit_state = TopKCommitState::Idle;
+                        return Ok(IOResult::Done(std::mem::take(output)));
+                    }
+
+                    let (row, weight) = &changes.changes[*current_idx];
+                    let storage_id = self.storage_id();
+                    let sort_key = Value::Blob(encode_sort_key(&row.values, &self.sort_keys));
+                    let element_hash = row.cached_hash();
+                    let index_key = vec![
+                        Value::from_i64(storage_id),
+                        sort_key.clone(),
+                        element_hash.to_value(),
+                    ];
+                    let record_values = vec![
+                        Value::from_i64(storage_id),
+                        sort_key,
+                        element_hash.to_value(),
+                        Value::Blob(serialize_hashable_row(row)),
+                    ];
+
+                    return_and_restore_if_io!(
+                        &mut self.commit_state,
+                        state,
+                        write_row.write_row(cursors, index_key, record_values, *weight)
+                    );
+
+                    self.commit_state = TopKCommitState::CommitDelta {
+                        changes: std::mem::take(changes),
+                        output: std::mem::take(output),
+                        current_idx: *current_idx + 1,
+                        write_row: WriteRow::new(),
+                    };
+                }
+                TopKCommitState::Invalid => {
+                    panic!("Invalid top-K commit state");
+                }
+            }
+        }
+    }
+
+    fn set_tracker(&mut self, _tracker: Arc<Mutex<ComputationTracker>>) {
+        // Top-K operator doesn't need tracking
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(value: Value, asc: bool, nulls_first: bool) -> Vec<u8> {
+        encode_sort_key(
+            &[value],
+            &[TopKSortKey {
+                column_index: 0,
+                asc,
+                nulls_first,
+                collation: CollationSeq::Binary,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_sort_key_follows_value_order() {
+        let values = vec![
+            Value::from_i64(i64::MIN),
+            Value::from_f64(-1.5),
+            Value::from_i64(-1),
+            Value::from_f64(-0.0),
+            Value::from_i64(1),
+            Value::from_f64(1.5),
+            Value::from_f64(9007199254740992.0),
+            Value::from_i64(9007199254740993),
+            Value::from_i64(i64::MAX),
+            Value::Text("".into()),
+            Value::Text("a".into()),
+            Value::Text("a\0".into()),
+            Value::Text("ab".into()),
+            Value::Blob(vec![]),
+            Value::Blob(vec![0]),
+            Value::Blob(vec![0, 0]),
+            Value::Blob(vec![1]),
+        ];
+        for pair in values.windows(2) {
+            assert!(
+                key(pair[0].clone(), true, true) < key(pair[1].clone(), true, true),
+                "{:?} should sort before {:?}",
+                pair[0],
+                pair[1]
+            );
+            assert!(
+                key(pair[0].clone(), false, false) > key(pair[1].clone(), false, false),
+                "{:?} should sort after {:?} in descending order",
+                pair[0],
+                pair[1]
+            );
+        }
+        // Integers and floats with the same value are equal
+        assert_eq!(
+            key(Value::from_i64(2), true, true),
+            key(Value::from_f64(2.0), true, true)
+        );
+        assert_eq!(
+            key(Value::from_i64(0), true, true),
+            key(Value::from_f64(-0.0), true, true)
+        );
+    }
+
+    #[test]
+    fn test_sort_key_nulls_placement() {
+        let smallest = Value::from_i64(i64::MIN);
+        let largest = Value::Blob(vec![0xFF; 4]);
+        for asc in [true, false] {
+            let null_first = key(Value::Null, asc, true);
+            let null_last = key(Value::Null, asc, false);
+            for value in [&smallest, &largest] {
+                assert!(null_first < key(value.clone(), asc, true));
+                assert!(null_last > key(value.clone(), asc, false));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_key_compares_terms_in_order() {
+        let sort_keys = vec![
+            TopKSortKey {
+                column_index: 1,
+                asc: false,
+                nulls_first: false,
+                collation: CollationSeq::Binary,
+            },
+            TopKSortKey {
+                column_index: 0,
+                asc: true,
+                nulls_first: true,
+                collation: CollationSeq::Binary,
+            },
+        ];
+        let row = |a: &str, b: i64| {
+            encode_sort_key(&[Value::Text(a.into()), Value::from_i64(b)], &sort_keys)
+        };
+        // b DESC first, then a ASC
+        assert!(row("z", 10) < row("a", 5));
+        assert!(row("a", 5) < row("b", 5));
+        assert!(row("ab", 5) > row("a", 5));
+    }
+
+    #[test]
+    fn test_sort_key_follows_collation() {
+        let text_key = |text: &str, collation: CollationSeq| {
+            encode_sort_key(
+                &[Value::Text(text.into())],
+                &[TopKSortKey {
+                    column_index: 0,
+                    asc: true,
+                    nulls_first: true,
+                    collation,
+                }],
+            )
+        };
+        // BINARY puts every upper case letter before the lower case ones
+        assert!(text_key("B", CollationSeq::Binary) < text_key("a", CollationSeq::Binary));
+        assert!(text_key("B", CollationSeq::NoCase) > text_key("a", CollationSeq::NoCase));
+        assert_eq!(
+            text_key("ABC", CollationSeq::NoCase),
+            text_key("abc", CollationSeq::NoCase)
+        );
+        assert_ne!(
+            text_key("abc  ", CollationSeq::Binary),
+            text_key("abc", CollationSeq::Binary)
+        );
+        assert_eq!(
+            text_key("abc  ", CollationSeq::Rtrim),
+            text_key("abc", CollationSeq::Rtrim)
+        );
+        assert!(text_key("ab ", CollationSeq::Rtrim) < text_key("abc", CollationSeq::Rtrim));
+    }
+
+    #[test]
+    fn test_window_delta() {
+        let ordered = |rowid: i64, weight: isize| OrderedRow {
+            sort_key: encode_sort_key(
+                &[Value::from_i64(rowid)],
+                &[TopKSortKey {
+                    column_index: 0,
+                    asc: true,
+                    nulls_first: true,
+                    collation: CollationSeq::Binary,
+                }],
+            ),
+            element_hash: HashableRow::new(rowid, vec![Value::from_i64(rowid)]).cached_hash(),
+            row: HashableRow::new(rowid, vec![Value::from_i64(rowid)]),
+            weight,
+        };
+        let stored = vec![ordered(1, 1), ordered(2, 1), ordered(3, 1), ordered(4, 1)];
+
+        // Deleting the first row pulls the third one into a LIMIT 2 window
+        let output = TopKEvalState::window_delta(&stored, &[ordered(1, -1)], 0, Some(2));
+        let changes: Vec<(i64, isize)> = output
+            .changes
+            .iter()
+            .map(|(row, weight)| (row.rowid, *weight))
+            .collect();
+        assert_eq!(changes, vec![(1, -1), (3, 1)]);
+
+        // With OFFSET 1, inserting a new smallest row shifts the window by one
+        let output = TopKEvalState::window_delta(&stored, &[ordered(0, 1)], 1, Some(2));
+        let mut changes: Vec<(i64, isize)> = output
+            .changes
+            .iter()
+            .map(|(row, weight)| (row.rowid, *weight))
+            .collect();
+        changes.sort();
+        assert_eq!(changes, vec![(1, 1), (3, -1)]);
+    }
+}
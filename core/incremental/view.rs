@@ -1,5 +1,5 @@
 use super::compiler::{DbspCircuit, DbspCompiler, DeltaSet};
-use super::dbsp::Delta;
+use super::dbsp::{Delta, Hash128};
 use super::operator::ComputationTracker;
 use crate::numeric::Numeric;
 use crate::schema::{BTreeTable, Schema};
@@ -9,7 +9,7 @@ use crate::sync::Mutex;
 use crate::translate::logical::LogicalPlanBuilder;
 use crate::types::{IOResult, Value};
 use crate::util::{extract_view_columns, ViewColumnSchema};
-use crate::{return_if_io, LimboError, Pager, Result, Statement};
+use crate::{return_if_io, LimboError, Pager, Result, Statement, SymbolTable};
 use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
 use std::cell::RefCell;
 use std::fmt;
@@ -132,10 +132,11 @@ impl ViewTransactionState {
 }
 
 /// Container for all view transaction states within a connection
-/// Provides interior mutability for the map of view states
+/// Provides interior mutability for the map of view states, keyed by the database the
+/// view lives in and its name
 #[derive(Debug, Clone, Default)]
 pub struct AllViewsTxState {
-    states: Rc<RefCell<HashMap<String, Arc<ViewTransactionState>>>>,
+    states: Rc<RefCell<HashMap<(usize, String), Arc<ViewTransactionState>>>>,
 }
 
 // SAFETY: This needs to be audited for thread safety.
@@ -154,20 +155,23 @@ impl AllViewsTxState {
 
     /// Get or create a transaction state for a view
     #[allow(clippy::arc_with_non_send_sync)]
-    pub fn get_or_create(&self, view_name: &str) -> Arc<ViewTransactionState> {
+    pub fn get_or_create(&self, database_id: usize, view_name: &str) -> Arc<ViewTransactionState> {
         let mut states = self.states.borrow_mut();
         // ViewTransactionState uses RefCell (not Sync), but AllViewsTxState is
         // single-threaded (Rc-based). Arc is used for shared ownership, not
         // cross-thread sharing.
         states
-            .entry(view_name.to_string())
+            .entry((database_id, view_name.to_string()))
             .or_insert_with(|| Arc::new(ViewTransactionState::new()))
             .clone()
     }
 
     /// Get a transaction state for a view if it exists
-    pub fn get(&self, view_name: &str) -> Option<Arc<ViewTransactionState>> {
-        self.states.borrow().get(view_name).cloned()
+    pub fn get(&self, database_id: usize, view_name: &str) -> Option<Arc<ViewTransactionState>> {
+        self.states
+            .borrow()
+            .get(&(database_id, view_name.to_string()))
+            .cloned()
     }
 
     /// Clear all transaction states
@@ -180,12 +184,53 @@ impl AllViewsTxState {
         self.states.borrow().is_empty()
     }
 
-    /// Get all view names that have transaction states
-    pub fn get_view_names(&self) -> Vec<String> {
+    /// Get the database and name of all views that have transaction states
+    pub fn get_views(&self) -> Vec<(usize, String)> {
         self.states.borrow().keys().cloned().collect()
     }
 }
 
+/// The row of `table` that materialized views see for a record read from its btree: the
+/// values in column order, keyed by the rowid. WITHOUT ROWID tables have no rowid, so their
+/// rows are keyed by a hash of the PRIMARY KEY, which every version of a row shares.
+pub fn row_for_views(
+    table: &BTreeTable,
+    rowid: Option<i64>,
+    record_values: Vec<Value>,
+) -> (i64, Vec<Value>) {
+    let mut values: Vec<Value> = (0..table.columns().len())
+        .map(|logical| {
+            record_values
+                .get(table.logical_to_physical_column(logical))
+                .cloned()
+                .unwrap_or(Value::Null)
+        })
+        .collect();
+    match rowid {
+        Some(rowid) => {
+            // Rowid alias columns are stored as NULL, their value is the rowid
+            for (i, col) in table.columns().iter().enumerate() {
+                if col.is_rowid_alias() {
+                    values[i] = Value::from_i64(rowid);
+                }
+            }
+            (rowid, values)
+        }
+        None => (without_rowid_key(table, &values), values),
+    }
+}
+
+/// Key standing in for the rowid of a WITHOUT ROWID table row, given its values in column order
+fn without_rowid_key(table: &BTreeTable, values: &[Value]) -> i64 {
+    let key: Vec<Value> = table
+        .primary_key_columns
+        .iter()
+        .filter_map(|(name, _)| table.get_column(name))
+        .map(|(idx, _)| values.get(idx).cloned().unwrap_or(Value::Null))
+        .collect();
+    Hash128::hash_values(&key).as_i64()
+}
+
 /// Incremental view that maintains its state through a DBSP circuit
 ///
 /// This version keeps everything in-memory. This is acceptable for small views, since DBSP
@@ -239,12 +284,13 @@ impl IncrementalView {
     fn try_compile_circuit(
         select: &ast::Select,
         schema: &Schema,
+        syms: &SymbolTable,
         main_data_root: i64,
         internal_state_root: i64,
         internal_state_index_root: i64,
     ) -> Result<DbspCircuit> {
         // Build the logical plan from the SELECT statement
-        let mut builder = LogicalPlanBuilder::new(schema);
+        let mut builder = LogicalPlanBuilder::new(schema).with_symbols(syms);
         // Convert Select to a Stmt for the builder
         let stmt = ast::Stmt::Select(select.clone());
         let logical_plan = builder.build_statement(&stmt)?;
@@ -300,6 +346,7 @@ impl IncrementalView {
     pub fn from_sql(
         sql: &str,
         schema: &Schema,
+        syms: &SymbolTable,
         main_data_root: i64,
         internal_state_root: i64,
         internal_state_index_root: i64,
@@ -317,6 +364,7 @@ impl IncrementalView {
                 view_name,
                 select,
                 schema,
+                syms,
                 main_data_root,
                 internal_state_root,
                 internal_state_index_root,
@@ -327,10 +375,33 @@ impl IncrementalView {
         }
     }
 
+    /// Names of the tables a materialized view definition reads from, without compiling
+    /// its circuit
+    pub fn referenced_table_names_from_sql(sql: &str, schema: &Schema) -> Result<Vec<String>> {
+        let mut parser = Parser::new(sql.as_bytes());
+        let cmd = parser.next_cmd()?;
+        let Some(Cmd::Stmt(Stmt::CreateMaterializedView { select, .. })) = cmd else {
+            return Err(LimboError::ParseError(format!(
+                "View is not a CREATE MATERIALIZED VIEW statement: {sql}"
+            )));
+        };
+        let mut tables = Vec::new();
+        Self::extract_all_tables(
+            &select,
+            schema,
+            &mut tables,
+            &mut HashMap::default(),
+            &mut HashMap::default(),
+            &mut HashMap::default(),
+        )?;
+        Ok(tables.iter().map(|t| t.name.clone()).collect())
+    }
+
     pub fn from_stmt(
         view_name: ast::QualifiedName,
         select: ast::Select,
         schema: &Schema,
+        syms: &SymbolTable,
         main_data_root: i64,
         internal_state_root: i64,
         internal_state_index_root: i64,
@@ -362,6 +433,7 @@ impl IncrementalView {
             table_conditions,
             column_schema,
             schema,
+            syms,
             main_data_root,
             internal_state_root,
             internal_state_index_root,
@@ -378,6 +450,7 @@ impl IncrementalView {
         table_conditions: HashMap<String, Vec<Option<ast::Expr>>>,
         column_schema: ViewColumnSchema,
         schema: &Schema,
+        syms: &SymbolTable,
         main_data_root: i64,
         internal_state_root: i64,
         internal_state_index_root: i64,
@@ -389,6 +462,7 @@ impl IncrementalView {
         let circuit = Self::try_compile_circuit(
             &select_stmt,
             schema,
+            syms,
             main_data_root,
             internal_state_root,
             internal_state_index_root,
@@ -495,48 +569,31 @@ impl IncrementalView {
         table_conditions: &mut HashMap<String, Vec<Option<ast::Expr>>>,
         cte_names: &HashSet<String>,
     ) -> Result<()> {
+        let mut has_subquery = false;
         if let ast::OneSelect::Select {
             from: Some(ref from),
             ..
         } = select
         {
-            // Get the main table from FROM clause
-            if let ast::SelectTable::Table(name, alias, _) = from.select.as_ref() {
-                Self::process_table_reference(
-                    name,
-                    alias,
-                    schema,
-                    table_map,
-                    aliases,
-                    qualified_names,
-                    cte_names,
-                )?;
-            }
-
-            // Get all tables from JOIN clauses
-            for join in &from.joins {
-                if let ast::SelectTable::Table(name, alias, _) = join.table.as_ref() {
-                    Self::process_table_reference(
-                        name,
-                        alias,
-                        schema,
-                        table_map,
-                        aliases,
-                        qualified_names,
-                        cte_names,
-                    )?;
-                }
-            }
+            has_subquery = Self::extract_from_clause(
+                from,
+                schema,
+                table_map,
+                aliases,
+                qualified_names,
+                table_conditions,
+                cte_names,
+            )?;
         }
-        // Extract WHERE conditions for this SELECT
-        let where_expr = if let ast::OneSelect::Select {
-            where_clause: Some(ref where_expr),
-            ..
-        } = select
-        {
-            Some(where_expr.as_ref().clone())
-        } else {
-            None
+        // Extract WHERE conditions for this SELECT. Conditions next to a FROM subquery or a
+        // table-valued function may reference its output columns, which cannot be pushed down
+        // to the base tables, so in that case all rows are fetched.
+        let where_expr = match select {
+            ast::OneSelect::Select {
+                where_clause: Some(ref where_expr),
+                ..
+            } if !has_subquery => Some(where_expr.as_ref().clone()),
+            _ => None,
         };
 
         // Ensure all tables have an entry in table_conditions (even if empty)
@@ -583,6 +640,64 @@ impl IncrementalView {
         Ok(())
     }
 
+    /// Extract the tables of a FROM clause, descending into subqueries and parenthesized joins
+    /// Returns true if the clause contains a subquery or a table-valued function
+    fn extract_from_clause(
+        from: &ast::FromClause,
+        schema: &Schema,
+        table_map: &mut HashMap<String, Arc<BTreeTable>>,
+        aliases: &mut HashMap<String, String>,
+        qualified_names: &mut HashMap<String, String>,
+        table_conditions: &mut HashMap<String, Vec<Option<ast::Expr>>>,
+        cte_names: &HashSet<String>,
+    ) -> Result<bool> {
+        let mut has_subquery = false;
+        for table in std::iter::once(&from.select).chain(from.joins.iter().map(|j| &j.table)) {
+            match table.as_ref() {
+                ast::SelectTable::Table(name, alias, _) => {
+                    Self::process_table_reference(
+                        name,
+                        alias,
+                        schema,
+                        table_map,
+                        aliases,
+                        qualified_names,
+                        cte_names,
+                    )?;
+                }
+                ast::SelectTable::Select(subquery, _) => {
+                    has_subquery = true;
+                    Self::extract_all_tables_inner(
+                        subquery,
+                        schema,
+                        table_map,
+                        aliases,
+                        qualified_names,
+                        table_conditions,
+                        cte_names,
+                    )?;
+                }
+                ast::SelectTable::Sub(inner, _) => {
+                    has_subquery = true;
+                    Self::extract_from_clause(
+                        inner,
+                        schema,
+                        table_map,
+                        aliases,
+                        qualified_names,
+                        table_conditions,
+                        cte_names,
+                    )?;
+                }
+                ast::SelectTable::TableCall(..) => {
+                    // The function is evaluated by the circuit for each row of the tables
+                    has_subquery = true;
+                }
+            }
+        }
+        Ok(has_subquery)
+    }
+
     /// Extract all tables and their aliases from the SELECT statement, handling CTEs
     /// Deduplicates tables and accumulates WHERE conditions
     fn extract_all_tables(
@@ -676,12 +791,35 @@ impl IncrementalView {
     /// Generate SQL queries for populating the view from each source table
     /// Returns a vector of SQL statements, one for each referenced table
     /// Each query includes the WHERE conditions accumulated from all occurrences
-    fn sql_for_populate(&self) -> crate::Result<Vec<String>> {
+    ///
+    /// `database_name` names the attached database the view lives in. Its tables live
+    /// there too, so the ones the definition left unqualified are qualified with it.
+    fn sql_for_populate(&self, database_name: Option<&str>) -> crate::Result<Vec<String>> {
+        let Some(database_name) = database_name else {
+            return Self::generate_populate_queries(
+                &self.select_stmt,
+                &self.referenced_tables,
+                &self.table_aliases,
+                &self.qualified_table_names,
+                &self.table_conditions,
+            );
+        };
+        let qualified_table_names: HashMap<String, String> = self
+            .referenced_tables
+            .iter()
+            .map(|table| {
+                let qualified_name = match self.qualified_table_names.get(&table.name) {
+                    Some(name) if name.contains('.') => name.clone(),
+                    _ => format!("{database_name}.{}", table.name),
+                };
+                (table.name.clone(), qualified_name)
+            })
+            .collect();
         Self::generate_populate_queries(
             &self.select_stmt,
             &self.referenced_tables,
             &self.table_aliases,
-            &self.qualified_table_names,
+            &qualified_table_names,
             &self.table_conditions,
         )
     }
@@ -706,8 +844,9 @@ impl IncrementalView {
             let has_rowid_alias = table.columns().iter().any(|col| col.is_rowid_alias());
 
             // Select all columns. The circuit will handle filtering and projection
-            // If there's a rowid alias, we don't need to select rowid separately
-            let select_clause = if has_rowid_alias {
+            // If there's a rowid alias, we don't need to select rowid separately, and
+            // WITHOUT ROWID tables are keyed by their PRIMARY KEY columns instead
+            let select_clause = if has_rowid_alias || !table.has_rowid {
                 "*".to_string()
             } else {
                 "*, rowid".to_string()
@@ -1141,10 +1280,12 @@ impl IncrementalView {
     /// Populate the view by scanning the source table using a state machine
     /// This can be called multiple times and will resume from where it left off
     /// This method is only for materialized views and will persist data to the btree
+    /// `database_name` is the attached database the view lives in, if it is not main
     pub fn populate_from_table(
         &mut self,
         conn: &crate::sync::Arc<crate::Connection>,
         pager: &crate::sync::Arc<crate::Pager>,
+        database_name: Option<&str>,
         _btree_cursor: &mut dyn CursorTrait,
     ) -> crate::Result<IOResult<()>> {
         // Assert that this is a materialized view with a root page
@@ -1158,7 +1299,7 @@ impl IncrementalView {
         // and decrement on every exit (including IO yields and errors) so re-entrant
         // calls keep the counter balanced.
         conn.start_nested();
-        let result = self.populate_from_table_inner(conn, pager, _btree_cursor);
+        let result = self.populate_from_table_inner(conn, pager, database_name, _btree_cursor);
         conn.end_nested();
         result
     }
@@ -1167,6 +1308,7 @@ impl IncrementalView {
         &mut self,
         conn: &crate::sync::Arc<crate::Connection>,
         pager: &crate::sync::Arc<crate::Pager>,
+        database_name: Option<&str>,
         _btree_cursor: &mut dyn CursorTrait,
     ) -> crate::Result<IOResult<()>> {
         'outer: loop {
@@ -1181,7 +1323,7 @@ impl IncrementalView {
                     //    btree and not in the table btree. Using cursors would force us to be aware of this
                     //    distinction (and others), and ultimately lead to reimplementing the whole query
                     //    machinery (next step is which index is best to use, etc)
-                    let queries = self.sql_for_populate()?;
+                    let queries = self.sql_for_populate(database_name)?;
 
                     self.populate_state = PopulateState::ProcessingAllTables {
                         queries,
@@ -1378,7 +1520,12 @@ impl IncrementalView {
         all_values: Vec<Value>,
         table_idx: usize,
     ) -> Option<(i64, Vec<Value>)> {
-        if let Some((idx, _)) = self.referenced_tables[table_idx].get_rowid_alias_column() {
+        let table = &self.referenced_tables[table_idx];
+        if !table.has_rowid {
+            // All values are table columns, and the key comes from the PRIMARY KEY
+            return Some((without_rowid_key(table, &all_values), all_values));
+        }
+        if let Some((idx, _)) = table.get_rowid_alias_column() {
             // The rowid is the value at the rowid alias column index
             let rowid = match all_values.get(idx) {
                 Some(Value::Numeric(Numeric::Integer(id))) => *id,
@@ -1792,13 +1939,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 1);
         // customers has id as rowid alias, so no need for explicit rowid
@@ -1822,13 +1970,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 1);
         // For single-table queries, we should get the full WHERE clause
@@ -1856,13 +2005,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 2);
 
@@ -1899,13 +2049,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 2);
 
@@ -1937,13 +2088,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 1);
         // logs table has no rowid alias, so we need to explicitly select rowid
@@ -1971,13 +2123,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 2);
         // customers has rowid alias (id), logs doesn't
@@ -2006,13 +2159,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 1);
         // The FROM clause should preserve the database qualification,
@@ -2042,13 +2196,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 2);
         // The FROM clauses should preserve database qualification,
@@ -2163,13 +2318,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 2);
 
@@ -2208,13 +2364,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 2);
 
@@ -2256,13 +2413,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 2);
 
@@ -2352,13 +2510,14 @@ mod tests {
             table_conditions,
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1, // main_data_root
             2, // internal_state_root
             3, // internal_state_index_root
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 1);
         // When the same table appears with and without WHERE conditions in a UNION,
@@ -2488,13 +2647,14 @@ mod tests {
             },
             select,
             &schema,
+            &SymbolTable::new(),
             1,
             2,
             3,
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         // We deduplicate tables, so we get 1 query for orders
         assert_eq!(queries.len(), 1);
@@ -2525,13 +2685,14 @@ mod tests {
             },
             select,
             &schema,
+            &SymbolTable::new(),
             1,
             2,
             3,
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         assert_eq!(queries.len(), 2, "Should have one query per table");
 
@@ -2571,13 +2732,14 @@ mod tests {
             HashMap::default(),
             extract_view_columns(&select, &schema).unwrap(),
             &schema,
+            &SymbolTable::new(),
             1,
             2,
             3,
         )
         .unwrap();
 
-        let queries = view.sql_for_populate().unwrap();
+        let queries = view.sql_for_populate(None).unwrap();
 
         // With duplicates, we should get 2 identical queries
         assert_eq!(queries.len(), 2);
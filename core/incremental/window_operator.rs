@@ -0,0 +1,818 @@
+// Window operator for DBSP-style incremental computation
+// Maintains the results of window functions (OVER clauses) in a view definition
+
+use crate::function::{AggFunc, WindowFunc};
+use crate::incremental::dbsp::{Delta, DeltaPair, Hash128, HashableRow};
+use crate::incremental::join_operator::serialize_hashable_row;
+use crate::incremental::operator::{
+    generate_storage_id, ComputationTracker, DbspStateCursors, EvalState, IncrementalOperator,
+};
+use crate::incremental::persistence::WriteRow;
+use crate::incremental::topk_operator::{
+    encode_sort_key, read_next_ordered_row, OrderedRow, OrderedScan, TopKSortKey,
+};
+use crate::sync::Arc;
+use crate::sync::Mutex;
+use crate::translate::collate::CollationSeq;
+use crate::translate::plan::WindowFunctionKind;
+use crate::types::IOResult;
+use crate::vdbe::execute::{compute_aggregate, compute_running_aggregate};
+use crate::{return_and_restore_if_io, return_if_io, LimboError, Result, Value};
+use std::collections::BTreeMap;
+
+/// Unit of the offsets of a window frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowFrameUnits {
+    Rows,
+    /// Only with unbounded and CURRENT ROW bounds, where it behaves like GROUPS
+    Range,
+    Groups,
+}
+
+/// One end of a window frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowFrameBound {
+    UnboundedPreceding,
+    Preceding(usize),
+    CurrentRow,
+    Following(usize),
+    UnboundedFollowing,
+}
+
+/// Rows of the partition that a window function sees for each row
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowFrame {
+    pub units: WindowFrameUnits,
+    pub start: WindowFrameBound,
+    pub end: WindowFrameBound,
+}
+
+impl WindowFrame {
+    /// RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW, used when no frame is given
+    pub const DEFAULT: WindowFrame = WindowFrame {
+        units: WindowFrameUnits::Range,
+        start: WindowFrameBound::UnboundedPreceding,
+        end: WindowFrameBound::CurrentRow,
+    };
+}
+
+/// Argument of a window function
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowArg {
+    /// Index of a column of the input rows
+    Column(usize),
+    Literal(Value),
+}
+
+/// A window function computed by the operator, resolved against its input
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowFunctionSpec {
+    pub func: WindowFunctionKind,
+    pub args: Vec<WindowArg>,
+    pub frame: WindowFrame,
+}
+
+/// Peer groups of a partition: rows with equal ORDER BY values are peers
+struct PeerGroups {
+    /// Peer group of each row
+    group_of: Vec<usize>,
+    /// First row of each peer group, followed by the number of rows
+    starts: Vec<usize>,
+}
+
+impl PeerGroups {
+    fn new(sort_keys: &[&[u8]]) -> Self {
+        let mut group_of = Vec::with_capacity(sort_keys.len());
+        let mut starts = Vec::new();
+        for (i, key) in sort_keys.iter().enumerate() {
+            if i == 0 || sort_keys[i - 1] != *key {
+                starts.push(i);
+            }
+            group_of.push(starts.len() - 1);
+        }
+        starts.push(sort_keys.len());
+        Self { group_of, starts }
+    }
+
+    fn count(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    /// First row of the peer group of row `i`
+    fn start_of(&self, i: usize) -> usize {
+        self.starts[self.group_of[i]]
+    }
+
+    /// Row after the last peer of row `i`
+    fn end_of(&self, i: usize) -> usize {
+        self.starts[self.group_of[i] + 1]
+    }
+
+    /// The rows of `frame` for row `i`, as a half-open range
+    fn frame_bounds(&self, frame: &WindowFrame, i: usize) -> (usize, usize) {
+        let n = self.group_of.len() as i64;
+        let (lo, hi) = match frame.units {
+            WindowFrameUnits::Rows => {
+                let i = i as i64;
+                let lo = match frame.start {
+                    WindowFrameBound::UnboundedPreceding => 0,
+                    WindowFrameBound::Preceding(k) => i - k as i64,
+                    WindowFrameBound::CurrentRow => i,
+                    WindowFrameBound::Following(k) => i + k as i64,
+                    WindowFrameBound::UnboundedFollowing => n,
+                };
+                let hi = match frame.end {
+                    WindowFrameBound::UnboundedPreceding => 0,
+                    WindowFrameBound::Preceding(k) => i - k as i64 + 1,
+                    WindowFrameBound::CurrentRow => i + 1,
+                    WindowFrameBound::Following(k) => i + k as i64 + 1,
+                    WindowFrameBound::UnboundedFollowing => n,
+                };
+                (lo, hi)
+            }
+            WindowFrameUnits::Range | WindowFrameUnits::Groups => {
+                let groups = self.count() as i64;
+                let g = self.group_of[i] as i64;
+                let start_group = match frame.start {
+                    WindowFrameBound::UnboundedPreceding => 0,
+                    WindowFrameBound::Preceding(k) => g - k as i64,
+                    WindowFrameBound::CurrentRow => g,
+                    WindowFrameBound::Following(k) => g + k as i64,
+                    WindowFrameBound::UnboundedFollowing => groups,
+                };
+                // One past the last group of the frame
+                let end_group = match frame.end {
+                    WindowFrameBound::UnboundedPreceding => 0,
+                    WindowFrameBound::Preceding(k) => g - k as i64 + 1,
+                    WindowFrameBound::CurrentRow => g + 1,
+                    WindowFrameBound::Following(k) => g + k as i64 + 1,
+                    WindowFrameBound::UnboundedFollowing => groups,
+                };
+                let row_of = |group: i64| self.starts[group.clamp(0, groups) as usize] as i64;
+                (row_of(start_group), row_of(end_group))
+            }
+        };
+        let lo = lo.clamp(0, n);
+        (lo as usize, hi.clamp(lo, n) as usize)
+    }
+}
+
+/// Rowid of the output row for one copy of an input row. Copies of a row get different
+/// window values, so all but the first need a rowid of their own.
+fn output_rowid(row: &HashableRow, copy: usize) -> i64 {
+    if copy == 0 {
+        return row.rowid;
+    }
+    Hash128::hash_values(&[Value::from_i64(row.rowid), Value::from_i64(copy as i64)]).as_i64()
+}
+
+/// Convert a window function argument to an integer, as a CAST would
+fn integer_arg(value: &Value) -> Option<i64> {
+    value.exec_cast("INTEGER").as_int()
+}
+
+fn positive_integer_arg(value: &Value, message: &str) -> Result<i64> {
+    match integer_arg(value) {
+        Some(n) if n > 0 => Ok(n),
+        _ => Err(LimboError::InternalError(message.to_string())),
+    }
+}
+
+/// Compute the output rows of a partition, given its rows in window order
+fn partition_output(
+    rows: &[(&[u8], &HashableRow, isize)],
+    functions: &[WindowFunctionSpec],
+) -> Result<Vec<HashableRow>> {
+    // Every copy of a row is a separate row of the partition
+    let mut sort_keys = Vec::new();
+    let mut copies = Vec::new();
+    for (sort_key, row, weight) in rows {
+        for copy in 0..(*weight).max(0) as usize {
+            sort_keys.push(*sort_key);
+            copies.push((*row, copy));
+        }
+    }
+    let peers = PeerGroups::new(&sort_keys);
+
+    let mut results = Vec::with_capacity(functions.len());
+    for function in functions {
+        let arg = |idx: usize, row: usize| -> Value {
+            match function.args.get(idx) {
+                Some(WindowArg::Column(col)) => copies[row]
+                    .0
+                    .values
+                    .get(*col)
+                    .cloned()
+                    .unwrap_or(Value::Null),
+                Some(WindowArg::Literal(value)) => value.clone(),
+                None => Value::Null,
+            }
+        };
+        results.push(evaluate_function(function, copies.len(), &peers, arg)?);
+    }
+
+    Ok(copies
+        .iter()
+        .enumerate()
+        .map(|(i, (row, copy))| {
+            let mut values = row.values.clone();
+            values.extend(results.iter().map(|result| result[i].clone()));
+            HashableRow::new(output_rowid(row, *copy), values)
+        })
+        .collect())
+}
+
+/// Compute one window function for every row of a partition
+fn evaluate_function(
+    function: &WindowFunctionSpec,
+    n: usize,
+    peers: &PeerGroups,
+    arg: impl Fn(usize, usize) -> Value,
+) -> Result<Vec<Value>> {
+    let window_func = match &function.func {
+        WindowFunctionKind::Agg(func) => return evaluate_aggregate(function, func, n, peers, arg),
+        WindowFunctionKind::Window(window_func) => *window_func,
+    };
+    (0..n)
+        .map(|i| {
+            Ok(match window_func {
+                WindowFunc::RowNumber => Value::from_i64(i as i64 + 1),
+                WindowFunc::Rank => Value::from_i64(peers.start_of(i) as i64 + 1),
+                WindowFunc::DenseRank => Value::from_i64(peers.group_of[i] as i64 + 1),
+                WindowFunc::PercentRank => {
+                    if n > 1 {
+                        Value::from_f64(peers.start_of(i) as f64 / (n - 1) as f64)
+                    } else {
+                        Value::from_f64(0.0)
+                    }
+                }
+                WindowFunc::CumeDist => Value::from_f64(peers.end_of(i) as f64 / n as f64),
+                WindowFunc::Ntile => {
+                    let buckets = positive_integer_arg(
+                        &arg(0, i),
+                        "argument of ntile must be a positive integer",
+                    )? as usize;
+                    // The first n % buckets buckets hold one extra row
+                    let size = n / buckets;
+                    let large = n % buckets;
+                    let small_start = large * (size + 1);
+                    let bucket = if i < small_start {
+                        i / (size + 1)
+                    } else {
+                        large + (i - small_start) / size
+                    };
+                    Value::from_i64(bucket as i64 + 1)
+                }
+                WindowFunc::Lag | WindowFunc::Lead => {
+                    let offset = if function.args.len() > 1 {
+                        integer_arg(&arg(1, i))
+                    } else {
+                        Some(1)
+                    };
+                    let target = offset.and_then(|offset| {
+                        let target = if window_func == WindowFunc::Lag {
+                            (i as i64).checked_sub(offset)
+                        } else {
+                            (i as i64).checked_add(offset)
+                        }?;
+                        usize::try_from(target).ok().filter(|target| *target < n)
+                    });
+                    match target {
+                        Some(target) => arg(0, target),
+                        None => arg(2, i),
+                    }
+                }
+                WindowFunc::FirstValue | WindowFunc::LastValue | WindowFunc::NthValue => {
+                    let (lo, hi) = peers.frame_bounds(&function.frame, i);
+                    let position = match window_func {
+                        WindowFunc::FirstValue => Some(lo),
+                        WindowFunc::LastValue => hi.checked_sub(1),
+                        _ => {
+                            let nth = positive_integer_arg(
+                                &arg(1, i),
+                                "second argument to nth_value must be a positive integer",
+                            )?;
+                            lo.checked_add(nth as usize - 1)
+                        }
+                    };
+                    match position.filter(|position| (lo..hi).contains(position)) {
+                        Some(position) => arg(0, position),
+                        None => Value::Null,
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+/// Compute an aggregate over the frame of every row of a partition
+fn evaluate_aggregate(
+    function: &WindowFunctionSpec,
+    func: &AggFunc,
+    n: usize,
+    peers: &PeerGroups,
+    arg: impl Fn(usize, usize) -> Value,
+) -> Result<Vec<Value>> {
+    let inputs: Vec<Vec<Value>> = (0..n)
+        .map(|i| (0..function.args.len()).map(|idx| arg(idx, i)).collect())
+        .collect();
+    let bounds: Vec<(usize, usize)> = (0..n)
+        .map(|i| peers.frame_bounds(&function.frame, i))
+        .collect();
+    if function.frame.start == WindowFrameBound::UnboundedPreceding {
+        // Every frame is a prefix of the partition, and the prefixes only grow
+        let ends: Vec<usize> = bounds.iter().map(|(_, hi)| *hi).collect();
+        return compute_running_aggregate(func, &inputs, &ends);
+    }
+    bounds
+        .iter()
+        .map(|(lo, hi)| compute_aggregate(func, &inputs[*lo..*hi]))
+        .collect()
+}
+
+// Window specific eval states
+#[derive(Debug)]
+pub enum WindowEvalState {
+    /// Reading the persisted rows of each changed partition, one partition at a time
+    ReadPartition {
+        /// Changed partitions with their consolidated changes, by partition key
+        partitions: Vec<(Vec<u8>, Vec<OrderedRow>)>,
+        current_idx: usize,
+        /// Persisted rows of the current partition read so far, in order
+        stored: Vec<OrderedRow>,
+        output: Delta,
+    },
+    Done {
+        output: Delta,
+    },
+}
+
+impl WindowEvalState {
+    fn process_window_state(
+        &mut self,
+        cursors: &mut DbspStateCursors,
+        storage_id: i64,
+        functions: &[WindowFunctionSpec],
+    ) -> Result<IOResult<Delta>> {
+        loop {
+            match self {
+                WindowEvalState::ReadPartition {
+                    partitions,
+                    current_idx,
+                    stored,
+                    output,
+                } => {
+                    let Some((partition_key, changes)) = partitions.get(*current_idx) else {
+                        let mut output = std::mem::take(output);
+                        output.consolidate();
+                        // Old rows are removed before new ones are added, so that a row
+                        // whose window values changed keeps its rowid
+                        output.changes.sort_by_key(|(_, weight)| *weight);
+                        *self = WindowEvalState::Done { output };
+                        continue;
+                    };
+
+                    let position = match stored.last() {
+                        Some(row) => OrderedScan::After(&row.sort_key, row.element_hash),
+                        None => OrderedScan::From(partition_key),
+                    };
+                    match return_if_io!(read_next_ordered_row(storage_id, position, cursors)) {
+                        Some(row) if row.sort_key.starts_with(partition_key) => stored.push(row),
+                        _ => {
+                            Self::partition_delta(stored, changes, functions, output)?;
+                            stored.clear();
+                            *current_idx += 1;
+                        }
+                    }
+                }
+                WindowEvalState::Done { output } => {
+                    return Ok(IOResult::Done(std::mem::take(output)));
+                }
+            }
+        }
+    }
+
+    /// Add the change of a partition's output to `output`, from its persisted rows before
+    /// and after applying `changes`
+    fn partition_delta(
+        stored: &[OrderedRow],
+        changes: &[OrderedRow],
+        functions: &[WindowFunctionSpec],
+        output: &mut Delta,
+    ) -> Result<()> {
+        let before: Vec<(&[u8], &HashableRow, isize)> = stored
+            .iter()
+            .map(|r| (r.sort_key.as_slice(), &r.row, r.weight))
+            .collect();
+        for row in partition_output(&before, functions)? {
+            output.changes.push((row, -1));
+        }
+
+        let mut merged: BTreeMap<(&[u8], Hash128), (&HashableRow, isize)> = BTreeMap::new();
+        for r in stored.iter().chain(changes) {
+            merged
+                .entry((r.sort_key.as_slice(), r.element_hash))
+                .or_insert((&r.row, 0))
+                .1 += r.weight;
+        }
+        let after: Vec<(&[u8], &HashableRow, isize)> = merged
+            .into_iter()
+            .map(|((sort_key, _), (row, weight))| (sort_key, row, weight))
+            .collect();
+        for row in partition_output(&after, functions)? {
+            output.changes.push((row, 1));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum WindowCommitState {
+    Idle,
+    Eval {
+        eval_state: EvalState,
+    },
+    CommitDelta {
+        changes: Delta,
+        output: Delta,
+        current_idx: usize,
+        write_row: WriteRow,
+    },
+    Invalid,
+}
+
+/// Window operator - appends the values of window functions to every input row
+///
+/// Every input row is persisted, keyed by its PARTITION BY values followed by its ORDER BY
+/// values, so each partition can be read back in window order. A delta only recomputes
+/// the partitions it touches, and only the rows whose window values changed are output.
+#[derive(Debug)]
+pub struct WindowOperator {
+    /// Unique operator ID for indexing in persistent storage
+    operator_id: i64,
+    /// Indices of the PARTITION BY columns in the input rows
+    partition_by: Vec<usize>,
+    /// ORDER BY terms of the window
+    order_by: Vec<TopKSortKey>,
+    /// Functions computed over the window, in output column order
+    functions: Vec<WindowFunctionSpec>,
+
+    commit_state: WindowCommitState,
+}
+
+impl WindowOperator {
+    pub fn new(
+        operator_id: i64,
+        partition_by: Vec<usize>,
+        order_by: Vec<TopKSortKey>,
+        functions: Vec<WindowFunctionSpec>,
+    ) -> Self {
+        Self {
+            operator_id,
+            partition_by,
+            order_by,
+            functions,
+            commit_state: WindowCommitState::Idle,
+        }
+    }
+
+    fn storage_id(&self) -> i64 {
+        generate_storage_id(self.operator_id, 0, 0)
+    }
+
+    /// Encode the PARTITION BY values of a row. Only equality matters here, so every
+    /// column is encoded in ascending order.
+    fn partition_key(&self, values: &[Value]) -> Vec<u8> {
+        let keys: Vec<TopKSortKey> = self
+            .partition_by
+            .iter()
+            .map(|column_index| TopKSortKey {
+                column_index: *column_index,
+                asc: true,
+                nulls_first: true,
+                collation: CollationSeq::Binary,
+            })
+            .collect();
+        encode_sort_key(values, &keys)
+    }
+
+    /// The partition key followed by the ORDER BY key of a row
+    fn sort_key(&self, values: &[Value]) -> Vec<u8> {
+        let mut key = self.partition_key(values);
+        key.extend(encode_sort_key(values, &self.order_by));
+        key
+    }
+
+    fn eval_internal(
+        &mut self,
+        state: &mut EvalState,
+        cursors: &mut DbspStateCursors,
+    ) -> Result<IOResult<Delta>> {
+        loop {
+            let loop_state = std::mem::replace(state, EvalState::Uninitialized);
+            match loop_state {
+                EvalState::Uninitialized => {
+                    panic!("Cannot eval WindowOperator with Uninitialized state");
+                }
+                EvalState::Init { deltas } => {
+                    let mut delta = deltas.left;
+                    delta.consolidate();
+                    if delta.is_empty() {
+                        *state = EvalState::Done;
+                        return Ok(IOResult::Done(Delta::new()));
+                    }
+
+                    let mut partitions: BTreeMap<Vec<u8>, Vec<OrderedRow>> = BTreeMap::new();
+                    for (row, weight) in &delta.changes {
+                        partitions
+                            .entry(self.partition_key(&row.values))
+                            .or_default()
+                            .push(OrderedRow {
+                                sort_key: self.sort_key(&row.values),
+                                element_hash: row.cached_hash(),
+                                row: row.clone(),
+                                weight: *weight,
+                            });
+                    }
+
+                    *state = EvalState::Window(Box::new(WindowEvalState::ReadPartition {
+                        partitions: partitions.into_iter().collect(),
+                        current_idx: 0,
+                        stored: Vec::new(),
+                        output: Delta::new(),
+                    }));
+                }
+                EvalState::Window(mut window_state) => {
+                    let result = window_state.process_window_state(
+                        cursors,
+                        self.storage_id(),
+                        &self.functions,
+                    );
+                    *state = EvalState::Window(window_state);
+                    let output = return_if_io!(result);
+                    *state = EvalState::Done;
+                    return Ok(IOResult::Done(output));
+                }
+                EvalState::Done => {
+                    return Ok(IOResult::Done(Delta::new()));
+                }
+                EvalState::Aggregate(_) | EvalState::Join(_) | EvalState::TopK(_) => {
+                    panic!("Only window state should appear in window operator");
+                }
+            }
+        }
+    }
+}
+
+impl IncrementalOperator for WindowOperator {
+    fn eval(
+        &mut self,
+        state: &mut EvalState,
+        cursors: &mut DbspStateCursors,
+    ) -> Result<IOResult<Delta>> {
+        let delta = return_if_io!(self.eval_internal(state, cursors));
+        Ok(IOResult::Done(delta))
+    }
+
+    fn commit(
+        &mut self,
+        deltas: DeltaPair,
+        cursors: &mut DbspStateCursors,
+    ) -> Result<IOResult<Delta>> {
+        loop {
+            let mut state = std::mem::replace(&mut self.commit_state, WindowCommitState::Invalid);
+            match &mut state {
+                WindowCommitState::Idle => {
+                    self.commit_state = WindowCommitState::Eval {
+                        eval_state: deltas.clone().into(),
+                    }
+                }
+                WindowCommitState::Eval { ref mut eval_state } => {
+                    // The output is computed against the state before this delta is persisted
+                    let output = return_and_restore_if_io!(
+                        &mut self.commit_state,
+                        state,
+                        self.eval(eval_state, cursors)
+                    );
+                    let mut changes = deltas.left.clone();
+                    changes.consolidate();
+                    self.commit_state = WindowCommitState::CommitDelta {
+                        changes,
+                        output,
+                        current_idx: 0,
+                        write_row: WriteRow::new(),
+                    };
+                }
+                WindowCommitState::CommitDelta {
+                    changes,
+                    output,
+                    current_idx,
+                    ref mut write_row,
+                } => {
+                    if *current_idx >= changes.changes.len() {
+                        self.commit_state = WindowCommitState::Idle;
+                        return Ok(IOResult::Done(std::mem::take(output)));
+                    }
+
+                    let (row, weight) = &changes.changes[*current_idx];
+                    let storage_id = self.storage_id();
+                    let sort_key = Value::Blob(self.sort_key(&row.values));
+                    let element_hash = row.cached_hash();
+                    let index_key = vec![
+                        Value::from_i64(storage_id),
+                        sort_key.clone(),
+                        element_hash.to_value(),
+                    ];
+                    let record_values = vec![
+                        Value::from_i64(storage_id),
+                        sort_key,
+                        element_hash.to_value(),
+                        Value::Blob(serialize_hashable_row(row)),
+                    ];
+
+                    return_and_restore_if_io!(
+                        &mut self.commit_state,
+                        state,
+                        write_row.write_row(cursors, index_key, record_values, *weight)
+                    );
+
+                    self.commit_state = WindowCommitState::CommitDelta {
+                        changes: std::mem::take(changes),
+                        output: std::mem::take(output),
+                        current_idx: *current_idx + 1,
+                        write_row: WriteRow::new(),
+                    };
+                }
+                WindowCommitState::Invalid => {
+                    panic!("Invalid window commit state");
+                }
+            }
+        }
+    }
+
+    fn set_tracker(&mut self, _tracker: Arc<Mutex<ComputationTracker>>) {
+        // Window operator doesn't need tracking
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::operator::create_dbsp_state_index;
+    use crate::storage::btree::BTreeCursor;
+    use crate::storage::pager::CreateBTreeFlags;
+    use crate::util::IOExt;
+    use crate::{Database, MemoryIO, Pager, IO};
+
+    fn create_test_cursors() -> (Arc<Pager>, DbspStateCursors) {
+        let io: Arc<dyn IO> = Arc::new(MemoryIO::new());
+        let db = Database::open_file(io.clone(), ":memory:").unwrap();
+        let conn = db.connect().unwrap();
+        let pager = conn.pager.load().clone();
+        let _ = pager.io.block(|| pager.allocate_page1());
+        let table_root_page_id = pager
+            .io
+            .block(|| pager.btree_create(&CreateBTreeFlags::new_table()))
+            .unwrap() as i64;
+        let index_root_page_id = pager
+            .io
+            .block(|| pager.btree_create(&CreateBTreeFlags::new_index()))
+            .unwrap() as i64;
+        let table_cursor = BTreeCursor::new_table(pager.clone(), table_root_page_id, 5);
+        let index_def = create_dbsp_state_index(index_root_page_id);
+        let index_cursor = BTreeCursor::new_index(pager.clone(), index_root_page_id, &index_def, 4);
+        (pager, DbspStateCursors::new(table_cursor, index_cursor))
+    }
+
+    /// ROW_NUMBER() and a running SUM(c2) over (PARTITION BY c1 ORDER BY c2)
+    fn create_operator() -> WindowOperator {
+        WindowOperator::new(
+            1,
+            vec![1],
+            vec![TopKSortKey {
+                column_index: 2,
+                asc: true,
+                nulls_first: true,
+                collation: CollationSeq::Binary,
+            }],
+            vec![
+                WindowFunctionSpec {
+                    func: WindowFunctionKind::Window(WindowFunc::RowNumber),
+                    args: vec![],
+                    frame: WindowFrame::DEFAULT,
+                },
+                WindowFunctionSpec {
+                    func: WindowFunctionKind::Agg(AggFunc::Sum),
+                    args: vec![WindowArg::Column(2)],
+                    frame: WindowFrame::DEFAULT,
+                },
+            ],
+        )
+    }
+
+    fn row(id: i64, partition: &str, value: i64) -> Vec<Value> {
+        vec![
+            Value::from_i64(id),
+            Value::Text(partition.into()),
+            Value::from_i64(value),
+        ]
+    }
+
+    fn output(id: i64, partition: &str, value: i64, row_number: i64, sum: i64) -> Vec<Value> {
+        let mut values = row(id, partition, value);
+        values.push(Value::from_i64(row_number));
+        values.push(Value::from_i64(sum));
+        values
+    }
+
+    fn sorted_changes(delta: &Delta) -> Vec<(isize, i64, Vec<Value>)> {
+        let mut changes: Vec<(isize, i64, Vec<Value>)> = delta
+            .changes
+            .iter()
+            .map(|(row, weight)| (*weight, row.rowid, row.values.clone()))
+            .collect();
+        changes.sort_by_key(|(weight, rowid, _)| (*weight, *rowid));
+        changes
+    }
+
+    #[test]
+    fn test_window_insert_recomputes_touched_partition() {
+        let (pager, mut cursors) = create_test_cursors();
+        let mut operator = create_operator();
+
+        let mut initial = Delta::new();
+        initial.insert(1, row(1, "a", 10));
+        initial.insert(2, row(2, "a", 20));
+        initial.insert(3, row(3, "b", 5));
+        let result = pager
+            .io
+            .block(|| operator.commit((&initial).into(), &mut cursors))
+            .unwrap();
+        assert_eq!(
+            sorted_changes(&result),
+            vec![
+                (1, 1, output(1, "a", 10, 1, 10)),
+                (1, 2, output(2, "a", 20, 2, 30)),
+                (1, 3, output(3, "b", 5, 1, 5)),
+            ]
+        );
+
+        // A row sorting first in partition "b" renumbers that partition only
+        let mut insert = Delta::new();
+        insert.insert(4, row(4, "b", 1));
+        let result = pager
+            .io
+            .block(|| operator.commit((&insert).into(), &mut cursors))
+            .unwrap();
+        assert_eq!(
+            sorted_changes(&result),
+            vec![
+                (-1, 3, output(3, "b", 5, 1, 5)),
+                (1, 3, output(3, "b", 5, 2, 6)),
+                (1, 4, output(4, "b", 1, 1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_window_delete_retracts_changed_rows() {
+        let (pager, mut cursors) = create_test_cursors();
+        let mut operator = create_operator();
+
+        let mut initial = Delta::new();
+        initial.insert(1, row(1, "a", 10));
+        initial.insert(2, row(2, "a", 20));
+        initial.insert(3, row(3, "b", 5));
+        pager
+            .io
+            .block(|| operator.commit((&initial).into(), &mut cursors))
+            .unwrap();
+
+        let mut delete = Delta::new();
+        delete.delete(1, row(1, "a", 10));
+        let result = pager
+            .io
+            .block(|| operator.commit((&delete).into(), &mut cursors))
+            .unwrap();
+        assert_eq!(
+            sorted_changes(&result),
+            vec![
+                (-1, 1, output(1, "a", 10, 1, 10)),
+                (-1, 2, output(2, "a", 20, 2, 30)),
+                (1, 2, output(2, "a", 20, 1, 20)),
+            ]
+        );
+
+        // Deleting the last row of a partition retracts it without adding anything
+        let mut delete = Delta::new();
+        delete.delete(3, row(3, "b", 5));
+        let result = pager
+            .io
+            .block(|| operator.commit((&delete).into(), &mut cursors))
+            .unwrap();
+        assert_eq!(
+            sorted_changes(&result),
+            vec![(-1, 3, output(3, "b", 5, 1, 5))]
+        );
+    }
+}
@@ -78,7 +78,8 @@ use crate::{
     storage::{
         checksum::CHECKSUM_REQUIRED_RESERVED_BYTES,
         encryption::{AtomicCipherMode, SQLITE_HEADER, TURSO_HEADER_PREFIX},
-        journal_mode,
+        journal::{self, RollbackJournal, RollbackJournalShared},
+        journal_mode::{self, JournalMode},
         pager::{self, AutoVacuumMode, HeaderRef, HeaderRefMut},
         sqlite3_ondisk::{RawVersion, TextEncoding, Version},
     },
@@ -202,7 +203,6 @@ pub struct DatabaseOpts {
     pub enable_encryption: bool,
     pub enable_index_method: bool,
     pub enable_autovacuum: bool,
-    pub enable_vacuum: bool,
     pub enable_attach: bool,
     pub enable_generated_columns: bool,
     pub enable_multiprocess_wal: bool,
@@ -247,8 +247,8 @@ impl DatabaseOpts {
         self
     }
 
-    pub fn with_vacuum(mut self, enable: bool) -> Self {
-        self.enable_vacuum = enable;
+    #[deprecated(note = "VACUUM is always enabled; this option has no effect")]
+    pub fn with_vacuum(self, _enable: bool) -> Self {
         self
     }
 
@@ -489,6 +489,9 @@ pub struct Database {
     shared_wal: Arc<RwLock<WalFileShared>>,
     #[cfg(host_shared_wal)]
     shared_wal_coordination: OnceLock<Arc<MappedSharedWalCoordination>>,
+    /// Set when the database is in one of the rollback journal modes, in which
+    /// case `shared_wal` is a disabled no-op WAL.
+    rollback_journal: ArcSwapOption<RollbackJournalShared>,
     init_lock: Arc<Mutex<()>>,
     open_flags: OpenFlags,
     // Use parking lot RwLock here and not `crate::sync::RwLock` because it relies on `data_ptr` and that is experimental
@@ -581,6 +584,15 @@ impl Database {
         let shared_wal = WalFileShared::new_noop();
         let mv_store = ArcSwapOption::empty();
 
+        // A hot journal must be rolled back before anything reads the database file.
+        if !is_memory_like(&path) {
+            journal::recover_hot_journal(
+                io,
+                &db_file,
+                &format!("{path}-journal"),
+                flags.contains(OpenFlags::ReadOnly),
+            )?;
+        }
         let db_size = db_file.size()?;
 
         let shared_page_cache = Arc::new(RwLock::new(PageCache::default()));
@@ -618,6 +630,7 @@ impl Database {
             shared_wal,
             #[cfg(host_shared_wal)]
             shared_wal_coordination: OnceLock::new(),
+            rollback_journal: ArcSwapOption::empty(),
             db_file,
             builtin_syms: parking_lot::RwLock::new(syms),
             io: io.clone(),
@@ -1182,9 +1195,13 @@ impl Database {
                         let wal_enabled =
                             db.shared_wal.read().metadata.enabled.load(Ordering::SeqCst);
                         let mv_store_enabled = db.get_mv_store().is_some();
+                        let rollback_journal_enabled = db.rollback_journal.load().is_some();
                         assert!(
-                            db.is_readonly() || wal_enabled || mv_store_enabled,
-                            "Either WAL or MVStore must be enabled"
+                            db.is_readonly()
+                                || wal_enabled
+                                || mv_store_enabled
+                                || rollback_journal_enabled,
+                            "Either WAL, MVStore or a rollback journal must be enabled"
                         );
                     }
 
@@ -1529,9 +1546,18 @@ impl Database {
         // MVCC is controlled only by the database header (set via PRAGMA journal_mode)
         let open_mv_store = matches!(read_version, Version::Mvcc);
 
+        // A legacy database stays in rollback journal mode, unless an earlier
+        // version left a WAL next to it, in which case it is converted to WAL
+        // mode so that the frames in the WAL are not lost. Rollback journal
+        // locking is process-local, so multiprocess access also requires WAL.
+        let open_rollback_journal = matches!(read_version, Version::Legacy)
+            && !self.opts.enable_multiprocess_wal
+            && !journal::file_exists(&self.io, &self.wal_path)?;
+
         // Now check the Header Version to see which mode the DB file really is on
         // Track if header was modified so we can write it to disk
         let header_modified = match read_version {
+            Version::Legacy if open_rollback_journal => false,
             Version::Legacy => {
                 if is_readonly {
                     tracing::warn!(
@@ -1570,6 +1596,19 @@ impl Database {
 
         drop(header);
 
+        if open_rollback_journal {
+            let shared = Arc::new(RollbackJournalShared::new(
+                self.journal_path(),
+                JournalMode::Delete,
+            ));
+            pager.set_rollback_journal(RollbackJournal::new(shared.clone()));
+            self.rollback_journal.store(Some(shared));
+            // Pages read while validating the header were read outside of any lock.
+            pager.clear_page_cache(true);
+            pager.set_schema_cookie(None);
+            return Ok(Arc::new(pager));
+        }
+
         let flags = self.open_flags;
 
         // Always Open shared wal and set it in the Database and Pager.
@@ -1633,6 +1672,54 @@ impl Database {
         Ok(Arc::new(pager))
     }
 
+    /// Path of the rollback journal, used in the rollback journal modes.
+    pub(crate) fn journal_path(&self) -> String {
+        format!("{}-journal", self.path)
+    }
+
+    /// Moves the database from a rollback journal mode to WAL mode, once the
+    /// header says so. `connection` must be the only connection.
+    pub(crate) fn switch_to_wal(&self, connection: &Connection) -> Result<()> {
+        let shared_wal =
+            WalFileShared::open_shared_if_exists(&self.io, &self.wal_path, self.open_flags)?;
+        std::mem::swap(&mut *self.shared_wal.write(), &mut *shared_wal.write());
+        self.rollback_journal.store(None);
+        self.rebuild_pager(connection)
+    }
+
+    /// Moves the database from WAL mode to a rollback journal mode, once the WAL
+    /// has been checkpointed and the header says so. `connection` must be the
+    /// only connection.
+    pub(crate) fn switch_to_rollback_journal(
+        &self,
+        connection: &Connection,
+        mode: JournalMode,
+    ) -> Result<()> {
+        std::mem::swap(
+            &mut *self.shared_wal.write(),
+            &mut *WalFileShared::new_noop().write(),
+        );
+        if journal::file_exists(&self.io, &self.wal_path)? {
+            self.io.remove_file(&self.wal_path)?;
+        }
+        self.rollback_journal
+            .store(Some(Arc::new(RollbackJournalShared::new(
+                self.journal_path(),
+                mode,
+            ))));
+        self.rebuild_pager(connection)
+    }
+
+    fn rebuild_pager(&self, connection: &Connection) -> Result<()> {
+        let pager = self._init(connection.encryption_key.read().as_ref())?;
+        // Carry the connection's journal tuning over to the new pager.
+        let old_pager = connection.pager.load();
+        pager.set_wal_autocheckpoint(old_pager.get_wal_autocheckpoint());
+        pager.set_journal_size_limit(old_pager.get_journal_size_limit());
+        connection.pager.store(Arc::new(pager));
+        Ok(())
+    }
+
     pub fn get_database_canonical_path(&self) -> String {
         if self.is_in_memory_db() {
             // For in-memory databases, SQLite shows empty string
@@ -1733,6 +1820,7 @@ impl Database {
             full_column_names: AtomicBool::new(false),
             short_column_names: AtomicBool::new(true),
             fk_pragma: AtomicBool::new(false),
+            fk_defer_pragma: AtomicBool::new(false),
             fk_deferred_violations: AtomicIsize::new(0),
             n_active_writes: AtomicI32::new(0),
             n_active_root_statements: AtomicI32::new(0),
@@ -2273,7 +2361,7 @@ impl Database {
             None
         };
 
-        let pager = Pager::new(
+        let mut pager = Pager::new(
             self.db_file.clone(),
             pager_wal,
             self.io.clone(),
@@ -2282,6 +2370,9 @@ impl Database {
             self.init_lock.clone(),
             self.init_page_1.clone(),
         )?;
+        if let Some(journal) = self.rollback_journal.load_full() {
+            pager.set_rollback_journal(RollbackJournal::new(journal));
+        }
         pager.set_page_size(page_size);
         if let Some(reserved_bytes) = reserved_bytes {
             pager.set_reserved_space_bytes(reserved_bytes);
@@ -2412,10 +2503,6 @@ impl Database {
         self.opts.enable_autovacuum
     }
 
-    pub fn experimental_vacuum_enabled(&self) -> bool {
-        self.opts.enable_vacuum
-    }
-
     pub fn experimental_attach_enabled(&self) -> bool {
         self.opts.enable_attach
     }
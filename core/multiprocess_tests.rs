@@ -931,7 +931,7 @@ fn plain_vacuum_rejects_multiprocess_wal_database() {
         io,
         db_path_str,
         OpenFlags::default(),
-        multiprocess_wal_db_opts().with_vacuum(true),
+        multiprocess_wal_db_opts(),
         None,
     )
     .unwrap();
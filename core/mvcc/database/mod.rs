@@ -5123,6 +5123,7 @@ impl<Clock: LogicalClock> MvStore<Clock> {
                     materialized_view_info,
                     dbsp_state_roots,
                     dbsp_state_index_roots,
+                    &syms,
                 )?;
                 Self::rehydrate_table_valued_functions(
                     &mut fresh,
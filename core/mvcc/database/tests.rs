@@ -6259,6 +6259,7 @@ fn test_checkpoint_index_writer_overwrites_existing_interior_key() {
         has_rowid: true,
         where_clause: None,
         index_method: None,
+        pk_suffix_start: None,
         on_conflict: None,
     };
 
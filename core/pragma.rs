@@ -59,6 +59,10 @@ pub fn pragma_for(pragma: &PragmaName) -> Pragma {
             PragmaFlags::NeedSchema | PragmaFlags::Result0 | PragmaFlags::SchemaReq,
             &["journal_mode"],
         ),
+        JournalSizeLimit => Pragma::new(
+            PragmaFlags::Result0 | PragmaFlags::SchemaReq,
+            &["journal_size_limit"],
+        ),
         LockingMode => Pragma::new(PragmaFlags::Result0, &["locking_mode"]),
         FullColumnNames | ShortColumnNames => {
             unreachable!("pragma_for() called with FullColumnNames/ShortColumnNames, which are deprecated no-ops")
@@ -136,6 +140,7 @@ pub fn pragma_for(pragma: &PragmaName) -> Pragma {
             PragmaFlags::NoColumns1 | PragmaFlags::Result0,
             &["user_version"],
         ),
+        WalAutocheckpoint => Pragma::new(PragmaFlags::Result0, &["wal_autocheckpoint"]),
         WalCheckpoint => Pragma::new(PragmaFlags::NeedSchema, &["busy", "log", "checkpointed"]),
         AutoVacuum => Pragma::new(
             PragmaFlags::NoColumns1 | PragmaFlags::Result0,
@@ -149,6 +154,7 @@ pub fn pragma_for(pragma: &PragmaName) -> Pragma {
             PragmaFlags::NeedSchema | PragmaFlags::ReadOnly | PragmaFlags::Result0,
             &["message"],
         ),
+        IncrementalVacuum => Pragma::new(PragmaFlags::NeedSchema | PragmaFlags::NoColumns, &[]),
         QuickCheck => Pragma::new(
             PragmaFlags::NeedSchema | PragmaFlags::ReadOnly | PragmaFlags::Result0,
             &["message"],
@@ -182,6 +188,17 @@ pub fn pragma_for(pragma: &PragmaName) -> Pragma {
             PragmaFlags::NoColumns1 | PragmaFlags::Result0,
             &["foreign_keys"],
         ),
+        DeferForeignKeys => Pragma::new(
+            PragmaFlags::NoColumns1 | PragmaFlags::Result0,
+            &["defer_foreign_keys"],
+        ),
+        ForeignKeyCheck => Pragma::new(
+            PragmaFlags::NeedSchema
+                | PragmaFlags::Result0
+                | PragmaFlags::Result1
+                | PragmaFlags::SchemaOpt,
+            &["table", "rowid", "parent", "fkid"],
+        ),
         ForeignKeyList => Pragma::new(
             PragmaFlags::NeedSchema | PragmaFlags::Result1 | PragmaFlags::SchemaOpt,
             &[
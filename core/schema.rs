@@ -144,7 +144,10 @@ use crate::storage::btree::{BTreeCursor, CursorTrait};
 use crate::sync::Arc;
 use crate::sync::Mutex;
 use crate::translate::collate::CollationSeq;
-use crate::translate::plan::{BitSet, ColumnMask, Plan, TableReferences};
+use crate::translate::plan::{
+    plan_has_outer_scope_dependency, select_plan_has_outer_scope_dependency, BitSet, ColumnMask,
+    Plan, RecursiveCte, SelectPlan, TableReferences,
+};
 use crate::util::{
     module_args_from_sql, module_name_from_sql, type_from_name, UnparsedFromSqlIndex,
 };
@@ -1381,6 +1384,7 @@ impl Schema {
                             acc.materialized_view_info,
                             acc.dbsp_state_roots,
                             acc.dbsp_state_index_roots,
+                            syms,
                         )?;
 
                         state.cursor = None;
@@ -1517,6 +1521,11 @@ impl Schema {
                     );
                     pk_index_added = true;
 
+                    if !table.has_rowid {
+                        // the table btree itself is keyed by the primary key, no index needed
+                        continue;
+                    }
+
                     if unique_set.columns.len() == 1 {
                         let col_name = &unique_set.columns.first().unwrap().0;
                         let Some((_, column)) = table.get_column(col_name) else {
@@ -1596,6 +1605,7 @@ impl Schema {
         materialized_view_info: HashMap<String, (String, i64)>,
         dbsp_state_roots: HashMap<String, i64>,
         dbsp_state_index_roots: HashMap<String, i64>,
+        syms: &SymbolTable,
     ) -> Result<()> {
         for (view_name, (sql, main_root)) in materialized_view_info {
             // Look up the DBSP state root for this view
@@ -1633,13 +1643,37 @@ impl Schema {
             }
 
             // Create the IncrementalView with all root pages
-            let incremental_view = IncrementalView::from_sql(
+            let incremental_view = match IncrementalView::from_sql(
                 &sql,
                 self,
+                syms,
                 main_root,
                 dbsp_state_root,
                 dbsp_state_index_root,
-            )?;
+            ) {
+                Ok(view) => view,
+                Err(LimboError::ExtensionError(msg)) => {
+                    // The view uses a function from an extension that is not loaded yet.
+                    // Keep it unusable, and its base tables read-only, until the extension
+                    // is loaded and the schema is parsed again.
+                    tracing::warn!(
+                        "Materialized view '{}' cannot be loaded: {}",
+                        view_name,
+                        msg
+                    );
+                    self.incompatible_views.insert(view_name.clone());
+                    for table_name in IncrementalView::referenced_table_names_from_sql(&sql, self)?
+                    {
+                        self.add_materialized_view_dependency(&table_name, &view_name);
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if dbsp_state_root > 0 {
+                // Loaded now, even if a previous parse was missing an extension
+                self.incompatible_views.remove(&view_name);
+            }
             let referenced_tables = incremental_view.get_referenced_table_names();
 
             // Create a BTreeTable for the materialized view
@@ -1722,7 +1756,9 @@ impl Schema {
                 } else {
                     let table = BTreeTable::from_sql(sql, root_page)?;
 
-                    if table.has_virtual_columns && !self.generated_columns_enabled {
+                    if table.columns.iter().any(|c| c.is_generated())
+                        && !self.generated_columns_enabled
+                    {
                         return Err(LimboError::ParseError(format!(
                             "table '{}' uses generated columns but the generated_columns feature is not enabled",
                             table.name
@@ -1841,8 +1877,6 @@ impl Schema {
                             select,
                             ..
                         } => {
-                            crate::util::validate_select_for_unsupported_features(&select)?;
-
                             // Extract actual columns from the SELECT statement
                             let view_column_schema =
                                 crate::util::extract_view_columns(&select, self)?;
@@ -1984,7 +2018,7 @@ impl Schema {
     /// Resolve a single FK declared on `child` referencing `parent_tbl`.
     /// When `require_unique` is set, a non-rowid parent key must be backed by
     /// a non-partial UNIQUE index on exactly those columns.
-    fn resolve_fk(
+    pub(crate) fn resolve_fk(
         &self,
         fk: &Arc<ForeignKey>,
         child: &Arc<BTreeTable>,
@@ -2059,14 +2093,15 @@ impl Schema {
                 .find(|idx| {
                     idx.unique
                         && idx.where_clause.is_none()
-                        && idx.columns.len() == parent_cols.len()
+                        && idx.key_columns().len() == parent_cols.len()
                         && idx
-                            .columns
+                            .key_columns()
                             .iter()
                             .zip(parent_cols.iter())
                             .all(|(ic, pc)| ic.name.eq_ignore_ascii_case(pc))
                 })
-                .cloned();
+                .cloned()
+                .or_else(|| Self::without_rowid_pk_as_index(parent_tbl, &parent_cols));
             if require_unique && found.is_none() {
                 return Err(fk_mismatch_err(&child.name, &parent_tbl.name));
             }
@@ -2085,6 +2120,36 @@ impl Schema {
         })
     }
 
+    /// WITHOUT ROWID tables have no separate autoindex for their PRIMARY KEY: the table btree
+    /// itself is keyed by it. Describe that btree as a unique index so FK probes can seek it
+    /// when the parent key is exactly the PRIMARY KEY.
+    fn without_rowid_pk_as_index(
+        parent_tbl: &BTreeTable,
+        parent_cols: &[String],
+    ) -> Option<Arc<Index>> {
+        if parent_tbl.has_rowid
+            || parent_tbl.primary_key_columns.len() != parent_cols.len()
+            || !parent_tbl
+                .primary_key_columns
+                .iter()
+                .zip(parent_cols.iter())
+                .all(|((pk, _), pc)| pk.eq_ignore_ascii_case(pc))
+        {
+            return None;
+        }
+        Index::automatic_from_primary_key(
+            parent_tbl,
+            (
+                format!("sqlite_autoindex_{}_1", parent_tbl.name),
+                parent_tbl.root_page,
+            ),
+            parent_cols.len(),
+            None,
+        )
+        .ok()
+        .map(Arc::new)
+    }
+
     /// Returns if any table declares a FOREIGN KEY whose parent is `table_name`.
     pub fn any_resolved_fks_referencing(&self, table_name: &str) -> bool {
         self.tables.values().any(|t| {
@@ -2545,9 +2610,9 @@ impl GeneratedColGraph {
         let mut direct_dependents = vec![ColumnMask::default(); n];
         let mut in_degree: Vec<u32> = vec![0; n];
 
-        // walk each virtual column's expression once to extract edges
+        // walk each generated column's expression once to extract edges
         for (j, col) in columns.iter().enumerate() {
-            let GeneratedType::Virtual { ref expr, .. } = col.generated_type() else {
+            let Some(expr) = col.generated_expr() else {
                 continue;
             };
             let mut direct = BitSet::default();
@@ -2879,6 +2944,10 @@ impl BTreeTable {
         self.has_virtual_columns
     }
 
+    pub fn has_stored_columns(&self) -> bool {
+        self.columns.iter().any(|c| c.is_stored_generated())
+    }
+
     /// Build a `ColumnLayout` for this table's register mapping.
     pub fn column_layout(&self) -> ColumnLayout {
         ColumnLayout::from_btree(self)
@@ -2953,10 +3022,18 @@ impl BTreeTable {
                 sql.push_str(&default.to_string());
             }
 
-            if let GeneratedType::Virtual { original_sql, .. } = &column.generated_type() {
-                sql.push_str(" AS (");
-                sql.push_str(original_sql);
-                sql.push(')');
+            match column.generated_type() {
+                GeneratedType::Virtual { original_sql, .. } => {
+                    sql.push_str(" AS (");
+                    sql.push_str(original_sql);
+                    sql.push(')');
+                }
+                GeneratedType::Stored { original_sql, .. } => {
+                    sql.push_str(" AS (");
+                    sql.push_str(original_sql);
+                    sql.push_str(") STORED");
+                }
+                GeneratedType::NotGenerated => {}
             }
 
             // Add column-level CHECK constraints inline
@@ -3103,6 +3180,14 @@ impl BTreeTable {
         self.logical_to_physical_map[logical]
     }
 
+    /// Columns in the order their values are laid out in the table's records
+    /// (PRIMARY KEY first for WITHOUT ROWID tables, virtual columns last).
+    pub fn columns_in_record_order(&self) -> Vec<&Column> {
+        let mut columns: Vec<_> = self.columns.iter().enumerate().collect();
+        columns.sort_by_key(|(logical, _)| self.logical_to_physical_map[*logical]);
+        columns.into_iter().map(|(_, column)| column).collect()
+    }
+
     pub fn build_logical_to_physical_map(
         columns: &[Column],
         primary_key_columns: &[(String, SortOrder)],
@@ -3149,7 +3234,7 @@ impl BTreeTable {
         {
             let mut guard = self.columns_mut();
             for i in 0..guard.len() {
-                if guard[i].is_virtual_generated() {
+                if guard[i].is_generated() {
                     let mut expr = guard[i].generated_expr().cloned().unwrap();
                     resolve_gencol_expr_columns(&mut expr, &guard)?;
                     *guard[i].generated_expr_mut().unwrap() = expr;
@@ -3164,7 +3249,7 @@ impl BTreeTable {
         &mut self,
         dropped_index: usize,
     ) -> Result<()> {
-        if !self.has_virtual_columns {
+        if !self.columns.iter().any(|c| c.is_generated()) {
             return Ok(());
         }
 
@@ -3315,6 +3400,13 @@ pub struct FromClauseSubquery {
     /// CTE-specific materialization metadata, when this FROM-subquery is a CTE
     /// reference rather than an inline derived table.
     pub cte: Option<FromClauseSubqueryCteMetadata>,
+    /// Set for `WITH RECURSIVE` CTEs; `plan` then only holds the anchor arms.
+    pub recursive: Option<Box<RecursiveCte>>,
+    /// Set for a parenthesized join group such as the `(b JOIN c ON ...)` in
+    /// `a LEFT JOIN (b JOIN c ON ...) ON ...`: for each column, the identifier
+    /// of the table inside the group it came from, so that `b.x` still resolves
+    /// from outside the group.
+    pub nested_from: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -3333,6 +3425,25 @@ impl FromClauseSubquery {
         self.cte.map(|cte| cte.id)
     }
 
+    /// Whether `table` names one of the tables inside this parenthesized join group.
+    pub fn nested_from_contains(&self, table: &str) -> bool {
+        self.nested_from
+            .as_ref()
+            .is_some_and(|tables| tables.iter().any(|t| t == table))
+    }
+
+    /// Index of the column `table.column` inside this parenthesized join group, if any.
+    pub fn nested_from_column(&self, table: &str, column: &str) -> Option<usize> {
+        let tables = self.nested_from.as_ref()?;
+        self.columns.iter().zip(tables).position(|(col, t)| {
+            t == table
+                && col
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(column))
+        })
+    }
+
     pub fn materialize_hint(&self) -> bool {
         self.cte.is_some_and(|cte| cte.materialize_hint)
     }
@@ -3358,7 +3469,30 @@ impl FromClauseSubquery {
     /// subqueries still need table-backed storage so their set-operation
     /// semantics are preserved before any later SEARCH shape is chosen.
     pub fn supports_direct_index_materialization(&self) -> bool {
-        matches!(self.plan.as_ref(), Plan::Select(_)) && !self.requires_table_materialization()
+        matches!(self.plan.as_ref(), Plan::Select(_))
+            && !self.requires_table_materialization()
+            && !self.is_recursive()
+    }
+
+    pub fn is_recursive(&self) -> bool {
+        self.recursive.is_some()
+    }
+
+    /// Whether evaluating this subquery, including the recursive arms of a
+    /// `WITH RECURSIVE` CTE, depends on values from an enclosing scope.
+    pub fn has_outer_scope_dependency(&self) -> bool {
+        plan_has_outer_scope_dependency(&self.plan)
+            || self
+                .recursive_selects()
+                .iter()
+                .any(select_plan_has_outer_scope_dependency)
+    }
+
+    /// The recursive arms of a `WITH RECURSIVE` CTE; empty for any other subquery.
+    pub fn recursive_selects(&self) -> &[SelectPlan] {
+        self.recursive
+            .as_ref()
+            .map_or(&[], |recursive| recursive.selects.as_slice())
     }
 }
 
@@ -3838,6 +3972,7 @@ pub fn create_table(tbl_name: &str, body: &CreateTableBody, root_page: i64) -> R
 
                 let mut default = None;
                 let mut generated: Option<Box<Expr>> = None;
+                let mut stored = false;
                 let mut primary_key = false;
                 let mut notnull = false;
                 let mut explicit_notnull = false;
@@ -3855,14 +3990,9 @@ pub fn create_table(tbl_name: &str, body: &CreateTableBody, root_page: i64) -> R
                             ));
                         }
                         ast::ColumnConstraint::Generated { expr, typ } => {
-                            if typ
-                                .as_ref()
-                                .is_some_and(|t| matches!(t, ast::GeneratedColumnType::Stored))
-                            {
-                                bail_parse_error!("Stored generated columns are not supported");
-                            }
                             validate_generated_expr(expr)?;
                             generated = Some(expr.clone());
+                            stored = matches!(typ, Some(ast::GeneratedColumnType::Stored));
                         }
                         ast::ColumnConstraint::PrimaryKey {
                             order: o,
@@ -4035,6 +4165,9 @@ pub fn create_table(tbl_name: &str, body: &CreateTableBody, root_page: i64) -> R
                     },
                 );
                 col.ty_params = ty_params;
+                if stored {
+                    col.set_generated_stored();
+                }
                 if let Some(t) = col_type.as_ref() {
                     if t.is_array() {
                         col.set_array_dimensions(t.array_dimensions);
@@ -4364,7 +4497,12 @@ pub enum GeneratedType {
         expr: Box<Expr>,
         original_sql: String,
     },
-    // Stored { resolved: Box<Expr>, original_sql: String },
+    /// Same as `Virtual`, but the value is computed on INSERT/UPDATE and
+    /// persisted in the record like an ordinary column.
+    Stored {
+        expr: Box<Expr>,
+        original_sql: String,
+    },
     NotGenerated,
 }
 
@@ -4593,10 +4731,30 @@ impl Column {
         matches!(self.generated_type, GeneratedType::Virtual { .. })
     }
 
+    #[inline]
+    pub const fn is_stored_generated(&self) -> bool {
+        matches!(self.generated_type, GeneratedType::Stored { .. })
+    }
+
+    /// Turns a virtual generated column into a STORED one. No-op for
+    /// columns that are not generated.
+    pub fn set_generated_stored(&mut self) {
+        let generated_type =
+            std::mem::replace(&mut self.generated_type, GeneratedType::NotGenerated);
+        self.generated_type = match generated_type {
+            GeneratedType::Virtual { expr, original_sql } => {
+                GeneratedType::Stored { expr, original_sql }
+            }
+            other => other,
+        };
+    }
+
     #[inline]
     pub fn generated_expr(&self) -> Option<&Expr> {
         match &self.generated_type {
-            GeneratedType::Virtual { expr, .. } => Some(expr.as_ref()),
+            GeneratedType::Virtual { expr, .. } | GeneratedType::Stored { expr, .. } => {
+                Some(expr.as_ref())
+            }
             GeneratedType::NotGenerated => None,
         }
     }
@@ -4604,7 +4762,9 @@ impl Column {
     #[inline]
     pub fn generated_expr_mut(&mut self) -> Option<&mut Expr> {
         match &mut self.generated_type {
-            GeneratedType::Virtual { expr, .. } => Some(expr.as_mut()),
+            GeneratedType::Virtual { expr, .. } | GeneratedType::Stored { expr, .. } => {
+                Some(expr.as_mut())
+            }
             GeneratedType::NotGenerated => None,
         }
     }
@@ -4614,6 +4774,10 @@ impl Column {
         if let GeneratedType::Virtual {
             ref mut original_sql,
             ..
+        }
+        | GeneratedType::Stored {
+            ref mut original_sql,
+            ..
         } = self.generated_type
         {
             *original_sql = new_sql;
@@ -4677,6 +4841,7 @@ impl TryFrom<&ColumnDefinition> for Column {
 
         let mut default = None;
         let mut generated = None;
+        let mut stored = false;
         let mut notnull = false;
         let mut notnull_conflict_clause = None;
         let mut primary_key = false;
@@ -4701,8 +4866,9 @@ impl TryFrom<&ColumnDefinition> for Column {
                 ast::ColumnConstraint::Collate { collation_name } => {
                     collation.replace(CollationSeq::new(collation_name.as_str())?);
                 }
-                ast::ColumnConstraint::Generated { expr, .. } => {
+                ast::ColumnConstraint::Generated { expr, typ } => {
                     generated = Some(expr.clone());
+                    stored = matches!(typ, Some(ast::GeneratedColumnType::Stored));
                 }
                 _ => {}
             };
@@ -4751,6 +4917,9 @@ impl TryFrom<&ColumnDefinition> for Column {
             },
         );
         col.ty_params = ty_params;
+        if stored {
+            col.set_generated_stored();
+        }
         if let Some(t) = value.col_type.as_ref() {
             if t.is_array() {
                 col.set_array_dimensions(t.array_dimensions);
@@ -4844,6 +5013,10 @@ pub struct Index {
     pub has_rowid: bool,
     pub where_clause: Option<Box<Expr>>,
     pub index_method: Option<Arc<dyn IndexMethodAttachment>>,
+    /// Indexes of WITHOUT ROWID tables identify their rows by the table's PRIMARY KEY instead
+    /// of a rowid: the PRIMARY KEY columns that are not already part of the key are appended
+    /// to `columns`, starting at this position. `None` for indexes of rowid tables.
+    pub pk_suffix_start: Option<usize>,
     /// ON CONFLICT clause from the constraint definition (PRIMARY KEY or UNIQUE).
     pub on_conflict: Option<ResolveType>,
 }
@@ -4888,6 +5061,11 @@ impl Index {
                 let index_name = normalize_ident(idx_name.name.as_str());
                 let index_columns = resolve_sorted_columns(table, &columns)?;
                 if let Some(using) = using {
+                    if !table.has_rowid {
+                        bail_parse_error!(
+                            "custom index modules are not supported on WITHOUT ROWID tables"
+                        );
+                    }
                     if where_clause.is_some() {
                         bail_parse_error!("custom index module do not support partial indices");
                     }
@@ -4915,10 +5093,11 @@ impl Index {
                         has_rowid: table.has_rowid,
                         where_clause: None,
                         index_method: Some(descriptor),
+                        pk_suffix_start: None,
                         on_conflict: None,
                     })
                 } else {
-                    Ok(Index {
+                    let mut index = Index {
                         name: index_name,
                         table_name: normalize_ident(tbl_name.as_str()),
                         root_page,
@@ -4928,8 +5107,11 @@ impl Index {
                         has_rowid: table.has_rowid,
                         where_clause,
                         index_method: None,
+                        pk_suffix_start: None,
                         on_conflict: None,
-                    })
+                    };
+                    index.append_pk_suffix(table)?;
+                    Ok(index)
                 }
             }
             _ => todo!("Expected create index statement"),
@@ -4990,6 +5172,7 @@ impl Index {
             has_rowid: table.has_rowid,
             where_clause: None,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: conflict_clause,
         })
     }
@@ -5025,7 +5208,7 @@ impl Index {
             });
         }
 
-        Ok(Index {
+        let mut index = Index {
             name: normalize_ident(index_name.as_str()),
             table_name: table.name.clone(),
             root_page,
@@ -5035,8 +5218,72 @@ impl Index {
             has_rowid: table.has_rowid,
             where_clause: None,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: conflict_clause,
-        })
+        };
+        index.append_pk_suffix(table)?;
+        Ok(index)
+    }
+
+    /// For an index of a WITHOUT ROWID table, append the PRIMARY KEY columns that are not already
+    /// part of the key, like SQLite does. Index entries then identify their row, the way the
+    /// rowid does for tables that have one.
+    pub fn append_pk_suffix(&mut self, table: &BTreeTable) -> Result<()> {
+        if table.has_rowid {
+            return Ok(());
+        }
+        let key_len = self.columns.len();
+        for (pk_name, order) in &table.primary_key_columns {
+            let Some((pos_in_table, column)) = table.get_column(pk_name) else {
+                return Err(crate::LimboError::ParseError(format!(
+                    "Column {} not found in table {}",
+                    pk_name, table.name
+                )));
+            };
+            let collation = column.collation_opt();
+            let already_in_key = self.columns[..key_len].iter().any(|c| {
+                c.expr.is_none()
+                    && c.pos_in_table == pos_in_table
+                    && c.collation.unwrap_or_default() == collation.unwrap_or_default()
+            });
+            if already_in_key {
+                continue;
+            }
+            self.columns.push(IndexColumn {
+                name: normalize_ident(pk_name),
+                order: *order,
+                pos_in_table,
+                collation,
+                default: column.default.clone(),
+                expr: None,
+            });
+        }
+        self.pk_suffix_start = Some(key_len);
+        Ok(())
+    }
+
+    /// The columns the index was declared on, without the PRIMARY KEY columns appended to the
+    /// indexes of WITHOUT ROWID tables. Uniqueness is enforced over these columns.
+    pub fn key_columns(&self) -> &[IndexColumn] {
+        &self.columns[..self.pk_suffix_start.unwrap_or(self.columns.len())]
+    }
+
+    /// Positions in `columns` of the PRIMARY KEY columns of a WITHOUT ROWID table, in PRIMARY
+    /// KEY order. Reading them from an index entry gives the key to seek the table row with.
+    pub fn pk_positions(&self, table: &BTreeTable) -> Vec<usize> {
+        table
+            .primary_key_columns
+            .iter()
+            .map(|(pk_name, _)| {
+                let (pos_in_table, _) = table
+                    .get_column(pk_name)
+                    .expect("PRIMARY KEY column should exist");
+                self.columns
+                    .iter()
+                    .position(|c| c.expr.is_none() && c.pos_in_table == pos_in_table)
+                    .expect("indexes of WITHOUT ROWID tables contain the PRIMARY KEY columns")
+            })
+            .collect()
     }
 
     /// Given a column position in the table, return the position in the index.
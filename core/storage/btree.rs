@@ -184,6 +184,14 @@ enum DeleteState {
         original_child_pointer: Option<u32>,
         post_balancing_seek_key: Option<CursorContext>,
     },
+    /// Record the interior page as the parent of the overflow chain of the cell that
+    /// replaced the deleted one.
+    UpdateMovedCellPtrmap {
+        first_overflow_page: u32,
+        interior_page_id: u32,
+        btree_depth: usize,
+        post_balancing_seek_key: Option<CursorContext>,
+    },
     CheckNeedsBalancing {
         /// same as `InteriorNodeReplacement::btree_depth`
         btree_depth: usize,
@@ -254,8 +262,14 @@ enum BalanceSubState {
     #[default]
     Start,
     BalanceRoot,
+    /// Record the child created by [BTreeCursor::balance_root] in the pointer map.
+    BalanceRootUpdatePtrmap,
     Decide,
     Quick,
+    /// Record the leaf created by [BTreeCursor::balance_quick] in the pointer map.
+    QuickUpdatePtrmap {
+        new_rightmost_leaf: PageRef,
+    },
     /// Choose which sibling pages to balance (max 3).
     /// Generally, the siblings involved will be the page that triggered the balancing and its left and right siblings.
     /// The exceptions are:
@@ -272,6 +286,15 @@ enum BalanceSubState {
     NonRootDoBalancingFinish {
         context: BalanceContext,
     },
+    /// Keep the pointer map in sync with the balanced pages: every new sibling hangs off the
+    /// parent, and cells that moved between pages take their children and overflow chains
+    /// with them.
+    NonRootUpdatePtrmap {
+        new_pages: Vec<PinGuard>,
+        next: usize,
+        /// Whether the parent's own cells changed children or overflow chains too.
+        update_parent_children: bool,
+    },
     /// Free pages that are not used anymore after balancing.
     FreePages {
         curr_page: usize,
@@ -2534,6 +2557,18 @@ impl BTreeCursor {
                     return_if_io!(self.balance_root());
 
                     let BalanceState { sub_state, .. } = &mut self.balance_state;
+                    *sub_state = BalanceSubState::BalanceRootUpdatePtrmap;
+                }
+                BalanceSubState::BalanceRootUpdatePtrmap => {
+                    // balance_root() left the new child on top of the stack, below the root.
+                    let child = self.stack.top_ref();
+                    let root = self
+                        .stack
+                        .get_page_at_level(self.stack.current() - 1)
+                        .expect("root page should be on the stack");
+                    return_if_io!(self
+                        .pager
+                        .ptrmap_put_btree_page(child, root.get().id as u32));
                     *sub_state = BalanceSubState::Decide;
                 }
                 BalanceSubState::Decide => {
@@ -2585,10 +2620,19 @@ impl BTreeCursor {
                 BalanceSubState::Quick => {
                     return_if_io!(self.balance_quick());
                 }
+                BalanceSubState::QuickUpdatePtrmap { new_rightmost_leaf } => {
+                    // balance_quick() already moved up to the parent of the new leaf.
+                    let parent = self.stack.top_ref();
+                    return_if_io!(self
+                        .pager
+                        .ptrmap_put_btree_page(new_rightmost_leaf, parent.get().id as u32));
+                    *sub_state = BalanceSubState::Start;
+                }
                 BalanceSubState::NonRootPickSiblings
                 | BalanceSubState::NonRootDoBalancing
                 | BalanceSubState::NonRootDoBalancingAllocate { .. }
                 | BalanceSubState::NonRootDoBalancingFinish { .. }
+                | BalanceSubState::NonRootUpdatePtrmap { .. }
                 | BalanceSubState::FreePages { .. } => {
                     return_if_io!(self.balance_non_root());
                 }
@@ -2676,7 +2720,7 @@ impl BTreeCursor {
         self.stack.pop();
 
         let BalanceState { sub_state, .. } = &mut self.balance_state;
-        *sub_state = BalanceSubState::Start;
+        *sub_state = BalanceSubState::QuickUpdatePtrmap { new_rightmost_leaf };
         Ok(IOResult::Done(()))
     }
 
@@ -2696,8 +2740,10 @@ impl BTreeCursor {
             match sub_state {
                 BalanceSubState::Start
                 | BalanceSubState::BalanceRoot
+                | BalanceSubState::BalanceRootUpdatePtrmap
                 | BalanceSubState::Decide
-                | BalanceSubState::Quick => {
+                | BalanceSubState::Quick
+                | BalanceSubState::QuickUpdatePtrmap { .. } => {
                     panic!("balance_non_root: unexpected state {sub_state:?}")
                 }
                 BalanceSubState::NonRootPickSiblings => {
@@ -3623,7 +3669,6 @@ impl BTreeCursor {
                         parent_contents.overflow_cells.is_empty(),
                         "parent page overflow cells should be empty before divider cell reinsertion"
                     );
-                    // TODO: pointer map update (vacuum support)
                     // Update divider cells in parent
                     // Cache first_divider_cell to allow mutable access to reusable_divider_cell
                     let first_divider_cell_cached = balance_info.first_divider_cell;
@@ -3853,7 +3898,6 @@ impl BTreeCursor {
                         }
                     }
 
-                    // TODO: vacuum support
                     let first_child_page = pages_to_balance_new[0].as_ref().unwrap();
                     let first_child_contents = first_child_page.get_contents();
                     if parent_is_root
@@ -3934,6 +3978,44 @@ impl BTreeCursor {
                     recovered_vec.clear();
                     *reusable_cell_payloads = recovered_vec;
 
+                    *sub_state = if self.pager.ptrmap_enabled() {
+                        // After balance-shallower the parent took over the cells of its only
+                        // child. Index divider cells may carry overflow chains into the parent.
+                        BalanceSubState::NonRootUpdatePtrmap {
+                            new_pages: pages_to_balance_new
+                                .iter()
+                                .take(sibling_count_new)
+                                .map(|page| page.as_ref().unwrap().clone())
+                                .collect(),
+                            next: 0,
+                            update_parent_children: sibling_count_new == 0
+                                || matches!(
+                                    page_type,
+                                    PageType::IndexLeaf | PageType::IndexInterior
+                                ),
+                        }
+                    } else {
+                        BalanceSubState::FreePages {
+                            curr_page: sibling_count_new,
+                            sibling_count_new,
+                        }
+                    };
+                }
+                BalanceSubState::NonRootUpdatePtrmap {
+                    new_pages,
+                    next,
+                    update_parent_children,
+                } => {
+                    let parent_page = self.stack.top_ref();
+                    let parent_page_id = parent_page.get().id as u32;
+                    while let Some(page) = new_pages.get(*next) {
+                        return_if_io!(self.pager.ptrmap_put_btree_page(page, parent_page_id));
+                        *next += 1;
+                    }
+                    if *update_parent_children {
+                        return_if_io!(self.pager.ptrmap_put_children(parent_page));
+                    }
+                    let sibling_count_new = new_pages.len();
                     *sub_state = BalanceSubState::FreePages {
                         curr_page: sibling_count_new,
                         sibling_count_new,
@@ -5365,7 +5447,7 @@ impl CursorTrait for BTreeCursor {
                         .get_mut(btree_depth)
                         .expect("parent page should be on the stack")
                         .cell_idx = cell_idx as i32;
-                    let (cell_payload, leaf_cell_idx) = {
+                    let (cell_payload, leaf_cell_idx, first_overflow_page) = {
                         let leaf_page = self.stack.top_ref();
                         let leaf_contents = leaf_page.get_contents();
                         turso_assert!(leaf_contents.is_leaf());
@@ -5375,6 +5457,7 @@ impl CursorTrait for BTreeCursor {
                             leaf_contents.cell_get(leaf_cell_idx, usable_space)?;
 
                         let mut cell_payload: Vec<u8> = Vec::new();
+                        let mut first_overflow_page = None;
                         let child_pointer =
                             original_child_pointer.expect("there should be a pointer");
                         // Rewrite the old leaf cell as an interior cell depending on type.
@@ -5397,10 +5480,11 @@ impl CursorTrait for BTreeCursor {
                                     cell_payload
                                         .extend_from_slice(&first_overflow_page.to_be_bytes());
                                 }
+                                first_overflow_page = leaf_cell.first_overflow_page;
                             }
                             _ => unreachable!("Expected table leaf cell"),
                         }
-                        (cell_payload, leaf_cell_idx)
+                        (cell_payload, leaf_cell_idx, first_overflow_page)
                     };
 
                     let leaf_page = self.stack.top_ref();
@@ -5433,8 +5517,37 @@ impl CursorTrait for BTreeCursor {
                         drop_cell(leaf_contents, leaf_cell_idx, usable_space)?;
                     }
 
+                    self.state = match first_overflow_page {
+                        Some(first_overflow_page) => {
+                            CursorState::Delete(DeleteState::UpdateMovedCellPtrmap {
+                                first_overflow_page,
+                                interior_page_id: page.get().id as u32,
+                                btree_depth,
+                                post_balancing_seek_key: post_balancing_seek_key.take(),
+                            })
+                        }
+                        None => CursorState::Delete(DeleteState::CheckNeedsBalancing {
+                            btree_depth,
+                            post_balancing_seek_key: post_balancing_seek_key.take(),
+                            interior_node_was_replaced: true,
+                        }),
+                    };
+                }
+
+                DeleteState::UpdateMovedCellPtrmap {
+                    first_overflow_page,
+                    interior_page_id,
+                    btree_depth,
+                    post_balancing_seek_key,
+                } => {
+                    // The overflow chain of the moved cell now hangs off the interior page.
+                    return_if_io!(self.pager.ptrmap_put_overflow_page(
+                        *first_overflow_page,
+                        *interior_page_id,
+                        true,
+                    ));
                     self.state = CursorState::Delete(DeleteState::CheckNeedsBalancing {
-                        btree_depth,
+                        btree_depth: *btree_depth,
                         post_balancing_seek_key: post_balancing_seek_key.take(),
                         interior_node_was_replaced: true,
                     });
@@ -8088,6 +8201,11 @@ pub enum CopyDataState {
     Copy,
     /// Allocate a new overflow page if we couldn't fit all data to the current page.
     AllocateOverflowPage,
+    /// Record the parent of the newly allocated overflow page in the pointer map.
+    UpdatePtrmap {
+        parent_page_id: u32,
+        first_in_chain: bool,
+    },
 }
 
 /// Fill in the cell payload with the record.
@@ -8209,6 +8327,10 @@ fn fill_cell_payload(
                             "new overflow page is not loaded"
                         );
                         let new_overflow_page_id = new_overflow_page.get().id as u32;
+                        let (parent_page_id, first_in_chain) = match current_overflow_page {
+                            Some(prev_page) => (prev_page.get().id as u32, false),
+                            None => (page.get().id as u32, true),
+                        };
 
                         if let Some(prev_page) = current_overflow_page {
                             // Update the previous overflow page's "next overflow page" pointer to point to the new overflow page.
@@ -8231,6 +8353,30 @@ fn fill_cell_payload(
                         *dst_data_offset = overflow_page_pointer_size;
                         *space_left_on_cur_page = overflow_page_data_size;
                         *current_overflow_page = Some(new_overflow_page.clone());
+                        *state = CopyDataState::UpdatePtrmap {
+                            parent_page_id,
+                            first_in_chain,
+                        };
+                    }
+                    CopyDataState::UpdatePtrmap {
+                        parent_page_id,
+                        first_in_chain,
+                    } => {
+                        let new_overflow_page = current_overflow_page
+                            .as_ref()
+                            .expect("overflow page should have been allocated");
+                        match pager.ptrmap_put_overflow_page(
+                            new_overflow_page.get().id as u32,
+                            *parent_page_id,
+                            *first_in_chain,
+                        ) {
+                            Ok(IOResult::Done(())) => {}
+                            Ok(IOResult::IO(io_result)) => return Ok(IOResult::IO(io_result)),
+                            Err(e) => {
+                                mark_unlikely();
+                                break Err(e);
+                            }
+                        }
                         *state = CopyDataState::Copy;
                     }
                 }
@@ -9105,6 +9251,7 @@ mod tests {
                 ephemeral: false,
                 has_rowid: false,
                 index_method: None,
+                pk_suffix_start: None,
                 on_conflict: None,
             };
             let num_columns = index_def.columns.len();
@@ -9280,6 +9427,7 @@ mod tests {
                 ephemeral: false,
                 has_rowid: false,
                 index_method: None,
+                pk_suffix_start: None,
                 on_conflict: None,
             };
             let mut cursor = BTreeCursor::new_index(pager.clone(), index_root_page, &index_def, 1);
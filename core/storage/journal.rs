@@ -0,0 +1,653 @@
+//! Rollback journal for databases in the legacy (version 1) file format.
+//!
+//! In the rollback journal modes (DELETE, TRUNCATE, PERSIST, MEMORY and OFF) a
+//! commit writes the new page images straight into the database file. Before
+//! doing so, the original content of every page that is about to be
+//! overwritten is saved in the `<db>-journal` file. If the process dies halfway
+//! through a commit, the next open finds a *hot* journal and plays it back,
+//! which restores the database to the state before the interrupted
+//! transaction.
+//!
+//! The journal format is byte-compatible with SQLite, so files can move back and
+//! forth between the two engines:
+//!
+//! ```text
+//! header (padded to the sector size):
+//!   0   8  magic: d9 d5 05 f9 20 a1 63 d7
+//!   8   4  number of page records, 0xFFFFFFFF means "until the end of the file"
+//!   12  4  checksum nonce
+//!   16  4  size of the database in pages before the transaction started
+//!   20  4  sector size
+//!   24  4  page size
+//! page record:
+//!   0   4  page number
+//!   4   N  original page content
+//!   4+N 4  checksum
+//! ```
+//!
+//! A journal may consist of several segments, each starting with its own header
+//! at a sector boundary. All integers are big-endian.
+//!
+//! Locking is process-local: database files are opened with an exclusive file
+//! lock, so the SHARED / RESERVED / PENDING / EXCLUSIVE states below only
+//! arbitrate between the connections of a single [crate::Database].
+
+use crate::io::{CompletionGroup, File, FileSyncType};
+use crate::storage::database::{DatabaseStorage, IOContext};
+use crate::storage::journal_mode::{AtomicJournalMode, JournalMode};
+use crate::storage::pager::PageRef;
+use crate::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::{Arc, Mutex, RwLock};
+use crate::{turso_assert, Buffer, Completion, CompletionError, LimboError, OpenFlags, Result, IO};
+use turso_macros::AtomicEnum;
+
+/// Magic number at the start of every journal header.
+pub const JOURNAL_MAGIC: [u8; 8] = [0xd9, 0xd5, 0x05, 0xf9, 0x20, 0xa1, 0x63, 0xd7];
+/// Number of meaningful bytes in a journal header.
+pub const JOURNAL_HEADER_SIZE: usize = 28;
+/// Sector size written into journal headers. Headers are padded to this size.
+pub const JOURNAL_SECTOR_SIZE: usize = 512;
+/// Record count meaning "the records extend to the end of the file".
+pub const JOURNAL_NREC_UNTIL_EOF: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalHeader {
+    /// Number of page records in this segment.
+    pub n_rec: u32,
+    /// Initial value of the page record checksums.
+    pub nonce: u32,
+    /// Size of the database in pages before the transaction started.
+    pub initial_db_size: u32,
+    pub sector_size: u32,
+    pub page_size: u32,
+}
+
+impl JournalHeader {
+    pub fn write_to(&self, buf: &mut [u8]) {
+        buf[0..8].copy_from_slice(&JOURNAL_MAGIC);
+        buf[8..12].copy_from_slice(&self.n_rec.to_be_bytes());
+        buf[12..16].copy_from_slice(&self.nonce.to_be_bytes());
+        buf[16..20].copy_from_slice(&self.initial_db_size.to_be_bytes());
+        buf[20..24].copy_from_slice(&self.sector_size.to_be_bytes());
+        buf[24..28].copy_from_slice(&self.page_size.to_be_bytes());
+    }
+
+    /// Parses a header, returning `None` if the magic does not match (e.g. the
+    /// header was zeroed by a PERSIST-mode commit).
+    pub fn read_from(buf: &[u8]) -> Option<Self> {
+        if buf.len() < JOURNAL_HEADER_SIZE || buf[0..8] != JOURNAL_MAGIC {
+            return None;
+        }
+        Some(Self {
+            n_rec: read_u32(buf, 8),
+            nonce: read_u32(buf, 12),
+            initial_db_size: read_u32(buf, 16),
+            sector_size: read_u32(buf, 20),
+            page_size: read_u32(buf, 24),
+        })
+    }
+
+    fn is_valid(&self) -> bool {
+        let page_size = self.page_size;
+        let sector_size = self.sector_size;
+        (512..=65536).contains(&page_size)
+            && page_size.is_power_of_two()
+            && (32..=65536).contains(&sector_size)
+            && sector_size.is_power_of_two()
+    }
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(
+        buf[offset..offset + 4]
+            .try_into()
+            .expect("slice is four bytes long"),
+    )
+}
+
+/// Checksum of a page record: the nonce plus every 200th byte of the page,
+/// walking backwards from `page_size - 200`.
+pub fn journal_checksum(nonce: u32, page: &[u8]) -> u32 {
+    let mut checksum = nonce;
+    let mut i = page.len() as isize - 200;
+    while i > 0 {
+        checksum = checksum.wrapping_add(page[i as usize] as u32);
+        i -= 200;
+    }
+    checksum
+}
+
+/// Encodes a single-segment journal holding `pages` (page number, original
+/// content), with the header padded to [JOURNAL_SECTOR_SIZE].
+pub fn encode_journal(
+    n_rec: u32,
+    nonce: u32,
+    initial_db_size: u32,
+    page_size: usize,
+    pages: &[(u32, Arc<Buffer>)],
+) -> Vec<u8> {
+    let record_size = page_size + 8;
+    let mut out = vec![0u8; JOURNAL_SECTOR_SIZE + pages.len() * record_size];
+    JournalHeader {
+        n_rec,
+        nonce,
+        initial_db_size,
+        sector_size: JOURNAL_SECTOR_SIZE as u32,
+        page_size: page_size as u32,
+    }
+    .write_to(&mut out);
+    for (i, (page_no, content)) in pages.iter().enumerate() {
+        let start = JOURNAL_SECTOR_SIZE + i * record_size;
+        let content = content.as_slice();
+        out[start..start + 4].copy_from_slice(&page_no.to_be_bytes());
+        out[start + 4..start + 4 + page_size].copy_from_slice(content);
+        out[start + 4 + page_size..start + record_size]
+            .copy_from_slice(&journal_checksum(nonce, content).to_be_bytes());
+    }
+    out
+}
+
+/// An [IOContext] that reads and writes pages as raw bytes. Journal records
+/// hold the on-disk image of a page, whatever encryption or checksums it uses.
+pub fn raw_io_context() -> IOContext {
+    let mut io_ctx = IOContext::default();
+    io_ctx.reset_checksum();
+    io_ctx
+}
+
+pub fn file_exists(io: &Arc<dyn IO>, path: &str) -> Result<bool> {
+    match io.open_file(path, OpenFlags::ReadOnly | OpenFlags::NoLock, false) {
+        Ok(_) => Ok(true),
+        Err(LimboError::CompletionError(CompletionError::IOError(
+            std::io::ErrorKind::NotFound,
+            _,
+        ))) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Lock held by a single pager on the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtomicEnum)]
+pub enum JournalLock {
+    None,
+    /// Reading.
+    Shared,
+    /// Reading, and planning to write. Only one pager holds it at a time.
+    Reserved,
+    /// Writing to the database file. No other pager may be reading.
+    Exclusive,
+}
+
+#[derive(Debug, Default)]
+struct LockTable {
+    readers: usize,
+    reserved: bool,
+    /// A writer is waiting for readers to drain; new readers are turned away.
+    pending: bool,
+    exclusive: bool,
+}
+
+/// Rollback journal state shared by every connection of a database.
+pub struct RollbackJournalShared {
+    path: String,
+    mode: AtomicJournalMode,
+    locks: Mutex<LockTable>,
+    /// Bumped by every commit so that other connections drop their page cache.
+    change_counter: AtomicU64,
+    file: Mutex<Option<Arc<dyn File>>>,
+}
+
+impl RollbackJournalShared {
+    pub fn new(path: String, mode: JournalMode) -> Self {
+        turso_assert!(
+            mode.is_rollback(),
+            "rollback journal requires a rollback journal mode"
+        );
+        Self {
+            path,
+            mode: AtomicJournalMode::new(mode),
+            locks: Mutex::new(LockTable::default()),
+            change_counter: AtomicU64::new(0),
+            file: Mutex::new(None),
+        }
+    }
+
+    pub fn mode(&self) -> JournalMode {
+        self.mode.get()
+    }
+
+    /// Switches between rollback journal modes. Leaving TRUNCATE or PERSIST for
+    /// DELETE removes the journal file they kept around.
+    pub fn set_mode(&self, io: &Arc<dyn IO>, mode: JournalMode) -> Result<()> {
+        turso_assert!(
+            mode.is_rollback(),
+            "rollback journal requires a rollback journal mode"
+        );
+        let prev = self.mode.swap(mode);
+        if prev != mode && mode == JournalMode::Delete {
+            self.delete_file(io)?;
+        }
+        Ok(())
+    }
+
+    fn open_file(&self, io: &Arc<dyn IO>) -> Result<Arc<dyn File>> {
+        let mut file = self.file.lock();
+        if let Some(file) = file.as_ref() {
+            return Ok(file.clone());
+        }
+        let opened = io.open_file(&self.path, OpenFlags::Create | OpenFlags::NoLock, false)?;
+        *file = Some(opened.clone());
+        Ok(opened)
+    }
+
+    fn delete_file(&self, io: &Arc<dyn IO>) -> Result<()> {
+        self.file.lock().take();
+        if file_exists(io, &self.path)? {
+            io.remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Phase of a rollback-journal commit, see [crate::storage::pager::Pager::commit_dirty_pages].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum JournalCommitPhase {
+    #[default]
+    Start,
+    /// Reading the original content of the pages about to be overwritten.
+    ReadOriginals,
+    WriteJournal,
+    /// FULL sync: the records are synced before the record count is written.
+    SyncJournalRecords,
+    WriteRecordCount,
+    SyncJournal,
+    WriteDb,
+    TruncateDb,
+    SyncDb,
+    /// Deleting, truncating or zeroing the journal, which ends the transaction.
+    FinalizeJournal,
+    SyncFinalizedJournal,
+    Done,
+}
+
+#[derive(Default)]
+pub(crate) struct JournalCommit {
+    pub phase: JournalCommitPhase,
+    /// Size of the database file in pages when the commit started.
+    pub initial_db_size: u32,
+    /// Original content of the journaled pages.
+    pub originals: Vec<(u32, Arc<Buffer>)>,
+    /// Dirty pages being written to the database file.
+    pub pages: Vec<PageRef>,
+    pub completions: Vec<Completion>,
+    /// Set once the database file may have been modified. A failure from then
+    /// on must restore the original pages before the locks are dropped.
+    pub db_written: bool,
+}
+
+impl JournalCommit {
+    /// Returns a completion to wait on while I/O of the current phase is in
+    /// flight, or the error of the first one that failed.
+    pub fn poll(&mut self) -> Result<Option<Completion>> {
+        if let Some(pending) = self.completions.iter().find(|c| !c.finished()) {
+            return Ok(Some(pending.clone()));
+        }
+        if let Some(failed) = self.completions.iter().find(|c| !c.succeeded()) {
+            let err = failed.get_error().unwrap_or(CompletionError::IOError(
+                std::io::ErrorKind::Other,
+                "journal",
+            ));
+            return Err(LimboError::CompletionError(err));
+        }
+        self.completions.clear();
+        Ok(None)
+    }
+}
+
+/// A pager's handle on the rollback journal of its database.
+pub struct RollbackJournal {
+    shared: Arc<RollbackJournalShared>,
+    lock: AtomicJournalLock,
+    /// Value of the shared change counter when this pager last read.
+    change_counter: AtomicU64,
+    pub(crate) commit: RwLock<JournalCommit>,
+}
+
+impl RollbackJournal {
+    pub fn new(shared: Arc<RollbackJournalShared>) -> Self {
+        let change_counter = shared.change_counter.load(Ordering::SeqCst);
+        Self {
+            shared,
+            lock: AtomicJournalLock::new(JournalLock::None),
+            change_counter: AtomicU64::new(change_counter),
+            commit: RwLock::new(JournalCommit::default()),
+        }
+    }
+
+    pub fn shared(&self) -> &Arc<RollbackJournalShared> {
+        &self.shared
+    }
+
+    pub fn mode(&self) -> JournalMode {
+        self.shared.mode()
+    }
+
+    /// Takes a SHARED lock. Returns true if another connection committed since
+    /// this pager last read, in which case its page cache is stale.
+    pub fn begin_read_tx(&self) -> Result<bool> {
+        if self.lock.get() != JournalLock::None {
+            return Ok(false);
+        }
+        let mut locks = self.shared.locks.lock();
+        if locks.pending || locks.exclusive {
+            return Err(LimboError::Busy);
+        }
+        locks.readers += 1;
+        self.lock.set(JournalLock::Shared);
+        let current = self.shared.change_counter.load(Ordering::SeqCst);
+        Ok(self.change_counter.swap(current, Ordering::SeqCst) != current)
+    }
+
+    /// Upgrades a SHARED lock to RESERVED.
+    pub fn begin_write_tx(&self) -> Result<()> {
+        match self.lock.get() {
+            JournalLock::Reserved | JournalLock::Exclusive => return Ok(()),
+            JournalLock::None => {
+                return Err(LimboError::InternalError(
+                    "write transaction started without a read transaction".into(),
+                ))
+            }
+            JournalLock::Shared => {}
+        }
+        let mut locks = self.shared.locks.lock();
+        if locks.reserved {
+            return Err(LimboError::Busy);
+        }
+        locks.reserved = true;
+        self.lock.set(JournalLock::Reserved);
+        Ok(())
+    }
+
+    /// Upgrades a RESERVED lock to EXCLUSIVE before the database file is
+    /// written. Fails with `Busy` while other connections are still reading;
+    /// the PENDING lock taken meanwhile keeps new readers out so the writer
+    /// is not starved.
+    pub fn begin_commit(&self) -> Result<()> {
+        match self.lock.get() {
+            JournalLock::Exclusive => return Ok(()),
+            JournalLock::Reserved => {}
+            lock => {
+                return Err(LimboError::InternalError(format!(
+                    "commit requires a RESERVED lock, holding {lock:?}"
+                )))
+            }
+        }
+        let mut locks = self.shared.locks.lock();
+        locks.pending = true;
+        if locks.readers > 1 {
+            return Err(LimboError::Busy);
+        }
+        locks.exclusive = true;
+        self.lock.set(JournalLock::Exclusive);
+        Ok(())
+    }
+
+    /// Drops back from RESERVED or EXCLUSIVE to SHARED.
+    pub fn end_write_tx(&self) {
+        if !self.holds_write_lock() {
+            return;
+        }
+        let mut locks = self.shared.locks.lock();
+        locks.reserved = false;
+        locks.pending = false;
+        locks.exclusive = false;
+        self.lock.set(JournalLock::Shared);
+    }
+
+    /// Releases every lock held by this pager.
+    pub fn end_read_tx(&self) {
+        self.end_write_tx();
+        if self.lock.get() != JournalLock::Shared {
+            return;
+        }
+        let mut locks = self.shared.locks.lock();
+        locks.readers -= 1;
+        self.lock.set(JournalLock::None);
+    }
+
+    pub fn holds_read_lock(&self) -> bool {
+        self.lock.get() != JournalLock::None
+    }
+
+    pub fn holds_write_lock(&self) -> bool {
+        matches!(
+            self.lock.get(),
+            JournalLock::Reserved | JournalLock::Exclusive
+        )
+    }
+
+    /// Publishes a commit to the other connections of the database.
+    pub fn mark_committed(&self) {
+        let current = self.shared.change_counter.fetch_add(1, Ordering::SeqCst) + 1;
+        self.change_counter.store(current, Ordering::SeqCst);
+    }
+
+    pub fn open_file(&self, io: &Arc<dyn IO>) -> Result<Arc<dyn File>> {
+        self.shared.open_file(io)
+    }
+
+    /// Ends a successful commit the way the journal mode asks for: DELETE
+    /// removes the file, TRUNCATE cuts it to zero bytes and PERSIST zeroes the
+    /// header. Any of these makes the journal cold.
+    ///
+    /// `size_limit` is `PRAGMA journal_size_limit`: a persisted journal larger
+    /// than the limit is also cut down to it.
+    pub fn finalize(
+        &self,
+        io: &Arc<dyn IO>,
+        size_limit: Option<u64>,
+    ) -> Result<Option<Completion>> {
+        match self.mode() {
+            JournalMode::Delete => {
+                self.shared.delete_file(io)?;
+                Ok(None)
+            }
+            JournalMode::Truncate => {
+                let file = self.open_file(io)?;
+                let c = file.truncate(0, Completion::new_trunc(|_| {}))?;
+                Ok(Some(c))
+            }
+            JournalMode::Persist => {
+                let file = self.open_file(io)?;
+                let limit = size_limit.filter(|&limit| file.size().is_ok_and(|size| size > limit));
+                match limit {
+                    // Too short to hold a header: the truncated file is cold by itself.
+                    Some(limit) if limit < JOURNAL_HEADER_SIZE as u64 => {
+                        let c = file.truncate(limit, Completion::new_trunc(|_| {}))?;
+                        Ok(Some(c))
+                    }
+                    _ => {
+                        let zeroes = Arc::new(Buffer::new(vec![0; JOURNAL_HEADER_SIZE]));
+                        let c = file.pwrite(0, zeroes, Completion::new_write(|_| {}))?;
+                        let Some(limit) = limit else {
+                            return Ok(Some(c));
+                        };
+                        let trunc_c = file.truncate(limit, Completion::new_trunc(|_| {}))?;
+                        let mut group = CompletionGroup::new(|_| {});
+                        group.add(&c);
+                        group.add(&trunc_c);
+                        Ok(Some(group.build()))
+                    }
+                }
+            }
+            JournalMode::Memory | JournalMode::Off => Ok(None),
+            JournalMode::Wal | JournalMode::Mvcc => {
+                unreachable!("rollback journal cannot be in {} mode", self.mode())
+            }
+        }
+    }
+}
+
+impl Drop for RollbackJournal {
+    fn drop(&mut self) {
+        self.end_read_tx();
+    }
+}
+
+/// Plays back a hot journal left behind by a writer that died mid-commit.
+///
+/// Must run before anything reads the database file. Returns true if a
+/// journal was rolled back. A database opened read-only cannot be recovered,
+/// so a hot journal makes the open fail.
+pub fn recover_hot_journal(
+    io: &Arc<dyn IO>,
+    db_file: &Arc<dyn DatabaseStorage>,
+    journal_path: &str,
+    read_only: bool,
+) -> Result<bool> {
+    if !file_exists(io, journal_path)? || db_file.size()? == 0 {
+        return Ok(false);
+    }
+    let file = io.open_file(journal_path, OpenFlags::ReadOnly | OpenFlags::NoLock, false)?;
+    let journal_size = file.size()? as usize;
+    if journal_size < JOURNAL_HEADER_SIZE {
+        return Ok(false);
+    }
+    let buf = Arc::new(Buffer::new_temporary(journal_size));
+    let c = file.pread(
+        0,
+        Completion::new_read(buf.clone(), move |res| {
+            let Ok((_, bytes_read)) = res else {
+                return None;
+            };
+            if bytes_read as usize != journal_size {
+                return Some(CompletionError::ShortRead {
+                    page_idx: 0,
+                    expected: journal_size,
+                    actual: bytes_read as usize,
+                });
+            }
+            None
+        }),
+    )?;
+    io.wait_for_completion(c)?;
+    drop(file);
+    let data = buf.as_slice();
+
+    let Some(first_header) = JournalHeader::read_from(data) else {
+        // Zeroed by PERSIST mode, or not a journal at all.
+        return Ok(false);
+    };
+    if read_only {
+        tracing::error!("hot journal {journal_path} found, but the database is read-only");
+        return Err(LimboError::ReadOnly);
+    }
+    tracing::info!("rolling back hot journal {journal_path}");
+
+    let raw_ctx = raw_io_context();
+    let mut offset = 0;
+    'segments: while let Some(header) = data.get(offset..).and_then(JournalHeader::read_from) {
+        if !header.is_valid() || header.page_size != first_header.page_size {
+            break;
+        }
+        let page_size = header.page_size as usize;
+        let sector_size = header.sector_size as usize;
+        let record_size = page_size + 8;
+        let records_start = offset + sector_size;
+        if records_start > data.len() {
+            break;
+        }
+        let n_rec = if header.n_rec == JOURNAL_NREC_UNTIL_EOF {
+            (data.len() - records_start) / record_size
+        } else {
+            header.n_rec as usize
+        };
+        for i in 0..n_rec {
+            let start = records_start + i * record_size;
+            let Some(record) = data.get(start..start + record_size) else {
+                break 'segments;
+            };
+            let page_no = read_u32(record, 0);
+            let content = &record[4..4 + page_size];
+            if page_no == 0
+                || read_u32(record, 4 + page_size) != journal_checksum(header.nonce, content)
+            {
+                // A torn record: everything before it was synced, nothing after it is valid.
+                break 'segments;
+            }
+            if page_no > first_header.initial_db_size {
+                continue;
+            }
+            let c = db_file.write_page(
+                page_no as usize,
+                Arc::new(Buffer::new(content.to_vec())),
+                &raw_ctx,
+                Completion::new_write(|_| {}),
+            )?;
+            io.wait_for_completion(c)?;
+        }
+        offset = (records_start + n_rec * record_size).next_multiple_of(sector_size);
+    }
+
+    let initial_size = first_header.initial_db_size as u64 * first_header.page_size as u64;
+    if db_file.size()? > initial_size {
+        let c = db_file.truncate(initial_size as usize, Completion::new_trunc(|_| {}))?;
+        io.wait_for_completion(c)?;
+    }
+    let c = db_file.sync(Completion::new_sync(|_| {}), FileSyncType::Fsync)?;
+    io.wait_for_completion(c)?;
+    io.remove_file(journal_path)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_header_roundtrip() {
+        let header = JournalHeader {
+            n_rec: 3,
+            nonce: 0xdead_beef,
+            initial_db_size: 42,
+            sector_size: 512,
+            page_size: 4096,
+        };
+        let mut buf = [0u8; JOURNAL_HEADER_SIZE];
+        header.write_to(&mut buf);
+        assert_eq!(buf[0..8], JOURNAL_MAGIC);
+        assert_eq!(JournalHeader::read_from(&buf), Some(header));
+        assert_eq!(JournalHeader::read_from(&[0u8; JOURNAL_HEADER_SIZE]), None);
+    }
+
+    #[test]
+    fn test_journal_checksum_samples_every_200th_byte() {
+        let mut page = vec![0u8; 1024];
+        assert_eq!(journal_checksum(7, &page), 7);
+        // Sampled offsets for a 1024 byte page: 824, 624, 424, 224, 24.
+        page[824] = 1;
+        page[24] = 2;
+        // Not sampled.
+        page[0] = 100;
+        page[825] = 100;
+        assert_eq!(journal_checksum(7, &page), 10);
+    }
+
+    #[test]
+    fn test_encode_journal_layout() {
+        let page_size = 512;
+        let page = Arc::new(Buffer::new(vec![0xab; page_size]));
+        let encoded = encode_journal(1, 99, 5, page_size, &[(3, page.clone())]);
+        assert_eq!(encoded.len(), JOURNAL_SECTOR_SIZE + page_size + 8);
+        let header = JournalHeader::read_from(&encoded).unwrap();
+        assert_eq!(header.n_rec, 1);
+        assert_eq!(header.initial_db_size, 5);
+        assert_eq!(header.page_size, page_size as u32);
+        let record = &encoded[JOURNAL_SECTOR_SIZE..];
+        assert_eq!(read_u32(record, 0), 3);
+        assert_eq!(
+            read_u32(record, 4 + page_size),
+            journal_checksum(99, page.as_slice())
+        );
+    }
+}
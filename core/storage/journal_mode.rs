@@ -1,7 +1,8 @@
 use crate::sync::Arc;
 
 use crate::storage::sqlite3_ondisk::Version;
-use crate::{mvcc, LimboError, MvStore, OpenFlags, Result, IO};
+use crate::{mvcc, Database, LimboError, MvStore, OpenFlags, Result, IO};
+use turso_macros::AtomicEnum;
 
 #[derive(
     Debug,
@@ -15,6 +16,7 @@ use crate::{mvcc, LimboError, MvStore, OpenFlags, Result, IO};
     strum_macros::EnumString,
     strum_macros::Display,
     strum_macros::IntoStaticStr,
+    AtomicEnum,
 )]
 #[strum(ascii_case_insensitive, serialize_all = "snake_case")]
 pub enum JournalMode {
@@ -29,10 +31,24 @@ pub enum JournalMode {
 }
 
 impl JournalMode {
-    /// Modes that are supported
+    /// Modes that are supported for `db`. The rollback journal modes need a
+    /// database file, and their locking is process-local.
     #[inline]
-    pub fn supported(&self) -> bool {
-        matches!(self, JournalMode::Wal | JournalMode::Mvcc)
+    pub fn supported(&self, db: &Database) -> bool {
+        !self.is_rollback() || (!db.is_in_memory_db() && !db.opts.enable_multiprocess_wal)
+    }
+
+    /// Rollback journal modes, where commits write directly to the database file.
+    #[inline]
+    pub fn is_rollback(&self) -> bool {
+        matches!(
+            self,
+            JournalMode::Delete
+                | JournalMode::Truncate
+                | JournalMode::Persist
+                | JournalMode::Memory
+                | JournalMode::Off
+        )
     }
 
     /// As the header file version
@@ -41,7 +57,11 @@ impl JournalMode {
         match self {
             JournalMode::Wal => Some(Version::Wal),
             JournalMode::Mvcc => Some(Version::Mvcc),
-            _ => None,
+            JournalMode::Delete
+            | JournalMode::Truncate
+            | JournalMode::Persist
+            | JournalMode::Memory
+            | JournalMode::Off => Some(Version::Legacy),
         }
     }
 }
@@ -15,6 +15,7 @@ pub(crate) mod buffer_pool;
 pub(crate) mod checksum;
 pub mod database;
 pub(crate) mod encryption;
+pub(crate) mod journal;
 pub(crate) mod journal_mode;
 pub(crate) mod page_cache;
 #[allow(clippy::arc_with_non_send_sync)]
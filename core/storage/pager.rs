@@ -4,6 +4,8 @@ use crate::io::AtomicFileSyncType;
 use crate::io::FileSyncType;
 use crate::io::WriteBatch;
 use crate::storage::btree::PinGuard;
+use crate::storage::journal::{self, JournalCommitPhase, RollbackJournal};
+use crate::storage::journal_mode::JournalMode;
 use crate::storage::subjournal::Subjournal;
 use crate::storage::wal::{CheckpointLockSource, PreparedFrames};
 use crate::storage::{
@@ -17,7 +19,8 @@ use crate::storage::{
     wal::{CheckpointResult, RollbackTo, Wal, IOV_MAX},
 };
 use crate::sync::atomic::{
-    AtomicBool, AtomicIsize, AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+    AtomicBool, AtomicI64, AtomicIsize, AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize,
+    Ordering,
 };
 use crate::sync::Arc;
 use crate::sync::{Mutex, RwLock};
@@ -57,6 +60,8 @@ use crate::storage::encryption::{CipherMode, EncryptionContext, EncryptionKey};
 
 /// SQLite's default maximum page count
 const DEFAULT_MAX_PAGE_COUNT: u32 = 0xfffffffe;
+/// SQLite's default `wal_autocheckpoint` threshold, in frames
+pub const DEFAULT_WAL_AUTOCHECKPOINT: u32 = 1000;
 const RESERVED_SPACE_NOT_SET: u16 = u16::MAX;
 
 #[cfg(feature = "test_helper")]
@@ -1183,6 +1188,90 @@ enum PtrMapPutState {
     },
 }
 
+#[derive(Debug, Clone)]
+#[cfg(not(feature = "omit_autovacuum"))]
+enum PtrMapPutEntriesState {
+    Start,
+    /// Writing `entries[next..]`, each a page number and its pointer map entry.
+    Put {
+        entries: Vec<(u32, PtrmapEntry)>,
+        next: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg(not(feature = "omit_autovacuum"))]
+enum TakeFreePageState {
+    Start,
+    /// Searching the freelist trunk `trunk_page_id`, which `prev_trunk_page_id` points to
+    /// (0 if it is the first trunk).
+    SearchTrunk {
+        prev_trunk_page_id: u32,
+        trunk_page_id: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg(not(feature = "omit_autovacuum"))]
+enum RelocatePageState {
+    /// Copy the page into its new location.
+    Start,
+    /// Point the pointer map entries of its children or next overflow page at the new location.
+    UpdateChildren,
+    /// Rewrite the pointer held by its parent.
+    UpdateParent,
+    /// Move its own pointer map entry.
+    UpdateOwnEntry,
+}
+
+#[derive(Debug, Clone)]
+#[cfg(not(feature = "omit_autovacuum"))]
+enum AllocateRootPageState {
+    Start,
+    TakeFreePage {
+        db_size: u32,
+    },
+    AllocatePage {
+        db_size: u32,
+    },
+    /// The desired root page is in use: look up what it holds so it can be moved into `page`.
+    ReadPtrmapEntry {
+        page: PageRef,
+    },
+    Relocate {
+        page: PageRef,
+        entry: PtrmapEntry,
+    },
+    LoadRootPage,
+}
+
+#[derive(Debug, Clone)]
+#[cfg(not(feature = "omit_autovacuum"))]
+enum IncrementalVacuumState {
+    Start,
+    /// The last page is free: take it off the freelist.
+    TakeLastPage {
+        last_page: u32,
+        new_db_size: u32,
+    },
+    /// The last page is in use: take a free page that survives the truncation to move it into.
+    TakeFreePage {
+        last_page: u32,
+        entry: PtrmapEntry,
+        final_size: u32,
+        new_db_size: u32,
+    },
+    Relocate {
+        last_page: u32,
+        entry: PtrmapEntry,
+        free_page: PageRef,
+        new_db_size: u32,
+    },
+    Truncate {
+        new_db_size: u32,
+    },
+}
+
 #[derive(Debug, Clone)]
 enum HeaderRefState {
     Start,
@@ -1197,6 +1286,7 @@ enum HeaderRefState {
 enum BtreeCreateVacuumFullState {
     Start,
     AllocatePage { root_page_num: u32 },
+    UpdateLargestRootPage { allocated_page_id: u32 },
     PtrMapPut { allocated_page_id: u32 },
 }
 
@@ -1325,6 +1415,9 @@ pub struct Pager {
     /// The write-ahead log (WAL) for the database.
     /// in-memory databases, ephemeral tables and ephemeral indexes do not have a WAL.
     pub(crate) wal: Option<Arc<dyn Wal>>,
+    /// The rollback journal, for databases in a rollback journal mode. Mutually
+    /// exclusive with `wal`.
+    pub(crate) journal: Option<RollbackJournal>,
     /// A page cache for the database.
     page_cache: Arc<RwLock<PageCache>>,
     /// Buffer pool for temporary data storage.
@@ -1362,6 +1455,12 @@ pub struct Pager {
     cacheflush_state: RwLock<CacheFlushState>,
     /// Maximum number of pages allowed in the database. Default is 1073741823 (SQLite default).
     max_page_count: AtomicU32,
+    /// Number of WAL frames that triggers a passive checkpoint after commit
+    /// (`PRAGMA wal_autocheckpoint`). Zero disables auto-checkpointing.
+    wal_autocheckpoint: AtomicU32,
+    /// Size in bytes the WAL or a persisted rollback journal is kept under
+    /// (`PRAGMA journal_size_limit`). Negative means no limit.
+    journal_size_limit: AtomicI64,
     header_ref_state: RwLock<HeaderRefState>,
     #[cfg(not(feature = "omit_autovacuum"))]
     vacuum_state: RwLock<VacuumState>,
@@ -1384,6 +1483,16 @@ pub struct VacuumState {
     ptrmap_get_state: PtrMapGetState,
     /// State machine for [Pager::ptrmap_put]
     ptrmap_put_state: PtrMapPutState,
+    /// State machine for [Pager::ptrmap_put_entries]
+    ptrmap_put_entries_state: PtrMapPutEntriesState,
+    /// State machine for [Pager::take_free_page]
+    take_free_page_state: TakeFreePageState,
+    /// State machine for [Pager::relocate_page]
+    relocate_page_state: RelocatePageState,
+    /// State machine for [Pager::allocate_root_page]
+    allocate_root_page_state: AllocateRootPageState,
+    /// State machine for [Pager::incremental_vacuum_step]
+    incremental_vacuum_state: IncrementalVacuumState,
     btree_create_vacuum_full_state: BtreeCreateVacuumFullState,
 }
 
@@ -1502,6 +1611,7 @@ impl Pager {
         Ok(Self {
             db_file,
             wal,
+            journal: None,
             page_cache: Arc::new(RwLock::new(page_cache)),
             io,
             dirty_pages: Arc::new(RwLock::new(RoaringBitmap::new())),
@@ -1530,11 +1640,18 @@ impl Pager {
             cacheflush_state: RwLock::new(CacheFlushState::default()),
             allocate_page_state: RwLock::new(AllocatePageState::Start),
             max_page_count: AtomicU32::new(DEFAULT_MAX_PAGE_COUNT),
+            wal_autocheckpoint: AtomicU32::new(DEFAULT_WAL_AUTOCHECKPOINT),
+            journal_size_limit: AtomicI64::new(-1),
             header_ref_state: RwLock::new(HeaderRefState::Start),
             #[cfg(not(feature = "omit_autovacuum"))]
             vacuum_state: RwLock::new(VacuumState {
                 ptrmap_get_state: PtrMapGetState::Start,
                 ptrmap_put_state: PtrMapPutState::Start,
+                ptrmap_put_entries_state: PtrMapPutEntriesState::Start,
+                take_free_page_state: TakeFreePageState::Start,
+                relocate_page_state: RelocatePageState::Start,
+                allocate_root_page_state: AllocateRootPageState::Start,
+                incremental_vacuum_state: IncrementalVacuumState::Start,
                 btree_create_vacuum_full_state: BtreeCreateVacuumFullState::Start,
             }),
             io_ctx: RwLock::new(IOContext::default()),
@@ -2081,6 +2198,30 @@ impl Pager {
         self.max_page_count.load(Ordering::SeqCst)
     }
 
+    /// Get the WAL frame count that triggers an auto-checkpoint (0 = disabled)
+    pub fn get_wal_autocheckpoint(&self) -> u32 {
+        self.wal_autocheckpoint.load(Ordering::SeqCst)
+    }
+
+    /// Set the WAL frame count that triggers an auto-checkpoint (0 = disabled)
+    pub fn set_wal_autocheckpoint(&self, frames: u32) {
+        self.wal_autocheckpoint.store(frames, Ordering::SeqCst);
+    }
+
+    /// Get the journal size limit in bytes (negative = no limit)
+    pub fn get_journal_size_limit(&self) -> i64 {
+        self.journal_size_limit.load(Ordering::SeqCst)
+    }
+
+    /// Set the journal size limit in bytes (negative = no limit)
+    pub fn set_journal_size_limit(&self, limit: i64) {
+        self.journal_size_limit.store(limit, Ordering::SeqCst);
+    }
+
+    fn journal_size_limit_bytes(&self) -> Option<u64> {
+        u64::try_from(self.get_journal_size_limit()).ok()
+    }
+
     /// Set the maximum page count for this database
     /// Returns the new maximum page count (may be clamped to current database size)
     pub fn set_max_page_count(&self, new_max: u32) -> crate::Result<IOResult<u32>> {
@@ -2099,6 +2240,18 @@ impl Pager {
         self.wal = Some(wal);
     }
 
+    pub fn set_rollback_journal(&mut self, journal: RollbackJournal) {
+        turso_assert!(
+            self.wal.is_none(),
+            "pager cannot have both a WAL and a journal"
+        );
+        self.journal = Some(journal);
+    }
+
+    pub fn has_rollback_journal(&self) -> bool {
+        self.journal.is_some()
+    }
+
     pub fn get_auto_vacuum_mode(&self) -> AutoVacuumMode {
         self.auto_vacuum_mode.load(Ordering::SeqCst).into()
     }
@@ -2320,6 +2473,666 @@ impl Pager {
         }
     }
 
+    /// Returns true if the database keeps pointer map pages, i.e. auto-vacuum is enabled.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    pub fn ptrmap_enabled(&self) -> bool {
+        self.get_auto_vacuum_mode() != AutoVacuumMode::None
+    }
+
+    #[cfg(feature = "omit_autovacuum")]
+    pub fn ptrmap_enabled(&self) -> bool {
+        false
+    }
+
+    /// Writes a batch of pointer map entries. `entries` is only called to collect the batch
+    /// on the first invocation; after I/O, the next invocation resumes where the batch stopped.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    fn ptrmap_put_entries(
+        &self,
+        entries: impl FnOnce() -> Result<Vec<(u32, PtrmapEntry)>>,
+    ) -> Result<IOResult<()>> {
+        let state = std::mem::replace(
+            &mut self.vacuum_state.write().ptrmap_put_entries_state,
+            PtrMapPutEntriesState::Start,
+        );
+        let (entries, mut next) = match state {
+            PtrMapPutEntriesState::Start => (entries()?, 0),
+            PtrMapPutEntriesState::Put { entries, next } => (entries, next),
+        };
+        while let Some(&(page_no, entry)) = entries.get(next) {
+            match self.ptrmap_put(page_no, entry.entry_type, entry.parent_page_no)? {
+                IOResult::Done(()) => next += 1,
+                IOResult::IO(io) => {
+                    self.vacuum_state.write().ptrmap_put_entries_state =
+                        PtrMapPutEntriesState::Put { entries, next };
+                    return Ok(IOResult::IO(io));
+                }
+            }
+        }
+        Ok(IOResult::Done(()))
+    }
+
+    /// Appends the pointer map entries that name `page` as the parent of its child pages and
+    /// of the first overflow page of each of its cells.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    fn ptrmap_children_entries(
+        &self,
+        page: &PageRef,
+        entries: &mut Vec<(u32, PtrmapEntry)>,
+    ) -> Result<()> {
+        let parent_page_no = page.get().id as u32;
+        let contents = page.get_contents();
+        let usable_space = self.usable_space();
+        let is_leaf = contents.is_leaf();
+        let child_entry = PtrmapEntry {
+            entry_type: PtrmapType::BTreeNode,
+            parent_page_no,
+        };
+        for idx in 0..contents.cell_count() {
+            if !is_leaf {
+                let child = contents.cell_interior_read_left_child_page(idx)?;
+                entries.push((child, child_entry));
+            }
+            let cell = contents.cell_get(idx, usable_space)?;
+            if let Some(first_overflow_page) = cell_first_overflow_page(&cell) {
+                entries.push((
+                    first_overflow_page,
+                    PtrmapEntry {
+                        entry_type: PtrmapType::Overflow1,
+                        parent_page_no,
+                    },
+                ));
+            }
+        }
+        if let Some(rightmost) = contents.rightmost_pointer()? {
+            entries.push((rightmost, child_entry));
+        }
+        Ok(())
+    }
+
+    /// Records `parent_page_no` as the parent of the non-root b-tree page `page`, and `page`
+    /// as the parent of its children and of the overflow chains of its cells.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    pub fn ptrmap_put_btree_page(
+        &self,
+        page: &PageRef,
+        parent_page_no: u32,
+    ) -> Result<IOResult<()>> {
+        if !self.ptrmap_enabled() {
+            return Ok(IOResult::Done(()));
+        }
+        self.ptrmap_put_entries(|| {
+            let mut entries = vec![(
+                page.get().id as u32,
+                PtrmapEntry {
+                    entry_type: PtrmapType::BTreeNode,
+                    parent_page_no,
+                },
+            )];
+            self.ptrmap_children_entries(page, &mut entries)?;
+            Ok(entries)
+        })
+    }
+
+    #[cfg(feature = "omit_autovacuum")]
+    pub fn ptrmap_put_btree_page(
+        &self,
+        _page: &PageRef,
+        _parent_page_no: u32,
+    ) -> Result<IOResult<()>> {
+        Ok(IOResult::Done(()))
+    }
+
+    /// Records `page` as the parent of its child pages and of the first overflow page of
+    /// each of its cells.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    pub fn ptrmap_put_children(&self, page: &PageRef) -> Result<IOResult<()>> {
+        if !self.ptrmap_enabled() {
+            return Ok(IOResult::Done(()));
+        }
+        self.ptrmap_put_entries(|| {
+            let mut entries = Vec::new();
+            self.ptrmap_children_entries(page, &mut entries)?;
+            Ok(entries)
+        })
+    }
+
+    #[cfg(feature = "omit_autovacuum")]
+    pub fn ptrmap_put_children(&self, _page: &PageRef) -> Result<IOResult<()>> {
+        Ok(IOResult::Done(()))
+    }
+
+    /// Records the parent of overflow page `page_no`: the b-tree page holding the cell for
+    /// the first page of a chain, the previous overflow page for the others.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    pub fn ptrmap_put_overflow_page(
+        &self,
+        page_no: u32,
+        parent_page_no: u32,
+        first_in_chain: bool,
+    ) -> Result<IOResult<()>> {
+        if !self.ptrmap_enabled() {
+            return Ok(IOResult::Done(()));
+        }
+        let entry_type = if first_in_chain {
+            PtrmapType::Overflow1
+        } else {
+            PtrmapType::Overflow2
+        };
+        self.ptrmap_put(page_no, entry_type, parent_page_no)
+    }
+
+    #[cfg(feature = "omit_autovacuum")]
+    pub fn ptrmap_put_overflow_page(
+        &self,
+        _page_no: u32,
+        _parent_page_no: u32,
+        _first_in_chain: bool,
+    ) -> Result<IOResult<()>> {
+        Ok(IOResult::Done(()))
+    }
+
+    /// Reads page `page_no`, yielding until it is loaded.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    fn read_loaded_page(&self, page_no: u32) -> Result<IOResult<PageRef>> {
+        let (page, c) = self.read_page(page_no as i64)?;
+        if let Some(c) = c {
+            if !c.succeeded() {
+                io_yield_one!(c);
+            }
+        }
+        Ok(IOResult::Done(page))
+    }
+
+    /// Removes a page from the freelist and returns it zeroed and dirty. With `exact`, only
+    /// `page_no` itself may be taken; otherwise any free page numbered at most `page_no`.
+    /// Returns `None` if the freelist holds no such page.
+    ///
+    /// From SQLite's allocateBtreePage() with BTALLOC_EXACT and BTALLOC_LE: a matching trunk
+    /// page is replaced by its first leaf, which inherits the remaining leaves; a matching leaf
+    /// is replaced by the last leaf of its trunk. Every page involved is loaded before any of
+    /// them is changed, so a step interrupted by I/O is simply retried.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    fn take_free_page(&self, page_no: u32, exact: bool) -> Result<IOResult<Option<PageRef>>> {
+        let header_ref = return_if_io!(HeaderRefMut::from_pager(self));
+        let header = header_ref.borrow_mut();
+        let matches = |id: u32| if exact { id == page_no } else { id <= page_no };
+
+        loop {
+            let state = self.vacuum_state.read().take_free_page_state;
+            let (prev_trunk_page_id, trunk_page_id) = match state {
+                TakeFreePageState::Start => (0, header.freelist_trunk_page.get()),
+                TakeFreePageState::SearchTrunk {
+                    prev_trunk_page_id,
+                    trunk_page_id,
+                } => (prev_trunk_page_id, trunk_page_id),
+            };
+            if trunk_page_id == 0 {
+                self.vacuum_state.write().take_free_page_state = TakeFreePageState::Start;
+                return Ok(IOResult::Done(None));
+            }
+
+            let trunk_page = PinGuard::new(return_if_io!(self.read_loaded_page(trunk_page_id)));
+            let trunk_contents = trunk_page.get_contents();
+            let next_trunk_page_id =
+                trunk_contents.read_u32_no_offset(FREELIST_TRUNK_OFFSET_NEXT_TRUNK_PTR);
+            let leaf_count =
+                trunk_contents.read_u32_no_offset(FREELIST_TRUNK_OFFSET_LEAF_COUNT) as usize;
+
+            if matches(trunk_page_id) {
+                let new_trunk = if leaf_count == 0 {
+                    None
+                } else {
+                    let new_trunk_id =
+                        trunk_contents.read_u32_no_offset(FREELIST_TRUNK_OFFSET_FIRST_LEAF_PTR);
+                    Some(PinGuard::new(return_if_io!(
+                        self.read_loaded_page(new_trunk_id)
+                    )))
+                };
+                let prev_trunk = if prev_trunk_page_id == 0 {
+                    None
+                } else {
+                    Some(PinGuard::new(return_if_io!(
+                        self.read_loaded_page(prev_trunk_page_id)
+                    )))
+                };
+
+                self.add_dirty(&trunk_page)?;
+                let replacement_trunk_id = match &new_trunk {
+                    None => next_trunk_page_id,
+                    Some(new_trunk) => {
+                        self.add_dirty(new_trunk)?;
+                        let new_trunk_contents = new_trunk.get_contents();
+                        new_trunk_contents.write_u32_no_offset(
+                            FREELIST_TRUNK_OFFSET_NEXT_TRUNK_PTR,
+                            next_trunk_page_id,
+                        );
+                        new_trunk_contents.write_u32_no_offset(
+                            FREELIST_TRUNK_OFFSET_LEAF_COUNT,
+                            (leaf_count - 1) as u32,
+                        );
+                        let remaining_leaves_start =
+                            FREELIST_TRUNK_OFFSET_FIRST_LEAF_PTR + FREELIST_LEAF_PTR_SIZE;
+                        let remaining_leaves_len = (leaf_count - 1) * FREELIST_LEAF_PTR_SIZE;
+                        new_trunk_contents.as_ptr()[FREELIST_TRUNK_OFFSET_FIRST_LEAF_PTR
+                            ..FREELIST_TRUNK_OFFSET_FIRST_LEAF_PTR + remaining_leaves_len]
+                            .copy_from_slice(
+                                &trunk_contents.as_ptr()[remaining_leaves_start
+                                    ..remaining_leaves_start + remaining_leaves_len],
+                            );
+                        new_trunk.get().id as u32
+                    }
+                };
+                match &prev_trunk {
+                    Some(prev_trunk) => {
+                        self.add_dirty(prev_trunk)?;
+                        prev_trunk.get_contents().write_u32_no_offset(
+                            FREELIST_TRUNK_OFFSET_NEXT_TRUNK_PTR,
+                            replacement_trunk_id,
+                        );
+                    }
+                    None => header.freelist_trunk_page = replacement_trunk_id.into(),
+                }
+                header.freelist_pages = (header.freelist_pages.get() - 1).into();
+                trunk_contents.as_ptr().fill(0);
+                self.vacuum_state.write().take_free_page_state = TakeFreePageState::Start;
+                return Ok(IOResult::Done(Some(trunk_page.to_page())));
+            }
+
+            for leaf_idx in 0..leaf_count {
+                let leaf_ptr_offset =
+                    FREELIST_TRUNK_OFFSET_FIRST_LEAF_PTR + leaf_idx * FREELIST_LEAF_PTR_SIZE;
+                let leaf_page_id = trunk_contents.read_u32_no_offset(leaf_ptr_offset);
+                if !matches(leaf_page_id) {
+                    continue;
+                }
+                let leaf_page = return_if_io!(self.read_loaded_page(leaf_page_id));
+                self.add_dirty(&trunk_page)?;
+                let last_leaf_ptr_offset = FREELIST_TRUNK_OFFSET_FIRST_LEAF_PTR
+                    + (leaf_count - 1) * FREELIST_LEAF_PTR_SIZE;
+                let last_leaf_page_id = trunk_contents.read_u32_no_offset(last_leaf_ptr_offset);
+                trunk_contents.write_u32_no_offset(leaf_ptr_offset, last_leaf_page_id);
+                trunk_contents
+                    .write_u32_no_offset(FREELIST_TRUNK_OFFSET_LEAF_COUNT, (leaf_count - 1) as u32);
+                header.freelist_pages = (header.freelist_pages.get() - 1).into();
+                self.add_dirty(&leaf_page)?;
+                leaf_page.get_contents().as_ptr().fill(0);
+                self.vacuum_state.write().take_free_page_state = TakeFreePageState::Start;
+                return Ok(IOResult::Done(Some(leaf_page)));
+            }
+
+            self.vacuum_state.write().take_free_page_state = TakeFreePageState::SearchTrunk {
+                prev_trunk_page_id: trunk_page_id,
+                trunk_page_id: next_trunk_page_id,
+            };
+        }
+    }
+
+    /// Allocates page `root_page_num` for a new b-tree root, so that root pages stay at the
+    /// front of an auto-vacuum database. A free page is taken off the freelist; a page in use
+    /// is first moved to a newly allocated page. Past the end of the database, whatever page
+    /// the allocator hands out is used instead.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    fn allocate_root_page(&self, root_page_num: u32) -> Result<IOResult<PageRef>> {
+        loop {
+            let state = self.vacuum_state.read().allocate_root_page_state.clone();
+            match state {
+                AllocateRootPageState::Start => {
+                    let db_size =
+                        return_if_io!(self.with_header(|header| header.database_size.get()));
+                    self.vacuum_state.write().allocate_root_page_state =
+                        AllocateRootPageState::TakeFreePage { db_size };
+                }
+                AllocateRootPageState::TakeFreePage { db_size } => {
+                    if let Some(page) = return_if_io!(self.take_free_page(root_page_num, true)) {
+                        self.vacuum_state.write().allocate_root_page_state =
+                            AllocateRootPageState::Start;
+                        return Ok(IOResult::Done(page));
+                    }
+                    self.vacuum_state.write().allocate_root_page_state =
+                        AllocateRootPageState::AllocatePage { db_size };
+                }
+                AllocateRootPageState::AllocatePage { db_size } => {
+                    let page = return_if_io!(self.allocate_page());
+                    if page.get().id as u32 == root_page_num || root_page_num > db_size {
+                        self.vacuum_state.write().allocate_root_page_state =
+                            AllocateRootPageState::Start;
+                        return Ok(IOResult::Done(page));
+                    }
+                    self.vacuum_state.write().allocate_root_page_state =
+                        AllocateRootPageState::ReadPtrmapEntry { page };
+                }
+                AllocateRootPageState::ReadPtrmapEntry { page } => {
+                    let entry = return_if_io!(self.ptrmap_get(root_page_num)).ok_or_else(|| {
+                        LimboError::Corrupt(format!(
+                            "Missing ptrmap entry for page {root_page_num}"
+                        ))
+                    })?;
+                    self.vacuum_state.write().allocate_root_page_state =
+                        AllocateRootPageState::Relocate { page, entry };
+                }
+                AllocateRootPageState::Relocate { page, entry } => {
+                    return_if_io!(self.relocate_page(root_page_num, entry, &page));
+                    self.vacuum_state.write().allocate_root_page_state =
+                        AllocateRootPageState::LoadRootPage;
+                }
+                AllocateRootPageState::LoadRootPage => {
+                    let root_page = return_if_io!(self.read_loaded_page(root_page_num));
+                    self.add_dirty(&root_page)?;
+                    self.vacuum_state.write().allocate_root_page_state =
+                        AllocateRootPageState::Start;
+                    return Ok(IOResult::Done(root_page));
+                }
+            }
+        }
+    }
+
+    /// Moves the content of page `page_no`, described by the pointer map entry `entry`, into
+    /// `new_page` and rewrites every reference to it: the pointer held by its parent, the
+    /// pointer map entries of its children and its own pointer map entry.
+    /// From SQLite's relocatePage(). Root pages are never moved.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    fn relocate_page(
+        &self,
+        page_no: u32,
+        entry: PtrmapEntry,
+        new_page: &PageRef,
+    ) -> Result<IOResult<()>> {
+        let new_page_no = new_page.get().id as u32;
+        loop {
+            let state = self.vacuum_state.read().relocate_page_state;
+            match state {
+                RelocatePageState::Start => {
+                    tracing::debug!(
+                        "relocate_page(page_no={}, new_page_no={}, entry={:?})",
+                        page_no,
+                        new_page_no,
+                        entry
+                    );
+                    if matches!(
+                        entry.entry_type,
+                        PtrmapType::RootPage | PtrmapType::FreePage
+                    ) {
+                        return Err(LimboError::Corrupt(format!(
+                            "Cannot relocate page {page_no} with ptrmap entry {:?}",
+                            entry.entry_type
+                        )));
+                    }
+
+                    let page = return_if_io!(self.read_loaded_page(page_no));
+                    self.add_dirty(new_page)?;
+                    new_page
+                        .get_contents()
+                        .as_ptr()
+                        .copy_from_slice(page.get_contents().as_ptr());
+                    self.vacuum_state.write().relocate_page_state =
+                        RelocatePageState::UpdateChildren;
+                }
+                RelocatePageState::UpdateChildren => {
+                    if entry.entry_type == PtrmapType::BTreeNode {
+                        return_if_io!(self.ptrmap_put_children(new_page));
+                    } else {
+                        let next_overflow_page = new_page.get_contents().read_u32_no_offset(0);
+                        if next_overflow_page != 0 {
+                            return_if_io!(self.ptrmap_put(
+                                next_overflow_page,
+                                PtrmapType::Overflow2,
+                                new_page_no
+                            ));
+                        }
+                    }
+                    self.vacuum_state.write().relocate_page_state = RelocatePageState::UpdateParent;
+                }
+                RelocatePageState::UpdateParent => {
+                    let parent = return_if_io!(self.read_loaded_page(entry.parent_page_no));
+                    self.add_dirty(&parent)?;
+                    self.modify_page_pointer(&parent, page_no, new_page_no, entry.entry_type)?;
+                    self.vacuum_state.write().relocate_page_state =
+                        RelocatePageState::UpdateOwnEntry;
+                }
+                RelocatePageState::UpdateOwnEntry => {
+                    return_if_io!(self.ptrmap_put(
+                        new_page_no,
+                        entry.entry_type,
+                        entry.parent_page_no
+                    ));
+                    self.vacuum_state.write().relocate_page_state = RelocatePageState::Start;
+                    return Ok(IOResult::Done(()));
+                }
+            }
+        }
+    }
+
+    /// Rewrites the reference to page `from` held by `parent` so that it points to page `to`.
+    /// From SQLite's modifyPagePointer().
+    #[cfg(not(feature = "omit_autovacuum"))]
+    fn modify_page_pointer(
+        &self,
+        parent: &PageRef,
+        from: u32,
+        to: u32,
+        entry_type: PtrmapType,
+    ) -> Result<()> {
+        let contents = parent.get_contents();
+        if entry_type == PtrmapType::Overflow2 {
+            if contents.read_u32_no_offset(0) != from {
+                return Err(LimboError::Corrupt(format!(
+                    "Overflow page {} does not point to overflow page {from}",
+                    parent.get().id
+                )));
+            }
+            contents.write_u32_no_offset(0, to);
+            return Ok(());
+        }
+
+        let usable_space = self.usable_space();
+        for idx in 0..contents.cell_count() {
+            if entry_type == PtrmapType::Overflow1 {
+                let cell = contents.cell_get(idx, usable_space)?;
+                if cell_first_overflow_page(&cell) == Some(from) {
+                    let (start, len) = contents.cell_get_raw_region(idx, usable_space)?;
+                    contents.write_u32_no_offset(start + len - 4, to);
+                    return Ok(());
+                }
+            } else if !contents.is_leaf() {
+                let start = contents.cell_get_raw_start_offset(idx);
+                if contents.read_u32_no_offset(start) == from {
+                    contents.write_u32_no_offset(start, to);
+                    return Ok(());
+                }
+            }
+        }
+        if entry_type == PtrmapType::BTreeNode && contents.rightmost_pointer()? == Some(from) {
+            contents.write_rightmost_ptr(to);
+            return Ok(());
+        }
+        Err(LimboError::Corrupt(format!(
+            "Page {} does not reference page {from}",
+            parent.get().id
+        )))
+    }
+
+    /// Computes the size the database will have once incremental vacuum has reclaimed every
+    /// free page, accounting for the pointer map pages that go away with them.
+    /// From SQLite's finalDbSize().
+    #[cfg(not(feature = "omit_autovacuum"))]
+    fn incremental_vacuum_final_size(
+        &self,
+        db_size: u32,
+        free_pages: u32,
+        page_size: usize,
+    ) -> i64 {
+        let entries = entries_per_ptrmap_page(page_size) as i64;
+        let ptrmap_pages = (free_pages as i64 - db_size as i64
+            + get_ptrmap_page_no_for_db_page(db_size, page_size) as i64
+            + entries)
+            / entries;
+        let mut final_size = db_size as i64 - free_pages as i64 - ptrmap_pages;
+        let pending_byte_page = self.pending_byte_page_id().map(i64::from);
+        if let Some(pending_byte_page) = pending_byte_page {
+            if db_size as i64 > pending_byte_page && final_size < pending_byte_page {
+                final_size -= 1;
+            }
+        }
+        while final_size > 1
+            && (is_ptrmap_page(final_size as u32, page_size)
+                || Some(final_size) == pending_byte_page)
+        {
+            final_size -= 1;
+        }
+        final_size
+    }
+
+    /// Reclaims one page for `PRAGMA incremental_vacuum`: the last page of the database is
+    /// dropped from the freelist if it is free, or moved into a free page that survives the
+    /// truncation otherwise, and the database shrinks past it. Returns `false` once there is
+    /// nothing left to reclaim.
+    ///
+    /// From SQLite's sqlite3BtreeIncrVacuum(). Root pages are never moved because that would
+    /// require rewriting the schema, so vacuuming stops when one is the last page.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    pub fn incremental_vacuum_step(&self) -> Result<IOResult<bool>> {
+        if !self.ptrmap_enabled() {
+            return Ok(IOResult::Done(false));
+        }
+        loop {
+            let state = self.vacuum_state.read().incremental_vacuum_state.clone();
+            match state {
+                IncrementalVacuumState::Start => {
+                    return_if_io!(self.ensure_cache_space());
+                    let (db_size, free_pages, page_size) =
+                        return_if_io!(self.with_header(|header| {
+                            (
+                                header.database_size.get(),
+                                header.freelist_pages.get(),
+                                header.page_size.get() as usize,
+                            )
+                        }));
+                    if free_pages == 0 {
+                        return Ok(IOResult::Done(false));
+                    }
+                    let final_size =
+                        self.incremental_vacuum_final_size(db_size, free_pages, page_size);
+                    if free_pages >= db_size || final_size < 1 || final_size > db_size as i64 {
+                        return Err(LimboError::Corrupt(format!(
+                            "Freelist of {free_pages} pages does not fit a database of {db_size} pages"
+                        )));
+                    }
+
+                    let last_page = db_size;
+                    let mut new_db_size = last_page - 1;
+                    while is_ptrmap_page(new_db_size, page_size)
+                        || Some(new_db_size) == self.pending_byte_page_id()
+                    {
+                        new_db_size -= 1;
+                    }
+                    let next_state = if is_ptrmap_page(last_page, page_size)
+                        || Some(last_page) == self.pending_byte_page_id()
+                    {
+                        IncrementalVacuumState::Truncate { new_db_size }
+                    } else {
+                        let entry = return_if_io!(self.ptrmap_get(last_page)).ok_or_else(|| {
+                            LimboError::Corrupt(format!(
+                                "Missing ptrmap entry for page {last_page}"
+                            ))
+                        })?;
+                        match entry.entry_type {
+                            PtrmapType::RootPage => return Ok(IOResult::Done(false)),
+                            PtrmapType::FreePage => IncrementalVacuumState::TakeLastPage {
+                                last_page,
+                                new_db_size,
+                            },
+                            _ => IncrementalVacuumState::TakeFreePage {
+                                last_page,
+                                entry,
+                                final_size: final_size as u32,
+                                new_db_size,
+                            },
+                        }
+                    };
+                    self.vacuum_state.write().incremental_vacuum_state = next_state;
+                }
+                IncrementalVacuumState::TakeLastPage {
+                    last_page,
+                    new_db_size,
+                } => {
+                    if return_if_io!(self.take_free_page(last_page, true)).is_none() {
+                        self.vacuum_state.write().incremental_vacuum_state =
+                            IncrementalVacuumState::Start;
+                        return Err(LimboError::Corrupt(format!(
+                            "Page {last_page} is marked free but is not on the freelist"
+                        )));
+                    }
+                    self.vacuum_state.write().incremental_vacuum_state =
+                        IncrementalVacuumState::Truncate { new_db_size };
+                }
+                IncrementalVacuumState::TakeFreePage {
+                    last_page,
+                    entry,
+                    final_size,
+                    new_db_size,
+                } => {
+                    let Some(free_page) = return_if_io!(self.take_free_page(final_size, false))
+                    else {
+                        self.vacuum_state.write().incremental_vacuum_state =
+                            IncrementalVacuumState::Start;
+                        return Err(LimboError::Corrupt(format!(
+                            "No free page at or below page {final_size}"
+                        )));
+                    };
+                    self.vacuum_state.write().incremental_vacuum_state =
+                        IncrementalVacuumState::Relocate {
+                            last_page,
+                            entry,
+                            free_page,
+                            new_db_size,
+                        };
+                }
+                IncrementalVacuumState::Relocate {
+                    last_page,
+                    entry,
+                    free_page,
+                    new_db_size,
+                } => {
+                    return_if_io!(self.relocate_page(last_page, entry, &free_page));
+                    self.vacuum_state.write().incremental_vacuum_state =
+                        IncrementalVacuumState::Truncate { new_db_size };
+                }
+                IncrementalVacuumState::Truncate { new_db_size } => {
+                    self.discard_pages_after(new_db_size)?;
+                    return_if_io!(
+                        self.with_header_mut(|header| header.database_size = new_db_size.into())
+                    );
+                    self.vacuum_state.write().incremental_vacuum_state =
+                        IncrementalVacuumState::Start;
+                    return Ok(IOResult::Done(true));
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "omit_autovacuum")]
+    pub fn incremental_vacuum_step(&self) -> Result<IOResult<bool>> {
+        Ok(IOResult::Done(false))
+    }
+
+    /// Drops cached pages past `db_size` once the database has shrunk. Dirty pages are
+    /// subjournaled first so that a statement rollback can bring them back.
+    #[cfg(not(feature = "omit_autovacuum"))]
+    fn discard_pages_after(&self, db_size: u32) -> Result<()> {
+        let mut dirty_pages = self.dirty_pages.write();
+        let mut cache = self.page_cache.write();
+        for page_id in dirty_pages.iter().filter(|&id| id > db_size) {
+            if let Some(page) = cache.get(&PageCacheKey::new(page_id as usize))? {
+                self.subjournal_page_if_required(&page)?;
+                page.clear_dirty();
+            }
+        }
+        dirty_pages.remove_range((db_size + 1)..);
+        cache.truncate(db_size as usize)?;
+        Ok(())
+    }
+
     /// This method is used to allocate a new root page for a btree, both for tables and indexes
     /// FIXME: handle no room in page cache
     #[instrument(skip_all, level = Level::DEBUG)]
@@ -2346,7 +3159,7 @@ impl Pager {
                         return_if_io!(self.do_allocate_page(page_type, 0, BtreePageAllocMode::Any));
                     Ok(IOResult::Done(page.get().id as u32))
                 }
-                AutoVacuumMode::Full => {
+                AutoVacuumMode::Full | AutoVacuumMode::Incremental => {
                     loop {
                         let btree_create_vacuum_full_state = {
                             let vacuum_state = self.vacuum_state.read();
@@ -2370,7 +3183,9 @@ impl Pager {
                                     "can never be less than 2 because we have already incremented"
                                 );
 
-                                while is_ptrmap_page(root_page_num, page_size as usize) {
+                                while is_ptrmap_page(root_page_num, page_size as usize)
+                                    || Some(root_page_num) == self.pending_byte_page_id()
+                                {
                                     root_page_num += 1;
                                 }
                                 turso_assert_greater_than_or_equal!(
@@ -2383,13 +3198,17 @@ impl Pager {
                             }
                             BtreeCreateVacuumFullState::AllocatePage { root_page_num } => {
                                 //  root_page_num here is the desired root page
-                                let page = return_if_io!(self.do_allocate_page(
-                                    page_type,
-                                    0,
-                                    BtreePageAllocMode::Exact(root_page_num),
-                                ));
+                                let page = return_if_io!(self.allocate_root_page(root_page_num));
+                                btree_init_page(&page, page_type, 0, self.usable_space());
                                 let allocated_page_id = page.get().id as u32;
-
+                                self.vacuum_state.write().btree_create_vacuum_full_state =
+                                    BtreeCreateVacuumFullState::UpdateLargestRootPage {
+                                        allocated_page_id,
+                                    };
+                            }
+                            BtreeCreateVacuumFullState::UpdateLargestRootPage {
+                                allocated_page_id,
+                            } => {
                                 return_if_io!(self.with_header_mut(|header| {
                                     if allocated_page_id
                                         > header.vacuum_mode_largest_root_page.get()
@@ -2403,17 +3222,10 @@ impl Pager {
                                             allocated_page_id.into();
                                     }
                                 }));
-
-                                if allocated_page_id != root_page_num {
-                                    //  TODO(Zaid): Handle swapping the allocated page with the desired root page
-                                }
-
-                                //  TODO(Zaid): Update the header metadata to reflect the new root page number
                                 self.vacuum_state.write().btree_create_vacuum_full_state =
                                     BtreeCreateVacuumFullState::PtrMapPut { allocated_page_id };
                             }
                             BtreeCreateVacuumFullState::PtrMapPut { allocated_page_id } => {
-                                //  For now map allocated_page_id since we are not swapping it with root_page_num
                                 return_if_io!(self.ptrmap_put(
                                     allocated_page_id,
                                     PtrmapType::RootPage,
@@ -2426,11 +3238,6 @@ impl Pager {
                         }
                     }
                 }
-                AutoVacuumMode::Incremental => {
-                    return Err(LimboError::InternalError(
-                        "Incremental auto-vacuum is not supported".to_string(),
-                    ));
-                }
             }
         }
     }
@@ -2642,10 +3449,13 @@ impl Pager {
     #[inline(always)]
     #[instrument(skip_all, level = Level::DEBUG)]
     pub fn begin_read_tx(&self) -> Result<()> {
-        let Some(wal) = self.wal.as_ref() else {
+        let changed = if let Some(wal) = self.wal.as_ref() {
+            wal.begin_read_tx()?
+        } else if let Some(journal) = self.journal.as_ref() {
+            journal.begin_read_tx()?
+        } else {
             return Ok(());
         };
-        let changed = wal.begin_read_tx()?;
         if changed {
             // Someone else changed the database -> assume our page cache is invalid (this is default SQLite behavior, we can probably do better with more granular invalidation)
             self.clear_page_cache(false);
@@ -2692,6 +3502,9 @@ impl Pager {
         // we should have a unique API to begin transactions, something like sqlite3BtreeBeginTrans
         return_if_io!(self.maybe_allocate_page1());
         let Some(wal) = self.wal.as_ref() else {
+            if let Some(journal) = self.journal.as_ref() {
+                journal.begin_write_tx()?;
+            }
             return Ok(IOResult::Done(()));
         };
         Ok(IOResult::Done(wal.begin_write_tx(allowed_auto_actions)?))
@@ -2702,17 +3515,29 @@ impl Pager {
     /// This is a blocking alternative to normal `begin_read_tx`.
     ///
     /// VACUUM runs on an existing database, so page 1 must already be allocated
-    /// and a WAL must be present.
+    /// and a WAL or rollback journal must be present. In the rollback-journal
+    /// modes this takes the RESERVED lock; readers are only shut out once the
+    /// commit upgrades it to EXCLUSIVE.
     pub fn begin_vacuum_blocking_tx(&self) -> Result<IOResult<()>> {
         if !self.db_initialized() {
             return Err(LimboError::InternalError(
                 "begin_vacuum_blocking_tx can be done on an initialized database (page 1 must already be allocated)".into(),
             ));
         }
-        let wal = self.wal.as_ref().ok_or_else(|| {
-            LimboError::InternalError("begin_vacuum_blocking_tx requires WAL mode".into())
-        })?;
-        wal.begin_vacuum_blocking_tx()?;
+        if let Some(journal) = self.journal.as_ref() {
+            journal.begin_read_tx()?;
+            if let Err(err) = journal.begin_write_tx() {
+                journal.end_read_tx();
+                return Err(err);
+            }
+        } else {
+            let wal = self.wal.as_ref().ok_or_else(|| {
+                LimboError::InternalError(
+                    "begin_vacuum_blocking_tx requires WAL or rollback journal mode".into(),
+                )
+            })?;
+            wal.begin_vacuum_blocking_tx()?;
+        }
         // let's be conservative and clear all cache for vacuum
         // todo: clear cache only if we detect that new writes have occurred like `begin_read_tx`
         self.clear_page_cache(false);
@@ -2734,6 +3559,9 @@ impl Pager {
             return Ok(IOResult::Done(()));
         }
         let Some(wal) = self.wal.as_ref() else {
+            if let Some(journal) = self.journal.as_ref() {
+                return self.commit_tx_journal(journal, connection, update_transaction_state);
+            }
             // TODO: Unsure what the semantics of "end_tx" is for in-memory databases, ephemeral tables and ephemeral indexes.
             self.clear_savepoints()?;
             return Ok(IOResult::Done(()));
@@ -2806,20 +3634,62 @@ impl Pager {
         }
     }
 
+    /// [Pager::commit_tx] for the rollback journal modes.
+    fn commit_tx_journal(
+        &self,
+        journal: &RollbackJournal,
+        connection: &Connection,
+        update_transaction_state: bool,
+    ) -> Result<IOResult<()>> {
+        return_if_io!(self.commit_dirty_pages(
+            connection.wal_auto_actions(),
+            connection.get_sync_mode(),
+            connection.get_data_sync_retry(),
+        ));
+        let schema_did_change = match connection.get_tx_state() {
+            TransactionState::Write { schema_did_change } => schema_did_change,
+            _ => false,
+        };
+        journal.end_write_tx();
+        journal.end_read_tx();
+        if schema_did_change {
+            let schema = connection.schema.read().clone();
+            connection.db.update_schema_if_newer(schema);
+        }
+        if update_transaction_state {
+            connection.set_tx_state(TransactionState::None);
+        }
+        self.commit_dirty_pages_end();
+        self.clear_savepoints()?;
+        Ok(IOResult::Done(()))
+    }
+
     #[instrument(skip_all, level = Level::DEBUG)]
     pub fn rollback_tx(&self, connection: &Connection) {
         if connection.is_nested_stmt() {
             // Parent statement will handle the transaction rollback.
             return;
         }
-        let Some(wal) = self.wal.as_ref() else {
-            // TODO: Unsure what the semantics of "end_tx" is for in-memory databases, ephemeral tables and ephemeral indexes.
-            return;
-        };
         let (is_write, schema_did_change) = match connection.get_tx_state() {
             TransactionState::Write { schema_did_change } => (true, schema_did_change),
             _ => (false, false),
         };
+        let Some(wal) = self.wal.as_ref() else {
+            if let Some(journal) = self.journal.as_ref() {
+                // Nothing reaches the database file before commit, so dropping the
+                // dirty pages is the whole rollback.
+                if is_write {
+                    self.clear_savepoints().expect(
+                        "in practice, clear_savepoints() should never fail as it uses memory IO",
+                    );
+                }
+                self.rollback(schema_did_change, connection, is_write);
+                journal.end_write_tx();
+                journal.end_read_tx();
+            }
+            // TODO: Unsure what the semantics of "end_tx" is for in-memory databases, ephemeral tables and ephemeral indexes.
+            return;
+        };
         tracing::trace!("rollback_tx(schema_did_change={})", schema_did_change);
         if is_write {
             self.clear_savepoints()
@@ -2837,6 +3707,12 @@ impl Pager {
 
     pub(crate) fn cleanup_read_tx(&self) {
         let Some(wal) = self.wal.as_ref() else {
+            if let Some(journal) = self.journal.as_ref() {
+                self.reset_internal_states();
+                if !journal.holds_write_lock() {
+                    journal.end_read_tx();
+                }
+            }
             return;
         };
         self.reset_internal_states();
@@ -2848,6 +3724,9 @@ impl Pager {
     #[instrument(skip_all, level = Level::DEBUG)]
     pub fn end_read_tx(&self) {
         let Some(wal) = self.wal.as_ref() else {
+            if let Some(journal) = self.journal.as_ref() {
+                journal.end_read_tx();
+            }
             return;
         };
         wal.end_read_tx();
@@ -2856,6 +3735,9 @@ impl Pager {
     /// End just the write transaction on the WAL, without affecting the read lock.
     pub fn end_write_tx(&self) {
         let Some(wal) = self.wal.as_ref() else {
+            if let Some(journal) = self.journal.as_ref() {
+                journal.end_write_tx();
+            }
             return;
         };
         wal.end_write_tx();
@@ -2864,14 +3746,20 @@ impl Pager {
     /// Returns true if this pager's WAL currently holds a read lock.
     pub fn holds_read_lock(&self) -> bool {
         let Some(wal) = self.wal.as_ref() else {
-            return false;
+            return self
+                .journal
+                .as_ref()
+                .is_some_and(|journal| journal.holds_read_lock());
         };
         wal.holds_read_lock()
     }
 
     pub fn holds_write_lock(&self) -> bool {
         let Some(wal) = self.wal.as_ref() else {
-            return false;
+            return self
+                .journal
+                .as_ref()
+                .is_some_and(|journal| journal.holds_write_lock());
         };
         wal.holds_write_lock()
     }
@@ -2880,6 +3768,19 @@ impl Pager {
     /// Unlike rollback_tx, this doesn't modify connection-level state.
     pub fn rollback_attached(&self) {
         let Some(wal) = self.wal.as_ref() else {
+            if let Some(journal) = self.journal.as_ref() {
+                if journal.holds_write_lock() {
+                    self.clear_savepoints()
+                        .expect("clear_savepoints should not fail for attached DB");
+                    self.clear_page_cache(true);
+                    self.dirty_pages.write().clear();
+                    self.reset_internal_states();
+                    self.set_schema_cookie(None);
+                } else {
+                    self.reset_internal_states();
+                }
+                journal.end_read_tx();
+            }
             return;
         };
         let is_write = wal.holds_write_lock();
@@ -3027,11 +3928,61 @@ impl Pager {
                     Err(e) => Err(e.into()),
                 }
             }
+            IOResult::Done(false) if self.journal.is_some() => {
+                let mut page_cache = self.page_cache.write();
+                Self::grow_cache(&mut page_cache);
+                page_cache.insert(PageCacheKey::new(page_idx), page)?;
+                Ok(IOResult::Done(()))
+            }
             IOResult::Done(false) => Err(LimboError::Busy),
             IOResult::IO(c) => Ok(IOResult::IO(c)),
         }
     }
 
+    /// Rollback-journal VACUUM: makes `image` the new content of `page_id` in the
+    /// current write transaction. The journal commit saves the original first.
+    pub(crate) fn stage_vacuum_page(&self, page_id: usize, image: &[u8]) -> Result<()> {
+        turso_assert!(
+            self.journal.is_some(),
+            "VACUUM stages pages only in the rollback journal modes"
+        );
+        let page = allocate_new_page(page_id as i64, &self.buffer_pool);
+        page.get_contents().as_ptr().copy_from_slice(image);
+        {
+            let mut page_cache = self.page_cache.write();
+            let key = PageCacheKey::new(page_id);
+            match page_cache.insert(key, page.clone()) {
+                Err(CacheError::Full) => {
+                    Self::grow_cache(&mut page_cache);
+                    page_cache.insert(key, page.clone())?;
+                }
+                result => result?,
+            }
+        }
+        self.add_dirty(&page)
+    }
+
+    /// Rollback-journal VACUUM: stages the pages past the end of the compacted
+    /// image, so the journal saves them before the commit truncates the file.
+    /// Their content is never written.
+    pub(crate) fn stage_vacuum_truncated_pages(&self, new_db_size: u32) -> Result<()> {
+        let page_size = self.get_page_size_unchecked().get() as usize;
+        let db_size = (self.db_file.size()? / page_size as u64) as u32;
+        let blank = vec![0; page_size];
+        for page_id in new_db_size + 1..=db_size {
+            self.stage_vacuum_page(page_id as usize, &blank)?;
+        }
+        Ok(())
+    }
+
+    /// Doubles the cache capacity. In the rollback journal modes the whole
+    /// transaction has to fit in the cache, which grows instead of spilling.
+    fn grow_cache(page_cache: &mut PageCache) {
+        let capacity = page_cache.capacity().max(1) * 2;
+        tracing::debug!("growing page cache to {capacity} pages");
+        let _ = page_cache.resize(capacity);
+    }
+
     // Get a page from the cache, if it exists.
     pub fn cache_get(&self, page_idx: usize) -> Result<Option<PageRef>> {
         tracing::trace!("read_page(page_idx = {})", page_idx);
@@ -3384,6 +4335,11 @@ impl Pager {
     /// For ephemeral tables: writes pages directly to the temp database file.
     #[instrument(skip_all, level = Level::DEBUG)]
     pub fn try_spill_dirty_pages(&self) -> Result<IOResult<bool>> {
+        if self.journal.is_some() {
+            // Rollback journal commits write the database file in place, so dirty
+            // pages cannot leave the cache before commit.
+            return Ok(IOResult::Done(false));
+        }
         let state = self.spill_state.read().clone();
         match state {
             SpillState::Idle => {
@@ -3581,6 +4537,13 @@ impl Pager {
     /// Check if the cache needs spilling and attempt to spill if necessary.
     /// This should be called before inserting new pages into the cache.
     pub fn ensure_cache_space(&self) -> Result<IOResult<()>> {
+        if self.journal.is_some() {
+            let mut cache = self.page_cache.write();
+            if cache.make_room_for(1, false).is_err() {
+                Self::grow_cache(&mut cache);
+            }
+            return Ok(IOResult::Done(()));
+        }
         let needs_spill = {
             let cache = self.page_cache.read();
             cache.needs_spill()
@@ -3649,6 +4612,261 @@ impl Pager {
         self.commit_info.write().reset();
     }
 
+    /// Commit dirty pages in one of the rollback journal modes.
+    ///
+    /// The original content of every page about to be overwritten is saved in
+    /// the journal (kept in memory for MEMORY mode), then the dirty pages are
+    /// written to the database file in place and the journal is finalized. If
+    /// the commit fails after the database file was touched, the original pages
+    /// are written back before the error is returned.
+    fn commit_dirty_pages_journal(
+        &self,
+        journal: &RollbackJournal,
+        sync_mode: SyncMode,
+        data_sync_retry: bool,
+    ) -> Result<IOResult<()>> {
+        let result = self.commit_dirty_pages_journal_inner(journal, sync_mode, data_sync_retry);
+        match result {
+            Ok(IOResult::IO(_)) => {}
+            Ok(IOResult::Done(())) => {
+                *journal.commit.write() = journal::JournalCommit::default();
+            }
+            Err(ref err) => {
+                let commit = std::mem::take(&mut *journal.commit.write());
+                if commit.db_written {
+                    tracing::error!("journal commit failed after writing the database: {err}");
+                    self.restore_journaled_pages(journal, &commit)?;
+                }
+            }
+        }
+        result
+    }
+
+    fn commit_dirty_pages_journal_inner(
+        &self,
+        journal: &RollbackJournal,
+        sync_mode: SyncMode,
+        data_sync_retry: bool,
+    ) -> Result<IOResult<()>> {
+        let mode = journal.mode();
+        let page_size = self.get_page_size_unchecked().get() as usize;
+        loop {
+            let mut commit = journal.commit.write();
+            let polled = commit.poll();
+            if let Err(err) = &polled {
+                // The database sync is the only I/O in flight when entering FinalizeJournal.
+                if commit.phase == JournalCommitPhase::FinalizeJournal && !data_sync_retry {
+                    panic!("fsync error (data_sync_retry=off): {err}");
+                }
+            }
+            if let Some(c) = polled? {
+                io_yield_one!(c);
+            }
+            trace!(phase = ?commit.phase);
+
+            match commit.phase {
+                JournalCommitPhase::Start => {
+                    journal.begin_commit()?;
+                    if self.dirty_pages.read().is_empty() {
+                        return Ok(IOResult::Done(()));
+                    }
+                    drop(commit);
+                    // SQLite bumps the file change counter on every commit made
+                    // outside of WAL mode.
+                    return_if_io!(self.with_header_mut(|header| {
+                        let change_counter = header.change_counter.get().wrapping_add(1);
+                        header.change_counter = change_counter.into();
+                        header.version_valid_for = change_counter.into();
+                    }));
+                    let mut commit = journal.commit.write();
+                    commit.initial_db_size = (self.db_file.size()? / page_size as u64) as u32;
+                    let mut cache = self.page_cache.write();
+                    for page_id in self.dirty_pages.read().iter() {
+                        let page = cache
+                            .peek(&PageCacheKey::new(page_id as usize), false)
+                            .ok_or_else(|| {
+                                LimboError::InternalError(format!(
+                                    "dirty page {page_id} is not in the page cache at commit time"
+                                ))
+                            })?;
+                        if mode != JournalMode::Off && page_id <= commit.initial_db_size {
+                            let original = Arc::new(Buffer::new_temporary(page_size));
+                            let c = self.db_file.read_page(
+                                page_id as usize,
+                                &journal::raw_io_context(),
+                                Completion::new_read(original.clone(), |_| None),
+                            )?;
+                            commit.originals.push((page_id, original));
+                            commit.completions.push(c);
+                        }
+                        commit.pages.push(page);
+                    }
+                    commit.phase = JournalCommitPhase::ReadOriginals;
+                }
+                JournalCommitPhase::ReadOriginals => {
+                    commit.phase = match mode {
+                        JournalMode::Memory | JournalMode::Off => JournalCommitPhase::WriteDb,
+                        _ => JournalCommitPhase::WriteJournal,
+                    };
+                }
+                JournalCommitPhase::WriteJournal => {
+                    // With synchronous=OFF the record count is never written, so
+                    // recovery reads records until the end of the file.
+                    let n_rec = if sync_mode == SyncMode::Off {
+                        journal::JOURNAL_NREC_UNTIL_EOF
+                    } else {
+                        0
+                    };
+                    let data = journal::encode_journal(
+                        n_rec,
+                        self.io.generate_random_number() as u32,
+                        commit.initial_db_size,
+                        page_size,
+                        &commit.originals,
+                    );
+                    let file = journal.open_file(&self.io)?;
+                    let c = file.pwrite(
+                        0,
+                        Arc::new(Buffer::new(data)),
+                        Completion::new_write(|_| {}),
+                    )?;
+                    commit.completions.push(c);
+                    commit.phase = match sync_mode {
+                        SyncMode::Off => JournalCommitPhase::WriteDb,
+                        SyncMode::Normal => JournalCommitPhase::WriteRecordCount,
+                        SyncMode::Full => JournalCommitPhase::SyncJournalRecords,
+                    };
+                }
+                JournalCommitPhase::SyncJournalRecords => {
+                    let file = journal.open_file(&self.io)?;
+                    let c = file.sync(Completion::new_sync(|_| {}), self.get_sync_type())?;
+                    commit.completions.push(c);
+                    commit.phase = JournalCommitPhase::WriteRecordCount;
+                }
+                JournalCommitPhase::WriteRecordCount => {
+                    let n_rec = commit.originals.len() as u32;
+                    let file = journal.open_file(&self.io)?;
+                    let c = file.pwrite(
+                        8,
+                        Arc::new(Buffer::new(n_rec.to_be_bytes().to_vec())),
+                        Completion::new_write(|_| {}),
+                    )?;
+                    commit.completions.push(c);
+                    commit.phase = JournalCommitPhase::SyncJournal;
+                }
+                JournalCommitPhase::SyncJournal => {
+                    let file = journal.open_file(&self.io)?;
+                    let c = file.sync(Completion::new_sync(|_| {}), self.get_sync_type())?;
+                    commit.completions.push(c);
+                    commit.phase = JournalCommitPhase::WriteDb;
+                }
+                JournalCommitPhase::WriteDb => {
+                    let db_size = self.dirty_header_database_size(&commit.pages);
+                    let pending_byte_page = self.pending_byte_page_id();
+                    commit.db_written = true;
+                    let mut completions = Vec::with_capacity(commit.pages.len());
+                    for page in commit.pages.iter() {
+                        let page_id = page.get().id as u32;
+                        if page_id > db_size || Some(page_id) == pending_byte_page {
+                            page.clear_dirty();
+                            continue;
+                        }
+                        completions.push(begin_write_btree_page(self, page)?);
+                    }
+                    commit.completions = completions;
+                    commit.phase = JournalCommitPhase::TruncateDb;
+                }
+                JournalCommitPhase::TruncateDb => {
+                    let db_size = self.dirty_header_database_size(&commit.pages);
+                    if db_size < commit.initial_db_size {
+                        let c = self.db_file.truncate(
+                            db_size as usize * page_size,
+                            Completion::new_trunc(|_| {}),
+                        )?;
+                        commit.completions.push(c);
+                    }
+                    commit.phase = JournalCommitPhase::SyncDb;
+                }
+                JournalCommitPhase::SyncDb => {
+                    if sync_mode != SyncMode::Off {
+                        let c = self
+                            .db_file
+                            .sync(Completion::new_sync(|_| {}), self.get_sync_type())?;
+                        commit.completions.push(c);
+                    }
+                    commit.phase = JournalCommitPhase::FinalizeJournal;
+                }
+                JournalCommitPhase::FinalizeJournal => {
+                    if let Some(c) = journal.finalize(&self.io, self.journal_size_limit_bytes())? {
+                        commit.completions.push(c);
+                    }
+                    commit.phase = JournalCommitPhase::SyncFinalizedJournal;
+                }
+                JournalCommitPhase::SyncFinalizedJournal => {
+                    if sync_mode == SyncMode::Full
+                        && matches!(mode, JournalMode::Truncate | JournalMode::Persist)
+                    {
+                        let file = journal.open_file(&self.io)?;
+                        let c = file.sync(Completion::new_sync(|_| {}), self.get_sync_type())?;
+                        commit.completions.push(c);
+                    }
+                    commit.phase = JournalCommitPhase::Done;
+                }
+                JournalCommitPhase::Done => {
+                    self.dirty_pages.write().clear();
+                    journal.mark_committed();
+                    return Ok(IOResult::Done(()));
+                }
+            }
+        }
+    }
+
+    /// Database size recorded in the (dirty) page 1 of a commit.
+    fn dirty_header_database_size(&self, pages: &[PageRef]) -> u32 {
+        let page1 = pages
+            .iter()
+            .find(|page| page.get().id == DatabaseHeader::PAGE_ID)
+            .expect("journal commits always modify page 1");
+        let header: &DatabaseHeader =
+            bytemuck::from_bytes(&page1.get_contents().as_ptr()[..DatabaseHeader::SIZE]);
+        header.database_size.get()
+    }
+
+    /// Puts back the original content of the pages touched by a failed journal
+    /// commit, then finalizes the journal so it is not mistaken for a hot one.
+    fn restore_journaled_pages(
+        &self,
+        journal: &RollbackJournal,
+        commit: &journal::JournalCommit,
+    ) -> Result<()> {
+        let page_size = self.get_page_size_unchecked().get() as usize;
+        let raw_ctx = journal::raw_io_context();
+        for (page_id, original) in commit.originals.iter() {
+            let c = self.db_file.write_page(
+                *page_id as usize,
+                original.clone(),
+                &raw_ctx,
+                Completion::new_write(|_| {}),
+            )?;
+            self.io.wait_for_completion(c)?;
+        }
+        let c = self.db_file.truncate(
+            commit.initial_db_size as usize * page_size,
+            Completion::new_trunc(|_| {}),
+        )?;
+        self.io.wait_for_completion(c)?;
+        let c = self
+            .db_file
+            .sync(Completion::new_sync(|_| {}), self.get_sync_type())?;
+        self.io.wait_for_completion(c)?;
+        if let Some(c) = journal.finalize(&self.io, self.journal_size_limit_bytes())? {
+            self.io.wait_for_completion(c)?;
+        }
+        self.clear_page_cache(true);
+        self.set_schema_cookie(None);
+        Ok(())
+    }
+
     #[instrument(skip_all, level = Level::DEBUG)]
     fn commit_dirty_pages_inner(
         &self,
@@ -3656,6 +4874,9 @@ impl Pager {
         sync_mode: SyncMode,
         data_sync_retry: bool,
     ) -> Result<IOResult<()>> {
+        if let Some(journal) = self.journal.as_ref() {
+            return self.commit_dirty_pages_journal(journal, sync_mode, data_sync_retry);
+        }
         let Some(wal) = self.wal.as_ref() else {
             turso_soft_unreachable!("commit_dirty_pages() called without WAL");
             return Err(LimboError::InternalError(
@@ -3915,8 +5136,10 @@ impl Pager {
                     self.dirty_pages.write().clear();
                     commit_info.prepared_frames.clear();
 
+                    let autocheckpoint = self.get_wal_autocheckpoint();
                     let need_checkpoint = allowed_auto_actions.contains(WalAutoActions::Checkpoint)
-                        && wal.should_checkpoint();
+                        && autocheckpoint > 0
+                        && wal.should_checkpoint(autocheckpoint as u64);
                     if need_checkpoint {
                         commit_info.state = CommitState::AutoCheckpoint;
                     }
@@ -4581,6 +5804,10 @@ impl Pager {
                             "Invalid page number {page_id} for free operation"
                         )));
                     }
+                    #[cfg(not(feature = "omit_autovacuum"))]
+                    if self.ptrmap_enabled() {
+                        return_if_io!(self.ptrmap_put(page_id as u32, PtrmapType::FreePage, 0));
+                    }
 
                     let (page, c) = match page.take() {
                         Some(page) => {
@@ -4796,28 +6023,26 @@ impl Pager {
                     let new_db_size = old_db_size;
 
                     tracing::debug!("allocate_page(database_size={})", new_db_size);
-                    #[cfg(not(feature = "omit_autovacuum"))]
-                    {
-                        //  If the following conditions are met, allocate a pointer map page, add to cache and increment the database size
-                        //  - autovacuum is enabled
-                        //  - the last page is a pointer map page
-                        if matches!(
-                            AutoVacuumMode::from(self.auto_vacuum_mode.load(Ordering::SeqCst)),
-                            AutoVacuumMode::Full
-                        ) && is_ptrmap_page(new_db_size + 1, header.page_size.get() as usize)
-                        {
-                            // we will allocate a ptrmap page, so increment size
-                            new_db_size += 1;
-                            let page = allocate_new_page(new_db_size as i64, &self.buffer_pool);
-                            self.add_dirty(&page)?;
-                            let page_key = PageCacheKey::new(page.get().id as usize);
-                            let mut cache = self.page_cache.write();
-                            cache.insert(page_key, page)?;
-                        }
-                    }
 
                     let first_freelist_trunk_page_id = header.freelist_trunk_page.get();
                     if first_freelist_trunk_page_id == 0 {
+                        #[cfg(not(feature = "omit_autovacuum"))]
+                        {
+                            //  If the following conditions are met, allocate a pointer map page, add to cache and increment the database size
+                            //  - autovacuum is enabled
+                            //  - the database is growing and the next page is a pointer map page
+                            if self.ptrmap_enabled()
+                                && is_ptrmap_page(new_db_size + 1, header.page_size.get() as usize)
+                            {
+                                // we will allocate a ptrmap page, so increment size
+                                new_db_size += 1;
+                                let page = allocate_new_page(new_db_size as i64, &self.buffer_pool);
+                                self.add_dirty(&page)?;
+                                let page_key = PageCacheKey::new(page.get().id as usize);
+                                let mut cache = self.page_cache.write();
+                                cache.insert(page_key, page)?;
+                            }
+                        }
                         *state = AllocatePageState::AllocateNewPage {
                             current_db_size: new_db_size,
                         };
@@ -5096,6 +6321,11 @@ impl Pager {
             let mut vacuum_state = self.vacuum_state.write();
             vacuum_state.ptrmap_get_state = PtrMapGetState::Start;
             vacuum_state.ptrmap_put_state = PtrMapPutState::Start;
+            vacuum_state.ptrmap_put_entries_state = PtrMapPutEntriesState::Start;
+            vacuum_state.take_free_page_state = TakeFreePageState::Start;
+            vacuum_state.relocate_page_state = RelocatePageState::Start;
+            vacuum_state.allocate_root_page_state = AllocateRootPageState::Start;
+            vacuum_state.incremental_vacuum_state = IncrementalVacuumState::Start;
             vacuum_state.btree_create_vacuum_full_state = BtreeCreateVacuumFullState::Start;
         }
 
@@ -5252,6 +6482,17 @@ impl CreateBTreeFlags {
     }
 }
 
+/// Returns the first overflow page of a cell's payload, if it spills.
+#[cfg(not(feature = "omit_autovacuum"))]
+fn cell_first_overflow_page(cell: &BTreeCell) -> Option<u32> {
+    match cell {
+        BTreeCell::TableInteriorCell(_) => None,
+        BTreeCell::TableLeafCell(cell) => cell.first_overflow_page,
+        BTreeCell::IndexInteriorCell(cell) => cell.first_overflow_page,
+        BTreeCell::IndexLeafCell(cell) => cell.first_overflow_page,
+    }
+}
+
 /*
 ** The pointer map is a lookup table that identifies the parent page for
 ** each child page in the database file.  The parent page is the page that
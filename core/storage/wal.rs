@@ -706,7 +706,8 @@ pub trait Wal: Debug + Send + Sync {
     /// all changes were stored locally.
     fn finish_append_frames_commit(&self) -> Result<()>;
 
-    fn should_checkpoint(&self) -> bool;
+    /// Whether more than `threshold` frames are waiting to be backfilled.
+    fn should_checkpoint(&self, threshold: u64) -> bool;
     fn checkpoint(&self, pager: &Pager, mode: CheckpointMode)
         -> Result<IOResult<CheckpointResult>>;
     fn install_durable_backfill_proof(
@@ -2540,7 +2541,6 @@ pub struct WalFile {
     write_lock_held: AtomicBool,
 
     ongoing_checkpoint: RwLock<OngoingCheckpoint>,
-    checkpoint_threshold: usize,
     /// This is the index to the read_lock in WalFileShared that we are holding. This lock contains
     /// the max frame for this connection.
     max_frame_read_lock_index: AtomicUsize,
@@ -2575,7 +2575,6 @@ impl fmt::Debug for WalFile {
             .field("syncing", &self.syncing.load(Ordering::Relaxed))
             .field("page_size", &self.page_size())
             .field("ongoing_checkpoint", &*self.ongoing_checkpoint.read())
-            .field("checkpoint_threshold", &self.checkpoint_threshold)
             .field("max_frame_read_lock_index", &self.max_frame_read_lock_index)
             .field("max_frame", &self.max_frame)
             .field("min_frame", &self.min_frame)
@@ -3686,9 +3685,9 @@ impl Wal for WalFile {
     }
 
     #[instrument(skip_all, level = Level::DEBUG)]
-    fn should_checkpoint(&self) -> bool {
+    fn should_checkpoint(&self, threshold: u64) -> bool {
         let snapshot = self.load_coordination_snapshot();
-        snapshot.max_frame as usize > self.checkpoint_threshold + snapshot.nbackfills as usize
+        snapshot.max_frame > threshold + snapshot.nbackfills
     }
 
     #[instrument(skip_all, level = Level::DEBUG)]
@@ -3957,6 +3956,8 @@ impl Wal for WalFile {
         // them so that classify_authority_snapshot_against_wal does not see a
         // length mismatch and unnecessarily fall back to a full disk scan
         // (which can race with concurrent writers and corrupt the authority).
+        // This is also where SQLite applies `PRAGMA journal_size_limit` to a
+        // restarted log; cutting back to the header satisfies any limit.
         let should_skip_truncate = match file.size() {
             Ok(size) => size <= WAL_HEADER_SIZE as u64,
             Err(_) => {
@@ -4360,7 +4361,6 @@ impl WalFile {
                 pages_to_checkpoint: Vec::new(),
                 inflight_reads: Vec::with_capacity(MAX_INFLIGHT_READS),
             }),
-            checkpoint_threshold: 1000,
             buffer_pool,
             checkpoint_seq: AtomicU32::new(0),
             syncing: Arc::new(AtomicBool::new(false)),
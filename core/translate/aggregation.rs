@@ -2,11 +2,11 @@ use turso_parser::ast;
 
 use crate::{
     function::AggFunc,
-    schema::Table,
+    schema::{PseudoCursorType, Table},
     translate::collate::CollationSeq,
     vdbe::{
-        builder::ProgramBuilder,
-        insn::{HashDistinctData, Insn},
+        builder::{CursorType, ProgramBuilder},
+        insn::{to_u16, HashDistinctData, Insn},
     },
     LimboError, Result,
 };
@@ -33,6 +33,15 @@ pub fn emit_ungrouped_aggregation<'a>(
 
     for (i, agg) in plan.aggregates.iter().enumerate() {
         let agg_result_reg = agg_start_reg + i;
+        if agg.is_ordered() {
+            emit_ordered_aggregation_steps(
+                program,
+                &plan.table_references,
+                agg,
+                agg_result_reg,
+                &t_ctx.resolver,
+            )?;
+        }
         program.emit_insn(Insn::AggFinal {
             register: agg_result_reg,
             func: agg.func.clone(),
@@ -593,6 +602,130 @@ pub fn translate_aggregation_step(
     Ok(dest)
 }
 
+/// Emits the bytecode for buffering one row of input of an aggregate with an ORDER BY clause,
+/// e.g. `group_concat(x ORDER BY y)`. Instead of stepping the aggregate, the ORDER BY keys and
+/// the aggregate arguments are inserted into the aggregate's sorter; they are fed to the
+/// aggregate by [emit_ordered_aggregation_steps] once the group is complete.
+pub fn emit_ordered_aggregation_insert(
+    program: &mut ProgramBuilder,
+    referenced_tables: &TableReferences,
+    agg_arg_source: AggArgumentSource,
+    aggregate: &Aggregate,
+    resolver: &Resolver,
+) -> Result<()> {
+    let cursor_id = aggregate
+        .sorter_cursor_id
+        .expect("ordered aggregate sorter not initialized");
+    let num_keys = aggregate.order_by.len();
+    let num_args = agg_arg_source.num_args();
+    let start_reg = program.alloc_registers(num_keys + num_args);
+    for (i, (expr, _, _)) in aggregate.order_by.iter().enumerate() {
+        translate_expr(
+            program,
+            Some(referenced_tables),
+            expr,
+            start_reg + i,
+            resolver,
+        )?;
+    }
+    for i in 0..num_args {
+        match &agg_arg_source {
+            AggArgumentSource::Register { src_reg_start, .. } => {
+                program.emit_insn(Insn::Copy {
+                    src_reg: src_reg_start + i,
+                    dst_reg: start_reg + num_keys + i,
+                    extra_amount: 0,
+                });
+            }
+            AggArgumentSource::Expression { args, .. } => {
+                translate_expr(
+                    program,
+                    Some(referenced_tables),
+                    &args[i],
+                    start_reg + num_keys + i,
+                    resolver,
+                )?;
+            }
+        }
+    }
+    let record_reg = program.alloc_register();
+    program.emit_insn(Insn::MakeRecord {
+        start_reg: to_u16(start_reg),
+        count: to_u16(num_keys + num_args),
+        dest_reg: to_u16(record_reg),
+        index_name: None,
+        affinity_str: None,
+    });
+    program.emit_insn(Insn::SorterInsert {
+        cursor_id,
+        record_reg,
+    });
+    Ok(())
+}
+
+/// Emits the bytecode for stepping an aggregate with an ORDER BY clause over the rows buffered
+/// by [emit_ordered_aggregation_insert], in sorted order. The sorter is reset afterwards so that
+/// the next group starts out empty. This must run right before the aggregate is finalized.
+pub fn emit_ordered_aggregation_steps(
+    program: &mut ProgramBuilder,
+    referenced_tables: &TableReferences,
+    aggregate: &Aggregate,
+    target_register: usize,
+    resolver: &Resolver,
+) -> Result<()> {
+    let cursor_id = aggregate
+        .sorter_cursor_id
+        .expect("ordered aggregate sorter not initialized");
+    let num_keys = aggregate.order_by.len();
+    let num_columns = num_keys + aggregate.args.len();
+    let pseudo_cursor = program.alloc_cursor_id(CursorType::Pseudo(PseudoCursorType {
+        column_count: num_columns,
+    }));
+    let reg_record = program.alloc_register();
+    program.emit_insn(Insn::OpenPseudo {
+        cursor_id: pseudo_cursor,
+        content_reg: reg_record,
+        num_fields: num_columns,
+    });
+
+    let label_loop_start = program.allocate_label();
+    let label_loop_end = program.allocate_label();
+    program.emit_insn(Insn::SorterSort {
+        cursor_id,
+        pc_if_empty: label_loop_end,
+    });
+    program.preassign_label_to_next_insn(label_loop_start);
+    program.emit_insn(Insn::SorterData {
+        cursor_id,
+        dest_reg: reg_record,
+        pseudo_cursor,
+    });
+    let reg_args = program.alloc_registers(aggregate.args.len().max(1));
+    for i in 0..aggregate.args.len() {
+        program.emit_column_or_rowid(pseudo_cursor, num_keys + i, reg_args + i);
+    }
+    translate_aggregation_step(
+        program,
+        referenced_tables,
+        AggArgumentSource::new_from_registers(reg_args, aggregate),
+        target_register,
+        resolver,
+    )?;
+    if let Distinctness::Distinct { ctx } = &aggregate.distinctness {
+        let ctx = ctx
+            .as_ref()
+            .expect("distinct aggregate context not populated");
+        program.preassign_label_to_next_insn(ctx.label_on_conflict);
+    }
+    program.emit_insn(Insn::SorterNext {
+        cursor_id,
+        pc_if_next: label_loop_start,
+    });
+    program.preassign_label_to_next_insn(label_loop_end);
+    program.emit_insn(Insn::ResetSorter { cursor_id });
+    Ok(())
+}
+
 fn translate_const_arg(
     program: &mut ProgramBuilder,
     referenced_tables: &TableReferences,
@@ -351,10 +351,12 @@ fn strict_default_type_mismatch(column: &Column) -> Result<bool> {
     Ok(!ok)
 }
 
-fn emit_add_column_default_type_validation(
+/// Halt with `description` if `original_btree` has any rows.
+fn emit_add_column_empty_table_check(
     program: &mut ProgramBuilder,
     original_btree: &Arc<BTreeTable>,
     database_id: usize,
+    description: &str,
 ) -> Result<()> {
     let check_cursor_id = program.alloc_cursor_id(CursorType::BTreeTable(original_btree.clone()));
     program.emit_insn(Insn::OpenRead {
@@ -371,7 +373,7 @@ fn emit_add_column_default_type_validation(
 
     program.emit_insn(Insn::Halt {
         err_code: 1,
-        description: "type mismatch on DEFAULT".to_string(),
+        description: description.to_string(),
         on_error: None,
         description_reg: None,
     });
@@ -899,11 +901,11 @@ pub fn translate_alter_table(
                 }
             }
 
-            // Check if any virtual column depends on the dropped column
+            // Check if any generated column depends on the dropped column
             {
                 let affected = btree.columns_affected_by_update([dropped_index])?;
                 for idx in &affected {
-                    if idx != dropped_index && btree.columns()[idx].is_virtual_generated() {
+                    if idx != dropped_index && btree.columns()[idx].is_generated() {
                         return Err(LimboError::ParseError(format!(
                             "error in table {table_name} after drop column: no such column: {column_name}"
                         )));
@@ -1041,20 +1043,6 @@ pub fn translate_alter_table(
                 .constraints
                 .iter()
                 .any(|c| matches!(c.constraint, ast::ColumnConstraint::Generated { .. }));
-            let is_stored = col_def.constraints.iter().any(|c| {
-                matches!(
-                    c.constraint,
-                    ast::ColumnConstraint::Generated {
-                        typ: Some(ast::GeneratedColumnType::Stored),
-                        ..
-                    }
-                )
-            });
-            if is_stored {
-                return Err(LimboError::ParseError(
-                    "cannot add a STORED column".to_string(),
-                ));
-            }
             if is_generated {
                 for c in &col_def.constraints {
                     if let ast::ColumnConstraint::Generated { expr, .. } = &c.constraint {
@@ -1264,7 +1252,16 @@ pub fn translate_alter_table(
                 ));
             };
 
-            if is_generated {
+            if column.is_stored_generated() {
+                // Existing rows have no slot for the new value, so SQLite only
+                // allows adding a STORED column to an empty table.
+                emit_add_column_empty_table_check(
+                    program,
+                    &original_btree,
+                    database_id,
+                    "cannot add a STORED column",
+                )?;
+            } else if is_generated {
                 emit_add_virtual_column_validation(
                     program,
                     &btree,
@@ -1325,7 +1322,12 @@ pub fn translate_alter_table(
                 }
 
                 if default_type_mismatch {
-                    emit_add_column_default_type_validation(program, &original_btree, database_id)?;
+                    emit_add_column_empty_table_check(
+                        program,
+                        &original_btree,
+                        database_id,
+                        "type mismatch on DEFAULT",
+                    )?;
                 }
 
                 emit_add_column_check_validation(
@@ -157,7 +157,11 @@ pub fn emit_program_for_compound_select(
         QueryDestination::CoroutineYield { .. }
         | QueryDestination::EphemeralTable { .. }
         | QueryDestination::EphemeralIndex { .. } => Some(program.alloc_registers(num_result_cols)),
-        QueryDestination::ResultRows => None,
+        // Subquery results are written by each subselect (or by the dedupe read-back loop)
+        // into the subquery's own registers, so every part can use private registers.
+        QueryDestination::ResultRows
+        | QueryDestination::ExistsSubqueryResult { .. }
+        | QueryDestination::RowValueSubqueryResult { .. } => None,
         other => {
             return Err(LimboError::InternalError(format!(
                 "Unexpected query destination: {other:?} for compound select"
@@ -582,6 +586,7 @@ fn create_dedupe_index(
         has_rowid: false,
         where_clause: None,
         index_method: None,
+        pk_suffix_start: None,
         on_conflict: None,
     });
     let cursor_id = program.alloc_cursor_id(CursorType::BTreeIndex(dedupe_index.clone()));
@@ -735,7 +740,7 @@ fn read_intersect_rows(
 
 /// Recursively sets the query_destination of all SelectPlans within a CompoundSelect.
 /// This ensures UNION ALL subselects write to the collection index instead of ResultRows.
-fn set_compound_plan_destinations(plan: &mut Plan, dest: &QueryDestination) {
+pub(crate) fn set_compound_plan_destinations(plan: &mut Plan, dest: &QueryDestination) {
     match plan {
         Plan::CompoundSelect {
             left, right_most, ..
@@ -802,6 +807,7 @@ fn create_collection_index(
         has_rowid: true, // Allow duplicates for UNION ALL
         where_clause: None,
         index_method: None,
+        pk_suffix_start: None,
         on_conflict: None,
     });
     let cursor_id = program.alloc_cursor_id(CursorType::BTreeIndex(index.clone()));
@@ -1,4 +1,4 @@
-use crate::schema::Table;
+use crate::schema::{BTreeTable, Index, IndexColumn, Table};
 use crate::sync::Arc;
 use crate::translate::emitter::{emit_program, Resolver};
 use crate::translate::expr::{process_returning_clause, walk_expr, WalkControl};
@@ -7,16 +7,21 @@ use crate::translate::plan::{
     DeletePlan, DmlSafety, DmlSafetyReason, IterationDirection, JoinOrderMember, Operation, Plan,
     QueryDestination, ResultSetColumn, Scan, SelectPlan,
 };
-use crate::translate::planner::{parse_limit, parse_where, plan_ctes_as_outer_refs};
+use crate::translate::planner::{
+    parse_dml_order_by, parse_limit, parse_where, plan_ctes_as_outer_refs,
+};
 use crate::translate::subquery::{
     plan_subqueries_from_returning, plan_subqueries_from_select_plan,
     plan_subqueries_from_where_clause,
 };
 use crate::translate::trigger_exec::has_triggers_including_temp;
+use crate::translate::view_dml::translate_delete_from_view;
 use crate::util::normalize_ident;
-use crate::vdbe::builder::{ProgramBuilder, ProgramBuilderOpts};
+use crate::vdbe::builder::{CursorType, ProgramBuilder, ProgramBuilderOpts};
 use crate::Result;
-use turso_parser::ast::{Expr, Limit, QualifiedName, ResultColumn, TriggerEvent, With};
+use turso_parser::ast::{
+    Expr, Limit, QualifiedName, ResultColumn, SortedColumn, TableInternalId, TriggerEvent, With,
+};
 
 use super::plan::{ColumnUsedMask, JoinedTable, TableReferences, WhereTerm};
 
@@ -42,17 +47,13 @@ fn validate_delete(
     if program.trigger.is_some() && table.virtual_table().is_some() {
         crate::bail_parse_error!("unsafe use of virtual table \"{}\"", tbl_name);
     }
-    if table.btree().is_some_and(|bt| !bt.has_rowid) {
-        crate::bail_parse_error!("DELETE from WITHOUT ROWID tables is not supported");
-    }
-
     // Check if this is a materialized view
-    if resolver.schema().is_materialized_view(tbl_name) {
+    if resolver.with_schema(database_id, |s| s.is_materialized_view(tbl_name)) {
         crate::bail_parse_error!("cannot modify materialized view {}", tbl_name);
     }
 
     // Check if this table has any incompatible dependent views
-    resolver.schema().with_incompatible_dependent_views(tbl_name, |views| {
+    resolver.with_schema(database_id, |s| s.with_incompatible_dependent_views(tbl_name, |views| {
     if !views.is_empty() {
         use crate::incremental::compiler::DBSP_CIRCUIT_VERSION;
         crate::bail_parse_error!(
@@ -63,7 +64,7 @@ fn validate_delete(
         );
     }
     Ok(())
-    })?;
+    }))?;
     Ok(table)
 }
 
@@ -73,6 +74,7 @@ pub fn translate_delete(
     tbl_name: &QualifiedName,
     resolver: &Resolver,
     where_clause: Option<Box<Expr>>,
+    order_by: Vec<SortedColumn>,
     limit: Option<Limit>,
     returning: Vec<ResultColumn>,
     indexed: Option<turso_parser::ast::Indexed>,
@@ -82,6 +84,22 @@ pub fn translate_delete(
 ) -> Result<()> {
     let database_id = resolver.resolve_existing_table_database_id_qualified(tbl_name)?;
     let normalized_table_name = normalize_ident(tbl_name.name.as_str());
+    if let Some(view) = resolver.with_schema(database_id, |s| s.get_view(&normalized_table_name)) {
+        return translate_delete_from_view(
+            program,
+            resolver,
+            &view,
+            database_id,
+            tbl_name,
+            where_clause,
+            order_by,
+            limit,
+            &returning,
+            indexed,
+            with,
+            connection,
+        );
+    }
     let table = validate_delete(
         resolver,
         &normalized_table_name,
@@ -99,6 +117,7 @@ pub fn translate_delete(
         tbl_name,
         table,
         where_clause,
+        order_by,
         limit,
         returning,
         indexed,
@@ -183,6 +202,7 @@ pub fn prepare_delete_plan(
     qualified_name: &QualifiedName,
     table: Arc<Table>,
     where_clause: Option<Box<Expr>>,
+    order_by: Vec<SortedColumn>,
     limit: Option<Limit>,
     mut returning: Vec<ResultColumn>,
     indexed: Option<turso_parser::ast::Indexed>,
@@ -229,6 +249,14 @@ pub fn prepare_delete_plan(
         resolver,
     )?;
 
+    let order_by = parse_dml_order_by(
+        order_by,
+        limit.is_some(),
+        "DELETE",
+        &mut table_references,
+        resolver,
+    )?;
+
     // Plan subqueries in RETURNING expressions before processing
     // (so SubqueryResult nodes are cloned into result_columns)
     let mut non_from_clause_subqueries = vec![];
@@ -265,12 +293,15 @@ pub fn prepare_delete_plan(
     if where_clause_has_subquery(&where_predicates) {
         safety.require(DmlSafetyReason::SubqueryInWhere);
     }
+    if !order_by.is_empty() {
+        safety.require(DmlSafetyReason::OrderByLimit);
+    }
 
     let mut delete_plan = DeletePlan {
         table_references,
         result_columns,
         where_clause: where_predicates,
-        order_by: vec![],
+        order_by,
         limit: resolved_limit,
         offset: resolved_offset,
         contains_constant_false_condition: false,
@@ -351,35 +382,58 @@ fn ensure_delete_uses_rowset(program: &mut ProgramBuilder, plan: &mut DeletePlan
         return;
     }
 
-    let rowid_internal_id = plan
+    let target = plan
         .table_references
         .joined_tables()
         .first()
-        .expect("DELETE should have one target table")
-        .internal_id;
-    let rowset_reg = plan.rowset_reg.unwrap_or_else(|| {
-        let reg = program.alloc_register();
-        plan.rowset_reg = Some(reg);
-        reg
-    });
+        .expect("DELETE should have one target table");
+    let rowid_internal_id = target.internal_id;
 
-    let rowset_plan = SelectPlan {
-        table_references: plan.table_references.clone(),
-        result_columns: vec![ResultSetColumn {
-            expr: Expr::RowId {
+    // WITHOUT ROWID tables have no rowid to collect, so the write set is materialized as the
+    // PRIMARY KEY tuples of the matching rows in an ephemeral index instead of a RowSet.
+    let (result_columns, query_destination) = match target.btree().filter(|bt| !bt.has_rowid) {
+        Some(btree) => {
+            let (pk_exprs, pk_index) = without_rowid_pk_write_set(&btree, rowid_internal_id);
+            let cursor_id = program.alloc_cursor_id(CursorType::BTreeIndex(pk_index.clone()));
+            let destination = QueryDestination::EphemeralIndex {
+                cursor_id,
+                index: pk_index,
+                affinity_str: None,
+                is_delete: false,
+            };
+            (pk_exprs, destination)
+        }
+        None => {
+            let rowset_reg = plan.rowset_reg.unwrap_or_else(|| {
+                let reg = program.alloc_register();
+                plan.rowset_reg = Some(reg);
+                reg
+            });
+            let rowid_expr = Expr::RowId {
                 database: None,
                 table: rowid_internal_id,
-            },
-            alias: None,
-            implicit_column_name: None,
-            contains_aggregates: false,
-        }],
+            };
+            (vec![rowid_expr], QueryDestination::RowSet { rowset_reg })
+        }
+    };
+
+    let rowset_plan = SelectPlan {
+        table_references: plan.table_references.clone(),
+        result_columns: result_columns
+            .into_iter()
+            .map(|expr| ResultSetColumn {
+                expr,
+                alias: None,
+                implicit_column_name: None,
+                contains_aggregates: false,
+            })
+            .collect(),
         where_clause: std::mem::take(&mut plan.where_clause),
         group_by: None,
-        order_by: vec![],
+        order_by: std::mem::take(&mut plan.order_by),
         aggregates: vec![],
         limit: plan.limit.take(),
-        query_destination: QueryDestination::RowSet { rowset_reg },
+        query_destination,
         join_order: plan
             .table_references
             .joined_tables()
@@ -405,3 +459,46 @@ fn ensure_delete_uses_rowset(program: &mut ProgramBuilder, plan: &mut DeletePlan
     };
     plan.rowset_plan = Some(rowset_plan);
 }
+
+/// Build the PRIMARY KEY column references and the ephemeral index used to collect the write set
+/// of a DML statement targeting a WITHOUT ROWID table.
+pub(crate) fn without_rowid_pk_write_set(
+    btree: &BTreeTable,
+    table_internal_id: TableInternalId,
+) -> (Vec<Expr>, Arc<Index>) {
+    let mut exprs = Vec::with_capacity(btree.primary_key_columns.len());
+    let mut columns = Vec::with_capacity(btree.primary_key_columns.len());
+    for (i, (pk_name, order)) in btree.primary_key_columns.iter().enumerate() {
+        let (pos, column) = btree
+            .get_column(pk_name)
+            .expect("PRIMARY KEY column must exist in its table");
+        exprs.push(Expr::Column {
+            database: None,
+            table: table_internal_id,
+            column: pos,
+            is_rowid_alias: false,
+        });
+        columns.push(IndexColumn {
+            name: normalize_ident(pk_name),
+            order: *order,
+            pos_in_table: i,
+            collation: column.collation_opt(),
+            default: None,
+            expr: None,
+        });
+    }
+    let index = Arc::new(Index {
+        name: format!("ephemeral_pk_write_set_{}", btree.name),
+        table_name: String::new(),
+        root_page: 0,
+        columns,
+        unique: false,
+        ephemeral: true,
+        has_rowid: false,
+        where_clause: None,
+        index_method: None,
+        pk_suffix_start: None,
+        on_conflict: None,
+    });
+    (exprs, index)
+}
@@ -9,7 +9,7 @@ use crate::{
             emit_cdc_autocommit_commit, emit_cdc_full_record, emit_cdc_insns,
             emit_index_column_value_old_image, emit_program_for_select,
             get_triggers_including_temp, has_triggers_including_temp, init_limit, OperationMode,
-            TriggerTime,
+            RowLocator, TriggerTime,
         },
         expr::{
             emit_returning_results, emit_returning_scan_back, emit_table_column,
@@ -23,7 +23,7 @@ use crate::{
         main_loop::{CloseLoop, InitLoop, OpenLoop},
         plan::{
             DeletePlan, EvalAt, JoinOrderMember, JoinedTable, NonFromClauseSubquery, Operation,
-            ResultSetColumn, Search, TableReferences,
+            QueryDestination, ResultSetColumn, Search, TableReferences,
         },
         subquery::{emit_non_from_clause_subqueries_for_eval_at, emit_non_from_clause_subquery},
         trigger_exec::{fire_trigger, TriggerContext},
@@ -125,15 +125,29 @@ pub fn emit_program_for_delete(
     // If there's a rowset_plan, materialize rowids into a RowSet first and then iterate the RowSet
     // to delete the rows.
     if let Some(rowset_plan) = plan.rowset_plan.take() {
-        let rowset_reg = plan
-            .rowset_reg
-            .expect("rowset_reg must be Some if rowset_plan is Some");
-
-        // Initialize the RowSet register with NULL (RowSet will be created on first RowSetAdd)
-        program.emit_insn(Insn::Null {
-            dest: rowset_reg,
-            dest_end: None,
-        });
+        // WITHOUT ROWID tables collect PRIMARY KEY tuples into an ephemeral index instead.
+        let pk_write_set = match &rowset_plan.query_destination {
+            QueryDestination::EphemeralIndex {
+                cursor_id, index, ..
+            } => Some((*cursor_id, index.columns.len())),
+            _ => None,
+        };
+        if let Some((pk_cursor_id, _)) = pk_write_set {
+            program.emit_insn(Insn::OpenEphemeral {
+                cursor_id: pk_cursor_id,
+                is_table: false,
+            });
+        } else {
+            let rowset_reg = plan
+                .rowset_reg
+                .expect("rowset_reg must be Some if rowset_plan is Some");
+
+            // Initialize the RowSet register with NULL (RowSet will be created on first RowSetAdd)
+            program.emit_insn(Insn::Null {
+                dest: rowset_reg,
+                dest_end: None,
+            });
+        }
 
         // Execute the rowset SELECT plan to populate the rowset.
         program.nested(|program| emit_program_for_select(program, resolver, rowset_plan))?;
@@ -186,15 +200,45 @@ pub fn emit_program_for_delete(
             });
         }
 
-        program.preassign_label_to_next_insn(rowset_loop_start);
-
-        // Read next rowid from RowSet
-        // Note: rowset_loop_end will be resolved later when we assign it
-        program.emit_insn(Insn::RowSetRead {
-            rowset_reg,
-            pc_if_empty: rowset_loop_end,
-            dest_reg: rowid_reg,
-        });
+        let row = if let Some((pk_cursor_id, num_pk_cols)) = pk_write_set {
+            // There is no rowid: OLD.rowid and the CDC change id are NULL.
+            program.emit_insn(Insn::Null {
+                dest: rowid_reg,
+                dest_end: None,
+            });
+            program.emit_insn(Insn::Rewind {
+                cursor_id: pk_cursor_id,
+                pc_if_empty: rowset_loop_end,
+            });
+            program.preassign_label_to_next_insn(rowset_loop_start);
+            let pk_start_reg = program.alloc_registers(num_pk_cols);
+            for i in 0..num_pk_cols {
+                program.emit_insn(Insn::Column {
+                    cursor_id: pk_cursor_id,
+                    column: i,
+                    dest: pk_start_reg + i,
+                    default: None,
+                });
+            }
+            RowLocator::PrimaryKey {
+                start_reg: pk_start_reg,
+                num_regs: num_pk_cols,
+            }
+        } else {
+            let rowset_reg = plan
+                .rowset_reg
+                .expect("rowset_reg must be Some if rowset_plan is Some");
+            program.preassign_label_to_next_insn(rowset_loop_start);
+
+            // Read next rowid from RowSet
+            // Note: rowset_loop_end will be resolved later when we assign it
+            program.emit_insn(Insn::RowSetRead {
+                rowset_reg,
+                pc_if_empty: rowset_loop_end,
+                dest_reg: rowid_reg,
+            });
+            RowLocator::Rowid(rowid_reg)
+        };
 
         emit_delete_insns_when_triggers_present(
             connection,
@@ -204,15 +248,23 @@ pub fn emit_program_for_delete(
             &mut plan.non_from_clause_subqueries,
             &plan.result_columns,
             rowid_reg,
+            row,
             table_cursor_id,
             resolver,
             returning_buffer.as_ref(),
         )?;
 
         // Continue loop
-        program.emit_insn(Insn::Goto {
-            target_pc: rowset_loop_start,
-        });
+        if let Some((pk_cursor_id, _)) = pk_write_set {
+            program.emit_insn(Insn::Next {
+                cursor_id: pk_cursor_id,
+                pc_if_next: rowset_loop_start,
+            });
+        } else {
+            program.emit_insn(Insn::Goto {
+                target_pc: rowset_loop_start,
+            });
+        }
 
         // Assign the end label here, after all loop body code
         program.preassign_label_to_next_insn(rowset_loop_end);
@@ -288,9 +340,7 @@ pub fn emit_fk_child_decrement_on_delete(
     database_id: usize,
     resolver: &Resolver,
 ) -> crate::Result<()> {
-    for fk_ref in
-        resolver.with_schema(database_id, |s| s.resolved_fks_for_child(child_table_name))?
-    {
+    for fk_ref in resolver.resolved_fks_for_child(database_id, child_table_name)? {
         if !fk_ref.fk.deferred {
             continue;
         }
@@ -304,7 +354,7 @@ pub fn emit_fk_child_decrement_on_delete(
                 let tmp = program.alloc_register();
                 program.emit_insn(Insn::Column {
                     cursor_id: child_cursor_id,
-                    column: pos,
+                    column: child_tbl.logical_to_physical_column(pos),
                     dest: tmp,
                     default: None,
                 });
@@ -330,7 +380,7 @@ pub fn emit_fk_child_decrement_on_delete(
                 let tmp = program.alloc_register();
                 program.emit_insn(Insn::Column {
                     cursor_id: child_cursor_id,
-                    column: pos,
+                    column: child_tbl.logical_to_physical_column(pos),
                     dest: tmp,
                     default: None,
                 });
@@ -382,7 +432,7 @@ pub fn emit_fk_child_decrement_on_delete(
                     let r = program.alloc_register();
                     program.emit_insn(Insn::Column {
                         cursor_id: child_cursor_id,
-                        column: pos,
+                        column: child_tbl.logical_to_physical_column(pos),
                         dest: r,
                         default: None,
                     });
@@ -541,7 +591,8 @@ fn emit_delete_insns<'a>(
     let iteration_idx_delete_ctx = if let Some(index) = iteration_index {
         let iteration_index_cursor =
             program.resolve_cursor_id(&CursorKey::index(internal_id, index.clone()));
-        let num_regs = index.columns.len() + 1;
+        // Indexes of WITHOUT ROWID tables end with the PRIMARY KEY instead of a rowid.
+        let num_regs = index.columns.len() + usize::from(index.has_rowid);
         let start_reg = program.alloc_registers(num_regs);
         for (reg_offset, column_index) in index.columns.iter().enumerate() {
             emit_index_column_value_old_image(
@@ -554,10 +605,12 @@ fn emit_delete_insns<'a>(
                 start_reg + reg_offset,
             )?;
         }
-        program.emit_insn(Insn::RowId {
-            cursor_id: main_table_cursor_id,
-            dest: start_reg + num_regs - 1,
-        });
+        if index.has_rowid {
+            program.emit_insn(Insn::RowId {
+                cursor_id: main_table_cursor_id,
+                dest: start_reg + num_regs - 1,
+            });
+        }
         Some((iteration_index_cursor, start_reg, num_regs, index))
     } else {
         None
@@ -727,7 +780,7 @@ fn emit_delete_row_common(
             } else {
                 None
             };
-            let num_regs = index.columns.len() + 1;
+            let num_regs = index.columns.len() + usize::from(index.has_rowid);
             let start_reg = program.alloc_registers(num_regs);
             for (reg_offset, column_index) in index.columns.iter().enumerate() {
                 emit_index_column_value_old_image(
@@ -740,10 +793,12 @@ fn emit_delete_row_common(
                     start_reg + reg_offset,
                 )?;
             }
-            program.emit_insn(Insn::RowId {
-                cursor_id: main_table_cursor_id,
-                dest: start_reg + num_regs - 1,
-            });
+            if index.has_rowid {
+                program.emit_insn(Insn::RowId {
+                    cursor_id: main_table_cursor_id,
+                    dest: start_reg + num_regs - 1,
+                });
+            }
             program.emit_insn(Insn::IdxDelete {
                 start_reg,
                 num_regs,
@@ -869,19 +924,16 @@ fn emit_delete_insns_when_triggers_present(
     non_from_clause_subqueries: &mut [NonFromClauseSubquery],
     result_columns: &[ResultSetColumn],
     rowid_reg: usize,
+    row: RowLocator,
     main_table_cursor_id: usize,
     resolver: &Resolver,
     returning_buffer: Option<&ReturningBufferCtx>,
 ) -> Result<()> {
-    // Seek to the rowid and delete it
+    // Seek to the row and delete it
     let skip_not_found_label = program.allocate_label();
 
-    // Skip if row with rowid pulled from the rowset does not exist in the table.
-    program.emit_insn(Insn::NotExists {
-        cursor: main_table_cursor_id,
-        rowid_reg,
-        target_pc: skip_not_found_label,
-    });
+    // Skip if the row pulled from the write set does not exist in the table.
+    row.emit_seek(program, main_table_cursor_id, skip_not_found_label);
 
     let table_reference: *const JoinedTable = table_references.joined_tables().first().unwrap();
     if unsafe { &*table_reference }
@@ -976,11 +1028,7 @@ fn emit_delete_insns_when_triggers_present(
     }
 
     // BEFORE DELETE Triggers may have altered the btree so we need to seek again.
-    program.emit_insn(Insn::NotExists {
-        cursor: main_table_cursor_id,
-        rowid_reg,
-        target_pc: skip_not_found_label,
-    });
+    row.emit_seek(program, main_table_cursor_id, skip_not_found_label);
 
     emit_delete_row_common(
         connection,
@@ -35,6 +35,36 @@ pub fn compute_virtual_columns(
     })
 }
 
+/// Emit bytecode to compute STORED generated columns for a row, so that their
+/// values are in place before the record is built. Virtual columns are computed
+/// along the way, since a stored column may reference them.
+#[turso_macros::trace_stack]
+pub fn compute_stored_columns(
+    program: &mut ProgramBuilder,
+    columns: &ColumnsTopologicalSort<'_>,
+    dml_ctx: &DmlColumnContext,
+    resolver: &Resolver,
+    table: &Arc<BTreeTable>,
+) -> Result<()> {
+    let ctx = SelfTableContext::ForDML {
+        dml_ctx: dml_ctx.clone(),
+        table: Arc::clone(table),
+    };
+    resolver.with_self_table_context(program, Some(&ctx), |program, _| {
+        for (idx, column) in columns.iter() {
+            let Some(expr) = column.generated_expr() else {
+                continue;
+            };
+            let target_reg = dml_ctx.to_column_reg(idx);
+            translate_expr(program, None, expr, target_reg, resolver)?;
+            if column.affinity() != Affinity::Blob {
+                program.emit_column_affinity(target_reg, column.affinity());
+            }
+        }
+        Ok(())
+    })
+}
+
 /// Emit bytecode to compute a single virtual generated column expression.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn emit_gencol_expr_from_registers(
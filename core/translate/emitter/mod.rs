@@ -24,14 +24,14 @@ use super::{
 };
 use crate::instrument;
 use crate::schema::{
-    BTreeTable, CheckConstraint, Column, ColumnLayout, GeneratedType, IndexColumn, Schema, Table,
-    EXPR_INDEX_SENTINEL,
+    BTreeTable, CheckConstraint, Column, ColumnLayout, ForeignKey, GeneratedType, Index,
+    IndexColumn, ResolvedFkRef, Schema, Table, EXPR_INDEX_SENTINEL,
 };
 use crate::translate::plan::ColumnMask;
 use crate::vdbe::{
     affinity::Affinity,
     builder::{CursorType, DmlColumnContext, ProgramBuilder, SelfTableContext},
-    insn::{to_u16, InsertFlags, Insn},
+    insn::{to_u16, CmpInsFlags, InsertFlags, Insn},
     BranchOffset, CursorID,
 };
 use crate::{
@@ -162,6 +162,9 @@ pub struct Resolver<'a> {
     /// Controls whether unresolved double-quoted identifiers fall back to string
     /// literals (SQLite's DQS misfeature) in DML statements.
     pub dqs_dml: DoubleQuotedDml,
+    /// Mirrors `PRAGMA defer_foreign_keys`: when set, every foreign key is
+    /// compiled as if it were declared DEFERRABLE INITIALLY DEFERRED.
+    pub defer_foreign_keys: bool,
     /// When set, we are compiling a trigger subprogram for this database.
     /// Ordinary triggers are restricted to their own database, but temp-backed
     /// triggers follow SQLite's looser resolution rules and may access objects
@@ -267,6 +270,7 @@ impl<'a> Resolver<'a> {
         symbol_table: &'a SymbolTable,
         enable_custom_types: bool,
         dqs_dml: DoubleQuotedDml,
+        defer_foreign_keys: bool,
     ) -> Self {
         let has_temp_schema = temp_database.read().is_some();
         Self {
@@ -283,6 +287,7 @@ impl<'a> Resolver<'a> {
             self_table_scope: RefCell::new(None),
             enable_custom_types,
             dqs_dml,
+            defer_foreign_keys,
             trigger_context: None,
             has_temp_schema,
         }
@@ -311,6 +316,7 @@ impl<'a> Resolver<'a> {
             self_table_scope: RefCell::new(self.self_table_scope.borrow().clone()),
             enable_custom_types: self.enable_custom_types,
             dqs_dml: self.dqs_dml,
+            defer_foreign_keys: self.defer_foreign_keys,
             trigger_context: self.trigger_context.clone(),
             has_temp_schema: self.has_temp_schema,
         }
@@ -331,6 +337,7 @@ impl<'a> Resolver<'a> {
             self_table_scope: RefCell::new(self.self_table_scope.borrow().clone()),
             enable_custom_types: self.enable_custom_types,
             dqs_dml: self.dqs_dml,
+            defer_foreign_keys: self.defer_foreign_keys,
             trigger_context: self.trigger_context.clone(),
             has_temp_schema: self.has_temp_schema,
         }
@@ -523,6 +530,40 @@ impl<'a> Resolver<'a> {
         }
     }
 
+    /// Resolve the foreign keys declared by `child_table`, honoring
+    /// `PRAGMA defer_foreign_keys`.
+    pub(crate) fn resolved_fks_for_child(
+        &self,
+        database_id: usize,
+        child_table: &str,
+    ) -> Result<Vec<ResolvedFkRef>> {
+        self.with_schema(database_id, |s| s.resolved_fks_for_child(child_table))
+            .map(|fks| self.apply_defer_foreign_keys(fks))
+    }
+
+    /// Resolve the foreign keys referencing `table_name` as their parent,
+    /// honoring `PRAGMA defer_foreign_keys`.
+    pub(crate) fn resolved_fks_referencing(
+        &self,
+        database_id: usize,
+        table_name: &str,
+    ) -> Result<Vec<ResolvedFkRef>> {
+        self.with_schema(database_id, |s| s.resolved_fks_referencing(table_name))
+            .map(|fks| self.apply_defer_foreign_keys(fks))
+    }
+
+    fn apply_defer_foreign_keys(&self, mut fks: Vec<ResolvedFkRef>) -> Vec<ResolvedFkRef> {
+        if self.defer_foreign_keys {
+            for fk_ref in fks.iter_mut().filter(|fk_ref| !fk_ref.fk.deferred) {
+                fk_ref.fk = Arc::new(ForeignKey {
+                    deferred: true,
+                    ..(*fk_ref.fk).clone()
+                });
+            }
+        }
+        fks
+    }
+
     pub(crate) fn attached_database_ids_in_search_order(&self) -> BitSet {
         self.attached_databases
             .read()
@@ -993,6 +1034,142 @@ pub enum UpdateRowSource {
     },
 }
 
+/// Registers identifying a row of a DML target table that has to be (re-)located by seeking.
+#[derive(Debug, Clone, Copy)]
+pub enum RowLocator {
+    /// Rowid tables are located by the rowid held in this register.
+    Rowid(usize),
+    /// WITHOUT ROWID tables are located by their PRIMARY KEY values, held in `num_regs`
+    /// consecutive registers starting at `start_reg`.
+    PrimaryKey { start_reg: usize, num_regs: usize },
+}
+
+impl RowLocator {
+    /// Read the location of the row that the entry under `index_cursor_id` points to: the
+    /// rowid, or for WITHOUT ROWID tables the PRIMARY KEY columns stored in the entry.
+    pub fn from_index_entry(
+        program: &mut ProgramBuilder,
+        table: &BTreeTable,
+        index: &Index,
+        index_cursor_id: CursorID,
+    ) -> Self {
+        if index.has_rowid {
+            let rowid_reg = program.alloc_register();
+            program.emit_insn(Insn::IdxRowId {
+                cursor_id: index_cursor_id,
+                dest: rowid_reg,
+            });
+            return RowLocator::Rowid(rowid_reg);
+        }
+        let pk_positions = index.pk_positions(table);
+        let start_reg = program.alloc_registers(pk_positions.len());
+        for (i, &column) in pk_positions.iter().enumerate() {
+            program.emit_insn(Insn::Column {
+                cursor_id: index_cursor_id,
+                column,
+                dest: start_reg + i,
+                default: None,
+            });
+        }
+        RowLocator::PrimaryKey {
+            start_reg,
+            num_regs: pk_positions.len(),
+        }
+    }
+
+    /// Copy the PRIMARY KEY of a WITHOUT ROWID table out of the row image starting at `start`.
+    pub fn primary_key_from_row(
+        program: &mut ProgramBuilder,
+        table: &BTreeTable,
+        start: usize,
+        layout: &ColumnLayout,
+    ) -> Self {
+        let num_regs = table.primary_key_columns.len();
+        let start_reg = program.alloc_registers(num_regs);
+        for (i, (pk_name, _)) in table.primary_key_columns.iter().enumerate() {
+            let (pos, _) = table
+                .get_column(pk_name)
+                .expect("primary key column must exist");
+            program.emit_insn(Insn::Copy {
+                src_reg: layout.to_register(start, pos),
+                dst_reg: start_reg + i,
+                extra_amount: 0,
+            });
+        }
+        RowLocator::PrimaryKey {
+            start_reg,
+            num_regs,
+        }
+    }
+
+    /// Jump to `target_pc` if `self` and `other` locate the same row of `table`.
+    pub fn emit_jump_if_same_row(
+        &self,
+        program: &mut ProgramBuilder,
+        table: &BTreeTable,
+        other: &RowLocator,
+        target_pc: BranchOffset,
+    ) {
+        match (*self, *other) {
+            (RowLocator::Rowid(lhs), RowLocator::Rowid(rhs)) => {
+                program.emit_insn(Insn::Eq {
+                    lhs,
+                    rhs,
+                    target_pc,
+                    flags: CmpInsFlags::default(),
+                    collation: program.curr_collation(),
+                });
+            }
+            (
+                RowLocator::PrimaryKey { start_reg: lhs, .. },
+                RowLocator::PrimaryKey { start_reg: rhs, .. },
+            ) => {
+                let different_row = program.allocate_label();
+                for (i, (pk_name, _)) in table.primary_key_columns.iter().enumerate() {
+                    let collation = table
+                        .get_column(pk_name)
+                        .and_then(|(_, column)| column.collation_opt());
+                    program.emit_insn(Insn::Ne {
+                        lhs: lhs + i,
+                        rhs: rhs + i,
+                        target_pc: different_row,
+                        flags: CmpInsFlags::default().null_eq(),
+                        collation,
+                    });
+                }
+                program.emit_insn(Insn::Goto { target_pc });
+                program.preassign_label_to_next_insn(different_row);
+            }
+            _ => unreachable!("a table is either located by rowid or by PRIMARY KEY"),
+        }
+    }
+
+    /// Position `cursor_id` on the row, jumping to `target_pc` if it does not exist.
+    pub fn emit_seek(
+        &self,
+        program: &mut ProgramBuilder,
+        cursor_id: CursorID,
+        target_pc: BranchOffset,
+    ) {
+        match *self {
+            RowLocator::Rowid(rowid_reg) => program.emit_insn(Insn::NotExists {
+                cursor: cursor_id,
+                rowid_reg,
+                target_pc,
+            }),
+            RowLocator::PrimaryKey {
+                start_reg,
+                num_regs,
+            } => program.emit_insn(Insn::NotFound {
+                cursor_id,
+                target_pc,
+                record_reg: start_reg,
+                num_regs,
+            }),
+        }
+    }
+}
+
 /// Used to distinguish database operations
 #[allow(clippy::upper_case_acronyms, dead_code)]
 #[derive(Debug, Clone)]
@@ -1905,7 +2082,7 @@ fn emit_index_column_value_new_image(
                 )?;
                 program.emit_column_affinity(dest_reg, col_in_table.affinity());
             }
-            GeneratedType::NotGenerated => {
+            GeneratedType::Stored { .. } | GeneratedType::NotGenerated => {
                 let src_reg = if col_in_table.is_rowid_alias() {
                     rowid_reg
                 } else {
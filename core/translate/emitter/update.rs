@@ -1,4 +1,4 @@
-use super::gencol::compute_virtual_columns;
+use super::gencol::{compute_stored_columns, compute_virtual_columns};
 use super::TranslateCtx;
 use crate::schema::{Column, ColumnLayout, GeneratedType, Table};
 use crate::translate::insert::halt_desc_and_on_error;
@@ -20,7 +20,7 @@ use crate::{
             emit_cdc_autocommit_commit, emit_cdc_full_record, emit_cdc_insns,
             emit_cdc_patch_record, emit_check_constraints, emit_index_column_value_new_image,
             emit_index_column_value_old_image, emit_make_record, emit_program_for_select,
-            init_limit, OperationMode, Resolver, UpdateRowSource,
+            init_limit, OperationMode, Resolver, RowLocator, UpdateRowSource,
         },
         expr::{
             emit_dml_expr_index_value, emit_returning_results, emit_returning_scan_back,
@@ -694,7 +694,7 @@ fn emit_replace_delete<'a>(
     };
 
     for (other_index, other_idx_cursor_id) in all_index_cursors {
-        let other_num_regs = other_index.columns.len() + 1;
+        let other_num_regs = other_index.columns.len() + usize::from(other_index.has_rowid);
         let other_start_reg = program.alloc_registers(other_num_regs);
 
         for (reg_offset, column_index) in other_index.columns.iter().enumerate() {
@@ -709,11 +709,13 @@ fn emit_replace_delete<'a>(
             )?;
         }
 
-        program.emit_insn(Insn::Copy {
-            src_reg: conflicting_rowid_reg,
-            dst_reg: other_start_reg + other_num_regs - 1,
-            extra_amount: 0,
-        });
+        if other_index.has_rowid {
+            program.emit_insn(Insn::Copy {
+                src_reg: conflicting_rowid_reg,
+                dst_reg: other_start_reg + other_num_regs - 1,
+                extra_amount: 0,
+            });
+        }
 
         program.emit_insn(Insn::IdxDelete {
             start_reg: other_start_reg,
@@ -739,6 +741,136 @@ fn emit_replace_delete<'a>(
     Ok(())
 }
 
+/// PRIMARY KEY conflict check for an UPDATE that changes the key of a WITHOUT ROWID table.
+/// A row that keeps its key cannot conflict; otherwise the NEW key is probed in the table
+/// btree and a conflicting row is resolved according to `on_conflict`. The probe moves the
+/// cursor, so callers must re-seek the row under update afterwards.
+#[allow(clippy::too_many_arguments)]
+fn emit_without_rowid_pk_conflict_check<'a>(
+    program: &mut ProgramBuilder,
+    connection: &Arc<Connection>,
+    table_references: &mut TableReferences,
+    target_table: &Arc<JoinedTable>,
+    target_table_cursor_id: usize,
+    all_index_cursors: &[(Arc<Index>, usize)],
+    old_key: RowLocator,
+    start: usize,
+    layout: &ColumnLayout,
+    on_conflict: ResolveType,
+    skip_row_label: BranchOffset,
+    update_database_id: usize,
+    t_ctx: &mut TranslateCtx<'a>,
+) -> crate::Result<()> {
+    let RowLocator::PrimaryKey {
+        start_reg: old_key_reg,
+        num_regs,
+    } = old_key
+    else {
+        crate::bail_parse_error!("PRIMARY KEY conflict check requires a WITHOUT ROWID table");
+    };
+    let btree = target_table
+        .table
+        .btree()
+        .expect("UPDATE target must be a BTree table");
+    let pk_columns = btree
+        .primary_key_columns
+        .iter()
+        .map(|(name, _)| {
+            btree
+                .get_column(name)
+                .expect("primary key column must exist")
+        })
+        .collect::<Vec<_>>();
+
+    let new_key_reg = program.alloc_registers(num_regs);
+    for (i, (pos, _)) in pk_columns.iter().enumerate() {
+        program.emit_insn(Insn::Copy {
+            src_reg: layout.to_register(start, *pos),
+            dst_reg: new_key_reg + i,
+            extra_amount: 0,
+        });
+    }
+    program.emit_insn(Insn::Affinity {
+        start_reg: new_key_reg,
+        count: NonZeroUsize::new(num_regs).expect("WITHOUT ROWID tables must have a primary key"),
+        affinities: pk_columns
+            .iter()
+            .map(|(_, column)| column.affinity_with_strict(btree.is_strict).aff_mask())
+            .collect(),
+    });
+
+    let no_conflict_label = program.allocate_label();
+    let key_changed_label = program.allocate_label();
+    for (i, (_, column)) in pk_columns.iter().enumerate() {
+        program.emit_insn(Insn::Ne {
+            lhs: new_key_reg + i,
+            rhs: old_key_reg + i,
+            target_pc: key_changed_label,
+            flags: CmpInsFlags::default().null_eq(),
+            collation: column.collation_opt(),
+        });
+    }
+    program.emit_insn(Insn::Goto {
+        target_pc: no_conflict_label,
+    });
+    program.preassign_label_to_next_insn(key_changed_label);
+
+    program.emit_insn(Insn::NoConflict {
+        cursor_id: target_table_cursor_id,
+        target_pc: no_conflict_label,
+        record_reg: new_key_reg,
+        num_regs,
+    });
+    match on_conflict {
+        ResolveType::Ignore => {
+            program.emit_insn(Insn::Goto {
+                target_pc: skip_row_label,
+            });
+        }
+        ResolveType::Replace => {
+            // NoConflict left the cursor on the conflicting row, which has no rowid.
+            let conflicting_rowid_reg = program.alloc_register();
+            program.emit_insn(Insn::Null {
+                dest: conflicting_rowid_reg,
+                dest_end: None,
+            });
+            emit_replace_delete(
+                program,
+                connection,
+                table_references,
+                target_table,
+                target_table_cursor_id,
+                all_index_cursors,
+                conflicting_rowid_reg,
+                Some((start, conflicting_rowid_reg)),
+                update_database_id,
+                t_ctx,
+            )?;
+        }
+        _ => {
+            let raw_desc = btree
+                .primary_key_columns
+                .iter()
+                .map(|(name, _)| format!("{}.{}", btree.name, name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let (description, on_error) = halt_desc_and_on_error(
+                &raw_desc,
+                on_conflict,
+                program.flags.has_statement_conflict(),
+            );
+            program.emit_insn(Insn::Halt {
+                err_code: SQLITE_CONSTRAINT_PRIMARYKEY,
+                description,
+                on_error,
+                description_reg: None,
+            });
+        }
+    }
+    program.preassign_label_to_next_insn(no_conflict_label);
+    Ok(())
+}
+
 /// Helper function to evaluate SET expressions and read column values for UPDATE.
 /// This is invoked once for every UPDATE, but will be invoked again if there are
 /// any BEFORE UPDATE triggers that fired, because the triggers may have modified the row,
@@ -821,15 +953,17 @@ fn emit_update_column_values<'a>(
                     program.emit_null(target_reg, None);
                 } else {
                     let self_table_context = match table_column.generated_type() {
-                        GeneratedType::Virtual { .. } => Some(SelfTableContext::ForDML {
-                            dml_ctx: DmlColumnContext::layout(
-                                column_ctx.target_table.table.columns(),
-                                column_ctx.start,
-                                column_ctx.rowid_reg,
-                                column_ctx.layout.clone(),
-                            ),
-                            table: column_ctx.target_table.table.require_btree()?,
-                        }),
+                        GeneratedType::Virtual { .. } | GeneratedType::Stored { .. } => {
+                            Some(SelfTableContext::ForDML {
+                                dml_ctx: DmlColumnContext::layout(
+                                    column_ctx.target_table.table.columns(),
+                                    column_ctx.start,
+                                    column_ctx.rowid_reg,
+                                    column_ctx.layout.clone(),
+                                ),
+                                table: column_ctx.target_table.table.require_btree()?,
+                            })
+                        }
                         GeneratedType::NotGenerated => None,
                     };
 
@@ -928,7 +1062,7 @@ fn emit_update_column_values<'a>(
         } else {
             // Column is not being updated, read it from the table
             match table_column.generated_type() {
-                GeneratedType::NotGenerated => {
+                GeneratedType::Stored { .. } | GeneratedType::NotGenerated => {
                     let column_idx_in_index = column_ctx.index.as_ref().and_then(|(idx, _)| {
                         idx.columns.iter().position(|c| {
                             table_column
@@ -980,6 +1114,28 @@ fn emit_update_column_values<'a>(
     Ok(())
 }
 
+/// Recompute the STORED generated columns of the NEW row held in the registers at `start`.
+fn emit_update_stored_columns(
+    program: &mut ProgramBuilder,
+    target_table: &JoinedTable,
+    start: usize,
+    rowid_reg: usize,
+    layout: &ColumnLayout,
+    t_ctx: &TranslateCtx<'_>,
+) -> crate::Result<()> {
+    let Table::BTree(ref btree) = target_table.table else {
+        return Ok(());
+    };
+    let dml_ctx = DmlColumnContext::layout(btree.columns(), start, rowid_reg, layout.clone());
+    compute_stored_columns(
+        program,
+        &btree.columns_topo_sort()?,
+        &dml_ctx,
+        &t_ctx.resolver,
+        btree,
+    )
+}
+
 /// Emit NOT NULL constraint checks for SET clause columns after BEFORE triggers have fired.
 /// This is deferred from the first `emit_update_column_values` call so that triggers
 /// run before constraint checks, matching SQLite's behavior.
@@ -1113,6 +1269,28 @@ fn emit_update_insns<'a>(
     let rowid_update = rowid_update_info(target_table.as_ref(), set_clauses);
     let rowid_alias_index = rowid_update.rowid_alias_index;
     let updates_rowid = rowid_update.updates_rowid;
+    // WITHOUT ROWID tables are keyed by their PRIMARY KEY, whose logical positions are kept here.
+    let without_rowid_pk: Option<Vec<usize>> = target_table
+        .table
+        .btree()
+        .filter(|btree| !btree.has_rowid)
+        .map(|btree| {
+            btree
+                .primary_key_columns
+                .iter()
+                .map(|(name, _)| {
+                    btree
+                        .get_column(name)
+                        .expect("primary key column must exist")
+                        .0
+                })
+                .collect()
+        });
+    let updates_pk = without_rowid_pk.as_ref().is_some_and(|pk_cols| {
+        set_clauses
+            .iter()
+            .any(|set_clause| pk_cols.contains(&set_clause.column_index))
+    });
 
     let beg = program.alloc_registers(
         target_table.table.columns().len()
@@ -1122,10 +1300,43 @@ fn emit_update_insns<'a>(
                 1 // rowid reg
             },
     );
-    program.emit_insn(Insn::RowId {
-        cursor_id: iteration_cursor_id,
-        dest: beg,
-    });
+    let row = match &without_rowid_pk {
+        Some(pk_cols) => {
+            // There is no rowid: keep the rowid register NULL and remember the old key instead.
+            program.emit_insn(Insn::Null {
+                dest: beg,
+                dest_end: None,
+            });
+            let start_reg = program.alloc_registers(pk_cols.len());
+            if uses_write_set {
+                // The write set stores the PRIMARY KEY values after the SET payloads.
+                let first_pk_column = access_table.table.columns().len() - pk_cols.len();
+                for i in 0..pk_cols.len() {
+                    program.emit_insn(Insn::Column {
+                        cursor_id: iteration_cursor_id,
+                        column: first_pk_column + i,
+                        dest: start_reg + i,
+                        default: None,
+                    });
+                }
+            } else {
+                for (i, &pos) in pk_cols.iter().enumerate() {
+                    program.emit_column_or_rowid(target_table_cursor_id, pos, start_reg + i);
+                }
+            }
+            RowLocator::PrimaryKey {
+                start_reg,
+                num_regs: pk_cols.len(),
+            }
+        }
+        None => {
+            program.emit_insn(Insn::RowId {
+                cursor_id: iteration_cursor_id,
+                dest: beg,
+            });
+            RowLocator::Rowid(beg)
+        }
+    };
 
     let rowid_set_clause_reg = if updates_rowid {
         Some(program.alloc_register())
@@ -1146,7 +1357,8 @@ fn emit_update_insns<'a>(
         constraint_rowid_alias_conflict.unwrap_or(ResolveType::Abort)
     };
 
-    let not_exists_check_required = updates_rowid || iteration_cursor_id != target_table_cursor_id;
+    let not_exists_check_required =
+        updates_rowid || updates_pk || iteration_cursor_id != target_table_cursor_id;
     let update_database_id = target_table.database_id;
     let updated_column_indices: ColumnMask = set_clauses
         .iter()
@@ -1174,12 +1386,12 @@ fn emit_update_insns<'a>(
     let trigger_ignore_jump_label = program.allocate_label();
 
     if not_exists_check_required {
-        program.emit_insn(Insn::NotExists {
-            cursor: target_table_cursor_id,
-            rowid_reg: beg,
-            target_pc: check_rowid_not_exists_label.unwrap(),
-        });
-    } else {
+        row.emit_seek(
+            program,
+            target_table_cursor_id,
+            check_rowid_not_exists_label.unwrap(),
+        );
+    } else if without_rowid_pk.is_none() {
         // if no rowid, we're done
         program.emit_insn(Insn::IsNull {
             reg: beg,
@@ -1276,8 +1488,8 @@ fn emit_update_insns<'a>(
     if let Some(btree_table) = target_table.table.btree() {
         if !btree_table.is_strict {
             let affinity = btree_table
-                .columns()
-                .iter()
+                .columns_in_record_order()
+                .into_iter()
                 .filter(|c| !c.is_virtual_generated())
                 .map(|c| c.affinity());
 
@@ -1294,6 +1506,24 @@ fn emit_update_insns<'a>(
         }
     }
 
+    // Stored generated columns that depend on a SET column were evaluated in column
+    // order above; recompute them in dependency order so the persisted values are right.
+    let update_affects_stored_columns = target_table.table.btree().is_some_and(|btree| {
+        affected_columns
+            .iter()
+            .any(|idx| btree.columns()[idx].is_stored_generated())
+    });
+    if update_affects_stored_columns {
+        emit_update_stored_columns(
+            program,
+            &target_table,
+            start,
+            effective_rowid_reg,
+            &layout,
+            t_ctx,
+        )?;
+    }
+
     // Fire BEFORE UPDATE triggers and preserve old_registers for AFTER triggers
     let mut has_before_triggers = false;
     let mut has_after_triggers = false;
@@ -1400,13 +1630,13 @@ fn emit_update_insns<'a>(
                 }
 
                 // BEFORE UPDATE Triggers may have altered the btree so we need to seek again.
-                program.emit_insn(Insn::NotExists {
-                cursor: target_table_cursor_id,
-                rowid_reg: beg,
-                target_pc: check_rowid_not_exists_label.expect(
+                row.emit_seek(
+                program,
+                target_table_cursor_id,
+                check_rowid_not_exists_label.expect(
                     "check_rowid_not_exists_label must be set if there are BEFORE UPDATE triggers",
                 ),
-            });
+            );
 
                 if has_after_triggers {
                     // Preserve pseudo-row 'OLD' for AFTER triggers by copying to new registers
@@ -1461,6 +1691,16 @@ fn emit_update_insns<'a>(
             skip_row_label,
             false,
         )?;
+        if update_affects_stored_columns {
+            emit_update_stored_columns(
+                program,
+                &target_table,
+                start,
+                effective_rowid_reg,
+                &layout,
+                t_ctx,
+            )?;
+        }
 
         // Now emit NOT NULL checks for SET clause columns that were deferred
         // from the first emit_update_column_values call. In SQLite, NOT NULL
@@ -1514,10 +1754,27 @@ fn emit_update_insns<'a>(
                 } else {
                     ParentKeyNewProbeMode::BeforeWrite
                 };
+                // A WITHOUT ROWID parent key is probed through the table btree itself.
+                let primary_key_parent_index: Vec<Arc<Index>> = if table_btree.has_rowid {
+                    vec![]
+                } else {
+                    t_ctx
+                        .resolver
+                        .with_schema(update_database_id, |s| {
+                            s.resolved_fks_referencing(table_name)
+                        })?
+                        .into_iter()
+                        .filter_map(|fk_ref| fk_ref.parent_unique_index)
+                        .find(|index| index.root_page == table_btree.root_page)
+                        .into_iter()
+                        .collect()
+                };
                 deferred_new_key_plans = emit_fk_update_parent_actions(
                     program,
                     &table_btree,
-                    indexes_to_update.iter(),
+                    indexes_to_update
+                        .iter()
+                        .chain(primary_key_parent_index.iter()),
                     target_table_cursor_id,
                     beg,
                     start,
@@ -1672,6 +1929,30 @@ fn emit_update_insns<'a>(
         program.preassign_label_to_next_insn(record_label);
     }
 
+    if updates_pk {
+        emit_without_rowid_pk_conflict_check(
+            program,
+            connection,
+            table_references,
+            &target_table,
+            target_table_cursor_id,
+            all_index_cursors,
+            row,
+            start,
+            &layout,
+            effective_rowid_alias_conflict,
+            skip_row_label,
+            update_database_id,
+            t_ctx,
+        )?;
+        row.emit_seek(
+            program,
+            target_table_cursor_id,
+            check_rowid_not_exists_label
+                .expect("check_rowid_not_exists_label must be set when the primary key is updated"),
+        );
+    }
+
     // After the PK check above, NotExists may have repositioned the cursor.
     // Re-seek to the row under update so old-image reads in Phase 2 are correct.
     if updates_rowid && !matches!(effective_rowid_alias_conflict, ResolveType::Replace) {
@@ -1824,7 +2105,7 @@ fn emit_update_insns<'a>(
         idx_cursor_id: usize,
         record_reg: usize,
         idx_start_reg: usize,
-        num_cols: usize,
+        record_len: usize,
         old_satisfies_where: Option<usize>,
         new_satisfies_where: Option<usize>,
     }
@@ -1893,8 +2174,10 @@ fn emit_update_insns<'a>(
         };
 
         // Build new index key for constraint checking and later insertion (Phase 3).
+        // Indexes of WITHOUT ROWID tables end with the PRIMARY KEY instead of a rowid.
         let num_cols = index.columns.len();
-        let idx_start_reg = program.alloc_registers(num_cols + 1);
+        let record_len = num_cols + usize::from(index.has_rowid);
+        let idx_start_reg = program.alloc_registers(record_len);
         let rowid_reg = effective_rowid_reg;
 
         for (i, col) in index.columns.iter().enumerate() {
@@ -1914,11 +2197,13 @@ fn emit_update_insns<'a>(
             )?;
         }
         // last register is the rowid
-        program.emit_insn(Insn::Copy {
-            src_reg: rowid_reg,
-            dst_reg: idx_start_reg + num_cols,
-            extra_amount: 0,
-        });
+        if index.has_rowid {
+            program.emit_insn(Insn::Copy {
+                src_reg: rowid_reg,
+                dst_reg: idx_start_reg + num_cols,
+                extra_amount: 0,
+            });
+        }
 
         // Apply affinity BEFORE MakeRecord so the index record has correctly converted values.
         // This is needed for all indexes (not just unique) because the index should store
@@ -1944,7 +2229,7 @@ fn emit_update_insns<'a>(
 
         program.emit_insn(Insn::MakeRecord {
             start_reg: to_u16(idx_start_reg),
-            count: to_u16(num_cols + 1),
+            count: to_u16(record_len),
             dest_reg: to_u16(*record_reg),
             index_name: Some(index.name.clone()),
             affinity_str: None,
@@ -1988,23 +2273,18 @@ fn emit_update_insns<'a>(
                 cursor_id: *idx_cursor_id,
                 target_pc: constraint_check,
                 record_reg: idx_start_reg,
-                num_regs: num_cols,
+                num_regs: index.key_columns().len(),
             });
 
-            let idx_rowid_reg = program.alloc_register();
-            program.emit_insn(Insn::IdxRowId {
-                cursor_id: *idx_cursor_id,
-                dest: idx_rowid_reg,
-            });
+            let target_btree = target_table
+                .table
+                .btree()
+                .expect("expected btree table: no indexes on virtual tables");
+            let conflicting_row =
+                RowLocator::from_index_entry(program, &target_btree, index, *idx_cursor_id);
 
             // Skip over the UNIQUE constraint failure if the existing row is the one that we are currently changing
-            program.emit_insn(Insn::Eq {
-                lhs: beg,
-                rhs: idx_rowid_reg,
-                target_pc: constraint_check,
-                flags: CmpInsFlags::default(),
-                collation: program.curr_collation(),
-            });
+            conflicting_row.emit_jump_if_same_row(program, &target_btree, &row, constraint_check);
             match idx_conflict {
                 ResolveType::Ignore => {
                     // For IGNORE, skip this row's update but continue with other rows
@@ -2013,20 +2293,25 @@ fn emit_update_insns<'a>(
                     });
                 }
                 ResolveType::Replace => {
-                    let original_rowid_reg = program.alloc_register();
-                    program.emit_insn(Insn::Copy {
-                        src_reg: beg,
-                        dst_reg: original_rowid_reg,
-                        extra_amount: 0,
-                    });
-
                     // Seek to the conflicting row
                     let after_delete_label = program.allocate_label();
-                    program.emit_insn(Insn::SeekRowid {
-                        cursor_id: target_table_cursor_id,
-                        src_reg: idx_rowid_reg,
-                        target_pc: after_delete_label, // Skip if row doesn't exist
-                    });
+                    conflicting_row.emit_seek(
+                        program,
+                        target_table_cursor_id,
+                        after_delete_label, // Skip if row doesn't exist
+                    );
+                    // A WITHOUT ROWID row has no rowid to hand to FK actions.
+                    let conflicting_rowid_reg = match conflicting_row {
+                        RowLocator::Rowid(rowid_reg) => rowid_reg,
+                        RowLocator::PrimaryKey { .. } => {
+                            let rowid_reg = program.alloc_register();
+                            program.emit_insn(Insn::Null {
+                                dest: rowid_reg,
+                                dest_end: None,
+                            });
+                            rowid_reg
+                        }
+                    };
 
                     emit_replace_delete(
                         program,
@@ -2035,7 +2320,7 @@ fn emit_update_insns<'a>(
                         &target_table,
                         target_table_cursor_id,
                         all_index_cursors,
-                        idx_rowid_reg,
+                        conflicting_rowid_reg,
                         Some((start, effective_rowid_reg)),
                         update_database_id,
                         t_ctx,
@@ -2045,16 +2330,16 @@ fn emit_update_insns<'a>(
 
                     // Seek back to the original row we're updating
                     let continue_label = program.allocate_label();
-                    program.emit_insn(Insn::SeekRowid {
-                        cursor_id: target_table_cursor_id,
-                        src_reg: original_rowid_reg,
-                        target_pc: continue_label, // Should always succeed
-                    });
+                    row.emit_seek(
+                        program,
+                        target_table_cursor_id,
+                        continue_label, // Should always succeed
+                    );
                     program.preassign_label_to_next_insn(continue_label);
                 }
                 _ => {
                     // ABORT/FAIL/ROLLBACK behavior
-                    let column_names = index.columns.iter().enumerate().fold(
+                    let column_names = index.key_columns().iter().enumerate().fold(
                         String::with_capacity(50),
                         |mut accum, (idx, col)| {
                             if idx > 0 {
@@ -2087,7 +2372,7 @@ fn emit_update_insns<'a>(
             idx_cursor_id: *idx_cursor_id,
             record_reg: *record_reg,
             idx_start_reg,
-            num_cols,
+            record_len,
             old_satisfies_where,
             new_satisfies_where,
         });
@@ -2163,7 +2448,7 @@ fn emit_update_insns<'a>(
             });
         }
 
-        let num_regs = index.columns.len() + 1;
+        let num_regs = index.columns.len() + usize::from(index.has_rowid);
         let delete_start_reg = program.alloc_registers(num_regs);
         for (reg_offset, column_index) in index.columns.iter().enumerate() {
             emit_index_column_value_old_image(
@@ -2176,10 +2461,12 @@ fn emit_update_insns<'a>(
                 delete_start_reg + reg_offset,
             )?;
         }
-        program.emit_insn(Insn::RowId {
-            cursor_id: target_table_cursor_id,
-            dest: delete_start_reg + num_regs - 1,
-        });
+        if index.has_rowid {
+            program.emit_insn(Insn::RowId {
+                cursor_id: target_table_cursor_id,
+                dest: delete_start_reg + num_regs - 1,
+            });
+        }
         program.emit_insn(Insn::IdxDelete {
             start_reg: delete_start_reg,
             num_regs,
@@ -2209,7 +2496,7 @@ fn emit_update_insns<'a>(
             cursor_id: ctx.idx_cursor_id,
             record_reg: ctx.record_reg,
             unpacked_start: Some(ctx.idx_start_reg),
-            unpacked_count: Some((ctx.num_cols + 1) as u16),
+            unpacked_count: Some(ctx.record_len as u16),
             flags: IdxInsertFlags::new().nchange(true),
         });
 
@@ -2223,18 +2510,18 @@ fn emit_update_insns<'a>(
             let record_reg = program.alloc_register();
             emit_make_record(
                 program,
-                target_table.table.columns().iter(),
+                table.columns_in_record_order(),
                 start,
                 record_reg,
                 table.is_strict,
             );
 
             if not_exists_check_required {
-                program.emit_insn(Insn::NotExists {
-                    cursor: target_table_cursor_id,
-                    rowid_reg: beg,
-                    target_pc: check_rowid_not_exists_label.unwrap(),
-                });
+                row.emit_seek(
+                    program,
+                    target_table_cursor_id,
+                    check_rowid_not_exists_label.unwrap(),
+                );
             }
 
             // create alias for CDC rowid after the change (will differ from cdc_rowid_before_reg only in case of UPDATE with change in rowid alias)
@@ -321,6 +321,34 @@ pub fn bind_and_rewrite_expr<'a>(
                         }
                         resolved = Some((joined_table.internal_id, candidate));
                     }
+                    // A table inside a parenthesized join group that was planned as a
+                    // subquery, e.g. `b` in `a LEFT JOIN (b JOIN c ON ...) ON ...`, is
+                    // reached through the group's columns.
+                    if !identifier_matched {
+                        for joined_table in referenced_tables.joined_tables() {
+                            let Table::FromClauseSubquery(subquery) = &joined_table.table else {
+                                continue;
+                            };
+                            if !subquery.nested_from_contains(&normalized_table_name) {
+                                continue;
+                            }
+                            identifier_matched = true;
+                            if let Some(col_idx) =
+                                subquery.nested_from_column(&normalized_table_name, &normalized_id)
+                            {
+                                if resolved.is_some() {
+                                    return Err(ambiguous());
+                                }
+                                resolved = Some((
+                                    joined_table.internal_id,
+                                    QualifiedMatch::Column {
+                                        col_idx,
+                                        is_rowid_alias: subquery.columns[col_idx].is_rowid_alias(),
+                                    },
+                                ));
+                            }
+                        }
+                    }
                     // --- Stage 2: fall back to enclosing scopes ---
                     // Only attempted if no inner-scope table matched the identifier — an
                     // inner alias of the same name shadows everything outside.
@@ -1,4 +1,5 @@
 use super::*;
+use crate::vdbe::builder::MaterializedCteInfo;
 
 /// Emit literal values - shared between regular and RETURNING expression evaluation
 pub fn emit_literal(
@@ -215,6 +216,21 @@ pub struct ReturningBufferCtx {
 /// Emit the scan-back loop that reads all buffered RETURNING rows from the
 /// ephemeral table and yields them via ResultRow. Called after all DML is complete.
 pub(crate) fn emit_returning_scan_back(program: &mut ProgramBuilder, buf: &ReturningBufferCtx) {
+    if program.is_capturing_returning() {
+        // The body of a data-modifying CTE: the enclosing statement reads the
+        // buffered rows like a materialized CTE instead.
+        let table = program
+            .btree_table_from_cursor(buf.cursor_id)
+            .expect("RETURNING buffer must be a btree table")
+            .clone();
+        program.capture_returning(MaterializedCteInfo {
+            cursor_id: buf.cursor_id,
+            table,
+            num_columns: buf.num_columns,
+        });
+        return;
+    }
+
     let end_label = program.allocate_label();
     let scan_start = program.allocate_label();
 
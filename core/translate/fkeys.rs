@@ -269,7 +269,7 @@ where
         let tmp = program.alloc_register();
         program.emit_insn(Insn::Column {
             cursor_id: ccur,
-            column: pos,
+            column: child_tbl.logical_to_physical_column(pos),
             dest: tmp,
             default: None,
         });
@@ -807,9 +807,7 @@ pub fn emit_fk_child_update_counters(
         Ok(Some((dml_ctx, fk_col_positions, null_skip_label)))
     };
 
-    for fk_ref in
-        resolver.with_schema(database_id, |s| s.resolved_fks_for_child(child_table_name))?
-    {
+    for fk_ref in resolver.resolved_fks_for_child(database_id, child_table_name)? {
         // If the child-side FK columns did not change, there is nothing to do.
         if !fk_ref.child_key_changed(updated_cols, child_tbl) {
             continue;
@@ -1128,9 +1126,7 @@ pub fn emit_fk_update_parent_actions(
 ) -> Result<Vec<DeferredNewKeyProbePlan>> {
     let mut deferred_new_key_plans = Vec::new();
     let mut check_fks: Vec<_> = Vec::new();
-    let referencing = resolver.with_schema(database_id, |s| {
-        s.resolved_fks_referencing(&table_btree.name)
-    })?;
+    let referencing = resolver.resolved_fks_referencing(database_id, &table_btree.name)?;
     for fk in referencing {
         if !fk.parent_key_may_change(updated_positions, table_btree)? {
             continue;
@@ -1708,9 +1704,7 @@ impl ForeignKeyActions<PreparedFkDeleteAction> {
 
         let mut prepared = Vec::new();
 
-        for fk_ref in resolver.with_schema(database_id, |s| {
-            s.resolved_fks_referencing(parent_table_name)
-        })? {
+        for fk_ref in resolver.resolved_fks_referencing(database_id, parent_table_name)? {
             let parent_cols: &[String] = &fk_ref.parent_cols;
             let ncols = parent_cols.len();
             let key_regs_start = program.alloc_registers(ncols);
@@ -1891,9 +1885,7 @@ pub fn fire_fk_update_actions(
     };
     let new_image_layout = ColumnLayout::from_btree(&parent_bt);
 
-    for fk_ref in resolver.with_schema(database_id, |s| {
-        s.resolved_fks_referencing(parent_table_name)
-    })? {
+    for fk_ref in resolver.resolved_fks_referencing(database_id, parent_table_name)? {
         let parent_cols: &[String] = &fk_ref.parent_cols;
         let ncols = parent_cols.len();
 
@@ -1991,9 +1983,7 @@ pub fn emit_fk_drop_table_check(
         })?;
 
     // Get all FK references to this parent table
-    let fk_refs = resolver.with_schema(database_id, |s| {
-        s.resolved_fks_referencing(parent_table_name)
-    })?;
+    let fk_refs = resolver.resolved_fks_referencing(database_id, parent_table_name)?;
 
     if fk_refs.is_empty() {
         return Ok(());
@@ -2162,7 +2152,7 @@ pub fn emit_fk_drop_table_check(
             let child_val_reg = program.alloc_register();
             program.emit_insn(Insn::Column {
                 cursor_id: child_cur,
-                column: pos,
+                column: child_tbl.logical_to_physical_column(pos),
                 dest: child_val_reg,
                 default: None,
             });
@@ -2229,3 +2219,157 @@ pub fn emit_fk_drop_table_check(
 
     Ok(())
 }
+
+/// Emit the body of `PRAGMA foreign_key_check`.
+///
+/// Scans every row of each child table (or only `table_name` when given) and emits a
+/// `(table, rowid, parent, fkid)` row for every foreign key whose parent key is missing.
+/// Rows with a NULL in any child key column are never violations, and a missing parent
+/// table makes every other row a violation. `fkid` matches `PRAGMA foreign_key_list`.
+pub fn emit_foreign_key_check(
+    program: &mut ProgramBuilder,
+    resolver: &Resolver,
+    database_id: usize,
+    table_name: Option<&str>,
+) -> Result<()> {
+    let child_tables = resolver.with_schema(database_id, |s| match table_name {
+        Some(name) => s
+            .get_btree_table(name)
+            .map(|table| vec![table])
+            .ok_or_else(|| LimboError::ParseError(format!("no such table: {name}"))),
+        None => {
+            let mut tables: Vec<_> = s
+                .tables
+                .values()
+                .filter_map(|table| table.btree())
+                .filter(|table| !table.foreign_keys.is_empty())
+                .collect();
+            tables.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(tables)
+        }
+    })?;
+
+    // 4 columns: table, rowid, parent, fkid
+    let base_reg = program.alloc_registers(4);
+    for child_tbl in child_tables {
+        let mut foreign_keys = child_tbl.foreign_keys.iter().collect::<Vec<_>>();
+        foreign_keys.sort_by_key(|fk| std::cmp::Reverse(fk.decl_order));
+
+        // Resolve every FK and open its parent cursor before scanning the child.
+        let mut checks = Vec::with_capacity(foreign_keys.len());
+        for fk in foreign_keys {
+            let parent_tbl =
+                resolver.with_schema(database_id, |s| s.get_btree_table(&fk.parent_table));
+            let parent = match parent_tbl {
+                Some(parent_tbl) => {
+                    let fk_ref = resolver.with_schema(database_id, |s| {
+                        s.resolve_fk(fk, &child_tbl, &parent_tbl, /*require_unique=*/ true)
+                    })?;
+                    let cursor_id = match &fk_ref.parent_unique_index {
+                        Some(idx) => open_read_index(program, idx, database_id),
+                        None => open_read_table(program, &parent_tbl, database_id),
+                    };
+                    Some((fk_ref, parent_tbl, cursor_id))
+                }
+                None => None,
+            };
+            checks.push((fk, parent));
+        }
+
+        let ccur = open_read_table(program, &child_tbl, database_id);
+        let done = program.allocate_label();
+        program.emit_insn(Insn::Rewind {
+            cursor_id: ccur,
+            pc_if_empty: done,
+        });
+        let loop_top = program.allocate_label();
+        program.preassign_label_to_next_insn(loop_top);
+
+        for (fkid, (fk, parent)) in checks.iter().enumerate() {
+            let next_fk = program.allocate_label();
+            let ncols = fk.child_columns.len();
+            let key_start = program.alloc_registers(ncols);
+            for (i, cname) in fk.child_columns.iter().enumerate() {
+                let (pos, col) = child_tbl.get_column(cname).ok_or_else(|| {
+                    LimboError::InternalError(format!("child col {cname} missing"))
+                })?;
+                if col.is_rowid_alias() {
+                    program.emit_insn(Insn::RowId {
+                        cursor_id: ccur,
+                        dest: key_start + i,
+                    });
+                } else {
+                    program.emit_insn(Insn::Column {
+                        cursor_id: ccur,
+                        column: child_tbl.logical_to_physical_column(pos),
+                        dest: key_start + i,
+                        default: None,
+                    });
+                }
+                program.emit_insn(Insn::IsNull {
+                    reg: key_start + i,
+                    target_pc: next_fk,
+                });
+            }
+
+            if let Some((fk_ref, parent_tbl, pcur)) = parent {
+                match &fk_ref.parent_unique_index {
+                    Some(idx) => {
+                        if let Some(count) = NonZeroUsize::new(ncols) {
+                            program.emit_insn(Insn::Affinity {
+                                start_reg: key_start,
+                                count,
+                                affinities: build_index_affinity_string(idx, parent_tbl),
+                            });
+                        }
+                        program.emit_insn(Insn::Found {
+                            cursor_id: *pcur,
+                            target_pc: next_fk,
+                            record_reg: key_start,
+                            num_regs: ncols,
+                        });
+                    }
+                    None => {
+                        let violation = program.allocate_label();
+                        program.emit_insn(Insn::SeekRowid {
+                            cursor_id: *pcur,
+                            src_reg: key_start,
+                            target_pc: violation,
+                        });
+                        program.emit_insn(Insn::Goto { target_pc: next_fk });
+                        program.preassign_label_to_next_insn(violation);
+                    }
+                }
+            }
+
+            // Parent key missing: report the violating child row.
+            program.emit_string8(child_tbl.name.clone(), base_reg);
+            if child_tbl.has_rowid {
+                program.emit_insn(Insn::RowId {
+                    cursor_id: ccur,
+                    dest: base_reg + 1,
+                });
+            } else {
+                program.emit_insn(Insn::Null {
+                    dest: base_reg + 1,
+                    dest_end: None,
+                });
+            }
+            program.emit_string8(fk.parent_table.clone(), base_reg + 2);
+            program.emit_int(fkid as i64, base_reg + 3);
+            program.emit_result_row(base_reg, 4);
+            program.preassign_label_to_next_insn(next_fk);
+        }
+
+        program.emit_insn(Insn::Next {
+            cursor_id: ccur,
+            pc_if_next: loop_top,
+        });
+        program.preassign_label_to_next_insn(done);
+        program.emit_insn(Insn::Close { cursor_id: ccur });
+        for (_, _, pcur) in checks.iter().filter_map(|(_, parent)| parent.as_ref()) {
+            program.emit_insn(Insn::Close { cursor_id: *pcur });
+        }
+    }
+    Ok(())
+}
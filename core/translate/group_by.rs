@@ -7,7 +7,10 @@ use super::{
     result_row::emit_select_result,
 };
 use crate::translate::{
-    aggregation::{translate_aggregation_step, AggArgumentSource},
+    aggregation::{
+        emit_ordered_aggregation_insert, emit_ordered_aggregation_steps,
+        translate_aggregation_step, AggArgumentSource,
+    },
     order_by::{custom_type_comparator, EmitOrderBy},
     plan::{Aggregate, NonFromClauseSubquery},
     subquery::emit_non_from_clause_subqueries_for_phase,
@@ -362,6 +365,9 @@ fn collect_agg_leaf_columns(aggregates: &[Aggregate], plan: &SelectPlan) -> Resu
         if let Some(filter_expr) = &agg.filter_expr {
             walk_expr(filter_expr, &mut collect)?;
         }
+        for (expr, _, _) in &agg.order_by {
+            walk_expr(expr, &mut collect)?;
+        }
     }
     Ok(leaf_columns)
 }
@@ -584,6 +590,41 @@ pub enum GroupByRowSource {
     },
 }
 
+/// Accumulates the current row into an aggregate of the current group, or buffers it
+/// in the aggregate's sorter if the aggregate has an ORDER BY clause.
+fn emit_group_aggregation_step(
+    program: &mut ProgramBuilder,
+    plan: &SelectPlan,
+    agg_arg_source: AggArgumentSource,
+    agg: &Aggregate,
+    agg_result_reg: usize,
+    resolver: &Resolver,
+) -> Result<()> {
+    if agg.is_ordered() {
+        return emit_ordered_aggregation_insert(
+            program,
+            &plan.table_references,
+            agg_arg_source,
+            agg,
+            resolver,
+        );
+    }
+    translate_aggregation_step(
+        program,
+        &plan.table_references,
+        agg_arg_source,
+        agg_result_reg,
+        resolver,
+    )?;
+    if let Distinctness::Distinct { ctx } = &agg.distinctness {
+        let ctx = ctx
+            .as_ref()
+            .expect("distinct aggregate context not populated");
+        program.preassign_label_to_next_insn(ctx.label_on_conflict);
+    }
+    Ok(())
+}
+
 /// Emits bytecode for processing a single GROUP BY group.
 pub fn group_by_process_single_group(
     program: &mut ProgramBuilder,
@@ -749,19 +790,14 @@ pub fn group_by_process_single_group(
 
                 let agg_arg_source =
                     AggArgumentSource::new_from_expression(&agg.func, &agg.args, &agg.distinctness);
-                translate_aggregation_step(
+                emit_group_aggregation_step(
                     program,
-                    &plan.table_references,
+                    plan,
                     agg_arg_source,
+                    agg,
                     agg_result_reg,
                     &t_ctx.resolver,
                 )?;
-                if let Distinctness::Distinct { ctx } = &agg.distinctness {
-                    let ctx = ctx
-                        .as_ref()
-                        .expect("distinct aggregate context not populated");
-                    program.preassign_label_to_next_insn(ctx.label_on_conflict);
-                }
 
                 if let Some(label) = filter_skip_label {
                     program.preassign_label_to_next_insn(label);
@@ -803,19 +839,14 @@ pub fn group_by_process_single_group(
                 let start_reg_aggs = start_reg_src + t_ctx.non_aggregate_expressions.len();
                 let agg_arg_source =
                     AggArgumentSource::new_from_registers(start_reg_aggs + offset, agg);
-                translate_aggregation_step(
+                emit_group_aggregation_step(
                     program,
-                    &plan.table_references,
+                    plan,
                     agg_arg_source,
+                    agg,
                     agg_result_reg,
                     &t_ctx.resolver,
                 )?;
-                if let Distinctness::Distinct { ctx } = &agg.distinctness {
-                    let ctx = ctx
-                        .as_ref()
-                        .expect("distinct aggregate context not populated");
-                    program.preassign_label_to_next_insn(ctx.label_on_conflict);
-                }
 
                 if let Some(label) = filter_skip_label {
                     program.preassign_label_to_next_insn(label);
@@ -1002,6 +1033,15 @@ pub fn group_by_emit_row_phase<'a>(
             .reg_agg_start
             .expect("aggregate registers must be initialized");
         let agg_result_reg = agg_start_reg + i;
+        if agg.is_ordered() {
+            emit_ordered_aggregation_steps(
+                program,
+                &plan.table_references,
+                agg,
+                agg_result_reg,
+                &t_ctx.resolver,
+            )?;
+        }
         program.emit_insn(Insn::AggFinal {
             register: agg_result_reg,
             func: agg.func.clone(),
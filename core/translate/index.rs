@@ -19,7 +19,7 @@ use crate::translate::{
 };
 use crate::vdbe::builder::{CursorKey, ProgramBuilderOpts, SelfTableContext};
 use crate::vdbe::insn::{to_u16, CmpInsFlags, Cookie};
-use crate::{bail_parse_error, CaptureDataChangesExt, LimboError, MAIN_DB_ID};
+use crate::{bail_parse_error, CaptureDataChangesExt, LimboError};
 use crate::{
     schema::{BTreeTable, Index, IndexColumn, PseudoCursorType},
     storage::pager::CreateBTreeFlags,
@@ -142,9 +142,6 @@ pub fn translate_create_index(
     let Some(tbl) = table.btree() else {
         crate::bail_parse_error!("Error: table '{tbl_name}' is not a b-tree table.");
     };
-    if !tbl.has_rowid {
-        bail_parse_error!("CREATE INDEX on WITHOUT ROWID tables is not supported");
-    }
     let columns = resolve_sorted_columns(&tbl, &columns)?;
 
     // Block CREATE INDEX on non-orderable custom type columns and STRUCT/UNION columns
@@ -184,6 +181,11 @@ pub fn translate_create_index(
 
     let mut index_method = None;
     if let Some(using) = &using {
+        if !tbl.has_rowid {
+            crate::bail_parse_error!(
+                "Error: custom index modules are not supported on WITHOUT ROWID tables"
+            );
+        }
         let index_modules = &resolver.symbol_table.index_methods;
         let using = using.as_str();
         let index_module = index_modules.get(using);
@@ -200,7 +202,7 @@ pub fn translate_create_index(
             })?);
         }
     }
-    let idx = Arc::new(Index {
+    let mut idx = Index {
         name: idx_name.clone(),
         table_name: tbl.name.clone(),
         root_page: 0, //  we dont have access till its created, after we parse the schema table
@@ -211,9 +213,12 @@ pub fn translate_create_index(
         // store the *original* where clause, because we need to rewrite it
         // before translating, and it cannot reference a table alias
         where_clause: where_clause.clone(),
-        index_method: index_method.clone(),
+        index_method,
+        pk_suffix_start: None,
         on_conflict: None,
-    });
+    };
+    idx.append_pk_suffix(&tbl)?;
+    let idx = Arc::new(idx);
 
     if !idx.validate_where_expr(&table, resolver) {
         crate::bail_parse_error!(
@@ -229,42 +234,13 @@ pub fn translate_create_index(
     //
     // 1. sqlite_schema_cursor_id - sqlite_schema table
     // 2. index_cursor_id         - new index cursor
-    // 3. table_cursor_id         - table we are creating the index on
-    // 4. sorter_cursor_id        - sorter
-    // 5. pseudo_cursor_id        - pseudo table to store the sorted index values
+    //
+    // The cursors used to scan the table and sort the index entries are allocated
+    // by [emit_populate_index].
     let sqlite_table = resolver.schema().get_btree_table(SQLITE_TABLEID).unwrap();
     let sqlite_schema_cursor_id =
         program.alloc_cursor_id(CursorType::BTreeTable(sqlite_table.clone()));
-    let table_ref = program.table_reference_counter.next();
     let index_cursor_id = program.alloc_cursor_index(None, &idx)?;
-    let table_cursor_id = program.alloc_cursor_id_keyed(
-        CursorKey::table(table_ref),
-        CursorType::BTreeTable(tbl.clone()),
-    );
-    let sorter_cursor_id = program.alloc_cursor_id(CursorType::Sorter);
-    let pseudo_cursor_id = program.alloc_cursor_id(CursorType::Pseudo(PseudoCursorType {
-        column_count: tbl.columns().len(),
-    }));
-
-    let mut table_references = TableReferences::new(
-        vec![JoinedTable {
-            op: Operation::Scan(Scan::BTreeTable {
-                iter_dir: IterationDirection::Forwards,
-                index: None,
-            }),
-            table: Table::BTree(tbl.clone()),
-            identifier: tbl_name.clone(),
-            internal_id: table_ref,
-            join_info: None,
-            col_used_mask: ColumnUsedMask::default(),
-            column_use_counts: Vec::new(),
-            expression_index_usages: Vec::new(),
-            database_id: MAIN_DB_ID,
-            indexed: None,
-        }],
-        vec![],
-    );
-    let where_clause = idx.bind_where_expr(Some(&mut table_references), resolver);
 
     // Create a new B-Tree and store the root page index in a register
     let root_page_reg = program.alloc_register();
@@ -302,10 +278,96 @@ pub fn translate_create_index(
         Some(sql),
     )?;
 
-    if index_method
-        .as_ref()
-        .is_some_and(|m| !m.definition().backing_btree)
-    {
+    if !idx.is_backing_btree_index() {
+        emit_populate_index(
+            program,
+            resolver,
+            &tbl,
+            &idx,
+            database_id,
+            index_cursor_id,
+            RegisterOrLiteral::Register(root_page_reg),
+        )?;
+    }
+
+    // End of the outer loop
+    //
+    // Keep schema table open to emit ParseSchema, close the other cursors.
+    program.close_cursors(&[index_cursor_id]);
+
+    let current_schema_version = resolver.with_schema(database_id, |s| s.schema_version);
+    program.emit_insn(Insn::SetCookie {
+        db: database_id,
+        cookie: Cookie::SchemaVersion,
+        value: current_schema_version as i32 + 1,
+        p5: 0,
+    });
+    // Parse the schema table to get the index root page and add new index to Schema
+    let escaped_idx_name = escape_sql_string_literal(&idx_name);
+    let parse_schema_where_clause = format!("name = '{escaped_idx_name}' AND type = 'index'");
+    program.emit_insn(Insn::ParseSchema {
+        db: database_id,
+        where_clause: Some(parse_schema_where_clause),
+    });
+    // Close the final sqlite_schema cursor
+    program.emit_insn(Insn::Close {
+        cursor_id: sqlite_schema_cursor_id,
+    });
+
+    Ok(())
+}
+
+/// Emits the bytecode that fills an empty index with one entry for every row of its table.
+///
+/// B-tree index entries are sorted before they are appended to the index, which also lets
+/// UNIQUE indexes detect duplicate keys. Custom index method entries are inserted directly
+/// through the index method cursor while the table is scanned.
+fn emit_populate_index(
+    program: &mut ProgramBuilder,
+    resolver: &Resolver,
+    tbl: &Arc<BTreeTable>,
+    idx: &Arc<Index>,
+    database_id: usize,
+    index_cursor_id: usize,
+    index_root_page: RegisterOrLiteral<i64>,
+) -> crate::Result<()> {
+    let columns = &idx.columns;
+    // Allocate the cursors:
+    //
+    // 1. table_cursor_id  - table the index is built on
+    // 2. sorter_cursor_id - sorter
+    // 3. pseudo_cursor_id - pseudo table to store the sorted index values
+    let table_ref = program.table_reference_counter.next();
+    let table_cursor_id = program.alloc_cursor_id_keyed(
+        CursorKey::table(table_ref),
+        CursorType::BTreeTable(tbl.clone()),
+    );
+    let sorter_cursor_id = program.alloc_cursor_id(CursorType::Sorter);
+    let pseudo_cursor_id = program.alloc_cursor_id(CursorType::Pseudo(PseudoCursorType {
+        column_count: tbl.columns().len(),
+    }));
+
+    let mut table_references = TableReferences::new(
+        vec![JoinedTable {
+            op: Operation::Scan(Scan::BTreeTable {
+                iter_dir: IterationDirection::Forwards,
+                index: None,
+            }),
+            table: Table::BTree(tbl.clone()),
+            identifier: tbl.name.clone(),
+            internal_id: table_ref,
+            join_info: None,
+            col_used_mask: ColumnUsedMask::default(),
+            column_use_counts: Vec::new(),
+            expression_index_usages: Vec::new(),
+            database_id,
+            indexed: None,
+        }],
+        vec![],
+    );
+    let where_clause = idx.bind_where_expr(Some(&mut table_references), resolver);
+
+    if idx.index_method.is_some() {
         // open the table we are creating the index on for reading
         program.emit_insn(Insn::OpenRead {
             cursor_id: table_cursor_id,
@@ -317,7 +379,7 @@ pub fn translate_create_index(
         // newly sorted index records.
         program.emit_insn(Insn::OpenWrite {
             cursor_id: index_cursor_id,
-            root_page: RegisterOrLiteral::Register(root_page_reg),
+            root_page: index_root_page,
             db: database_id,
         });
 
@@ -361,7 +423,7 @@ pub fn translate_create_index(
                 resolver,
                 &mut table_references,
                 table_cursor_id,
-                &tbl,
+                tbl,
                 col,
                 start_reg + i,
             )?;
@@ -376,7 +438,7 @@ pub fn translate_create_index(
             start_reg: to_u16(start_reg),
             count: to_u16(columns.len() + 1),
             dest_reg: to_u16(record_reg),
-            index_name: Some(idx_name.clone()),
+            index_name: Some(idx.name.clone()),
             affinity_str: None,
         });
 
@@ -397,13 +459,19 @@ pub fn translate_create_index(
             pc_if_next: loop_start_label,
         });
         program.preassign_label_to_next_insn(loop_end_label);
-    } else if index_method.is_none() {
+    } else {
         // determine the order, collation, and nulls ordering of the columns in the index for the sorter
         let order_collations_nulls = idx
             .columns
             .iter()
             .map(|c| (c.order, c.collation, None))
             .collect();
+        // Indexes of WITHOUT ROWID tables end with the primary key columns instead of a rowid
+        let record_len = if idx.has_rowid {
+            columns.len() + 1
+        } else {
+            columns.len()
+        };
         // open the sorter and the pseudo table
         program.emit_insn(Insn::SorterOpen {
             cursor_id: sorter_cursor_id,
@@ -415,7 +483,7 @@ pub fn translate_create_index(
         program.emit_insn(Insn::OpenPseudo {
             cursor_id: pseudo_cursor_id,
             content_reg,
-            num_fields: columns.len() + 1,
+            num_fields: record_len,
         });
 
         // open the table we are creating the index on for reading
@@ -458,29 +526,31 @@ pub fn translate_create_index(
             skip_row_label = Some(label);
         }
 
-        let start_reg = program.alloc_registers(columns.len() + 1);
+        let start_reg = program.alloc_registers(record_len);
         for (i, col) in columns.iter().enumerate() {
             emit_index_column_value_from_cursor(
                 program,
                 resolver,
                 &mut table_references,
                 table_cursor_id,
-                &tbl,
+                tbl,
                 col,
                 start_reg + i,
             )?;
         }
-        let rowid_reg = start_reg + columns.len();
-        program.emit_insn(Insn::RowId {
-            cursor_id: table_cursor_id,
-            dest: rowid_reg,
-        });
+        if idx.has_rowid {
+            let rowid_reg = start_reg + columns.len();
+            program.emit_insn(Insn::RowId {
+                cursor_id: table_cursor_id,
+                dest: rowid_reg,
+            });
+        }
         let record_reg = program.alloc_register();
         program.emit_insn(Insn::MakeRecord {
             start_reg: to_u16(start_reg),
-            count: to_u16(columns.len() + 1),
+            count: to_u16(record_len),
             dest_reg: to_u16(record_reg),
-            index_name: Some(idx_name.clone()),
+            index_name: Some(idx.name.clone()),
             affinity_str: None,
         });
         program.emit_insn(Insn::SorterInsert {
@@ -501,7 +571,7 @@ pub fn translate_create_index(
         // newly sorted index records.
         program.emit_insn(Insn::OpenWrite {
             cursor_id: index_cursor_id,
-            root_page: RegisterOrLiteral::Register(root_page_reg),
+            root_page: index_root_page,
             db: database_id,
         });
 
@@ -516,7 +586,7 @@ pub fn translate_create_index(
 
         let sorted_record_reg = program.alloc_register();
 
-        if unique {
+        if idx.unique {
             // Since the records to be inserted are sorted, we can compare prev with current and if they are equal,
             // we fall through to Halt with a unique constraint violation error.
             let goto_label = program.allocate_label();
@@ -529,12 +599,12 @@ pub fn translate_create_index(
             program.emit_insn(Insn::SorterCompare {
                 cursor_id: sorter_cursor_id,
                 sorted_record_reg,
-                num_regs: columns.len(),
+                num_regs: idx.key_columns().len(),
                 pc_when_nonequal: goto_label,
             });
             program.emit_insn(Insn::Halt {
                 err_code: SQLITE_CONSTRAINT_UNIQUE,
-                description: format_unique_violation_desc(tbl_name.as_str(), &idx),
+                description: format_unique_violation_desc(tbl.name.as_str(), idx),
                 on_error: None,
                 description_reg: None,
             });
@@ -568,30 +638,7 @@ pub fn translate_create_index(
         program.preassign_label_to_next_insn(sorted_loop_end);
     }
 
-    // End of the outer loop
-    //
-    // Keep schema table open to emit ParseSchema, close the other cursors.
-    program.close_cursors(&[sorter_cursor_id, table_cursor_id, index_cursor_id]);
-
-    let current_schema_version = resolver.with_schema(database_id, |s| s.schema_version);
-    program.emit_insn(Insn::SetCookie {
-        db: database_id,
-        cookie: Cookie::SchemaVersion,
-        value: current_schema_version as i32 + 1,
-        p5: 0,
-    });
-    // Parse the schema table to get the index root page and add new index to Schema
-    let escaped_idx_name = escape_sql_string_literal(&idx_name);
-    let parse_schema_where_clause = format!("name = '{escaped_idx_name}' AND type = 'index'");
-    program.emit_insn(Insn::ParseSchema {
-        db: database_id,
-        where_clause: Some(parse_schema_where_clause),
-    });
-    // Close the final sqlite_schema cursor
-    program.emit_insn(Insn::Close {
-        cursor_id: sqlite_schema_cursor_id,
-    });
-
+    program.close_cursors(&[sorter_cursor_id, table_cursor_id]);
     Ok(())
 }
 
@@ -609,7 +656,7 @@ pub fn resolve_sorted_columns(
             let collation = explicit_collation.or_else(|| column.collation_opt());
             let expr = match column.generated_type() {
                 GeneratedType::Virtual { expr, .. } => Some(expr.clone()),
-                GeneratedType::NotGenerated => None,
+                GeneratedType::Stored { .. } | GeneratedType::NotGenerated => None,
             };
             resolved.push(IndexColumn {
                 name: column_name,
@@ -1130,6 +1177,129 @@ pub fn translate_drop_index(
     Ok(())
 }
 
+/// Translate `REINDEX [collation-name | [schema.]table-name | [schema.]index-name]` statement.
+///
+/// Without a name every index of every database is rebuilt. A name that matches a collation
+/// sequence rebuilds every index using that collation, otherwise all indexes of the named table
+/// or the named index itself are rebuilt. Each index is emptied and filled again from its table.
+pub fn translate_reindex(
+    name: Option<QualifiedName>,
+    resolver: &Resolver,
+    program: &mut ProgramBuilder,
+    connection: &Arc<crate::Connection>,
+) -> crate::Result<()> {
+    if connection.mvcc_enabled() {
+        bail_parse_error!("REINDEX is not supported in MVCC mode");
+    }
+    let opts = ProgramBuilderOpts::new(5, 40, 5);
+    program.extend(&opts);
+
+    let mut indexes_to_rebuild: Vec<(usize, Arc<Index>)> = Vec::new();
+    let collation = name
+        .as_ref()
+        .filter(|name| name.db_name.is_none())
+        .and_then(|name| CollationSeq::new(name.name.as_str()).ok());
+
+    if name.is_none() || collation.is_some() {
+        for (database_id, _, _) in connection.list_all_databases() {
+            resolver.with_schema(database_id, |schema| {
+                for idx in schema.indexes.values().flatten() {
+                    let uses_collation = collation.is_none_or(|collation| {
+                        idx.columns
+                            .iter()
+                            .any(|col| col.collation.unwrap_or_default() == collation)
+                    });
+                    if uses_collation {
+                        indexes_to_rebuild.push((database_id, idx.clone()));
+                    }
+                }
+            });
+        }
+    } else if let Some(name) = name {
+        let object_name = normalize_ident(name.name.as_str());
+        let database_id = resolver.resolve_existing_table_database_id_qualified(&name)?;
+        let table_indexes = resolver.with_schema(database_id, |schema| {
+            schema.get_table(&object_name).map(|_| {
+                schema
+                    .indexes
+                    .get(&object_name)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+        });
+        if let Some(table_indexes) = table_indexes {
+            indexes_to_rebuild.extend(table_indexes.into_iter().map(|idx| (database_id, idx)));
+        } else {
+            let database_id = resolver.resolve_existing_index_database_id(&name)?;
+            let idx = resolver.with_schema(database_id, |schema| {
+                schema
+                    .indexes
+                    .values()
+                    .flatten()
+                    .find(|idx| idx.name == object_name)
+                    .cloned()
+            });
+            let Some(idx) = idx else {
+                bail_parse_error!("unable to identify the object to be reindexed");
+            };
+            if idx.is_backing_btree_index() {
+                bail_parse_error!(
+                    "index {} is managed by an index method: reindex its table instead",
+                    idx.name
+                );
+            }
+            indexes_to_rebuild.push((database_id, idx));
+        }
+    }
+
+    // Index methods are rebuilt last: recreating their storage runs nested DDL that may
+    // move root pages, so the b-tree indexes are cleared by root page before that.
+    indexes_to_rebuild.sort_by_key(|(_, idx)| idx.index_method.is_some());
+    for (database_id, idx) in indexes_to_rebuild {
+        // The b-trees backing an index method are rebuilt along with the index that owns them.
+        if idx.is_backing_btree_index() {
+            continue;
+        }
+        let Some(tbl) = resolver.with_schema(database_id, |schema| {
+            schema.get_btree_table(&idx.table_name)
+        }) else {
+            continue;
+        };
+        let schema_cookie = resolver.with_schema(database_id, |s| s.schema_version);
+        program.begin_write_on_database(database_id, schema_cookie);
+
+        let index_cursor_id = program.alloc_cursor_index(None, &idx)?;
+        if idx.index_method.is_some() {
+            // An index method owns its storage: drop and recreate it before refilling it.
+            program.emit_insn(Insn::IndexMethodDestroy {
+                db: database_id,
+                cursor_id: index_cursor_id,
+            });
+            program.emit_insn(Insn::IndexMethodCreate {
+                db: database_id,
+                cursor_id: index_cursor_id,
+            });
+        } else {
+            program.emit_insn(Insn::Clear {
+                db: database_id,
+                root: idx.root_page,
+            });
+        }
+        emit_populate_index(
+            program,
+            resolver,
+            &tbl,
+            &idx,
+            database_id,
+            index_cursor_id,
+            RegisterOrLiteral::Literal(idx.root_page),
+        )?;
+        program.close_cursors(&[index_cursor_id]);
+    }
+
+    Ok(())
+}
+
 /// Translate `OPTIMIZE INDEX [idx_name]` statement.
 /// If idx_name is provided, optimize that specific index.
 /// If idx_name is None, optimize all index method indexes.
@@ -1,5 +1,5 @@
 use crate::schema::ColumnLayout;
-use crate::translate::emitter::{emit_index_column_value_old_image, gencol};
+use crate::translate::emitter::{emit_index_column_value_old_image, gencol, RowLocator};
 use crate::turso_debug_assert;
 use crate::{
     error::{SQLITE_CONSTRAINT_NOTNULL, SQLITE_CONSTRAINT_PRIMARYKEY, SQLITE_CONSTRAINT_UNIQUE},
@@ -43,6 +43,7 @@ use crate::{
             collect_set_clauses_for_upsert, emit_upsert, resolve_upsert_target,
             ResolvedUpsertTarget,
         },
+        view_dml::translate_insert_into_view,
     },
     util::normalize_ident,
     vdbe::{
@@ -53,7 +54,7 @@ use crate::{
     },
     CaptureDataChangesExt, Connection, LimboError, Result, VirtualTable,
 };
-use gencol::compute_virtual_columns;
+use gencol::{compute_stored_columns, compute_virtual_columns};
 use std::num::NonZeroUsize;
 use turso_macros::turso_assert;
 use turso_parser::ast::{
@@ -78,7 +79,7 @@ fn validate(
     }
     // Check if this table has any incompatible dependent views
     // Check if this is a materialized view
-    if resolver.schema().is_materialized_view(table_name) {
+    if resolver.with_schema(database_id, |s| s.is_materialized_view(table_name)) {
         crate::bail_parse_error!("cannot modify materialized view {}", table_name);
     }
     if table.btree().is_some_and(|t| t.has_autoincrement)
@@ -88,7 +89,7 @@ fn validate(
             "AUTOINCREMENT is not supported in MVCC mode (journal_mode=experimental_mvcc)"
         );
     }
-    resolver.schema().with_incompatible_dependent_views(table_name, |views| {
+    resolver.with_schema(database_id, |s| s.with_incompatible_dependent_views(table_name, |views| {
     if !views.is_empty() {
         use crate::incremental::compiler::DBSP_CIRCUIT_VERSION;
         crate::bail_parse_error!(
@@ -99,7 +100,7 @@ fn validate(
         );
     }
     Ok(())
-    })
+    }))
 }
 
 pub struct TempTableCtx {
@@ -272,6 +273,19 @@ pub fn translate_insert(
 
     let database_id = resolver.resolve_existing_table_database_id_qualified(&tbl_name)?;
     let table_name = &tbl_name.name;
+    if let Some(view) = resolver.with_schema(database_id, |s| s.get_view(table_name.as_str())) {
+        return translate_insert_into_view(
+            program,
+            resolver,
+            &view,
+            database_id,
+            &tbl_name,
+            &columns,
+            body,
+            &returning,
+            connection,
+        );
+    }
     let table = match resolver.with_schema(database_id, |s| s.get_table(table_name.as_str())) {
         Some(table) => table,
         None => crate::bail_parse_error!("no such table: {}", table_name),
@@ -377,34 +391,6 @@ pub fn translate_insert(
             || resolver.with_schema(database_id, |s| {
                 s.any_resolved_fks_referencing(table_name.as_str())
             }));
-    if !btree_table.has_rowid {
-        if has_fks {
-            crate::bail_parse_error!("foreign keys on WITHOUT ROWID tables are not supported");
-        }
-        if on_conflict == Some(ResolveType::Replace) || !upsert_actions.is_empty() {
-            crate::bail_parse_error!(
-                "UPSERT and REPLACE on WITHOUT ROWID tables are not supported"
-            );
-        }
-        if cdc_table.is_some() {
-            crate::bail_parse_error!("CDC on WITHOUT ROWID tables is not supported");
-        }
-        if !resolver
-            .schema()
-            .get_dependent_materialized_views(table_name.as_str())
-            .is_empty()
-        {
-            crate::bail_parse_error!(
-                "materialized views on WITHOUT ROWID tables are not supported"
-            );
-        }
-        if resolver.with_schema(database_id, |s| {
-            s.get_indices(table_name.as_str()).next().is_some()
-        }) {
-            crate::bail_parse_error!("secondary indexes on WITHOUT ROWID tables are not supported");
-        }
-    }
-
     let mut ctx = InsertEmitCtx::new(
         program,
         resolver,
@@ -484,11 +470,12 @@ pub fn translate_insert(
     // trigger bodies see affinity-applied values. Affinity is idempotent, so
     // applying it here means we can skip the per-trigger Copy+Affinity in fire_trigger.
     if !ctx.table.is_strict {
-        let affinity = insertion
-            .col_mappings
-            .iter()
-            .filter(|cm| !cm.column.is_virtual_generated())
-            .map(|col_mapping| col_mapping.column.affinity());
+        let affinity = ctx
+            .table
+            .columns_in_record_order()
+            .into_iter()
+            .filter(|column| !column.is_virtual_generated())
+            .map(|column| column.affinity());
 
         // Only emit Affinity if there's meaningful affinity to apply
         // (i.e., not all BLOB/NONE affinity)
@@ -528,13 +515,23 @@ pub fn translate_insert(
 
     let has_before_triggers = !relevant_before_triggers.is_empty();
     if has_before_triggers {
-        compute_virtual_columns(
-            program,
-            &ctx.table.columns_topo_sort()?,
-            &dml_ctx,
-            resolver,
-            &btree_table,
-        )?;
+        if ctx.table.has_stored_columns() {
+            compute_stored_columns(
+                program,
+                &ctx.table.columns_topo_sort()?,
+                &dml_ctx,
+                resolver,
+                &btree_table,
+            )?;
+        } else {
+            compute_virtual_columns(
+                program,
+                &ctx.table.columns_topo_sort()?,
+                &dml_ctx,
+                resolver,
+                &btree_table,
+            )?;
+        }
 
         // In SQLite, NEW.<rowid_alias> returns -1 in BEFORE INSERT triggers when the rowid
         // hasn't been assigned yet (i.e., it's NULL). We need to temporarily set the key
@@ -640,6 +637,18 @@ pub fn translate_insert(
 
     program.preassign_label_to_next_insn(ctx.key_labels.key_ready_for_check);
 
+    // Stored generated columns are persisted in the record, so compute them once the
+    // rowid is known and before the values are type-checked and encoded.
+    if ctx.table.has_stored_columns() {
+        compute_stored_columns(
+            program,
+            &ctx.table.columns_topo_sort()?,
+            &dml_ctx,
+            resolver,
+            &btree_table,
+        )?;
+    }
+
     if ctx.table.is_strict {
         // Pre-encode TypeCheck: validate input types match the custom type's
         // declared value type BEFORE encoding. This catches type mismatches
@@ -865,7 +874,7 @@ pub fn translate_insert(
     // Create and insert the record
     emit_make_record(
         program,
-        insertion.col_mappings.iter().map(|m| m.column),
+        ctx.table.columns_in_record_order(),
         insertion.base_reg,
         insertion.record_register(),
         ctx.table.is_strict,
@@ -1340,9 +1349,11 @@ fn emit_commit_phase(
             emit_partial_index_check(program, resolver, index, insertion, ctx.table)?;
 
         let num_cols = index.columns.len();
-        let idx_start_reg = program.alloc_registers(num_cols + 1);
+        let record_len = num_cols + usize::from(index.has_rowid);
+        let idx_start_reg = program.alloc_registers(record_len);
 
-        // Build [key cols..., rowid] from insertion registers
+        // Build [key cols..., rowid] from insertion registers. Indexes of WITHOUT ROWID
+        // tables already end with the primary key columns instead.
         for (i, idx_col) in index.columns.iter().enumerate() {
             emit_index_column_value_for_insert(
                 program,
@@ -1353,16 +1364,18 @@ fn emit_commit_phase(
                 idx_start_reg + i,
             )?;
         }
-        program.emit_insn(Insn::Copy {
-            src_reg: insertion.key_register(),
-            dst_reg: idx_start_reg + num_cols,
-            extra_amount: 0,
-        });
+        if index.has_rowid {
+            program.emit_insn(Insn::Copy {
+                src_reg: insertion.key_register(),
+                dst_reg: idx_start_reg + num_cols,
+                extra_amount: 0,
+            });
+        }
 
         let record_reg = program.alloc_register();
         program.emit_insn(Insn::MakeRecord {
             start_reg: to_u16(idx_start_reg),
-            count: to_u16(num_cols + 1),
+            count: to_u16(record_len),
             dest_reg: to_u16(record_reg),
             index_name: Some(index.name.clone()),
             affinity_str: None,
@@ -1371,7 +1384,7 @@ fn emit_commit_phase(
             cursor_id: idx_cursor_id,
             record_reg,
             unpacked_start: Some(idx_start_reg),
-            unpacked_count: Some((num_cols + 1) as u16),
+            unpacked_count: Some(record_len as u16),
             flags: IdxInsertFlags::new().nchange(true),
         });
 
@@ -2797,7 +2810,25 @@ fn emit_pk_uniqueness_check(
             record_reg: pk_regs,
             num_regs: ctx.table.primary_key_columns.len(),
         });
-        if let Some(position) = position.or(upsert_catch_all) {
+        if preflight.on_replace {
+            // NoConflict left the cursor on the conflicting row; there is no rowid to seek by.
+            program.emit_insn(Insn::Null {
+                dest: ctx.conflict_rowid_reg,
+                dest_end: None,
+            });
+            emit_replace_delete_conflicting_row(
+                program,
+                resolver,
+                preflight.connection,
+                ctx,
+                preflight.table_references,
+            )?;
+        } else if let Some(position) = position.or(upsert_catch_all) {
+            // NoConflict left the cursor on the conflicting row; there is no rowid to capture.
+            program.emit_insn(Insn::Null {
+                dest: ctx.conflict_rowid_reg,
+                dest_end: None,
+            });
             program.emit_insn(Insn::Goto {
                 target_pc: preflight.upsert_actions[position].1,
             });
@@ -2922,8 +2953,10 @@ fn emit_index_uniqueness_check(
         emit_partial_index_check(program, resolver, index, insertion, ctx.table)?;
 
     let num_cols = index.columns.len();
-    // allocate scratch registers for the index columns plus rowid
-    let idx_start_reg = program.alloc_registers(num_cols + 1);
+    // allocate scratch registers for the index columns plus rowid; indexes of WITHOUT ROWID
+    // tables end with the PRIMARY KEY columns instead
+    let record_len = num_cols + usize::from(index.has_rowid);
+    let idx_start_reg = program.alloc_registers(record_len);
 
     // build unpacked key [idx_start_reg .. idx_start_reg+num_cols-1], and rowid in last reg,
     // copy each index column from the table's column registers into these scratch regs
@@ -2938,11 +2971,13 @@ fn emit_index_uniqueness_check(
         )?;
     }
     // last register is the rowid
-    program.emit_insn(Insn::Copy {
-        src_reg: insertion.key_register(),
-        dst_reg: idx_start_reg + num_cols,
-        extra_amount: 0,
-    });
+    if index.has_rowid {
+        program.emit_insn(Insn::Copy {
+            src_reg: insertion.key_register(),
+            dst_reg: idx_start_reg + num_cols,
+            extra_amount: 0,
+        });
+    }
 
     if index.unique {
         emit_unique_index_check(
@@ -2964,7 +2999,7 @@ fn emit_index_uniqueness_check(
             let record_reg = program.alloc_register();
             program.emit_insn(Insn::MakeRecord {
                 start_reg: to_u16(idx_start_reg),
-                count: to_u16(num_cols + 1),
+                count: to_u16(record_len),
                 dest_reg: to_u16(record_reg),
                 index_name: Some(index.name.clone()),
                 affinity_str: None,
@@ -2973,7 +3008,7 @@ fn emit_index_uniqueness_check(
                 cursor_id: idx_cursor_id,
                 record_reg,
                 unpacked_start: Some(idx_start_reg),
-                unpacked_count: Some((num_cols + 1) as u16),
+                unpacked_count: Some(record_len as u16),
                 flags: IdxInsertFlags::new().nchange(true),
             });
         }
@@ -2986,6 +3021,30 @@ fn emit_index_uniqueness_check(
     Ok(())
 }
 
+/// Capture the row that a conflicting unique index entry belongs to: its rowid goes into
+/// `ctx.conflict_rowid_reg`. WITHOUT ROWID tables have no rowid, so the register is set to NULL
+/// and the table cursor is positioned on the row by the PRIMARY KEY stored in the entry instead.
+fn emit_locate_conflicting_row(
+    program: &mut ProgramBuilder,
+    ctx: &InsertEmitCtx,
+    index: &Index,
+    idx_cursor_id: usize,
+) {
+    if index.has_rowid {
+        program.emit_insn(Insn::IdxRowId {
+            cursor_id: idx_cursor_id,
+            dest: ctx.conflict_rowid_reg,
+        });
+        return;
+    }
+    let conflicting_row = RowLocator::from_index_entry(program, ctx.table, index, idx_cursor_id);
+    conflicting_row.emit_seek(program, ctx.cursor_id, ctx.loop_labels.row_done);
+    program.emit_insn(Insn::Null {
+        dest: ctx.conflict_rowid_reg,
+        dest_end: None,
+    });
+}
+
 /// Emit bytecode for unique index conflict detection and handling.
 #[allow(clippy::too_many_arguments)]
 fn emit_unique_index_check(
@@ -3025,7 +3084,7 @@ fn emit_unique_index_check(
             cursor_id: idx_cursor_id,
             target_pc: next_check,
             record_reg: idx_start_reg,
-            num_regs: num_cols,
+            num_regs: index.key_columns().len(),
         });
         // Conflict detected, figure out if this UPSERT handles the conflict
         if let Some(position) = position.or(upsert_catch_all) {
@@ -3038,10 +3097,7 @@ fn emit_unique_index_check(
                 }
                 UpsertDo::Set { .. } => {
                     // Route to DO UPDATE: capture conflicting rowid then jump
-                    program.emit_insn(Insn::IdxRowId {
-                        cursor_id: idx_cursor_id,
-                        dest: ctx.conflict_rowid_reg,
-                    });
+                    emit_locate_conflicting_row(program, ctx, index, idx_cursor_id);
                     program.emit_insn(Insn::Goto {
                         target_pc: preflight.upsert_actions[position].1,
                     });
@@ -3072,14 +3128,11 @@ fn emit_unique_index_check(
             cursor_id: idx_cursor_id,
             target_pc: ok,
             record_reg: idx_start_reg,
-            num_regs: num_cols,
+            num_regs: index.key_columns().len(),
         });
         if preflight.on_replace {
             // REPLACE: delete conflicting row immediately, then insert eagerly.
-            program.emit_insn(Insn::IdxRowId {
-                cursor_id: idx_cursor_id,
-                dest: ctx.conflict_rowid_reg,
-            });
+            emit_locate_conflicting_row(program, ctx, index, idx_cursor_id);
             emit_replace_delete_conflicting_row(
                 program,
                 resolver,
@@ -3113,10 +3166,11 @@ fn emit_unique_index_check(
         if preflight.on_replace {
             // REPLACE: insert index entry eagerly (right after delete).
             // IdxDelete repositions the cursor, so we must NOT use USE_SEEK.
+            let record_len = num_cols + usize::from(index.has_rowid);
             let record_reg = program.alloc_register();
             program.emit_insn(Insn::MakeRecord {
                 start_reg: to_u16(idx_start_reg),
-                count: to_u16(num_cols + 1),
+                count: to_u16(record_len),
                 dest_reg: to_u16(record_reg),
                 index_name: Some(index.name.clone()),
                 affinity_str: None,
@@ -3125,7 +3179,7 @@ fn emit_unique_index_check(
                 cursor_id: idx_cursor_id,
                 record_reg,
                 unpacked_start: Some(idx_start_reg),
-                unpacked_count: Some((num_cols + 1) as u16),
+                unpacked_count: Some(record_len as u16),
                 flags: IdxInsertFlags::new().nchange(true),
             });
         }
@@ -3422,19 +3476,19 @@ pub(crate) fn halt_desc_and_on_error(
 }
 
 pub fn format_unique_violation_desc(table_name: &str, index: &Index) -> String {
-    if index.columns.len() == 1 {
-        let mut s = String::with_capacity(table_name.len() + 1 + index.columns[0].name.len());
+    let columns = index.key_columns();
+    if columns.len() == 1 {
+        let mut s = String::with_capacity(table_name.len() + 1 + columns[0].name.len());
         s.push_str(table_name);
         s.push('.');
-        s.push_str(&index.columns[0].name);
+        s.push_str(&columns[0].name);
         s
     } else {
-        let mut s = String::with_capacity(table_name.len() + 3 + 4 * index.columns.len());
+        let mut s = String::with_capacity(table_name.len() + 3 + 4 * columns.len());
         s.push_str(table_name);
         s.push_str(".(");
         s.push_str(
-            &index
-                .columns
+            &columns
                 .iter()
                 .map(|c| c.name.as_str())
                 .collect::<Vec<_>>()
@@ -3709,11 +3763,15 @@ fn emit_replace_delete_conflicting_row(
     ctx: &mut InsertEmitCtx,
     table_references: &mut TableReferences,
 ) -> Result<()> {
-    program.emit_insn(Insn::SeekRowid {
-        cursor_id: ctx.cursor_id,
-        src_reg: ctx.conflict_rowid_reg,
-        target_pc: ctx.halt_label,
-    });
+    // WITHOUT ROWID tables are already positioned on the conflicting row by the PRIMARY KEY probe
+    // or by emit_locate_conflicting_row.
+    if ctx.table.has_rowid {
+        program.emit_insn(Insn::SeekRowid {
+            cursor_id: ctx.cursor_id,
+            src_reg: ctx.conflict_rowid_reg,
+            target_pc: ctx.halt_label,
+        });
+    }
 
     // Phase 1: Before Delete - build parent key registers and handle NoAction/Restrict.
     // CASCADE/SetNull/SetDefault actions are prepared but deferred until after Delete.
@@ -3775,7 +3833,7 @@ fn emit_replace_delete_conflicting_row(
             None
         };
 
-        let num_regs = index.columns.len() + 1;
+        let num_regs = index.columns.len() + usize::from(index.has_rowid);
         let start_reg = program.alloc_registers(num_regs);
 
         let table_internal_id = table_references.joined_tables()[0].internal_id;
@@ -3790,11 +3848,13 @@ fn emit_replace_delete_conflicting_row(
                 start_reg + reg_offset,
             )?;
         }
-        program.emit_insn(Insn::Copy {
-            src_reg: ctx.conflict_rowid_reg,
-            dst_reg: start_reg + num_regs - 1,
-            extra_amount: 0,
-        });
+        if index.has_rowid {
+            program.emit_insn(Insn::Copy {
+                src_reg: ctx.conflict_rowid_reg,
+                dst_reg: start_reg + num_regs - 1,
+                extra_amount: 0,
+            });
+        }
         program.emit_insn(Insn::IdxDelete {
             start_reg,
             num_regs,
@@ -3862,9 +3922,7 @@ pub fn emit_fk_child_insert_checks(
     database_id: usize,
     layout: &ColumnLayout,
 ) -> crate::Result<()> {
-    for fk_ref in
-        resolver.with_schema(database_id, |s| s.resolved_fks_for_child(&child_tbl.name))?
-    {
+    for fk_ref in resolver.resolved_fks_for_child(database_id, &child_tbl.name)? {
         let is_self_ref = fk_ref.fk.parent_table.eq_ignore_ascii_case(&child_tbl.name);
 
         // Short-circuit if any NEW component is NULL
@@ -3979,7 +4037,7 @@ pub fn emit_fk_child_insert_checks(
                         src_reg: if col.is_rowid_alias() {
                             new_rowid_reg
                         } else {
-                            new_start_reg + pos
+                            layout.to_register(new_start_reg, pos)
                         },
                         dst_reg: parent_new + i,
                         extra_amount: 0,
@@ -4125,9 +4183,7 @@ pub fn emit_parent_side_fk_decrement_on_insert(
     resolver: &Resolver,
     database_id: usize,
 ) -> crate::Result<()> {
-    for pref in resolver.with_schema(database_id, |s| {
-        s.resolved_fks_referencing(&parent_table.name)
-    })? {
+    for pref in resolver.resolved_fks_referencing(database_id, &parent_table.name)? {
         let is_self_ref = pref
             .child_table
             .name
@@ -4209,7 +4265,7 @@ pub fn emit_parent_side_fk_decrement_on_insert(
                 let tmp = program.alloc_register();
                 program.emit_insn(Insn::Column {
                     cursor_id: ccur,
-                    column: pos,
+                    column: child_tbl.logical_to_physical_column(pos),
                     dest: tmp,
                     default: None,
                 });
@@ -323,7 +323,9 @@ fn translate_integrity_check_impl(
                             name,
                         ))
                     }
-                    GeneratedType::NotGenerated => Some((BoundIndexColumn::Column(idx), name)),
+                    GeneratedType::Stored { .. } | GeneratedType::NotGenerated => {
+                        Some((BoundIndexColumn::Column(idx), name))
+                    }
                 }
             })
             .collect();
@@ -447,7 +449,9 @@ fn translate_integrity_check_impl(
                 value: 1,
             });
 
-            let key_start_reg = program.alloc_registers(bound_index.columns.len() + 1);
+            // Entries of WITHOUT ROWID table indexes end with the PRIMARY KEY instead of a rowid
+            let record_len = bound_index.columns.len() + usize::from(bound_index.index.has_rowid);
+            let key_start_reg = program.alloc_registers(record_len);
             for (i, col) in bound_index.columns.iter().enumerate() {
                 let target = key_start_reg + i;
                 match col {
@@ -494,11 +498,13 @@ fn translate_integrity_check_impl(
                 }
             }
 
-            let rowid_reg = key_start_reg + bound_index.columns.len();
-            program.emit_insn(Insn::RowId {
-                cursor_id: table_cursor_id,
-                dest: rowid_reg,
-            });
+            if bound_index.index.has_rowid {
+                let rowid_reg = key_start_reg + bound_index.columns.len();
+                program.emit_insn(Insn::RowId {
+                    cursor_id: table_cursor_id,
+                    dest: rowid_reg,
+                });
+            }
 
             if !quick {
                 let found_label = program.allocate_label();
@@ -507,7 +513,7 @@ fn translate_integrity_check_impl(
                     cursor_id: bound_index.cursor_id,
                     target_pc: found_label,
                     record_reg: key_start_reg,
-                    num_regs: bound_index.columns.len() + 1,
+                    num_regs: record_len,
                 });
                 emit_row_missing_from_index_error(
                     program,
@@ -530,7 +536,13 @@ fn translate_integrity_check_impl(
                     //   unique key is valid if any key column is NULL, OR
                     //   the next index entry is strictly greater on key columns.
                     let unique_ok = program.allocate_label();
-                    for (i, is_nullable) in bound_index.unique_nullable.iter().enumerate() {
+                    let num_key_columns = bound_index.index.key_columns().len();
+                    for (i, is_nullable) in bound_index
+                        .unique_nullable
+                        .iter()
+                        .take(num_key_columns)
+                        .enumerate()
+                    {
                         if *is_nullable {
                             program.emit_insn(Insn::IsNull {
                                 reg: key_start_reg + i,
@@ -552,7 +564,7 @@ fn translate_integrity_check_impl(
                     program.emit_insn(Insn::IdxGT {
                         cursor_id: bound_index.cursor_id,
                         start_reg: key_start_reg,
-                        num_regs: bound_index.columns.len(),
+                        num_regs: num_key_columns,
                         target_pc: unique_ok,
                     });
                     program.emit_string8(
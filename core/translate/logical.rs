@@ -7,15 +7,19 @@
 //!
 //! The main entry point is `LogicalPlanBuilder` which constructs logical plans
 //! from SQL AST nodes.
-use crate::function::AggFunc;
+use crate::function::{AggFunc, ExtFunc, WindowFunc};
 use crate::numeric::Numeric;
-use crate::schema::{Schema, Type};
+use crate::schema::{Schema, Table, Type};
 use crate::sync::Arc;
+use crate::translate::collate::CollationSeq;
+use crate::translate::plan::WindowFunctionKind;
 use crate::turso_assert_ne;
 use crate::types::Value;
-use crate::{LimboError, Result};
+use crate::vtab::VirtualTable;
+use crate::{LimboError, Result, SymbolTable};
 use rustc_hash::FxHashMap as HashMap;
 use std::fmt::{self, Display, Formatter};
+use turso_ext::VTabKind;
 use turso_macros::match_ignore_ascii_case;
 use turso_parser::ast;
 
@@ -112,6 +116,8 @@ pub enum LogicalPlan {
     Filter(Filter),
     /// Aggregate - GROUP BY with aggregate functions
     Aggregate(Aggregate),
+    /// Window - window functions sharing one PARTITION BY and ORDER BY
+    Window(Window),
     /// Join - combining two relations
     Join(Join),
     /// Sort - ORDER BY clause
@@ -120,8 +126,12 @@ pub enum LogicalPlan {
     Limit(Limit),
     /// Table scan - reading from a base table
     TableScan(TableScan),
-    /// Union - UNION/UNION ALL/INTERSECT/EXCEPT
+    /// Table-valued function - rows of a function called for each input row
+    TableFunction(TableFunction),
+    /// Union - UNION/UNION ALL
     Union(Union),
+    /// Set operation - INTERSECT/EXCEPT
+    SetOperation(SetOperation),
     /// Distinct - remove duplicates
     Distinct(Distinct),
     /// Empty relation - no rows
@@ -141,11 +151,14 @@ impl LogicalPlan {
             LogicalPlan::Projection(p) => &p.schema,
             LogicalPlan::Filter(f) => f.input.schema(),
             LogicalPlan::Aggregate(a) => &a.schema,
+            LogicalPlan::Window(w) => &w.schema,
             LogicalPlan::Join(j) => &j.schema,
             LogicalPlan::Sort(s) => s.input.schema(),
             LogicalPlan::Limit(l) => l.input.schema(),
             LogicalPlan::TableScan(t) => &t.schema,
+            LogicalPlan::TableFunction(t) => &t.schema,
             LogicalPlan::Union(u) => &u.schema,
+            LogicalPlan::SetOperation(s) => &s.schema,
             LogicalPlan::Distinct(d) => d.input.schema(),
             LogicalPlan::EmptyRelation(e) => &e.schema,
             LogicalPlan::Values(v) => &v.schema,
@@ -179,6 +192,17 @@ pub struct Aggregate {
     pub schema: SchemaRef,
 }
 
+/// Window operator - appends one column per window function to each input row.
+///
+/// All functions of a node share the same PARTITION BY and ORDER BY; a SELECT with
+/// several window specifications gets one node per specification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Window {
+    pub input: Arc<LogicalPlan>,
+    pub window_expr: Vec<LogicalExpr>,
+    pub schema: SchemaRef,
+}
+
 /// Types of joins
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum JoinType {
@@ -213,6 +237,9 @@ pub struct SortExpr {
     pub expr: LogicalExpr,
     pub asc: bool,
     pub nulls_first: bool,
+    /// Collation of text values, from a COLLATE clause or the declaration of the sorted
+    /// column. None means BINARY.
+    pub collation: Option<CollationSeq>,
 }
 
 /// Limit operator - LIMIT/OFFSET
@@ -232,6 +259,30 @@ pub struct TableScan {
     pub projection: Option<Vec<usize>>, // Column indices to project
 }
 
+/// Table-valued function joined to the rows before it in the FROM clause.
+///
+/// The function is called once for each input row, with arguments that may reference the
+/// columns of that row (`FROM t, json_each(t.tags)`), and its rows are appended to the row.
+#[derive(Debug, Clone)]
+pub struct TableFunction {
+    pub input: Arc<LogicalPlan>,
+    pub vtab: Arc<VirtualTable>,
+    pub args: Vec<LogicalExpr>,
+    /// LEFT JOIN: input rows the function returns no rows for are kept, padded with NULLs
+    pub outer: bool,
+    pub schema: SchemaRef,
+}
+
+impl PartialEq for TableFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.input == other.input
+            && self.vtab.name == other.vtab.name
+            && self.args == other.args
+            && self.outer == other.outer
+            && self.schema == other.schema
+    }
+}
+
 /// Union operator
 #[derive(Debug, Clone, PartialEq)]
 pub struct Union {
@@ -240,6 +291,22 @@ pub struct Union {
     pub schema: SchemaRef,
 }
 
+/// Kind of a set operation other than UNION
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetOperator {
+    Intersect,
+    Except,
+}
+
+/// Set operation - INTERSECT/EXCEPT. Like UNION, the result has no duplicates
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetOperation {
+    pub left: Arc<LogicalPlan>,
+    pub right: Arc<LogicalPlan>,
+    pub op: SetOperator,
+    pub schema: SchemaRef,
+}
+
 /// Distinct operator
 #[derive(Debug, Clone, PartialEq)]
 pub struct Distinct {
@@ -298,6 +365,14 @@ pub enum LogicalExpr {
         args: Vec<LogicalExpr>,
         distinct: bool,
     },
+    /// Window function, or an aggregate used as a window function
+    WindowFunction {
+        fun: WindowFunctionKind,
+        args: Vec<LogicalExpr>,
+        partition_by: Vec<LogicalExpr>,
+        order_by: Vec<SortExpr>,
+        frame: Option<ast::FrameClause>,
+    },
     /// Scalar function call
     ScalarFunction { fun: String, args: Vec<LogicalExpr> },
     /// CASE expression
@@ -411,6 +486,10 @@ pub type AggregateFunction = AggFunc;
 pub struct LogicalPlanBuilder<'a> {
     schema: &'a Schema,
     ctes: HashMap<String, Arc<LogicalPlan>>,
+    // Used to resolve aggregate functions registered by extensions
+    syms: Option<&'a SymbolTable>,
+    // Named windows of the SELECT being built (WINDOW clause)
+    windows: HashMap<String, ast::Window>,
 }
 
 impl<'a> LogicalPlanBuilder<'a> {
@@ -418,9 +497,17 @@ impl<'a> LogicalPlanBuilder<'a> {
         Self {
             schema,
             ctes: HashMap::default(),
+            syms: None,
+            windows: HashMap::default(),
         }
     }
 
+    /// Resolve function names that are not built in against the given symbol table
+    pub fn with_symbols(mut self, syms: &'a SymbolTable) -> Self {
+        self.syms = Some(syms);
+        self
+    }
+
     /// Main entry point: compile a statement to a logical plan
     pub fn build_statement(&mut self, stmt: &ast::Stmt) -> Result<LogicalPlan> {
         match stmt {
@@ -518,7 +605,7 @@ impl<'a> LogicalPlanBuilder<'a> {
                 from,
                 where_clause,
                 group_by,
-                window_clause: _,
+                window_clause,
             } => {
                 // Start with FROM clause
                 let mut plan = if let Some(from) = from {
@@ -540,16 +627,31 @@ impl<'a> LogicalPlanBuilder<'a> {
                     });
                 }
 
+                // Named windows are visible to the OVER clauses of this SELECT only
+                let windows = window_clause
+                    .iter()
+                    .map(|def| (Self::name_to_string(&def.name), def.window.clone()))
+                    .collect();
+                let outer_windows = std::mem::replace(&mut self.windows, windows);
+
                 // Apply GROUP BY and aggregations
-                if let Some(group_by) = group_by {
-                    plan = self.build_aggregate(plan, group_by, columns)?;
-                } else if Self::has_aggregates(columns) {
-                    // Aggregation without GROUP BY
-                    plan = self.build_aggregate_no_group(plan, columns)?;
+                let result = if group_by.is_some() || self.has_aggregates(columns) {
+                    if Self::has_window_functions(columns) {
+                        Err(LimboError::ParseError(
+                            "window functions over aggregated rows are not supported".to_string(),
+                        ))
+                    } else if let Some(group_by) = group_by {
+                        self.build_aggregate(plan, group_by, columns)
+                    } else {
+                        // Aggregation without GROUP BY
+                        self.build_aggregate_no_group(plan, columns)
+                    }
                 } else {
-                    // Regular projection
-                    plan = self.build_projection(plan, columns)?;
-                }
+                    // Regular projection, with window functions if there are any
+                    self.build_projection(plan, columns)
+                };
+                self.windows = outer_windows;
+                plan = result?;
 
                 // Apply HAVING (part of GROUP BY)
                 if let Some(ref group_by) = group_by {
@@ -582,6 +684,18 @@ impl<'a> LogicalPlanBuilder<'a> {
         // Handle JOINs
         if !from.joins.is_empty() {
             for join in &from.joins {
+                // A table-valued function is called for each row of the tables before it
+                if let ast::SelectTable::TableCall(name, args, alias) = join.table.as_ref() {
+                    plan = self.build_table_function(
+                        plan,
+                        name,
+                        args,
+                        alias,
+                        &join.operator,
+                        &join.constraint,
+                    )?;
+                    continue;
+                }
                 let right = self.build_select_table(&join.table)?;
                 plan = self.build_join(plan, right, &join.operator, &join.constraint)?;
             }
@@ -613,14 +727,148 @@ impl<'a> LogicalPlanBuilder<'a> {
                     projection: None,
                 }))
             }
-            ast::SelectTable::Select(subquery, _alias) => self.build_select(subquery),
-            ast::SelectTable::TableCall(_, _, _) => Err(LimboError::ParseError(
-                "Table-valued functions are not supported in logical plans".to_string(),
-            )),
-            ast::SelectTable::Sub(_, _) => Err(LimboError::ParseError(
-                "Subquery in FROM clause not yet supported".to_string(),
-            )),
+            ast::SelectTable::Select(subquery, alias) => {
+                let plan = self.build_select(subquery)?;
+                Ok(Self::build_subquery_alias(plan, alias))
+            }
+            ast::SelectTable::TableCall(name, _, _) => Err(LimboError::ParseError(format!(
+                "Table-valued function {} must follow a table it takes its arguments from",
+                Self::name_to_string(&name.name)
+            ))),
+            ast::SelectTable::Sub(from, alias) => {
+                let plan = self.build_from(from)?;
+                Ok(Self::build_subquery_alias(plan, alias))
+            }
+        }
+    }
+
+    // Build a table-valued function called for each row of `input`
+    fn build_table_function(
+        &mut self,
+        input: LogicalPlan,
+        name: &ast::QualifiedName,
+        args: &[Box<ast::Expr>],
+        alias: &Option<ast::As>,
+        op: &ast::JoinOperator,
+        constraint: &Option<ast::JoinConstraint>,
+    ) -> Result<LogicalPlan> {
+        let function_name = Self::name_to_string(&name.name);
+        let vtab = match self.schema.get_table(&function_name).as_deref() {
+            Some(Table::Virtual(vtab)) if vtab.is_pragma() => {
+                return Err(LimboError::ParseError(format!(
+                    "PRAGMA function {function_name} is not supported in incremental views"
+                )))
+            }
+            Some(Table::Virtual(vtab)) if vtab.kind == VTabKind::TableValuedFunction => {
+                vtab.clone()
+            }
+            Some(_) => {
+                return Err(LimboError::ParseError(format!(
+                    "'{function_name}' is not a function"
+                )))
+            }
+            // The function may come from an extension that is not loaded yet
+            None => {
+                return Err(LimboError::ExtensionError(format!(
+                    "no such table-valued function: {function_name}"
+                )))
+            }
+        };
+
+        let hidden_count = vtab.columns.iter().filter(|col| col.hidden()).count();
+        if args.len() > hidden_count {
+            return Err(LimboError::ParseError(format!(
+                "Too many arguments for {function_name}: expected at most {hidden_count}, got {}",
+                args.len()
+            )));
+        }
+
+        let outer = match op {
+            ast::JoinOperator::Comma | ast::JoinOperator::TypedJoin(None) => false,
+            ast::JoinOperator::TypedJoin(Some(jt))
+                if jt.contains(ast::JoinType::NATURAL) || jt.contains(ast::JoinType::RIGHT) =>
+            {
+                return Err(LimboError::ParseError(format!(
+                    "Only inner and LEFT joins with table-valued function {function_name} are supported"
+                )));
+            }
+            ast::JoinOperator::TypedJoin(Some(jt)) => jt.contains(ast::JoinType::LEFT),
+        };
+
+        let args = args
+            .iter()
+            .map(|arg| self.build_expr(arg, input.schema()))
+            .collect::<Result<Vec<_>>>()?;
+
+        // The input columns, then the columns of the function that are not arguments
+        let table_alias = alias.as_ref().map(|a| Self::name_to_string(a.name()));
+        let mut columns = input.schema().columns.clone();
+        for col in vtab.columns.iter().filter(|col| !col.hidden()) {
+            if let Some(ref name) = col.name {
+                columns.push(ColumnInfo {
+                    name: name.clone(),
+                    ty: col.ty(),
+                    database: None,
+                    table: Some(function_name.clone()),
+                    table_alias: table_alias.clone(),
+                });
+            }
+        }
+        let schema = Arc::new(LogicalSchema::new(columns));
+
+        let plan = LogicalPlan::TableFunction(TableFunction {
+            input: Arc::new(input),
+            vtab,
+            args,
+            outer,
+            schema,
+        });
+
+        match constraint {
+            None => Ok(plan),
+            // An inner join condition only drops rows, so it can be checked afterwards
+            Some(ast::JoinConstraint::On(expr)) if !outer => {
+                let predicate = self.build_expr(expr, plan.schema())?;
+                Ok(LogicalPlan::Filter(Filter {
+                    input: Arc::new(plan),
+                    predicate,
+                }))
+            }
+            Some(_) => Err(LimboError::ParseError(format!(
+                "Only ON conditions of inner joins with table-valued function {function_name} are supported"
+            ))),
+        }
+    }
+
+    // Relabel the output of a FROM subquery so its columns resolve through the alias
+    fn build_subquery_alias(plan: LogicalPlan, alias: &Option<ast::As>) -> LogicalPlan {
+        let Some(alias) = alias else {
+            return plan;
+        };
+        let alias = Self::name_to_string(alias.name());
+        let input_schema = plan.schema().clone();
+
+        let mut exprs = Vec::with_capacity(input_schema.column_count());
+        let mut columns = Vec::with_capacity(input_schema.column_count());
+        for col in &input_schema.columns {
+            exprs.push(LogicalExpr::Column(Column {
+                name: col.name.clone(),
+                table: col.table_alias.clone().or_else(|| col.table.clone()),
+            }));
+            columns.push(ColumnInfo {
+                name: col.name.clone(),
+                ty: col.ty,
+                database: None,
+                table: Some(alias.clone()),
+                table_alias: Some(alias.clone()),
+            });
         }
+
+        LogicalPlan::Projection(Projection {
+            input: Arc::new(plan),
+            exprs,
+            schema: Arc::new(LogicalSchema::new(columns)),
+        })
     }
 
     // Build JOIN
@@ -974,6 +1222,19 @@ impl<'a> LogicalPlanBuilder<'a> {
             }
         }
 
+        // Window functions are computed by Window nodes below the projection, which then
+        // reads their results as columns
+        let mut window_exprs = Vec::new();
+        let proj_exprs = proj_exprs
+            .into_iter()
+            .map(|expr| Self::replace_window_functions(expr, &mut window_exprs))
+            .collect();
+        let input = if window_exprs.is_empty() {
+            input
+        } else {
+            Self::build_windows(input, window_exprs)?
+        };
+
         Ok(LogicalPlan::Projection(Projection {
             input: Arc::new(input),
             exprs: proj_exprs,
@@ -981,6 +1242,128 @@ impl<'a> LogicalPlanBuilder<'a> {
         }))
     }
 
+    // Recursively replace window functions with references to the columns that hold their
+    // results, collecting each distinct window function once
+    fn replace_window_functions(
+        expr: LogicalExpr,
+        window_exprs: &mut Vec<(LogicalExpr, String)>,
+    ) -> LogicalExpr {
+        fn boxed(
+            expr: Box<LogicalExpr>,
+            window_exprs: &mut Vec<(LogicalExpr, String)>,
+        ) -> Box<LogicalExpr> {
+            Box::new(LogicalPlanBuilder::replace_window_functions(
+                *expr,
+                window_exprs,
+            ))
+        }
+        match expr {
+            LogicalExpr::WindowFunction { .. } => {
+                let name = match window_exprs.iter().find(|(e, _)| *e == expr) {
+                    Some((_, name)) => name.clone(),
+                    None => {
+                        let name = format!("__window_{}", window_exprs.len());
+                        window_exprs.push((expr, name.clone()));
+                        name
+                    }
+                };
+                LogicalExpr::Column(Column::new(name))
+            }
+            LogicalExpr::Alias { expr, alias } => LogicalExpr::Alias {
+                expr: boxed(expr, window_exprs),
+                alias,
+            },
+            LogicalExpr::BinaryExpr { left, op, right } => LogicalExpr::BinaryExpr {
+                left: boxed(left, window_exprs),
+                op,
+                right: boxed(right, window_exprs),
+            },
+            LogicalExpr::UnaryExpr { op, expr } => LogicalExpr::UnaryExpr {
+                op,
+                expr: boxed(expr, window_exprs),
+            },
+            LogicalExpr::Cast { expr, type_name } => LogicalExpr::Cast {
+                expr: boxed(expr, window_exprs),
+                type_name,
+            },
+            LogicalExpr::IsNull { expr, negated } => LogicalExpr::IsNull {
+                expr: boxed(expr, window_exprs),
+                negated,
+            },
+            LogicalExpr::ScalarFunction { fun, args } => LogicalExpr::ScalarFunction {
+                fun,
+                args: args
+                    .into_iter()
+                    .map(|arg| Self::replace_window_functions(arg, window_exprs))
+                    .collect(),
+            },
+            LogicalExpr::Case {
+                expr,
+                when_then,
+                else_expr,
+            } => LogicalExpr::Case {
+                expr: expr.map(|e| boxed(e, window_exprs)),
+                when_then: when_then
+                    .into_iter()
+                    .map(|(w, t)| {
+                        (
+                            Self::replace_window_functions(w, window_exprs),
+                            Self::replace_window_functions(t, window_exprs),
+                        )
+                    })
+                    .collect(),
+                else_expr: else_expr.map(|e| boxed(e, window_exprs)),
+            },
+            other => other,
+        }
+    }
+
+    // Build one Window node per distinct PARTITION BY / ORDER BY, stacked on top of each other
+    fn build_windows(
+        input: LogicalPlan,
+        window_exprs: Vec<(LogicalExpr, String)>,
+    ) -> Result<LogicalPlan> {
+        let mut specs: Vec<(&[LogicalExpr], &[SortExpr], Vec<&(LogicalExpr, String)>)> = Vec::new();
+        for entry in &window_exprs {
+            let LogicalExpr::WindowFunction {
+                partition_by,
+                order_by,
+                ..
+            } = &entry.0
+            else {
+                unreachable!("only window functions are collected");
+            };
+            match specs
+                .iter_mut()
+                .find(|(p, o, _)| *p == partition_by.as_slice() && *o == order_by.as_slice())
+            {
+                Some((_, _, exprs)) => exprs.push(entry),
+                None => specs.push((partition_by.as_slice(), order_by.as_slice(), vec![entry])),
+            }
+        }
+
+        let mut plan = input;
+        for (_, _, exprs) in specs {
+            let input_schema = plan.schema().clone();
+            let mut columns = input_schema.columns.clone();
+            for (expr, name) in &exprs {
+                columns.push(ColumnInfo {
+                    name: name.clone(),
+                    ty: Self::infer_expr_type(expr, &input_schema)?,
+                    database: None,
+                    table: None,
+                    table_alias: None,
+                });
+            }
+            plan = LogicalPlan::Window(Window {
+                input: Arc::new(plan),
+                window_expr: exprs.into_iter().map(|(expr, _)| expr.clone()).collect(),
+                schema: Arc::new(LogicalSchema::new(columns)),
+            });
+        }
+        Ok(plan)
+    }
+
     // Helper function to preprocess aggregate expressions that contain complex arguments
     // Returns: (needs_pre_projection, pre_projection_exprs, pre_projection_schema, modified_aggr_exprs)
     //
@@ -1538,12 +1921,24 @@ impl<'a> LogicalPlanBuilder<'a> {
         let mut sort_exprs = Vec::new();
 
         for sorted_col in exprs {
-            let expr = self.build_expr(&sorted_col.expr, input_schema)?;
-            sort_exprs.push(SortExpr {
-                expr,
-                asc: sorted_col.order != Some(ast::SortOrder::Desc),
-                nulls_first: sorted_col.nulls == Some(ast::NullsOrder::First),
-            });
+            let mut sort_expr = self.build_sort_expr(sorted_col, input_schema)?;
+            if sort_expr.collation.is_none() {
+                // Without a COLLATE clause, a column sorts with its declared collation
+                let column_index = match strip_alias(&sort_expr.expr) {
+                    LogicalExpr::Column(col) => {
+                        Self::find_plan_column(input_schema, col).map(|(idx, _)| idx)
+                    }
+                    LogicalExpr::Literal(Value::Numeric(Numeric::Integer(pos))) => {
+                        usize::try_from(*pos)
+                            .ok()
+                            .and_then(|pos| pos.checked_sub(1))
+                    }
+                    _ => None,
+                };
+                sort_expr.collation =
+                    column_index.and_then(|idx| self.column_collation(&input, idx));
+            }
+            sort_exprs.push(sort_expr);
         }
 
         Ok(LogicalPlan::Sort(Sort {
@@ -1552,6 +1947,89 @@ impl<'a> LogicalPlanBuilder<'a> {
         }))
     }
 
+    // Build one ORDER BY term, taking its collation from a COLLATE clause
+    fn build_sort_expr(
+        &mut self,
+        sorted_col: &ast::SortedColumn,
+        schema: &SchemaRef,
+    ) -> Result<SortExpr> {
+        let (expr, collation) = match sorted_col.expr.as_ref() {
+            ast::Expr::Collate(expr, collation) => {
+                (expr.as_ref(), Some(CollationSeq::new(collation.as_str())?))
+            }
+            expr => (expr, None),
+        };
+        let asc = sorted_col.order != Some(ast::SortOrder::Desc);
+        Ok(SortExpr {
+            expr: self.build_expr(expr, schema)?,
+            asc,
+            // NULLs are the smallest values unless NULLS FIRST/LAST says otherwise
+            nulls_first: match sorted_col.nulls {
+                Some(ast::NullsOrder::First) => true,
+                Some(ast::NullsOrder::Last) => false,
+                None => asc,
+            },
+            collation,
+        })
+    }
+
+    fn find_plan_column<'s>(
+        schema: &'s LogicalSchema,
+        col: &Column,
+    ) -> Option<(usize, &'s ColumnInfo)> {
+        schema
+            .find_column(&col.name, col.table.as_deref())
+            .or_else(|| schema.find_column(&col.name, None))
+    }
+
+    // Declared collation of the table column that output column `index` of `plan` passes
+    // through unchanged, if any
+    fn column_collation(&self, plan: &LogicalPlan, index: usize) -> Option<CollationSeq> {
+        let passed_through = |input: &LogicalPlan, expr: Option<&LogicalExpr>| {
+            let LogicalExpr::Column(col) = strip_alias(expr?) else {
+                return None;
+            };
+            let (index, _) = Self::find_plan_column(input.schema(), col)?;
+            self.column_collation(input, index)
+        };
+        match plan {
+            LogicalPlan::TableScan(scan) => {
+                let column = scan.schema.columns.get(index)?;
+                let table = self.schema.get_table(&scan.table_name)?;
+                let (_, column) = table.get_column_by_name(&column.name)?;
+                column.collation_opt()
+            }
+            LogicalPlan::Projection(projection) => {
+                passed_through(&projection.input, projection.exprs.get(index))
+            }
+            LogicalPlan::Aggregate(aggregate) => {
+                // Group by columns come first in the output
+                passed_through(&aggregate.input, aggregate.group_expr.get(index))
+            }
+            LogicalPlan::Join(join) => {
+                let left_count = join.left.schema().column_count();
+                if index < left_count {
+                    self.column_collation(&join.left, index)
+                } else {
+                    self.column_collation(&join.right, index - left_count)
+                }
+            }
+            LogicalPlan::Window(window) if index < window.input.schema().column_count() => {
+                self.column_collation(&window.input, index)
+            }
+            LogicalPlan::TableFunction(table_function)
+                if index < table_function.input.schema().column_count() =>
+            {
+                self.column_collation(&table_function.input, index)
+            }
+            LogicalPlan::Filter(filter) => self.column_collation(&filter.input, index),
+            LogicalPlan::Sort(sort) => self.column_collation(&sort.input, index),
+            LogicalPlan::Limit(limit) => self.column_collation(&limit.input, index),
+            LogicalPlan::Distinct(distinct) => self.column_collation(&distinct.input, index),
+            _ => None,
+        }
+    }
+
     // Build LIMIT
     fn build_limit(input: LogicalPlan, limit: &ast::Limit) -> Result<LogicalPlan> {
         let fetch = match limit.expr.as_ref() {
@@ -1607,9 +2085,20 @@ impl<'a> LogicalPlanBuilder<'a> {
                     schema,
                 }))
             }
-            _ => Err(LimboError::ParseError(
-                "INTERSECT and EXCEPT not yet supported in logical plans".to_string(),
-            )),
+            ast::CompoundOperator::Intersect | ast::CompoundOperator::Except => {
+                let schema = left.schema().clone();
+                let op = if matches!(op, ast::CompoundOperator::Intersect) {
+                    SetOperator::Intersect
+                } else {
+                    SetOperator::Except
+                };
+                Ok(LogicalPlan::SetOperation(SetOperation {
+                    left: Arc::new(left),
+                    right: Arc::new(right),
+                    op,
+                    schema,
+                }))
+            }
         }
     }
 
@@ -1672,18 +2161,19 @@ impl<'a> LogicalPlanBuilder<'a> {
                 filter_over,
                 ..
             } => {
-                // Check for window functions (OVER clause)
+                let func_name = Self::name_to_string(name);
                 if filter_over.over_clause.is_some() {
-                    return Err(LimboError::ParseError(
-                        "Unsupported expression type: window functions are not yet supported"
-                            .to_string(),
-                    ));
+                    if distinctness.is_some() {
+                        return Err(LimboError::ParseError(
+                            "DISTINCT is not supported for window functions".to_string(),
+                        ));
+                    }
+                    return self.build_window_function(&func_name, args, filter_over, _schema);
                 }
 
-                let func_name = Self::name_to_string(name);
                 let arg_count = args.len();
                 // Check if it's an aggregate function (considering argument count for min/max)
-                if let Some(agg_fun) = Self::parse_aggregate_function(&func_name, arg_count) {
+                if let Some(agg_fun) = self.parse_aggregate_function(&func_name, arg_count) {
                     let distinct = distinctness.is_some();
                     let arg_exprs = args
                         .iter()
@@ -1695,6 +2185,20 @@ impl<'a> LogicalPlanBuilder<'a> {
                         distinct,
                     })
                 } else {
+                    // With a symbol table at hand, reject names that neither the core nor a
+                    // loaded extension provides, so callers can tell a missing extension apart
+                    // from an invalid plan
+                    if let Some(syms) = self.syms {
+                        if matches!(
+                            crate::function::Func::resolve_function(&func_name, arg_count),
+                            Ok(None)
+                        ) && syms.resolve_function(&func_name, arg_count).is_none()
+                        {
+                            return Err(LimboError::ExtensionError(format!(
+                                "no such function: {func_name}"
+                            )));
+                        }
+                    }
                     // Regular scalar function
                     let arg_exprs = args
                         .iter()
@@ -1707,11 +2211,14 @@ impl<'a> LogicalPlanBuilder<'a> {
                 }
             }
 
-            ast::Expr::FunctionCallStar { name, .. } => {
+            ast::Expr::FunctionCallStar { name, filter_over } => {
                 // Handle COUNT(*) and similar
                 let func_name = Self::name_to_string(name);
+                if filter_over.over_clause.is_some() {
+                    return self.build_window_function(&func_name, &[], filter_over, _schema);
+                }
                 // FunctionCallStar always has 0 args (it's the * form)
-                if let Some(agg_fun) = Self::parse_aggregate_function(&func_name, 0) {
+                if let Some(agg_fun) = self.parse_aggregate_function(&func_name, 0) {
                     Ok(LogicalExpr::AggregateFunction {
                         fun: agg_fun,
                         args: vec![],
@@ -1899,6 +2406,86 @@ impl<'a> LogicalPlanBuilder<'a> {
     }
 
     /// Build literal value
+    // Build a function call with an OVER clause
+    fn build_window_function(
+        &mut self,
+        func_name: &str,
+        args: &[Box<ast::Expr>],
+        filter_over: &ast::FunctionTail,
+        schema: &SchemaRef,
+    ) -> Result<LogicalExpr> {
+        if filter_over.filter_clause.is_some() {
+            return Err(LimboError::ParseError(
+                "FILTER is not supported for window functions".to_string(),
+            ));
+        }
+        let fun = if let Some(agg_fun) = self.parse_aggregate_function(func_name, args.len()) {
+            WindowFunctionKind::Agg(agg_fun)
+        } else if let Ok(Some(crate::function::Func::Window(window_fun))) =
+            crate::function::Func::resolve_function(func_name, args.len())
+        {
+            WindowFunctionKind::Window(window_fun)
+        } else {
+            return Err(LimboError::ParseError(format!(
+                "{func_name}() may not be used as a window function"
+            )));
+        };
+
+        // Resolve the window, which may name or extend a window of the WINDOW clause
+        let window = match &filter_over.over_clause {
+            Some(ast::Over::Window(window)) => window,
+            Some(ast::Over::Name(name)) => {
+                let name = Self::name_to_string(name);
+                self.windows
+                    .get(&name)
+                    .ok_or_else(|| LimboError::ParseError(format!("no such window: {name}")))?
+            }
+            None => unreachable!("window functions have an OVER clause"),
+        }
+        .clone();
+        let (partition_by, order_by, frame) = match &window.base {
+            Some(base) => {
+                let name = Self::name_to_string(base);
+                let base = self
+                    .windows
+                    .get(&name)
+                    .ok_or_else(|| LimboError::ParseError(format!("no such window: {name}")))?;
+                let order_by = if window.order_by.is_empty() {
+                    base.order_by.clone()
+                } else {
+                    window.order_by
+                };
+                (
+                    base.partition_by.clone(),
+                    order_by,
+                    window.frame_clause.or_else(|| base.frame_clause.clone()),
+                )
+            }
+            None => (window.partition_by, window.order_by, window.frame_clause),
+        };
+
+        let args = args
+            .iter()
+            .map(|e| self.build_expr(e, schema))
+            .collect::<Result<Vec<_>>>()?;
+        let partition_by = partition_by
+            .iter()
+            .map(|e| self.build_expr(e, schema))
+            .collect::<Result<Vec<_>>>()?;
+        let order_by = order_by
+            .iter()
+            .map(|sorted_col| self.build_sort_expr(sorted_col, schema))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(LogicalExpr::WindowFunction {
+            fun,
+            args,
+            partition_by,
+            order_by,
+            frame,
+        })
+    }
+
     fn build_literal(lit: &ast::Literal) -> Result<Value> {
         match lit {
             ast::Literal::Null => Ok(Value::Null),
@@ -1941,9 +2528,9 @@ impl<'a> LogicalPlanBuilder<'a> {
     }
 
     /// Parse aggregate function name (considering argument count for min/max)
-    fn parse_aggregate_function(name: &str, arg_count: usize) -> Option<AggregateFunction> {
+    fn parse_aggregate_function(&self, name: &str, arg_count: usize) -> Option<AggregateFunction> {
         let name_bytes = name.as_bytes();
-        match_ignore_ascii_case!(match name_bytes {
+        let builtin = match_ignore_ascii_case!(match name_bytes {
             b"COUNT" => Some(AggFunc::Count),
             b"SUM" => Some(AggFunc::Sum),
             b"AVG" => Some(AggFunc::Avg),
@@ -1956,14 +2543,37 @@ impl<'a> LogicalPlanBuilder<'a> {
             b"TOTAL" => Some(AggFunc::Total),
             b"ARRAY_AGG" => Some(AggFunc::ArrayAgg),
             _ => None,
-        })
+        });
+        if builtin.is_some() {
+            return builtin;
+        }
+        #[cfg(feature = "json")]
+        {
+            let json_agg = match_ignore_ascii_case!(match name_bytes {
+                b"JSON_GROUP_ARRAY" => Some(AggFunc::JsonGroupArray),
+                b"JSONB_GROUP_ARRAY" => Some(AggFunc::JsonbGroupArray),
+                b"JSON_GROUP_OBJECT" => Some(AggFunc::JsonGroupObject),
+                b"JSONB_GROUP_OBJECT" => Some(AggFunc::JsonbGroupObject),
+                _ => None,
+            });
+            if json_agg.is_some() {
+                return json_agg;
+            }
+        }
+
+        // Aggregates registered by loaded extensions (e.g. median, percentile)
+        let func = self.syms?.resolve_function(name, arg_count)?;
+        match func.func {
+            ExtFunc::Aggregate { .. } => Some(AggFunc::External(func.func.clone().into())),
+            ExtFunc::Scalar(_) => None,
+        }
     }
 
     // Check if expression contains aggregates
-    fn has_aggregates(columns: &[ast::ResultColumn]) -> bool {
+    fn has_aggregates(&self, columns: &[ast::ResultColumn]) -> bool {
         for col in columns {
             if let ast::ResultColumn::Expr(expr, _) = col {
-                if Self::expr_has_aggregate(expr) {
+                if self.expr_has_aggregate(expr) {
                     return true;
                 }
             }
@@ -1972,26 +2582,38 @@ impl<'a> LogicalPlanBuilder<'a> {
     }
 
     // Check if AST expression contains aggregates
-    fn expr_has_aggregate(expr: &ast::Expr) -> bool {
+    fn expr_has_aggregate(&self, expr: &ast::Expr) -> bool {
         match expr {
-            ast::Expr::FunctionCall { name, args, .. } => {
+            ast::Expr::FunctionCall {
+                name,
+                args,
+                filter_over,
+                ..
+            } => {
                 // Check if the function itself is an aggregate (considering arg count for min/max)
+                // An aggregate with an OVER clause is a window function
                 let arg_count = args.len();
-                if Self::parse_aggregate_function(&Self::name_to_string(name), arg_count).is_some()
+                if filter_over.over_clause.is_none()
+                    && self
+                        .parse_aggregate_function(&Self::name_to_string(name), arg_count)
+                        .is_some()
                 {
                     return true;
                 }
                 // Also check if any arguments contain aggregates (for nested functions like HEX(SUM(...)))
-                args.iter().any(|arg| Self::expr_has_aggregate(arg))
+                args.iter().any(|arg| self.expr_has_aggregate(arg))
             }
-            ast::Expr::FunctionCallStar { name, .. } => {
+            ast::Expr::FunctionCallStar { name, filter_over } => {
                 // FunctionCallStar always has 0 args
-                Self::parse_aggregate_function(&Self::name_to_string(name), 0).is_some()
+                filter_over.over_clause.is_none()
+                    && self
+                        .parse_aggregate_function(&Self::name_to_string(name), 0)
+                        .is_some()
             }
             ast::Expr::Binary(lhs, _, rhs) => {
-                Self::expr_has_aggregate(lhs) || Self::expr_has_aggregate(rhs)
+                self.expr_has_aggregate(lhs) || self.expr_has_aggregate(rhs)
             }
-            ast::Expr::Unary(_, e) => Self::expr_has_aggregate(e),
+            ast::Expr::Unary(_, e) => self.expr_has_aggregate(e),
             ast::Expr::Case {
                 when_then_pairs,
                 else_expr,
@@ -1999,14 +2621,59 @@ impl<'a> LogicalPlanBuilder<'a> {
             } => {
                 when_then_pairs
                     .iter()
-                    .any(|(w, t)| Self::expr_has_aggregate(w) || Self::expr_has_aggregate(t))
+                    .any(|(w, t)| self.expr_has_aggregate(w) || self.expr_has_aggregate(t))
                     || else_expr
                         .as_ref()
-                        .is_some_and(|e| Self::expr_has_aggregate(e))
+                        .is_some_and(|e| self.expr_has_aggregate(e))
             }
             ast::Expr::Parenthesized(exprs) => {
                 // Check if any parenthesized expression contains an aggregate
-                exprs.iter().any(|e| Self::expr_has_aggregate(e))
+                exprs.iter().any(|e| self.expr_has_aggregate(e))
+            }
+            _ => false,
+        }
+    }
+
+    // Check if any result column contains a window function
+    fn has_window_functions(columns: &[ast::ResultColumn]) -> bool {
+        columns.iter().any(|col| match col {
+            ast::ResultColumn::Expr(expr, _) => Self::expr_has_window_function(expr),
+            _ => false,
+        })
+    }
+
+    // Check if AST expression contains a function call with an OVER clause
+    fn expr_has_window_function(expr: &ast::Expr) -> bool {
+        match expr {
+            ast::Expr::FunctionCall {
+                args, filter_over, ..
+            } => {
+                filter_over.over_clause.is_some()
+                    || args.iter().any(|arg| Self::expr_has_window_function(arg))
+            }
+            ast::Expr::FunctionCallStar { filter_over, .. } => filter_over.over_clause.is_some(),
+            ast::Expr::Binary(lhs, _, rhs) => {
+                Self::expr_has_window_function(lhs) || Self::expr_has_window_function(rhs)
+            }
+            ast::Expr::Unary(_, e) | ast::Expr::Cast { expr: e, .. } => {
+                Self::expr_has_window_function(e)
+            }
+            ast::Expr::Case {
+                base,
+                when_then_pairs,
+                else_expr,
+            } => {
+                base.as_ref()
+                    .is_some_and(|e| Self::expr_has_window_function(e))
+                    || when_then_pairs.iter().any(|(w, t)| {
+                        Self::expr_has_window_function(w) || Self::expr_has_window_function(t)
+                    })
+                    || else_expr
+                        .as_ref()
+                        .is_some_and(|e| Self::expr_has_window_function(e))
+            }
+            ast::Expr::Parenthesized(exprs) => {
+                exprs.iter().any(|e| Self::expr_has_window_function(e))
             }
             _ => false,
         }
@@ -2386,6 +3053,30 @@ impl<'a> LogicalPlanBuilder<'a> {
                 | AggFunc::JsonGroupObject => Ok(Type::Text),
                 AggFunc::External(_) => Ok(Type::Text), // Default for external
             },
+            LogicalExpr::WindowFunction { fun, args, .. } => match fun {
+                WindowFunctionKind::Agg(fun) => Self::infer_expr_type(
+                    &LogicalExpr::AggregateFunction {
+                        fun: fun.clone(),
+                        args: vec![],
+                        distinct: false,
+                    },
+                    schema,
+                ),
+                WindowFunctionKind::Window(
+                    WindowFunc::RowNumber
+                    | WindowFunc::Rank
+                    | WindowFunc::DenseRank
+                    | WindowFunc::Ntile,
+                ) => Ok(Type::Integer),
+                WindowFunctionKind::Window(WindowFunc::PercentRank | WindowFunc::CumeDist) => {
+                    Ok(Type::Real)
+                }
+                // LAG, LEAD, FIRST_VALUE, LAST_VALUE and NTH_VALUE return their argument
+                WindowFunctionKind::Window(_) => match args.first() {
+                    Some(arg) => Self::infer_expr_type(arg, schema),
+                    None => Ok(Type::Null),
+                },
+            },
             LogicalExpr::Alias { expr, .. } => Self::infer_expr_type(expr, schema),
             LogicalExpr::IsNull { .. } => Ok(Type::Integer),
             LogicalExpr::InList { .. } | LogicalExpr::InSubquery { .. } => Ok(Type::Integer),
@@ -2757,6 +3448,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_window_functions_share_window_node() {
+        let schema = create_test_schema();
+        let sql =
+            "SELECT name, row_number() OVER w, rank() OVER w, sum(age) OVER (PARTITION BY age) \
+                   FROM users WINDOW w AS (PARTITION BY age ORDER BY id)";
+        let plan = parse_and_build(sql, &schema).unwrap();
+
+        // One window node per distinct PARTITION BY / ORDER BY, stacked under the projection
+        let LogicalPlan::Projection(proj) = plan else {
+            panic!("Expected Projection");
+        };
+        assert_eq!(proj.exprs.len(), 4);
+        let LogicalPlan::Window(outer) = &*proj.input else {
+            panic!("Expected Window under Projection");
+        };
+        let LogicalPlan::Window(inner) = &*outer.input else {
+            panic!("Expected Window under Window");
+        };
+        assert!(matches!(&*inner.input, LogicalPlan::TableScan(_)));
+        assert_eq!(inner.window_expr.len() + outer.window_expr.len(), 3);
+        assert_eq!(
+            outer.schema.columns.len(),
+            inner.input.schema().columns.len() + 3
+        );
+    }
+
+    #[test]
+    fn test_window_function_over_aggregate_rejected() {
+        let schema = create_test_schema();
+        let sql = "SELECT age, rank() OVER (ORDER BY count(*)) FROM users GROUP BY age";
+        assert!(parse_and_build(sql, &schema).is_err());
+    }
+
+    #[test]
+    fn test_window_function_unknown_window() {
+        let schema = create_test_schema();
+        let sql = "SELECT row_number() OVER w FROM users";
+        let err = parse_and_build(sql, &schema).unwrap_err();
+        assert!(err.to_string().contains("no such window: w"));
+    }
+
     #[test]
     fn test_union() {
         let schema = create_test_schema();
@@ -2810,6 +3543,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_intersect() {
+        let schema = create_test_schema();
+        let sql = "SELECT id FROM users INTERSECT SELECT user_id FROM orders";
+        let plan = parse_and_build(sql, &schema).unwrap();
+
+        match plan {
+            LogicalPlan::SetOperation(set_op) => {
+                assert_eq!(set_op.op, SetOperator::Intersect);
+                assert_eq!(set_op.schema.column_count(), 1);
+                assert_eq!(set_op.schema.columns[0].name, "id");
+            }
+            _ => panic!("Expected SetOperation"),
+        }
+    }
+
+    #[test]
+    fn test_except_mixed_with_union() {
+        let schema = create_test_schema();
+        let sql =
+            "SELECT id FROM users UNION SELECT id FROM products EXCEPT SELECT user_id FROM orders";
+        let plan = parse_and_build(sql, &schema).unwrap();
+
+        // Compound operators are left-associative
+        match plan {
+            LogicalPlan::SetOperation(set_op) => {
+                assert_eq!(set_op.op, SetOperator::Except);
+                assert!(matches!(&*set_op.left, LogicalPlan::Union(_)));
+            }
+            _ => panic!("Expected SetOperation"),
+        }
+    }
+
+    #[test]
+    fn test_except_column_count_mismatch() {
+        let schema = create_test_schema();
+        let sql = "SELECT id, name FROM users EXCEPT SELECT user_id FROM orders";
+        let result = parse_and_build(sql, &schema);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_with_cte() {
         let schema = create_test_schema();
@@ -4033,6 +4807,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_subquery_alias_in_from() {
+        let schema = create_test_schema();
+        let sql = "SELECT s.user_id, s.total FROM (
+                     SELECT user_id, SUM(amount) AS total FROM orders GROUP BY user_id
+                   ) AS s WHERE s.total > 100";
+        let plan = parse_and_build(sql, &schema).unwrap();
+
+        match plan {
+            LogicalPlan::Projection(outer_proj) => match &*outer_proj.input {
+                LogicalPlan::Filter(filter) => match &*filter.input {
+                    LogicalPlan::Projection(alias_proj) => {
+                        // The subquery output is relabeled with its alias
+                        assert!(alias_proj
+                            .schema
+                            .columns
+                            .iter()
+                            .all(|c| c.table_alias.as_deref() == Some("s")));
+                        assert!(alias_proj.schema.find_column("total", Some("s")).is_some());
+                    }
+                    _ => panic!("Expected aliasing Projection for subquery"),
+                },
+                _ => panic!("Expected Filter"),
+            },
+            _ => panic!("Expected Projection at top level"),
+        }
+    }
+
+    #[test]
+    fn test_join_with_subquery() {
+        let schema = create_test_schema();
+        let sql = "SELECT u.name, t.total FROM users u JOIN (
+                     SELECT user_id, SUM(amount) AS total FROM orders GROUP BY user_id
+                   ) t ON u.id = t.user_id";
+        let plan = parse_and_build(sql, &schema).unwrap();
+
+        match plan {
+            LogicalPlan::Projection(proj) => match &*proj.input {
+                LogicalPlan::Join(join) => {
+                    assert_eq!(join.on.len(), 1);
+                    assert!(matches!(&*join.right, LogicalPlan::Projection(_)));
+                }
+                _ => panic!("Expected Join"),
+            },
+            _ => panic!("Expected Projection at top level"),
+        }
+    }
+
+    #[test]
+    fn test_parenthesized_join_group() {
+        let schema = create_test_schema();
+        let sql = "SELECT u.name, p.name FROM users u JOIN (orders o JOIN products p ON o.id = p.product_id) ON u.id = o.user_id";
+        let plan = parse_and_build(sql, &schema).unwrap();
+
+        match plan {
+            LogicalPlan::Projection(proj) => match &*proj.input {
+                LogicalPlan::Join(join) => {
+                    assert!(matches!(&*join.right, LogicalPlan::Join(_)));
+                }
+                _ => panic!("Expected Join"),
+            },
+            _ => panic!("Expected Projection at top level"),
+        }
+    }
+
     #[test]
     fn test_join_ambiguous_column() {
         let schema = create_test_schema();
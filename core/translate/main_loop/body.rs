@@ -1,4 +1,4 @@
-use crate::translate::plan::SimpleAggregate;
+use crate::translate::plan::{SimpleAggregate, SubqueryState};
 use crate::translate::{
     aggregation::emit_collseq_if_needed,
     order_by::{custom_type_comparator, EmitOrderBy},
@@ -302,18 +302,30 @@ fn emit_loop_source<'a>(
                     None
                 };
 
-                translate_aggregation_step(
-                    program,
-                    &plan.table_references,
-                    AggArgumentSource::new_from_expression(&agg.func, &agg.args, &agg.distinctness),
-                    reg,
-                    &t_ctx.resolver,
-                )?;
-                if let Distinctness::Distinct { ctx } = &agg.distinctness {
-                    let ctx = ctx
-                        .as_ref()
-                        .expect("distinct aggregate context not populated");
-                    program.preassign_label_to_next_insn(ctx.label_on_conflict);
+                let agg_arg_source =
+                    AggArgumentSource::new_from_expression(&agg.func, &agg.args, &agg.distinctness);
+                if agg.is_ordered() {
+                    emit_ordered_aggregation_insert(
+                        program,
+                        &plan.table_references,
+                        agg_arg_source,
+                        agg,
+                        &t_ctx.resolver,
+                    )?;
+                } else {
+                    translate_aggregation_step(
+                        program,
+                        &plan.table_references,
+                        agg_arg_source,
+                        reg,
+                        &t_ctx.resolver,
+                    )?;
+                    if let Distinctness::Distinct { ctx } = &agg.distinctness {
+                        let ctx = ctx
+                            .as_ref()
+                            .expect("distinct aggregate context not populated");
+                        program.preassign_label_to_next_insn(ctx.label_on_conflict);
+                    }
                 }
 
                 if let Some(label) = filter_skip_label {
@@ -447,9 +459,11 @@ fn emit_loop_source<'a>(
 
 /// Emit WHERE conditions and inner-loop entry for an unmatched outer hash join row.
 ///
-/// Filters applicable WHERE terms (non-ON, non-consumed), optionally restricted to
-/// `build_table_idx` / `probe_table_idx` when a Gosub wraps inner tables. Then either
-/// enters the inner-loop subroutine via Gosub or calls `emit_loop` directly.
+/// Re-runs correlated subqueries evaluated in the probe loop, since the joined
+/// cursors have been repositioned. Then filters applicable WHERE terms (non-ON,
+/// non-consumed), optionally restricted to `build_table_idx` / `probe_table_idx`
+/// when a Gosub wraps inner tables. Then either enters the inner-loop subroutine
+/// via Gosub or calls `emit_loop` directly.
 pub(super) fn emit_unmatched_row_conditions_and_loop<'a>(
     program: &mut ProgramBuilder,
     t_ctx: &mut TranslateCtx<'a>,
@@ -480,6 +494,25 @@ pub(super) fn emit_unmatched_row_conditions_and_loop<'a>(
         }
         m
     };
+    let probe_loop_idx = plan
+        .join_order
+        .iter()
+        .position(|j| j.original_idx == probe_table_idx);
+    for subquery in plan.non_from_clause_subqueries.iter() {
+        if let SubqueryState::Evaluated {
+            evaluated_at: EvalAt::Loop(loop_idx),
+            rerun: Some(rerun),
+            ..
+        } = &subquery.state
+        {
+            if Some(*loop_idx) == probe_loop_idx {
+                program.emit_insn(Insn::Gosub {
+                    target_pc: rerun.start,
+                    return_reg: rerun.return_reg,
+                });
+            }
+        }
+    }
     for cond in plan
         .where_clause
         .iter()
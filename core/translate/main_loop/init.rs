@@ -86,6 +86,38 @@ impl InitLoop {
                 format!("USE HASH TABLE FOR {}(DISTINCT)", agg.func)
             );
         }
+        // Initialize sorters buffering the inputs of aggregates with an ORDER BY clause,
+        // e.g. group_concat(x ORDER BY y). Each record holds the ORDER BY keys followed
+        // by the aggregate arguments.
+        for agg in aggregates.iter_mut().filter(|agg| agg.is_ordered()) {
+            let order_collations_nulls = agg
+                .order_by
+                .iter()
+                .map(|(expr, order, nulls)| {
+                    let collation = get_collseq_from_expr(expr, tables)?;
+                    Ok((*order, collation, *nulls))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let comparators = agg
+                .order_by
+                .iter()
+                .map(|(expr, _, _)| {
+                    crate::translate::order_by::custom_type_comparator(
+                        expr,
+                        tables,
+                        t_ctx.resolver.schema(),
+                    )
+                })
+                .collect();
+            let cursor_id = program.alloc_cursor_id(CursorType::Sorter);
+            program.emit_insn(Insn::SorterOpen {
+                cursor_id,
+                columns: agg.order_by.len() + agg.args.len(),
+                order_collations_nulls,
+                comparators,
+            });
+            agg.sorter_cursor_id = Some(cursor_id);
+        }
         // Include hash-join build tables so their cursors are opened for hash build.
         let mut required_tables: TableMask = join_order
             .iter()
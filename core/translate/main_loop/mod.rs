@@ -1,7 +1,7 @@
 use turso_parser::ast::{Expr, SortOrder, TableInternalId};
 
 use super::{
-    aggregation::{translate_aggregation_step, AggArgumentSource},
+    aggregation::{emit_ordered_aggregation_insert, translate_aggregation_step, AggArgumentSource},
     emitter::{
         InSeekMetadata, MaterializedBuildInputMode, MaterializedColumnRef, OperationMode, Resolver,
         TranslateCtx, UpdateRowSource,
@@ -1,6 +1,7 @@
 use super::*;
 use crate::translate::main_loop::{conditions::LoopConditionEmitter, hash::HashProbeSetupEmitter};
 use crate::translate::{
+    emitter::RowLocator,
     main_loop::close::AutoIndexBuild,
     plan::{self, SubqueryEvalPhase},
     subquery::{materialized_from_clause_subquery_storage, MaterializedFromClauseSubqueryStorage},
@@ -41,6 +42,32 @@ fn emit_materialized_subquery_result_columns(
     }
 }
 
+/// Position the table cursor on the row that the index cursor points to. Rowid tables defer
+/// the seek until a table column is actually read; WITHOUT ROWID tables have no rowid to defer
+/// on, so the PRIMARY KEY is read out of the index entry and sought right away.
+fn emit_index_to_table_seek(
+    program: &mut ProgramBuilder,
+    table: &Table,
+    index: Option<&Index>,
+    index_cursor_id: CursorID,
+    table_cursor_id: CursorID,
+    not_found: BranchOffset,
+) {
+    match (table, index) {
+        (Table::BTree(btree), Some(index)) if !btree.has_rowid && !index.ephemeral => {
+            RowLocator::from_index_entry(program, btree, index, index_cursor_id).emit_seek(
+                program,
+                table_cursor_id,
+                not_found,
+            );
+        }
+        _ => program.emit_insn(Insn::DeferredSeek {
+            index_cursor_id,
+            table_cursor_id,
+        }),
+    }
+}
+
 /// Opens the main loop for each table in the join order, emitting instructions to initialize
 /// cursors and perform index seeks as necessary.
 pub struct OpenLoop;
@@ -243,10 +270,14 @@ impl OpenLoop {
                     }
                     if let Some(table_cursor_id) = table_cursor_id {
                         if let Some(index_cursor_id) = index_cursor_id {
-                            program.emit_insn(Insn::DeferredSeek {
+                            emit_index_to_table_seek(
+                                program,
+                                &table.table,
+                                table.op.index().map(Arc::as_ref),
                                 index_cursor_id,
                                 table_cursor_id,
-                            });
+                                next,
+                            );
                         }
                     }
                 }
@@ -411,10 +442,14 @@ impl OpenLoop {
                                 if let Some(index_cursor_id) = index_cursor_id {
                                     if let Some(table_cursor_id) = table_cursor_id {
                                         // Don't do a btree table seek until it's actually necessary to read from the table.
-                                        program.emit_insn(Insn::DeferredSeek {
+                                        emit_index_to_table_seek(
+                                            program,
+                                            &table.table,
+                                            index.as_deref(),
                                             index_cursor_id,
                                             table_cursor_id,
-                                        });
+                                            next,
+                                        );
                                     }
                                 }
                             }
@@ -483,10 +518,14 @@ impl OpenLoop {
                                     target_pc: next_val_label,
                                 });
                                 if let Some(table_cursor_id) = table_cursor_id {
-                                    program.emit_insn(Insn::DeferredSeek {
-                                        index_cursor_id: idx_cursor,
+                                    emit_index_to_table_seek(
+                                        program,
+                                        &table.table,
+                                        index.as_deref(),
+                                        idx_cursor,
                                         table_cursor_id,
-                                    });
+                                        next,
+                                    );
                                 }
                             }
 
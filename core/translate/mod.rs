@@ -12,7 +12,7 @@ pub(crate) mod alter;
 pub(crate) mod analyze;
 pub(crate) mod attach;
 pub(crate) mod collate;
-mod compound_select;
+pub(crate) mod compound_select;
 pub(crate) mod delete;
 pub(crate) mod display;
 pub(crate) mod emitter;
@@ -30,6 +30,7 @@ pub(crate) mod order_by;
 pub(crate) mod plan;
 pub(crate) mod planner;
 pub(crate) mod pragma;
+pub(crate) mod recursive_cte;
 pub(crate) mod result_row;
 pub(crate) mod rollback;
 pub(crate) mod schema;
@@ -44,6 +45,7 @@ pub(crate) mod upsert;
 pub(crate) mod vacuum;
 mod values;
 pub(crate) mod view;
+mod view_dml;
 mod window;
 
 use crate::schema::Schema;
@@ -51,12 +53,13 @@ use crate::storage::pager::Pager;
 use crate::sync::Arc;
 use crate::translate::delete::translate_delete;
 use crate::translate::emitter::Resolver;
+use crate::util::normalize_ident;
 use crate::vdbe::builder::{ProgramBuilder, ProgramBuilderOpts, QueryMode};
 use crate::vdbe::Program;
 use crate::{bail_parse_error, Connection, Result, SymbolTable};
 use alter::translate_alter_table;
 use analyze::translate_analyze;
-use index::{translate_create_index, translate_drop_index, translate_optimize};
+use index::{translate_create_index, translate_drop_index, translate_optimize, translate_reindex};
 use insert::translate_insert;
 use rollback::{translate_release, translate_rollback, translate_savepoint};
 use schema::{translate_create_table, translate_create_virtual_table, translate_drop_table};
@@ -104,6 +107,7 @@ pub fn translate(
         syms,
         connection.experimental_custom_types_enabled(),
         connection.get_dqs_dml().into(),
+        connection.defer_foreign_keys_enabled(),
     );
 
     match stmt {
@@ -172,6 +176,8 @@ pub fn translate_inner(
 
     let is_select = matches!(stmt, ast::Stmt::Select { .. });
 
+    let has_dml_ctes = translate_dml_ctes(&stmt, resolver, program, connection, input)?;
+
     match stmt {
         ast::Stmt::AlterTable(alter) => {
             translate_alter_table(alter, resolver, program, connection, input)?;
@@ -264,9 +270,6 @@ pub fn translate_inner(
             order_by,
             with,
         } => {
-            if !order_by.is_empty() {
-                bail_parse_error!("ORDER BY clause is not supported in DELETE");
-            }
             if where_clause.is_none() && connection.get_dml_require_where() {
                 bail_parse_error!(
                     "DELETE without a WHERE clause is not allowed when require_where (or i_am_a_dummy) is enabled"
@@ -276,6 +279,7 @@ pub fn translate_inner(
                 &tbl_name,
                 resolver,
                 where_clause,
+                order_by,
                 limit,
                 returning,
                 indexed,
@@ -356,7 +360,7 @@ pub fn translate_inner(
         ast::Stmt::Pragma { .. } => {
             bail_parse_error!("PRAGMA statement cannot be evaluated in a nested context")
         }
-        ast::Stmt::Reindex { .. } => bail_parse_error!("REINDEX not supported yet"),
+        ast::Stmt::Reindex { name } => translate_reindex(name, resolver, program, connection)?,
         ast::Stmt::Optimize { idx_name } => {
             translate_optimize(idx_name, resolver, program, connection)?
         }
@@ -416,9 +420,91 @@ pub fn translate_inner(
         program.begin_read_operation();
     }
 
+    // The data-modifying CTEs and the statement itself must roll back together.
+    if has_dml_ctes {
+        program.set_multi_write(true);
+    }
+
     Ok(())
 }
 
+/// Emits the data-modifying CTEs of a statement's top-level WITH clause ahead of
+/// the statement itself, e.g. `WITH moved AS (DELETE FROM inbox RETURNING *)
+/// INSERT INTO archive SELECT * FROM moved`. Each one runs to completion in order,
+/// so later CTEs and the statement see its changes, and its RETURNING rows stay in
+/// their buffer, which references to the CTE then read as a materialized CTE.
+/// Returns whether any data-modifying CTE was emitted.
+fn translate_dml_ctes(
+    stmt: &ast::Stmt,
+    resolver: &mut Resolver,
+    program: &mut ProgramBuilder,
+    connection: &Arc<Connection>,
+    input: &str,
+) -> Result<bool> {
+    let with = match stmt {
+        ast::Stmt::Select(select) => &select.with,
+        ast::Stmt::Insert { with, .. } | ast::Stmt::Delete { with, .. } => with,
+        ast::Stmt::Update(update) => &update.with,
+        _ => return Ok(false),
+    };
+    let Some(with) = with else {
+        return Ok(false);
+    };
+
+    let mut emitted = false;
+
+    for (idx, cte) in with.ctes.iter().enumerate() {
+        let Some(dml) = &cte.dml else {
+            continue;
+        };
+        let cte_name = normalize_ident(cte.tbl_name.as_str());
+        // Already emitted: this is the WITH clause handed down to a later
+        // data-modifying CTE so that it can read the ones before it.
+        if program.dml_cte_id(&cte_name).is_some() {
+            continue;
+        }
+
+        let mut dml = dml.as_ref().clone();
+        let preceding = (idx > 0).then(|| ast::With {
+            recursive: with.recursive,
+            ctes: with.ctes[..idx].to_vec(),
+        });
+        match &mut dml {
+            ast::Stmt::Delete { with, .. } | ast::Stmt::Insert { with, .. } => *with = preceding,
+            ast::Stmt::Update(update) => update.with = preceding,
+            _ => unreachable!("not a data-modifying statement"),
+        }
+
+        program.begin_returning_capture();
+        let result = translate_inner(dml, resolver, program, connection, input);
+        let returning = program.end_returning_capture();
+        result?;
+        let Some(returning) = returning else {
+            bail_parse_error!(
+                "data-modifying statement in WITH is not supported for this table: {}",
+                cte.tbl_name.as_str()
+            );
+        };
+        // The buffer is read back through OpenDup, which opens cursors of the
+        // buffer's table type; a WITHOUT ROWID type would not match the buffer.
+        if !returning.table.has_rowid {
+            bail_parse_error!(
+                "data-modifying statement in WITH is not supported for WITHOUT ROWID table: {}",
+                returning.table.name
+            );
+        }
+
+        let cte_id = program.alloc_cte_id();
+        program.register_materialized_cte(cte_id, returning);
+        program.register_dml_cte(cte_name, cte_id);
+        // The RETURNING rows are not results of the statement.
+        program.result_columns.clear();
+        emitted = true;
+    }
+
+    Ok(emitted)
+}
+
 fn stmt_kind(stmt: &ast::Stmt) -> &'static str {
     match stmt {
         ast::Stmt::AlterTable(_) => "alter_table",
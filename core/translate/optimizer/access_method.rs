@@ -15,8 +15,8 @@ use crate::translate::optimizer::constraints::{
 use crate::translate::optimizer::cost::{rows_per_leaf_page_for_index, RowCountEstimate};
 use crate::translate::optimizer::cost_params::CostModelParams;
 use crate::translate::plan::{
-    plan_has_outer_scope_dependency, HashJoinKey, HashJoinType, NonFromClauseSubquery,
-    SetOperation, SubqueryState, TableReferences, WhereTerm,
+    HashJoinKey, HashJoinType, NonFromClauseSubquery, Plan, SetOperation, SubqueryState,
+    TableReferences, WhereTerm,
 };
 use crate::vdbe::affinity::Affinity;
 use crate::vdbe::hash_table::DEFAULT_MEM_BUDGET;
@@ -255,7 +255,7 @@ pub(super) fn choose_best_btree_candidate(
             Some(index) => IndexInfo {
                 unique: index.unique,
                 covering: rhs_table.index_is_covering(index),
-                column_count: index.columns.len(),
+                column_count: index.key_columns().len(),
                 rows_per_leaf_page: rows_per_leaf_page_for_index(
                     index.columns.len(),
                     rhs_table,
@@ -527,7 +527,7 @@ pub(super) fn choose_best_in_seek_candidate(
             Some(index) => IndexInfo {
                 unique: index.unique,
                 covering: rowid_only || rhs_table.index_is_covering(index),
-                column_count: index.columns.len(),
+                column_count: index.key_columns().len(),
                 rows_per_leaf_page: rows_per_leaf_page_for_index(
                     index.columns.len(),
                     rhs_table,
@@ -750,7 +750,7 @@ fn find_best_access_method_for_btree(
             Some(index) => IndexInfo {
                 unique: index.unique,
                 covering: rhs_table.index_is_covering(index),
-                column_count: index.columns.len(),
+                column_count: index.key_columns().len(),
                 rows_per_leaf_page: rows_per_leaf_page_for_index(
                     index.columns.len(),
                     rhs_table,
@@ -1107,6 +1107,9 @@ pub fn try_hash_join_access_method(
     }
 
     // Avoid hash joins when there are correlated subqueries that reference the joined tables.
+    // FULL OUTER has no nested-loop fallback, so it re-runs correlated SELECTs on the
+    // unmatched-row paths instead; only compound subqueries, which cannot read the hash
+    // payload registers, still rule it out.
     for subquery in subqueries {
         if !subquery.correlated {
             continue;
@@ -1114,6 +1117,11 @@ pub fn try_hash_join_access_method(
         // Check if the subquery references the build or probe table
         if let SubqueryState::Unevaluated { plan } = &subquery.state {
             if let Some(plan) = plan.as_ref() {
+                if hash_join_type == HashJoinType::FullOuter
+                    && !matches!(plan.as_ref(), Plan::CompoundSelect { .. })
+                {
+                    continue;
+                }
                 let outer_ref_ids = plan.used_outer_query_ref_ids();
                 for outer_ref_id in &outer_ref_ids {
                     if *outer_ref_id == build_table.internal_id
@@ -1347,7 +1355,7 @@ fn find_best_access_method_for_subquery(
     // Plans with outer-scope dependencies cannot be materialized once -
     // they must re-execute for each outer row. Use coroutine for these.
     // This check must come first because correlated CTEs should NOT share materialized data.
-    if plan_has_outer_scope_dependency(&subquery.plan) {
+    if subquery.has_outer_scope_dependency() {
         return Ok(Some(AccessMethod {
             // Correlated subqueries always rerun for each outer row, even if the
             // enclosing CTE/subquery might otherwise be shareable.
@@ -1629,6 +1637,7 @@ fn materialized_subquery_ephemeral_index(
         where_clause: None,
         has_rowid: true,
         index_method: None,
+        pk_suffix_start: None,
         on_conflict: None,
     })
 }
@@ -1323,7 +1323,7 @@ pub(crate) fn compute_best_join_order_with_context<'a>(
                 let msg = if build_is_outer {
                     "FULL OUTER JOIN chaining is not yet supported"
                 } else if has_correlated_subquery {
-                    "FULL OUTER JOIN is not supported with correlated subqueries that reference the joined tables"
+                    "FULL OUTER JOIN is not supported with correlated compound subqueries that reference the joined tables"
                 } else {
                     "FULL OUTER JOIN requires an equality condition in the ON clause"
                 };
@@ -2013,6 +2013,7 @@ mod tests {
             root_page: 1,
             has_rowid: true,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: None,
         });
         available_indexes.insert_for_table_name(
@@ -2110,6 +2111,7 @@ mod tests {
             root_page: 1,
             has_rowid: true,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: None,
         });
         available_indexes.insert_for_table_name(&joined_tables, "table1", VecDeque::from([index1]));
@@ -2252,6 +2254,7 @@ mod tests {
                     root_page: 1,
                     has_rowid: true,
                     index_method: None,
+                    pk_suffix_start: None,
                     on_conflict: None,
                 });
                 available_indexes.insert_for_table_name(
@@ -2277,6 +2280,7 @@ mod tests {
             root_page: 1,
             has_rowid: true,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: None,
         });
         let order_id_idx = Arc::new(Index {
@@ -2296,6 +2300,7 @@ mod tests {
             root_page: 1,
             has_rowid: true,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: None,
         });
 
@@ -2796,6 +2801,7 @@ mod tests {
             ephemeral: false,
             has_rowid: true,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: None,
         });
 
@@ -2916,6 +2922,7 @@ mod tests {
             ephemeral: false,
             has_rowid: true,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: None,
         });
         let table = Table::BTree(table);
@@ -3054,6 +3061,7 @@ mod tests {
             has_rowid: true,
             unique: false,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: None,
         });
         let table = Table::BTree(table);
@@ -3331,6 +3339,7 @@ mod tests {
             root_page: 2,
             has_rowid: true,
             index_method: None,
+            pk_suffix_start: None,
             on_conflict: None,
         });
         available_indexes.insert_for_table_name(&joined_tables, "t2", VecDeque::from([index_t2_a]));
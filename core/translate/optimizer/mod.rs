@@ -21,6 +21,7 @@ use crate::{
         ROWID_SENTINEL,
     },
     translate::{
+        delete::without_rowid_pk_write_set,
         insert::ROWID_COLUMN,
         optimizer::{
             access_method::AccessMethodParams,
@@ -665,6 +666,7 @@ fn detect_simple_aggregate(plan: &SelectPlan) -> Option<SimpleAggregate> {
         || plan.group_by.is_some()
         || plan.contains_constant_false_condition
         || plan.aggregates.first().unwrap().filter_expr.is_some()
+        || !plan.aggregates.first().unwrap().order_by.is_empty()
     {
         return None;
     }
@@ -1053,6 +1055,17 @@ fn update_write_set_reason(
             break 'requires Some(DmlSafetyReason::KeyMutation);
         }
 
+        // WITHOUT ROWID rows are keyed by their PRIMARY KEY, so changing it moves the row
+        // within the table btree that is being scanned.
+        let primary_key_updated = !btree_table.has_rowid
+            && plan
+                .set_clauses
+                .iter()
+                .any(|set_clause| btree_table.columns()[set_clause.column_index].primary_key());
+        if primary_key_updated {
+            break 'requires Some(DmlSafetyReason::KeyMutation);
+        }
+
         let Some(index) = table_ref.op.index() else {
             break 'requires None;
         };
@@ -1138,6 +1151,26 @@ fn update_from_scratch_columns(set_clause_count: usize) -> Vec<Column> {
         .collect()
 }
 
+fn update_pk_scratch_columns(btree: &BTreeTable) -> Vec<Column> {
+    btree
+        .primary_key_columns
+        .iter()
+        .map(|(name, _)| {
+            // Materialized key values are only read back to seek the target row, so they keep
+            // BLOB affinity like the SET payloads.
+            Column::new(
+                Some(format!("__update_pk_{name}")),
+                "BLOB".to_string(),
+                None,
+                None,
+                Type::Blob,
+                None,
+                ColDef::default(),
+            )
+        })
+        .collect()
+}
+
 /// Build the SELECT that gathers the stable write set for an UPDATE before the
 /// mutating write loop runs.
 ///
@@ -1151,11 +1184,19 @@ fn build_update_write_set_plan(
 ) -> Result<()> {
     let scratch_table_id = program.table_reference_counter.next();
     let is_update_from = !plan.from_tables.joined_tables().is_empty();
-    let columns = if is_update_from {
+    // WITHOUT ROWID targets are identified by their PRIMARY KEY values, which are materialized
+    // after the SET payloads instead of a rowid.
+    let without_rowid_target = plan.target_table.btree().filter(|btree| !btree.has_rowid);
+    let mut columns = if is_update_from {
         update_from_scratch_columns(plan.set_clauses.len())
     } else {
-        vec![(*ROWID_COLUMN).clone()]
+        vec![]
     };
+    match &without_rowid_target {
+        Some(btree) => columns.extend(update_pk_scratch_columns(btree)),
+        None if !is_update_from => columns.push((*ROWID_COLUMN).clone()),
+        None => {}
+    }
     let ephemeral_table = Arc::new(BTreeTable::new(
         0, // root_page, not relevant for ephemeral table definition
         "ephemeral_scratch".to_string(),
@@ -1192,32 +1233,53 @@ fn build_update_write_set_plan(
     let rowid_internal_id = plan.target_table.internal_id;
 
     let mut result_columns = update_from_set_result_columns;
-    result_columns.push(ResultSetColumn {
-        expr: Expr::RowId {
-            database: None,
-            table: rowid_internal_id,
-        },
+    let (key_exprs, rowid_mode) = match &without_rowid_target {
+        Some(btree) => (
+            without_rowid_pk_write_set(btree, rowid_internal_id).0,
+            EphemeralRowidMode::Auto,
+        ),
+        None => (
+            vec![Expr::RowId {
+                database: None,
+                table: rowid_internal_id,
+            }],
+            EphemeralRowidMode::FromResultColumns,
+        ),
+    };
+    result_columns.extend(key_exprs.into_iter().map(|expr| ResultSetColumn {
+        expr,
         alias: None,
         implicit_column_name: None,
         contains_aggregates: false,
-    });
+    }));
 
+    // With ORDER BY, the LIMIT decides which rows are collected, so it moves into the
+    // write-set SELECT; otherwise it is applied while iterating the collected rows.
+    let (order_by, limit, offset) = if plan.order_by.is_empty() {
+        (vec![], None, None)
+    } else {
+        (
+            std::mem::take(&mut plan.order_by),
+            plan.limit.take(),
+            plan.offset.take(),
+        )
+    };
     let join_order = default_join_order(&write_set_tables);
     let write_set_select = SelectPlan {
         table_references: write_set_tables,
         result_columns,
         where_clause: std::mem::take(&mut plan.where_clause),
-        group_by: None,     // N/A
-        order_by: vec![],   // N/A
+        group_by: None, // N/A
+        order_by,
         aggregates: vec![], // N/A
-        limit: None,        // N/A
+        limit,
         query_destination: QueryDestination::EphemeralTable {
             cursor_id: temp_cursor_id,
             table: ephemeral_table,
-            rowid_mode: EphemeralRowidMode::FromResultColumns,
+            rowid_mode,
         },
         join_order,
-        offset: None,
+        offset,
         contains_constant_false_condition: false,
         distinctness: super::plan::Distinctness::NonDistinct,
         values: vec![],
@@ -1332,6 +1394,11 @@ fn optimize_subqueries(plan: &mut SelectPlan, resolver: &Resolver) -> Result<()>
                     ));
                 }
             }
+            if let Some(recursive) = from_clause_subquery.recursive.as_mut() {
+                for select_plan in recursive.selects.iter_mut() {
+                    optimize_select_plan(select_plan, resolver)?;
+                }
+            }
         }
     }
 
@@ -3147,7 +3214,7 @@ fn ephemeral_index_build(
         .map(|(i, c)| {
             let expr = match c.generated_type() {
                 GeneratedType::Virtual { .. } => c.generated_expr().cloned(),
-                GeneratedType::NotGenerated => None,
+                GeneratedType::Stored { .. } | GeneratedType::NotGenerated => None,
             };
             IndexColumn {
                 name: c.name.clone().unwrap(),
@@ -3195,6 +3262,7 @@ fn ephemeral_index_build(
             .btree()
             .is_some_and(|btree| btree.has_rowid),
         index_method: None,
+        pk_suffix_start: None,
         on_conflict: None,
     };
 
@@ -3665,6 +3733,7 @@ mod tests {
             syms,
             true,
             DoubleQuotedDml::Enabled,
+            false,
         )
     }
 
@@ -1330,6 +1330,7 @@ mod tests {
                 root_page: 2,
                 has_rowid: true,
                 index_method: None,
+                pk_suffix_start: None,
                 on_conflict: None,
             })]),
         );
@@ -1480,6 +1481,7 @@ mod tests {
                 root_page: 2,
                 has_rowid: true,
                 index_method: None,
+                pk_suffix_start: None,
                 on_conflict: None,
             })]),
         );
@@ -1604,6 +1606,7 @@ mod tests {
                 root_page: 2,
                 has_rowid: true,
                 index_method: None,
+                pk_suffix_start: None,
                 on_conflict: None,
             })]),
         );
@@ -1791,6 +1794,7 @@ mod tests {
                 root_page: 2,
                 has_rowid: true,
                 index_method: None,
+                pk_suffix_start: None,
                 on_conflict: None,
             })]),
         );
@@ -403,7 +403,7 @@ fn access_path_makes_consumed_prefix_unique(
 
             // Unique indexes become prefix-unique once all key columns are either
             // fixed by equality or consumed as ORDER BY terms.
-            if index.unique && unique_prefix_terms >= index.columns.len() {
+            if index.unique && unique_prefix_terms >= index.key_columns().len() {
                 return true;
             }
 
@@ -450,6 +450,11 @@ pub fn subquery_intrinsic_order_consumed(
         // Don't consider sort elision for compound selects
         return 0;
     };
+    // The plan of a recursive CTE only describes its anchor; the rows it
+    // produces come out in queue order instead.
+    if subquery.is_recursive() {
+        return 0;
+    }
     // Explicit ORDER BY takes priority.
     if !select_plan.order_by.is_empty() {
         let intrinsic = build_intrinsic_order(
@@ -244,6 +244,7 @@ impl EmitOrderBy {
                 has_rowid: false,
                 where_clause: None,
                 index_method: None,
+                pk_suffix_start: None,
                 on_conflict: None,
             });
             program.alloc_cursor_id(CursorType::BTreeIndex(index))
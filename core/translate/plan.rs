@@ -622,6 +622,34 @@ pub enum SimpleAggregate {
     MinMax(Box<MinMaxDef>),
 }
 
+/// The recursive part of a `WITH RECURSIVE` common table expression.
+///
+/// The non-recursive (anchor) arms are stored as the regular plan of the
+/// [FromClauseSubquery]; this struct holds everything needed to run the
+/// queue-driven iteration on top of it, mirroring SQLite's algorithm:
+/// rows are pulled one at a time from a queue, emitted, copied into a
+/// single-row "current row" table and then every recursive arm is run
+/// against that table, pushing its output back onto the queue.
+#[derive(Debug, Clone)]
+pub struct RecursiveCte {
+    /// CTE identity of the single-row table that recursive arms read from.
+    /// The recursive self-reference inside each arm is a materialized CTE
+    /// reference with this id.
+    pub current_row_cte_id: usize,
+    /// The recursive arms, each planned as a simple SELECT.
+    pub selects: Vec<SelectPlan>,
+    /// True when the recursive arms are joined with UNION rather than
+    /// UNION ALL, i.e. rows already seen must not be emitted again.
+    pub distinct: bool,
+    /// ORDER BY of the CTE body, as (result column index, direction) pairs.
+    /// When non-empty, the queue is a priority queue instead of a FIFO.
+    pub order_by: Vec<(usize, SortOrder)>,
+    /// LIMIT of the CTE body; bounds the number of rows produced.
+    pub limit: Option<Box<Expr>>,
+    /// OFFSET of the CTE body; skipped rows are still fed to the recursive arms.
+    pub offset: Option<Box<Expr>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SelectPlan {
     pub table_references: TableReferences,
@@ -695,7 +723,13 @@ impl SelectPlan {
                 .joined_tables()
                 .iter()
                 .any(|t| match &t.table {
-                    Table::FromClauseSubquery(subquery) => plan_is_correlated(&subquery.plan),
+                    Table::FromClauseSubquery(subquery) => {
+                        plan_is_correlated(&subquery.plan)
+                            || subquery
+                                .recursive_selects()
+                                .iter()
+                                .any(|select| select.is_correlated())
+                    }
                     _ => false,
                 })
     }
@@ -706,6 +740,10 @@ impl SelectPlan {
                 || match &table.table {
                     Table::FromClauseSubquery(subquery) => {
                         subquery.plan.reads_table(database_id, table_name)
+                            || subquery
+                                .recursive_selects()
+                                .iter()
+                                .any(|select| select.reads_table(database_id, table_name))
                     }
                     Table::BTree(_) | Table::Virtual(_) => false,
                 }
@@ -739,6 +777,9 @@ pub enum DmlSafetyReason {
     /// triggers on referencing tables that write back to the target,
     /// which would invalidate the live scan iterator.
     FkCascade,
+    /// ORDER BY ... LIMIT picks which rows are written, so the rows have to be
+    /// sorted and cut off before any of them is modified.
+    OrderByLimit,
 }
 
 /// Safety decisions made while planning UPDATE/DELETE.
@@ -843,6 +884,8 @@ pub struct UpdatePlan {
     /// SET clause assignments
     pub set_clauses: Vec<UpdateSetClause>,
     pub where_clause: Vec<WhereTerm>,
+    /// ORDER BY of `UPDATE ... ORDER BY ... LIMIT`; moved into the write-set SELECT.
+    pub order_by: Vec<(Box<ast::Expr>, SortOrder, Option<ast::NullsOrder>)>,
     pub limit: Option<Box<Expr>>,
     pub offset: Option<Box<Expr>>,
     /// Optional RETURNING clause.
@@ -2267,6 +2310,8 @@ impl JoinedTable {
             result_columns_start_reg: None,
             materialized_cursor_id: None,
             cte: None,
+            recursive: None,
+            nested_from: None,
         }));
         Ok(Self {
             op: Operation::default_scan_for(&table),
@@ -2368,6 +2413,8 @@ impl JoinedTable {
             result_columns_start_reg: None,
             materialized_cursor_id: None,
             cte,
+            recursive: None,
+            nested_from: None,
         }));
         Ok(Self {
             op: Operation::default_scan_for(&table),
@@ -2899,6 +2946,13 @@ pub struct Aggregate {
     pub original_expr: ast::Expr,
     pub distinctness: Distinctness,
     pub filter_expr: Option<ast::Expr>,
+    /// ORDER BY terms inside the aggregate call, e.g. `group_concat(x, ',' ORDER BY y)`.
+    /// The inputs of each group are buffered in a sorter and only fed to the aggregate,
+    /// in this order, when the group is finalized.
+    pub order_by: Vec<(ast::Expr, SortOrder, Option<ast::NullsOrder>)>,
+    /// The sorter cursor buffering the inputs of an ordered aggregate.
+    /// Populated when the main loop is initialized.
+    pub sorter_cursor_id: Option<CursorID>,
 }
 
 impl Aggregate {
@@ -2908,6 +2962,7 @@ impl Aggregate {
         expr: &Expr,
         distinctness: Distinctness,
         filter_expr: Option<ast::Expr>,
+        order_by: &[ast::SortedColumn],
     ) -> Self {
         Aggregate {
             func,
@@ -2915,12 +2970,27 @@ impl Aggregate {
             original_expr: expr.clone(),
             distinctness,
             filter_expr,
+            order_by: order_by
+                .iter()
+                .map(|col| {
+                    (
+                        *col.expr.clone(),
+                        col.order.unwrap_or(SortOrder::Asc),
+                        col.nulls,
+                    )
+                })
+                .collect(),
+            sorter_cursor_id: None,
         }
     }
 
     pub fn is_distinct(&self) -> bool {
         self.distinctness.is_distinct()
     }
+
+    pub fn is_ordered(&self) -> bool {
+        !self.order_by.is_empty()
+    }
 }
 
 /// Represents the window definition and all window functions associated with a single SELECT.
@@ -2937,6 +3007,9 @@ pub struct Window {
     pub deduplicated_partition_by_len: Option<usize>,
     /// Expressions from the ORDER BY clause.
     pub order_by: Vec<(Expr, SortOrder, Option<ast::NullsOrder>)>,
+    /// The frame specification, or `None` if the window definition has none, in which case
+    /// the default `RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW` frame applies.
+    pub frame: Option<WindowFrame>,
     /// All window functions associated with this window.
     pub functions: Vec<WindowFunction>,
 }
@@ -2944,88 +3017,186 @@ pub struct Window {
 impl Window {
     const DEFAULT_SORT_ORDER: SortOrder = SortOrder::Asc;
 
-    pub fn new(name: Option<String>, ast: &ast::Window) -> Result<Self> {
-        if !Self::is_default_frame_spec(&ast.frame_clause) {
-            crate::bail_parse_error!("Custom frame specifications are not supported yet");
+    /// Creates a window from its definition. If the definition is based on another window
+    /// (e.g. `OVER (w ORDER BY x)`), that window is looked up in `windows` and its PARTITION BY
+    /// and ORDER BY clauses are inherited, following the same rules as SQLite.
+    pub fn new(name: Option<String>, ast: &ast::Window, windows: &[Window]) -> Result<Self> {
+        let frame = ast
+            .frame_clause
+            .as_ref()
+            .map(WindowFrame::from_ast)
+            .transpose()?;
+
+        let mut partition_by: Vec<Expr> = ast.partition_by.iter().map(|arg| *arg.clone()).collect();
+        let mut order_by: Vec<_> = ast
+            .order_by
+            .iter()
+            .map(|col| {
+                (
+                    *col.expr.clone(),
+                    col.order.unwrap_or(Self::DEFAULT_SORT_ORDER),
+                    col.nulls,
+                )
+            })
+            .collect();
+
+        if let Some(base) = &ast.base {
+            let base_name = crate::util::normalize_ident(base.as_str());
+            // When multiple windows share the same name, the most recent definition wins.
+            let Some(base_window) = windows
+                .iter()
+                .rev()
+                .find(|w| w.name.as_ref() == Some(&base_name))
+            else {
+                crate::bail_parse_error!("no such window: {}", base_name);
+            };
+            if !partition_by.is_empty() {
+                crate::bail_parse_error!(
+                    "cannot override PARTITION clause of window: {}",
+                    base_name
+                );
+            }
+            if !order_by.is_empty() && !base_window.order_by.is_empty() {
+                crate::bail_parse_error!(
+                    "cannot override ORDER BY clause of window: {}",
+                    base_name
+                );
+            }
+            if base_window.frame.is_some() {
+                crate::bail_parse_error!(
+                    "cannot override frame specification of window: {}",
+                    base_name
+                );
+            }
+            partition_by = base_window.partition_by.clone();
+            if order_by.is_empty() {
+                order_by = base_window.order_by.clone();
+            }
+        }
+
+        if let Some(frame) = &frame {
+            if frame.mode == FrameMode::Range && frame.has_offset() && order_by.len() != 1 {
+                crate::bail_parse_error!(
+                    "RANGE with offset PRECEDING/FOLLOWING requires one ORDER BY expression"
+                );
+            }
         }
 
         Ok(Window {
             name,
-            partition_by: ast.partition_by.iter().map(|arg| *arg.clone()).collect(),
+            partition_by,
             deduplicated_partition_by_len: None,
-            order_by: ast
-                .order_by
-                .iter()
-                .map(|col| {
-                    (
-                        *col.expr.clone(),
-                        col.order.unwrap_or(Self::DEFAULT_SORT_ORDER),
-                        col.nulls,
-                    )
-                })
-                .collect(),
+            order_by,
+            frame,
             functions: vec![],
         })
     }
 
-    pub fn is_equivalent(&self, ast: &ast::Window) -> bool {
-        if !Self::is_default_frame_spec(&ast.frame_clause) {
+    pub fn is_equivalent(&self, other: &Window) -> bool {
+        let frames_are_equivalent = match (&self.frame, &other.frame) {
+            (None, None) => true,
+            (Some(a), Some(b)) => a.is_equivalent(b),
+            (Some(frame), None) | (None, Some(frame)) => frame.is_default(),
+        };
+        if !frames_are_equivalent {
             return false;
         }
 
-        if self.partition_by.len() != ast.partition_by.len() {
+        if self.partition_by.len() != other.partition_by.len() {
             return false;
         }
         if !self
             .partition_by
             .iter()
-            .zip(&ast.partition_by)
+            .zip(&other.partition_by)
             .all(|(a, b)| exprs_are_equivalent(a, b))
         {
             return false;
         }
 
-        if self.order_by.len() != ast.order_by.len() {
+        if self.order_by.len() != other.order_by.len() {
             return false;
         }
-        self.order_by
-            .iter()
-            .zip(&ast.order_by)
-            .all(|((expr_a, order_a, nulls_a), col_b)| {
-                exprs_are_equivalent(expr_a, &col_b.expr)
-                    && *order_a == col_b.order.unwrap_or(Self::DEFAULT_SORT_ORDER)
-                    && *nulls_a == col_b.nulls
-            })
+        self.order_by.iter().zip(&other.order_by).all(
+            |((expr_a, order_a, nulls_a), (expr_b, order_b, nulls_b))| {
+                exprs_are_equivalent(expr_a, expr_b) && order_a == order_b && nulls_a == nulls_b
+            },
+        )
     }
 
-    fn is_default_frame_spec(frame: &Option<FrameClause>) -> bool {
-        if let Some(frame_clause) = frame {
-            let FrameClause {
-                mode,
-                start,
-                end,
-                exclude,
-            } = frame_clause;
-            if *mode != FrameMode::Range {
-                return false;
-            }
-            if *start != FrameBound::UnboundedPreceding {
-                return false;
-            }
-            if *end != Some(FrameBound::CurrentRow) {
-                return false;
-            }
-            if let Some(exclude) = exclude {
-                if *exclude != FrameExclude::NoOthers {
-                    return false;
-                }
-            }
-        }
-        true
+    /// Returns true if the window uses the default frame, either implicitly or because the
+    /// frame specification spells it out.
+    pub fn has_default_frame(&self) -> bool {
+        self.frame.as_ref().is_none_or(WindowFrame::is_default)
     }
 }
 
+/// A window frame specification, normalized so that the end bound and the exclusion are
+/// always present.
 #[derive(Debug, Clone)]
+pub struct WindowFrame {
+    pub mode: FrameMode,
+    pub start: FrameBound,
+    pub end: FrameBound,
+    pub exclude: FrameExclude,
+}
+
+impl WindowFrame {
+    fn from_ast(frame_clause: &FrameClause) -> Result<Self> {
+        let FrameClause {
+            mode,
+            start,
+            end,
+            exclude,
+        } = frame_clause;
+        let end = end.clone().unwrap_or(FrameBound::CurrentRow);
+        let supported = match (start, &end) {
+            (FrameBound::UnboundedFollowing, _) | (_, FrameBound::UnboundedPreceding) => false,
+            (FrameBound::CurrentRow, FrameBound::Preceding(_)) => false,
+            (FrameBound::Following(_), FrameBound::Preceding(_) | FrameBound::CurrentRow) => false,
+            _ => true,
+        };
+        if !supported {
+            crate::bail_parse_error!("unsupported frame specification");
+        }
+
+        Ok(WindowFrame {
+            mode: *mode,
+            start: start.clone(),
+            end,
+            exclude: exclude.clone().unwrap_or(FrameExclude::NoOthers),
+        })
+    }
+
+    /// Returns true for `RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW` without exclusions.
+    pub fn is_default(&self) -> bool {
+        self.mode == FrameMode::Range
+            && self.start == FrameBound::UnboundedPreceding
+            && self.end == FrameBound::CurrentRow
+            && self.exclude == FrameExclude::NoOthers
+    }
+
+    /// Returns true if either bound is `<expr> PRECEDING` or `<expr> FOLLOWING`.
+    pub fn has_offset(&self) -> bool {
+        [&self.start, &self.end]
+            .into_iter()
+            .any(|bound| matches!(bound, FrameBound::Preceding(_) | FrameBound::Following(_)))
+    }
+
+    fn is_equivalent(&self, other: &WindowFrame) -> bool {
+        let bounds_are_equivalent = |a: &FrameBound, b: &FrameBound| match (a, b) {
+            (FrameBound::Preceding(a), FrameBound::Preceding(b))
+            | (FrameBound::Following(a), FrameBound::Following(b)) => exprs_are_equivalent(a, b),
+            _ => a == b,
+        };
+        self.mode == other.mode
+            && bounds_are_equivalent(&self.start, &other.start)
+            && bounds_are_equivalent(&self.end, &other.end)
+            && self.exclude == other.exclude
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum WindowFunctionKind {
     Agg(AggFunc),
     Window(WindowFunc),
@@ -3065,9 +3236,23 @@ pub enum SubqueryState {
         /// We keep these so later analysis can still understand dependencies
         /// even after the plan is consumed.
         outer_ref_ids: Vec<TableInternalId>,
+        /// Set when the subquery was emitted as a subroutine that other code
+        /// paths can re-enter, e.g. FULL OUTER JOIN unmatched-row emission.
+        rerun: Option<SubqueryRerun>,
     },
 }
 
+/// Entry point of a correlated subquery emitted as a re-enterable subroutine.
+///
+/// Unmatched-row paths of a FULL OUTER hash join reposition the joined cursors
+/// after the probe loop body has already evaluated the subquery, so they `Gosub`
+/// into it again before re-checking the WHERE clause.
+#[derive(Debug, Clone, Copy)]
+pub struct SubqueryRerun {
+    pub return_reg: usize,
+    pub start: BranchOffset,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SubqueryPosition {
     ResultColumn,
@@ -3086,6 +3271,7 @@ impl SubqueryPosition {
             SubqueryPosition::ResultColumn
                 | SubqueryPosition::Where
                 | SubqueryPosition::GroupBy
+                | SubqueryPosition::Having
                 | SubqueryPosition::OrderBy
         )
     }
@@ -3169,6 +3355,7 @@ impl NonFromClauseSubquery {
                 self.state = SubqueryState::Evaluated {
                     evaluated_at,
                     outer_ref_ids,
+                    rerun: None,
                 };
                 plan
             }
@@ -3265,7 +3452,12 @@ fn select_plan_has_outer_scope_dependency_with_tables(
                         plan_has_outer_scope_dependency_with_tables(
                             subquery.plan.as_ref(),
                             accessible_table_ids,
-                        )
+                        ) || subquery.recursive_selects().iter().any(|select| {
+                            select_plan_has_outer_scope_dependency_with_tables(
+                                select,
+                                accessible_table_ids,
+                            )
+                        })
                     }
                     _ => false,
                 });
@@ -3345,6 +3537,13 @@ fn eval_at_for_select_plan(
                 join_order,
                 table_references,
             )?);
+            for select_plan in from_clause_subquery.recursive_selects() {
+                eval_at = eval_at.max(eval_at_for_select_plan(
+                    select_plan,
+                    join_order,
+                    table_references,
+                )?);
+            }
         }
     }
     Ok(eval_at)
@@ -6,9 +6,10 @@ use super::{
     plan::{
         Aggregate, ColumnMask, ColumnUsedMask, Distinctness, EvalAt, IterationDirection, JoinInfo,
         JoinOrderMember, JoinType as PlanJoinType, JoinedTable, Operation, OuterQueryReference,
-        Plan, QueryDestination, ResultSetColumn, Scan, TableReferences, WhereTerm,
+        Plan, QueryDestination, RecursiveCte, ResultSetColumn, Scan, SelectPlan, TableReferences,
+        WhereTerm,
     },
-    select::prepare_select_plan,
+    select::{prepare_select_plan, resolve_compound_order_by_expr},
 };
 use crate::translate::plan::BitSet;
 use crate::translate::{
@@ -58,6 +59,8 @@ struct CteDefinition {
     referenced_cte_indices: SmallVec<[usize; 2]>,
     /// True if WITH ... AS MATERIALIZED was specified, forcing materialization
     materialize_hint: bool,
+    /// True if the CTE body refers to its own name, i.e. it is a recursive CTE
+    recursive: bool,
 }
 
 /// Collect all table names referenced in a SELECT's FROM clause.
@@ -212,11 +215,6 @@ pub fn resolve_window_and_aggregate_functions(
                 filter_over,
                 order_by,
             } => {
-                if !order_by.is_empty() {
-                    crate::bail_parse_error!(
-                        "ORDER BY clause is not supported yet in aggregate functions"
-                    );
-                }
                 let args_count = args.len();
                 let distinctness = Distinctness::from_ast(distinctness.as_ref());
 
@@ -238,6 +236,7 @@ pub fn resolve_window_and_aggregate_functions(
                                 distinctness,
                                 f,
                                 filter_over.filter_clause.as_deref().cloned(),
+                                order_by,
                             )?;
                             contains_aggregates = true;
                         }
@@ -280,12 +279,19 @@ pub fn resolve_window_and_aggregate_functions(
                                         distinctness,
                                         func,
                                         filter_over.filter_clause.as_deref().cloned(),
+                                        order_by,
                                     )?;
                                     contains_aggregates = true;
                                 }
                                 return Ok(WalkControl::SkipChildren);
                             }
                         }
+                        if !order_by.is_empty() {
+                            crate::bail_parse_error!(
+                                "ORDER BY may not be used with non-aggregate {}()",
+                                name.as_str()
+                            );
+                        }
                     }
                     _ => {
                         if filter_over.over_clause.is_some() {
@@ -294,6 +300,12 @@ pub fn resolve_window_and_aggregate_functions(
                                 name.as_str()
                             );
                         }
+                        if !order_by.is_empty() {
+                            crate::bail_parse_error!(
+                                "ORDER BY may not be used with non-aggregate {}()",
+                                name.as_str()
+                            );
+                        }
                     }
                 }
             }
@@ -316,6 +328,7 @@ pub fn resolve_window_and_aggregate_functions(
                                 Distinctness::NonDistinct,
                                 f,
                                 filter_over.filter_clause.as_deref().cloned(),
+                                &[],
                             )?;
                             contains_aggregates = true;
                         }
@@ -373,6 +386,7 @@ pub fn resolve_window_and_aggregate_functions(
                                         Distinctness::NonDistinct,
                                         func,
                                         filter_over.filter_clause.as_deref().cloned(),
+                                        &[],
                                     )?;
                                     contains_aggregates = true;
                                 }
@@ -403,6 +417,25 @@ fn link_with_window(
     if distinctness.is_distinct() {
         crate::bail_parse_error!("DISTINCT is not supported for window functions");
     }
+    if let Expr::FunctionCall { name, order_by, .. } = expr {
+        if !order_by.is_empty() {
+            crate::bail_parse_error!(
+                "ORDER BY may not be used with non-aggregate {}()",
+                name.as_str()
+            );
+        }
+    }
+    if let (
+        WindowFunctionKind::Window(_),
+        Expr::FunctionCall { filter_over, .. } | Expr::FunctionCallStar { filter_over, .. },
+    ) = (&func, expr)
+    {
+        if filter_over.filter_clause.is_some() {
+            crate::bail_parse_error!(
+                "FILTER clause may only be used with aggregate window functions"
+            );
+        }
+    }
     expr_vector_size(expr)?;
     if let Some(windows) = windows {
         let window = resolve_window(windows, over_clause)?;
@@ -435,11 +468,12 @@ fn link_with_window(
 fn resolve_window<'a>(windows: &'a mut Vec<Window>, over_clause: &Over) -> Result<&'a mut Window> {
     match over_clause {
         Over::Window(window) => {
-            if let Some(idx) = windows.iter().position(|w| w.is_equivalent(window)) {
+            let window = Window::new(None, window, windows)?;
+            if let Some(idx) = windows.iter().position(|w| w.is_equivalent(&window)) {
                 return Ok(&mut windows[idx]);
             }
 
-            windows.push(Window::new(None, window)?);
+            windows.push(window);
             Ok(windows.last_mut().expect("just pushed, so must exist"))
         }
         Over::Name(name) => {
@@ -463,6 +497,7 @@ fn add_aggregate_if_not_exists(
     distinctness: Distinctness,
     func: AggFunc,
     filter_expr: Option<ast::Expr>,
+    order_by: &[ast::SortedColumn],
 ) -> Result<()> {
     if distinctness.is_distinct() && args.len() != 1 {
         crate::bail_parse_error!("DISTINCT aggregate functions must have exactly one argument");
@@ -471,7 +506,14 @@ fn add_aggregate_if_not_exists(
         .iter()
         .all(|a| !exprs_are_equivalent(&a.original_expr, expr))
     {
-        aggs.push(Aggregate::new(func, args, expr, distinctness, filter_expr));
+        aggs.push(Aggregate::new(
+            func,
+            args,
+            expr,
+            distinctness,
+            filter_expr,
+            order_by,
+        ));
     }
     Ok(())
 }
@@ -535,23 +577,35 @@ fn plan_cte(
         });
     }
 
-    // Block the CTE's own name from resolving to a schema object during
-    // planning of its body. Without this, a same-named view would be expanded
-    // recursively (stack overflow) and a same-named table would give wrong
-    // results. `parse_table` checks this and produces "circular reference".
-    program.push_cte_being_defined(cte_def.name.clone());
-
     // Plan this CTE with fresh IDs
-    let cte_plan = prepare_select_plan(
-        cte_def.select.clone(),
-        resolver,
-        program,
-        &outer_query_refs,
-        QueryDestination::placeholder_for_subquery(),
-        connection,
-    );
-    program.pop_cte_being_defined();
-    let cte_plan = cte_plan?;
+    let (cte_plan, recursive_cte) = if cte_def.recursive {
+        let (cte_plan, recursive_cte) = plan_recursive_cte(
+            &cte_def.name,
+            cte_def.select.clone(),
+            &cte_def.explicit_columns,
+            &outer_query_refs,
+            resolver,
+            program,
+            connection,
+        )?;
+        (cte_plan, Some(recursive_cte))
+    } else {
+        // Block the CTE's own name from resolving to a schema object during
+        // planning of its body. Without this, a same-named view would be expanded
+        // recursively (stack overflow) and a same-named table would give wrong
+        // results. `parse_table` checks this and produces "circular reference".
+        program.push_cte_being_defined(cte_def.name.clone());
+        let cte_plan = prepare_select_plan(
+            cte_def.select.clone(),
+            resolver,
+            program,
+            &outer_query_refs,
+            QueryDestination::placeholder_for_subquery(),
+            connection,
+        );
+        program.pop_cte_being_defined();
+        (cte_plan?, None)
+    };
 
     // CTEs can be either simple SELECT or compound SELECT (UNION/INTERSECT/EXCEPT)
     let explicit_cols = if cte_def.explicit_columns.is_empty() {
@@ -576,7 +630,7 @@ fn plan_cte(
         }
     }
 
-    match cte_plan {
+    let mut cte_table = match cte_plan {
         Plan::Select(_) | Plan::CompoundSelect { .. } => JoinedTable::new_subquery_from_plan(
             cte_def.name.clone(),
             cte_plan,
@@ -585,10 +639,288 @@ fn plan_cte(
             explicit_cols,
             Some(cte_def.cte_id), // Pass the CTE identity for sharing materialized data
             cte_def.materialize_hint,
-        ),
+        )?,
         Plan::Delete(_) | Plan::Update(_) => {
             crate::bail_parse_error!("DELETE/UPDATE queries are not supported in CTEs")
         }
+    };
+    if let Some(recursive_cte) = recursive_cte {
+        attach_recursive_cte(&mut cte_table, recursive_cte);
+    }
+    Ok(cte_table)
+}
+
+/// Returns true if a CTE body refers to the CTE's own name anywhere in a FROM
+/// clause, including the FROM clauses of nested subqueries.
+fn cte_references_itself(cte_name: &str, select: &Select) -> bool {
+    let mut referenced_tables = Vec::new();
+    collect_from_clause_table_refs(select, &mut referenced_tables);
+    referenced_tables.iter().any(|name| name == cte_name)
+}
+
+/// Count the references to `table_name` in a single SELECT, both direct FROM
+/// clause entries and those inside subqueries.
+fn count_table_refs(one: &ast::OneSelect, table_name: &str) -> usize {
+    let mut referenced_tables = Vec::new();
+    collect_from_one_select(one, &mut referenced_tables);
+    collect_subquery_table_refs_in_one_select(one, &mut referenced_tables);
+    referenced_tables
+        .iter()
+        .filter(|name| *name == table_name)
+        .count()
+}
+
+/// Count the references to `table_name` in the FROM clause of a single SELECT,
+/// looking through parenthesized join groups but not into subqueries.
+fn count_direct_table_refs(one: &ast::OneSelect, table_name: &str) -> usize {
+    fn count_in_select_table(table: &ast::SelectTable, table_name: &str) -> usize {
+        match table {
+            ast::SelectTable::Table(qualified_name, _, _) => {
+                usize::from(normalize_ident(qualified_name.name.as_str()) == table_name)
+            }
+            ast::SelectTable::Sub(from_clause, _) => count_in_from_clause(from_clause, table_name),
+            ast::SelectTable::TableCall(..) | ast::SelectTable::Select(..) => 0,
+        }
+    }
+    fn count_in_from_clause(from_clause: &FromClause, table_name: &str) -> usize {
+        count_in_select_table(&from_clause.select, table_name)
+            + from_clause
+                .joins
+                .iter()
+                .map(|join| count_in_select_table(&join.table, table_name))
+                .sum::<usize>()
+    }
+    match one {
+        ast::OneSelect::Select {
+            from: Some(from_clause),
+            ..
+        } => count_in_from_clause(from_clause, table_name),
+        ast::OneSelect::Select { from: None, .. } | ast::OneSelect::Values(_) => 0,
+    }
+}
+
+/// Plan the body of a `WITH RECURSIVE` CTE that refers to its own name.
+///
+/// The leading arms that don't mention the CTE form the anchor, which is returned
+/// as a regular plan. The remaining arms must be joined with UNION or UNION ALL;
+/// each is planned on its own with the CTE name bound to a single-row table that
+/// holds the row currently being processed (see [RecursiveCte]).
+fn plan_recursive_cte(
+    cte_name: &str,
+    select: Select,
+    explicit_columns: &[String],
+    outer_query_refs: &[OuterQueryReference],
+    resolver: &Resolver,
+    program: &mut ProgramBuilder,
+    connection: &Arc<crate::Connection>,
+) -> Result<(Plan, RecursiveCte)> {
+    let Select {
+        with,
+        body,
+        order_by,
+        limit,
+    } = select;
+    let ast::SelectBody {
+        select: first,
+        mut compounds,
+    } = body;
+
+    // Like SQLite, the anchor arms must all come before the recursive ones, and
+    // the recursive arms can only be combined with UNION or UNION ALL.
+    let first_recursive = compounds
+        .iter()
+        .position(|compound| count_table_refs(&compound.select, cte_name) > 0);
+    let Some(first_recursive) = first_recursive.filter(|_| count_table_refs(&first, cte_name) == 0)
+    else {
+        crate::bail_parse_error!("circular reference: {}", cte_name);
+    };
+    let recursive_compounds = compounds.split_off(first_recursive);
+    if recursive_compounds.iter().any(|compound| {
+        !matches!(
+            compound.operator,
+            ast::CompoundOperator::Union | ast::CompoundOperator::UnionAll
+        ) || count_table_refs(&compound.select, cte_name) == 0
+    }) {
+        crate::bail_parse_error!("circular reference: {}", cte_name);
+    }
+    let distinct = recursive_compounds
+        .last()
+        .is_some_and(|compound| matches!(compound.operator, ast::CompoundOperator::Union));
+
+    program.push_cte_being_defined(cte_name.to_string());
+    let anchor_plan = prepare_select_plan(
+        Select {
+            with: with.clone(),
+            body: ast::SelectBody {
+                select: first,
+                compounds,
+            },
+            order_by: vec![],
+            limit: None,
+        },
+        resolver,
+        program,
+        outer_query_refs,
+        QueryDestination::placeholder_for_subquery(),
+        connection,
+    );
+    program.pop_cte_being_defined();
+    let anchor_plan = anchor_plan?;
+    let num_result_columns = anchor_plan.select_result_columns().len();
+
+    // Inside the recursive arms the CTE name refers to the current row only.
+    let explicit_cols = if explicit_columns.is_empty() {
+        None
+    } else {
+        Some(explicit_columns)
+    };
+    let current_row_cte_id = program.alloc_cte_id();
+    let current_row_table = JoinedTable::new_subquery_from_plan(
+        cte_name.to_string(),
+        anchor_plan.clone(),
+        None,
+        program.table_reference_counter.next(),
+        explicit_cols,
+        Some(current_row_cte_id),
+        false,
+    )?;
+    let mut recursive_outer_query_refs: Vec<OuterQueryReference> = outer_query_refs
+        .iter()
+        .filter(|outer_ref| outer_ref.identifier != cte_name)
+        .cloned()
+        .collect();
+    recursive_outer_query_refs.push(OuterQueryReference {
+        identifier: cte_name.to_string(),
+        internal_id: current_row_table.internal_id,
+        table: current_row_table.table,
+        using_dedup_hidden_cols: ColumnMask::default(),
+        col_used_mask: ColumnUsedMask::default(),
+        cte_select: None,
+        cte_explicit_columns: vec![],
+        cte_id: Some(current_row_cte_id),
+        cte_definition_only: true,
+        rowid_referenced: false,
+        scope_depth: 0,
+    });
+
+    let mut selects = Vec::with_capacity(recursive_compounds.len());
+    for compound in recursive_compounds {
+        let direct_refs = count_direct_table_refs(&compound.select, cte_name);
+        if direct_refs > 1 {
+            crate::bail_parse_error!("multiple references to recursive table: {}", cte_name);
+        }
+        if count_table_refs(&compound.select, cte_name) > direct_refs {
+            crate::bail_parse_error!("recursive reference in a subquery: {}", cte_name);
+        }
+        let operator = compound.operator;
+        let plan = prepare_select_plan(
+            Select {
+                with: with.clone(),
+                body: ast::SelectBody {
+                    select: compound.select,
+                    compounds: vec![],
+                },
+                order_by: vec![],
+                limit: None,
+            },
+            resolver,
+            program,
+            &recursive_outer_query_refs,
+            QueryDestination::placeholder_for_subquery(),
+            connection,
+        )?;
+        let Plan::Select(select_plan) = plan else {
+            unreachable!("a single SELECT without compounds is planned as Plan::Select");
+        };
+        if select_plan.group_by.is_some()
+            || !select_plan.aggregates.is_empty()
+            || select_plan.window.is_some()
+        {
+            crate::bail_parse_error!("recursive aggregate queries not supported");
+        }
+        if select_plan.result_columns.len() != num_result_columns {
+            crate::bail_parse_error!(
+                "SELECTs to the left and right of {} do not have the same number of result columns",
+                operator
+            );
+        }
+        selects.push(*select_plan);
+    }
+
+    // ORDER BY turns the queue into a priority queue. Terms resolve like the
+    // ORDER BY of a compound SELECT, plus the CTE's explicit column names.
+    let mut queue_order_by = Vec::with_capacity(order_by.len());
+    if !order_by.is_empty() {
+        let anchor_selects: Vec<&SelectPlan> = match &anchor_plan {
+            Plan::Select(select_plan) => vec![select_plan.as_ref()],
+            Plan::CompoundSelect {
+                left, right_most, ..
+            } => left
+                .iter()
+                .map(|(select_plan, _)| select_plan)
+                .chain(std::iter::once(right_most.as_ref()))
+                .collect(),
+            Plan::Delete(_) | Plan::Update(_) => {
+                crate::bail_parse_error!("DELETE/UPDATE queries are not supported in CTEs")
+            }
+        };
+        let all_plans: Vec<&SelectPlan> =
+            anchor_selects.into_iter().chain(selects.iter()).collect();
+        for (i, term) in order_by.iter().enumerate() {
+            let explicit_col_idx = match term.expr.as_ref() {
+                Expr::Id(name) => {
+                    let name = normalize_ident(name.as_str());
+                    explicit_columns.iter().position(|col| *col == name)
+                }
+                _ => None,
+            };
+            let col_idx = match explicit_col_idx {
+                Some(col_idx) => col_idx,
+                None => resolve_compound_order_by_expr(&term.expr, &all_plans, i + 1)?,
+            };
+            let sort_order = term.order.unwrap_or(ast::SortOrder::Asc);
+            match (sort_order, term.nulls) {
+                (_, None)
+                | (ast::SortOrder::Asc, Some(ast::NullsOrder::First))
+                | (ast::SortOrder::Desc, Some(ast::NullsOrder::Last)) => {}
+                _ => crate::bail_parse_error!(
+                    "NULLS FIRST/LAST is not supported in the ORDER BY of a recursive CTE"
+                ),
+            }
+            queue_order_by.push((col_idx, sort_order));
+        }
+    }
+
+    let (limit, offset) = limit.map_or(Ok((None, None)), |l| parse_limit(l, resolver))?;
+
+    Ok((
+        anchor_plan,
+        RecursiveCte {
+            current_row_cte_id,
+            selects,
+            distinct,
+            order_by: queue_order_by,
+            limit,
+            offset,
+        },
+    ))
+}
+
+/// Attach the recursive part of a `WITH RECURSIVE` CTE to its table reference.
+fn attach_recursive_cte(table: &mut JoinedTable, recursive_cte: RecursiveCte) {
+    if let Table::FromClauseSubquery(subquery) = &mut table.table {
+        Arc::make_mut(subquery).recursive = Some(Box::new(recursive_cte));
+    }
+}
+
+/// The CTE identity under which a data-modifying CTE's RETURNING rows were
+/// registered when it was emitted ahead of its statement.
+fn dml_cte_id(program: &ProgramBuilder, cte_name: &str) -> Result<usize> {
+    match program.dml_cte_id(cte_name) {
+        Some(cte_id) => Ok(cte_id),
+        None => crate::bail_parse_error!(
+            "WITH clause containing a data-modifying statement must be at the top level"
+        ),
     }
 }
 
@@ -607,10 +939,6 @@ pub fn plan_ctes_as_outer_refs(
         return Ok(());
     };
 
-    if with.recursive {
-        crate::bail_parse_error!("Recursive CTEs are not yet supported");
-    }
-
     for cte in with.ctes {
         // Normalize explicit column names
         let explicit_columns: Vec<String> = cte
@@ -632,23 +960,43 @@ pub fn plan_ctes_as_outer_refs(
 
         // Clone the CTE select AST before planning, so we can store it for re-planning
         let cte_select_ast = cte.select.clone();
-        // AS MATERIALIZED forces materialization
-        let materialize_hint = cte.materialized == Materialized::Yes;
-        // Block the CTE's own name from resolving to a schema object during
-        // planning of its body (see push_cte_being_defined).
-        program.push_cte_being_defined(cte_name.clone());
+        // AS MATERIALIZED forces materialization. A data-modifying CTE has already
+        // left its RETURNING rows in an ephemeral table that all references share.
+        let dml_cte_id = match cte.dml {
+            Some(_) => Some(dml_cte_id(program, &cte_name)?),
+            None => None,
+        };
+        let materialize_hint = cte.materialized == Materialized::Yes || dml_cte_id.is_some();
 
         // Plan the CTE SELECT
-        let cte_plan = prepare_select_plan(
-            cte.select,
-            resolver,
-            program,
-            table_references.outer_query_refs(),
-            QueryDestination::placeholder_for_subquery(),
-            connection,
-        );
-        program.pop_cte_being_defined();
-        let cte_plan = cte_plan?;
+        let self_referencing =
+            dml_cte_id.is_none() && cte_references_itself(&cte_name, &cte.select);
+        let (cte_plan, recursive_cte) = if self_referencing {
+            let (cte_plan, recursive_cte) = plan_recursive_cte(
+                &cte_name,
+                cte.select,
+                &explicit_columns,
+                table_references.outer_query_refs(),
+                resolver,
+                program,
+                connection,
+            )?;
+            (cte_plan, Some(recursive_cte))
+        } else {
+            // Block the CTE's own name from resolving to a schema object during
+            // planning of its body (see push_cte_being_defined).
+            program.push_cte_being_defined(cte_name.clone());
+            let cte_plan = prepare_select_plan(
+                cte.select,
+                resolver,
+                program,
+                table_references.outer_query_refs(),
+                QueryDestination::placeholder_for_subquery(),
+                connection,
+            );
+            program.pop_cte_being_defined();
+            (cte_plan?, None)
+        };
 
         // Convert plan to JoinedTable to extract column info
         let explicit_cols = if explicit_columns.is_empty() {
@@ -656,20 +1004,23 @@ pub fn plan_ctes_as_outer_refs(
         } else {
             Some(explicit_columns.as_slice())
         };
-        let joined_table = match cte_plan {
+        let mut joined_table = match cte_plan {
             Plan::Select(_) | Plan::CompoundSelect { .. } => JoinedTable::new_subquery_from_plan(
                 cte_name.clone(),
                 cte_plan,
                 None,
                 program.table_reference_counter.next(),
                 explicit_cols,
-                None, // DML CTEs don't share materialized data (TODO: implement if needed)
+                dml_cte_id, // Only data-modifying CTEs share materialized data here
                 materialize_hint,
             )?,
             Plan::Delete(_) | Plan::Update(_) => {
                 crate::bail_parse_error!("Only SELECT queries are supported in CTEs")
             }
         };
+        if let Some(recursive_cte) = recursive_cte {
+            attach_recursive_cte(&mut joined_table, recursive_cte);
+        }
 
         // Add CTE as outer query reference so it's available to subqueries.
         // cte_definition_only = true: the CTE is only for subquery FROM lookup
@@ -684,7 +1035,7 @@ pub fn plan_ctes_as_outer_refs(
             col_used_mask: ColumnUsedMask::default(),
             cte_select: Some(cte_select_ast),
             cte_explicit_columns: explicit_columns,
-            cte_id: None, // DML CTEs don't track CTE sharing (TODO: implement if needed)
+            cte_id: dml_cte_id,
             cte_definition_only: true,
             rowid_referenced: false,
             scope_depth: 0,
@@ -717,67 +1068,14 @@ fn parse_from_clause_table(
             connection,
         ),
         ast::SelectTable::Select(subselect, maybe_alias) => {
-            // For inline subqueries, we plan all CTEs once and pass them as outer_query_refs.
-            // This allows the subquery to reference CTEs defined in the parent's WITH clause.
-            let mut outer_query_refs_for_subquery = table_references.outer_query_refs().to_vec();
-            let base_outer_query_refs_for_subquery = base_outer_refs_for_cte_planning(
-                table_references.outer_query_refs(),
-                cte_definitions,
-            );
-            for (idx, cte_def) in cte_definitions.iter().enumerate() {
-                // Check if this CTE has already been planned and is in outer_query_refs.
-                // This avoids exponential re-planning when CTEs have transitive dependencies.
-                if outer_query_refs_for_subquery
-                    .iter()
-                    .any(|r| r.identifier == cte_def.name)
-                {
-                    continue;
-                }
-                // Plan each CTE so it's visible to this inline subquery's FROM clause.
-                // Example: WITH cte AS (...) SELECT * FROM (SELECT * FROM cte) sub
-                // The inline subquery "(SELECT * FROM cte)" needs cte in scope, but
-                // planning this visibility isn't a reference - the actual reference
-                // happens when the inline subquery's FROM clause resolves "cte".
-                let cte_table = plan_cte(
-                    idx,
-                    cte_definitions,
-                    &base_outer_query_refs_for_subquery,
-                    resolver,
-                    program,
-                    connection,
-                    false,
-                )?;
-                outer_query_refs_for_subquery.push(OuterQueryReference {
-                    identifier: cte_def.name.clone(),
-                    internal_id: cte_table.internal_id,
-                    table: cte_table.table,
-                    using_dedup_hidden_cols: ColumnMask::default(),
-                    col_used_mask: ColumnUsedMask::default(),
-                    cte_select: Some(cte_def.select.clone()),
-                    cte_explicit_columns: cte_def.explicit_columns.clone(),
-                    cte_id: Some(cte_def.cte_id),
-                    cte_definition_only: false,
-                    rowid_referenced: false,
-                    scope_depth: 0,
-                });
-            }
-
-            let subplan = prepare_select_plan(
+            let subplan = plan_from_clause_subquery(
                 subselect,
                 resolver,
                 program,
-                &outer_query_refs_for_subquery,
-                QueryDestination::placeholder_for_subquery(),
+                table_references,
+                cte_definitions,
                 connection,
             )?;
-            match &subplan {
-                Plan::Select(_) | Plan::CompoundSelect { .. } => {}
-                Plan::Delete(_) | Plan::Update(_) => {
-                    crate::bail_parse_error!(
-                        "DELETE/UPDATE queries are not supported in FROM clause subqueries"
-                    );
-                }
-            }
             let cur_table_index = table_references.joined_tables().len();
             let identifier = maybe_alias
                 .map(|a| normalize_ident(a.name().as_str()))
@@ -805,10 +1103,184 @@ fn parse_from_clause_table(
             None, // table-valued functions don't support INDEXED BY
             connection,
         ),
-        ast::SelectTable::Sub(..) => {
-            crate::bail_parse_error!("Parenthesized FROM clause subqueries are not supported")
+        ast::SelectTable::Sub(from_clause, maybe_alias) => {
+            // `(t)` is just `t`.
+            if from_clause.joins.is_empty() && maybe_alias.is_none() {
+                return parse_from_clause_table(
+                    *from_clause.select,
+                    resolver,
+                    program,
+                    table_references,
+                    vtab_predicates,
+                    cte_definitions,
+                    connection,
+                );
+            }
+            parse_join_group(
+                from_clause,
+                maybe_alias,
+                resolver,
+                program,
+                table_references,
+                cte_definitions,
+                connection,
+            )
+        }
+    }
+}
+
+/// Plans a FROM clause subquery, making the CTEs of the enclosing WITH clause visible to it.
+fn plan_from_clause_subquery(
+    subselect: Select,
+    resolver: &Resolver,
+    program: &mut ProgramBuilder,
+    table_references: &TableReferences,
+    cte_definitions: &[CteDefinition],
+    connection: &Arc<crate::Connection>,
+) -> Result<Plan> {
+    // For inline subqueries, we plan all CTEs once and pass them as outer_query_refs.
+    // This allows the subquery to reference CTEs defined in the parent's WITH clause.
+    let mut outer_query_refs_for_subquery = table_references.outer_query_refs().to_vec();
+    let base_outer_query_refs_for_subquery =
+        base_outer_refs_for_cte_planning(table_references.outer_query_refs(), cte_definitions);
+    for (idx, cte_def) in cte_definitions.iter().enumerate() {
+        // Check if this CTE has already been planned and is in outer_query_refs.
+        // This avoids exponential re-planning when CTEs have transitive dependencies.
+        if outer_query_refs_for_subquery
+            .iter()
+            .any(|r| r.identifier == cte_def.name)
+        {
+            continue;
+        }
+        // Plan each CTE so it's visible to this inline subquery's FROM clause.
+        // Example: WITH cte AS (...) SELECT * FROM (SELECT * FROM cte) sub
+        // The inline subquery "(SELECT * FROM cte)" needs cte in scope, but
+        // planning this visibility isn't a reference - the actual reference
+        // happens when the inline subquery's FROM clause resolves "cte".
+        let cte_table = plan_cte(
+            idx,
+            cte_definitions,
+            &base_outer_query_refs_for_subquery,
+            resolver,
+            program,
+            connection,
+            false,
+        )?;
+        outer_query_refs_for_subquery.push(OuterQueryReference {
+            identifier: cte_def.name.clone(),
+            internal_id: cte_table.internal_id,
+            table: cte_table.table,
+            using_dedup_hidden_cols: ColumnMask::default(),
+            col_used_mask: ColumnUsedMask::default(),
+            cte_select: Some(cte_def.select.clone()),
+            cte_explicit_columns: cte_def.explicit_columns.clone(),
+            cte_id: Some(cte_def.cte_id),
+            cte_definition_only: false,
+            rowid_referenced: false,
+            scope_depth: 0,
+        });
+    }
+
+    let subplan = prepare_select_plan(
+        subselect,
+        resolver,
+        program,
+        &outer_query_refs_for_subquery,
+        QueryDestination::placeholder_for_subquery(),
+        connection,
+    )?;
+    match &subplan {
+        Plan::Select(_) | Plan::CompoundSelect { .. } => {}
+        Plan::Delete(_) | Plan::Update(_) => {
+            crate::bail_parse_error!(
+                "DELETE/UPDATE queries are not supported in FROM clause subqueries"
+            );
         }
     }
+    Ok(subplan)
+}
+
+/// Plans a parenthesized join group that cannot be spliced into the enclosing
+/// FROM clause, e.g. the right-hand side of `a LEFT JOIN (b JOIN c ON ...) ON ...`.
+///
+/// Like SQLite, the group becomes a `SELECT * FROM <group>` subquery, so that it is
+/// joined to the rest of the FROM clause as a single unit and outer-join semantics
+/// apply to the group as a whole. The tables inside the group remain addressable by
+/// name through [crate::schema::FromClauseSubquery::nested_from].
+fn parse_join_group(
+    from_clause: FromClause,
+    maybe_alias: Option<As>,
+    resolver: &Resolver,
+    program: &mut ProgramBuilder,
+    table_references: &mut TableReferences,
+    cte_definitions: &[CteDefinition],
+    connection: &Arc<crate::Connection>,
+) -> Result<()> {
+    let subselect = Select {
+        with: None,
+        body: ast::SelectBody {
+            select: ast::OneSelect::Select {
+                distinctness: None,
+                columns: vec![ast::ResultColumn::Star],
+                from: Some(from_clause),
+                where_clause: None,
+                group_by: None,
+                window_clause: vec![],
+            },
+            compounds: vec![],
+        },
+        order_by: vec![],
+        limit: None,
+    };
+    let subplan = plan_from_clause_subquery(
+        subselect,
+        resolver,
+        program,
+        table_references,
+        cte_definitions,
+        connection,
+    )?;
+    let Plan::Select(select_plan) = &subplan else {
+        unreachable!("a parenthesized join group is planned as a simple SELECT");
+    };
+    let nested_from = select_plan
+        .result_columns
+        .iter()
+        .map(|rc| match &rc.expr {
+            Expr::Column { table, column, .. } => select_plan
+                .table_references
+                .find_joined_table_by_internal_id(*table)
+                .map(|t| match &t.table {
+                    Table::FromClauseSubquery(subquery) => subquery
+                        .nested_from
+                        .as_ref()
+                        .and_then(|tables| tables.get(*column).cloned())
+                        .unwrap_or_else(|| t.identifier.clone()),
+                    _ => t.identifier.clone(),
+                })
+                .unwrap_or_default(),
+            _ => String::new(),
+        })
+        .collect();
+
+    let cur_table_index = table_references.joined_tables().len();
+    let identifier = maybe_alias
+        .map(|a| normalize_ident(a.name().as_str()))
+        .unwrap_or_else(|| format!("(subquery-{cur_table_index})"));
+    let mut joined_table = JoinedTable::new_subquery_from_plan(
+        identifier,
+        subplan,
+        None,
+        program.table_reference_counter.next(),
+        None,  // A join group has no explicit column list
+        None,  // nor a CTE identity
+        false, // nor a materialize hint
+    )?;
+    if let Table::FromClauseSubquery(subquery) = &mut joined_table.table {
+        Arc::make_mut(subquery).nested_from = Some(nested_from);
+    }
+    table_references.add_joined_table(joined_table);
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -863,8 +1335,10 @@ fn parse_table(
         return Ok(());
     }
 
-    // A non-recursive CTE's body cannot reference its own name. The CTE name
-    // shadows any same-named schema object, but without RECURSIVE it's circular.
+    // A CTE whose body references its own name is planned as a recursive CTE when the
+    // body is an anchor/recursive compound, RECURSIVE keyword or not. Reaching this point
+    // means the self-reference sits in a body that is not such a compound. The CTE name
+    // shadows any same-named schema object, so the reference is circular.
     if program.is_cte_being_defined(&normalized_qualified_name) {
         crate::bail_parse_error!("circular reference: {}", table_name.as_str());
     }
@@ -881,23 +1355,39 @@ fn parse_table(
         let cte_explicit_columns = outer_ref.cte_explicit_columns.clone();
         let cte_id = outer_ref.cte_id;
         let outer_table = outer_ref.table.clone();
-        let materialize_hint = match &outer_table {
-            Table::FromClauseSubquery(subquery) => subquery.materialize_hint(),
-            _ => false,
+        let (materialize_hint, recursive) = match &outer_table {
+            Table::FromClauseSubquery(subquery) => {
+                (subquery.materialize_hint(), subquery.is_recursive())
+            }
+            _ => (false, false),
         };
 
         if let Some(cte_ast) = cte_select {
             // Re-plan the CTE from its original AST to get fresh internal_ids.
             // This prevents cursor key collisions when the same CTE is
             // referenced multiple times in the same scope.
-            let cte_plan = prepare_select_plan(
-                cte_ast,
-                resolver,
-                program,
-                table_references.outer_query_refs(),
-                QueryDestination::placeholder_for_subquery(),
-                connection,
-            )?;
+            let (cte_plan, recursive_cte) = if recursive {
+                let (cte_plan, recursive_cte) = plan_recursive_cte(
+                    &normalized_qualified_name,
+                    cte_ast,
+                    &cte_explicit_columns,
+                    table_references.outer_query_refs(),
+                    resolver,
+                    program,
+                    connection,
+                )?;
+                (cte_plan, Some(recursive_cte))
+            } else {
+                let cte_plan = prepare_select_plan(
+                    cte_ast,
+                    resolver,
+                    program,
+                    table_references.outer_query_refs(),
+                    QueryDestination::placeholder_for_subquery(),
+                    connection,
+                )?;
+                (cte_plan, None)
+            };
             let explicit_cols = if cte_explicit_columns.is_empty() {
                 None
             } else {
@@ -927,6 +1417,9 @@ fn parse_table(
                 cte_id,
                 materialize_hint,
             )?;
+            if let Some(recursive_cte) = recursive_cte {
+                attach_recursive_cte(&mut jt, recursive_cte);
+            }
             if let Some(alias) = alias {
                 jt.identifier = alias;
             }
@@ -1220,10 +1713,6 @@ pub fn parse_from(
     let mut cte_definitions: Vec<CteDefinition> = vec![];
 
     if let Some(with) = with {
-        if with.recursive {
-            crate::bail_parse_error!("Recursive CTEs are not yet supported");
-        }
-
         for (idx, cte) in with.ctes.into_iter().enumerate() {
             // Normalize explicit column names
             let explicit_columns: Vec<String> = cte
@@ -1249,16 +1738,26 @@ pub fn parse_from(
                 .filter(|&i| referenced_tables.contains(&cte_definitions[i].name))
                 .collect();
 
-            // AS MATERIALIZED forces materialization; AS NOT MATERIALIZED prevents it
-            let materialize_hint = cte.materialized == Materialized::Yes;
+            // AS MATERIALIZED forces materialization; AS NOT MATERIALIZED prevents it.
+            // A data-modifying CTE has already run and left its RETURNING rows in an
+            // ephemeral table, so every reference reads that table.
+            let materialize_hint = cte.materialized == Materialized::Yes || cte.dml.is_some();
+            // As in SQLite, a CTE is recursive because it refers to itself; the
+            // RECURSIVE keyword is accepted but not required.
+            let recursive = cte.dml.is_none() && referenced_tables.contains(&cte_name_normalized);
+            let cte_id = match cte.dml {
+                Some(_) => dml_cte_id(program, &cte_name_normalized)?,
+                None => program.alloc_cte_id(),
+            };
 
             cte_definitions.push(CteDefinition {
-                cte_id: program.alloc_cte_id(),
+                cte_id,
                 name: cte_name_normalized,
                 select: cte.select,
                 explicit_columns,
                 referenced_cte_indices,
                 materialize_hint,
+                recursive,
             });
         }
 
@@ -1302,30 +1801,72 @@ pub fn parse_from(
 
     // Process FROM clause if present
     if let Some(from_owned) = from {
-        let select_owned = from_owned.select;
-        let joins_owned = from_owned.joins;
-        parse_from_clause_table(
-            *select_owned,
+        parse_from_clause_items(
+            from_owned,
             resolver,
             program,
-            table_references,
-            vtab_predicates,
             &cte_definitions,
+            out_where_clause,
+            vtab_predicates,
+            table_references,
             connection,
         )?;
+    }
 
-        for join in joins_owned.into_iter() {
-            parse_join(
-                join,
-                resolver,
-                program,
-                &cte_definitions,
-                out_where_clause,
-                vtab_predicates,
-                table_references,
-                connection,
-            )?;
-        }
+    Ok(())
+}
+
+/// Adds the tables of `from` and their join constraints to `table_references`.
+///
+/// Also used for parenthesized join groups that can be spliced into the enclosing
+/// FROM clause without changing its meaning.
+#[allow(clippy::too_many_arguments)]
+fn parse_from_clause_items(
+    from: FromClause,
+    resolver: &Resolver,
+    program: &mut ProgramBuilder,
+    cte_definitions: &[CteDefinition],
+    out_where_clause: &mut Vec<WhereTerm>,
+    vtab_predicates: &mut Vec<Expr>,
+    table_references: &mut TableReferences,
+    connection: &Arc<crate::Connection>,
+) -> Result<()> {
+    let FromClause { select, joins } = from;
+    match *select {
+        // Joins associate left to right, so a leading group such as the one in
+        // `(a LEFT JOIN b ON ...) JOIN c ON ...` means the same without the parentheses.
+        ast::SelectTable::Sub(from_clause, None) => parse_from_clause_items(
+            from_clause,
+            resolver,
+            program,
+            cte_definitions,
+            out_where_clause,
+            vtab_predicates,
+            table_references,
+            connection,
+        )?,
+        table => parse_from_clause_table(
+            table,
+            resolver,
+            program,
+            table_references,
+            vtab_predicates,
+            cte_definitions,
+            connection,
+        )?,
+    }
+
+    for join in joins.into_iter() {
+        parse_join(
+            join,
+            resolver,
+            program,
+            cte_definitions,
+            out_where_clause,
+            vtab_predicates,
+            table_references,
+            connection,
+        )?;
     }
 
     Ok(())
@@ -1660,8 +2201,55 @@ fn parse_join(
         constraint,
     } = join;
 
+    // An inner join against a parenthesized join group is the same as joining each
+    // table of the group, so the group is spliced in and the join optimizer stays free
+    // to reorder its tables. Any other join against a group is planned as a subquery.
+    let is_plain_inner = match join_operator {
+        ast::JoinOperator::Comma | ast::JoinOperator::TypedJoin(None) => true,
+        ast::JoinOperator::TypedJoin(Some(join_type)) => join_type == JoinType::INNER,
+    };
+    let table = match *table {
+        ast::SelectTable::Sub(from_clause, None)
+            if is_plain_inner
+                && !from_clause.joins.is_empty()
+                && !matches!(constraint, Some(ast::JoinConstraint::Using(_))) =>
+        {
+            let first_group_table = table_references.joined_tables().len();
+            parse_from_clause_items(
+                from_clause,
+                resolver,
+                program,
+                cte_definitions,
+                out_where_clause,
+                vtab_predicates,
+                table_references,
+                connection,
+            )?;
+            table_references.joined_tables_mut()[first_group_table].join_info = Some(JoinInfo {
+                join_type: PlanJoinType::Inner,
+                using: vec![],
+                no_reorder: false,
+            });
+            if let Some(ast::JoinConstraint::On(expr)) = constraint {
+                let start_idx = out_where_clause.len();
+                break_predicate_at_and_boundaries(&expr, out_where_clause);
+                for predicate in out_where_clause[start_idx..].iter_mut() {
+                    bind_and_rewrite_expr(
+                        &mut predicate.expr,
+                        Some(table_references),
+                        None,
+                        resolver,
+                        BindingBehavior::TryResultColumnsFirst,
+                    )?;
+                }
+            }
+            return Ok(());
+        }
+        table => table,
+    };
+
     parse_from_clause_table(
-        table.as_ref().clone(),
+        table,
         resolver,
         program,
         table_references,
@@ -1997,3 +2585,32 @@ pub fn parse_limit(
     }
     Ok((Some(limit.expr), limit.offset))
 }
+
+/// Binds the ORDER BY terms of an UPDATE or DELETE against the tables it reads.
+///
+/// As in SQLite builds with SQLITE_ENABLE_UPDATE_DELETE_LIMIT, ORDER BY only picks
+/// which rows a LIMIT keeps, so it is rejected without one.
+#[allow(clippy::type_complexity)]
+pub fn parse_dml_order_by(
+    order_by: Vec<ast::SortedColumn>,
+    has_limit: bool,
+    stmt_type: &str,
+    table_references: &mut TableReferences,
+    resolver: &Resolver,
+) -> Result<Vec<(Box<Expr>, ast::SortOrder, Option<ast::NullsOrder>)>> {
+    if !order_by.is_empty() && !has_limit {
+        crate::bail_parse_error!("ORDER BY without LIMIT on {}", stmt_type);
+    }
+    let mut key = Vec::with_capacity(order_by.len());
+    for mut o in order_by {
+        bind_and_rewrite_expr(
+            &mut o.expr,
+            Some(table_references),
+            None,
+            resolver,
+            BindingBehavior::ResultColumnsNotAllowed,
+        )?;
+        key.push((o.expr, o.order.unwrap_or(ast::SortOrder::Asc), o.nulls));
+    }
+    Ok(key)
+}
@@ -20,6 +20,7 @@ use crate::storage::pager::Pager;
 use crate::storage::sqlite3_ondisk::CacheSize;
 use crate::storage::wal::CheckpointMode;
 use crate::translate::emitter::{Resolver, TransactionMode};
+use crate::translate::fkeys::emit_foreign_key_check;
 use crate::translate::plan::BitSet;
 use crate::util::{normalize_ident, parse_signed_number, parse_string, IOExt as _};
 use crate::vdbe::builder::{ProgramBuilder, ProgramBuilderOpts};
@@ -247,11 +248,13 @@ pub fn translate_pragma(
             PragmaName::IndexInfo
             | PragmaName::IndexXinfo
             | PragmaName::IndexList
+            | PragmaName::ForeignKeyCheck
             | PragmaName::ForeignKeyList
             | PragmaName::TableList
             | PragmaName::TableInfo
             | PragmaName::TableXinfo
             | PragmaName::IntegrityCheck
+            | PragmaName::IncrementalVacuum
             | PragmaName::DatabaseList
             | PragmaName::QuickCheck => query_pragma(
                 pragma,
@@ -320,6 +323,14 @@ fn update_pragma(
             _ => false,
         })
     };
+    // Mirrors sqlite3Atoi(): anything that is not a number reads as 0.
+    let parse_pragma_integer = |expr: &ast::Expr| -> i64 {
+        match parse_signed_number(expr) {
+            Ok(Value::Numeric(Numeric::Integer(i))) => i,
+            Ok(Value::Numeric(Numeric::Float(f))) => f64::from(f) as i64,
+            _ => 0,
+        }
+    };
     match pragma {
         PragmaName::ApplicationId => {
             let data = parse_signed_number(&value)?;
@@ -443,6 +454,55 @@ fn update_pragma(
             schema_was_explicit,
             program,
         ),
+        PragmaName::WalAutocheckpoint => {
+            // Like SQLite, zero, a negative or a non-numeric value turns auto-checkpointing off.
+            let frames = u32::try_from(parse_pragma_integer(&value).max(0)).unwrap_or(u32::MAX);
+            // The threshold is a connection setting: it covers every attached database,
+            // and a schema name is accepted but ignored, as in SQLite.
+            connection.get_pager().set_wal_autocheckpoint(frames);
+            connection.with_all_attached_pagers_with_index(|pagers| {
+                for (_, attached_pager) in pagers {
+                    attached_pager.set_wal_autocheckpoint(frames);
+                }
+            });
+            query_pragma(
+                PragmaName::WalAutocheckpoint,
+                resolver,
+                None,
+                pager,
+                connection,
+                database_id,
+                schema_was_explicit,
+                program,
+            )
+        }
+        PragmaName::JournalSizeLimit => {
+            // Any negative value means "no limit".
+            let limit = parse_pragma_integer(&value).max(-1);
+            if schema_was_explicit {
+                connection
+                    .get_pager_from_database_index(&database_id)?
+                    .set_journal_size_limit(limit);
+            } else {
+                // Without a schema name the limit applies to every open database, as in SQLite.
+                connection.get_pager().set_journal_size_limit(limit);
+                connection.with_all_attached_pagers_with_index(|pagers| {
+                    for (_, attached_pager) in pagers {
+                        attached_pager.set_journal_size_limit(limit);
+                    }
+                });
+            }
+            query_pragma(
+                PragmaName::JournalSizeLimit,
+                resolver,
+                None,
+                pager,
+                connection,
+                database_id,
+                schema_was_explicit,
+                program,
+            )
+        }
         PragmaName::MaxPageCount => {
             let data = parse_signed_number(&value)?;
             let max_page_count_value = match data {
@@ -585,6 +645,9 @@ fn update_pragma(
             Ok(TransactionMode::None)
         }
         PragmaName::IntegrityCheck => unreachable!("integrity_check cannot be set"),
+        PragmaName::IncrementalVacuum => {
+            unreachable!("incremental_vacuum is handled by query_pragma")
+        }
         PragmaName::QuickCheck => unreachable!("quick_check cannot be set"),
         PragmaName::CaptureDataChangesConn | PragmaName::UnstableCaptureDataChangesConn => {
             let value = parse_string(&value)?;
@@ -611,6 +674,7 @@ fn update_pragma(
         PragmaName::IndexInfo => unreachable!("index_info cannot be set"),
         PragmaName::IndexXinfo => unreachable!("index_xinfo cannot be set"),
         PragmaName::IndexList => unreachable!("index_list cannot be set"),
+        PragmaName::ForeignKeyCheck => unreachable!("foreign_key_check cannot be set"),
         PragmaName::ForeignKeyList => unreachable!("foreign_key_list cannot be set"),
         PragmaName::TableList => unreachable!("table_list cannot be set"),
         PragmaName::QueryOnly => query_pragma(
@@ -689,6 +753,11 @@ fn update_pragma(
             connection.set_foreign_keys_enabled(enabled);
             Ok(TransactionMode::None)
         }
+        PragmaName::DeferForeignKeys => {
+            let enabled = parse_pragma_enabled(&value);
+            connection.set_defer_foreign_keys_enabled(enabled);
+            Ok(TransactionMode::None)
+        }
         PragmaName::IAmADummy | PragmaName::RequireWhere => {
             let enabled = parse_pragma_enabled(&value);
             connection.set_dml_require_where(enabled);
@@ -969,6 +1038,22 @@ fn query_pragma(
             program.add_pragma_result_column(pragma.to_string());
             Ok(TransactionMode::Read)
         }
+        PragmaName::WalAutocheckpoint => {
+            let frames = connection.get_pager().get_wal_autocheckpoint();
+            program.emit_int(frames as i64, register);
+            program.emit_result_row(register, 1);
+            program.add_pragma_result_column(pragma.to_string());
+            Ok(TransactionMode::None)
+        }
+        PragmaName::JournalSizeLimit => {
+            let limit = connection
+                .get_pager_from_database_index(&database_id)?
+                .get_journal_size_limit();
+            program.emit_int(limit, register);
+            program.emit_result_row(register, 1);
+            program.add_pragma_result_column(pragma.to_string());
+            Ok(TransactionMode::None)
+        }
         PragmaName::MaxPageCount => {
             program.emit_insn(Insn::MaxPgcnt {
                 db: database_id,
@@ -1004,7 +1089,7 @@ fn query_pragma(
                         .find(|idx| idx.name.eq_ignore_ascii_case(&index_name));
 
                     if let Some(index) = index {
-                        for (seqno, col) in index.columns.iter().enumerate() {
+                        for (seqno, col) in index.key_columns().iter().enumerate() {
                             program.emit_int(seqno as i64, base_reg);
                             program.emit_int(col.pos_in_table as i64, base_reg + 1);
                             program.emit_string8(col.name.clone(), base_reg + 2);
@@ -1057,7 +1142,9 @@ fn query_pragma(
                             program.emit_string8(col.name.clone(), base_reg + 2);
                             program.emit_int(desc as i64, base_reg + 3);
                             program.emit_string8(coll, base_reg + 4);
-                            program.emit_int(1, base_reg + 5); // key column
+                            // the PRIMARY KEY suffix of WITHOUT ROWID indexes is not part of the key
+                            let is_key = seqno < index.key_columns().len();
+                            program.emit_int(is_key as i64, base_reg + 5);
                             program.emit_result_row(base_reg, 6);
                         }
 
@@ -1113,8 +1200,11 @@ fn query_pragma(
 
                         for (seq, index) in schema.get_indices(&table_name).enumerate() {
                             let origin = if index.name.starts_with("sqlite_autoindex_") {
-                                let idx_cols: Vec<&str> =
-                                    index.columns.iter().map(|c| c.name.as_str()).collect();
+                                let idx_cols: Vec<&str> = index
+                                    .key_columns()
+                                    .iter()
+                                    .map(|c| c.name.as_str())
+                                    .collect();
                                 if idx_cols.len() == pk_cols.len()
                                     && idx_cols
                                         .iter()
@@ -1146,6 +1236,40 @@ fn query_pragma(
             }
             Ok(TransactionMode::None)
         }
+        PragmaName::ForeignKeyCheck => {
+            let table_name = match value {
+                Some(ast::Expr::Name(name)) => Some(name),
+                _ => None,
+            };
+            let check_database_id = match &table_name {
+                Some(table_name) => resolve_table_pragma_database_id(
+                    resolver,
+                    database_id,
+                    schema_was_explicit,
+                    table_name.as_str(),
+                )?,
+                None => database_id,
+            };
+            emit_foreign_key_check(
+                program,
+                resolver,
+                check_database_id,
+                table_name.as_ref().map(|name| name.as_str()),
+            )?;
+
+            let pragma_meta = pragma_for(&pragma);
+            for col_name in pragma_meta.columns.iter() {
+                program.add_pragma_result_column(col_name.to_string());
+            }
+            if check_database_id == database_id {
+                Ok(TransactionMode::Read)
+            } else {
+                let schema_cookie = resolver.with_schema(check_database_id, |s| s.schema_version);
+                program.begin_read_on_database(check_database_id, schema_cookie);
+                program.begin_read_operation();
+                Ok(TransactionMode::None)
+            }
+        }
         PragmaName::ForeignKeyList => {
             let table_name = match value {
                 Some(ast::Expr::Name(name)) => Some(name),
@@ -1385,6 +1509,36 @@ fn query_pragma(
             translate_quick_check(schema, program, resolver, database_id, max_errors)?;
             Ok(TransactionMode::Read)
         }
+        PragmaName::IncrementalVacuum => {
+            if connection.mvcc_enabled() {
+                bail_parse_error!("incremental_vacuum is not supported in MVCC mode");
+            }
+            // Like SQLite, a missing, zero, negative or non-integer page count reclaims every
+            // free page.
+            let limit = match value.as_ref().map(parse_signed_number) {
+                Some(Ok(Value::Numeric(Numeric::Integer(n)))) if n > 0 => n.min(i32::MAX as i64),
+                _ => i32::MAX as i64,
+            };
+            program.emit_int(limit, register);
+            let loop_start = program.allocate_label();
+            let end_label = program.allocate_label();
+            program.preassign_label_to_next_insn(loop_start);
+            program.emit_insn(Insn::IncrVacuum {
+                db: database_id,
+                target_pc: end_label,
+            });
+            program.emit_insn(Insn::AddImm {
+                register,
+                value: -1,
+            });
+            program.emit_insn(Insn::IfPos {
+                reg: register,
+                target_pc: loop_start,
+                decrement_by: 0,
+            });
+            program.preassign_label_to_next_insn(end_label);
+            Ok(TransactionMode::Write)
+        }
         PragmaName::CaptureDataChangesConn | PragmaName::UnstableCaptureDataChangesConn => {
             let pragma = pragma_for(&pragma);
             let second_column = program.alloc_register();
@@ -1515,6 +1669,14 @@ fn query_pragma(
             program.add_pragma_result_column(pragma.to_string());
             Ok(TransactionMode::None)
         }
+        PragmaName::DeferForeignKeys => {
+            let enabled = connection.defer_foreign_keys_enabled();
+            let register = program.alloc_register();
+            program.emit_int(enabled as i64, register);
+            program.emit_result_row(register, 1);
+            program.add_pragma_result_column(pragma.to_string());
+            Ok(TransactionMode::None)
+        }
         PragmaName::IAmADummy | PragmaName::RequireWhere => {
             let register = program.alloc_register();
             let enabled = connection.get_dml_require_where();
@@ -1658,6 +1820,8 @@ fn emit_columns_for_table_info(
             1
         } else if column.is_virtual_generated() {
             2
+        } else if column.is_stored_generated() {
+            3
         } else {
             // normal column
             0
@@ -0,0 +1,466 @@
+use std::sync::Arc;
+
+use turso_parser::ast::SortOrder;
+
+use crate::{
+    emit_explain,
+    schema::{BTreeCharacteristics, BTreeTable, Column, Index, IndexColumn},
+    translate::{
+        compound_select::{emit_program_for_compound_select, set_compound_plan_destinations},
+        emitter::{
+            select::{emit_materialized_build_inputs, emit_query},
+            TranslateCtx,
+        },
+        expr::{translate_expr_no_constant_opt, NoConstantOptReason},
+        plan::{EphemeralRowidMode, Plan, QueryDestination, RecursiveCte},
+        result_row::emit_columns_to_destination,
+    },
+    vdbe::{
+        builder::{CursorType, MaterializedCteInfo, ProgramBuilder},
+        insn::{to_u16, IdxInsertFlags, InsertFlags, Insn},
+        CursorID,
+    },
+    Result,
+};
+
+/// The queue that holds rows waiting to be fed to the recursive arms.
+enum RecursiveQueue {
+    /// Rows are processed in insertion order.
+    Fifo { cursor_id: CursorID },
+    /// Rows are processed in ORDER BY order. Arms write into `staging_cursor_id`,
+    /// whose rows are then moved into the index `cursor_id`, keyed by the ORDER BY
+    /// columns followed by a sequence number that keeps ties in insertion order.
+    Ordered {
+        cursor_id: CursorID,
+        index: Arc<Index>,
+        staging_cursor_id: CursorID,
+    },
+}
+
+/// Emit the rows of a `WITH RECURSIVE` CTE into `destination`.
+///
+/// `anchor` is the non-recursive part of the CTE body and `recursive` holds the
+/// recursive arms. The generated code follows SQLite's algorithm:
+///
+/// 1. Run the anchor, pushing its rows onto a queue.
+/// 2. While the queue is not empty, pop a row, emit it to `destination`,
+///    make it the only row of the "current row" table and run every recursive
+///    arm, pushing their rows onto the queue.
+///
+/// With UNION (rather than UNION ALL) rows that were already popped once are
+/// discarded. LIMIT stops the loop once enough rows were emitted; OFFSET rows are
+/// not emitted but still drive the recursion.
+///
+/// Returns the first register of the emitted row.
+pub fn emit_recursive_cte(
+    program: &mut ProgramBuilder,
+    t_ctx: &TranslateCtx,
+    anchor: &Plan,
+    recursive: &RecursiveCte,
+    columns: &[Column],
+    destination: &QueryDestination,
+) -> Result<usize> {
+    let num_columns = columns.len();
+    let reg_row = program.alloc_registers(num_columns);
+    let label_end = program.allocate_label();
+
+    // The counters are decremented while the loop runs, so they must be
+    // initialized every time the CTE is (re)computed rather than hoisted.
+    let reg_limit = match &recursive.limit {
+        Some(limit) => {
+            let reg = program.alloc_register();
+            translate_expr_no_constant_opt(
+                program,
+                None,
+                limit,
+                reg,
+                &t_ctx.resolver,
+                NoConstantOptReason::RegisterReuse,
+            )?;
+            program.emit_insn(Insn::MustBeInt { reg });
+            program.emit_insn(Insn::IfNot {
+                reg,
+                target_pc: label_end,
+                jump_if_null: false,
+            });
+            Some(reg)
+        }
+        None => None,
+    };
+    let reg_offset = match &recursive.offset {
+        Some(offset) => {
+            let reg = program.alloc_register();
+            translate_expr_no_constant_opt(
+                program,
+                None,
+                offset,
+                reg,
+                &t_ctx.resolver,
+                NoConstantOptReason::RegisterReuse,
+            )?;
+            program.emit_insn(Insn::MustBeInt { reg });
+            Some(reg)
+        }
+        None => None,
+    };
+
+    let row_table = Arc::new(BTreeTable::new(
+        0,
+        String::new(),
+        vec![],
+        columns.to_vec(),
+        BTreeCharacteristics::HAS_ROWID,
+        vec![],
+        vec![],
+        vec![],
+        None,
+    ));
+
+    // The recursive arms read the current row through a materialized CTE
+    // reference, so it must be registered before they are emitted.
+    let current_cursor_id = program.alloc_cursor_id(CursorType::BTreeTable(row_table.clone()));
+    program.emit_insn(Insn::OpenEphemeral {
+        cursor_id: current_cursor_id,
+        is_table: true,
+    });
+    program.register_materialized_cte(
+        recursive.current_row_cte_id,
+        MaterializedCteInfo {
+            cursor_id: current_cursor_id,
+            table: row_table.clone(),
+            num_columns,
+        },
+    );
+
+    let queue = if recursive.order_by.is_empty() {
+        let cursor_id = program.alloc_cursor_id(CursorType::BTreeTable(row_table.clone()));
+        program.emit_insn(Insn::OpenEphemeral {
+            cursor_id,
+            is_table: true,
+        });
+        RecursiveQueue::Fifo { cursor_id }
+    } else {
+        let mut index_columns: Vec<IndexColumn> = recursive
+            .order_by
+            .iter()
+            .map(|(col_idx, order)| IndexColumn {
+                name: columns[*col_idx].name.clone().unwrap_or_default(),
+                order: *order,
+                pos_in_table: *col_idx,
+                default: None,
+                collation: columns[*col_idx].collation_opt(),
+                expr: None,
+            })
+            .collect();
+        index_columns.push(IndexColumn {
+            name: String::new(),
+            order: SortOrder::Asc,
+            pos_in_table: num_columns,
+            default: None,
+            collation: None,
+            expr: None,
+        });
+        index_columns.extend(columns.iter().enumerate().map(|(i, column)| IndexColumn {
+            name: column.name.clone().unwrap_or_default(),
+            order: SortOrder::Asc,
+            pos_in_table: i,
+            default: None,
+            collation: column.collation_opt(),
+            expr: None,
+        }));
+        let index = Arc::new(Index {
+            columns: index_columns,
+            name: "recursive_queue".to_string(),
+            root_page: 0,
+            ephemeral: true,
+            table_name: String::new(),
+            unique: false,
+            has_rowid: false,
+            where_clause: None,
+            index_method: None,
+            pk_suffix_start: None,
+            on_conflict: None,
+        });
+        let cursor_id = program.alloc_cursor_id(CursorType::BTreeIndex(index.clone()));
+        program.emit_insn(Insn::OpenEphemeral {
+            cursor_id,
+            is_table: false,
+        });
+        let staging_cursor_id = program.alloc_cursor_id(CursorType::BTreeTable(row_table.clone()));
+        program.emit_insn(Insn::OpenEphemeral {
+            cursor_id: staging_cursor_id,
+            is_table: true,
+        });
+        RecursiveQueue::Ordered {
+            cursor_id,
+            index,
+            staging_cursor_id,
+        }
+    };
+    let arm_destination = QueryDestination::EphemeralTable {
+        cursor_id: match &queue {
+            RecursiveQueue::Fifo { cursor_id } => *cursor_id,
+            RecursiveQueue::Ordered {
+                staging_cursor_id, ..
+            } => *staging_cursor_id,
+        },
+        table: row_table.clone(),
+        rowid_mode: EphemeralRowidMode::Auto,
+    };
+
+    let distinct = if recursive.distinct {
+        let index = Arc::new(Index {
+            columns: columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| IndexColumn {
+                    name: column.name.clone().unwrap_or_default(),
+                    order: SortOrder::Asc,
+                    pos_in_table: i,
+                    default: None,
+                    collation: column.collation_opt(),
+                    expr: None,
+                })
+                .collect(),
+            name: "recursive_distinct".to_string(),
+            root_page: 0,
+            ephemeral: true,
+            table_name: String::new(),
+            unique: false,
+            has_rowid: false,
+            where_clause: None,
+            index_method: None,
+            pk_suffix_start: None,
+            on_conflict: None,
+        });
+        let cursor_id = program.alloc_cursor_id(CursorType::BTreeIndex(index.clone()));
+        program.emit_insn(Insn::OpenEphemeral {
+            cursor_id,
+            is_table: false,
+        });
+        Some((cursor_id, index))
+    } else {
+        None
+    };
+
+    emit_explain!(program, true, "SETUP".to_owned());
+    emit_plan_into(program, t_ctx, anchor.clone(), &arm_destination)?;
+    program.pop_current_parent_explain();
+    emit_queue_transfer(program, &queue, num_columns, &recursive.order_by);
+
+    // Pop the next row off the queue.
+    let label_loop = program.allocate_label();
+    program.preassign_label_to_next_insn(label_loop);
+    let (queue_cursor_id, first_column) = match &queue {
+        RecursiveQueue::Fifo { cursor_id } => (*cursor_id, 0),
+        RecursiveQueue::Ordered { cursor_id, .. } => (*cursor_id, recursive.order_by.len() + 1),
+    };
+    program.emit_insn(Insn::Rewind {
+        cursor_id: queue_cursor_id,
+        pc_if_empty: label_end,
+    });
+    for i in 0..num_columns {
+        program.emit_insn(Insn::Column {
+            cursor_id: queue_cursor_id,
+            column: first_column + i,
+            dest: reg_row + i,
+            default: None,
+        });
+    }
+    program.emit_insn(Insn::Delete {
+        cursor_id: queue_cursor_id,
+        table_name: String::new(),
+        is_part_of_update: true,
+    });
+
+    // UNION: a row that was already processed is dropped along with everything
+    // it would have produced.
+    if let Some((distinct_cursor_id, distinct_index)) = &distinct {
+        program.emit_insn(Insn::Found {
+            cursor_id: *distinct_cursor_id,
+            target_pc: label_loop,
+            record_reg: reg_row,
+            num_regs: num_columns,
+        });
+        let record_reg = program.alloc_register();
+        program.emit_insn(Insn::MakeRecord {
+            start_reg: to_u16(reg_row),
+            count: to_u16(num_columns),
+            dest_reg: to_u16(record_reg),
+            index_name: Some(distinct_index.name.clone()),
+            affinity_str: None,
+        });
+        program.emit_insn(Insn::IdxInsert {
+            cursor_id: *distinct_cursor_id,
+            record_reg,
+            unpacked_start: None,
+            unpacked_count: None,
+            flags: IdxInsertFlags::new().no_op_duplicate(),
+        });
+    }
+
+    // Make the popped row the only row of the current-row table.
+    program.emit_insn(Insn::ResetSorter {
+        cursor_id: current_cursor_id,
+    });
+    let record_reg = program.alloc_register();
+    let rowid_reg = program.alloc_register();
+    program.emit_insn(Insn::MakeRecord {
+        start_reg: to_u16(reg_row),
+        count: to_u16(num_columns),
+        dest_reg: to_u16(record_reg),
+        index_name: Some(row_table.name.clone()),
+        affinity_str: None,
+    });
+    program.emit_insn(Insn::NewRowid {
+        cursor: current_cursor_id,
+        rowid_reg,
+        prev_largest_reg: 0,
+    });
+    program.emit_insn(Insn::Insert {
+        cursor: current_cursor_id,
+        key_reg: rowid_reg,
+        record_reg,
+        flag: InsertFlags::new().is_ephemeral_table_insert(),
+        table_name: row_table.name.clone(),
+    });
+
+    // Emit the row, unless it is still covered by OFFSET.
+    let label_skip_output = program.allocate_label();
+    if let Some(reg_offset) = reg_offset {
+        program.emit_insn(Insn::IfPos {
+            reg: reg_offset,
+            target_pc: label_skip_output,
+            decrement_by: 1,
+        });
+    }
+    emit_columns_to_destination(program, destination, reg_row, num_columns)?;
+    if let Some(reg_limit) = reg_limit {
+        program.emit_insn(Insn::DecrJumpZero {
+            reg: reg_limit,
+            target_pc: label_end,
+        });
+    }
+    program.preassign_label_to_next_insn(label_skip_output);
+
+    emit_explain!(program, true, "RECURSIVE STEP".to_owned());
+    for select in recursive.selects.iter() {
+        emit_plan_into(
+            program,
+            t_ctx,
+            Plan::Select(Box::new(select.clone())),
+            &arm_destination,
+        )?;
+    }
+    program.pop_current_parent_explain();
+    emit_queue_transfer(program, &queue, num_columns, &recursive.order_by);
+    program.emit_insn(Insn::Goto {
+        target_pc: label_loop,
+    });
+
+    program.preassign_label_to_next_insn(label_end);
+    Ok(reg_row)
+}
+
+/// Emit one part of the CTE body so that its rows are written to `destination`.
+fn emit_plan_into(
+    program: &mut ProgramBuilder,
+    t_ctx: &TranslateCtx,
+    plan: Plan,
+    destination: &QueryDestination,
+) -> Result<()> {
+    match plan {
+        Plan::Select(mut select_plan) => {
+            select_plan.query_destination = destination.clone();
+            let mut ctx = TranslateCtx::new(
+                program,
+                t_ctx.resolver.fork(),
+                select_plan.joined_tables().len(),
+                t_ctx.unsafe_testing,
+            );
+            ctx.materialized_build_inputs =
+                emit_materialized_build_inputs(program, &ctx.resolver, &mut select_plan)?;
+            emit_query(program, &mut select_plan, &mut ctx)?;
+        }
+        mut compound @ Plan::CompoundSelect { .. } => {
+            set_compound_plan_destinations(&mut compound, destination);
+            let resolver = t_ctx.resolver.fork();
+            emit_program_for_compound_select(program, &resolver, compound)?;
+        }
+        Plan::Delete(_) | Plan::Update(_) => {
+            unreachable!("DELETE/UPDATE plans cannot be part of a recursive CTE")
+        }
+    }
+    Ok(())
+}
+
+/// For an ordered queue, move the rows written to the staging table into the
+/// queue index, keyed by the ORDER BY columns and a sequence number.
+fn emit_queue_transfer(
+    program: &mut ProgramBuilder,
+    queue: &RecursiveQueue,
+    num_columns: usize,
+    order_by: &[(usize, SortOrder)],
+) {
+    let RecursiveQueue::Ordered {
+        cursor_id,
+        index,
+        staging_cursor_id,
+    } = queue
+    else {
+        return;
+    };
+    let num_keys = order_by.len();
+    let reg_record_start = program.alloc_registers(num_keys + 1 + num_columns);
+    let reg_columns = reg_record_start + num_keys + 1;
+    let record_reg = program.alloc_register();
+    let label_done = program.allocate_label();
+    let label_next_row = program.allocate_label();
+
+    program.emit_insn(Insn::Rewind {
+        cursor_id: *staging_cursor_id,
+        pc_if_empty: label_done,
+    });
+    program.preassign_label_to_next_insn(label_next_row);
+    for i in 0..num_columns {
+        program.emit_insn(Insn::Column {
+            cursor_id: *staging_cursor_id,
+            column: i,
+            dest: reg_columns + i,
+            default: None,
+        });
+    }
+    for (i, (col_idx, _)) in order_by.iter().enumerate() {
+        program.emit_insn(Insn::Copy {
+            src_reg: reg_columns + col_idx,
+            dst_reg: reg_record_start + i,
+            extra_amount: 0,
+        });
+    }
+    program.emit_insn(Insn::Sequence {
+        cursor_id: *cursor_id,
+        target_reg: reg_record_start + num_keys,
+    });
+    program.emit_insn(Insn::MakeRecord {
+        start_reg: to_u16(reg_record_start),
+        count: to_u16(num_keys + 1 + num_columns),
+        dest_reg: to_u16(record_reg),
+        index_name: Some(index.name.clone()),
+        affinity_str: None,
+    });
+    program.emit_insn(Insn::IdxInsert {
+        cursor_id: *cursor_id,
+        record_reg,
+        unpacked_start: None,
+        unpacked_count: None,
+        flags: IdxInsertFlags::new().no_op_duplicate(),
+    });
+    program.emit_insn(Insn::Next {
+        cursor_id: *staging_cursor_id,
+        pc_if_next: label_next_row,
+    });
+    program.preassign_label_to_next_insn(label_done);
+    program.emit_insn(Insn::ResetSorter {
+        cursor_id: *staging_cursor_id,
+    });
+}
@@ -14,9 +14,9 @@ use crate::translate::emitter::{
     emit_cdc_autocommit_commit, emit_cdc_full_record, emit_cdc_insns, prepare_cdc_if_necessary,
     OperationMode, Resolver,
 };
-use crate::translate::expr::{walk_expr, WalkControl};
+use crate::translate::expr::{get_expr_affinity, walk_expr, WalkControl};
 use crate::translate::fkeys::emit_fk_drop_table_check;
-use crate::translate::plan::{Plan, QueryDestination};
+use crate::translate::plan::{Plan, QueryDestination, SelectPlan, TableReferences};
 use crate::translate::planner::ROWID_STRS;
 use crate::translate::select::{emit_select_plan, prepare_select_plan};
 use crate::translate::{ProgramBuilder, ProgramBuilderOpts};
@@ -24,6 +24,7 @@ use crate::util::{
     escape_sql_string_literal, normalize_ident, quote_identifier,
     PRIMARY_KEY_AUTOMATIC_INDEX_NAME_PREFIX,
 };
+use crate::vdbe::affinity::Affinity;
 use crate::vdbe::builder::CursorType;
 use crate::vdbe::insn::{
     to_u16, {CmpInsFlags, Cookie, InsertFlags, Insn, RegisterOrLiteral},
@@ -871,11 +872,6 @@ fn validate(
             if table.primary_key_columns.is_empty() {
                 bail_parse_error!("PRIMARY KEY missing on table {}", table_name);
             }
-            if table.unique_sets.iter().any(|us| !us.is_primary_key) {
-                bail_parse_error!(
-                    "secondary UNIQUE constraints on WITHOUT ROWID tables are not supported"
-                );
-            }
         }
     }
     Ok(())
@@ -887,6 +883,91 @@ struct CtasInfo {
     schema_sql: String,
 }
 
+/// Bits returned by [ctas_expr_data_types], mirroring `sqlite3ExprDataType()`.
+const CTAS_DATA_TYPE_NUMERIC: u8 = 0x01;
+const CTAS_DATA_TYPE_TEXT: u8 = 0x02;
+const CTAS_DATA_TYPE_BLOB: u8 = 0x04;
+const CTAS_DATA_TYPE_ANY: u8 = 0x07;
+
+/// Returns the set of storage classes (besides NULL) that `expr` may produce.
+fn ctas_expr_data_types(expr: &ast::Expr, tables: &TableReferences) -> u8 {
+    match expr {
+        ast::Expr::Collate(expr, _) | ast::Expr::Unary(ast::UnaryOperator::Positive, expr) => {
+            ctas_expr_data_types(expr, tables)
+        }
+        ast::Expr::Parenthesized(exprs) if exprs.len() == 1 => {
+            ctas_expr_data_types(&exprs[0], tables)
+        }
+        ast::Expr::Literal(ast::Literal::Null) => 0,
+        ast::Expr::Literal(ast::Literal::String(_)) => CTAS_DATA_TYPE_TEXT,
+        ast::Expr::Literal(ast::Literal::Blob(_)) => CTAS_DATA_TYPE_BLOB,
+        ast::Expr::Binary(_, ast::Operator::Concat, _) => CTAS_DATA_TYPE_TEXT | CTAS_DATA_TYPE_BLOB,
+        ast::Expr::Variable(_)
+        | ast::Expr::FunctionCall { .. }
+        | ast::Expr::FunctionCallStar { .. } => CTAS_DATA_TYPE_ANY,
+        ast::Expr::Column { .. }
+        | ast::Expr::RowId { .. }
+        | ast::Expr::Cast { .. }
+        | ast::Expr::SubqueryResult { .. } => match get_expr_affinity(expr, Some(tables), None) {
+            Affinity::Numeric | Affinity::Integer | Affinity::Real => {
+                CTAS_DATA_TYPE_NUMERIC | CTAS_DATA_TYPE_BLOB
+            }
+            Affinity::Text => CTAS_DATA_TYPE_TEXT | CTAS_DATA_TYPE_BLOB,
+            Affinity::Blob => CTAS_DATA_TYPE_ANY,
+        },
+        ast::Expr::Case {
+            when_then_pairs,
+            else_expr,
+            ..
+        } => when_then_pairs
+            .iter()
+            .map(|(_, then)| then.as_ref())
+            .chain(else_expr.as_deref())
+            .fold(0, |types, expr| types | ctas_expr_data_types(expr, tables)),
+        _ => CTAS_DATA_TYPE_NUMERIC,
+    }
+}
+
+/// Affinity of the `i`-th column of a CTAS table, following `sqlite3SubqueryColumnTypes()`.
+///
+/// `arms` are the SELECTs of a compound statement from left to right. The leftmost arm with
+/// an affinity decides the column affinity, which falls back to BLOB when a later arm may
+/// produce values of a conflicting storage class.
+fn ctas_column_affinity(arms: &[&SelectPlan], i: usize) -> Affinity {
+    let column_of = |arm: &SelectPlan| &arm.result_columns[i].expr;
+    let affinity_of =
+        |arm: &SelectPlan| get_expr_affinity(column_of(arm), Some(&arm.table_references), None);
+    let types_of = |arm: &SelectPlan| ctas_expr_data_types(column_of(arm), &arm.table_references);
+
+    let mut affinity = affinity_of(arms[0]);
+    let mut data_types = 0;
+    let mut pos = 0;
+    while affinity == Affinity::Blob && pos + 1 < arms.len() {
+        data_types |= types_of(arms[pos]);
+        pos += 1;
+        affinity = affinity_of(arms[pos]);
+    }
+    if affinity == Affinity::Blob || arms.len() == 1 {
+        return affinity;
+    }
+    for arm in &arms[pos + 1..] {
+        data_types |= types_of(arm);
+    }
+    let numeric = matches!(
+        affinity,
+        Affinity::Numeric | Affinity::Integer | Affinity::Real
+    );
+    if (affinity == Affinity::Text && data_types & CTAS_DATA_TYPE_NUMERIC != 0)
+        || (numeric && data_types & CTAS_DATA_TYPE_TEXT != 0)
+    {
+        Affinity::Blob
+    } else if numeric && matches!(column_of(arms[0]), ast::Expr::Cast { .. }) {
+        Affinity::Numeric
+    } else {
+        affinity
+    }
+}
+
 /// Pre-plan the SELECT to derive the schema for a CTAS table.
 /// Returns the plan (for reuse in emission) along with the complete schema SQL and column definitions.
 fn derive_ctas_schema(
@@ -907,13 +988,18 @@ fn derive_ctas_schema(
 
     // For compound selects, use the leftmost select's columns for naming (matching SQLite).
     // The planner guarantees `left` is always non-empty in a CompoundSelect.
-    let (result_columns, table_refs) = match &plan {
-        Plan::Select(sp) => (&sp.result_columns, &sp.table_references),
-        Plan::CompoundSelect { left, .. } => {
-            (&left[0].0.result_columns, &left[0].0.table_references)
-        }
+    let arms: Vec<&SelectPlan> = match &plan {
+        Plan::Select(sp) => vec![sp],
+        Plan::CompoundSelect {
+            left, right_most, ..
+        } => left
+            .iter()
+            .map(|(sp, _)| sp)
+            .chain(std::iter::once(right_most.as_ref()))
+            .collect(),
         _ => bail_parse_error!("unexpected plan type for CTAS"),
     };
+    let (result_columns, table_refs) = (&arms[0].result_columns, &arms[0].table_references);
 
     // Collect names first, then deduplicate using SQLite's :N suffix convention.
     let mut names: Vec<String> = result_columns
@@ -931,17 +1017,27 @@ fn derive_ctas_schema(
         *count += 1;
     }
 
-    let mut sql_parts = Vec::with_capacity(result_columns.len());
+    // SQLite spreads the column list over several lines once the statement gets long.
+    let ident_len = |name: &str| name.len() + name.matches('"').count() + 2;
+    let layout_len =
+        names.iter().map(|name| ident_len(name) + 5).sum::<usize>() + ident_len(table_name);
+    let (separator_first, separator, end) = if layout_len < 50 {
+        ("", ",", ")")
+    } else {
+        ("\n  ", ",\n  ", "\n)")
+    };
+
+    let mut schema_sql = format!("CREATE TABLE {}(", quote_identifier(table_name));
     let mut col_defs = Vec::with_capacity(result_columns.len());
 
-    for (col, name) in result_columns.iter().zip(names) {
-        let ty = col.declared_type(table_refs);
+    for (i, name) in names.into_iter().enumerate() {
+        let ty = ctas_column_affinity(&arms, i).short_type_name();
 
-        let quoted = quote_identifier(&name);
-        if ty.is_empty() {
-            sql_parts.push(quoted);
-        } else {
-            sql_parts.push(format!("{quoted} {ty}"));
+        schema_sql.push_str(if i == 0 { separator_first } else { separator });
+        schema_sql.push_str(&quote_identifier(&name));
+        if !ty.is_empty() {
+            schema_sql.push(' ');
+            schema_sql.push_str(ty);
         }
 
         let col_type = if ty.is_empty() {
@@ -959,12 +1055,9 @@ fn derive_ctas_schema(
             constraints: vec![],
         });
     }
+    schema_sql.push_str(end);
 
-    let info = CtasInfo {
-        plan,
-        schema_sql: format!("CREATE TABLE {table_name}({})", sql_parts.join(",")),
-    };
-    Ok((info, col_defs))
+    Ok((CtasInfo { plan, schema_sql }, col_defs))
 }
 
 /// Emit bytecode to populate a newly-created CTAS table from the SELECT.
@@ -1100,7 +1193,7 @@ pub fn translate_create_table(
         ast::CreateTableBody::AsSelect(select) => {
             let (info, col_defs) = derive_ctas_schema(
                 select,
-                &tbl_name.name.as_ident(),
+                tbl_name.name.as_str(),
                 resolver,
                 program,
                 connection,
@@ -1339,7 +1432,7 @@ pub fn translate_create_table(
 
     let index_regs = collect_autoindexes(&body, program, &normalized_tbl_name)?;
     if let Some(index_regs) = index_regs.as_ref() {
-        for index_reg in index_regs.iter() {
+        for (index_reg, _) in index_regs.iter() {
             program.emit_insn(Insn::CreateBtree {
                 db: database_id,
                 root: *index_reg,
@@ -1371,11 +1464,9 @@ pub fn translate_create_table(
     )?;
 
     if let Some(index_regs) = index_regs {
-        for (idx, index_reg) in index_regs.into_iter().enumerate() {
+        for (index_reg, index_number) in index_regs {
             let index_name = format!(
-                "{PRIMARY_KEY_AUTOMATIC_INDEX_NAME_PREFIX}{}_{}",
-                normalized_tbl_name,
-                idx + 1
+                "{PRIMARY_KEY_AUTOMATIC_INDEX_NAME_PREFIX}{normalized_tbl_name}_{index_number}"
             );
             emit_schema_entry(
                 program,
@@ -1539,26 +1630,31 @@ pub fn emit_schema_entry(
 }
 
 /// Check if an automatic PRIMARY KEY index is required for the table.
-/// If so, create a register for the index root page and return it.
+/// If so, create a register for the index root page and return it, along with the number
+/// that goes into the `sqlite_autoindex_<table>_<n>` name of the index.
 ///
 /// An automatic PRIMARY KEY index is not required if:
 /// - The table has no PRIMARY KEY
 /// - The table has a single-column PRIMARY KEY whose typename is _exactly_ "INTEGER" e.g. not "INT".
 ///   In this case, the PRIMARY KEY column becomes an alias for the rowid.
+/// - The table is a WITHOUT ROWID table, whose btree is keyed by the PRIMARY KEY. Like SQLite,
+///   the PRIMARY KEY still takes up a number in the names of the other automatic indexes.
 ///
 /// Otherwise, an automatic PRIMARY KEY index is required.
 fn collect_autoindexes(
     body: &ast::CreateTableBody,
     program: &mut ProgramBuilder,
     tbl_name: &str,
-) -> Result<Option<Vec<usize>>> {
+) -> Result<Option<Vec<(usize, usize)>>> {
     let table = create_table(tbl_name, body, 0)?;
 
-    let mut regs: Vec<usize> = Vec::new();
+    let mut regs: Vec<(usize, usize)> = Vec::new();
+    let mut index_number = 0;
 
     // include UNIQUE singles, include PK single only if not rowid alias
     for us in table.unique_sets.iter().filter(|us| us.columns.len() == 1) {
         if us.is_primary_key && !table.has_rowid {
+            index_number += 1;
             continue;
         }
         let (col_name, _sort) = us.columns.first().unwrap();
@@ -1574,15 +1670,17 @@ fn collect_autoindexes(
         };
 
         if needs_index {
-            regs.push(program.alloc_register());
+            index_number += 1;
+            regs.push((program.alloc_register(), index_number));
         }
     }
 
     for _us in table.unique_sets.iter().filter(|us| us.columns.len() > 1) {
+        index_number += 1;
         if !table.has_rowid && _us.is_primary_key {
             continue;
         }
-        regs.push(program.alloc_register());
+        regs.push((program.alloc_register(), index_number));
     }
     if regs.is_empty() {
         Ok(None)
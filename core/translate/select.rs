@@ -373,7 +373,7 @@ fn prepare_one_select_plan(
                 trace_stack!("bind_windows");
                 for window_def in window_clause.iter() {
                     let name = normalize_ident(window_def.name.as_str());
-                    let mut window = Window::new(Some(name), &window_def.window)?;
+                    let mut window = Window::new(Some(name), &window_def.window, &windows)?;
 
                     for expr in window.partition_by.iter_mut() {
                         bind_and_rewrite_expr(
@@ -459,7 +459,54 @@ fn prepare_one_select_plan(
                                 .find(|t| t.identifier == name_normalized);
 
                             if referenced_table.is_none() {
-                                crate::bail_parse_error!("no such table: {}", name.as_str());
+                                // `b.*` where `b` is inside a parenthesized join group that
+                                // was planned as a subquery expands to b's columns of the group.
+                                let group =
+                                    plan.table_references.joined_tables_mut().iter_mut().find(
+                                        |t| match &t.table {
+                                            Table::FromClauseSubquery(subquery) => {
+                                                subquery.nested_from_contains(&name_normalized)
+                                            }
+                                            _ => false,
+                                        },
+                                    );
+                                let Some(group) = group else {
+                                    crate::bail_parse_error!("no such table: {}", name.as_str());
+                                };
+                                let Table::FromClauseSubquery(subquery) = &group.table else {
+                                    unreachable!();
+                                };
+                                let subquery = subquery.clone();
+                                let nested_from =
+                                    subquery.nested_from.as_deref().unwrap_or_default();
+                                for (idx, (column, _)) in subquery
+                                    .columns
+                                    .iter()
+                                    .zip(nested_from)
+                                    .enumerate()
+                                    .filter(|(_, (_, t))| **t == name_normalized)
+                                {
+                                    let alias = column.name.as_ref().map(|col_name| {
+                                        if long_names {
+                                            format!("{name_normalized}.{col_name}")
+                                        } else {
+                                            col_name.clone()
+                                        }
+                                    });
+                                    plan.result_columns.push(ResultSetColumn {
+                                        expr: ast::Expr::Column {
+                                            database: None,
+                                            table: group.internal_id,
+                                            column: idx,
+                                            is_rowid_alias: column.is_rowid_alias(),
+                                        },
+                                        alias,
+                                        implicit_column_name: None,
+                                        contains_aggregates: false,
+                                    });
+                                    group.mark_column_used(idx);
+                                }
+                                continue;
                             }
                             let table = referenced_table.unwrap();
                             let num_columns = table.columns().len();
@@ -1125,7 +1172,7 @@ fn replace_column_number_with_copy_of_column_expr(
 /// ORDER BY in compound selects can reference columns by:
 /// 1. Numeric position (1-based): ORDER BY 1
 /// 2. Column name or alias from any constituent SELECT: ORDER BY name
-fn resolve_compound_order_by_expr(
+pub(crate) fn resolve_compound_order_by_expr(
     expr: &ast::Expr,
     all_plans: &[&SelectPlan],
     term_number: usize,
@@ -8,7 +8,7 @@ use crate::{
     schema::{BTreeCharacteristics, BTreeTable, Column, Index, IndexColumn, Table},
     translate::{
         collate::get_collseq_from_expr,
-        compound_select::emit_program_for_compound_select,
+        compound_select::{emit_program_for_compound_select, set_compound_plan_destinations},
         emitter::select::{
             emit_materialized_build_inputs, emit_program_for_select,
             emit_program_for_select_with_resolver, emit_query,
@@ -18,11 +18,12 @@ use crate::{
         },
         optimizer::optimize_select_plan,
         plan::{
-            plan_has_outer_scope_dependency, plan_is_correlated,
-            select_plan_has_outer_scope_dependency, ColumnUsedMask, EvalAt, JoinOrderMember,
-            NonFromClauseSubquery, OuterQueryReference, Plan, SetOperation, SubqueryEvalPhase,
-            SubqueryOrigin, SubqueryPosition, SubqueryState, TableReferences, WhereTerm,
+            plan_has_outer_scope_dependency, plan_is_correlated, ColumnUsedMask, EvalAt,
+            HashJoinType, JoinOrderMember, NonFromClauseSubquery, OuterQueryReference, Plan,
+            RecursiveCte, SetOperation, SubqueryEvalPhase, SubqueryOrigin, SubqueryPosition,
+            SubqueryRerun, SubqueryState, TableReferences, WhereTerm,
         },
+        recursive_cte::emit_recursive_cte,
         select::prepare_select_plan,
     },
     types::Value,
@@ -181,7 +182,7 @@ pub(crate) fn mark_shared_cte_materialization_requirements(
             let from_clause_subquery = Arc::make_mut(from_clause_subquery);
             let shared_materialization = from_clause_subquery.cte_id().is_some_and(|cte_id| {
                 shared_ref_counts.get(&cte_id).copied().unwrap_or_default() > 1
-                    && !plan_has_outer_scope_dependency(&from_clause_subquery.plan)
+                    && !from_clause_subquery.has_outer_scope_dependency()
             });
             from_clause_subquery.set_shared_materialization(shared_materialization);
             if let Some(cte_id) = from_clause_subquery.cte_id() {
@@ -189,15 +190,21 @@ pub(crate) fn mark_shared_cte_materialization_requirements(
                     cte_id,
                     shared_ref_count = shared_ref_counts.get(&cte_id).copied().unwrap_or_default(),
                     shared_materialization,
-                    outer_scope_dependency = plan_has_outer_scope_dependency(
-                        &from_clause_subquery.plan,
-                    ),
+                    outer_scope_dependency = from_clause_subquery.has_outer_scope_dependency(),
                     contains_nested_correlation = plan_is_correlated(&from_clause_subquery.plan),
                     identifier = %table.identifier,
                     "annotated CTE materialization requirements"
                 );
             }
             annotate_plan(from_clause_subquery.plan.as_mut());
+            if let Some(recursive) = from_clause_subquery.recursive.as_mut() {
+                for select_plan in recursive.selects.iter_mut() {
+                    mark_shared_cte_materialization_requirements(
+                        &mut select_plan.table_references,
+                        &mut select_plan.non_from_clause_subqueries,
+                    );
+                }
+            }
         }
     }
 
@@ -270,7 +277,7 @@ pub fn plan_subqueries_from_select_plan(
                 connection,
                 SubqueryPosition::Having,
                 SubqueryOrigin::SelectHaving,
-                !group_by.exprs.is_empty(),
+                SubqueryPosition::Having.allow_correlated(),
             )?;
         }
     }
@@ -637,19 +644,14 @@ fn get_subquery_parser<'a>(
                     QueryDestination::ExistsSubqueryResult { result_reg },
                     connection,
                 )?;
-                let Plan::Select(mut plan) = plan else {
-                    crate::bail_parse_error!(
-                        "compound SELECT queries not supported yet in WHERE clause subqueries"
-                    );
-                };
-                optimize_select_plan(&mut plan, resolver)?;
-                let correlated = select_plan_has_outer_scope_dependency(&plan);
+                let plan = optimize_subquery_plan(plan, resolver)?;
+                let correlated = plan_has_outer_scope_dependency(&plan);
                 handle_unsupported_correlation(correlated, position, allow_correlated)?;
                 out_subqueries.push(NonFromClauseSubquery {
                     internal_id: subquery_id,
                     query_type: subquery_type,
                     state: SubqueryState::Unevaluated {
-                        plan: Some(Box::new(Plan::Select(plan))),
+                        plan: Some(Box::new(plan)),
                     },
                     correlated,
                     origin,
@@ -689,20 +691,21 @@ fn get_subquery_parser<'a>(
                     QueryDestination::Unset,
                     connection,
                 )?;
-                let Plan::Select(mut plan) = plan else {
-                    crate::bail_parse_error!(
-                        "compound SELECT queries not supported yet in WHERE clause subqueries"
-                    );
-                };
-                optimize_select_plan(&mut plan, resolver)?;
-                let reg_count = plan.result_columns.len();
+                let mut plan = optimize_subquery_plan(plan, resolver)?;
+                let reg_count = plan.select_result_columns().len();
                 let reg_start = program.alloc_registers(reg_count);
 
                 if reg_count == 1 {
-                    if let Some(result_col) = plan.result_columns.first() {
+                    // A compound SELECT takes its column affinity from the left-most SELECT.
+                    let leftmost = match &plan {
+                        Plan::CompoundSelect { left, .. } => &left[0].0,
+                        Plan::Select(select_plan) => select_plan.as_ref(),
+                        _ => unreachable!("prepare_select_plan cannot return Delete/Update"),
+                    };
+                    if let Some(result_col) = leftmost.result_columns.first() {
                         let affinity = get_expr_affinity_info(
                             &result_col.expr,
-                            Some(&plan.table_references),
+                            Some(&leftmost.table_references),
                             None,
                         );
                         resolver
@@ -712,14 +715,22 @@ fn get_subquery_parser<'a>(
                     }
                 }
 
-                plan.query_destination = QueryDestination::RowValueSubqueryResult {
-                    result_reg_start: reg_start,
-                    num_regs: reg_count,
-                };
+                set_compound_plan_destinations(
+                    &mut plan,
+                    &QueryDestination::RowValueSubqueryResult {
+                        result_reg_start: reg_start,
+                        num_regs: reg_count,
+                    },
+                );
 
                 // Only inject LIMIT 1 if there's no existing limit, or the existing limit is > 1,
                 // If LIMIT 0, subquery should return no rows (NULL).
-                let limit = match &plan.limit {
+                let plan_limit = match &mut plan {
+                    Plan::Select(select_plan) => &mut select_plan.limit,
+                    Plan::CompoundSelect { limit, .. } => limit,
+                    _ => unreachable!("prepare_select_plan cannot return Delete/Update"),
+                };
+                let limit = match plan_limit.as_deref() {
                     Some(expr) => match parse_signed_number(expr) {
                         Ok(Value::Numeric(Numeric::Integer(v))) => !(0..=1).contains(&v),
                         _ => true,
@@ -729,7 +740,7 @@ fn get_subquery_parser<'a>(
                 if limit {
                     // RowValue subqueries are satisfied after at most 1 row has been returned,
                     // as they are used in comparisons with a scalar or a tuple of scalars like (x,y) = (SELECT ...) or x = (SELECT ...).
-                    plan.limit = Some(Box::new(ast::Expr::Literal(ast::Literal::Numeric(
+                    *plan_limit = Some(Box::new(ast::Expr::Literal(ast::Literal::Numeric(
                         "1".to_string(),
                     ))));
                 }
@@ -750,7 +761,7 @@ fn get_subquery_parser<'a>(
                 *result_reg_start = reg_start;
                 *num_regs = reg_count;
 
-                let correlated = select_plan_has_outer_scope_dependency(&plan);
+                let correlated = plan_has_outer_scope_dependency(&plan);
                 handle_unsupported_correlation(correlated, position, allow_correlated)?;
 
                 out_subqueries.push(NonFromClauseSubquery {
@@ -760,7 +771,7 @@ fn get_subquery_parser<'a>(
                         num_regs: reg_count,
                     },
                     state: SubqueryState::Unevaluated {
-                        plan: Some(Box::new(Plan::Select(plan))),
+                        plan: Some(Box::new(plan)),
                     },
                     correlated,
                     origin,
@@ -789,32 +800,7 @@ fn get_subquery_parser<'a>(
                     QueryDestination::Unset,
                     connection,
                 )?;
-                let mut plan = match plan {
-                    Plan::Select(mut select_plan) => {
-                        optimize_select_plan(&mut select_plan, resolver)?;
-                        Plan::Select(select_plan)
-                    }
-                    Plan::CompoundSelect {
-                        mut left,
-                        mut right_most,
-                        limit,
-                        offset,
-                        order_by,
-                    } => {
-                        optimize_select_plan(&mut right_most, resolver)?;
-                        for (select_plan, _) in left.iter_mut() {
-                            optimize_select_plan(select_plan, resolver)?;
-                        }
-                        Plan::CompoundSelect {
-                            left,
-                            right_most,
-                            limit,
-                            offset,
-                            order_by,
-                        }
-                    }
-                    _ => unreachable!("prepare_select_plan cannot return Delete/Update"),
-                };
+                let mut plan = optimize_subquery_plan(plan, resolver)?;
                 let result_columns = plan.select_result_columns();
                 let table_references = plan.select_table_references();
                 // e.g. (x,y) IN (SELECT ...)
@@ -881,6 +867,7 @@ fn get_subquery_parser<'a>(
                     unique: false,
                     where_clause: None,
                     index_method: None,
+                    pk_suffix_start: None,
                     on_conflict: None,
                 });
 
@@ -927,6 +914,36 @@ fn get_subquery_parser<'a>(
     }
 }
 
+/// Optimize a (possibly compound) SELECT plan of a non-FROM-clause subquery.
+fn optimize_subquery_plan(plan: Plan, resolver: &Resolver) -> Result<Plan> {
+    match plan {
+        Plan::Select(mut select_plan) => {
+            optimize_select_plan(&mut select_plan, resolver)?;
+            Ok(Plan::Select(select_plan))
+        }
+        Plan::CompoundSelect {
+            mut left,
+            mut right_most,
+            limit,
+            offset,
+            order_by,
+        } => {
+            optimize_select_plan(&mut right_most, resolver)?;
+            for (select_plan, _) in left.iter_mut() {
+                optimize_select_plan(select_plan, resolver)?;
+            }
+            Ok(Plan::CompoundSelect {
+                left,
+                right_most,
+                limit,
+                offset,
+                order_by,
+            })
+        }
+        _ => unreachable!("prepare_select_plan cannot return Delete/Update"),
+    }
+}
+
 /// Recollect all aggregates after subquery planning.
 ///
 /// Aggregates are collected during parsing with cloned expressions. When subquery planning
@@ -1010,6 +1027,9 @@ fn update_column_used_masks(
         for joined_table in plan.table_references.joined_tables().iter() {
             if let Table::FromClauseSubquery(from_clause_subquery) = &joined_table.table {
                 propagate_outer_refs_from_plan(table_refs, from_clause_subquery.plan.as_ref())?;
+                for select_plan in from_clause_subquery.recursive_selects() {
+                    propagate_outer_refs_from_select_plan(table_refs, select_plan)?;
+                }
             }
         }
         Ok(())
@@ -1068,6 +1088,23 @@ fn update_column_used_masks(
         let plan = unsafe { &*plan };
         propagate_outer_refs_from_plan(table_refs, plan)?;
     }
+    let recursive_select_plans = table_refs
+        .joined_tables()
+        .iter()
+        .filter_map(|t| match &t.table {
+            Table::FromClauseSubquery(from_clause_subquery) => {
+                Some(from_clause_subquery.recursive_selects())
+            }
+            _ => None,
+        })
+        .flatten()
+        .map(|select_plan| select_plan as *const SelectPlan)
+        .collect::<Vec<_>>();
+    for select_plan in recursive_select_plans {
+        // SAFETY: plans live within table_refs for the duration of this function.
+        let select_plan = unsafe { &*select_plan };
+        propagate_outer_refs_from_select_plan(table_refs, select_plan)?;
+    }
     Ok(())
 }
 
@@ -1154,6 +1191,7 @@ fn pre_materialize_multi_ref_ctes_in_tables(
                             from_clause_subquery.plan.as_mut(),
                             t_ctx,
                             &from_clause_subquery.columns,
+                            from_clause_subquery.recursive.as_deref(),
                         )?;
                     program.register_materialized_cte(
                         cte_id,
@@ -1430,9 +1468,13 @@ pub fn emit_from_clause_subqueries(
             }
 
             let result_columns_start = match execution_mode {
-                FromClauseSubqueryExecutionMode::Coroutine => {
-                    emit_from_clause_subquery(program, from_clause_subquery.plan.as_mut(), t_ctx)?
-                }
+                FromClauseSubqueryExecutionMode::Coroutine => emit_from_clause_subquery(
+                    program,
+                    from_clause_subquery.plan.as_mut(),
+                    t_ctx,
+                    &from_clause_subquery.columns,
+                    from_clause_subquery.recursive.as_deref(),
+                )?,
                 FromClauseSubqueryExecutionMode::MaterializedTable => {
                     let (result_columns_start, cte_cursor_id, cte_table) =
                         emit_materialized_subquery_table(
@@ -1440,6 +1482,7 @@ pub fn emit_from_clause_subqueries(
                             from_clause_subquery.plan.as_mut(),
                             t_ctx,
                             &from_clause_subquery.columns,
+                            from_clause_subquery.recursive.as_deref(),
                         )?;
                     from_clause_subquery.materialized_cursor_id = Some(cte_cursor_id);
                     if let Some(cte_id) = from_clause_subquery.cte_id() {
@@ -1493,6 +1536,8 @@ pub fn emit_from_clause_subquery(
     program: &mut ProgramBuilder,
     plan: &mut Plan,
     t_ctx: &mut TranslateCtx,
+    columns: &[Column],
+    recursive: Option<&RecursiveCte>,
 ) -> Result<usize> {
     let yield_reg = program.alloc_register();
     let coroutine_implementation_start_offset = program.allocate_label();
@@ -1520,6 +1565,18 @@ pub fn emit_from_clause_subquery(
     });
     program.preassign_label_to_next_insn(coroutine_implementation_start_offset);
 
+    if let Some(recursive) = recursive {
+        let destination = QueryDestination::CoroutineYield {
+            yield_reg,
+            coroutine_implementation_start: coroutine_implementation_start_offset,
+        };
+        let result_column_start_reg =
+            emit_recursive_cte(program, t_ctx, plan, recursive, columns, &destination)?;
+        program.emit_insn(Insn::EndCoroutine { yield_reg });
+        program.preassign_label_to_next_insn(subquery_body_end_label);
+        return Ok(result_column_start_reg);
+    }
+
     let result_column_start_reg = match plan {
         Plan::Select(select_plan) => {
             let mut metadata = Box::new(TranslateCtx {
@@ -1663,6 +1720,7 @@ fn emit_materialized_subquery_table(
     plan: &mut Plan,
     t_ctx: &mut TranslateCtx,
     columns: &[Column],
+    recursive: Option<&RecursiveCte>,
 ) -> Result<(usize, CursorID, Arc<BTreeTable>)> {
     use super::plan::EphemeralRowidMode;
 
@@ -1702,6 +1760,16 @@ fn emit_materialized_subquery_table(
         };
     }
 
+    if let Some(recursive) = recursive {
+        let destination = QueryDestination::EphemeralTable {
+            cursor_id,
+            table: ephemeral_table.clone(),
+            rowid_mode: EphemeralRowidMode::Auto,
+        };
+        emit_recursive_cte(program, t_ctx, plan, recursive, columns, &destination)?;
+        return Ok((result_columns_start_reg, cursor_id, ephemeral_table));
+    }
+
     // Emit the subquery - it will insert rows into the ephemeral table
     match plan {
         Plan::Select(select_plan) => {
@@ -1929,6 +1997,26 @@ pub fn emit_non_from_clause_subqueries_for_phase(
             }
         };
 
+        // Correlated subqueries evaluated in a FULL OUTER hash-join probe loop are
+        // wrapped in a subroutine so the unmatched-row paths can re-run them. They
+        // also keep the outer expr cache so build-table columns are read from the
+        // hash payload registers, which every path fills in.
+        let rerunnable = subquery.correlated
+            && matches!(evaluated_at, EvalAt::Loop(loop_idx)
+                if is_full_outer_hash_probe(join_order, table_references, loop_idx));
+        let rerun = if rerunnable {
+            let return_reg = program.alloc_register();
+            program.emit_insn(Insn::BeginSubrtn {
+                dest: return_reg,
+                dest_end: None,
+            });
+            let start = program.allocate_label();
+            program.preassign_label_to_next_insn(start);
+            Some(SubqueryRerun { return_reg, start })
+        } else {
+            None
+        };
+
         let subquery_plan = subquery.consume_plan(evaluated_at);
         emit_non_from_clause_subquery(
             program,
@@ -1936,16 +2024,42 @@ pub fn emit_non_from_clause_subqueries_for_phase(
             *subquery_plan,
             &subquery.query_type,
             subquery.correlated,
-            !matches!(
-                phase,
-                SubqueryEvalPhase::BeforeLoop | SubqueryEvalPhase::Loop(_)
-            ),
+            rerunnable
+                || !matches!(
+                    phase,
+                    SubqueryEvalPhase::BeforeLoop | SubqueryEvalPhase::Loop(_)
+                ),
         )?;
+
+        if let Some(rerun) = rerun {
+            program.emit_insn(Insn::Return {
+                return_reg: rerun.return_reg,
+                can_fallthrough: true,
+            });
+            if let SubqueryState::Evaluated { rerun: slot, .. } = &mut subquery.state {
+                *slot = Some(rerun);
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Returns true if the loop at `loop_idx` is the probe side of a FULL OUTER hash join.
+fn is_full_outer_hash_probe(
+    join_order: &[JoinOrderMember],
+    table_references: Option<&TableReferences>,
+    loop_idx: usize,
+) -> bool {
+    let (Some(member), Some(tables)) = (join_order.get(loop_idx), table_references) else {
+        return false;
+    };
+    matches!(
+        &tables.joined_tables()[member.original_idx].op,
+        Operation::HashJoin(hj) if hj.join_type == HashJoinType::FullOuter
+    )
+}
+
 pub fn emit_non_from_clause_subqueries_for_eval_at(
     program: &mut ProgramBuilder,
     resolver: &Resolver,
@@ -154,22 +154,43 @@ pub fn translate_create_trigger(
         bail_parse_error!("Trigger {} already exists", normalized_trigger_name);
     }
 
-    // Verify the table exists (use the table's database, not the trigger's).
-    let table = resolver.with_schema(target_table_database_id, |s| {
-        s.get_table(&normalized_table_name)
-    });
-    let Some(table) = table else {
-        bail_parse_error!("no such table: {}", normalized_table_name);
-    };
-    if table.virtual_table().is_some() {
-        bail_parse_error!("cannot create triggers on virtual tables");
-    }
-
-    if time
+    let instead_of = time
         .as_ref()
-        .is_some_and(|t| *t == ast::TriggerTime::InsteadOf)
-    {
-        bail_parse_error!("INSTEAD OF triggers are not supported yet");
+        .is_some_and(|t| *t == ast::TriggerTime::InsteadOf);
+
+    // Verify the target exists (use the target's database, not the trigger's).
+    // Views only accept INSTEAD OF triggers, and tables never do.
+    let (table, is_view) = resolver.with_schema(target_table_database_id, |s| {
+        (
+            s.get_table(&normalized_table_name),
+            s.get_view(&normalized_table_name).is_some(),
+        )
+    });
+    if is_view {
+        if !instead_of {
+            let time_str = match time {
+                Some(ast::TriggerTime::After) => "AFTER",
+                _ => "BEFORE",
+            };
+            bail_parse_error!(
+                "cannot create {} trigger on view: {}",
+                time_str,
+                normalized_table_name
+            );
+        }
+    } else {
+        let Some(table) = table else {
+            bail_parse_error!("no such table: {}", normalized_table_name);
+        };
+        if table.virtual_table().is_some() {
+            bail_parse_error!("cannot create triggers on virtual tables");
+        }
+        if instead_of {
+            bail_parse_error!(
+                "cannot create INSTEAD OF trigger on table: {}",
+                normalized_table_name
+            );
+        }
     }
 
     let opts = ProgramBuilderOpts::new(1, 30, 1);
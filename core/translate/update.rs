@@ -6,7 +6,7 @@ use crate::translate::emitter::Resolver;
 use crate::translate::expr::{bind_and_rewrite_expr, BindingBehavior};
 use crate::translate::expression_index::expression_index_column_usage;
 use crate::translate::plan::{ColumnMask, Operation};
-use crate::translate::planner::{parse_limit, ROWID_STRS};
+use crate::translate::planner::{parse_dml_order_by, parse_limit, ROWID_STRS};
 use crate::{
     bail_parse_error,
     schema::{Schema, Table},
@@ -20,13 +20,15 @@ use super::emitter::emit_program;
 use super::expr::process_returning_clause;
 use super::optimizer::optimize_plan;
 use super::plan::{
-    ColumnUsedMask, DmlSafety, JoinedTable, Plan, TableReferences, UpdatePlan, UpdateSetClause,
+    ColumnUsedMask, DmlSafety, DmlSafetyReason, JoinedTable, Plan, TableReferences, UpdatePlan,
+    UpdateSetClause,
 };
 use super::planner::{append_vtab_predicates_to_where_clause, parse_from, parse_where};
 use super::subquery::{
     mark_shared_cte_materialization_requirements, plan_subqueries_from_returning,
     plan_subqueries_from_update_sets, plan_subqueries_from_where_clause,
 };
+use super::view_dml::translate_update_of_view;
 /*
 * Update is simple. By default we scan the table, and for each row, we check the WHERE
 * clause. If it evaluates to true, we build the new record with the updated value and insert.
@@ -62,6 +64,12 @@ pub fn translate_update(
     program: &mut ProgramBuilder,
     connection: &Arc<crate::Connection>,
 ) -> crate::Result<()> {
+    let database_id = resolver.resolve_existing_table_database_id_qualified(&body.tbl_name)?;
+    if let Some(view) =
+        resolver.with_schema(database_id, |s| s.get_view(body.tbl_name.name.as_str()))
+    {
+        return translate_update_of_view(program, resolver, &view, database_id, body, connection);
+    }
     let plan = prepare_and_optimize_update_plan(program, resolver, body, connection, false, None)?;
     let Plan::Update(ref update_plan) = plan else {
         unreachable!("prepare_and_optimize_update_plan must return Plan::Update");
@@ -215,9 +223,6 @@ fn validate_update(
     {
         crate::bail_parse_error!("table {} may not be modified", table_name);
     }
-    if !body.order_by.is_empty() {
-        bail_parse_error!("ORDER BY is not supported in UPDATE");
-    }
     // Check if this is a materialized view
     if schema.is_materialized_view(table_name) {
         bail_parse_error!("cannot modify materialized view {}", table_name);
@@ -246,7 +251,6 @@ fn prepare_update_plan(
     is_internal_schema_change: bool,
 ) -> crate::Result<UpdatePlan> {
     let database_id = resolver.resolve_existing_table_database_id_qualified(&body.tbl_name)?;
-    let schema = resolver.schema();
     let target_name = &body.tbl_name.name;
     let table = match resolver.with_schema(database_id, |s| s.get_table(target_name.as_str())) {
         Some(table) => table,
@@ -258,18 +262,17 @@ fn prepare_update_plan(
             body.tbl_name.name.as_str()
         );
     }
-    if table.btree().is_some_and(|bt| !bt.has_rowid) {
-        bail_parse_error!("UPDATE of WITHOUT ROWID tables is not supported");
-    }
     let schema_cookie = resolver.with_schema(database_id, |s| s.schema_version);
     program.begin_write_on_database(database_id, schema_cookie);
-    validate_update(
-        schema,
-        &body,
-        target_name.as_str(),
-        is_internal_schema_change,
-        connection,
-    )?;
+    resolver.with_schema(database_id, |schema| {
+        validate_update(
+            schema,
+            &body,
+            target_name.as_str(),
+            is_internal_schema_change,
+            connection,
+        )
+    })?;
 
     // Extract WITH, OR conflict clause, and INDEXED BY before borrowing body mutably
     let with = body.with.take();
@@ -421,6 +424,22 @@ fn prepare_update_plan(
         resolver,
     )?;
 
+    let order_by = parse_dml_order_by(
+        std::mem::take(&mut body.order_by),
+        body.limit.is_some(),
+        "UPDATE",
+        &mut read_scope_tables,
+        resolver,
+    )?;
+    let mut safety = DmlSafety::default();
+    if !order_by.is_empty() {
+        // The sorted write set is collected by rowid, which virtual tables don't expose here.
+        if table.virtual_table().is_some() {
+            bail_parse_error!("ORDER BY is not supported in UPDATE of a virtual table");
+        }
+        safety.require(DmlSafetyReason::OrderByLimit);
+    }
+
     let (limit, offset) = body
         .limit
         .map_or(Ok((None, None)), |l| parse_limit(l, resolver))?;
@@ -447,6 +466,7 @@ fn prepare_update_plan(
         set_clauses,
         where_clause,
         returning: (!result_columns.is_empty()).then_some(result_columns),
+        order_by,
         limit,
         offset,
         contains_constant_false_condition: false,
@@ -454,7 +474,7 @@ fn prepare_update_plan(
         write_set_plan: None,
         cdc_update_alter_statement: None,
         non_from_clause_subqueries,
-        safety: DmlSafety::default(),
+        safety,
     })
 }
 
@@ -484,12 +504,16 @@ fn collect_update_set_clauses(
                     table.columns()[*idx].ensure_not_generated("UPDATE", col_name.as_str())?;
                     *idx
                 }
-                None if ROWID_STRS.iter().any(|s| s.eq_ignore_ascii_case(&ident)) => table
-                    .columns()
-                    .iter()
-                    .enumerate()
-                    .find(|(_i, c)| c.is_rowid_alias())
-                    .map_or(ROWID_SENTINEL, |(idx, _col)| idx),
+                None if ROWID_STRS.iter().any(|s| s.eq_ignore_ascii_case(&ident))
+                    && table.btree().is_none_or(|bt| bt.has_rowid) =>
+                {
+                    table
+                        .columns()
+                        .iter()
+                        .enumerate()
+                        .find(|(_i, c)| c.is_rowid_alias())
+                        .map_or(ROWID_SENTINEL, |(idx, _col)| idx)
+                }
                 None => crate::bail_parse_error!("no such column: {}.{}", table_name, col_name),
             };
 
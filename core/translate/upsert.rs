@@ -4,10 +4,12 @@ use std::sync::Arc;
 
 use turso_parser::ast::{self, TriggerEvent, TriggerTime, Upsert};
 
-use super::emitter::gencol::compute_virtual_columns;
+use super::emitter::gencol::{compute_stored_columns, compute_virtual_columns};
 use crate::error::SQLITE_CONSTRAINT_PRIMARYKEY;
 use crate::schema::{BTreeTable, ColumnLayout, IndexColumn, ROWID_SENTINEL};
-use crate::translate::emitter::{emit_check_constraints, emit_make_record, UpdateRowSource};
+use crate::translate::emitter::{
+    emit_check_constraints, emit_make_record, RowLocator, UpdateRowSource,
+};
 use crate::translate::expr::{walk_expr, WalkControl};
 use crate::translate::fkeys::{
     emit_fk_child_update_counters, emit_fk_update_parent_actions, fire_fk_update_actions,
@@ -267,8 +269,8 @@ pub fn upsert_matches_index(upsert: &Upsert, index: &Index, table: &Table) -> bo
         return true;
     };
     // must be a non-partial UNIQUE index with identical arity
-    if !index.unique || index.where_clause.is_some() || target.targets.len() != index.columns.len()
-    {
+    let key_columns = index.key_columns();
+    if !index.unique || index.where_clause.is_some() || target.targets.len() != key_columns.len() {
         return false;
     }
 
@@ -281,7 +283,7 @@ pub fn upsert_matches_index(upsert: &Upsert, index: &Index, table: &Table) -> bo
         if let Some(conflict_target) = extract_conflict_target(&te.expr) {
             // Simple column reference target: match by name and collation.
             let tname = &conflict_target.col_name;
-            for (i, ic) in index.columns.iter().enumerate() {
+            for (i, ic) in key_columns.iter().enumerate() {
                 if matched.get(i) || ic.expr.is_some() {
                     continue;
                 }
@@ -301,7 +303,7 @@ pub fn upsert_matches_index(upsert: &Upsert, index: &Index, table: &Table) -> bo
             // Expression target (e.g. lower(val)): match against expression index
             // columns using semantic equivalence.
             let (target_expr, target_collate) = extract_target_expr(&te.expr);
-            for (i, ic) in index.columns.iter().enumerate() {
+            for (i, ic) in key_columns.iter().enumerate() {
                 if matched.get(i) {
                     continue;
                 }
@@ -328,7 +330,7 @@ pub fn upsert_matches_index(upsert: &Upsert, index: &Index, table: &Table) -> bo
         }
     }
     // All target columns matched exactly once, and all index columns consumed
-    matched.count() == index.columns.len()
+    matched.count() == key_columns.len()
 }
 
 #[derive(Clone, Debug)]
@@ -356,6 +358,19 @@ pub fn resolve_upsert_target(
         return Ok(ResolvedUpsertTarget::PrimaryKey);
     }
 
+    // The PRIMARY KEY of a WITHOUT ROWID table is the table btree itself, not an index
+    if let Some(btree) = table.btree().filter(|btree| !btree.has_rowid) {
+        let pk_index = Index::automatic_from_primary_key(
+            &btree,
+            (String::new(), btree.root_page),
+            btree.primary_key_columns.len(),
+            None,
+        )?;
+        if upsert_matches_index(upsert, &pk_index, table) {
+            return Ok(ResolvedUpsertTarget::PrimaryKey);
+        }
+    }
+
     // Otherwise match a UNIQUE index, also covering non-rowid PRIMARY KEYs
     for idx in schema.get_indices(table.get_name()) {
         if idx.unique && upsert_matches_index(upsert, idx, table) {
@@ -403,12 +418,15 @@ pub fn emit_upsert(
     connection: &Arc<Connection>,
     table_references: &mut TableReferences,
 ) -> crate::Result<()> {
-    // Seek & snapshot CURRENT
-    program.emit_insn(Insn::SeekRowid {
-        cursor_id: ctx.cursor_id,
-        src_reg: ctx.conflict_rowid_reg,
-        target_pc: ctx.loop_labels.row_done,
-    });
+    // Seek & snapshot CURRENT. WITHOUT ROWID tables have no rowid to seek by: the conflict
+    // check already left the cursor on the conflicting row.
+    if ctx.table.has_rowid {
+        program.emit_insn(Insn::SeekRowid {
+            cursor_id: ctx.cursor_id,
+            src_reg: ctx.conflict_rowid_reg,
+            target_pc: ctx.loop_labels.row_done,
+        });
+    }
     let num_cols = ctx.table.columns().len();
     let layout = ctx.table.column_layout();
 
@@ -433,6 +451,10 @@ pub fn emit_upsert(
         )?;
     }
 
+    // WITHOUT ROWID rows are located by their PRIMARY KEY, remember it to re-seek the row.
+    let old_key = (!ctx.table.has_rowid)
+        .then(|| RowLocator::primary_key_from_row(program, ctx.table, current_start, &layout));
+
     // BEFORE for index maintenance / CDC
     let before_start = if ctx.cdc_table.is_some() || !ctx.idx_cursors.is_empty() {
         let s = program.alloc_registers(num_cols);
@@ -592,19 +614,29 @@ pub fn emit_upsert(
         }
     }
 
-    // Recompute virtual columns for the new row after SET clauses have modified base columns.
-    // This must happen before CHECK constraints, triggers, and index updates.
-    if ctx.table.has_virtual_columns() {
+    // Recompute generated columns for the new row after SET clauses have modified base
+    // columns. This must happen before CHECK constraints, triggers, and index updates.
+    if ctx.table.has_virtual_columns() || ctx.table.has_stored_columns() {
         let rowid_reg = new_rowid_reg.unwrap_or(ctx.conflict_rowid_reg);
         let dml_ctx =
             DmlColumnContext::layout(ctx.table.columns(), new_start, rowid_reg, layout.clone());
-        compute_virtual_columns(
-            program,
-            &ctx.table.columns_topo_sort()?,
-            &dml_ctx,
-            resolver,
-            ctx.table,
-        )?;
+        if ctx.table.has_stored_columns() {
+            compute_stored_columns(
+                program,
+                &ctx.table.columns_topo_sort()?,
+                &dml_ctx,
+                resolver,
+                ctx.table,
+            )?;
+        } else {
+            compute_virtual_columns(
+                program,
+                &ctx.table.columns_topo_sort()?,
+                &dml_ctx,
+                resolver,
+                ctx.table,
+            )?;
+        }
     }
 
     if let Some(bt) = table.btree() {
@@ -734,11 +766,9 @@ pub fn emit_upsert(
             }
 
             // BEFORE UPDATE triggers may have altered the btree, need to re-seek
-            program.emit_insn(Insn::NotExists {
-                cursor: ctx.cursor_id,
-                rowid_reg: ctx.conflict_rowid_reg,
-                target_pc: ctx.loop_labels.row_done,
-            });
+            old_key
+                .unwrap_or(RowLocator::Rowid(ctx.conflict_rowid_reg))
+                .emit_seek(program, ctx.cursor_id, ctx.loop_labels.row_done);
 
             let has_relevant_after_triggers = has_triggers_including_temp(
                 resolver,
@@ -846,6 +876,9 @@ pub fn emit_upsert(
         }
     }
 
+    let new_key = (!ctx.table.has_rowid)
+        .then(|| RowLocator::primary_key_from_row(program, ctx.table, new_start, &layout));
+
     // Index rebuild (DELETE old, INSERT new), honoring partial-index WHEREs
     if let Some(before) = before_start {
         for (idx_name, _root, idx_cid) in &ctx.idx_cursors {
@@ -858,7 +891,9 @@ pub fn emit_upsert(
             if !upsert_index_is_affected(table, &idx_meta, &directly_changed_cols, rowid_changed)? {
                 continue; // skip untouched index completely
             }
+            // Indexes of WITHOUT ROWID tables end with the PRIMARY KEY instead of a rowid.
             let k = idx_meta.columns.len();
+            let record_len = k + usize::from(idx_meta.has_rowid);
 
             let before_pred_reg = eval_partial_pred_for_row_image(
                 program,
@@ -886,7 +921,7 @@ pub fn emit_upsert(
             });
 
             // DELETE old key
-            let del = program.alloc_registers(k + 1);
+            let del = program.alloc_registers(record_len);
             for (i, ic) in idx_meta.columns.iter().enumerate() {
                 if ic.expr.is_some() {
                     emit_upsert_expr_index_value(
@@ -908,14 +943,16 @@ pub fn emit_upsert(
                     });
                 }
             }
-            program.emit_insn(Insn::Copy {
-                src_reg: ctx.conflict_rowid_reg,
-                dst_reg: del + k,
-                extra_amount: 0,
-            });
+            if idx_meta.has_rowid {
+                program.emit_insn(Insn::Copy {
+                    src_reg: ctx.conflict_rowid_reg,
+                    dst_reg: del + k,
+                    extra_amount: 0,
+                });
+            }
             program.emit_insn(Insn::IdxDelete {
                 start_reg: del,
-                num_regs: k + 1,
+                num_regs: record_len,
                 cursor_id: *idx_cid,
                 raise_error_if_no_matching_entry: false,
             });
@@ -935,7 +972,7 @@ pub fn emit_upsert(
             });
 
             // INSERT new key (use NEW rowid if present)
-            let ins = program.alloc_registers(k + 1);
+            let ins = program.alloc_registers(record_len);
             for (i, ic) in idx_meta.columns.iter().enumerate() {
                 if ic.expr.is_some() {
                     emit_upsert_expr_index_value(
@@ -957,16 +994,18 @@ pub fn emit_upsert(
                     });
                 }
             }
-            program.emit_insn(Insn::Copy {
-                src_reg: new_rowid,
-                dst_reg: ins + k,
-                extra_amount: 0,
-            });
+            if idx_meta.has_rowid {
+                program.emit_insn(Insn::Copy {
+                    src_reg: new_rowid,
+                    dst_reg: ins + k,
+                    extra_amount: 0,
+                });
+            }
 
             let rec = program.alloc_register();
             program.emit_insn(Insn::MakeRecord {
                 start_reg: to_u16(ins),
-                count: to_u16(k + 1),
+                count: to_u16(record_len),
                 dest_reg: to_u16(rec),
                 index_name: Some((*idx_name).clone()),
                 affinity_str: None,
@@ -1004,20 +1043,15 @@ pub fn emit_upsert(
                     cursor_id: *idx_cid,
                     target_pc: ok,
                     record_reg: ins,
-                    num_regs: k,
-                });
-                let hit = program.alloc_register();
-                program.emit_insn(Insn::IdxRowId {
-                    cursor_id: *idx_cid,
-                    dest: hit,
-                });
-                program.emit_insn(Insn::Eq {
-                    lhs: new_rowid,
-                    rhs: hit,
-                    target_pc: ok,
-                    flags: CmpInsFlags::default(),
-                    collation: program.curr_collation(),
+                    num_regs: idx_meta.key_columns().len(),
                 });
+                let hit = RowLocator::from_index_entry(program, ctx.table, &idx_meta, *idx_cid);
+                hit.emit_jump_if_same_row(
+                    program,
+                    ctx.table,
+                    &new_key.unwrap_or(RowLocator::Rowid(new_rowid)),
+                    ok,
+                );
                 let description = format_unique_violation_desc(table.get_name(), &idx_meta);
                 program.emit_insn(Insn::Halt {
                     err_code: SQLITE_CONSTRAINT_PRIMARYKEY,
@@ -1032,7 +1066,7 @@ pub fn emit_upsert(
                 cursor_id: *idx_cid,
                 record_reg: rec,
                 unpacked_start: Some(ins),
-                unpacked_count: Some((k + 1) as u16),
+                unpacked_count: Some(record_len as u16),
                 flags: IdxInsertFlags::new().nchange(true),
             });
 
@@ -1046,14 +1080,78 @@ pub fn emit_upsert(
     let record_reg = program.alloc_register();
     emit_make_record(
         program,
-        table.columns().iter(),
+        ctx.table.columns_in_record_order(),
         new_start,
         record_reg,
         table.btree().is_some_and(|bt| bt.is_strict),
     );
 
-    // If rowid changed, first ensure no other row owns it, then delete+insert
-    if let Some(rnew) = new_rowid_reg {
+    if let (Some(old_key), Some(new_key)) = (old_key, new_key) {
+        // WITHOUT ROWID rows are keyed by their PRIMARY KEY. If it changed, ensure no other
+        // row owns the new key, then move the row by deleting it and inserting it again.
+        let RowLocator::PrimaryKey {
+            start_reg: new_key_reg,
+            num_regs,
+        } = new_key
+        else {
+            unreachable!("WITHOUT ROWID rows are located by their PRIMARY KEY");
+        };
+        let same_key = program.allocate_label();
+        let write_done = program.allocate_label();
+        old_key.emit_jump_if_same_row(program, ctx.table, &new_key, same_key);
+        let ok = program.allocate_label();
+        program.emit_insn(Insn::NoConflict {
+            cursor_id: ctx.cursor_id,
+            target_pc: ok,
+            record_reg: new_key_reg,
+            num_regs,
+        });
+        program.emit_insn(Insn::Halt {
+            err_code: SQLITE_CONSTRAINT_PRIMARYKEY,
+            description: ctx
+                .table
+                .primary_key_columns
+                .iter()
+                .map(|(name, _)| format!("{}.{}", table.get_name(), name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            on_error: None,
+            description_reg: None,
+        });
+        program.preassign_label_to_next_insn(ok);
+        old_key.emit_seek(program, ctx.cursor_id, ctx.loop_labels.row_done);
+        program.emit_insn(Insn::Delete {
+            cursor_id: ctx.cursor_id,
+            table_name: table.get_name().to_string(),
+            is_part_of_update: true,
+        });
+        program.emit_insn(Insn::Insert {
+            cursor: ctx.cursor_id,
+            key_reg: ctx.conflict_rowid_reg,
+            record_reg,
+            flag: InsertFlags::new()
+                .require_seek()
+                .update_rowid_change()
+                .skip_last_rowid(),
+            table_name: table.get_name().to_string(),
+        });
+        program.emit_insn(Insn::Goto {
+            target_pc: write_done,
+        });
+
+        // Same key: overwrite the row in place. Index probes and FK checks may have moved the
+        // cursor, so seek to it again.
+        program.preassign_label_to_next_insn(same_key);
+        program.emit_insn(Insn::Insert {
+            cursor: ctx.cursor_id,
+            key_reg: ctx.conflict_rowid_reg,
+            record_reg,
+            flag: InsertFlags::new().require_seek().skip_last_rowid(),
+            table_name: table.get_name().to_string(),
+        });
+        program.preassign_label_to_next_insn(write_done);
+    } else if let Some(rnew) = new_rowid_reg {
+        // If rowid changed, first ensure no other row owns it, then delete+insert
         let ok = program.allocate_label();
 
         // If equal to old rowid, skip uniqueness probe
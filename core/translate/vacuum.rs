@@ -8,8 +8,8 @@ use turso_parser::ast::{Expr, Literal, Name};
 
 /// Translate a VACUUM statement into VDBE bytecode.
 ///
-/// We have `VACUUM INTO`. The in-place `VACUUM` is experimental and gated
-/// behind the experimental vacuum feature flag.
+/// `VACUUM INTO` writes a compacted copy to a new file; plain `VACUUM`
+/// rebuilds the main or an attached database in place.
 ///
 /// # Arguments
 /// * `program` - The program builder to emit instructions to
@@ -36,28 +36,19 @@ pub fn translate_vacuum(
             Ok(())
         }
         None => {
-            if !connection.experimental_vacuum_enabled() {
-                return Err(LimboError::ParseError(
-                    "VACUUM is an experimental feature. Enable with --experimental-vacuum flag"
-                        .to_string(),
-                ));
-            }
             if connection.experimental_multiprocess_wal_enabled() {
                 return Err(LimboError::ParseError(
                     "VACUUM is incompatible with experimental multiprocess WAL".to_string(),
                 ));
             }
 
-            // Schema-qualified VACUUM is not supported yet.
-            if schema_name != "main" {
-                bail_parse_error!(
-                    "VACUUM is only supported for the main database; schema '{}' is not supported yet",
-                    schema_name
-                );
+            let db = connection.get_database_id_by_name(&schema_name)?;
+            // Like VACUUM temp INTO, there is nothing worth compacting in the
+            // connection-local temp database.
+            if db == crate::TEMP_DB_ID {
+                return Ok(());
             }
-            program.emit_insn(Insn::Vacuum {
-                db: crate::MAIN_DB_ID,
-            });
+            program.emit_insn(Insn::Vacuum { db });
             Ok(())
         }
     }
@@ -29,11 +29,6 @@ fn validate_materialized(
                 .to_string(),
         ));
     }
-    // The DBSP incremental maintenance runtime (populate_from_table, etc.) assumes
-    // the main database pager/schema. Block attached databases until that is fixed.
-    if database_id != crate::MAIN_DB_ID {
-        crate::bail_parse_error!("materialized views are not supported on attached databases");
-    }
     if RESERVED_TABLE_PREFIXES
         .iter()
         .any(|prefix| normalized_view_name.starts_with(prefix))
@@ -255,6 +250,7 @@ pub fn translate_create_materialized_view(
     // Populate the materialized view
     let cursor_info = vec![(normalized_view_name.clone(), view_cursor_id)];
     program.emit_insn(Insn::PopulateMaterializedViews {
+        db: database_id,
         cursors: cursor_info,
     });
 
@@ -488,6 +484,11 @@ pub fn translate_drop_view(
         dest: type_reg,
         value: "view".to_string(),
     });
+    let trigger_type_reg = program.alloc_register();
+    program.emit_insn(Insn::String8 {
+        dest: trigger_type_reg,
+        value: "trigger".to_string(),
+    });
 
     // Start scanning from the beginning
     let end_loop_label = program.allocate_label();
@@ -503,18 +504,21 @@ pub fn translate_drop_view(
     // Column 0 is type, Column 1 is name, Column 2 is tbl_name
     let col0_reg = program.alloc_register();
     let col1_reg = program.alloc_register();
+    let col2_reg = program.alloc_register();
 
     program.emit_column_or_rowid(sqlite_schema_cursor_id, 0, col0_reg);
     program.emit_column_or_rowid(sqlite_schema_cursor_id, 1, col1_reg);
 
-    // Check if this row matches the view, DBSP table, or DBSP index
+    // Check if this row matches the view or one of its INSTEAD OF triggers
     let skip_delete_label = program.allocate_label();
+    let check_trigger_label = program.allocate_label();
+    let delete_label = program.allocate_label();
 
     // Check if this is the view entry (type='view' and name=view_name)
     program.emit_insn(Insn::Ne {
         lhs: col0_reg,
         rhs: type_reg,
-        target_pc: skip_delete_label,
+        target_pc: check_trigger_label,
         flags: CmpInsFlags::default(),
         collation: program.curr_collation(),
     });
@@ -525,7 +529,30 @@ pub fn translate_drop_view(
         flags: CmpInsFlags::default(),
         collation: program.curr_collation(),
     });
-    // Matches view - delete it
+    program.emit_insn(Insn::Goto {
+        target_pc: delete_label,
+    });
+
+    // Check if this is a trigger on the view (type='trigger' and tbl_name=view_name)
+    program.preassign_label_to_next_insn(check_trigger_label);
+    program.emit_insn(Insn::Ne {
+        lhs: col0_reg,
+        rhs: trigger_type_reg,
+        target_pc: skip_delete_label,
+        flags: CmpInsFlags::default(),
+        collation: program.curr_collation(),
+    });
+    program.emit_column_or_rowid(sqlite_schema_cursor_id, 2, col2_reg);
+    program.emit_insn(Insn::Ne {
+        lhs: col2_reg,
+        rhs: view_name_reg,
+        target_pc: skip_delete_label,
+        flags: CmpInsFlags::default(),
+        collation: program.curr_collation(),
+    });
+
+    // Matches - delete it
+    program.preassign_label_to_next_insn(delete_label);
     program.emit_insn(Insn::RowId {
         cursor_id: sqlite_schema_cursor_id,
         dest: rowid_reg,
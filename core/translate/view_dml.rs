@@ -0,0 +1,516 @@
+//! INSERT, UPDATE and DELETE statements whose target is a view.
+//!
+//! A view has no storage, so these statements are only valid when the view has
+//! matching INSTEAD OF triggers. The affected rows are computed up front by a
+//! SELECT against the view and buffered in an ephemeral table, so that trigger
+//! bodies modifying the underlying tables cannot disturb the iteration. Each
+//! buffered row then fires the triggers with OLD/NEW bound to the view's columns.
+//!
+//! EXAMPLE:
+//!
+//! ```sql
+//! CREATE VIEW v AS SELECT a, b FROM t;
+//! CREATE TRIGGER tr INSTEAD OF UPDATE ON v BEGIN
+//!   UPDATE t SET b = NEW.b WHERE a = OLD.a;
+//! END;
+//! UPDATE v SET b = b * 10 WHERE a = 1;
+//! ```
+//!
+//! is executed as if by `SELECT v.*, b * 10 FROM v WHERE a = 1`, with OLD bound
+//! to the first two columns of every result row and NEW to the view columns with
+//! `b` replaced by the last one.
+
+use crate::schema::{BTreeCharacteristics, BTreeTable, Column, Trigger, View};
+use crate::sync::Arc;
+use crate::translate::emitter::Resolver;
+use crate::translate::plan::{ColumnMask, EphemeralRowidMode, Plan, QueryDestination};
+use crate::translate::select::{emit_select_plan, prepare_select_plan};
+use crate::translate::trigger_exec::{fire_trigger, get_triggers_including_temp, TriggerContext};
+use crate::util::normalize_ident;
+use crate::vdbe::builder::{CursorType, ProgramBuilder};
+use crate::vdbe::insn::Insn;
+use crate::vdbe::CursorID;
+use crate::{bail_parse_error, Connection, LimboError, Result};
+use turso_parser::ast::{self, TriggerEvent, TriggerTime};
+
+/// Translate `INSERT INTO view ...` into invocations of its INSTEAD OF INSERT triggers.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn translate_insert_into_view(
+    program: &mut ProgramBuilder,
+    resolver: &Resolver,
+    view: &View,
+    database_id: usize,
+    tbl_name: &ast::QualifiedName,
+    columns: &[ast::Name],
+    body: ast::InsertBody,
+    returning: &[ast::ResultColumn],
+    connection: &Arc<Connection>,
+) -> Result<()> {
+    if !returning.is_empty() {
+        bail_parse_error!("RETURNING is not supported on views");
+    }
+    let table = view_trigger_table(view);
+    let triggers = instead_of_triggers(resolver, database_id, &table, TriggerEvent::Insert, None)?;
+
+    // Map every view column to the position of its value in the source rows.
+    let num_view_cols = table.columns().len();
+    let mut new_columns = vec![None; num_view_cols];
+    if columns.is_empty() {
+        for (i, source) in new_columns.iter_mut().enumerate() {
+            *source = Some(i);
+        }
+    } else {
+        for (value_idx, name) in columns.iter().enumerate() {
+            let Some((col_idx, _)) = table.get_column(&normalize_ident(name.as_str())) else {
+                bail_parse_error!("table {} has no column named {}", view.name, name.as_str());
+            };
+            new_columns[col_idx] = Some(value_idx);
+        }
+    }
+    let num_values = if columns.is_empty() {
+        num_view_cols
+    } else {
+        columns.len()
+    };
+
+    let select = match body {
+        ast::InsertBody::Select(_, Some(_)) => bail_parse_error!("cannot UPSERT a view"),
+        ast::InsertBody::Select(select, None) => select,
+        // Views have no column defaults, so every NEW value is NULL.
+        ast::InsertBody::DefaultValues => ast::Select {
+            with: None,
+            body: ast::SelectBody {
+                select: ast::OneSelect::Values(vec![(0..num_values)
+                    .map(|_| Box::new(ast::Expr::Literal(ast::Literal::Null)))
+                    .collect()]),
+                compounds: vec![],
+            },
+            order_by: vec![],
+            limit: None,
+        },
+    };
+
+    begin_view_write(program, resolver, database_id);
+    let view_name = tbl_name.name.as_str();
+    let cursor_id = emit_buffered_rows(program, resolver, select, num_values, connection, |n| {
+        if columns.is_empty() {
+            LimboError::ParseError(format!(
+                "table {view_name} has {num_values} columns but {n} values were supplied"
+            ))
+        } else {
+            LimboError::ParseError(format!("{n} values for {num_values} columns"))
+        }
+    })?;
+    emit_instead_of_trigger_loop(
+        program,
+        resolver,
+        connection,
+        database_id,
+        table,
+        triggers,
+        cursor_id,
+        None,
+        Some(&new_columns),
+    )
+}
+
+/// Translate `UPDATE view ...` into invocations of its INSTEAD OF UPDATE triggers.
+pub(crate) fn translate_update_of_view(
+    program: &mut ProgramBuilder,
+    resolver: &Resolver,
+    view: &View,
+    database_id: usize,
+    body: ast::Update,
+    connection: &Arc<Connection>,
+) -> Result<()> {
+    if !body.returning.is_empty() {
+        bail_parse_error!("RETURNING is not supported on views");
+    }
+    let table = view_trigger_table(view);
+    let num_view_cols = table.columns().len();
+
+    // The buffered rows hold the OLD view row followed by one value per assignment.
+    let mut result_columns = vec![ast::ResultColumn::TableStar(target_identifier(
+        &body.tbl_name,
+    ))];
+    let mut new_columns: Vec<Option<usize>> = (0..num_view_cols).map(Some).collect();
+    let mut updated_columns = ColumnMask::default();
+    for set in body.sets {
+        let values = match *set.expr {
+            ast::Expr::Parenthesized(values) if set.col_names.len() > 1 => values,
+            ast::Expr::Subquery(_) if set.col_names.len() > 1 => {
+                bail_parse_error!("row value subqueries in UPDATE of a view are not supported")
+            }
+            expr => vec![Box::new(expr)],
+        };
+        if values.len() != set.col_names.len() {
+            bail_parse_error!(
+                "{} columns assigned {} values",
+                set.col_names.len(),
+                values.len()
+            );
+        }
+        for (name, value) in set.col_names.iter().zip(values) {
+            let Some((col_idx, _)) = table.get_column(&normalize_ident(name.as_str())) else {
+                bail_parse_error!("no such column: {}", name.as_str());
+            };
+            new_columns[col_idx] = Some(num_view_cols + result_columns.len() - 1);
+            updated_columns.set(col_idx);
+            result_columns.push(ast::ResultColumn::Expr(value, None));
+        }
+    }
+    let triggers = instead_of_triggers(
+        resolver,
+        database_id,
+        &table,
+        TriggerEvent::Update,
+        Some(updated_columns),
+    )?;
+
+    let width = num_view_cols + result_columns.len() - 1;
+    let select = view_rows_select(
+        &body.tbl_name,
+        body.indexed,
+        body.from,
+        body.with,
+        result_columns,
+        body.where_clause,
+        body.order_by,
+        body.limit,
+    );
+
+    begin_view_write(program, resolver, database_id);
+    let cursor_id = emit_buffered_rows(program, resolver, select, width, connection, |_| {
+        LimboError::InternalError("UPDATE of a view produced an unexpected row shape".into())
+    })?;
+    let old_columns: Vec<usize> = (0..num_view_cols).collect();
+    emit_instead_of_trigger_loop(
+        program,
+        resolver,
+        connection,
+        database_id,
+        table,
+        triggers,
+        cursor_id,
+        Some(&old_columns),
+        Some(&new_columns),
+    )
+}
+
+/// Translate `DELETE FROM view ...` into invocations of its INSTEAD OF DELETE triggers.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn translate_delete_from_view(
+    program: &mut ProgramBuilder,
+    resolver: &Resolver,
+    view: &View,
+    database_id: usize,
+    tbl_name: &ast::QualifiedName,
+    where_clause: Option<Box<ast::Expr>>,
+    order_by: Vec<ast::SortedColumn>,
+    limit: Option<ast::Limit>,
+    returning: &[ast::ResultColumn],
+    indexed: Option<ast::Indexed>,
+    with: Option<ast::With>,
+    connection: &Arc<Connection>,
+) -> Result<()> {
+    if !returning.is_empty() {
+        bail_parse_error!("RETURNING is not supported on views");
+    }
+    let table = view_trigger_table(view);
+    let num_view_cols = table.columns().len();
+    let triggers = instead_of_triggers(resolver, database_id, &table, TriggerEvent::Delete, None)?;
+
+    let select = view_rows_select(
+        tbl_name,
+        indexed,
+        None,
+        with,
+        vec![ast::ResultColumn::TableStar(target_identifier(tbl_name))],
+        where_clause,
+        order_by,
+        limit,
+    );
+
+    begin_view_write(program, resolver, database_id);
+    let cursor_id =
+        emit_buffered_rows(program, resolver, select, num_view_cols, connection, |_| {
+            LimboError::InternalError("DELETE from a view produced an unexpected row shape".into())
+        })?;
+    let old_columns: Vec<usize> = (0..num_view_cols).collect();
+    emit_instead_of_trigger_loop(
+        program,
+        resolver,
+        connection,
+        database_id,
+        table,
+        triggers,
+        cursor_id,
+        Some(&old_columns),
+        None,
+    )
+}
+
+/// Build the table that stands in for the view in the trigger context, so that
+/// NEW/OLD references resolve against the view's column names.
+fn view_trigger_table(view: &View) -> Arc<BTreeTable> {
+    // Only the name and declared type are carried over: constraints such as a
+    // rowid alias belong to the underlying table and must not leak into OLD/NEW.
+    let columns = view
+        .columns
+        .iter()
+        .map(|col| Column::new_default_text(col.name.clone(), col.ty_str.clone(), None))
+        .collect();
+    Arc::new(BTreeTable::new(
+        0,
+        view.name.clone(),
+        vec![],
+        columns,
+        BTreeCharacteristics::HAS_ROWID,
+        vec![],
+        vec![],
+        vec![],
+        None,
+    ))
+}
+
+/// Collect the INSTEAD OF triggers for `event`, failing the way SQLite does when
+/// there are none: a view without them cannot be modified.
+fn instead_of_triggers(
+    resolver: &Resolver,
+    database_id: usize,
+    table: &BTreeTable,
+    event: TriggerEvent,
+    updated_columns: Option<ColumnMask>,
+) -> Result<Vec<Arc<Trigger>>> {
+    let triggers = get_triggers_including_temp(
+        resolver,
+        database_id,
+        event,
+        TriggerTime::InsteadOf,
+        updated_columns,
+        table,
+    );
+    if triggers.is_empty() {
+        bail_parse_error!("cannot modify {} because it is a view", table.name);
+    }
+    Ok(triggers)
+}
+
+fn begin_view_write(program: &mut ProgramBuilder, resolver: &Resolver, database_id: usize) {
+    let schema_cookie = resolver.with_schema(database_id, |s| s.schema_version);
+    program.begin_write_on_database(database_id, schema_cookie);
+}
+
+/// The name the view is referred to by in the statement: its alias, if any.
+fn target_identifier(tbl_name: &ast::QualifiedName) -> ast::Name {
+    tbl_name
+        .alias
+        .clone()
+        .unwrap_or_else(|| tbl_name.name.clone())
+}
+
+/// Build `SELECT <columns> FROM view [, from...] WHERE ... ORDER BY ... LIMIT ...`.
+#[allow(clippy::too_many_arguments)]
+fn view_rows_select(
+    tbl_name: &ast::QualifiedName,
+    indexed: Option<ast::Indexed>,
+    from: Option<ast::FromClause>,
+    with: Option<ast::With>,
+    columns: Vec<ast::ResultColumn>,
+    where_clause: Option<Box<ast::Expr>>,
+    order_by: Vec<ast::SortedColumn>,
+    limit: Option<ast::Limit>,
+) -> ast::Select {
+    let view_table = ast::SelectTable::Table(
+        ast::QualifiedName {
+            db_name: tbl_name.db_name.clone(),
+            name: tbl_name.name.clone(),
+            alias: None,
+        },
+        tbl_name.alias.clone().map(ast::As::As),
+        indexed,
+    );
+    // UPDATE ... FROM joins the extra tables to the view like a comma join.
+    let joins = match from {
+        Some(from) => std::iter::once(ast::JoinedSelectTable {
+            operator: ast::JoinOperator::Comma,
+            table: from.select,
+            constraint: None,
+        })
+        .chain(from.joins)
+        .collect(),
+        None => vec![],
+    };
+    ast::Select {
+        with,
+        body: ast::SelectBody {
+            select: ast::OneSelect::Select {
+                distinctness: None,
+                columns,
+                from: Some(ast::FromClause {
+                    select: Box::new(view_table),
+                    joins,
+                }),
+                where_clause,
+                group_by: None,
+                window_clause: vec![],
+            },
+            compounds: vec![],
+        },
+        order_by,
+        limit,
+    }
+}
+
+/// Run `select` to completion, buffering its rows in a new ephemeral table whose
+/// cursor is returned. `width_mismatch` builds the error reported when the
+/// SELECT does not produce exactly `width` columns.
+fn emit_buffered_rows(
+    program: &mut ProgramBuilder,
+    resolver: &Resolver,
+    select: ast::Select,
+    width: usize,
+    connection: &Arc<Connection>,
+    width_mismatch: impl FnOnce(usize) -> LimboError,
+) -> Result<CursorID> {
+    let columns = (0..width)
+        .map(|i| Column::new_default_text(Some(format!("c{i}")), String::new(), None))
+        .collect();
+    let buffer = Arc::new(BTreeTable::new(
+        0,
+        String::new(),
+        vec![],
+        columns,
+        BTreeCharacteristics::HAS_ROWID,
+        vec![],
+        vec![],
+        vec![],
+        None,
+    ));
+    let cursor_id = program.alloc_cursor_id(CursorType::BTreeTable(buffer.clone()));
+    program.emit_insn(Insn::OpenEphemeral {
+        cursor_id,
+        is_table: true,
+    });
+    let plan = prepare_select_plan(
+        select,
+        resolver,
+        program,
+        &[],
+        QueryDestination::EphemeralTable {
+            cursor_id,
+            table: buffer,
+            rowid_mode: EphemeralRowidMode::Auto,
+        },
+        connection,
+    )?;
+    let num_result_cols = match &plan {
+        Plan::Select(select) => select.result_columns.len(),
+        Plan::CompoundSelect { right_most, .. } => right_most.result_columns.len(),
+        Plan::Delete(_) | Plan::Update(_) => unreachable!("prepare_select_plan returned DML"),
+    };
+    if num_result_cols != width {
+        return Err(width_mismatch(num_result_cols));
+    }
+    emit_select_plan(plan, resolver, program, connection)?;
+    // The SELECT only feeds the buffer; the DML statement itself returns no rows.
+    program.result_columns.clear();
+    Ok(cursor_id)
+}
+
+/// Loop over the buffered rows and fire `triggers` once per row. `old_columns`
+/// and `new_columns` give, for every view column, the buffer column its OLD or
+/// NEW value is read from; a `None` NEW source reads as NULL.
+#[allow(clippy::too_many_arguments)]
+fn emit_instead_of_trigger_loop(
+    program: &mut ProgramBuilder,
+    resolver: &Resolver,
+    connection: &Arc<Connection>,
+    database_id: usize,
+    table: Arc<BTreeTable>,
+    triggers: Vec<Arc<Trigger>>,
+    cursor_id: CursorID,
+    old_columns: Option<&[usize]>,
+    new_columns: Option<&[Option<usize>]>,
+) -> Result<()> {
+    // Views have no rowid, so OLD.rowid and NEW.rowid read as NULL.
+    let rowid_reg = program.alloc_register();
+    program.emit_insn(Insn::Null {
+        dest: rowid_reg,
+        dest_end: None,
+    });
+    let alloc_row = |program: &mut ProgramBuilder, num_cols: usize| {
+        let start = program.alloc_registers(num_cols);
+        (start..start + num_cols)
+            .chain(std::iter::once(rowid_reg))
+            .collect::<Vec<_>>()
+    };
+    let old_registers = old_columns.map(|cols| alloc_row(program, cols.len()));
+    let new_registers = new_columns.map(|cols| alloc_row(program, cols.len()));
+
+    let label_done = program.allocate_label();
+    let label_next_row = program.allocate_label();
+    let label_loop_start = program.allocate_label();
+    program.emit_insn(Insn::Rewind {
+        cursor_id,
+        pc_if_empty: label_done,
+    });
+    program.preassign_label_to_next_insn(label_loop_start);
+    if let (Some(cols), Some(regs)) = (old_columns, &old_registers) {
+        for (&column, &dest) in cols.iter().zip(regs) {
+            program.emit_insn(Insn::Column {
+                cursor_id,
+                column,
+                dest,
+                default: None,
+            });
+        }
+    }
+    if let (Some(cols), Some(regs)) = (new_columns, &new_registers) {
+        for (&source, &dest) in cols.iter().zip(regs) {
+            match source {
+                Some(column) => program.emit_insn(Insn::Column {
+                    cursor_id,
+                    column,
+                    dest,
+                    default: None,
+                }),
+                None => program.emit_insn(Insn::Null {
+                    dest,
+                    dest_end: None,
+                }),
+            }
+        }
+    }
+
+    let trigger_ctx = match program.trigger_conflict_override {
+        Some(override_conflict) => TriggerContext::new_with_override_conflict(
+            table,
+            new_registers,
+            old_registers,
+            override_conflict,
+        ),
+        None => TriggerContext::new(table, new_registers, old_registers),
+    };
+    let mut resolver = resolver.fork();
+    for trigger in triggers {
+        // RAISE(IGNORE) abandons the current row and moves on to the next one.
+        fire_trigger(
+            program,
+            &mut resolver,
+            trigger,
+            &trigger_ctx,
+            connection,
+            database_id,
+            label_next_row,
+        )?;
+    }
+
+    program.preassign_label_to_next_insn(label_next_row);
+    program.emit_insn(Insn::Next {
+        cursor_id,
+        pc_if_next: label_loop_start,
+    });
+    program.preassign_label_to_next_insn(label_done);
+    Ok(())
+}
@@ -1,31 +1,37 @@
+use crate::error::SQLITE_ERROR;
 use crate::function::WindowFunc;
 use crate::schema::{BTreeCharacteristics, BTreeTable, Table};
 use crate::sync::Arc;
 use crate::translate::aggregation::{translate_aggregation_step, AggArgumentSource};
 use crate::translate::collate::{get_collseq_from_expr, CollationSeq};
 use crate::translate::emitter::{Resolver, TranslateCtx};
-use crate::translate::expr::{walk_expr, walk_expr_mut, WalkControl};
+use crate::translate::expr::{translate_expr, walk_expr, walk_expr_mut, WalkControl};
 use crate::translate::order_by::EmitOrderBy;
 use crate::translate::plan::{
     Aggregate, Distinctness, JoinOrderMember, JoinedTable, QueryDestination, ResultSetColumn,
-    SelectPlan, TableReferences, Window, WindowFunctionKind,
+    SelectPlan, TableReferences, Window, WindowFunction, WindowFunctionKind,
 };
 use crate::translate::planner::resolve_window_and_aggregate_functions;
 use crate::translate::result_row::emit_select_result;
 use crate::translate::subquery::plan_subqueries_from_select_plan;
 use crate::types::KeyInfo;
 use crate::util::exprs_are_equivalent;
+use crate::vdbe::affinity::Affinity;
 use crate::vdbe::builder::{CursorType, ProgramBuilder};
 use crate::vdbe::insn::{
-    to_u16, {InsertFlags, Insn},
+    to_u16, {CmpInsFlags, InsertFlags, Insn},
 };
 use crate::vdbe::{BranchOffset, CursorID};
 use crate::Connection;
 use crate::Result;
 use crate::{turso_assert, turso_assert_eq};
 use std::mem;
+use std::num::NonZeroUsize;
 use turso_parser::ast::Name;
-use turso_parser::ast::{Expr, FunctionTail, Literal, Over, SortOrder, TableInternalId};
+use turso_parser::ast::{
+    self, Expr, FrameBound, FrameExclude, FrameMode, FunctionTail, Literal, Over, SortOrder,
+    TableInternalId,
+};
 
 const SUBQUERY_DATABASE_ID: usize = 0;
 
@@ -373,20 +379,28 @@ fn rewrite_expr_referencing_current_window(
     ctx: &mut WindowSubqueryContext,
     expr: &mut Expr,
 ) -> crate::Result<()> {
-    fn normalize_over_clause(filter_over: &mut FunctionTail, window_name: &str) {
-        // FILTER clause is not supported yet. Proper checks elsewhere return appropriate
-        // error messages, and this ensures that nothing slips through unnoticed.
-        turso_assert!(
-            filter_over.filter_clause.is_none(),
-            "FILTER in window functions is not supported"
-        );
+    fn normalize_over_clause(
+        filter_over: &mut FunctionTail,
+        window_name: &str,
+        aggregates: &mut Vec<Aggregate>,
+        ctx: &mut WindowSubqueryContext,
+    ) -> crate::Result<()> {
+        // The FILTER condition is evaluated per input row, so it is pushed down to the
+        // subquery like the function arguments.
+        if let Some(filter_clause) = filter_over.filter_clause.as_deref_mut() {
+            let contains_aggregates = resolve_window_and_aggregate_functions(
+                filter_clause,
+                ctx.resolver,
+                aggregates,
+                None,
+            )?;
+            rewrite_expr_as_subquery_column(filter_clause, ctx, contains_aggregates);
+        }
 
         // Replace inline OVER clause with a reference to the named window.
         // The window name may be user-provided or planner-generated.
-        *filter_over = FunctionTail {
-            filter_clause: None,
-            over_clause: Some(Over::Name(Name::exact(window_name.to_string()))),
-        };
+        filter_over.over_clause = Some(Over::Name(Name::exact(window_name.to_string())));
+        Ok(())
     }
 
     match expr {
@@ -406,13 +420,13 @@ fn rewrite_expr_referencing_current_window(
                 order_by.is_empty(),
                 "ORDER BY in window functions is not supported"
             );
-            normalize_over_clause(filter_over, &window_name);
+            normalize_over_clause(filter_over, &window_name, aggregates, ctx)?;
         }
         Expr::FunctionCallStar {
             filter_over,
             name: _,
         } => {
-            normalize_over_clause(filter_over, &window_name);
+            normalize_over_clause(filter_over, &window_name, aggregates, ctx)?;
         }
         _ => unreachable!("only functions can reference windows"),
     }
@@ -514,6 +528,9 @@ pub struct WindowCursors {
     pub buffer_read: CursorID,
     /// Cursor used to write to the ephemeral buffer table
     pub buffer_write: CursorID,
+    /// Cursor used to look up arbitrary rows of a buffered partition by position.
+    /// Only present when the whole partition is buffered (see [uses_partition_buffer]).
+    pub buffer_seek: Option<CursorID>,
 }
 
 pub struct EmitWindow;
@@ -578,6 +595,16 @@ impl EmitWindow {
             original_cursor_id: cursor_buffer_read,
             new_cursor_id: cursor_buffer_write,
         });
+        let cursor_buffer_seek = if uses_partition_buffer(window) {
+            let cursor_id = program.alloc_cursor_id(CursorType::BTreeTable(buffer_table.clone()));
+            program.emit_insn(Insn::OpenDup {
+                original_cursor_id: cursor_buffer_read,
+                new_cursor_id: cursor_id,
+            });
+            Some(cursor_id)
+        } else {
+            None
+        };
 
         // Window function processing is similar to aggregation processing in how results are mapped
         // to registers. Each function expression is stored in `expr_to_reg_cache` along with its
@@ -640,6 +667,7 @@ impl EmitWindow {
             cursors: WindowCursors {
                 buffer_read: cursor_buffer_read,
                 buffer_write: cursor_buffer_write,
+                buffer_seek: cursor_buffer_seek,
             },
             src_column_count,
             expressions_referencing_subquery,
@@ -664,6 +692,10 @@ impl EmitWindow {
     ///   of intermediate results.
     /// - The row is then inserted into the window’s buffer table.
     /// - Aggregate steps for any window functions are executed.
+    ///
+    /// When the whole partition has to be buffered (see [uses_partition_buffer]), only the
+    /// partition check and the insertion into the buffer happen here; peer groups and
+    /// function values are worked out while the partition is returned.
     pub fn emit_window_loop_source(
         program: &mut ProgramBuilder,
         t_ctx: &mut TranslateCtx,
@@ -679,6 +711,18 @@ impl EmitWindow {
         } = t_ctx.meta_window.as_ref().expect("missing window metadata");
         let window = plan.window.as_ref().expect("missing window");
 
+        if uses_partition_buffer(window) {
+            emit_flush_buffer_if_new_partition(program, labels, registers, window, plan)?;
+            emit_insert_row_into_buffer(
+                program,
+                registers,
+                cursors,
+                input_column_count,
+                buffer_table_name,
+            );
+            return Ok(());
+        }
+
         emit_load_order_by_columns(program, window, registers);
         emit_flush_buffer_if_new_partition(program, labels, registers, window, plan)?;
         emit_reset_state_if_new_partition(program, registers, window);
@@ -696,6 +740,25 @@ impl EmitWindow {
     }
 }
 
+/// Returns true if the window's functions can only be computed once the whole partition
+/// has been read.
+///
+/// Aggregates and ROW_NUMBER() only ever look at rows up to the end of the current peer
+/// group, so their rows are returned one peer group at a time. Every other window function
+/// either needs the partition size (PERCENT_RANK, CUME_DIST, NTILE), looks at arbitrary rows
+/// (LAG, LEAD, NTH_VALUE, ...), or is cheaper to compute from row positions, so the partition
+/// is buffered and rows are returned when it ends. The same applies to any window with a
+/// custom frame, as the frame may extend past the current peer group.
+pub fn uses_partition_buffer(window: &Window) -> bool {
+    !window.has_default_frame()
+        || window.functions.iter().any(|func| {
+            matches!(
+                func.func,
+                WindowFunctionKind::Window(window_func) if window_func != WindowFunc::RowNumber
+            )
+        })
+}
+
 fn alloc_optional_registers(program: &mut ProgramBuilder, count: usize) -> Option<usize> {
     if count > 0 {
         Some(program.alloc_registers(count))
@@ -888,27 +951,11 @@ fn emit_flush_buffer_if_not_peer(
             .expect("prev_order_by_columns_start must exist");
 
         program.add_comment(program.offset(), "compare ORDER BY columns to detect peer");
-        let mut compare_key_info = (0..window.order_by.len())
-            .map(|_| KeyInfo {
-                sort_order: SortOrder::Asc,
-                collation: CollationSeq::default(),
-                nulls_order: None,
-            })
-            .collect::<Vec<_>>();
-        for (i, c) in compare_key_info
-            .iter_mut()
-            .enumerate()
-            .take(window.order_by.len())
-        {
-            let maybe_collation =
-                get_collseq_from_expr(&window.order_by[i].0, &plan.table_references)?;
-            c.collation = maybe_collation.unwrap_or_default();
-        }
         program.emit_insn(Insn::Compare {
             start_reg_a: reg_prev_order_by_columns_start,
             start_reg_b: reg_new_order_by_columns_start,
             count: order_by_len,
-            key_info: compare_key_info,
+            key_info: order_by_key_info(window, plan)?,
         });
         program.emit_insn(Insn::Jump {
             target_pc_lt: label_not_peer,
@@ -1013,6 +1060,23 @@ fn emit_aggregation_step(
             ),
         };
 
+        let label_skip_step = program.allocate_label();
+        if let Some(filter_expr) = window_function_filter(func) {
+            let reg_filter = program.alloc_register();
+            translate_expr(
+                program,
+                Some(&plan.table_references),
+                filter_expr,
+                reg_filter,
+                resolver,
+            )?;
+            program.emit_insn(Insn::IfNot {
+                reg: reg_filter,
+                target_pc: label_skip_step,
+                jump_if_null: true,
+            });
+        }
+
         let reg_acc_start = registers.acc_start + i;
         translate_aggregation_step(
             program,
@@ -1021,11 +1085,23 @@ fn emit_aggregation_step(
             reg_acc_start,
             resolver,
         )?;
+        program.preassign_label_to_next_insn(label_skip_step);
     }
 
     Ok(())
 }
 
+/// Returns the condition of a window function's FILTER clause, if it has one.
+fn window_function_filter(func: &WindowFunction) -> Option<&Expr> {
+    let func_expr = func.rewritten_expr.as_ref().unwrap_or(&func.original_expr);
+    match func_expr {
+        Expr::FunctionCall { filter_over, .. } | Expr::FunctionCallStar { filter_over, .. } => {
+            filter_over.filter_clause.as_deref()
+        }
+        _ => None,
+    }
+}
+
 /// Emits bytecode to output all buffered rows produced by window processing.
 ///
 /// The generated code has two possible entry points:
@@ -1071,7 +1147,11 @@ pub fn emit_window_results(
         pc_if_empty: label_empty,
     });
 
-    emit_return_buffered_rows(program, window, t_ctx, plan)?;
+    if uses_partition_buffer(window) {
+        emit_return_partition_rows(program, window, t_ctx, plan)?;
+    } else {
+        emit_return_buffered_rows(program, window, t_ctx, plan)?;
+    }
 
     program.preassign_label_to_next_insn(label_empty);
 
@@ -1178,3 +1258,1550 @@ fn emit_return_buffered_rows(
 
     Ok(())
 }
+
+/// Registers used while returning the rows of a fully buffered partition.
+///
+/// Rows are inserted into the buffer table with consecutive rowids starting at 1, so the
+/// rowid of a buffered row is also its position within the partition.
+struct PartitionRegisters {
+    /// Number of rows in the partition.
+    size: usize,
+    /// Position of the row being returned.
+    position: usize,
+    /// Position of the first row of the current peer group.
+    group_start: usize,
+    /// Position of the last row of the current peer group.
+    group_end: usize,
+    /// Number of peer groups seen so far in the partition.
+    group_count: usize,
+    /// Position of the first row of the current row's frame.
+    frame_start: usize,
+    /// Position of the last row of the current row's frame.
+    frame_end: usize,
+    /// Position of the last row added to the aggregate accumulators.
+    agg_end: usize,
+    /// Holds the constant 1.
+    one: usize,
+    /// Value of the frame's `<expr> PRECEDING/FOLLOWING` start bound, if it has one.
+    frame_start_offset: Option<usize>,
+    /// Value of the frame's `<expr> PRECEDING/FOLLOWING` end bound, if it has one.
+    frame_end_offset: Option<usize>,
+}
+
+/// Emits bytecode to return the rows of a fully buffered partition.
+///
+/// Unlike [emit_return_buffered_rows], the buffer holds the whole partition, so every
+/// window function can look at any row of it. For each row, the bounds of its peer group
+/// and frame are computed first, then aggregates are brought up to date with the frame and
+/// the remaining window functions are evaluated from row positions.
+fn emit_return_partition_rows(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    t_ctx: &mut TranslateCtx,
+    plan: &SelectPlan,
+) -> crate::Result<()> {
+    let WindowMetadata {
+        labels,
+        registers,
+        cursors,
+        expressions_referencing_subquery,
+        ..
+    } = t_ctx.meta_window.as_ref().expect("missing window metadata");
+    let label_window_processing_end = labels.window_processing_end;
+    let reg_acc_start = registers.acc_start;
+    let reg_acc_result_start = registers.acc_result_start;
+    let reg_result_columns_start = registers.result_columns_start;
+    let cursor_buffer_read = cursors.buffer_read;
+    let cursor_buffer_seek = cursors
+        .buffer_seek
+        .expect("buffer_seek cursor must exist when the partition is buffered");
+    let columns_to_copy = expressions_referencing_subquery
+        .iter()
+        .map(|(_, col_idx)| *col_idx)
+        .collect::<Vec<_>>();
+
+    let regs = PartitionRegisters {
+        size: program.alloc_register(),
+        position: program.alloc_register(),
+        group_start: program.alloc_register(),
+        group_end: program.alloc_register(),
+        group_count: program.alloc_register(),
+        frame_start: program.alloc_register(),
+        frame_end: program.alloc_register(),
+        agg_end: program.alloc_register(),
+        one: program.alloc_register(),
+        frame_start_offset: window
+            .frame
+            .as_ref()
+            .and_then(|frame| frame_bound_offset(&frame.start))
+            .map(|_| program.alloc_register()),
+        frame_end_offset: window
+            .frame
+            .as_ref()
+            .and_then(|frame| frame_bound_offset(&frame.end))
+            .map(|_| program.alloc_register()),
+    };
+    let label_partition_end = program.allocate_label();
+
+    program.add_comment(program.offset(), "initialize partition state");
+    program.emit_insn(Insn::Last {
+        cursor_id: cursor_buffer_seek,
+        pc_if_empty: label_partition_end,
+    });
+    program.emit_insn(Insn::RowId {
+        cursor_id: cursor_buffer_seek,
+        dest: regs.size,
+    });
+    program.emit_int(1, regs.one);
+    program.emit_int(0, regs.group_end);
+    program.emit_int(0, regs.group_count);
+    program.emit_int(0, regs.agg_end);
+    program.emit_null(
+        reg_acc_start,
+        Some(reg_acc_start + window.functions.len() - 1),
+    );
+    emit_frame_offsets(program, window, &regs, &t_ctx.resolver)?;
+
+    let label_skip_returning_row = program.allocate_label();
+    let label_loop_start = program.allocate_label();
+    program.preassign_label_to_next_insn(label_loop_start);
+    program.emit_insn(Insn::RowId {
+        cursor_id: cursor_buffer_read,
+        dest: regs.position,
+    });
+    emit_advance_peer_group(program, window, plan, &regs, cursor_buffer_seek)?;
+
+    emit_frame_bounds(
+        program,
+        window,
+        plan,
+        &regs,
+        cursor_buffer_read,
+        cursor_buffer_seek,
+    )?;
+
+    emit_partition_aggregates(
+        program,
+        window,
+        plan,
+        &t_ctx.resolver,
+        &regs,
+        cursor_buffer_seek,
+        reg_acc_start,
+        reg_acc_result_start,
+    )?;
+    emit_partition_window_function_values(
+        program,
+        window,
+        &regs,
+        cursor_buffer_read,
+        cursor_buffer_seek,
+        reg_acc_result_start,
+    );
+
+    for (i, col_idx) in columns_to_copy.iter().enumerate() {
+        program.emit_column_or_rowid(cursor_buffer_read, *col_idx, reg_result_columns_start + i);
+    }
+    t_ctx.resolver.enable_expr_to_reg_cache();
+
+    match plan.order_by.is_empty() {
+        true => {
+            emit_select_result(
+                program,
+                &t_ctx.resolver,
+                plan,
+                Some(label_window_processing_end),
+                Some(label_skip_returning_row),
+                t_ctx.reg_nonagg_emit_once_flag,
+                t_ctx.reg_offset,
+                t_ctx.reg_result_cols_start.unwrap(),
+                t_ctx.limit_ctx,
+            )?;
+        }
+        false => {
+            EmitOrderBy::sorter_insert(program, t_ctx, plan)?;
+        }
+    }
+
+    program.preassign_label_to_next_insn(label_skip_returning_row);
+
+    if let Distinctness::Distinct { ctx } = &plan.distinctness {
+        let distinct_ctx = ctx.as_ref().expect("distinct context must exist");
+        program.preassign_label_to_next_insn(distinct_ctx.label_on_conflict);
+    }
+
+    program.emit_insn(Insn::Next {
+        cursor_id: cursor_buffer_read,
+        pc_if_next: label_loop_start,
+    });
+    program.preassign_label_to_next_insn(label_partition_end);
+
+    Ok(())
+}
+
+/// Key info for comparing the ORDER BY columns of two rows of the window's input.
+fn order_by_key_info(window: &Window, plan: &SelectPlan) -> Result<Vec<KeyInfo>> {
+    let mut key_info = (0..window.order_by.len())
+        .map(|_| KeyInfo {
+            sort_order: SortOrder::Asc,
+            collation: CollationSeq::default(),
+            nulls_order: None,
+        })
+        .collect::<Vec<_>>();
+    for (c, (expr, _, _)) in key_info.iter_mut().zip(window.order_by.iter()) {
+        c.collation = get_collseq_from_expr(expr, &plan.table_references)?.unwrap_or_default();
+    }
+    Ok(key_info)
+}
+
+/// Returns the subquery column indexes of a window function's arguments. Arguments are
+/// always rewritten into references to the window's input subquery.
+fn window_function_arg_columns(func: &WindowFunction) -> Vec<usize> {
+    let func_expr = func.rewritten_expr.as_ref().unwrap_or(&func.original_expr);
+    match func_expr {
+        Expr::FunctionCall { args, .. } => args
+            .iter()
+            .map(|arg| match arg.as_ref() {
+                Expr::Column { column, .. } => *column,
+                _ => unreachable!("expected Column, got {:?}", arg),
+            })
+            .collect(),
+        Expr::FunctionCallStar { .. } => vec![],
+        _ => unreachable!(
+            "All window functions should be either FunctionCall or FunctionCallStar expressions"
+        ),
+    }
+}
+
+/// Emits bytecode that, when the current row starts a new peer group, records where the
+/// group starts and finds its last row.
+fn emit_advance_peer_group(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    plan: &SelectPlan,
+    regs: &PartitionRegisters,
+    cursor_buffer_seek: CursorID,
+) -> Result<()> {
+    let label_same_group = program.allocate_label();
+    program.emit_insn(Insn::Le {
+        lhs: regs.position,
+        rhs: regs.group_end,
+        target_pc: label_same_group,
+        flags: CmpInsFlags::default(),
+        collation: None,
+    });
+    program.add_comment(program.offset(), "start of a new peer group");
+    program.emit_insn(Insn::Copy {
+        src_reg: regs.position,
+        dst_reg: regs.group_start,
+        extra_amount: 0,
+    });
+    program.emit_insn(Insn::AddImm {
+        register: regs.group_count,
+        value: 1,
+    });
+    emit_peer_scan(
+        program,
+        window,
+        plan,
+        regs,
+        cursor_buffer_seek,
+        regs.position,
+        1,
+        regs.group_end,
+    )?;
+    program.preassign_label_to_next_insn(label_same_group);
+    Ok(())
+}
+
+/// Emits bytecode that finds the first (`step` = -1) or last (`step` = 1) row of the peer
+/// group containing the row at position `reg_from`, and stores its position in `reg_dest`.
+/// Peers are found by comparing the ORDER BY columns of neighbouring rows; without ORDER BY
+/// every row of the partition is a peer.
+#[allow(clippy::too_many_arguments)]
+fn emit_peer_scan(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    plan: &SelectPlan,
+    regs: &PartitionRegisters,
+    cursor_buffer_seek: CursorID,
+    reg_from: usize,
+    step: i64,
+    reg_dest: usize,
+) -> Result<()> {
+    if window.order_by.is_empty() {
+        match step > 0 {
+            true => program.emit_insn(Insn::Copy {
+                src_reg: regs.size,
+                dst_reg: reg_dest,
+                extra_amount: 0,
+            }),
+            false => program.emit_int(1, reg_dest),
+        }
+        return Ok(());
+    }
+
+    let order_by_len = window.order_by.len();
+    let order_by_columns = window
+        .order_by
+        .iter()
+        .map(|(expr, _, _)| match expr {
+            Expr::Column { column, .. } => *column,
+            _ => unreachable!("expected Column, got {:?}", expr),
+        })
+        .collect::<Vec<_>>();
+    let reg_from_keys = program.alloc_registers(order_by_len);
+    let reg_next_keys = program.alloc_registers(order_by_len);
+    let reg_next_position = program.alloc_register();
+    let label_scan = program.allocate_label();
+    let label_peer = program.allocate_label();
+    let label_scan_done = program.allocate_label();
+
+    program.emit_insn(Insn::Copy {
+        src_reg: reg_from,
+        dst_reg: reg_dest,
+        extra_amount: 0,
+    });
+    program.emit_insn(Insn::SeekRowid {
+        cursor_id: cursor_buffer_seek,
+        src_reg: reg_from,
+        target_pc: label_scan_done,
+    });
+    for (i, column) in order_by_columns.iter().enumerate() {
+        program.emit_insn(Insn::Column {
+            cursor_id: cursor_buffer_seek,
+            column: *column,
+            dest: reg_from_keys + i,
+            default: None,
+        });
+    }
+
+    program.preassign_label_to_next_insn(label_scan);
+    program.emit_insn(Insn::Copy {
+        src_reg: reg_dest,
+        dst_reg: reg_next_position,
+        extra_amount: 0,
+    });
+    program.emit_insn(Insn::AddImm {
+        register: reg_next_position,
+        value: step,
+    });
+    emit_jump_if_outside_partition(program, regs, reg_next_position, label_scan_done);
+    program.emit_insn(Insn::SeekRowid {
+        cursor_id: cursor_buffer_seek,
+        src_reg: reg_next_position,
+        target_pc: label_scan_done,
+    });
+    for (i, column) in order_by_columns.iter().enumerate() {
+        program.emit_insn(Insn::Column {
+            cursor_id: cursor_buffer_seek,
+            column: *column,
+            dest: reg_next_keys + i,
+            default: None,
+        });
+    }
+    program.emit_insn(Insn::Compare {
+        start_reg_a: reg_from_keys,
+        start_reg_b: reg_next_keys,
+        count: order_by_len,
+        key_info: order_by_key_info(window, plan)?,
+    });
+    program.emit_insn(Insn::Jump {
+        target_pc_lt: label_scan_done,
+        target_pc_eq: label_peer,
+        target_pc_gt: label_scan_done,
+    });
+    program.preassign_label_to_next_insn(label_peer);
+    program.emit_insn(Insn::Copy {
+        src_reg: reg_next_position,
+        dst_reg: reg_dest,
+        extra_amount: 0,
+    });
+    program.emit_insn(Insn::Goto {
+        target_pc: label_scan,
+    });
+    program.preassign_label_to_next_insn(label_scan_done);
+
+    Ok(())
+}
+
+/// Emits a jump to `target_pc` if `reg_position` is not the position of a row of the
+/// current partition.
+fn emit_jump_if_outside_partition(
+    program: &mut ProgramBuilder,
+    regs: &PartitionRegisters,
+    reg_position: usize,
+    target_pc: BranchOffset,
+) {
+    program.emit_insn(Insn::Lt {
+        lhs: reg_position,
+        rhs: regs.one,
+        target_pc,
+        flags: CmpInsFlags::default(),
+        collation: None,
+    });
+    program.emit_insn(Insn::Gt {
+        lhs: reg_position,
+        rhs: regs.size,
+        target_pc,
+        flags: CmpInsFlags::default(),
+        collation: None,
+    });
+}
+
+/// Returns the offset expression of a `<expr> PRECEDING` or `<expr> FOLLOWING` frame bound.
+fn frame_bound_offset(bound: &FrameBound) -> Option<&Expr> {
+    match bound {
+        FrameBound::Preceding(expr) | FrameBound::Following(expr) => Some(expr),
+        _ => None,
+    }
+}
+
+/// Emits bytecode evaluating the frame offsets into their registers and checking that they
+/// are valid: non-negative integers for ROWS and GROUPS frames, non-negative numbers for
+/// RANGE frames.
+fn emit_frame_offsets(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    regs: &PartitionRegisters,
+    resolver: &Resolver,
+) -> Result<()> {
+    let Some(frame) = &window.frame else {
+        return Ok(());
+    };
+    let bounds = [
+        (&frame.start, regs.frame_start_offset, "starting"),
+        (&frame.end, regs.frame_end_offset, "ending"),
+    ];
+    for (bound, reg_offset, which) in bounds {
+        let (Some(expr), Some(reg_offset)) = (frame_bound_offset(bound), reg_offset) else {
+            continue;
+        };
+        translate_expr(program, None, expr, reg_offset, resolver)?;
+
+        let (affinity, kind) = match frame.mode {
+            FrameMode::Range => (Affinity::Numeric, "number"),
+            FrameMode::Rows | FrameMode::Groups => (Affinity::Integer, "integer"),
+        };
+        let reg_check = program.alloc_register();
+        let label_invalid = program.allocate_label();
+        let label_valid = program.allocate_label();
+        program.emit_insn(Insn::IsNull {
+            reg: reg_offset,
+            target_pc: label_invalid,
+        });
+        program.emit_insn(Insn::Copy {
+            src_reg: reg_offset,
+            dst_reg: reg_check,
+            extra_amount: 0,
+        });
+        program.emit_insn(Insn::Cast {
+            reg: reg_check,
+            affinity,
+        });
+        program.emit_insn(Insn::Ne {
+            lhs: reg_offset,
+            rhs: reg_check,
+            target_pc: label_invalid,
+            flags: CmpInsFlags::default(),
+            collation: None,
+        });
+        program.emit_int(0, reg_check);
+        program.emit_insn(Insn::Ge {
+            lhs: reg_offset,
+            rhs: reg_check,
+            target_pc: label_valid,
+            flags: CmpInsFlags::default(),
+            collation: None,
+        });
+        program.preassign_label_to_next_insn(label_invalid);
+        program.emit_insn(Insn::Halt {
+            err_code: SQLITE_ERROR,
+            description: format!("frame {which} offset must be a non-negative {kind}"),
+            on_error: None,
+            description_reg: None,
+        });
+        program.preassign_label_to_next_insn(label_valid);
+    }
+    Ok(())
+}
+
+/// Emits bytecode computing the positions of the first and last rows of the current row's
+/// frame. The frame is empty when its start is past its end.
+fn emit_frame_bounds(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    plan: &SelectPlan,
+    regs: &PartitionRegisters,
+    cursor_buffer_read: CursorID,
+    cursor_buffer_seek: CursorID,
+) -> Result<()> {
+    let frame = match &window.frame {
+        Some(frame) if !frame.is_default() => frame,
+        _ => {
+            // The default frame: RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW.
+            program.emit_int(1, regs.frame_start);
+            program.emit_insn(Insn::Copy {
+                src_reg: regs.group_end,
+                dst_reg: regs.frame_end,
+                extra_amount: 0,
+            });
+            return Ok(());
+        }
+    };
+
+    let bounds = [
+        (&frame.start, regs.frame_start, regs.frame_start_offset),
+        (&frame.end, regs.frame_end, regs.frame_end_offset),
+    ];
+    for (bound, reg_dest, reg_offset) in bounds {
+        let is_start = reg_dest == regs.frame_start;
+        match bound {
+            FrameBound::UnboundedPreceding => program.emit_int(1, reg_dest),
+            FrameBound::UnboundedFollowing => program.emit_insn(Insn::Copy {
+                src_reg: regs.size,
+                dst_reg: reg_dest,
+                extra_amount: 0,
+            }),
+            FrameBound::CurrentRow => {
+                // In RANGE and GROUPS frames, the current row stands for its whole peer group.
+                let src_reg = match (frame.mode, is_start) {
+                    (FrameMode::Rows, _) => regs.position,
+                    (_, true) => regs.group_start,
+                    (_, false) => regs.group_end,
+                };
+                program.emit_insn(Insn::Copy {
+                    src_reg,
+                    dst_reg: reg_dest,
+                    extra_amount: 0,
+                });
+            }
+            FrameBound::Preceding(_) | FrameBound::Following(_) => {
+                let preceding = matches!(bound, FrameBound::Preceding(_));
+                let reg_offset = reg_offset.expect("frame offset register must exist");
+                match frame.mode {
+                    FrameMode::Rows => match preceding {
+                        true => program.emit_insn(Insn::Subtract {
+                            lhs: regs.position,
+                            rhs: reg_offset,
+                            dest: reg_dest,
+                        }),
+                        false => program.emit_insn(Insn::Add {
+                            lhs: regs.position,
+                            rhs: reg_offset,
+                            dest: reg_dest,
+                        }),
+                    },
+                    FrameMode::Groups => emit_groups_frame_bound(
+                        program,
+                        window,
+                        plan,
+                        regs,
+                        cursor_buffer_seek,
+                        reg_offset,
+                        preceding,
+                        reg_dest,
+                    )?,
+                    FrameMode::Range => emit_range_frame_bound(
+                        program,
+                        window,
+                        regs,
+                        cursor_buffer_read,
+                        cursor_buffer_seek,
+                        reg_offset,
+                        preceding,
+                        reg_dest,
+                    ),
+                }
+            }
+        }
+    }
+
+    // ROWS offsets may point before the first or after the last row of the partition.
+    let label_start_in_bounds = program.allocate_label();
+    program.emit_insn(Insn::Ge {
+        lhs: regs.frame_start,
+        rhs: regs.one,
+        target_pc: label_start_in_bounds,
+        flags: CmpInsFlags::default(),
+        collation: None,
+    });
+    program.emit_int(1, regs.frame_start);
+    program.preassign_label_to_next_insn(label_start_in_bounds);
+    let label_end_in_bounds = program.allocate_label();
+    program.emit_insn(Insn::Le {
+        lhs: regs.frame_end,
+        rhs: regs.size,
+        target_pc: label_end_in_bounds,
+        flags: CmpInsFlags::default(),
+        collation: None,
+    });
+    program.emit_insn(Insn::Copy {
+        src_reg: regs.size,
+        dst_reg: regs.frame_end,
+        extra_amount: 0,
+    });
+    program.preassign_label_to_next_insn(label_end_in_bounds);
+
+    Ok(())
+}
+
+/// Emits bytecode for a `<n> PRECEDING` or `<n> FOLLOWING` bound of a GROUPS frame, which
+/// starts (or ends) `n` peer groups away from the current row's peer group.
+#[allow(clippy::too_many_arguments)]
+fn emit_groups_frame_bound(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    plan: &SelectPlan,
+    regs: &PartitionRegisters,
+    cursor_buffer_seek: CursorID,
+    reg_offset: usize,
+    preceding: bool,
+    reg_dest: usize,
+) -> Result<()> {
+    let is_start = reg_dest == regs.frame_start;
+    let reg_group_start = program.alloc_register();
+    let reg_group_end = program.alloc_register();
+    let reg_remaining = program.alloc_register();
+    program.emit_insn(Insn::Copy {
+        src_reg: regs.group_start,
+        dst_reg: reg_group_start,
+        extra_amount: 0,
+    });
+    program.emit_insn(Insn::Copy {
+        src_reg: regs.group_end,
+        dst_reg: reg_group_end,
+        extra_amount: 0,
+    });
+    program.emit_insn(Insn::Copy {
+        src_reg: reg_offset,
+        dst_reg: reg_remaining,
+        extra_amount: 0,
+    });
+
+    let label_loop = program.allocate_label();
+    let label_step = program.allocate_label();
+    let label_no_more_groups = program.allocate_label();
+    let label_done = program.allocate_label();
+    program.preassign_label_to_next_insn(label_loop);
+    program.emit_insn(Insn::IfPos {
+        reg: reg_remaining,
+        target_pc: label_step,
+        decrement_by: 1,
+    });
+    program.emit_insn(Insn::Goto {
+        target_pc: label_done,
+    });
+    program.preassign_label_to_next_insn(label_step);
+    if preceding {
+        program.emit_insn(Insn::Le {
+            lhs: reg_group_start,
+            rhs: regs.one,
+            target_pc: label_no_more_groups,
+            flags: CmpInsFlags::default(),
+            collation: None,
+        });
+        program.emit_insn(Insn::Subtract {
+            lhs: reg_group_start,
+            rhs: regs.one,
+            dest: reg_group_end,
+        });
+        emit_peer_scan(
+            program,
+            window,
+            plan,
+            regs,
+            cursor_buffer_seek,
+            reg_group_end,
+            -1,
+            reg_group_start,
+        )?;
+    } else {
+        program.emit_insn(Insn::Ge {
+            lhs: reg_group_end,
+            rhs: regs.size,
+            target_pc: label_no_more_groups,
+            flags: CmpInsFlags::default(),
+            collation: None,
+        });
+        program.emit_insn(Insn::Add {
+            lhs: reg_group_end,
+            rhs: regs.one,
+            dest: reg_group_start,
+        });
+        emit_peer_scan(
+            program,
+            window,
+            plan,
+            regs,
+            cursor_buffer_seek,
+            reg_group_start,
+            1,
+            reg_group_end,
+        )?;
+    }
+    program.emit_insn(Insn::Goto {
+        target_pc: label_loop,
+    });
+
+    // Running out of groups clamps the bound to the partition, except when the frame
+    // would start after the last group or end before the first one, making it empty.
+    program.preassign_label_to_next_insn(label_no_more_groups);
+    match (preceding, is_start) {
+        (false, true) => program.emit_insn(Insn::Add {
+            lhs: regs.size,
+            rhs: regs.one,
+            dest: reg_group_start,
+        }),
+        (true, false) => program.emit_int(0, reg_group_end),
+        _ => {}
+    }
+
+    program.preassign_label_to_next_insn(label_done);
+    program.emit_insn(Insn::Copy {
+        src_reg: if is_start {
+            reg_group_start
+        } else {
+            reg_group_end
+        },
+        dst_reg: reg_dest,
+        extra_amount: 0,
+    });
+    Ok(())
+}
+
+/// Emits bytecode for a `<n> PRECEDING` or `<n> FOLLOWING` bound of a RANGE frame, which
+/// covers the rows whose ORDER BY value is within `n` of the current row's value.
+///
+/// Starting from the edge of the current peer group, the bound is moved one row at a time
+/// while the ORDER BY value of the row stays within range. Rows with a NULL ORDER BY value are
+/// never in range of a non-NULL value; for a row with a NULL value, the bound is its peer group.
+#[allow(clippy::too_many_arguments)]
+fn emit_range_frame_bound(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    regs: &PartitionRegisters,
+    cursor_buffer_read: CursorID,
+    cursor_buffer_seek: CursorID,
+    reg_offset: usize,
+    preceding: bool,
+    reg_dest: usize,
+) {
+    let is_start = reg_dest == regs.frame_start;
+    let (order_by_expr, sort_order, _) = &window.order_by[0];
+    let Expr::Column {
+        column: order_by_column,
+        ..
+    } = order_by_expr
+    else {
+        unreachable!("expected Column, got {:?}", order_by_expr);
+    };
+    let ascending = *sort_order == SortOrder::Asc;
+
+    let reg_current = program.alloc_register();
+    let reg_target = program.alloc_register();
+    let reg_candidate = program.alloc_register();
+    let reg_candidate_value = program.alloc_register();
+    let label_loop = program.allocate_label();
+    let label_advance = program.allocate_label();
+    let label_done = program.allocate_label();
+
+    program.emit_insn(Insn::Copy {
+        src_reg: if is_start {
+            regs.group_start
+        } else {
+            regs.group_end
+        },
+        dst_reg: reg_dest,
+        extra_amount: 0,
+    });
+    program.emit_insn(Insn::Column {
+        cursor_id: cursor_buffer_read,
+        column: *order_by_column,
+        dest: reg_current,
+        default: None,
+    });
+    program.emit_insn(Insn::IsNull {
+        reg: reg_current,
+        target_pc: label_done,
+    });
+    // PRECEDING moves towards the start of the partition, which holds smaller values in
+    // ascending order and larger values in descending order.
+    match preceding == ascending {
+        true => program.emit_insn(Insn::Subtract {
+            lhs: reg_current,
+            rhs: reg_offset,
+            dest: reg_target,
+        }),
+        false => program.emit_insn(Insn::Add {
+            lhs: reg_current,
+            rhs: reg_offset,
+            dest: reg_target,
+        }),
+    }
+
+    // A PRECEDING start and a FOLLOWING end extend the frame past the current peer group,
+    // so they look at the next row; the other two shrink it and look at the bound itself.
+    let step: i64 = if preceding { -1 } else { 1 };
+    let extends = preceding == is_start;
+    let in_range_op = match (preceding, is_start, ascending) {
+        (true, true, true) | (false, false, false) => ast::Operator::GreaterEquals,
+        (true, true, false) | (false, false, true) => ast::Operator::LessEquals,
+        (false, true, true) | (true, false, false) => ast::Operator::Less,
+        (false, true, false) | (true, false, true) => ast::Operator::Greater,
+    };
+
+    program.preassign_label_to_next_insn(label_loop);
+    program.emit_insn(Insn::Copy {
+        src_reg: reg_dest,
+        dst_reg: reg_candidate,
+        extra_amount: 0,
+    });
+    if extends {
+        program.emit_insn(Insn::AddImm {
+            register: reg_candidate,
+            value: step,
+        });
+    }
+    emit_jump_if_outside_partition(program, regs, reg_candidate, label_done);
+    program.emit_insn(Insn::SeekRowid {
+        cursor_id: cursor_buffer_seek,
+        src_reg: reg_candidate,
+        target_pc: label_done,
+    });
+    program.emit_insn(Insn::Column {
+        cursor_id: cursor_buffer_seek,
+        column: *order_by_column,
+        dest: reg_candidate_value,
+        default: None,
+    });
+    program.emit_insn(Insn::IsNull {
+        reg: reg_candidate_value,
+        target_pc: label_done,
+    });
+    program.emit_insn(comparison_insn(
+        in_range_op,
+        reg_candidate_value,
+        reg_target,
+        label_advance,
+    ));
+    program.emit_insn(Insn::Goto {
+        target_pc: label_done,
+    });
+    program.preassign_label_to_next_insn(label_advance);
+    program.emit_insn(Insn::AddImm {
+        register: reg_dest,
+        value: step,
+    });
+    program.emit_insn(Insn::Goto {
+        target_pc: label_loop,
+    });
+    program.preassign_label_to_next_insn(label_done);
+}
+
+/// Builds an instruction jumping to `target_pc` if `lhs <op> rhs` holds.
+fn comparison_insn(op: ast::Operator, lhs: usize, rhs: usize, target_pc: BranchOffset) -> Insn {
+    let flags = CmpInsFlags::default();
+    match op {
+        ast::Operator::Less => Insn::Lt {
+            lhs,
+            rhs,
+            target_pc,
+            flags,
+            collation: None,
+        },
+        ast::Operator::LessEquals => Insn::Le {
+            lhs,
+            rhs,
+            target_pc,
+            flags,
+            collation: None,
+        },
+        ast::Operator::Greater => Insn::Gt {
+            lhs,
+            rhs,
+            target_pc,
+            flags,
+            collation: None,
+        },
+        ast::Operator::GreaterEquals => Insn::Ge {
+            lhs,
+            rhs,
+            target_pc,
+            flags,
+            collation: None,
+        },
+        _ => unreachable!("unexpected comparison operator {:?}", op),
+    }
+}
+
+/// Emits a jump to `target_pc` if the row at `reg_position` is removed from the current
+/// row's frame by the frame's EXCLUDE clause.
+fn emit_jump_if_excluded(
+    program: &mut ProgramBuilder,
+    exclude: &FrameExclude,
+    regs: &PartitionRegisters,
+    reg_position: usize,
+    target_pc: BranchOffset,
+) {
+    match exclude {
+        FrameExclude::NoOthers => {}
+        FrameExclude::CurrentRow => {
+            program.emit_insn(Insn::Eq {
+                lhs: reg_position,
+                rhs: regs.position,
+                target_pc,
+                flags: CmpInsFlags::default(),
+                collation: None,
+            });
+        }
+        FrameExclude::Group | FrameExclude::Ties => {
+            let label_not_excluded = program.allocate_label();
+            if *exclude == FrameExclude::Ties {
+                program.emit_insn(Insn::Eq {
+                    lhs: reg_position,
+                    rhs: regs.position,
+                    target_pc: label_not_excluded,
+                    flags: CmpInsFlags::default(),
+                    collation: None,
+                });
+            }
+            program.emit_insn(Insn::Lt {
+                lhs: reg_position,
+                rhs: regs.group_start,
+                target_pc: label_not_excluded,
+                flags: CmpInsFlags::default(),
+                collation: None,
+            });
+            program.emit_insn(Insn::Gt {
+                lhs: reg_position,
+                rhs: regs.group_end,
+                target_pc: label_not_excluded,
+                flags: CmpInsFlags::default(),
+                collation: None,
+            });
+            program.emit_insn(Insn::Goto { target_pc });
+            program.preassign_label_to_next_insn(label_not_excluded);
+        }
+    }
+}
+
+/// Emits bytecode that adds the rows between the last accumulated row and the end of the
+/// current frame to the aggregate accumulators, then computes the aggregate values.
+///
+/// Frame ends never move backwards, so when every frame starts at the first row of the
+/// partition the accumulators are only extended. Otherwise they are reset and the whole
+/// frame is aggregated again for every row.
+#[allow(clippy::too_many_arguments)]
+fn emit_partition_aggregates(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    plan: &SelectPlan,
+    resolver: &Resolver,
+    regs: &PartitionRegisters,
+    cursor_buffer_seek: CursorID,
+    reg_acc_start: usize,
+    reg_acc_result_start: usize,
+) -> Result<()> {
+    if !window
+        .functions
+        .iter()
+        .any(|func| matches!(func.func, WindowFunctionKind::Agg(_)))
+    {
+        return Ok(());
+    }
+
+    let exclude = window
+        .frame
+        .as_ref()
+        .map_or(FrameExclude::NoOthers, |frame| frame.exclude.clone());
+    let is_cumulative = window.frame.as_ref().is_none_or(|frame| {
+        frame.start == FrameBound::UnboundedPreceding && frame.exclude == FrameExclude::NoOthers
+    });
+    if !is_cumulative {
+        program.emit_null(
+            reg_acc_start,
+            Some(reg_acc_start + window.functions.len() - 1),
+        );
+        program.emit_insn(Insn::Subtract {
+            lhs: regs.frame_start,
+            rhs: regs.one,
+            dest: regs.agg_end,
+        });
+    }
+
+    let label_step = program.allocate_label();
+    let label_done = program.allocate_label();
+    program.preassign_label_to_next_insn(label_step);
+    program.emit_insn(Insn::Ge {
+        lhs: regs.agg_end,
+        rhs: regs.frame_end,
+        target_pc: label_done,
+        flags: CmpInsFlags::default(),
+        collation: None,
+    });
+    program.emit_insn(Insn::AddImm {
+        register: regs.agg_end,
+        value: 1,
+    });
+    emit_jump_if_excluded(program, &exclude, regs, regs.agg_end, label_step);
+    program.emit_insn(Insn::SeekRowid {
+        cursor_id: cursor_buffer_seek,
+        src_reg: regs.agg_end,
+        target_pc: label_done,
+    });
+    for (i, func) in window.functions.iter().enumerate() {
+        let WindowFunctionKind::Agg(agg_func) = &func.func else {
+            continue;
+        };
+        // Arguments are read from the buffered row rather than evaluated, as the source
+        // registers may already hold the first row of the next partition.
+        let func_expr = func.rewritten_expr.as_ref().unwrap_or(&func.original_expr);
+        let args: &[Box<Expr>] = match func_expr {
+            Expr::FunctionCall { args, .. } => args,
+            _ => &[],
+        };
+        let label_skip_step = program.allocate_label();
+        if let Some(filter_expr) = window_function_filter(func) {
+            let Expr::Column { column, .. } = filter_expr else {
+                unreachable!("expected Column, got {:?}", filter_expr);
+            };
+            let reg_filter = program.alloc_register();
+            program.emit_insn(Insn::Column {
+                cursor_id: cursor_buffer_seek,
+                column: *column,
+                dest: reg_filter,
+                default: None,
+            });
+            program.emit_insn(Insn::IfNot {
+                reg: reg_filter,
+                target_pc: label_skip_step,
+                jump_if_null: true,
+            });
+        }
+        let arg_columns = window_function_arg_columns(func);
+        let reg_args = program.alloc_registers(arg_columns.len().max(1));
+        for (j, column) in arg_columns.iter().enumerate() {
+            program.emit_insn(Insn::Column {
+                cursor_id: cursor_buffer_seek,
+                column: *column,
+                dest: reg_args + j,
+                default: None,
+            });
+        }
+        let aggregate = Aggregate::new(
+            agg_func.clone(),
+            args,
+            func_expr,
+            Distinctness::NonDistinct,
+            None,
+            &[],
+        );
+        translate_aggregation_step(
+            program,
+            &plan.table_references,
+            AggArgumentSource::new_from_registers(reg_args, &aggregate),
+            reg_acc_start + i,
+            resolver,
+        )?;
+        program.preassign_label_to_next_insn(label_skip_step);
+    }
+    program.emit_insn(Insn::Goto {
+        target_pc: label_step,
+    });
+    program.preassign_label_to_next_insn(label_done);
+
+    for (i, func) in window.functions.iter().enumerate() {
+        if let WindowFunctionKind::Agg(agg_func) = &func.func {
+            // AggValue leaves the destination untouched for most aggregates over an empty
+            // frame, which must produce NULL rather than the previous row's value.
+            program.emit_null(reg_acc_result_start + i, None);
+            program.emit_insn(Insn::AggValue {
+                acc_reg: reg_acc_start + i,
+                dest_reg: reg_acc_result_start + i,
+                func: agg_func.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Emits bytecode computing the value of every non-aggregate window function for the
+/// current row of a buffered partition.
+fn emit_partition_window_function_values(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    regs: &PartitionRegisters,
+    cursor_buffer_read: CursorID,
+    cursor_buffer_seek: CursorID,
+    reg_acc_result_start: usize,
+) {
+    for (i, func) in window.functions.iter().enumerate() {
+        let WindowFunctionKind::Window(window_func) = &func.func else {
+            continue;
+        };
+        let reg_result = reg_acc_result_start + i;
+        let args = window_function_arg_columns(func);
+        match window_func {
+            WindowFunc::RowNumber => {
+                program.emit_insn(Insn::Copy {
+                    src_reg: regs.position,
+                    dst_reg: reg_result,
+                    extra_amount: 0,
+                });
+            }
+            WindowFunc::Rank => {
+                program.emit_insn(Insn::Copy {
+                    src_reg: regs.group_start,
+                    dst_reg: reg_result,
+                    extra_amount: 0,
+                });
+            }
+            WindowFunc::DenseRank => {
+                program.emit_insn(Insn::Copy {
+                    src_reg: regs.group_count,
+                    dst_reg: reg_result,
+                    extra_amount: 0,
+                });
+            }
+            WindowFunc::PercentRank => {
+                // (rank - 1) / (partition size - 1), or 0.0 for a single-row partition.
+                let label_done = program.allocate_label();
+                let reg_divisor = program.alloc_register();
+                program.emit_insn(Insn::Real {
+                    value: 0.0,
+                    dest: reg_result,
+                });
+                program.emit_insn(Insn::Le {
+                    lhs: regs.size,
+                    rhs: regs.one,
+                    target_pc: label_done,
+                    flags: CmpInsFlags::default(),
+                    collation: None,
+                });
+                program.emit_insn(Insn::Subtract {
+                    lhs: regs.group_start,
+                    rhs: regs.one,
+                    dest: reg_result,
+                });
+                program.emit_insn(Insn::RealAffinity {
+                    register: reg_result,
+                });
+                program.emit_insn(Insn::Subtract {
+                    lhs: regs.size,
+                    rhs: regs.one,
+                    dest: reg_divisor,
+                });
+                program.emit_insn(Insn::Divide {
+                    lhs: reg_result,
+                    rhs: reg_divisor,
+                    dest: reg_result,
+                });
+                program.preassign_label_to_next_insn(label_done);
+            }
+            WindowFunc::CumeDist => {
+                // Number of rows up to the end of the peer group / partition size.
+                program.emit_insn(Insn::Copy {
+                    src_reg: regs.group_end,
+                    dst_reg: reg_result,
+                    extra_amount: 0,
+                });
+                program.emit_insn(Insn::RealAffinity {
+                    register: reg_result,
+                });
+                program.emit_insn(Insn::Divide {
+                    lhs: reg_result,
+                    rhs: regs.size,
+                    dest: reg_result,
+                });
+            }
+            WindowFunc::Ntile => {
+                emit_ntile(program, regs, cursor_buffer_read, args[0], reg_result);
+            }
+            WindowFunc::Lag | WindowFunc::Lead => {
+                let reg_offset = match args.get(1) {
+                    Some(column) => {
+                        let reg = program.alloc_register();
+                        program.emit_insn(Insn::Column {
+                            cursor_id: cursor_buffer_read,
+                            column: *column,
+                            dest: reg,
+                            default: None,
+                        });
+                        reg
+                    }
+                    None => regs.one,
+                };
+                let reg_target = program.alloc_register();
+                if *window_func == WindowFunc::Lag {
+                    program.emit_insn(Insn::Subtract {
+                        lhs: regs.position,
+                        rhs: reg_offset,
+                        dest: reg_target,
+                    });
+                } else {
+                    program.emit_insn(Insn::Add {
+                        lhs: regs.position,
+                        rhs: reg_offset,
+                        dest: reg_target,
+                    });
+                }
+                let label_default = program.allocate_label();
+                let label_done = program.allocate_label();
+                // The buffer only holds the current partition, so any position outside
+                // of it is simply not found.
+                program.emit_insn(Insn::SeekRowid {
+                    cursor_id: cursor_buffer_seek,
+                    src_reg: reg_target,
+                    target_pc: label_default,
+                });
+                program.emit_insn(Insn::Column {
+                    cursor_id: cursor_buffer_seek,
+                    column: args[0],
+                    dest: reg_result,
+                    default: None,
+                });
+                program.emit_insn(Insn::Goto {
+                    target_pc: label_done,
+                });
+                program.preassign_label_to_next_insn(label_default);
+                match args.get(2) {
+                    Some(column) => {
+                        program.emit_insn(Insn::Column {
+                            cursor_id: cursor_buffer_read,
+                            column: *column,
+                            dest: reg_result,
+                            default: None,
+                        });
+                    }
+                    None => program.emit_null(reg_result, None),
+                }
+                program.preassign_label_to_next_insn(label_done);
+            }
+            WindowFunc::FirstValue | WindowFunc::LastValue => {
+                emit_frame_row_value(
+                    program,
+                    window,
+                    regs,
+                    cursor_buffer_seek,
+                    regs.one,
+                    *window_func == WindowFunc::LastValue,
+                    args[0],
+                    reg_result,
+                );
+            }
+            WindowFunc::NthValue => {
+                let reg_n = program.alloc_register();
+                program.emit_insn(Insn::Column {
+                    cursor_id: cursor_buffer_read,
+                    column: args[1],
+                    dest: reg_n,
+                    default: None,
+                });
+                emit_check_nth_value_argument(program, reg_n);
+                emit_frame_row_value(
+                    program,
+                    window,
+                    regs,
+                    cursor_buffer_seek,
+                    reg_n,
+                    false,
+                    args[0],
+                    reg_result,
+                );
+            }
+        }
+    }
+}
+
+/// Emits bytecode reading `column` of the `n`-th row of the current frame, counting from
+/// its first row, or from its last row if `from_end` is set. The result is NULL if the frame
+/// has fewer than `n` rows.
+#[allow(clippy::too_many_arguments)]
+fn emit_frame_row_value(
+    program: &mut ProgramBuilder,
+    window: &Window,
+    regs: &PartitionRegisters,
+    cursor_buffer_seek: CursorID,
+    reg_n: usize,
+    from_end: bool,
+    column: usize,
+    reg_result: usize,
+) {
+    let exclude = window
+        .frame
+        .as_ref()
+        .map_or(FrameExclude::NoOthers, |frame| frame.exclude.clone());
+    let reg_target = program.alloc_register();
+    let label_found = program.allocate_label();
+    let label_done = program.allocate_label();
+    program.emit_null(reg_result, None);
+
+    if exclude == FrameExclude::NoOthers {
+        // The frame is contiguous, so the row's position can be computed directly.
+        match from_end {
+            true => program.emit_insn(Insn::Subtract {
+                lhs: regs.frame_end,
+                rhs: reg_n,
+                dest: reg_target,
+            }),
+            false => program.emit_insn(Insn::Add {
+                lhs: regs.frame_start,
+                rhs: reg_n,
+                dest: reg_target,
+            }),
+        }
+        program.emit_insn(Insn::AddImm {
+            register: reg_target,
+            value: if from_end { 1 } else { -1 },
+        });
+        emit_jump_if_outside_frame(program, regs, reg_target, label_done);
+    } else {
+        // Walk the frame, skipping excluded rows, until the n-th remaining row is reached.
+        let reg_remaining = program.alloc_register();
+        let label_loop = program.allocate_label();
+        let label_next = program.allocate_label();
+        program.emit_insn(Insn::Copy {
+            src_reg: if from_end {
+                regs.frame_end
+            } else {
+                regs.frame_start
+            },
+            dst_reg: reg_target,
+            extra_amount: 0,
+        });
+        program.emit_insn(Insn::Subtract {
+            lhs: reg_n,
+            rhs: regs.one,
+            dest: reg_remaining,
+        });
+        program.preassign_label_to_next_insn(label_loop);
+        emit_jump_if_outside_frame(program, regs, reg_target, label_done);
+        emit_jump_if_excluded(program, &exclude, regs, reg_target, label_next);
+        program.emit_insn(Insn::IfPos {
+            reg: reg_remaining,
+            target_pc: label_next,
+            decrement_by: 1,
+        });
+        program.emit_insn(Insn::Goto {
+            target_pc: label_found,
+        });
+        program.preassign_label_to_next_insn(label_next);
+        program.emit_insn(Insn::AddImm {
+            register: reg_target,
+            value: if from_end { -1 } else { 1 },
+        });
+        program.emit_insn(Insn::Goto {
+            target_pc: label_loop,
+        });
+    }
+
+    program.preassign_label_to_next_insn(label_found);
+    program.emit_insn(Insn::SeekRowid {
+        cursor_id: cursor_buffer_seek,
+        src_reg: reg_target,
+        target_pc: label_done,
+    });
+    program.emit_insn(Insn::Column {
+        cursor_id: cursor_buffer_seek,
+        column,
+        dest: reg_result,
+        default: None,
+    });
+    program.preassign_label_to_next_insn(label_done);
+}
+
+/// Emits a jump to `target_pc` if `reg_position` is outside of the current frame.
+fn emit_jump_if_outside_frame(
+    program: &mut ProgramBuilder,
+    regs: &PartitionRegisters,
+    reg_position: usize,
+    target_pc: BranchOffset,
+) {
+    program.emit_insn(Insn::Lt {
+        lhs: reg_position,
+        rhs: regs.frame_start,
+        target_pc,
+        flags: CmpInsFlags::default(),
+        collation: None,
+    });
+    program.emit_insn(Insn::Gt {
+        lhs: reg_position,
+        rhs: regs.frame_end,
+        target_pc,
+        flags: CmpInsFlags::default(),
+        collation: None,
+    });
+}
+
+/// Emits bytecode that converts `reg` to an integer and halts with `message` unless it is
+/// positive.
+fn emit_check_positive_integer(program: &mut ProgramBuilder, reg: usize, message: &str) {
+    let label_ok = program.allocate_label();
+    program.emit_insn(Insn::Cast {
+        reg,
+        affinity: Affinity::Integer,
+    });
+    program.emit_insn(Insn::IfPos {
+        reg,
+        target_pc: label_ok,
+        decrement_by: 0,
+    });
+    program.emit_insn(Insn::Halt {
+        err_code: SQLITE_ERROR,
+        description: message.to_string(),
+        on_error: None,
+        description_reg: None,
+    });
+    program.preassign_label_to_next_insn(label_ok);
+}
+
+/// Emits bytecode rejecting an nth_value() N that is not a positive integer. Like SQLite, N
+/// gets numeric affinity first, so '2' and 2.0 are accepted while 1.5, '1.5' and 'abc' are not.
+/// On success `reg` holds N as an integer.
+fn emit_check_nth_value_argument(program: &mut ProgramBuilder, reg: usize) {
+    let reg_int = program.alloc_register();
+    let label_invalid = program.allocate_label();
+    let label_ok = program.allocate_label();
+    program.emit_insn(Insn::Affinity {
+        start_reg: reg,
+        count: NonZeroUsize::MIN,
+        affinities: Affinity::Numeric.aff_mask().to_string(),
+    });
+    program.emit_insn(Insn::Copy {
+        src_reg: reg,
+        dst_reg: reg_int,
+        extra_amount: 0,
+    });
+    program.emit_insn(Insn::Cast {
+        reg: reg_int,
+        affinity: Affinity::Integer,
+    });
+    // Anything the cast changed (text, a fractional real, NULL) is not an integer.
+    program.emit_insn(Insn::Ne {
+        lhs: reg,
+        rhs: reg_int,
+        target_pc: label_invalid,
+        flags: CmpInsFlags::default().jump_if_null(),
+        collation: None,
+    });
+    program.emit_insn(Insn::IfPos {
+        reg: reg_int,
+        target_pc: label_ok,
+        decrement_by: 0,
+    });
+    program.preassign_label_to_next_insn(label_invalid);
+    program.emit_insn(Insn::Halt {
+        err_code: SQLITE_ERROR,
+        description: "second argument to nth_value must be a positive integer".to_string(),
+        on_error: None,
+        description_reg: None,
+    });
+    program.preassign_label_to_next_insn(label_ok);
+    program.emit_insn(Insn::Copy {
+        src_reg: reg_int,
+        dst_reg: reg,
+        extra_amount: 0,
+    });
+}
+
+/// Emits bytecode for NTILE(n): the partition is split into `n` buckets whose sizes differ
+/// by at most one, with the larger buckets first.
+fn emit_ntile(
+    program: &mut ProgramBuilder,
+    regs: &PartitionRegisters,
+    cursor_buffer_read: CursorID,
+    arg_column: usize,
+    reg_result: usize,
+) {
+    let reg_buckets = program.alloc_register();
+    program.emit_insn(Insn::Column {
+        cursor_id: cursor_buffer_read,
+        column: arg_column,
+        dest: reg_buckets,
+        default: None,
+    });
+    emit_check_positive_integer(
+        program,
+        reg_buckets,
+        "argument of ntile must be a positive integer",
+    );
+
+    // bucket_size = size / buckets; the first `large_buckets` buckets hold one extra row,
+    // covering the rows before position `small_start` (0-based).
+    let reg_bucket_size = program.alloc_register();
+    let reg_large_buckets = program.alloc_register();
+    let reg_large_bucket_size = program.alloc_register();
+    let reg_small_start = program.alloc_register();
+    let reg_index = program.alloc_register();
+    program.emit_insn(Insn::Divide {
+        lhs: regs.size,
+        rhs: reg_buckets,
+        dest: reg_bucket_size,
+    });
+    program.emit_insn(Insn::Multiply {
+        lhs: reg_buckets,
+        rhs: reg_bucket_size,
+        dest: reg_large_buckets,
+    });
+    program.emit_insn(Insn::Subtract {
+        lhs: regs.size,
+        rhs: reg_large_buckets,
+        dest: reg_large_buckets,
+    });
+    program.emit_insn(Insn::Add {
+        lhs: reg_bucket_size,
+        rhs: regs.one,
+        dest: reg_large_bucket_size,
+    });
+    program.emit_insn(Insn::Multiply {
+        lhs: reg_large_buckets,
+        rhs: reg_large_bucket_size,
+        dest: reg_small_start,
+    });
+    program.emit_insn(Insn::Subtract {
+        lhs: regs.position,
+        rhs: regs.one,
+        dest: reg_index,
+    });
+
+    let label_small_bucket = program.allocate_label();
+    let label_done = program.allocate_label();
+    program.emit_insn(Insn::Ge {
+        lhs: reg_index,
+        rhs: reg_small_start,
+        target_pc: label_small_bucket,
+        flags: CmpInsFlags::default(),
+        collation: None,
+    });
+    program.emit_insn(Insn::Divide {
+        lhs: reg_index,
+        rhs: reg_large_bucket_size,
+        dest: reg_result,
+    });
+    program.emit_insn(Insn::Goto {
+        target_pc: label_done,
+    });
+    program.preassign_label_to_next_insn(label_small_bucket);
+    program.emit_insn(Insn::Subtract {
+        lhs: reg_index,
+        rhs: reg_small_start,
+        dest: reg_result,
+    });
+    program.emit_insn(Insn::Divide {
+        lhs: reg_result,
+        rhs: reg_bucket_size,
+        dest: reg_result,
+    });
+    program.emit_insn(Insn::Add {
+        lhs: reg_result,
+        rhs: reg_large_buckets,
+        dest: reg_result,
+    });
+    program.preassign_label_to_next_insn(label_done);
+    program.emit_insn(Insn::AddImm {
+        register: reg_result,
+        value: 1,
+    });
+}
@@ -211,6 +211,7 @@ pub fn parse_schema_rows(
         materialized_view_info,
         dbsp_state_roots,
         dbsp_state_index_roots,
+        syms,
     )?;
 
     Ok(())
@@ -1734,7 +1735,7 @@ pub fn validate_select_for_unsupported_features(select_stmt: &ast::Select) -> Re
         if let ast::Expr::FunctionCall { order_by, .. } = expr {
             if !order_by.is_empty() {
                 crate::bail_parse_error!(
-                    "ORDER BY clause is not supported yet in aggregate functions"
+                    "ORDER BY clause in aggregate functions is not supported in materialized views"
                 );
             }
         }
@@ -1746,8 +1747,6 @@ pub fn validate_select_for_views(
     select_stmt: &ast::Select,
     view_db_name: Option<&ast::Name>,
 ) -> Result<()> {
-    validate_select_for_unsupported_features(select_stmt)?;
-
     validate_no_cross_db_references(select_stmt, view_db_name)?;
 
     walk_select_expressions(select_stmt, &mut |expr| {
@@ -1775,6 +1774,8 @@ pub fn extract_view_columns(
     let mut tables = Vec::new();
     let mut columns = Vec::new();
     let mut column_name_counts: HashMap<String, usize> = HashMap::default();
+    // Output columns of FROM subqueries, keyed by their index in `tables`
+    let mut derived_columns: HashMap<usize, ViewColumnSchema> = HashMap::default();
 
     // Navigate to the first SELECT in the statement
     if let ast::OneSelect::Select {
@@ -1799,8 +1800,23 @@ pub fn extract_view_columns(
                         alias: alias.as_ref().map(|a| normalize_ident(a.name().as_str())),
                     });
                 }
+                ast::SelectTable::Select(subquery, alias) => {
+                    derived_columns.insert(tables.len(), extract_view_columns(subquery, schema)?);
+                    tables.push(ViewTable {
+                        name: String::new(),
+                        db_name: None,
+                        alias: alias.as_ref().map(|a| normalize_ident(a.name().as_str())),
+                    });
+                }
+                ast::SelectTable::TableCall(qualified_name, _, alias) => {
+                    tables.push(ViewTable {
+                        name: normalize_ident(qualified_name.name.as_str()),
+                        db_name: None,
+                        alias: alias.as_ref().map(|a| normalize_ident(a.name().as_str())),
+                    });
+                }
                 _ => {
-                    // Handle other types like subqueries if needed
+                    // Handle other types like parenthesized joins if needed
                 }
             }
 
@@ -1819,8 +1835,24 @@ pub fn extract_view_columns(
                             alias: alias.as_ref().map(|a| normalize_ident(a.name().as_str())),
                         });
                     }
+                    ast::SelectTable::Select(subquery, alias) => {
+                        derived_columns
+                            .insert(tables.len(), extract_view_columns(subquery, schema)?);
+                        tables.push(ViewTable {
+                            name: String::new(),
+                            db_name: None,
+                            alias: alias.as_ref().map(|a| normalize_ident(a.name().as_str())),
+                        });
+                    }
+                    ast::SelectTable::TableCall(qualified_name, _, alias) => {
+                        tables.push(ViewTable {
+                            name: normalize_ident(qualified_name.name.as_str()),
+                            db_name: None,
+                            alias: alias.as_ref().map(|a| normalize_ident(a.name().as_str())),
+                        });
+                    }
                     _ => {
-                        // Handle other types like subqueries if needed
+                        // Handle other types like parenthesized joins if needed
                     }
                 }
             }
@@ -1878,8 +1910,27 @@ pub fn extract_view_columns(
                 ast::ResultColumn::Star => {
                     // For SELECT *, expand to all columns from all tables
                     for (table_idx, table) in tables.iter().enumerate() {
-                        if let Some(table_obj) = schema.get_table(&table.name) {
-                            for table_column in table_obj.columns() {
+                        if let Some(derived) = derived_columns.get(&table_idx) {
+                            for derived_column in &derived.columns {
+                                let mut column = derived_column.column.clone();
+                                let col_name = column.name.clone().unwrap_or_default();
+
+                                // Handle duplicate column names by adding suffix
+                                if let Some(count) = column_name_counts.get_mut(&col_name) {
+                                    *count += 1;
+                                    column.name = Some(format!("{}:{}", col_name, *count - 1));
+                                } else {
+                                    column_name_counts.insert(col_name, 1);
+                                }
+
+                                columns.push(ViewColumn {
+                                    table_index: usize::MAX,
+                                    column,
+                                });
+                            }
+                        } else if let Some(table_obj) = schema.get_table(&table.name) {
+                            // Arguments of table-valued functions are not part of `*`
+                            for table_column in table_obj.columns().iter().filter(|c| !c.hidden()) {
                                 let col_name =
                                     table_column.name.clone().unwrap_or_else(|| "?".to_string());
 
@@ -1925,8 +1976,15 @@ pub fn extract_view_columns(
                     // For table.*, expand to all columns from the specified table
                     let table_name_str = normalize_ident(table_ref.as_str());
                     if let Some(table_idx) = find_table_index(&table_name_str) {
-                        if let Some(table) = schema.get_table(&tables[table_idx].name) {
-                            for table_column in table.columns() {
+                        if let Some(derived) = derived_columns.get(&table_idx) {
+                            columns.extend(derived.columns.iter().map(|derived_column| {
+                                ViewColumn {
+                                    table_index: usize::MAX,
+                                    column: derived_column.column.clone(),
+                                }
+                            }));
+                        } else if let Some(table) = schema.get_table(&tables[table_idx].name) {
+                            for table_column in table.columns().iter().filter(|c| !c.hidden()) {
                                 let col_name =
                                     table_column.name.clone().unwrap_or_else(|| "?".to_string());
 
@@ -218,6 +218,12 @@ pub struct ProgramBuilder {
     /// references in non-recursive CTEs and to prevent fallthrough to schema
     /// resolution for same-named tables/views.
     ctes_being_defined: Vec<String>,
+    /// Data-modifying CTEs of the statement being translated, as (name, cte_id).
+    /// They are emitted ahead of the statement and read back like materialized CTEs.
+    dml_ctes: Vec<(String, usize)>,
+    /// Set while translating the body of a data-modifying CTE: its RETURNING
+    /// rows are left in their buffer, recorded here, instead of being yielded.
+    returning_capture: Option<Option<MaterializedCteInfo>>,
     /// If this ProgramBuilder is building trigger subprogram, a ref to the trigger is stored here.
     pub trigger: Option<Arc<Trigger>>,
     pub table_reference_counter: TableRefIdCounter,
@@ -687,6 +693,8 @@ impl ProgramBuilder {
             next_cte_id: 0,
             materialized_ctes: HashMap::default(),
             ctes_being_defined: Vec::new(),
+            dml_ctes: Vec::new(),
+            returning_capture: None,
             next_subquery_eqp_id: 1,
             target_union_type: None,
         }
@@ -726,6 +734,39 @@ impl ProgramBuilder {
         self.materialized_ctes.insert(cte_id, info);
     }
 
+    /// Record a data-modifying CTE that has been emitted ahead of its statement.
+    pub fn register_dml_cte(&mut self, name: String, cte_id: usize) {
+        self.dml_ctes.push((name, cte_id));
+    }
+
+    /// The CTE identity of an already emitted data-modifying CTE.
+    pub fn dml_cte_id(&self, name: &str) -> Option<usize> {
+        self.dml_ctes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, cte_id)| *cte_id)
+    }
+
+    /// Start capturing the RETURNING buffer of the statement about to be translated.
+    pub fn begin_returning_capture(&mut self) {
+        self.returning_capture = Some(None);
+    }
+
+    /// Whether RETURNING rows should stay in their buffer instead of being yielded.
+    pub fn is_capturing_returning(&self) -> bool {
+        self.returning_capture.is_some()
+    }
+
+    /// Record the RETURNING buffer of the statement being captured.
+    pub fn capture_returning(&mut self, info: MaterializedCteInfo) {
+        self.returning_capture = Some(Some(info));
+    }
+
+    /// Stop capturing, returning the captured RETURNING buffer, if any.
+    pub fn end_returning_capture(&mut self) -> Option<MaterializedCteInfo> {
+        self.returning_capture.take().flatten()
+    }
+
     /// Mark a CTE name as currently being planned. While on the stack,
     /// `parse_table` will reject references to this name with "circular
     /// reference" instead of falling through to schema resolution.
@@ -1448,6 +1489,7 @@ impl ProgramBuilder {
                 Insn::Found { target_pc, .. } => resolve(target_pc, "Found")?,
                 Insn::NotFound { target_pc, .. } => resolve(target_pc, "NotFound")?,
                 Insn::FkIfZero { target_pc, .. } => resolve(target_pc, "FkIfZero")?,
+                Insn::IncrVacuum { target_pc, .. } => resolve(target_pc, "IncrVacuum")?,
                 Insn::Filter { target_pc, .. } => resolve(target_pc, "Filter")?,
                 Insn::HashProbe { target_pc, .. } => resolve(target_pc, "HashProbe")?,
                 Insn::HashNext { target_pc, .. } => resolve(target_pc, "HashNext")?,
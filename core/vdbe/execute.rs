@@ -1,13 +1,14 @@
 use crate::error::SQLITE_CONSTRAINT_UNIQUE;
 use crate::function::AlterTableFunc;
+use crate::incremental::view::row_for_views;
 use crate::io::TempFile;
 use crate::mvcc::cursor::{MvccCursorType, NextRowidResult};
 use crate::mvcc::database::CheckpointStateMachine;
 use crate::mvcc::MvccClock;
 use crate::numeric::Numeric;
 use crate::schema::{
-    render_gencol_expr_sql_with_new_names, Schema, Table, EXPR_INDEX_SENTINEL, SCHEMA_TABLE_NAME,
-    SQLITE_SEQUENCE_TABLE_NAME,
+    render_gencol_expr_sql_with_new_names, BTreeTable, Schema, Table, EXPR_INDEX_SENTINEL,
+    SCHEMA_TABLE_NAME, SQLITE_SEQUENCE_TABLE_NAME,
 };
 use crate::state_machine::StateMachine;
 use crate::storage::btree::{
@@ -17,7 +18,7 @@ use crate::storage::database::DatabaseFile;
 use crate::storage::journal_mode;
 use crate::storage::page_cache::PageCache;
 use crate::storage::pager::{default_page1, CreateBTreeFlags, PageRef, SavepointResult};
-use crate::storage::sqlite3_ondisk::{DatabaseHeader, PageSize, RawVersion};
+use crate::storage::sqlite3_ondisk::{DatabaseHeader, PageSize, RawVersion, Version};
 use crate::translate::collate::CollationSeq;
 use crate::translate::pragma::TURSO_CDC_VERSION_TABLE_NAME;
 use crate::types::{
@@ -117,9 +118,8 @@ use turso_parser::parser::Parser;
 
 use super::sorter::Sorter;
 use crate::vdbe::vacuum::{
-    capture_custom_types, mirror_symbols, reject_unsupported_vacuum_auto_vacuum_mode,
-    vacuum_target_build_step, vacuum_target_opts_from_source, VacuumDbHeaderMeta,
-    VacuumTargetBuildConfig, VacuumTargetBuildContext,
+    capture_custom_types, mirror_symbols, vacuum_target_build_step, vacuum_target_opts_from_source,
+    VacuumDbHeaderMeta, VacuumTargetBuildConfig, VacuumTargetBuildContext,
 };
 
 #[cfg(feature = "json")]
@@ -1166,7 +1166,7 @@ pub fn op_open_read(
             let tx_state = program
                 .connection
                 .view_transaction_states
-                .get_or_create(&view_name);
+                .get_or_create(*db, &view_name);
 
             // Create materialized view cursor with this view's transaction state
             let mv_cursor = crate::incremental::cursor::MaterializedViewCursor::new(
@@ -3955,6 +3955,7 @@ pub fn op_auto_commit(
     if fk_on {
         conn.clear_deferred_foreign_key_violations();
     }
+    conn.reset_defer_foreign_keys();
 
     // Reset CDC transaction ID after successful COMMIT or ROLLBACK.
     conn.set_cdc_transaction_id(-1);
@@ -6096,6 +6097,91 @@ fn finalize_agg_payload(func: &AggFunc, payload: &[Value]) -> Result<Value> {
     Ok(val)
 }
 
+/// Computes an aggregate over the complete list of its argument tuples in one pass.
+///
+/// Incremental views use this for aggregates that cannot be maintained from a running
+/// summary (e.g. `group_concat`, where a deleted value may sit anywhere in the result),
+/// by replaying every remaining input of a group in order.
+pub(crate) fn compute_aggregate(func: &AggFunc, inputs: &[Vec<Value>]) -> Result<Value> {
+    let mut results = compute_running_aggregate(func, inputs, &[inputs.len()])?;
+    Ok(results.pop().expect("one result per end"))
+}
+
+/// Computes an aggregate over the prefixes `inputs[..end]` for each of the ascending `ends`,
+/// as window aggregates need for every peer group of a partition.
+pub(crate) fn compute_running_aggregate(
+    func: &AggFunc,
+    inputs: &[Vec<Value>],
+    ends: &[usize],
+) -> Result<Vec<Value>> {
+    if let AggFunc::External(ext_func) = func {
+        let ExtFunc::Aggregate {
+            init,
+            step,
+            finalize,
+            argc,
+        } = ext_func.as_ref()
+        else {
+            return Err(LimboError::InternalError(
+                "scalar function called in aggregate context".to_string(),
+            ));
+        };
+        // Finalizing consumes the extension's state, so every prefix is replayed
+        let mut results = Vec::with_capacity(ends.len());
+        for &end in ends {
+            let state = unsafe { (init)() };
+            for args in &inputs[..end] {
+                if *argc == 0 {
+                    unsafe { step(state, 0, std::ptr::null()) };
+                    continue;
+                }
+                let ext_values: Vec<ExtValue> =
+                    args.iter().take(*argc).map(Value::to_ffi).collect();
+                unsafe { step(state, ext_values.len() as i32, ext_values.as_ptr()) };
+                for ext_value in ext_values {
+                    unsafe { ext_value.__free_internal_type() };
+                }
+            }
+            let final_value = unsafe { (finalize)(state) };
+            results.push(Value::from_ffi(final_value)?);
+        }
+        return Ok(results);
+    }
+
+    let mut payload = Vec::new();
+    init_agg_payload(func, &mut payload)?;
+    let mut results = Vec::with_capacity(ends.len());
+    let mut ends = ends.iter().peekable();
+    for (idx, args) in inputs.iter().enumerate() {
+        while ends.next_if(|end| **end == idx).is_some() {
+            results.push(finalize_agg_payload(func, &payload)?);
+        }
+        let mut args = args.iter().cloned();
+        let arg = args.next().unwrap_or(Value::Null);
+        if matches!(func, AggFunc::ArrayAgg) {
+            // Same layout as op_agg_step: element count followed by the elements
+            let count = payload[0]
+                .as_int()
+                .expect("array_agg count must be an integer");
+            payload[0] = Value::from_i64(count + 1);
+            payload.push(arg);
+            continue;
+        }
+        update_agg_payload(
+            func,
+            arg,
+            args.next(),
+            &mut payload,
+            CollationSeq::Binary,
+            &None,
+        )?;
+    }
+    for _ in ends {
+        results.push(finalize_agg_payload(func, &payload)?);
+    }
+    Ok(results)
+}
+
 pub fn op_agg_step(
     _program: &Program,
     state: &mut ProgramState,
@@ -9070,12 +9156,9 @@ pub fn op_insert(
     loop {
         match state.active_op_state.insert().sub_state {
             OpInsertSubState::MaybeCaptureRecord => {
-                let has_dependent_views = {
-                    let schema = program.connection.schema.read();
-                    !schema
-                        .get_dependent_materialized_views(table_name)
-                        .is_empty()
-                };
+                let has_dependent_views =
+                    !dependent_materialized_views(program, state, *cursor_id, table_name)
+                        .is_empty();
                 // If there are no dependent views, we don't need to capture the old record.
                 // We also don't need to do it if the rowid of the UPDATEd row was changed, because
                 // op_delete already captured the deletion for IVM, and this insert only needs to
@@ -9117,12 +9200,9 @@ pub fn op_insert(
                 )? {
                     return Ok(InsnFunctionStepResult::IO(io));
                 }
-                let has_dependent_views = {
-                    let schema = program.connection.schema.read();
-                    !schema
-                        .get_dependent_materialized_views(table_name)
-                        .is_empty()
-                };
+                let has_dependent_views =
+                    !dependent_materialized_views(program, state, *cursor_id, table_name)
+                        .is_empty();
                 let needs_capture =
                     has_dependent_views && !flag.has(InsertFlags::UPDATE_ROWID_CHANGE);
                 if needs_capture {
@@ -9133,45 +9213,55 @@ pub fn op_insert(
                 continue;
             }
             OpInsertSubState::CaptureRecord => {
-                {
-                    let cursor = state.get_cursor(*cursor_id);
-                    let cursor = cursor.as_btree_mut();
-                    if !cursor.has_rowid() {
-                        state.active_op_state.insert().old_record = None;
-                        state.active_op_state.insert().sub_state = OpInsertSubState::NoopCheck;
-                        continue;
-                    }
-                }
-                let insert_key = match &state.registers[*key_reg].get_value() {
-                    Value::Numeric(Numeric::Integer(i)) => *i,
-                    _ => unreachable!("expected integer key in insert"),
+                let Some(table) = cursor_btree_table(program, state, *cursor_id, table_name) else {
+                    state.active_op_state.insert().old_record = None;
+                    state.active_op_state.insert().sub_state = OpInsertSubState::NoopCheck;
+                    continue;
                 };
+                let old_record = if table.has_rowid {
+                    let insert_key = match &state.registers[*key_reg].get_value() {
+                        Value::Numeric(Numeric::Integer(i)) => *i,
+                        _ => unreachable!("expected integer key in insert"),
+                    };
 
-                let cursor = state.get_cursor(*cursor_id);
-                let cursor = cursor.as_btree_mut();
-                let maybe_key = return_if_io!(cursor.rowid());
-                let old_record = if let Some(key) = maybe_key {
-                    if key == insert_key {
-                        let maybe_record = return_if_io!(cursor.record());
-                        if let Some(record) = maybe_record {
-                            let mut values = record.get_values_owned()?;
-                            let schema = program.connection.schema.read();
-                            if let Some(table) = schema.get_table(table_name) {
-                                for (i, col) in table.columns().iter().enumerate() {
-                                    if col.is_rowid_alias() && i < values.len() {
-                                        values[i] = Value::from_i64(key);
-                                    }
-                                }
-                            }
-                            Some((key, values))
-                        } else {
-                            None
-                        }
+                    let cursor = state.get_cursor(*cursor_id);
+                    let cursor = cursor.as_btree_mut();
+                    let maybe_key = return_if_io!(cursor.rowid());
+                    if maybe_key == Some(insert_key) {
+                        return_if_io!(cursor.record())
+                            .map(|record| record.get_values_owned())
+                            .transpose()?
+                            .map(|values| row_for_views(&table, Some(insert_key), values))
                     } else {
                         None
                     }
                 } else {
-                    None
+                    // WITHOUT ROWID rows are overwritten in place when the new record has the
+                    // same PRIMARY KEY, which its records start with.
+                    let new_values = match &state.registers[*record_reg] {
+                        Register::Record(r) => r.get_values_owned()?,
+                        Register::Value(value) => vec![value.clone()],
+                        Register::Aggregate(..) => {
+                            unreachable!("Cannot insert an aggregate value.")
+                        }
+                    };
+                    let pk_len = table.primary_key_columns.len();
+                    let cursor = state.get_cursor(*cursor_id);
+                    let cursor = cursor.as_btree_mut();
+                    match return_if_io!(cursor.record()) {
+                        Some(record) => {
+                            let values = record.get_values_owned()?;
+                            if values.len() >= pk_len
+                                && new_values.len() >= pk_len
+                                && values[..pk_len] == new_values[..pk_len]
+                            {
+                                Some(row_for_views(&table, None, values))
+                            } else {
+                                None
+                            }
+                        }
+                        None => None,
+                    }
                 };
                 state.active_op_state.insert().old_record = old_record;
                 state.active_op_state.insert().sub_state = OpInsertSubState::NoopCheck;
@@ -9305,31 +9395,24 @@ pub fn op_insert(
                         .n_change
                         .fetch_add(1, crate::sync::atomic::Ordering::SeqCst);
                 }
-                let schema = program.connection.schema.read();
-                let dependent_views = schema.get_dependent_materialized_views(table_name);
+                let dependent_views =
+                    dependent_materialized_views(program, state, *cursor_id, table_name);
                 if !dependent_views.is_empty() {
-                    if !has_rowid {
-                        return Err(LimboError::ParseError(
-                            "WITHOUT ROWID tables with dependent materialized views are not supported"
-                                .to_string(),
-                        ));
-                    }
                     state.active_op_state.insert().sub_state = OpInsertSubState::ApplyViewChange;
                     continue;
                 }
                 break;
             }
             OpInsertSubState::ApplyViewChange => {
-                let schema = program.connection.schema.read();
-                let dependent_views = schema.get_dependent_materialized_views(table_name);
+                let database_id = state.cursor_databases[*cursor_id];
+                let dependent_views =
+                    dependent_materialized_views(program, state, *cursor_id, table_name);
                 assert!(!dependent_views.is_empty());
+                let table = cursor_btree_table(program, state, *cursor_id, table_name).ok_or_else(
+                    || LimboError::InternalError(format!("no such table: {table_name}")),
+                )?;
 
                 let (key, values) = {
-                    let key = match &state.registers[*key_reg].get_value() {
-                        Value::Numeric(Numeric::Integer(i)) => *i,
-                        _ => unreachable!("expected integer key"),
-                    };
-
                     let record = match &state.registers[*record_reg] {
                         Register::Record(r) => std::borrow::Cow::Borrowed(r),
                         Register::Value(value) => {
@@ -9343,19 +9426,15 @@ pub fn op_insert(
                     };
 
                     // Add insertion of new row to view deltas
-                    let mut new_values = record.get_values_owned()?;
-
-                    // Fix rowid alias columns: replace Null with actual rowid value
-                    let schema = program.connection.schema.read();
-                    if let Some(table) = schema.get_table(table_name) {
-                        for (i, col) in table.columns().iter().enumerate() {
-                            if col.is_rowid_alias() && i < new_values.len() {
-                                new_values[i] = Value::from_i64(key);
-                            }
+                    let rowid = if table.has_rowid {
+                        match &state.registers[*key_reg].get_value() {
+                            Value::Numeric(Numeric::Integer(i)) => Some(*i),
+                            _ => unreachable!("expected integer key"),
                         }
-                    }
-
-                    (key, new_values)
+                    } else {
+                        None
+                    };
+                    row_for_views(&table, rowid, record.get_values_owned()?)
                 };
 
                 if let Some((key, values)) = state.active_op_state.insert().old_record.take() {
@@ -9363,7 +9442,7 @@ pub fn op_insert(
                         let tx_state = program
                             .connection
                             .view_transaction_states
-                            .get_or_create(view_name);
+                            .get_or_create(database_id, view_name);
                         tx_state.delete(table_name, key, values.clone());
                     }
                 }
@@ -9371,7 +9450,7 @@ pub fn op_insert(
                     let tx_state = program
                         .connection
                         .view_transaction_states
-                        .get_or_create(view_name);
+                        .get_or_create(database_id, view_name);
 
                     tx_state.insert(table_name, key, values.clone());
                 }
@@ -9406,6 +9485,34 @@ pub fn op_int_64(
     Ok(InsnFunctionStepResult::Step)
 }
 
+/// Materialized views that depend on `table_name` in the database `cursor_id` writes to.
+fn dependent_materialized_views(
+    program: &Program,
+    state: &ProgramState,
+    cursor_id: usize,
+    table_name: &str,
+) -> Vec<String> {
+    program
+        .connection
+        .with_schema(state.cursor_databases[cursor_id], |schema| {
+            schema.get_dependent_materialized_views(table_name)
+        })
+}
+
+/// The table `cursor_id` writes to, looked up in the database the cursor was opened on.
+fn cursor_btree_table(
+    program: &Program,
+    state: &ProgramState,
+    cursor_id: usize,
+    table_name: &str,
+) -> Option<Arc<BTreeTable>> {
+    program
+        .connection
+        .with_schema(state.cursor_databases[cursor_id], |schema| {
+            schema.get_btree_table(table_name)
+        })
+}
+
 pub struct OpDeleteState {
     pub sub_state: OpDeleteSubState,
     pub deleted_record: Option<(i64, Vec<Value>)>,
@@ -9439,35 +9546,30 @@ pub fn op_delete(
     loop {
         match state.active_op_state.delete().sub_state {
             OpDeleteSubState::MaybeCaptureRecord => {
-                let schema = program.connection.schema.read();
-                let dependent_views = schema.get_dependent_materialized_views(table_name);
-                if dependent_views.is_empty() {
+                if dependent_materialized_views(program, state, *cursor_id, table_name).is_empty() {
                     state.active_op_state.delete().sub_state = OpDeleteSubState::Delete;
                     continue;
                 }
+                let table = cursor_btree_table(program, state, *cursor_id, table_name).ok_or_else(
+                    || LimboError::InternalError(format!("no such table: {table_name}")),
+                )?;
 
                 let deleted_record = {
                     let cursor = state.get_cursor(*cursor_id);
                     let cursor = cursor.as_btree_mut();
-                    // Get the current key
-                    let maybe_key = return_if_io!(cursor.rowid());
-                    let key = maybe_key.ok_or_else(|| {
-                        LimboError::InternalError("Cannot delete: no current row".to_string())
-                    })?;
+                    // Get the current key, WITHOUT ROWID rows are keyed by their PRIMARY KEY
+                    let key = if table.has_rowid {
+                        let maybe_key = return_if_io!(cursor.rowid());
+                        Some(maybe_key.ok_or_else(|| {
+                            LimboError::InternalError("Cannot delete: no current row".to_string())
+                        })?)
+                    } else {
+                        None
+                    };
                     // Get the current record before deletion and extract values
                     let maybe_record = return_if_io!(cursor.record());
                     if let Some(record) = maybe_record {
-                        let mut values = record.get_values_owned()?;
-
-                        // Fix rowid alias columns: replace Null with actual rowid value
-                        if let Some(table) = schema.get_table(table_name) {
-                            for (i, col) in table.columns().iter().enumerate() {
-                                if col.is_rowid_alias() && i < values.len() {
-                                    values[i] = Value::from_i64(key);
-                                }
-                            }
-                        }
-                        Some((key, values))
+                        Some(row_for_views(&table, key, record.get_values_owned()?))
                     } else {
                         None
                     }
@@ -9484,17 +9586,16 @@ pub fn op_delete(
                 }
                 // Increment metrics for row write (DELETE is a write operation)
                 state.record_rows_written(1);
-                let schema = program.connection.schema.read();
-                let dependent_views = schema.get_dependent_materialized_views(table_name);
-                if dependent_views.is_empty() {
+                if dependent_materialized_views(program, state, *cursor_id, table_name).is_empty() {
                     break;
                 }
                 state.active_op_state.delete().sub_state = OpDeleteSubState::ApplyViewChange;
                 continue;
             }
             OpDeleteSubState::ApplyViewChange => {
-                let schema = program.connection.schema.read();
-                let dependent_views = schema.get_dependent_materialized_views(table_name);
+                let database_id = state.cursor_databases[*cursor_id];
+                let dependent_views =
+                    dependent_materialized_views(program, state, *cursor_id, table_name);
                 assert!(!dependent_views.is_empty());
                 let maybe_deleted_record = state.active_op_state.delete().deleted_record.take();
                 if let Some((key, values)) = maybe_deleted_record {
@@ -9502,7 +9603,7 @@ pub fn op_delete(
                         let tx_state = program
                             .connection
                             .view_transaction_states
-                            .get_or_create(&view_name);
+                            .get_or_create(database_id, &view_name);
                         tx_state.delete(table_name, key, values.clone());
                     }
                 }
@@ -10295,6 +10396,7 @@ pub fn op_open_write(
     if program.connection.is_readonly(*db) {
         return Err(LimboError::ReadOnly);
     }
+    state.cursor_databases[*cursor_id] = *db;
     let pager = program.get_pager_from_database_index(db)?;
     let mv_store = program.connection.mv_store_for_db(*db);
 
@@ -10685,6 +10787,49 @@ pub fn op_destroy(
     }
 }
 
+pub enum OpClearState {
+    CreateCursor,
+    ClearBtree(Arc<RwLock<BTreeCursor>>),
+}
+
+pub fn op_clear(
+    program: &Program,
+    state: &mut ProgramState,
+    insn: &Insn,
+    pager: &Arc<Pager>,
+) -> Result<InsnFunctionStepResult> {
+    load_insn!(Clear { db, root }, insn);
+    if program.connection.mv_store_for_db(*db).is_some() {
+        return Err(LimboError::InternalError(
+            "Clear is not supported in MVCC mode".to_string(),
+        ));
+    }
+
+    let clear_pager = if *db != MAIN_DB_ID {
+        program.get_pager_from_database_index(db)?
+    } else {
+        pager.clone()
+    };
+
+    loop {
+        match state.active_op_state.clear_btree() {
+            OpClearState::CreateCursor => {
+                // Like Destroy, clearing doesn't depend on the table/index distinction: the root
+                // page keeps its own page type.
+                let cursor = BTreeCursor::new(clear_pager.clone(), *root, 0);
+                *state.active_op_state.clear_btree() =
+                    OpClearState::ClearBtree(Arc::new(RwLock::new(cursor)));
+            }
+            OpClearState::ClearBtree(ref mut cursor) => {
+                return_if_io!(cursor.write().clear_btree());
+                state.active_op_state.clear();
+                state.pc += 1;
+                return Ok(InsnFunctionStepResult::Step);
+            }
+        }
+    }
+}
+
 pub fn op_reset_sorter(
     program: &Program,
     state: &mut ProgramState,
@@ -10726,9 +10871,7 @@ pub fn op_reset_sorter(
             }
         }
         CursorType::Sorter => {
-            return Err(LimboError::InternalError(
-                "ResetSorter is not supported for sorter cursors".to_string(),
-            ));
+            cursor.as_sorter_mut().reset();
         }
         _ => {
             return Err(LimboError::InternalError(format!(
@@ -10805,7 +10948,15 @@ pub fn op_drop_view(
     let conn = program.connection.clone();
     conn.with_database_schema_mut(*db, |schema| {
         schema.remove_view(view_name).ok();
+        schema.remove_triggers_for_table(view_name);
     });
+    // Temp triggers targeting the dropped view go away with it.
+    if *db != crate::TEMP_DB_ID && conn.temp.database.read().is_some() {
+        let dropped_db = *db;
+        conn.with_database_schema_mut(crate::TEMP_DB_ID, |temp_schema| {
+            temp_schema.remove_triggers_for_table_with_db(view_name, dropped_db);
+        });
+    }
     state.pc += 1;
     Ok(InsnFunctionStepResult::Step)
 }
@@ -11133,6 +11284,7 @@ fn op_parse_schema_step(
                     materialized_view_info,
                     dbsp_state_roots,
                     dbsp_state_index_roots,
+                    &syms,
                 );
 
                 // Store the modified schema back
@@ -11415,11 +11567,18 @@ pub fn op_populate_materialized_views(
     program: &Program,
     state: &mut ProgramState,
     insn: &Insn,
-    pager: &Arc<Pager>,
+    _pager: &Arc<Pager>,
 ) -> Result<InsnFunctionStepResult> {
-    load_insn!(PopulateMaterializedViews { cursors }, insn);
+    load_insn!(PopulateMaterializedViews { db, cursors }, insn);
 
     let conn = program.connection.clone();
+    // Views in an attached database read their tables and write their state through it
+    let pager = program.get_pager_from_database_index(db)?;
+    let database_name = if *db == MAIN_DB_ID {
+        None
+    } else {
+        conn.get_database_name_by_index(*db)
+    };
 
     // For each view, get its cursor and root page
     let mut view_info = Vec::new();
@@ -11449,11 +11608,8 @@ pub fn op_populate_materialized_views(
 
     // Now populate the views (after releasing the schema borrow)
     for (view_name, _root_page, cursor_id) in view_info {
-        let schema = conn.schema.read();
-        if let Some(view) = schema.get_materialized_view(&view_name) {
+        if let Some(view) = conn.with_schema(*db, |s| s.get_materialized_view(&view_name)) {
             let mut view = view.lock();
-            // Drop the schema borrow before calling populate_from_table
-            drop(schema);
 
             // Get the cursor for writing
             // Get a mutable reference to the cursor
@@ -11478,7 +11634,12 @@ pub fn op_populate_materialized_views(
             };
 
             // Now populate it with the cursor for writing
-            return_if_io!(view.populate_from_table(&conn, pager, btree_cursor.as_mut()));
+            return_if_io!(view.populate_from_table(
+                &conn,
+                &pager,
+                database_name.as_deref(),
+                btree_cursor.as_mut()
+            ));
         }
     }
 
@@ -14342,6 +14503,23 @@ pub fn op_max_pgcnt(
     Ok(InsnFunctionStepResult::Step)
 }
 
+pub fn op_incr_vacuum(
+    program: &Program,
+    state: &mut ProgramState,
+    insn: &Insn,
+    _pager: &Arc<Pager>,
+) -> Result<InsnFunctionStepResult> {
+    load_insn!(IncrVacuum { db, target_pc }, insn);
+
+    let pager = program.get_pager_from_database_index(db)?;
+    if return_if_io!(pager.incremental_vacuum_step()) {
+        state.pc += 1;
+    } else {
+        state.pc = target_pc.as_offset_int();
+    }
+    Ok(InsnFunctionStepResult::Step)
+}
+
 /// State machine for PRAGMA journal_mode changes
 #[derive(Debug, Clone, Copy, Default)]
 pub enum OpJournalModeSubState {
@@ -14421,7 +14599,12 @@ fn op_journal_mode_inner(
                     .to_version()
                     .map_err(|val| LimboError::Corrupt(format!("Invalid read_version: {val}")))?;
 
-                let prev_mode = journal_mode::JournalMode::from(prev_mode_version);
+                // The header only says "legacy"; the rollback journal knows which
+                // rollback journal mode is in use.
+                let prev_mode = match pager.journal.as_ref() {
+                    Some(journal) if matches!(prev_mode_version, Version::Legacy) => journal.mode(),
+                    _ => journal_mode::JournalMode::from(prev_mode_version),
+                };
                 state.active_op_state.journal_mode().prev_mode = Some(prev_mode);
 
                 // If no new mode specified, just return current mode
@@ -14435,7 +14618,7 @@ fn op_journal_mode_inner(
                 // Parse the new mode. If unknown or unsupported, silently return
                 // current mode (matches SQLite behavior).
                 let new_mode = match journal_mode::JournalMode::from_str(mode_str.as_str()) {
-                    Ok(mode) if mode.supported() => mode,
+                    Ok(mode) if mode.supported(&program.connection.db) => mode,
                     _ => {
                         let ret: &'static str = prev_mode.into();
                         state.registers[*dest].set_text(Text::new(ret));
@@ -14464,8 +14647,35 @@ fn op_journal_mode_inner(
                     return Err(LimboError::ReadOnly);
                 }
 
+                // Switching between rollback journal modes only changes how the
+                // journal file is finalized; the database file is untouched.
+                if prev_mode.is_rollback() && new_mode.is_rollback() {
+                    let journal = pager
+                        .journal
+                        .as_ref()
+                        .expect("rollback journal mode without a rollback journal");
+                    journal.shared().set_mode(&pager.io, new_mode)?;
+                    let ret: &'static str = new_mode.into();
+                    state.registers[*dest].set_text(Text::new(ret));
+                    state.pc += 1;
+                    return Ok(InsnFunctionStepResult::Step);
+                }
+
+                // Moving between the WAL and rollback journal worlds replaces the
+                // pager, which is only possible for the connection's own pager.
+                if prev_mode.is_rollback() != new_mode.is_rollback()
+                    && program.connection.db.n_connections.load(Ordering::SeqCst) > 1
+                {
+                    return Err(LimboError::Busy);
+                }
+
                 state.active_op_state.journal_mode().new_mode = Some(new_mode);
-                state.active_op_state.journal_mode().sub_state = OpJournalModeSubState::Checkpoint;
+                state.active_op_state.journal_mode().sub_state = if prev_mode.is_rollback() {
+                    // Nothing to checkpoint: the database file is always up to date.
+                    OpJournalModeSubState::UpdateHeader
+                } else {
+                    OpJournalModeSubState::Checkpoint
+                };
             }
 
             OpJournalModeSubState::Checkpoint => {
@@ -14563,6 +14773,21 @@ fn op_journal_mode_inner(
                 // Clear page cache
                 pager.clear_page_cache(true);
 
+                let prev_mode = state
+                    .active_op_state
+                    .journal_mode()
+                    .prev_mode
+                    .expect("prev_mode should be set");
+                if new_mode.is_rollback() {
+                    program.connection.db.mv_store.store(None);
+                    program
+                        .connection
+                        .db
+                        .switch_to_rollback_journal(&program.connection, new_mode)?;
+                } else if prev_mode.is_rollback() {
+                    program.connection.db.switch_to_wal(&program.connection)?;
+                }
+
                 // Setup new mode
                 if matches!(new_mode, journal_mode::JournalMode::Mvcc) {
                     if program.connection.get_capture_data_changes_info().is_some() {
@@ -14903,7 +15128,6 @@ fn op_vacuum_into_inner(
                     .connection
                     .get_pager_from_database_index(&source_db_id)?;
                 let source_auto_vacuum_mode = source_pager.get_auto_vacuum_mode();
-                reject_unsupported_vacuum_auto_vacuum_mode(source_auto_vacuum_mode)?;
                 let header_meta = if let Some(mv_store) =
                     program.connection.mv_store_for_db(source_db_id)
                 {
@@ -15340,7 +15564,7 @@ mod tests {
             io,
             "in-place-vacuum-design-b.db",
             OpenFlags::Create,
-            DatabaseOpts::new().with_vacuum(true),
+            DatabaseOpts::new(),
             None,
         )
         .unwrap();
@@ -15384,7 +15608,7 @@ mod tests {
             io,
             "in-place-vacuum-busy-before-copyback.db",
             OpenFlags::Create,
-            DatabaseOpts::new().with_vacuum(true),
+            DatabaseOpts::new(),
             None,
         )
         .unwrap();
@@ -1650,6 +1650,15 @@ pub fn insn_to_row(
                     "root iDb={db} former_root={former_root_reg} is_temp={is_temp}"
                 ),
             ),
+            Insn::Clear { db, root } => (
+                "Clear",
+                *root,
+                *db as i64,
+                0,
+                Value::build_text(""),
+                0,
+                format!("root iDb={db}"),
+            ),
             Insn::ResetSorter { cursor_id } => (
                 "ResetSorter",
                 *cursor_id as i64,
@@ -1757,9 +1766,9 @@ pub fn insn_to_row(
                 0,
                 where_clause.clone().unwrap_or_else(|| "NULL".to_string()),
             ),
-            Insn::PopulateMaterializedViews { cursors } => (
+            Insn::PopulateMaterializedViews { db, cursors } => (
                 "PopulateMaterializedViews",
-                0,
+                *db as i64,
                 0,
                 0,
                 Value::Null,
@@ -2156,6 +2165,15 @@ pub fn insn_to_row(
                 0,
                 format!("r[{dest}]=max_page_count(db[{db}],{new_max})"),
             ),
+            Insn::IncrVacuum { db, target_pc } => (
+                "IncrVacuum",
+                *db as i64,
+                target_pc.as_debug_int() as i64,
+                0,
+                Value::build_text(""),
+                0,
+                format!("incremental_vacuum(db[{db}])"),
+            ),
             Insn::JournalMode { db, dest, new_mode } => (
                 "JournalMode",
                 *db as i64,
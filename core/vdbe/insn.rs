@@ -1259,14 +1259,21 @@ pub enum Insn {
         is_temp: usize,
     },
 
-    /// Deletes all contents from the ephemeral table that the cursor points to.
+    /// Deletes all contents of the database table or index whose root page in the database file
+    /// is given by `root`. Unlike [Insn::Destroy], the root page itself is kept (as an empty leaf).
+    Clear {
+        /// The database index (0 = main, 1 = temp, 2+ = attached)
+        db: usize,
+        /// The root page of the table/index to clear
+        root: i64,
+    },
+
+    /// Deletes all contents from the ephemeral table or sorter that the cursor points to.
     ///
     /// In Turso, we do not currently distinguish strictly between ephemeral
     /// and standard tables at the type level. Therefore, it is the caller’s
     /// responsibility to ensure that `ResetSorter` is applied only to ephemeral
     /// tables.
-    ///
-    /// SQLite also supports sorter cursors, but this is not yet implemented in Turso.
     ResetSorter {
         cursor_id: CursorID,
     },
@@ -1354,6 +1361,8 @@ pub enum Insn {
     /// The cursors parameter contains a mapping of view names to cursor IDs that have been
     /// opened to the view's btree for writing the materialized data
     PopulateMaterializedViews {
+        /// Database the views live in
+        db: usize,
         /// Mapping of view name to cursor_id for writing to the view's btree
         cursors: Vec<(String, usize)>,
     },
@@ -1560,6 +1569,13 @@ pub enum Insn {
         dest: usize,    // P2: output register
         new_max: usize, // P3: new maximum page count (0 = just return current)
     },
+    /// Perform a single step of the incremental vacuum procedure on database P1.
+    /// If the database is not in auto-vacuum mode or there is nothing left to
+    /// reclaim, jump to P2. Otherwise, fall through to the next instruction.
+    IncrVacuum {
+        db: usize,               // P1: database index
+        target_pc: BranchOffset, // P2: jump target when done
+    },
     /// Get or set the journal mode for database P1.
     /// If P3 is not null, it contains the new journal mode string.
     /// Store the resulting journal mode in register P2.
@@ -1933,6 +1949,7 @@ impl InsnVariants {
             InsnVariants::IndexMethodOptimize => execute::op_index_method_optimize,
             InsnVariants::IndexMethodQuery => execute::op_index_method_query,
             InsnVariants::Destroy => execute::op_destroy,
+            InsnVariants::Clear => execute::op_clear,
             InsnVariants::ResetSorter => execute::op_reset_sorter,
             InsnVariants::DropTable => execute::op_drop_table,
             InsnVariants::DropTrigger => execute::op_drop_trigger,
@@ -1970,6 +1987,7 @@ impl InsnVariants {
             InsnVariants::AddColumn => execute::op_add_column,
             InsnVariants::AlterColumn => execute::op_alter_column,
             InsnVariants::MaxPgcnt => execute::op_max_pgcnt,
+            InsnVariants::IncrVacuum => execute::op_incr_vacuum,
             InsnVariants::JournalMode => execute::op_journal_mode,
             InsnVariants::IfNeg => execute::op_if_neg,
             InsnVariants::Explain => execute::op_noop,
@@ -2044,6 +2062,7 @@ impl Insn {
             | Self::IndexMethodDestroy { .. }
             | Self::IndexMethodOptimize { .. }
             | Self::Destroy { .. }
+            | Self::Clear { .. }
             | Self::DropTable { .. }
             | Self::DropView { .. }
             | Self::DropIndex { .. }
@@ -2058,6 +2077,7 @@ impl Insn {
             | Self::AddColumn { .. }
             | Self::AlterColumn { .. }
             | Self::JournalMode { .. }
+            | Self::IncrVacuum { .. }
             | Self::Vacuum { .. } => false,
             Self::MaxPgcnt { new_max, .. } => *new_max == 0,
             Self::Program { program, .. } => program.is_readonly(),
@@ -48,10 +48,10 @@ use crate::{
     types::{IOCompletions, IOResult},
     vdbe::{
         execute::{
-            OpColumnState, OpDeleteState, OpDeleteSubState, OpDestroyState, OpIdxInsertState,
-            OpInitCdcVersionState, OpInsertState, OpInsertSubState, OpJournalModeState,
-            OpNewRowidState, OpNoConflictState, OpParseSchemaState, OpProgramState, OpRowIdState,
-            OpSeekState, OpTransactionState, VacuumIntoOpContext,
+            OpClearState, OpColumnState, OpDeleteState, OpDeleteSubState, OpDestroyState,
+            OpIdxInsertState, OpInitCdcVersionState, OpInsertState, OpInsertSubState,
+            OpJournalModeState, OpNewRowidState, OpNoConflictState, OpParseSchemaState,
+            OpProgramState, OpRowIdState, OpSeekState, OpTransactionState, VacuumIntoOpContext,
         },
         hash_table::HashTable,
         metrics::StatementMetrics,
@@ -103,7 +103,7 @@ use tracing::{instrument, Level};
 pub enum ViewDeltaCommitState {
     NotStarted,
     Processing {
-        views: Vec<String>, // view names (all materialized views have storage)
+        views: Vec<(usize, String)>, // database and name of each view (all materialized views have storage)
         current_index: usize,
     },
     Done,
@@ -424,6 +424,7 @@ enum ActiveOpState {
     None,
     Delete(OpDeleteState),
     Destroy(OpDestroyState),
+    Clear(OpClearState),
     IdxDelete(OpIdxDeleteState),
     IntegrityCheck(OpIntegrityCheckState),
     OpenEphemeral(OpOpenEphemeralState),
@@ -448,6 +449,7 @@ impl std::fmt::Debug for ActiveOpState {
             ActiveOpState::None => "None",
             ActiveOpState::Delete(_) => "Delete",
             ActiveOpState::Destroy(_) => "Destroy",
+            ActiveOpState::Clear(_) => "Clear",
             ActiveOpState::IdxDelete(_) => "IdxDelete",
             ActiveOpState::IntegrityCheck(_) => "IntegrityCheck",
             ActiveOpState::OpenEphemeral(_) => "OpenEphemeral",
@@ -518,6 +520,7 @@ impl ActiveOpStateSlot {
         OpDestroyState,
         OpDestroyState::CreateCursor
     );
+    active_state_accessor!(clear_btree, Clear, OpClearState, OpClearState::CreateCursor);
     active_state_accessor!(
         idx_delete,
         IdxDelete,
@@ -623,6 +626,9 @@ pub struct ProgramState {
     pub pc: InsnReference,
     pub(crate) cursors: Vec<Option<Cursor>>,
     cursor_seqs: Vec<i64>,
+    /// Database each cursor was last opened for writing on, so that writes through it reach
+    /// the materialized views of that database
+    cursor_databases: Vec<usize>,
     registers: Box<[Register]>,
     pub(crate) result_row: Option<Row>,
     last_compare: Option<std::cmp::Ordering>,
@@ -714,12 +720,14 @@ impl ProgramState {
     pub fn new(max_registers: usize, max_cursors: usize) -> Self {
         let cursors: Vec<Option<Cursor>> = (0..max_cursors).map(|_| None).collect();
         let cursor_seqs = vec![0i64; max_cursors];
+        let cursor_databases = vec![crate::MAIN_DB_ID; max_cursors];
         let registers = vec![Register::Value(Value::Null); max_registers].into_boxed_slice();
         Self {
             io_completions: None,
             pc: 0,
             cursors,
             cursor_seqs,
+            cursor_databases,
             registers,
             result_row: None,
             last_compare: None,
@@ -818,6 +826,7 @@ impl ProgramState {
         if let Some(max_cursors) = max_cursors {
             self.cursors.resize_with(max_cursors, || None);
             self.cursor_seqs.resize(max_cursors, 0);
+            self.cursor_databases.resize(max_cursors, crate::MAIN_DB_ID);
             self.deferred_seeks.resize(max_cursors, None);
         }
         self.result_row = None;
@@ -1670,12 +1679,15 @@ impl Program {
                     }
 
                     // Not a rollback - proceed with processing
-                    let schema = self.connection.schema.read();
-
                     // Collect materialized views - they should all have storage
                     let mut views = Vec::new();
-                    for view_name in self.connection.view_transaction_states.get_view_names() {
-                        if let Some(view_mutex) = schema.get_materialized_view(&view_name) {
+                    for (database_id, view_name) in
+                        self.connection.view_transaction_states.get_views()
+                    {
+                        let view = self
+                            .connection
+                            .with_schema(database_id, |s| s.get_materialized_view(&view_name));
+                        if let Some(view_mutex) = view {
                             let view = view_mutex.lock();
                             let root_page = view.get_root_page();
 
@@ -1686,7 +1698,7 @@ impl Program {
                                 { "view_name": view_name }
                             );
 
-                            views.push(view_name);
+                            views.push((database_id, view_name));
                         }
                     }
 
@@ -1708,17 +1720,19 @@ impl Program {
                         return Ok(IOResult::Done(()));
                     }
 
-                    let view_name = &views[*current_index];
+                    let (database_id, view_name) = &views[*current_index];
 
                     let table_deltas = self
                         .connection
                         .view_transaction_states
-                        .get(view_name)
+                        .get(*database_id, view_name)
                         .expect("view should have transaction state")
                         .get_table_deltas();
 
-                    let schema = self.connection.schema.read();
-                    if let Some(view_mutex) = schema.get_materialized_view(view_name) {
+                    let view = self
+                        .connection
+                        .with_schema(*database_id, |s| s.get_materialized_view(view_name));
+                    if let Some(view_mutex) = view {
                         let mut view = view_mutex.lock();
 
                         // Create a DeltaSet from the per-table deltas
@@ -1727,8 +1741,14 @@ impl Program {
                             delta_set.insert(table_name, delta);
                         }
 
-                        // Handle I/O from merge_delta - pass pager, circuit will create its own cursor
-                        match view.merge_delta(delta_set, pager.clone())? {
+                        // Handle I/O from merge_delta - pass the pager of the view's database,
+                        // circuit will create its own cursor
+                        let pager = if *database_id == crate::MAIN_DB_ID {
+                            pager.clone()
+                        } else {
+                            self.connection.get_pager_from_database_index(database_id)?
+                        };
+                        match view.merge_delta(delta_set, pager)? {
                             IOResult::Done(_) => {
                                 // Move to next view
                                 state.view_delta_state = ViewDeltaCommitState::Processing {
@@ -1811,6 +1831,7 @@ impl Program {
                 } else {
                     self.connection.commit_temp_schema();
                 }
+                self.connection.reset_defer_foreign_keys();
             }
         }
         Ok(res)
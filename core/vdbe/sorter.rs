@@ -143,6 +143,24 @@ impl Sorter {
         }
     }
 
+    /// Discards all records so the sorter can be filled and sorted again. The temporary
+    /// file, if any, is kept and reused for the chunks of the next sort.
+    pub fn reset(&mut self) {
+        // Drop the record pointers before resetting the arena they point into.
+        self.records.clear();
+        self.arena.reset();
+        self.current = None;
+        self.chunks.clear();
+        self.chunk_heap.clear();
+        self.current_buffer_size = 0;
+        self.max_payload_size_in_buffer = 0;
+        self.next_chunk_offset = 0;
+        self.sort_state = SortState::Start;
+        self.insert_state = InsertState::Start;
+        self.init_chunk_heap_state = InitChunkHeapState::Start;
+        self.pending_completion = None;
+    }
+
     pub const fn is_empty(&self) -> bool {
         self.records.is_empty() && self.chunks.is_empty()
     }
@@ -1082,6 +1100,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reset_discards_records() {
+        let io = Arc::new(PlatformIO::new().unwrap());
+        let mut sorter = Sorter::new(
+            &[SortOrder::Asc],
+            vec![CollationSeq::Binary],
+            vec![None],
+            vec![None],
+            256,
+            64,
+            io.clone(),
+            crate::TempStore::Default,
+        );
+
+        for round in 0..3i64 {
+            for i in (0..500).rev() {
+                let values = vec![Value::from_i64(round * 1000 + i)];
+                let record = ImmutableRecord::from_values(&values, values.len());
+                io.block(|| sorter.insert(&record))
+                    .expect("Failed to insert the record");
+            }
+            io.block(|| sorter.sort())
+                .expect("Failed to sort the records");
+
+            for i in 0..500 {
+                let record = sorter.record().unwrap();
+                assert_eq!(
+                    record.get_values().unwrap()[0],
+                    ValueRef::from_i64(round * 1000 + i)
+                );
+                io.block(|| sorter.next())
+                    .expect("Failed to get the next record");
+            }
+
+            sorter.reset();
+            assert!(sorter.is_empty());
+            assert!(!sorter.has_more());
+        }
+    }
+
     fn generate_value_types<R: RngCore>(rng: &mut R, num_values: usize) -> Vec<ValueType> {
         let mut value_types = Vec::with_capacity(num_values);
 
@@ -2,7 +2,10 @@ use std::sync::{atomic::Ordering, Arc};
 
 use crate::error::LimboError;
 use crate::io::{Buffer, Completion, CompletionGroup, WriteBatch as IOWriteBatch};
+use crate::mvcc::database::CheckpointStateMachine;
+use crate::mvcc::MvccClock;
 use crate::schema::{BTreeTable, Schema, TypeDef};
+use crate::state_machine::StateMachine;
 use crate::storage::pager::{AutoVacuumMode, Page, PageRef, Pager};
 use crate::storage::sqlite3_ondisk::{
     CacheSize, DatabaseHeader, PageSize, RawVersion, TextEncoding, WAL_FRAME_HEADER_SIZE,
@@ -163,15 +166,6 @@ pub(crate) fn vacuum_target_opts_from_source(source_db: &Database) -> DatabaseOp
         .with_without_rowid(source_db.experimental_without_rowid_enabled())
 }
 
-pub(crate) fn reject_unsupported_vacuum_auto_vacuum_mode(mode: AutoVacuumMode) -> Result<()> {
-    if matches!(mode, AutoVacuumMode::Incremental) {
-        return Err(LimboError::InternalError(
-            "Incremental auto-vacuum is not supported".to_string(),
-        ));
-    }
-    Ok(())
-}
-
 /// Database header metadata that the target build must finalize before commit.
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct VacuumDbHeaderMeta {
@@ -1001,11 +995,12 @@ pub(crate) fn build_copy_sql(
         )));
     };
 
-    // Collect non-virtual-generated columns with their quoted names.
+    // Collect non-generated columns with their quoted names. STORED generated
+    // columns are recomputed by the INSERT, which rejects explicit values for them.
     let mut data_columns: Vec<String> = Vec::new();
     let mut rowid_alias_col_idx: Option<usize> = None;
     for (i, col) in btree.columns().iter().enumerate() {
-        if col.is_virtual_generated() {
+        if col.is_generated() {
             continue;
         }
         if col.is_rowid_alias() {
@@ -1050,7 +1045,7 @@ pub(crate) fn build_copy_sql(
             let mut filtered: Vec<&str> = Vec::new();
             let mut col_physical_idx = 0;
             for (i, col) in btree.columns().iter().enumerate() {
-                if col.is_virtual_generated() {
+                if col.is_generated() {
                     continue;
                 }
                 if i != alias_idx {
@@ -1195,8 +1190,17 @@ struct VacuumInPlaceCleanupState {
 /// Phases for the in-place VACUUM state machine.
 enum VacuumInPlacePhase {
     /// Validate preconditions (auto_commit, active statements, readonly, memory,
-    /// MVCC, WAL-backed pager). Then acquire check_point lock
-    Preflight,
+    /// MVCC, WAL- or journal-backed pager). Then acquire check_point lock in WAL mode
+    Preflight {
+        /// Whether `CheckpointMvccLog` already ran for this VACUUM. A logical
+        /// log that is non-empty again afterwards means a writer raced us.
+        mvcc_log_checkpointed: bool,
+    },
+    /// Fold the MVCC logical log into the B-tree so VACUUM copies a complete
+    /// physical image, then go back to `Preflight`.
+    CheckpointMvccLog {
+        checkpoint_sm: Box<StateMachine<Box<CheckpointStateMachine<MvccClock>>>>,
+    },
     /// Acquire exclusive source access on the source WAL via
     /// `begin_vacuum_blocking_tx`: checkpoint_lock is already held from Preflight;
     /// this acquires the exclusive VACUUM lock and installs the source snapshot.
@@ -1225,6 +1229,8 @@ enum VacuumInPlacePhase {
     },
     /// Read a batch of temp pages in using multi-inflight
     /// `Wal::read_frames_batch` calls, one per contiguous frame-id run.
+    /// In the rollback-journal modes the batch is staged as dirty source
+    /// pages instead of being prepared as WAL frames.
     /// The state waits on a single `CompletionGroup` completion that
     /// covers every run in the batch.
     ReadTempBatch {
@@ -1258,6 +1264,9 @@ enum VacuumInPlacePhase {
     /// source WAL write/source snapshot state, keep the VACUUM lock, and drop
     /// temp resources.
     PublishWalCommit,
+    /// Rollback-journal mode: commit the staged pages through the journal, which
+    /// writes them into the database file in place and truncates it.
+    CommitSourceJournal,
     /// TRUNCATE checkpoint: copy WAL frames back into the DB file and truncate
     /// the WAL to zero. The pager's internal state machine handles the multi-step
     /// IO (backfill → sync DB → truncate WAL). Plain VACUUM requires this fold
@@ -1272,7 +1281,9 @@ enum VacuumInPlacePhase {
 
 impl Default for VacuumInPlacePhase {
     fn default() -> Self {
-        Self::Preflight
+        Self::Preflight {
+            mvcc_log_checkpointed: false,
+        }
     }
 }
 
@@ -1297,7 +1308,7 @@ impl VacuumInPlaceOpContext {
     pub(crate) fn new(db: usize) -> Self {
         Self {
             db,
-            phase: VacuumInPlacePhase::Preflight,
+            phase: VacuumInPlacePhase::default(),
             temp_db: None,
             committed_image: None,
             cleanup_state: VacuumInPlaceCleanupState::default(),
@@ -1573,7 +1584,14 @@ fn install_committed_vacuum_image(
 ) {
     let source_db = connection.get_source_database(db);
     let schema = committed_image.schema.clone();
-    *connection.schema.write() = schema.clone();
+    if db == crate::MAIN_DB_ID {
+        *connection.schema.write() = schema.clone();
+    } else {
+        // Drop any connection-local copy so reads fall through to the shared
+        // schema replaced below.
+        connection.database_schemas().write().remove(&db);
+        connection.bump_prepare_context_generation();
+    }
 
     if source_db.mvcc_enabled() {
         let mv_store = source_db
@@ -1624,7 +1642,9 @@ fn vacuum_in_place_step(
 
     loop {
         match phase {
-            VacuumInPlacePhase::Preflight => {
+            VacuumInPlacePhase::Preflight {
+                mvcc_log_checkpointed,
+            } => {
                 // 1. Must be in auto-commit mode (no explicit transaction).
                 if !connection.auto_commit.load(Ordering::SeqCst) {
                     return Err(LimboError::TxError(
@@ -1650,27 +1670,48 @@ fn vacuum_in_place_step(
                         "cannot VACUUM an in-memory database".to_string(),
                     ));
                 }
-                reject_unsupported_vacuum_auto_vacuum_mode(source_pager.get_auto_vacuum_mode())?;
-                // 6. Reject non-WAL pagers.
-                let wal = source_pager.wal.as_ref().ok_or_else(|| {
-                    LimboError::InternalError("VACUUM requires a WAL-mode database".to_string())
-                })?;
-                // 7. In MVCC mode, hold the existing MVCC stop-the-world
-                // gate for the full VACUUM. Callers are expected to run MVCC
-                // checkpoint before VACUUM; this gate only enforces that no
-                // active or new MVCC transactions overlap the physical copy.
+                // 6. The new image is published either as WAL frames or through a
+                // rollback-journal commit, so the pager must have one of the two.
+                if source_pager.wal.is_none() && source_pager.journal.is_none() {
+                    return Err(LimboError::InternalError(
+                        "VACUUM requires a WAL or rollback-journal database".to_string(),
+                    ));
+                }
+                // 7. In MVCC mode, checkpoint the logical log first and then
+                // hold the existing MVCC stop-the-world gate for the full
+                // VACUUM. The gate enforces that no active or new MVCC
+                // transactions overlap the physical copy.
                 let mvcc_guard = if source_mvcc_enabled {
+                    if db != crate::MAIN_DB_ID {
+                        return Err(LimboError::InternalError(
+                            "cannot VACUUM an attached MVCC database".to_string(),
+                        ));
+                    }
                     let mv_store = source_db.get_mv_store().as_ref().cloned().ok_or_else(|| {
                         LimboError::InternalError(
                             "MVCC database missing MV store during VACUUM".to_string(),
                         )
                     })?;
+                    if !*mvcc_log_checkpointed && mv_store.has_uncheckpointed_log()? {
+                        *phase = VacuumInPlacePhase::CheckpointMvccLog {
+                            checkpoint_sm: Box::new(StateMachine::new(Box::new(
+                                CheckpointStateMachine::new(
+                                    source_pager.clone(),
+                                    mv_store,
+                                    connection.clone(),
+                                    true,
+                                    connection.get_sync_mode(),
+                                ),
+                            ))),
+                        };
+                        continue;
+                    }
                     // acquire the exclusive lock on the mvcc
                     let mut guard = MvccVacuumGuard::acquire(connection.clone(), mv_store.clone())?;
                     if mv_store.has_uncheckpointed_log()? {
-                        return Err(LimboError::TxError(
-                            "cannot VACUUM an MVCC database with uncheckpointed changes; run PRAGMA wal_checkpoint(TRUNCATE) first".to_string(),
-                        ));
+                        // Another connection committed between our checkpoint
+                        // and acquiring the gate.
+                        return Err(LimboError::Busy);
                     }
                     if connection.get_mv_tx_id_for_db(db).is_some() {
                         turso_assert!(
@@ -1702,12 +1743,28 @@ fn vacuum_in_place_step(
                 // ensures a post-commit TRUNCATE checkpoint won't fail due
                 // to a concurrent checkpointer. If the lock is unavailable
                 // we fail fast before doing any expensive work.
-                wal.try_begin_vacuum_checkpoint_lock()?;
-                cleanup_state.checkpoint_cleanup = CheckpointLockCleanup::ReleaseRaw;
+                if let Some(wal) = source_pager.wal.as_ref() {
+                    wal.try_begin_vacuum_checkpoint_lock()?;
+                    cleanup_state.checkpoint_cleanup = CheckpointLockCleanup::ReleaseRaw;
+                }
                 *phase = VacuumInPlacePhase::BeginSourceTx;
                 continue;
             }
 
+            VacuumInPlacePhase::CheckpointMvccLog { checkpoint_sm } => {
+                match checkpoint_sm.step(&())? {
+                    crate::IOResult::Done(_) => {
+                        *phase = VacuumInPlacePhase::Preflight {
+                            mvcc_log_checkpointed: true,
+                        };
+                        continue;
+                    }
+                    crate::IOResult::IO(io) => {
+                        return Ok(IOResult::IO(io));
+                    }
+                }
+            }
+
             VacuumInPlacePhase::BeginSourceTx => {
                 // Acquire exclusive WAL access in one shot:
                 // vacuum lock + WAL write lock + connection snapshot.
@@ -1719,7 +1776,7 @@ fn vacuum_in_place_step(
                             schema_did_change: false,
                         });
                         cleanup_state.source_tx_open = true;
-                        cleanup_state.vacuum_lock_held = true;
+                        cleanup_state.vacuum_lock_held = source_pager.wal.is_some();
                         *phase = VacuumInPlacePhase::ReadSourceMetadata;
                         continue;
                     }
@@ -1732,7 +1789,8 @@ fn vacuum_in_place_step(
 
             VacuumInPlacePhase::ReadSourceMetadata => {
                 turso_assert!(
-                    cleanup_state.checkpoint_cleanup == CheckpointLockCleanup::ReleaseRaw,
+                    cleanup_state.checkpoint_cleanup == CheckpointLockCleanup::ReleaseRaw
+                        || source_pager.wal.is_none(),
                     "VACUUM source metadata phase requires held checkpoint lock"
                 );
                 turso_assert!(
@@ -1762,9 +1820,12 @@ fn vacuum_in_place_step(
                 mirror_symbols(connection, &new_temp_db.conn);
                 let source_custom_types = capture_custom_types(connection, db);
 
+                let schema_name = connection.get_database_name_by_index(db).ok_or_else(|| {
+                    LimboError::InternalError(format!("no database at index {db}"))
+                })?;
                 let config = VacuumTargetBuildConfig {
                     source_conn: connection.clone(),
-                    escaped_schema_name: "main".to_string(),
+                    escaped_schema_name: schema_name.replace('"', "\"\""),
                     source_db_id: db,
                     header_meta,
                     source_custom_types,
@@ -1843,20 +1904,25 @@ fn vacuum_in_place_step(
                 let io = &*temp_pager.io;
                 let total_pages: u32 =
                     io.block(|| temp_pager.with_header(|h| h.database_size.get()))?;
-                let wal = source_pager
-                    .wal
-                    .as_ref()
-                    .ok_or_else(|| LimboError::InternalError("VACUUM requires WAL".into()))?;
 
                 turso_assert!(
                     total_pages > 0,
                     "VACUUM target build must produce at least page 1 in the committed temp image"
                 );
 
-                // Initialize the source WAL header before the first batch.
+                // Initialize the source WAL header before the first batch. In the
+                // rollback-journal modes the copied pages are staged in the source
+                // page cache instead, so drop what the target build read into it.
                 let page_sz = source_pager.get_page_size_unchecked();
+                let header_write_c = match source_pager.wal.as_ref() {
+                    Some(wal) => wal.prepare_wal_start(page_sz)?,
+                    None => {
+                        source_pager.clear_page_cache(false);
+                        None
+                    }
+                };
 
-                if let Some(header_write_c) = wal.prepare_wal_start(page_sz)? {
+                if let Some(header_write_c) = header_write_c {
                     // WAL not yet initialized — yield on the header write.
                     *phase = VacuumInPlacePhase::InitSourceWalHeader {
                         total_pages,
@@ -1976,10 +2042,39 @@ fn vacuum_in_place_step(
                     );
                 }
                 let all_read = *next_page > *total_pages;
-                let wal = source_pager
-                    .wal
-                    .as_ref()
-                    .ok_or_else(|| LimboError::InternalError("VACUUM requires WAL".into()))?;
+                let Some(wal) = source_pager.wal.as_ref() else {
+                    // Rollback-journal mode: the batch becomes dirty pages of the
+                    // source write transaction.
+                    for page in std::mem::take(batch_pages) {
+                        let page_id = page.get().id;
+                        source_pager.stage_vacuum_page(page_id, page.get_contents().as_ptr())?;
+                    }
+                    if all_read {
+                        source_pager.stage_vacuum_truncated_pages(*total_pages)?;
+                        *phase = VacuumInPlacePhase::CommitSourceJournal;
+                        continue;
+                    }
+                    let temp_db_ref = temp_db
+                        .as_ref()
+                        .expect("VACUUM ReadTempBatch phase requires temp db");
+                    let temp_pager = temp_db_ref.conn.get_pager();
+                    let batch_end = next_vacuum_copy_batch_start(*next_page, *total_pages);
+                    let (batch_pages, read_completion) = start_temp_batch_reads(
+                        &temp_pager,
+                        *next_page,
+                        batch_end,
+                        read_scratch_buf,
+                    )?;
+                    *phase = VacuumInPlacePhase::ReadTempBatch {
+                        total_pages: *total_pages,
+                        next_page: batch_end,
+                        prev_prepared: None,
+                        read_scratch_buf: Arc::clone(read_scratch_buf),
+                        batch_pages,
+                        read_completion,
+                    };
+                    continue;
+                };
                 let page_sz = source_pager.get_page_size_unchecked();
 
                 let db_size_on_commit = if all_read { Some(*total_pages) } else { None };
@@ -2163,6 +2258,45 @@ fn vacuum_in_place_step(
                 continue;
             }
 
+            VacuumInPlacePhase::CommitSourceJournal => {
+                turso_assert!(
+                    cleanup_state.source_tx_open,
+                    "VACUUM journal commit phase requires source write transaction"
+                );
+                // If the commit fails after touching the database file, the pager
+                // writes the journaled originals back before returning the error.
+                match source_pager.commit_dirty_pages(
+                    crate::storage::wal::WalAutoActions::empty(),
+                    connection.get_sync_mode(),
+                    connection.get_data_sync_retry(),
+                )? {
+                    crate::IOResult::Done(()) => {}
+                    crate::IOResult::IO(io) => {
+                        return Ok(IOResult::IO(io));
+                    }
+                }
+                source_pager.commit_dirty_pages_end();
+                source_pager.end_write_tx();
+                source_pager.end_read_tx();
+                cleanup_state.source_tx_open = false;
+
+                connection.auto_commit.store(true, Ordering::SeqCst);
+                connection.set_tx_state(crate::connection::TransactionState::None);
+
+                // The cache still holds the blank pages staged past the new end
+                // of the file.
+                source_pager.clear_page_cache(false);
+                source_pager.set_schema_cookie(None);
+
+                let temp_db = temp_db
+                    .take()
+                    .expect("VACUUM CommitSourceJournal phase requires temp db");
+                drop(temp_db);
+
+                *phase = VacuumInPlacePhase::InstallCommittedImage;
+                continue;
+            }
+
             VacuumInPlacePhase::Checkpoint => {
                 // TRUNCATE checkpoint: copy WAL frames into the DB file,
                 // force a durable DB sync, then truncate the WAL to zero
@@ -2250,8 +2384,13 @@ fn vacuum_in_place_cleanup(
     committed_image: Option<VacuumCommittedImageMeta>,
     mut cleanup_state: VacuumInPlaceCleanupState,
 ) -> Result<()> {
-    if let VacuumInPlacePhase::TargetBuild { context, .. } = &mut phase {
-        context.cleanup_after_error()?;
+    match &mut phase {
+        VacuumInPlacePhase::TargetBuild { context, .. } => context.cleanup_after_error()?,
+        VacuumInPlacePhase::CheckpointMvccLog { checkpoint_sm } => {
+            // The checkpoint may have been abandoned while waiting on I/O.
+            checkpoint_sm.inner_mut().cleanup_after_external_io_error();
+        }
+        _ => {}
     }
 
     match cleanup_state.checkpoint_cleanup {
@@ -2839,7 +2978,7 @@ mod tests {
             io_dyn,
             "vacuum-source.db",
             OpenFlags::Create,
-            DatabaseOpts::new().with_vacuum(true),
+            DatabaseOpts::new(),
             None,
         )?;
         let conn = db.connect()?;
@@ -2880,7 +3019,7 @@ mod tests {
             io_dyn,
             "vacuum-source-full.db",
             OpenFlags::Create,
-            DatabaseOpts::new().with_vacuum(true),
+            DatabaseOpts::new(),
             None,
         )?;
         let conn = db.connect()?;
@@ -33,6 +33,10 @@ impl VirtualTable {
     pub(crate) fn id(&self) -> u64 {
         self.vtab_id
     }
+    /// Whether this is a PRAGMA table-valued function, which reads the connection's schema
+    pub(crate) fn is_pragma(&self) -> bool {
+        matches!(self.vtab_type, VirtualTableType::Pragma(_))
+    }
     pub(crate) fn readonly(&self) -> bool {
         match &self.vtab_type {
             VirtualTableType::Pragma(_) => true,
@@ -1760,12 +1760,16 @@ pub enum PragmaName {
     DataSyncRetry,
     /// List databases
     DatabaseList,
+    /// Defer enforcement of all foreign key constraints until the transaction commits
+    DeferForeignKeys,
     /// Encoding - only support utf8
     Encoding,
     /// Current free page count.
     FreelistCount,
     /// Enable or disable foreign key constraint enforcement
     ForeignKeys,
+    /// Check the database for foreign key constraint violations
+    ForeignKeyCheck,
     /// Returns information about foreign keys declared by a table
     ForeignKeyList,
     /// Deprecated: control whether column names include table name prefix
@@ -1777,10 +1781,14 @@ pub enum PragmaName {
     Fullfsync,
     /// Enable or disable CHECK constraint enforcement
     IgnoreCheckConstraints,
+    /// Reclaim free pages of an incremental auto-vacuum database
+    IncrementalVacuum,
     /// Run integrity check on the database file
     IntegrityCheck,
     /// `journal_mode` pragma
     JournalMode,
+    /// Size limit (in bytes) the WAL or a persisted rollback journal is truncated to
+    JournalSizeLimit,
     /// `locking_mode` pragma
     LockingMode,
     /// Run a quick integrity check (skips expensive index consistency validation)
@@ -1837,6 +1845,8 @@ pub enum PragmaName {
     UnstableCaptureDataChangesConn,
     /// Returns the user version of the database file.
     UserVersion,
+    /// WAL frame count that triggers an automatic checkpoint after commit
+    WalAutocheckpoint,
     /// trigger a checkpoint to run on database(s) if WAL is enabled
     WalCheckpoint,
     /// Sets or queries the threshold (in bytes) at which MVCC triggers an automatic checkpoint.
@@ -1984,6 +1994,9 @@ pub struct CommonTableExpr {
     pub materialized: Materialized,
     /// query
     pub select: Select,
+    /// data-modifying statement (`DELETE`, `INSERT` or `UPDATE` with `RETURNING`);
+    /// `select` then describes the rows it returns
+    pub dml: Option<Box<Stmt>>,
 }
 
 /// A field in a STRUCT or UNION type declaration, e.g. `x INT` in `STRUCT(x INT, y TEXT)`.
@@ -2358,7 +2358,10 @@ impl ToTokens for CommonTableExpr {
             }
         };
         s.append(TK_LP, None)?;
-        self.select.to_tokens(s, context)?;
+        match &self.dml {
+            Some(dml) => dml.to_tokens(s, context)?,
+            None => self.select.to_tokens(s, context)?,
+        }
         s.append(TK_RP, None)
     }
 }
@@ -143,6 +143,69 @@ fn new_join_type(n0: &[u8], n1: Option<&[u8]>, n2: Option<&[u8]>) -> Result<Join
     Ok(jt)
 }
 
+/// Build the `SELECT <returning> FROM <target>` describing the rows produced by
+/// a data-modifying CTE, so the rest of the query sees it like any other CTE.
+fn returning_select(dml: &Stmt) -> Result<Select> {
+    let (tbl_name, from, returning) = match dml {
+        Stmt::Delete {
+            tbl_name,
+            returning,
+            ..
+        }
+        | Stmt::Insert {
+            tbl_name,
+            returning,
+            ..
+        } => (tbl_name, None, returning),
+        Stmt::Update(update) => (&update.tbl_name, update.from.as_ref(), &update.returning),
+        _ => unreachable!("not a data-modifying statement"),
+    };
+    if returning.is_empty() {
+        return Err(Error::Custom(
+            "data-modifying statement in WITH must have a RETURNING clause".to_owned(),
+        ));
+    }
+
+    let target = SelectTable::Table(
+        QualifiedName {
+            db_name: tbl_name.db_name.clone(),
+            name: tbl_name.name.clone(),
+            alias: None,
+        },
+        tbl_name.alias.clone().map(As::As),
+        None,
+    );
+    let mut joins = vec![];
+    if let Some(from) = from {
+        joins.push(JoinedSelectTable {
+            operator: JoinOperator::Comma,
+            table: from.select.clone(),
+            constraint: None,
+        });
+        joins.extend(from.joins.iter().cloned());
+    }
+
+    Ok(Select {
+        with: None,
+        body: SelectBody {
+            select: OneSelect::Select {
+                distinctness: None,
+                columns: returning.clone(),
+                from: Some(FromClause {
+                    select: Box::new(target),
+                    joins,
+                }),
+                where_clause: None,
+                group_by: None,
+                window_clause: vec![],
+            },
+            compounds: vec![],
+        },
+        order_by: vec![],
+        limit: None,
+    })
+}
+
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
 
@@ -976,7 +1039,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_with_stmt(&mut self) -> Result<Stmt> {
-        let with = self.parse_with()?;
+        let with = self.parse_with(true)?;
         debug_assert!(with.is_some());
         let first_tok =
             peek_expect!(self, TK_SELECT, TK_VALUES, TK_UPDATE, TK_DELETE, TK_INSERT, TK_REPLACE,);
@@ -2257,7 +2320,7 @@ impl<'a> Parser<'a> {
         Ok(columns)
     }
 
-    fn parse_common_table_expr(&mut self) -> Result<CommonTableExpr> {
+    fn parse_common_table_expr(&mut self, allow_dml: bool) -> Result<CommonTableExpr> {
         let nm = self.parse_nm()?;
         let eid_list = self.parse_eid_list(false)?;
         eat_expect!(self, TK_AS);
@@ -2274,17 +2337,34 @@ impl<'a> Parser<'a> {
             _ => Materialized::Any,
         };
         eat_expect!(self, TK_LP);
-        let select = self.parse_select()?;
+        let (select, dml) = match self.peek_no_eof()?.token_type {
+            TK_DELETE | TK_INSERT | TK_REPLACE | TK_UPDATE => {
+                if !allow_dml {
+                    return Err(Error::Custom(
+                        "WITH clause containing a data-modifying statement must be at the top level"
+                            .to_owned(),
+                    ));
+                }
+                let dml = match self.peek_no_eof()?.token_type {
+                    TK_DELETE => self.parse_delete_without_cte(None)?,
+                    TK_UPDATE => self.parse_update_without_cte(None)?,
+                    _ => self.parse_insert_without_cte(None)?,
+                };
+                (returning_select(&dml)?, Some(Box::new(dml)))
+            }
+            _ => (self.parse_select()?, None),
+        };
         eat_expect!(self, TK_RP);
         Ok(CommonTableExpr {
             tbl_name: nm,
             columns: eid_list,
             materialized: wqas,
             select,
+            dml,
         })
     }
 
-    fn parse_with(&mut self) -> Result<Option<With>> {
+    fn parse_with(&mut self, allow_dml: bool) -> Result<Option<With>> {
         if let Some(tok) = self.peek()? {
             if tok.token_type == TK_WITH {
                 eat_assert!(self, TK_WITH);
@@ -2302,13 +2382,13 @@ impl<'a> Parser<'a> {
             false
         };
 
-        let mut ctes = vec![self.parse_common_table_expr()?];
+        let mut ctes = vec![self.parse_common_table_expr(allow_dml)?];
 
         loop {
             match self.peek()? {
                 Some(tok) if tok.token_type == TK_COMMA => {
                     eat_assert!(self, TK_COMMA);
-                    let cte = self.parse_common_table_expr()?;
+                    let cte = self.parse_common_table_expr(allow_dml)?;
                     if ctes.iter().any(|existing| {
                         existing
                             .tbl_name
@@ -2971,7 +3051,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_select(&mut self) -> Result<Select> {
-        let with = self.parse_with()?;
+        let with = self.parse_with(false)?;
         self.parse_select_without_cte(with)
     }
 
@@ -4331,7 +4411,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_delete(&mut self) -> Result<Stmt> {
-        let with = self.parse_with()?;
+        let with = self.parse_with(false)?;
         self.parse_delete_without_cte(with)
     }
 
@@ -4807,7 +4887,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_insert(&mut self) -> Result<Stmt> {
-        let with = self.parse_with()?;
+        let with = self.parse_with(false)?;
         self.parse_insert_without_cte(with)
     }
 
@@ -4841,7 +4921,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_update(&mut self) -> Result<Stmt> {
-        let with = self.parse_with()?;
+        let with = self.parse_with(false)?;
         self.parse_update_without_cte(with)
     }
 
@@ -8206,7 +8286,8 @@ mod tests {
                                     },
                                     order_by: vec![],
                                     limit: None,
-                                }
+                                },
+                                dml: None,
                             },
                         ]
                     }),
@@ -8262,7 +8343,8 @@ mod tests {
                                     },
                                     order_by: vec![],
                                     limit: None,
-                                }
+                                },
+                                dml: None,
                             },
                         ]
                     }),
@@ -8318,7 +8400,8 @@ mod tests {
                                     },
                                     order_by: vec![],
                                     limit: None,
-                                }
+                                },
+                                dml: None,
                             },
                         ]
                     }),
@@ -8368,7 +8451,8 @@ mod tests {
                                     },
                                     order_by: vec![],
                                     limit: None,
-                                }
+                                },
+                                dml: None,
                             },
                             CommonTableExpr {
                                 tbl_name: Name::exact("test_2".to_owned()),
@@ -8392,7 +8476,8 @@ mod tests {
                                     },
                                     order_by: vec![],
                                     limit: None,
-                                }
+                                },
+                                dml: None,
                             },
                         ]
                     }),
@@ -11973,6 +12058,7 @@ mod tests {
                                     order_by: vec![],
                                     limit: None,
                                 },
+                                dml: None,
                             }
                         ],
                     }),
@@ -12180,6 +12266,7 @@ mod tests {
                                     order_by: vec![],
                                     limit: None,
                                 },
+                                dml: None,
                             }
                         ],
                     }),
@@ -12228,6 +12315,7 @@ mod tests {
                                     order_by: vec![],
                                     limit: None,
                                 },
+                                dml: None,
                             }
                         ],
                     }),
@@ -12304,6 +12392,7 @@ mod tests {
                                     order_by: vec![],
                                     limit: None,
                                 },
+                                dml: None,
                             }
                         ],
                     }),
@@ -238,6 +238,49 @@ def test_grouped_aggregates():
     turso.quit()
 
 
+def test_incremental_view_aggregates():
+    db_path = "testing/system/matview_ext.db"
+    extension_path = "./target/debug/liblimbo_percentile"
+    view_query = "SELECT * FROM sensor_stats ORDER BY sensor;"
+    recompute_query = (
+        "SELECT sensor, median(value), percentile(value, 25) FROM readings GROUP BY sensor ORDER BY sensor;"
+    )
+    cleanup()
+    turso = TestTursoShell(init_commands="", flags=f"--experimental-views {db_path}")
+    turso.execute_dot(f".load {extension_path}")
+    turso.execute_dot("CREATE TABLE readings (id INTEGER PRIMARY KEY, sensor TEXT, value REAL);")
+    turso.execute_dot("INSERT INTO readings VALUES (1, 'a', 1.0), (2, 'a', 3.0), (3, 'a', 8.0);")
+    turso.execute_dot("INSERT INTO readings VALUES (4, 'b', 4.0), (5, 'b', 6.0);")
+    turso.execute_dot(
+        "CREATE MATERIALIZED VIEW sensor_stats AS "
+        "SELECT sensor, median(value) AS med, percentile(value, 25) AS p25 FROM readings GROUP BY sensor;"
+    )
+    turso.run_test_fn(
+        view_query,
+        lambda res: res == "a|3.0|2.0\nb|5.0|4.5",
+        "materialized view with extension aggregates is populated",
+    )
+    turso.quit()
+
+    # The view must be loaded again once the extension is, and keep its state across the reopen
+    turso = TestTursoShell(init_commands="", flags=f"--experimental-views {db_path}")
+    turso.execute_dot(f".load {extension_path}")
+    turso.execute_dot("INSERT INTO readings VALUES (6, 'b', 10.0);")
+    turso.execute_dot("DELETE FROM readings WHERE id = 1;")
+    turso.execute_dot("UPDATE readings SET value = 2.0 WHERE id = 4;")
+    turso.run_test_fn(
+        view_query,
+        lambda res: res == "a|5.5|4.25\nb|6.0|4.0",
+        "materialized view with extension aggregates is maintained after reopen",
+    )
+    turso.run_test_fn(
+        recompute_query,
+        lambda res: res == "a|5.5|4.25\nb|6.0|4.0",
+        "materialized view with extension aggregates matches a full recompute",
+    )
+    turso.quit()
+
+
 # Encoders and decoders
 def validate_url_encode(a):
     return a == "%2Fhello%3Ftext%3D%28%E0%B2%A0_%E0%B2%A0%29"
@@ -857,10 +900,14 @@ def test_csv():
 
 
 def cleanup():
-    if os.path.exists("testing/system/vfs.db"):
-        os.remove("testing/system/vfs.db")
-    if os.path.exists("testing/system/vfs.db-wal"):
-        os.remove("testing/system/vfs.db-wal")
+    for path in [
+        "testing/system/vfs.db",
+        "testing/system/vfs.db-wal",
+        "testing/system/matview_ext.db",
+        "testing/system/matview_ext.db-wal",
+    ]:
+        if os.path.exists(path):
+            os.remove(path)
 
 
 def test_tablestats():
@@ -974,6 +1021,7 @@ def main():
         test_uuid()
         test_aggregates()
         test_grouped_aggregates()
+        test_incremental_view_aggregates()
         test_crypto()
         test_series()
         test_ipaddr()
@@ -20,7 +20,6 @@ const TURSO_CLI_EXPERIMENTAL_FLAGS: &[&str] = &[
     "--experimental-attach",
     "--experimental-index-method",
     "--experimental-generated-columns",
-    "--experimental-vacuum",
     "--experimental-without-rowid",
 ];
 
@@ -15,7 +15,6 @@ const TURSO_RUST_EXPERIMENTAL_FEATURES: &[&str] = &[
     "views",
     "custom_types",
     "generated_columns",
-    "vacuum",
     "without_rowid",
 ];
 
@@ -27,7 +26,6 @@ fn apply_turso_experimental_features(mut builder: Builder) -> Builder {
             "views" => builder.experimental_materialized_views(true),
             "custom_types" => builder.experimental_custom_types(true),
             "generated_columns" => builder.experimental_generated_columns(true),
-            "vacuum" => builder.experimental_vacuum(true),
             "without_rowid" => builder.experimental_without_rowid(true),
             _ => unreachable!("unexpected sqltests Rust backend experimental feature"),
         };
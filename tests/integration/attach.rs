@@ -145,6 +145,45 @@ fn test_fresh_attach_from_legacy_main_respects_attached_reserved_space_minimum(
     Ok(())
 }
 
+#[turso_macros::test]
+fn test_attached_database_follows_wal_autocheckpoint(_tmp_db: TempDatabase) -> anyhow::Result<()> {
+    let db = attach_enabled_db(DatabaseOpts::new());
+    let conn = db.connect_limbo();
+    conn.execute("PRAGMA wal_autocheckpoint = 10")?;
+
+    let aux_path = db.path.with_extension("attach_autocheckpoint.db");
+    let aux_wal_size = || {
+        std::fs::metadata(format!("{}-wal", aux_path.display()))
+            .map(|m| m.len())
+            .unwrap_or(0)
+    };
+    conn.execute(format!("ATTACH '{}' AS aux", aux_path.display()))?;
+    conn.execute("CREATE TABLE aux.t(x)")?;
+
+    // The attached database inherits the threshold: crossing it checkpoints, so the
+    // next writer starts the log over.
+    for _ in 0..2 {
+        conn.execute("INSERT INTO aux.t VALUES (randomblob(20000))")?;
+    }
+    conn.execute("INSERT INTO aux.t VALUES (1)")?;
+    assert!(aux_wal_size() < 16384);
+
+    // A schema name is ignored: the setting covers the whole connection.
+    assert_eq!(
+        limbo_exec_rows(&conn, "PRAGMA aux.wal_autocheckpoint = 0"),
+        vec![vec![rusqlite::types::Value::Integer(0)]]
+    );
+    assert_eq!(
+        limbo_exec_rows(&conn, "PRAGMA wal_autocheckpoint"),
+        vec![vec![rusqlite::types::Value::Integer(0)]]
+    );
+    for _ in 0..5 {
+        conn.execute("INSERT INTO aux.t VALUES (randomblob(20000))")?;
+    }
+    assert!(aux_wal_size() > 100_000);
+    Ok(())
+}
+
 #[turso_macros::test]
 fn test_fresh_attach_inherits_mvcc_before_first_write(_tmp_db: TempDatabase) -> anyhow::Result<()> {
     let db = attach_enabled_db(DatabaseOpts::new());
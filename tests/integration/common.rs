@@ -170,7 +170,7 @@ impl TempDatabaseBuilder {
         let mut opts = self
             .opts
             .unwrap_or_else(|| turso_core::DatabaseOpts::new().with_encryption(true));
-        opts = opts.with_vacuum(true).with_without_rowid(true);
+        opts = opts.with_without_rowid(true);
 
         if self.enable_views {
             opts = opts.with_views(true);
@@ -1592,6 +1592,56 @@ fn test_integrity_check_row_missing_from_index(db: TempDatabase) {
     }
 }
 
+/// Test that REINDEX rebuilds an index that drifted from its table.
+/// Drops the last entry from the index page like the test above, then checks that
+/// REINDEX brings lookups and integrity_check back in line with the table.
+#[cfg(not(feature = "checksum"))]
+#[turso_macros::test]
+fn test_reindex_repairs_index_missing_row(db: TempDatabase) {
+    let conn = db.connect_limbo();
+    conn.execute("CREATE TABLE t1(id INTEGER PRIMARY KEY, name TEXT);")
+        .unwrap();
+    conn.execute("CREATE INDEX idx_t1_name ON t1(name);")
+        .unwrap();
+    for i in 0..10 {
+        conn.execute(format!("INSERT INTO t1 VALUES ({i}, 'name_{i:03}');"))
+            .unwrap();
+    }
+    checkpoint_database(&conn);
+    drop(conn);
+
+    {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&db.path)
+            .unwrap();
+        let mut page3 = [0u8; PAGE_SIZE];
+        file.seek(SeekFrom::Start(2 * PAGE_SIZE as u64)).unwrap();
+        file.read_exact(&mut page3).unwrap();
+        assert_eq!(page3[0], 0x0a, "Expected leaf index page");
+        let cell_count = read_u16_be(&page3, 3);
+        write_u16_be(&mut page3, 3, cell_count - 1);
+        file.seek(SeekFrom::Start(2 * PAGE_SIZE as u64)).unwrap();
+        file.write_all(&page3).unwrap();
+        file.sync_all().unwrap();
+    }
+
+    let db = TempDatabase::new_with_existent(&db.path);
+    let conn = db.connect_limbo();
+    let lookup = "SELECT id FROM t1 INDEXED BY idx_t1_name WHERE name = 'name_009'";
+    assert!(crate::common::limbo_exec_rows(&conn, lookup).is_empty());
+    assert!(run_integrity_check(&conn).contains("missing from index"));
+
+    conn.execute("REINDEX idx_t1_name").unwrap();
+
+    assert_eq!(
+        crate::common::limbo_exec_rows(&conn, lookup),
+        vec![vec![rusqlite::types::Value::Integer(9)]]
+    );
+    assert_eq!(run_integrity_check(&conn), "ok");
+}
+
 /// Test integrity_check with index on column added via ALTER TABLE ADD COLUMN with DEFAULT.
 ///
 /// This is a regression test for a bug where integrity_check would report false positives
@@ -433,3 +433,61 @@ fn test_pragma_wal_checkpoint_targets_attached_database(db: TempDatabase) {
         "aux pager should have checkpointed frames (got {checkpointed})"
     );
 }
+
+#[turso_macros::test]
+fn test_pragma_wal_autocheckpoint_and_journal_size_limit(db: TempDatabase) {
+    let conn = db.connect_limbo();
+    let wal_size = || {
+        std::fs::metadata(format!("{}-wal", db.path.display()))
+            .map(|m| m.len())
+            .unwrap_or(0)
+    };
+
+    assert_eq!(
+        limbo_exec_rows(&conn, "PRAGMA wal_autocheckpoint"),
+        vec![vec![RValue::Integer(1000)]]
+    );
+    assert_eq!(
+        limbo_exec_rows(&conn, "PRAGMA journal_size_limit"),
+        vec![vec![RValue::Integer(-1)]]
+    );
+
+    // With auto-checkpointing off the WAL keeps every frame, whatever the size limit.
+    assert_eq!(
+        limbo_exec_rows(&conn, "PRAGMA wal_autocheckpoint = -5"),
+        vec![vec![RValue::Integer(0)]]
+    );
+    assert_eq!(
+        limbo_exec_rows(&conn, "PRAGMA journal_size_limit = 16384"),
+        vec![vec![RValue::Integer(16384)]]
+    );
+    conn.execute("CREATE TABLE t(x)").unwrap();
+    for _ in 0..5 {
+        conn.execute("INSERT INTO t VALUES (randomblob(20000))")
+            .unwrap();
+    }
+    assert!(wal_size() > 100_000);
+
+    // A RESTART checkpoint leaves the file alone; the next writer cuts the
+    // restarted log back to its header, well under the limit.
+    limbo_exec_rows(&conn, "PRAGMA wal_checkpoint(RESTART)");
+    assert!(wal_size() > 100_000);
+    conn.execute("INSERT INTO t VALUES (1)").unwrap();
+    assert!(wal_size() < 16384);
+
+    // Crossing the threshold checkpoints, so the next writer starts the log over.
+    assert_eq!(
+        limbo_exec_rows(&conn, "PRAGMA wal_autocheckpoint = 10"),
+        vec![vec![RValue::Integer(10)]]
+    );
+    for _ in 0..2 {
+        conn.execute("INSERT INTO t VALUES (randomblob(20000))")
+            .unwrap();
+    }
+    conn.execute("INSERT INTO t VALUES (2)").unwrap();
+    assert!(wal_size() < 16384);
+    assert_eq!(
+        limbo_exec_rows(&conn, "SELECT count(*) FROM t"),
+        vec![vec![RValue::Integer(9)]]
+    );
+}
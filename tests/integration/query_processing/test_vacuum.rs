@@ -3338,6 +3338,107 @@ fn test_vacuum_into_attached_database(tmp_db: TempDatabase) -> anyhow::Result<()
     Ok(())
 }
 
+#[turso_macros::test(init_sql = "CREATE TABLE main_t(x INTEGER);")]
+fn test_plain_vacuum_attached_database(tmp_db: TempDatabase) -> anyhow::Result<()> {
+    let conn = tmp_db.connect_limbo();
+    conn.execute("INSERT INTO main_t VALUES (1), (2), (3)")?;
+
+    let attached_path = tmp_db.path.with_file_name("attached.db");
+    conn.execute(format!(
+        "ATTACH DATABASE '{}' AS att",
+        attached_path.display()
+    ))?;
+    conn.execute("CREATE TABLE att.att_t(id INTEGER PRIMARY KEY, y TEXT)")?;
+    conn.execute("CREATE INDEX att.att_t_y ON att_t(y)")?;
+    for i in 0..200 {
+        conn.execute(format!(
+            "INSERT INTO att.att_t VALUES ({i}, '{}')",
+            "y".repeat(200)
+        ))?;
+    }
+    conn.execute("DELETE FROM att.att_t WHERE id % 4 != 0")?;
+    let main_pages_before = scalar_i64(&conn, "PRAGMA main.page_count");
+    let att_pages_before = scalar_i64(&conn, "PRAGMA att.page_count");
+
+    conn.execute("VACUUM att")?;
+
+    assert_eq!(scalar_i64(&conn, "PRAGMA att.freelist_count"), 0);
+    assert!(
+        scalar_i64(&conn, "PRAGMA att.page_count") < att_pages_before,
+        "VACUUM att should shrink the attached database"
+    );
+    assert_eq!(
+        scalar_i64(&conn, "PRAGMA main.page_count"),
+        main_pages_before
+    );
+    assert_eq!(scalar_i64(&conn, "SELECT count(*) FROM att.att_t"), 50);
+    assert_eq!(
+        scalar_i64(&conn, "SELECT count(*) FROM att.att_t INDEXED BY att_t_y"),
+        50
+    );
+    conn.execute("INSERT INTO att.att_t VALUES (1000, 'after')")?;
+    assert_eq!(scalar_i64(&conn, "SELECT count(*) FROM main_t"), 3);
+
+    // VACUUM temp is a no-op, and unknown schemas are rejected.
+    conn.execute("VACUUM temp")?;
+    assert!(conn.execute("VACUUM nope").is_err());
+
+    let attached_db = TempDatabase::new_with_existent(&attached_path);
+    let attached_conn = attached_db.connect_limbo();
+    assert_eq!(run_integrity_check(&attached_conn), "ok");
+    assert_eq!(scalar_i64(&attached_conn, "SELECT count(*) FROM att_t"), 51);
+
+    Ok(())
+}
+
+#[turso_macros::test(init_sql = "CREATE TABLE t(id INTEGER PRIMARY KEY, v TEXT);")]
+fn test_plain_vacuum_rollback_journal_mode(tmp_db: TempDatabase) -> anyhow::Result<()> {
+    let conn = tmp_db.connect_limbo();
+    let mode: Vec<(String,)> = conn.exec_rows("PRAGMA journal_mode = delete");
+    assert_eq!(mode, vec![("delete".to_string(),)]);
+    conn.execute("CREATE INDEX t_v ON t(v)")?;
+    for i in 0..200 {
+        conn.execute(format!("INSERT INTO t VALUES ({i}, '{}')", "v".repeat(200)))?;
+    }
+    conn.execute("DELETE FROM t WHERE id % 4 != 0")?;
+    let pages_before = scalar_i64(&conn, "PRAGMA page_count");
+
+    conn.execute("VACUUM")?;
+
+    assert_eq!(scalar_i64(&conn, "PRAGMA freelist_count"), 0);
+    let page_count = scalar_i64(&conn, "PRAGMA page_count");
+    assert!(
+        page_count < pages_before,
+        "VACUUM should shrink a rollback-journal database"
+    );
+    // The compacted image is committed through the journal straight into the
+    // database file, which is cut down to the new page count.
+    let page_size = scalar_i64(&conn, "PRAGMA page_size");
+    assert_eq!(
+        std::fs::metadata(&tmp_db.path)?.len(),
+        (page_count * page_size) as u64
+    );
+    assert!(!tmp_db.path.with_extension("db-journal").exists());
+    assert_eq!(wal_file_size(&tmp_db), 0);
+    assert_eq!(scalar_i64(&conn, "SELECT count(*) FROM t"), 50);
+    assert_eq!(
+        scalar_i64(&conn, "SELECT count(*) FROM t INDEXED BY t_v"),
+        50
+    );
+    conn.execute("INSERT INTO t VALUES (1000, 'after')")?;
+    assert_eq!(run_integrity_check(&conn), "ok");
+    conn.close()?;
+
+    let reopened = TempDatabase::new_with_existent(&tmp_db.path);
+    let reopened_conn = reopened.connect_limbo();
+    let mode: Vec<(String,)> = reopened_conn.exec_rows("PRAGMA journal_mode");
+    assert_eq!(mode, vec![("delete".to_string(),)]);
+    assert_eq!(run_integrity_check(&reopened_conn), "ok");
+    assert_eq!(scalar_i64(&reopened_conn, "SELECT count(*) FROM t"), 51);
+
+    Ok(())
+}
+
 /// Column names containing commas must not confuse the
 /// bind-parameter count in the generated INSERT statement.
 #[turso_macros::test(mvcc)]
@@ -5581,47 +5682,13 @@ fn test_mvcc_vacuum_into_preserves_full_autovacuum_after_reopen() -> anyhow::Res
 }
 
 #[test]
-fn test_plain_vacuum_incremental_autovacuum_still_unsupported() -> anyhow::Result<()> {
-    let opts = DatabaseOpts::new()
-        .with_encryption(true)
-        .with_autovacuum(true);
-    let (_temp_dir, tmp_db) = open_sqlite_autovacuum_db("incremental", opts)?;
-    let conn = tmp_db.connect_limbo();
-
-    let err = conn.execute("VACUUM").unwrap_err();
-    assert_eq!(
-        err.to_string(),
-        "Internal error: Incremental auto-vacuum is not supported",
-        "unexpected error: {err}"
-    );
-
-    Ok(())
+fn test_plain_vacuum_preserves_incremental_autovacuum() -> anyhow::Result<()> {
+    assert_plain_vacuum_preserves_autovacuum_mode("incremental", 2)
 }
 
 #[test]
-fn test_vacuum_into_incremental_autovacuum_still_unsupported() -> anyhow::Result<()> {
-    let opts = DatabaseOpts::new()
-        .with_encryption(true)
-        .with_autovacuum(true);
-    let (_temp_dir, tmp_db) = open_sqlite_autovacuum_db("incremental", opts)?;
-    let conn = tmp_db.connect_limbo();
-    let dest_dir = TempDir::new()?;
-    let dest_path = dest_dir.path().join("vacuum-into.db");
-
-    let err = conn
-        .execute(format!("VACUUM INTO '{}'", dest_path.to_str().unwrap()))
-        .unwrap_err();
-    assert_eq!(
-        err.to_string(),
-        "Internal error: Incremental auto-vacuum is not supported",
-        "unexpected error: {err}"
-    );
-    assert!(
-        !dest_path.exists(),
-        "unsupported VACUUM INTO should not leave an output file behind"
-    );
-
-    Ok(())
+fn test_vacuum_into_preserves_incremental_autovacuum() -> anyhow::Result<()> {
+    assert_vacuum_into_preserves_autovacuum_mode("incremental", 2)
 }
 
 /// Keep one non-ignored plain VACUUM test under the checksum feature so
@@ -5928,30 +5995,23 @@ fn test_mvcc_plain_vacuum_discards_reused_index_rootpage_state() -> anyhow::Resu
 }
 
 #[test]
-fn test_mvcc_plain_vacuum_requires_checkpointed_image() -> anyhow::Result<()> {
-    let tmp_db =
-        TempDatabase::new_with_mvcc("test_mvcc_plain_vacuum_requires_checkpointed_image.db");
+fn test_mvcc_plain_vacuum_checkpoints_logical_log() -> anyhow::Result<()> {
+    let tmp_db = TempDatabase::new_with_mvcc("test_mvcc_plain_vacuum_checkpoints_logical_log.db");
     let conn = tmp_db.connect_limbo();
 
     let expected_rows = populate_mvcc_vacuum_workload(&conn)?;
-
-    let err = conn.execute("VACUUM").unwrap_err();
-    assert_eq!(
-        err.to_string(),
-        "Transaction error: cannot VACUUM an MVCC database with uncheckpointed changes; run PRAGMA wal_checkpoint(TRUNCATE) first",
-        "MVCC VACUUM must reject uncheckpointed logical changes"
+    assert!(
+        mvcc_log_file_size(&tmp_db) > 0,
+        "workload should leave uncheckpointed changes in the MVCC log"
     );
 
-    conn.execute("PRAGMA wal_checkpoint(TRUNCATE)")?;
+    conn.execute("VACUUM")?;
     assert_eq!(
         mvcc_log_file_size(&tmp_db),
         0,
-        "MVCC log should be empty before plain VACUUM starts"
+        "plain VACUUM should checkpoint the MVCC log before rebuilding"
     );
-
-    let before_hash = compute_plain_vacuum_dbhash(&tmp_db);
-    conn.execute("VACUUM")?;
-    assert_plain_vacuum_integrity_and_hash(&tmp_db, &conn, &before_hash);
+    assert_eq!(run_integrity_check(&conn), "ok");
     assert_plain_vacuum_folded_into_db_file(&tmp_db, &conn);
     assert_mvcc_vacuum_workload(&conn, &expected_rows)?;
 
@@ -6068,7 +6128,7 @@ fn open_queued_db(io: Arc<QueuedIo>, path: &str) -> anyhow::Result<Arc<Database>
         io,
         path,
         Default::default(),
-        DatabaseOpts::new().with_vacuum(true),
+        DatabaseOpts::new(),
         None,
     )?)
 }
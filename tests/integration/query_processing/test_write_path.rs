@@ -1768,6 +1768,69 @@ fn test_matview_row_loss_during_btree_split(tmp_db: TempDatabase) -> anyhow::Res
     Ok(())
 }
 
+/// Recomputed aggregates (GROUP_CONCAT, COUNT(expr) over NULLs) keep their inputs in the
+/// DBSP state table. After a reopen, further DML must keep the view equal to a full recompute.
+#[turso_macros::test(views)]
+fn test_matview_recomputed_aggregates_after_reopen(tmp_db: TempDatabase) -> anyhow::Result<()> {
+    const VIEW_QUERY: &str = "SELECT tag, group_concat(body, ';'), count(body), count(*) \
+                              FROM notes GROUP BY tag ORDER BY tag";
+    let opts = tmp_db.db_opts;
+    let db_path = {
+        let tmp_db = tmp_db;
+        let conn = tmp_db.connect_limbo();
+        conn.execute("CREATE TABLE notes(id INTEGER PRIMARY KEY, tag TEXT, body TEXT)")?;
+        conn.execute(
+            "INSERT INTO notes VALUES (1, 'a', 'x'), (2, 'b', 'y'), (3, 'a', 'z'), (4, 'c', NULL)",
+        )?;
+        conn.execute(
+            "CREATE MATERIALIZED VIEW note_tags AS \
+             SELECT tag, group_concat(body, ';') AS bodies, count(body) AS with_body, \
+             count(*) AS n FROM notes GROUP BY tag",
+        )?;
+        conn.close()?;
+        tmp_db.path.clone()
+    };
+
+    let tmp_db = TempDatabase::new_with_existent_with_opts(&db_path, opts);
+    let conn = tmp_db.connect_limbo();
+    let view_rows = || limbo_exec_rows(&conn, "SELECT * FROM note_tags ORDER BY tag");
+    assert_eq!(view_rows(), limbo_exec_rows(&conn, VIEW_QUERY));
+
+    conn.execute("INSERT INTO notes VALUES (5, 'a', 'w'), (6, 'c', NULL)")?;
+    conn.execute("DELETE FROM notes WHERE id = 1")?;
+    conn.execute("UPDATE notes SET body = 'q' WHERE id = 2")?;
+    assert_eq!(view_rows(), limbo_exec_rows(&conn, VIEW_QUERY));
+    assert_eq!(
+        view_rows(),
+        vec![
+            vec![
+                RValue::Text("a".into()),
+                RValue::Text("z;w".into()),
+                RValue::Integer(2),
+                RValue::Integer(2),
+            ],
+            vec![
+                RValue::Text("b".into()),
+                RValue::Text("q".into()),
+                RValue::Integer(1),
+                RValue::Integer(1),
+            ],
+            vec![
+                RValue::Text("c".into()),
+                RValue::Null,
+                RValue::Integer(0),
+                RValue::Integer(2),
+            ],
+        ]
+    );
+
+    conn.execute("UPDATE notes SET body = 'v' WHERE id = 6")?;
+    conn.execute("DELETE FROM notes WHERE tag = 'b'")?;
+    assert_eq!(view_rows(), limbo_exec_rows(&conn, VIEW_QUERY));
+
+    Ok(())
+}
+
 /// Regression test for simulator seed 867: UPDATE on an attached database table
 /// that changes the primary key (rowid) while a UNIQUE index exists would use
 /// the wrong database_id (0 instead of the attached db) for OpenWrite cursors,
@@ -2,6 +2,8 @@ use std::sync::Arc;
 use tempfile::TempDir;
 use turso_core::{Database, DatabaseOpts, OpenFlags, PlatformIO};
 
+use crate::common::ExecRows;
+
 #[test]
 fn test_autovacuum_readonly_behavior() {
     // (autovacuum_mode, enable_autovacuum_flag, expected_readonly)
@@ -43,3 +45,170 @@ fn test_autovacuum_readonly_behavior() {
         );
     }
 }
+
+/// Create an incremental auto-vacuum database with rusqlite and open it with autovacuum enabled.
+fn open_incremental_db(db_path: &std::path::Path) -> Arc<Database> {
+    {
+        let conn = rusqlite::Connection::open(db_path).unwrap();
+        conn.pragma_update(None, "page_size", 1024).unwrap();
+        conn.pragma_update(None, "auto_vacuum", "INCREMENTAL")
+            .unwrap();
+        conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, val BLOB)", ())
+            .unwrap();
+    }
+    open_autovacuum_db(db_path)
+}
+
+/// Open an existing auto-vacuum database with autovacuum enabled.
+fn open_autovacuum_db(db_path: &std::path::Path) -> Arc<Database> {
+    let io = Arc::new(PlatformIO::new().unwrap()) as Arc<dyn turso_core::IO>;
+    Database::open_file_with_flags(
+        io,
+        db_path.to_str().unwrap(),
+        OpenFlags::default(),
+        DatabaseOpts::new().with_autovacuum(true),
+        None,
+    )
+    .unwrap()
+}
+
+fn pragma_i64(conn: &Arc<turso_core::Connection>, pragma: &str) -> i64 {
+    let rows: Vec<(i64,)> = conn.exec_rows(&format!("PRAGMA {pragma}"));
+    rows[0].0
+}
+
+#[test]
+fn test_incremental_vacuum_reclaims_free_pages() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let db = open_incremental_db(&db_path);
+    let conn = db.connect().unwrap();
+
+    // Rows larger than a page spill onto overflow chains.
+    for i in 0..50 {
+        conn.execute(format!("INSERT INTO t VALUES ({i}, randomblob(3000))"))
+            .unwrap();
+    }
+    conn.execute("CREATE INDEX t_val ON t (id, val)").unwrap();
+    conn.execute("DELETE FROM t WHERE id % 2 = 0").unwrap();
+
+    let free_before = pragma_i64(&conn, "freelist_count");
+    let pages_before = pragma_i64(&conn, "page_count");
+    assert!(free_before > 10, "expected free pages, got {free_before}");
+
+    conn.execute("PRAGMA incremental_vacuum(5)").unwrap();
+    assert_eq!(pragma_i64(&conn, "freelist_count"), free_before - 5);
+    assert!(pragma_i64(&conn, "page_count") < pages_before);
+
+    conn.execute("PRAGMA incremental_vacuum").unwrap();
+    assert_eq!(pragma_i64(&conn, "freelist_count"), 0);
+
+    let rows: Vec<(i64,)> = conn.exec_rows("SELECT count(*) FROM t WHERE length(val) = 3000");
+    assert_eq!(rows, vec![(25,)]);
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    // SQLite's integrity check also validates the pointer map.
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(integrity, "ok");
+    let free: i64 = conn
+        .query_row("PRAGMA freelist_count", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(free, 0);
+}
+
+#[test]
+fn test_incremental_vacuum_maintains_pointer_map() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let db = open_incremental_db(&db_path);
+    let conn = db.connect().unwrap();
+
+    // Tables created after the first one get root pages relocated to the front.
+    for i in 0..200 {
+        conn.execute(format!("INSERT INTO t VALUES ({i}, randomblob(500))"))
+            .unwrap();
+    }
+    conn.execute("CREATE TABLE u (a TEXT)").unwrap();
+    conn.execute("INSERT INTO u SELECT hex(randomblob(800)) FROM t")
+        .unwrap();
+    conn.execute("DELETE FROM t WHERE id < 150").unwrap();
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    let sqlite = rusqlite::Connection::open(&db_path).unwrap();
+    let integrity: String = sqlite
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(integrity, "ok");
+    drop(sqlite);
+
+    let io = Arc::new(PlatformIO::new().unwrap()) as Arc<dyn turso_core::IO>;
+    let db = Database::open_file_with_flags(
+        io,
+        db_path.to_str().unwrap(),
+        OpenFlags::default(),
+        DatabaseOpts::new().with_autovacuum(true),
+        None,
+    )
+    .unwrap();
+    let conn = db.connect().unwrap();
+    conn.execute("PRAGMA incremental_vacuum").unwrap();
+    assert_eq!(pragma_i64(&conn, "freelist_count"), 0);
+    let rows: Vec<(i64, i64)> = conn.exec_rows("SELECT (SELECT count(*) FROM t), count(*) FROM u");
+    assert_eq!(rows, vec![(50, 200)]);
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    let sqlite = rusqlite::Connection::open(&db_path).unwrap();
+    let integrity: String = sqlite
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(integrity, "ok");
+}
+
+#[test]
+fn test_pointer_map_maintenance_with_cold_cache() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let db = open_incremental_db(&db_path);
+    let conn = db.connect().unwrap();
+    for i in 0..300 {
+        conn.execute(format!("INSERT INTO t VALUES ({i}, randomblob(600))"))
+            .unwrap();
+    }
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    // With a tiny cache, the pointer map and freelist pages touched by splits, deletes and
+    // incremental vacuum mostly have to be read back from disk.
+    let db = open_autovacuum_db(&db_path);
+    let conn = db.connect().unwrap();
+    conn.execute("PRAGMA cache_size = 10").unwrap();
+    conn.execute("CREATE INDEX t_val ON t (val)").unwrap();
+    for i in 300..400 {
+        conn.execute(format!("INSERT INTO t VALUES ({i}, randomblob(3000))"))
+            .unwrap();
+    }
+    conn.execute("DELETE FROM t WHERE id % 3 = 0").unwrap();
+    conn.execute("PRAGMA incremental_vacuum").unwrap();
+    assert_eq!(pragma_i64(&conn, "freelist_count"), 0);
+    let rows: Vec<(i64,)> = conn.exec_rows("SELECT count(*) FROM t");
+    assert_eq!(rows, vec![(266,)]);
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    let sqlite = rusqlite::Connection::open(&db_path).unwrap();
+    let integrity: String = sqlite
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(integrity, "ok");
+}
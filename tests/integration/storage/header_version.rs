@@ -93,7 +93,7 @@ fn open_with_limbo_and_check(db_path: &Path, enable_mvcc: bool) -> (u8, u8) {
 }
 
 #[test]
-fn test_legacy_db_opened_without_mvcc_converts_to_wal() {
+fn test_legacy_db_opened_without_mvcc_stays_legacy() {
     let tmp_dir = TempDir::new().unwrap();
     let db_path = tmp_dir.path().join("test.db");
 
@@ -103,14 +103,18 @@ fn test_legacy_db_opened_without_mvcc_converts_to_wal() {
     // Open with limbo (without MVCC)
     let (write_ver, read_ver) = open_with_limbo_and_check(&db_path, false);
 
-    // Should be converted to WAL mode (version 2)
+    // Should stay in Legacy mode (version 1), using a rollback journal
     assert_eq!(
-        write_ver, 2,
-        "Legacy DB opened without MVCC should convert to WAL (write_version=2), got {write_ver}"
+        write_ver, 1,
+        "Legacy DB opened without MVCC should stay Legacy (write_version=1), got {write_ver}"
     );
     assert_eq!(
-        read_ver, 2,
-        "Legacy DB opened without MVCC should convert to WAL (read_version=2), got {read_ver}"
+        read_ver, 1,
+        "Legacy DB opened without MVCC should stay Legacy (read_version=1), got {read_ver}"
+    );
+    assert!(
+        !db_path.with_extension("db-wal").exists(),
+        "Legacy DB should not get a WAL file"
     );
 }
 
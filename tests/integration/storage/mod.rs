@@ -2,5 +2,6 @@ mod autovacuum;
 #[cfg(feature = "checksum")]
 mod checksum;
 mod header_version;
+mod rollback_journal;
 #[cfg(not(feature = "checksum"))]
 mod short_read;
@@ -0,0 +1,315 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempfile::TempDir;
+use turso_core::{Database, DatabaseOpts, OpenFlags};
+
+use crate::common::ExecRows;
+
+const PAGE_SIZE: usize = 4096;
+
+fn journal_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("db-journal")
+}
+
+fn wal_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("db-wal")
+}
+
+/// Create a database in DELETE journal mode with rusqlite.
+fn create_legacy_db(db_path: &Path) {
+    let conn = rusqlite::Connection::open(db_path).unwrap();
+    conn.pragma_update(None, "journal_mode", "delete").unwrap();
+    conn.pragma_update(None, "page_size", PAGE_SIZE).unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, val TEXT)", ())
+        .unwrap();
+    conn.execute("INSERT INTO t (val) VALUES ('a'), ('b'), ('c')", ())
+        .unwrap();
+}
+
+fn open_db(db_path: &Path) -> Arc<Database> {
+    let io = Arc::new(turso_core::PlatformIO::new().unwrap());
+    Database::open_file_with_flags(
+        io,
+        db_path.to_str().unwrap(),
+        OpenFlags::default(),
+        DatabaseOpts::new(),
+        None,
+    )
+    .unwrap()
+}
+
+fn read_header_versions(db_path: &Path) -> (u8, u8) {
+    let bytes = std::fs::read(db_path).unwrap();
+    (bytes[18], bytes[19])
+}
+
+fn sqlite_vals(db_path: &Path) -> Vec<String> {
+    let conn = rusqlite::Connection::open(db_path).unwrap();
+    let mut stmt = conn.prepare("SELECT val FROM t ORDER BY id").unwrap();
+    stmt.query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn journal_checksum(nonce: u32, page: &[u8]) -> u32 {
+    let mut checksum = nonce;
+    let mut i = page.len() as isize - 200;
+    while i > 0 {
+        checksum = checksum.wrapping_add(page[i as usize] as u32);
+        i -= 200;
+    }
+    checksum
+}
+
+#[test]
+fn test_legacy_db_writes_in_place() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db_path = tmp_dir.path().join("test.db");
+    create_legacy_db(&db_path);
+    let change_counter_before = std::fs::read(&db_path).unwrap()[24..28].to_vec();
+
+    let db = open_db(&db_path);
+    let conn = db.connect().unwrap();
+    let mode: Vec<(String,)> = conn.exec_rows("PRAGMA journal_mode");
+    assert_eq!(mode, vec![("delete".to_string(),)]);
+    conn.execute("INSERT INTO t (val) VALUES ('d')").unwrap();
+    conn.execute("UPDATE t SET val = 'B' WHERE id = 2").unwrap();
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    assert_eq!(read_header_versions(&db_path), (1, 1));
+    assert!(!journal_path(&db_path).exists());
+    assert!(!wal_path(&db_path).exists());
+    let bytes = std::fs::read(&db_path).unwrap();
+    assert_ne!(bytes[24..28], change_counter_before[..]);
+    // version-valid-for must match the change counter for SQLite to trust the
+    // in-header database size.
+    assert_eq!(bytes[24..28], bytes[92..96]);
+    assert_eq!(sqlite_vals(&db_path), vec!["a", "B", "c", "d"]);
+}
+
+#[test]
+fn test_rollback_discards_changes() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db_path = tmp_dir.path().join("test.db");
+    create_legacy_db(&db_path);
+
+    let db = open_db(&db_path);
+    let conn = db.connect().unwrap();
+    conn.execute("BEGIN").unwrap();
+    conn.execute("INSERT INTO t (val) VALUES ('d')").unwrap();
+    conn.execute("DELETE FROM t WHERE id = 1").unwrap();
+    conn.execute("ROLLBACK").unwrap();
+    let rows: Vec<(String,)> = conn.exec_rows("SELECT val FROM t ORDER BY id");
+    assert_eq!(
+        rows,
+        vec![("a".to_string(),), ("b".to_string(),), ("c".to_string(),)]
+    );
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    assert_eq!(sqlite_vals(&db_path), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_hot_journal_is_rolled_back_on_open() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db_path = tmp_dir.path().join("test.db");
+    create_legacy_db(&db_path);
+
+    // Simulate a writer that died after journaling page 2 and overwriting it,
+    // and after growing the file by one page.
+    let mut db_bytes = std::fs::read(&db_path).unwrap();
+    let initial_pages = (db_bytes.len() / PAGE_SIZE) as u32;
+    let original_page2 = db_bytes[PAGE_SIZE..2 * PAGE_SIZE].to_vec();
+    let nonce = 0x1234_5678u32;
+    let mut journal = vec![0u8; 512];
+    journal[0..8].copy_from_slice(&[0xd9, 0xd5, 0x05, 0xf9, 0x20, 0xa1, 0x63, 0xd7]);
+    journal[8..12].copy_from_slice(&1u32.to_be_bytes());
+    journal[12..16].copy_from_slice(&nonce.to_be_bytes());
+    journal[16..20].copy_from_slice(&initial_pages.to_be_bytes());
+    journal[20..24].copy_from_slice(&512u32.to_be_bytes());
+    journal[24..28].copy_from_slice(&(PAGE_SIZE as u32).to_be_bytes());
+    journal.extend_from_slice(&2u32.to_be_bytes());
+    journal.extend_from_slice(&original_page2);
+    journal.extend_from_slice(&journal_checksum(nonce, &original_page2).to_be_bytes());
+    std::fs::write(journal_path(&db_path), &journal).unwrap();
+
+    db_bytes[PAGE_SIZE..2 * PAGE_SIZE].fill(0xff);
+    db_bytes.extend_from_slice(&[0xee; PAGE_SIZE]);
+    std::fs::write(&db_path, &db_bytes).unwrap();
+
+    let db = open_db(&db_path);
+    assert!(!journal_path(&db_path).exists());
+    let conn = db.connect().unwrap();
+    let rows: Vec<(String,)> = conn.exec_rows("SELECT val FROM t ORDER BY id");
+    assert_eq!(
+        rows,
+        vec![("a".to_string(),), ("b".to_string(),), ("c".to_string(),)]
+    );
+    let integrity: Vec<(String,)> = conn.exec_rows("PRAGMA integrity_check");
+    assert_eq!(integrity, vec![("ok".to_string(),)]);
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    assert_eq!(
+        std::fs::metadata(&db_path).unwrap().len(),
+        initial_pages as u64 * PAGE_SIZE as u64
+    );
+}
+
+#[test]
+fn test_zeroed_journal_is_not_hot() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db_path = tmp_dir.path().join("test.db");
+    create_legacy_db(&db_path);
+    // What a PERSIST mode commit leaves behind.
+    std::fs::write(journal_path(&db_path), vec![0u8; 512 + PAGE_SIZE + 8]).unwrap();
+
+    let db = open_db(&db_path);
+    let conn = db.connect().unwrap();
+    let rows: Vec<(i64,)> = conn.exec_rows("SELECT count(*) FROM t");
+    assert_eq!(rows, vec![(3,)]);
+}
+
+#[test]
+fn test_journal_mode_switching_between_rollback_modes() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db_path = tmp_dir.path().join("test.db");
+    create_legacy_db(&db_path);
+
+    let db = open_db(&db_path);
+    let conn = db.connect().unwrap();
+
+    let mode: Vec<(String,)> = conn.exec_rows("PRAGMA journal_mode = truncate");
+    assert_eq!(mode, vec![("truncate".to_string(),)]);
+    conn.execute("INSERT INTO t (val) VALUES ('d')").unwrap();
+    assert_eq!(std::fs::metadata(journal_path(&db_path)).unwrap().len(), 0);
+
+    let mode: Vec<(String,)> = conn.exec_rows("PRAGMA journal_mode = persist");
+    assert_eq!(mode, vec![("persist".to_string(),)]);
+    conn.execute("INSERT INTO t (val) VALUES ('e')").unwrap();
+    let journal = std::fs::read(journal_path(&db_path)).unwrap();
+    assert!(journal.len() > 28);
+    assert!(journal[..28].iter().all(|b| *b == 0));
+
+    let mode: Vec<(String,)> = conn.exec_rows("PRAGMA journal_mode = delete");
+    assert_eq!(mode, vec![("delete".to_string(),)]);
+    assert!(!journal_path(&db_path).exists());
+
+    for mode in ["memory", "off"] {
+        let rows: Vec<(String,)> = conn.exec_rows(&format!("PRAGMA journal_mode = {mode}"));
+        assert_eq!(rows, vec![(mode.to_string(),)]);
+        conn.execute(format!("INSERT INTO t (val) VALUES ('{mode}')"))
+            .unwrap();
+        assert!(!journal_path(&db_path).exists());
+    }
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    assert_eq!(read_header_versions(&db_path), (1, 1));
+    assert_eq!(
+        sqlite_vals(&db_path),
+        vec!["a", "b", "c", "d", "e", "memory", "off"]
+    );
+}
+
+#[test]
+fn test_journal_mode_switch_between_rollback_and_wal() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db_path = tmp_dir.path().join("test.db");
+    create_legacy_db(&db_path);
+
+    {
+        let db = open_db(&db_path);
+        let conn = db.connect().unwrap();
+        let mode: Vec<(String,)> = conn.exec_rows("PRAGMA journal_mode = wal");
+        assert_eq!(mode, vec![("wal".to_string(),)]);
+        conn.execute("INSERT INTO t (val) VALUES ('d')").unwrap();
+        assert!(wal_path(&db_path).exists());
+        conn.close().unwrap();
+    }
+    assert_eq!(read_header_versions(&db_path), (2, 2));
+
+    {
+        let db = open_db(&db_path);
+        let conn = db.connect().unwrap();
+        let mode: Vec<(String,)> = conn.exec_rows("PRAGMA journal_mode = delete");
+        assert_eq!(mode, vec![("delete".to_string(),)]);
+        assert!(!wal_path(&db_path).exists());
+        conn.execute("INSERT INTO t (val) VALUES ('e')").unwrap();
+        conn.close().unwrap();
+    }
+    assert_eq!(read_header_versions(&db_path), (1, 1));
+    assert!(!journal_path(&db_path).exists());
+    assert_eq!(sqlite_vals(&db_path), vec!["a", "b", "c", "d", "e"]);
+}
+
+#[test]
+fn test_wal_close_still_checkpoints() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db_path = tmp_dir.path().join("test.db");
+    create_legacy_db(&db_path);
+
+    let db = open_db(&db_path);
+    let conn = db.connect().unwrap();
+    let mode: Vec<(String,)> = conn.exec_rows("PRAGMA journal_mode = wal");
+    assert_eq!(mode, vec![("wal".to_string(),)]);
+    conn.execute("INSERT INTO t (val) VALUES ('d')").unwrap();
+    assert!(std::fs::metadata(wal_path(&db_path)).unwrap().len() > 0);
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    // Closing the last connection copies the frames into the database file and
+    // truncates the WAL.
+    assert_eq!(std::fs::metadata(wal_path(&db_path)).unwrap().len(), 0);
+    assert_eq!(sqlite_vals(&db_path), vec!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn test_persist_journal_respects_size_limit() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db_path = tmp_dir.path().join("test.db");
+    create_legacy_db(&db_path);
+
+    let db = open_db(&db_path);
+    let conn = db.connect().unwrap();
+
+    // The limit survives the journal mode switch.
+    let limit: Vec<(i64,)> = conn.exec_rows("PRAGMA journal_size_limit = 1024");
+    assert_eq!(limit, vec![(1024,)]);
+    let mode: Vec<(String,)> = conn.exec_rows("PRAGMA journal_mode = persist");
+    assert_eq!(mode, vec![("persist".to_string(),)]);
+    conn.execute("INSERT INTO t (val) VALUES (randomblob(20000))")
+        .unwrap();
+    let journal = std::fs::read(journal_path(&db_path)).unwrap();
+    assert_eq!(journal.len(), 1024);
+    assert!(journal[..28].iter().all(|b| *b == 0));
+
+    // A limit below the header size leaves an empty, cold journal.
+    let limit: Vec<(i64,)> = conn.exec_rows("PRAGMA journal_size_limit = 0");
+    assert_eq!(limit, vec![(0,)]);
+    conn.execute("INSERT INTO t (val) VALUES ('d')").unwrap();
+    assert_eq!(std::fs::metadata(journal_path(&db_path)).unwrap().len(), 0);
+
+    let limit: Vec<(i64,)> = conn.exec_rows("PRAGMA journal_size_limit = -42");
+    assert_eq!(limit, vec![(-1,)]);
+    conn.execute("INSERT INTO t (val) VALUES (randomblob(20000))")
+        .unwrap();
+    assert!(std::fs::metadata(journal_path(&db_path)).unwrap().len() > 20000);
+    conn.close().unwrap();
+    drop(conn);
+    drop(db);
+
+    let sqlite = rusqlite::Connection::open(&db_path).unwrap();
+    let count: i64 = sqlite
+        .query_row("SELECT count(*) FROM t", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 6);
+}